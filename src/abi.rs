@@ -0,0 +1,229 @@
+//! Decoding of calldata and event logs using the ABIs of verified contracts
+use std::fmt;
+
+use alloy::{
+    dyn_abi::{DynSolValue, EventExt, JsonAbiExt},
+    json_abi::JsonAbi,
+    primitives::{Address, Selector, B256},
+};
+
+use crate::CONTRACT_ABIS;
+
+/// A function call or event, decoded using the ABI of the contract it
+/// concerns
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Decoded {
+    /// Name of the function or event
+    pub name: String,
+    /// Name (which may be empty) and value of each argument, in order
+    pub arguments: Vec<(String, String)>,
+}
+
+impl fmt::Display for Decoded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}(", self.name)?;
+
+        for (i, (name, value)) in self.arguments.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            if name.is_empty() {
+                write!(f, "{value}")?;
+            } else {
+                write!(f, "{name}: {value}")?;
+            }
+        }
+
+        write!(f, ")")
+    }
+}
+
+/// Remember the ABI of the contract at the given [`Address`] for the
+/// remainder of this session
+pub fn set_contract_abi(address: Address, abi: JsonAbi) {
+    if let Ok(mut abis) = CONTRACT_ABIS.write() {
+        abis.insert(address, abi);
+    }
+}
+
+/// Name of the function of the contract at the given [`Address`] that the
+/// given calldata calls (if the contract's ABI is known)
+pub fn function_name(address: &Address, input: &[u8]) -> Option<String> {
+    let selector = input.get(..4)?;
+    CONTRACT_ABIS
+        .read()
+        .ok()?
+        .get(address)?
+        .functions()
+        .find(|function| function.selector() == selector)
+        .map(|function| function.name.clone())
+}
+
+/// Signature (e.g., `transfer(address,uint256)`) of the function with the
+/// given selector, as per the ABI of any contract that has one
+pub fn function_signature(selector: &Selector) -> Option<String> {
+    CONTRACT_ABIS
+        .read()
+        .ok()?
+        .values()
+        .flat_map(JsonAbi::functions)
+        .find(|function| function.selector() == *selector)
+        .map(|function| function.signature())
+}
+
+/// Signature (e.g., `Transfer(address,address,uint256)`) of the event of
+/// the contract at the given [`Address`] with the given topic (if the
+/// contract's ABI is known)
+pub fn event_signature(address: &Address, topic0: &B256) -> Option<String> {
+    CONTRACT_ABIS
+        .read()
+        .ok()?
+        .get(address)?
+        .events()
+        .find(|event| !event.anonymous && event.selector() == *topic0)
+        .map(|event| event.signature())
+}
+
+/// Decode a call to the contract at the given [`Address`] with the given
+/// calldata (if the contract's ABI is known and has a matching function)
+pub fn decode_call(address: &Address, input: &[u8]) -> Option<Decoded> {
+    let abis = CONTRACT_ABIS.read().ok()?;
+    let selector = input.get(..4)?;
+    let function = abis
+        .get(address)?
+        .functions()
+        .find(|function| function.selector() == selector)?;
+    let values = function.abi_decode_input(&input[4..]).ok()?;
+    Some(Decoded {
+        name: function.name.clone(),
+        arguments: function
+            .inputs
+            .iter()
+            .zip(values)
+            .map(|(param, value)| (param.name.clone(), format_value(&value)))
+            .collect(),
+    })
+}
+
+/// Decode a log emitted by the contract at the given [`Address`] (if the
+/// contract's ABI is known and has a matching event)
+pub fn decode_log(
+    address: &Address,
+    topics: &[B256],
+    data: &[u8],
+) -> Option<Decoded> {
+    let abis = CONTRACT_ABIS.read().ok()?;
+    let topic0 = topics.first()?;
+    let event = abis
+        .get(address)?
+        .events()
+        .find(|event| !event.anonymous && event.selector() == *topic0)?;
+    let decoded = event.decode_log_parts(topics.iter().copied(), data).ok()?;
+    let mut indexed = decoded.indexed.into_iter();
+    let mut body = decoded.body.into_iter();
+    Some(Decoded {
+        name: event.name.clone(),
+        arguments: event
+            .inputs
+            .iter()
+            .filter_map(|param| {
+                let value = if param.indexed {
+                    indexed.next()
+                } else {
+                    body.next()
+                }?;
+                Some((param.name.clone(), format_value(&value)))
+            })
+            .collect(),
+    })
+}
+
+/// Render a decoded value roughly as it'd be written in Solidity
+fn format_value(value: &DynSolValue) -> String {
+    let list = |values: &[DynSolValue]| {
+        values
+            .iter()
+            .map(format_value)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    match value {
+        DynSolValue::Bool(t) => t.to_string(),
+        DynSolValue::Int(t, _) => t.to_string(),
+        DynSolValue::Uint(t, _) => t.to_string(),
+        DynSolValue::FixedBytes(t, size) => {
+            alloy::hex::encode_prefixed(&t[..*size])
+        }
+        DynSolValue::Address(t) => t.to_string(),
+        DynSolValue::Function(t) => t.to_string(),
+        DynSolValue::Bytes(t) => alloy::hex::encode_prefixed(t),
+        DynSolValue::String(t) => format!("{t:?}"),
+        DynSolValue::Array(t) | DynSolValue::FixedArray(t) => {
+            format!("[{}]", list(t))
+        }
+        DynSolValue::Tuple(t) => format!("({})", list(t)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        hex,
+        primitives::{keccak256, U256},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let address = Address::repeat_byte(0xee);
+        let abi: JsonAbi = serde_json::from_str(
+            r#"[
+                {"type": "function", "name": "transfer", "stateMutability": "nonpayable", "inputs": [{"name": "to", "type": "address"}, {"name": "amount", "type": "uint256"}], "outputs": [{"name": "", "type": "bool"}]},
+                {"type": "event", "name": "Transfer", "anonymous": false, "inputs": [{"name": "from", "type": "address", "indexed": true}, {"name": "to", "type": "address", "indexed": true}, {"name": "value", "type": "uint256", "indexed": false}]}
+            ]"#,
+        )
+        .unwrap();
+        set_contract_abi(address, abi);
+
+        let recipient = Address::repeat_byte(1);
+        let input = [
+            &hex!("a9059cbb")[..],
+            &B256::left_padding_from(recipient.as_slice())[..],
+            &U256::from(1000).to_be_bytes::<32>()[..],
+        ]
+        .concat();
+        assert_eq!(
+            decode_call(&address, &input).unwrap().to_string(),
+            format!("transfer(to: {recipient}, amount: 1000)")
+        );
+        assert_eq!(
+            function_name(&address, &input),
+            Some("transfer".to_string())
+        );
+        assert_eq!(
+            function_signature(&Selector::from(hex!("a9059cbb"))),
+            Some("transfer(address,uint256)".to_string())
+        );
+        assert_eq!(function_signature(&Selector::from(hex!("deadbeef"))), None);
+        assert_eq!(decode_call(&Address::ZERO, &input), None);
+        assert_eq!(decode_call(&address, &hex!("deadbeef")), None);
+
+        let topics = [
+            keccak256("Transfer(address,address,uint256)"),
+            B256::left_padding_from(Address::ZERO.as_slice()),
+            B256::left_padding_from(recipient.as_slice()),
+        ];
+        assert_eq!(
+            decode_log(&address, &topics, &U256::from(5).to_be_bytes::<32>())
+                .unwrap()
+                .to_string(),
+            format!(
+                "Transfer(from: {}, to: {recipient}, value: 5)",
+                Address::ZERO
+            )
+        );
+    }
+}
@@ -1,9 +1,34 @@
-use std::path::PathBuf;
+use std::{
+    collections::HashSet,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    num::{NonZeroU32, NonZeroU64},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
 
-use alloy::{eips::BlockHashOrNumber, primitives::TxHash};
-use clap::Parser;
+use alloy::{
+    eips::BlockHashOrNumber,
+    primitives::{Address, BlockNumber, Bytes, TxHash},
+};
+use clap::{parser::ValueSource, ArgMatches, Parser, Subcommand};
 use url::Url;
 
+use crate::{
+    client::ipc_url,
+    config::Config,
+    db::{JournalMode, Pragmas, Synchronous},
+    export::{Format, Table},
+    filter::Filter,
+    logging::{LogFormat, SyslogAddr},
+    query::Query,
+    services::blockchain::ResetAction,
+    sinks::{
+        alerts::{AlertRule, NotifierSpec},
+        SinkSpec,
+    },
+    ui::theme::ThemeName,
+};
+
 pub const DEFAULT_PORT: u16 = 80;
 pub const DEFAULT_METRICS_ONLY_PORT: u16 = 9898;
 
@@ -11,27 +36,278 @@ pub const DEFAULT_METRICS_ONLY_PORT: u16 = 9898;
 #[derive(Clone, Debug, Parser)]
 #[clap(version, about, author)]
 pub struct Opts {
-    #[clap(short, long, default_value = "wss://eth.merkle.io")]
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+    /// RPC endpoint: a Websockets URL, an `ipc://` URL, or the path of a
+    /// Windows named pipe (e.g., `\\.\pipe\geth.ipc`)
+    #[clap(short, long, default_value = "wss://eth.merkle.io", value_parser = parse_rpc)]
     pub rpc: Url,
+    /// RPC endpoint to fail over to should the previous one (starting with
+    /// `--rpc`) become unhealthy (may be repeated)
+    #[clap(long, value_parser = parse_rpc)]
+    pub fallback_rpc: Vec<Url>,
+    /// File containing a hex-encoded secret to sign JWTs with when connecting
+    /// to RPC nodes (as for an execution client's engine API)
+    #[clap(long)]
+    pub rpc_jwt_secret: Option<PathBuf>,
+    /// Header to send when connecting to RPC nodes over Websockets, given as
+    /// `name=value` (may be repeated)
+    #[clap(long, value_parser = parse_header)]
+    pub rpc_header: Vec<(String, String)>,
+    /// SQLite database file, or a PostgreSQL URI (headless mode only)
     #[clap(short, long)]
     pub db: Option<PathBuf>,
+    /// SQLite journal mode for on-disk databases
+    #[clap(long, value_enum, default_value_t = JournalMode::Wal)]
+    pub db_journal_mode: JournalMode,
+    /// SQLite synchronisation level for on-disk databases
+    #[clap(long, value_enum, default_value_t = Synchronous::Normal)]
+    pub db_synchronous: Synchronous,
+    /// SQLite page cache size (in KiB) for on-disk databases
+    #[clap(long, default_value_t = Pragmas::default().cache_size_kib)]
+    pub db_cache_size: u64,
+    /// SQLite page size (in bytes) for newly created on-disk databases
+    #[clap(long, default_value_t = Pragmas::default().page_size)]
+    pub db_page_size: u64,
     #[clap(long, action)]
     pub headless: bool,
+    /// What to do with indexed data should the RPC node turn out to serve a
+    /// different chain (e.g., a restarted dev chain): `wipe` it, or `archive`
+    /// a copy of the database first (if not given, the TUI asks, whereas
+    /// headless mode stops)
+    #[clap(long, value_enum, value_name = "ACTION")]
+    pub on_chain_reset: Option<ResetAction>,
+    /// Where to write indexed data: `db`, `ndjson` (headless mode only), a
+    /// webhook URL, or `kafka://<brokers>/<topic>` (may be repeated;
+    /// defaults to `db`)
+    #[clap(long = "sink")]
+    pub sinks: Vec<SinkSpec>,
+    /// Print each indexed transaction matching the given expression (e.g.,
+    /// `to == 0xdead...beef && value > 1 ether`) in headless mode
+    #[clap(long, requires = "headless")]
+    pub filter: Option<Filter>,
     #[clap(long, action)]
     pub list_block_hashes: bool,
     #[clap(long)]
     pub block: Option<BlockHashOrNumber>,
     #[clap(long, alias("tx"))]
     pub transaction: Option<TxHash>,
+    /// Follow the given transaction until it's finalized, printing each
+    /// change in its status, and exit
+    #[clap(long)]
+    pub await_tx: Option<TxHash>,
+    /// Give up on `--await-tx` after this many seconds (exiting with status
+    /// 2)
+    #[clap(long, requires = "await_tx")]
+    pub await_timeout: Option<u64>,
+    /// Print per-builder statistics over an indexed block range (e.g.,
+    /// `19000000..19000100`) as JSON and exit
+    #[clap(long, value_parser = parse_block_range)]
+    pub builder_stats: Option<RangeInclusive<BlockNumber>>,
+    /// Write every indexed block (or those within `--snapshot-range`) to a
+    /// snapshot file (or `-` for standard output) and exit
+    #[clap(long, conflicts_with = "load_snapshot")]
+    pub dump_snapshot: Option<PathBuf>,
+    /// Indexed block range to dump (e.g., `19000000..19001000`)
+    #[clap(long, value_parser = parse_block_range, requires = "dump_snapshot")]
+    pub snapshot_range: Option<RangeInclusive<BlockNumber>>,
+    /// Store every block in a snapshot file (or `-` for standard input)
+    /// written by `--dump-snapshot` and exit
+    #[clap(long)]
+    pub load_snapshot: Option<PathBuf>,
+    /// Serve queries over indexed data via HTTP (e.g., `/api/builders`)
     #[clap(long, short, action)]
     pub serve: bool,
     #[clap(long, short, action)]
     pub metrics: bool,
     #[clap(long, short)]
     pub port: Option<u16>,
+    /// Address that HTTP services (e.g., metrics) listen on
+    #[clap(long, default_value_t = IpAddr::V4(Ipv4Addr::LOCALHOST))]
+    pub bind: IpAddr,
+    /// PEM-encoded certificate chain to serve HTTP services over TLS with
+    #[clap(long, requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+    /// PEM-encoded private key corresponding to `--tls-cert`
+    #[clap(long, requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+    /// Load additional address labels from a JSON file in the same format as
+    /// `assets/labels/mainnet.json` (may be repeated; later files take
+    /// precedence)
+    #[clap(long)]
+    pub labels: Vec<PathBuf>,
+    /// Don't load the bundled address labels
+    #[clap(long, action)]
+    pub no_default_labels: bool,
+    /// Endpoint of a local builder or validator node whose pending block is
+    /// compared against the block that actually lands at each height
+    #[clap(long, value_parser = parse_rpc)]
+    pub local_node: Option<Url>,
+    /// HTTP API of a consensus layer node to retrieve the slot, epoch, and
+    /// proposer of each block from (e.g., `http://localhost:5052`)
+    #[clap(long)]
+    pub cl_rpc: Option<Url>,
+    /// Sourcify server to look up the verified source code of contracts on
+    /// (e.g., `https://sourcify.dev/server`)
+    #[clap(long)]
+    pub sourcify: Option<Url>,
+    /// Etherscan API key, with which to look up the verified source code of
+    /// contracts that Sourcify doesn't have
+    #[clap(long)]
+    pub etherscan_api_key: Option<String>,
+    /// Address whose balance and nonce are recorded as blocks arrive, and
+    /// whose transactions are highlighted (may be repeated)
+    #[clap(long)]
+    pub watch: Vec<Address>,
+    /// Number of blocks a watched address's transaction may remain pending
+    /// for before it's flagged as stuck (defaults to 5)
+    #[clap(long)]
+    pub stuck_after: Option<u64>,
+    /// Raise an alert when an indexed block meets the given condition:
+    /// `address:<address>`, `base-fee:<gwei>`, `value:<ether>`, or
+    /// `event:<contract>[:<signature or topic0>]` (may be repeated)
+    #[clap(long)]
+    pub alert: Vec<AlertRule>,
+    /// Where to send alerts: `desktop`, a webhook URL, or
+    /// `telegram://<bot token>@<chat ID>` (may be repeated)
+    #[clap(long)]
+    pub notify: Vec<NotifierSpec>,
+    /// Maximum number of requests per second made to RPC nodes (on average)
+    #[clap(long)]
+    pub rpc_rate_limit: Option<NonZeroU32>,
+    /// Number of requests that may be made to RPC nodes at once, despite
+    /// `--rpc-rate-limit` (defaults to one second's worth)
+    #[clap(long)]
+    pub rpc_burst: Option<NonZeroU32>,
+    /// Configuration file to use instead of
+    /// `~/.config/blocktop/config.toml`
+    #[clap(long)]
+    pub config: Option<PathBuf>,
+    /// Interval between TUI ticks while new blocks are arriving (in
+    /// milliseconds), which lengthens to up to a second while idle
+    #[clap(long = "tick-ms", alias("tick-rate"), value_name = "MS")]
+    pub tick_rate: Option<NonZeroU64>,
+    /// Colour scheme of the TUI
+    #[clap(long, value_enum)]
+    pub theme: Option<ThemeName>,
+    /// Show the latest block number and base fee in the terminal's title
+    #[clap(long, action)]
+    pub set_title: bool,
+    /// Additionally forward logs to a syslog server in headless mode (e.g.,
+    /// `udp://localhost:514` or `tcp://logs.example.com:601`)
+    #[clap(long)]
+    pub log_syslog: Option<SyslogAddr>,
+    /// Format of logs written to standard error in headless mode (`json`
+    /// additionally writes a summary of each indexed block and transaction to
+    /// standard output)
+    #[clap(long, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
 }
 
 impl Opts {
+    /// Fall back to the given [`Config`] for any options that weren't
+    /// specified on the command line
+    pub fn with_config(
+        mut self,
+        matches: &ArgMatches,
+        config: &Config,
+    ) -> Self {
+        if matches.value_source("rpc") == Some(ValueSource::DefaultValue) {
+            if let Some(rpc) = &config.rpc {
+                self.rpc = rpc.clone();
+            }
+        }
+
+        if self.fallback_rpc.is_empty() {
+            self.fallback_rpc = config.fallback_rpcs.clone();
+        }
+
+        if self.rpc_header.is_empty() {
+            self.rpc_header = config
+                .rpc_headers
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect();
+        }
+
+        self.rpc_jwt_secret = self
+            .rpc_jwt_secret
+            .or_else(|| config.rpc_jwt_secret.clone());
+
+        self.db = self.db.or_else(|| config.db.clone());
+        self.cl_rpc = self.cl_rpc.or_else(|| config.cl_rpc.clone());
+        self.sourcify = self.sourcify.or_else(|| config.sourcify_url.clone());
+        self.etherscan_api_key = self
+            .etherscan_api_key
+            .or_else(|| config.etherscan_api_key.clone());
+        self.tick_rate = self.tick_rate.or(config.tick_rate);
+        self.stuck_after = self.stuck_after.or(config.stuck_after);
+        self.rpc_rate_limit = self.rpc_rate_limit.or(config.rpc_rate_limit);
+        self.rpc_burst = self.rpc_burst.or(config.rpc_burst);
+        self.theme = self.theme.or(config.theme);
+        self.set_title |= config.set_title;
+
+        if self.sinks.is_empty() {
+            self.sinks = config.sinks.clone();
+        }
+
+        if self.watch.is_empty() {
+            self.watch = config.watchlist.clone();
+        }
+
+        if self.alert.is_empty() {
+            self.alert = config.alerts.rules.clone();
+        }
+
+        if self.notify.is_empty() {
+            self.notify = config.alerts.notify.clone();
+        }
+
+        self
+    }
+
+    /// The sinks to write indexed data to
+    ///
+    /// The TUI reads everything from the database, so it's always included
+    /// outside of headless mode.
+    pub fn sinks(&self) -> Vec<SinkSpec> {
+        let mut sinks = self.sinks.clone();
+
+        if sinks.is_empty()
+            || (!self.headless && !sinks.contains(&SinkSpec::Database))
+        {
+            sinks.insert(0, SinkSpec::Database);
+        }
+
+        /* keeping the first of each, wherever they were given */
+        let mut seen = HashSet::new();
+        sinks.retain(|sink| seen.insert(sink.clone()));
+        sinks
+    }
+
+    /// The SQLite tuning requested for on-disk databases
+    pub fn pragmas(&self) -> Pragmas {
+        Pragmas {
+            journal_mode: self.db_journal_mode,
+            synchronous: self.db_synchronous,
+            cache_size_kib: self.db_cache_size,
+            page_size: self.db_page_size,
+        }
+    }
+
+    /// Whether this is a query for a single block or transaction (rather than
+    /// a long-running indexer)
+    pub fn is_one_shot(&self) -> bool {
+        self.command.is_some()
+            || (self.headless
+                && (self.block.is_some() || self.transaction.is_some()))
+    }
+
+    /// The socket that HTTP services listen on (if any are enabled)
+    pub fn socket_addr(&self) -> Option<SocketAddr> {
+        self.port().map(|port| SocketAddr::new(self.bind, port))
+    }
+
     pub fn port(&self) -> Option<u16> {
         if let Some(port) = self.port {
             Some(port)
@@ -45,3 +321,105 @@ impl Opts {
         }
     }
 }
+
+/// Modes other than running the TUI or the indexer
+#[derive(Clone, Debug, Subcommand)]
+pub enum Command {
+    /// Print a block or transaction from the database (or, if it isn't
+    /// indexed, the RPC node) and exit
+    Query {
+        #[clap(subcommand)]
+        query: Query,
+        /// Print the full object as JSON rather than a summary
+        #[clap(long, global = true)]
+        json: bool,
+    },
+    /// Dump the indexed `blocks`, `transactions`, or `logs` within a range of
+    /// blocks and exit
+    Export {
+        #[clap(value_enum)]
+        table: Table,
+        /// First block to export
+        #[clap(long)]
+        from: BlockNumber,
+        /// Last block to export
+        #[clap(long)]
+        to: BlockNumber,
+        /// Format to export in (`parquet` requires building with the
+        /// `parquet` feature)
+        #[clap(long, value_enum, default_value_t = Format::default())]
+        format: Format,
+        /// File to export to (instead of standard output)
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Broadcast a signed transaction, print each change in its status until
+    /// it's included, then print its receipt as JSON and exit
+    Send {
+        /// Signed, EIP-2718-encoded transaction (e.g., as output by `cast
+        /// mktx`)
+        #[clap(long)]
+        raw: Bytes,
+        /// Give up on waiting for inclusion after this many seconds (exiting
+        /// with status 2)
+        #[clap(long)]
+        timeout: Option<u64>,
+    },
+}
+
+/// Parses an RPC endpoint, accepting Windows named pipes (e.g.,
+/// `\\.\pipe\geth.ipc`, optionally prefixed by `ipc://`) as is
+fn parse_rpc(s: &str) -> Result<Url, String> {
+    let pipe = s.strip_prefix("ipc://").unwrap_or(s);
+
+    if pipe.starts_with(r"\\.\") || pipe.starts_with("//./") {
+        ipc_url(Path::new(pipe)).map_err(|e| e.to_string())
+    } else {
+        Url::parse(s).map_err(|e| e.to_string())
+    }
+}
+
+/// Parses a header given as `name=value`
+fn parse_header(s: &str) -> Result<(String, String), String> {
+    s.split_once('=')
+        .map(|(name, value)| (name.trim().to_string(), value.to_string()))
+        .ok_or("expected a header as name=value".to_string())
+}
+
+/// Parses an inclusive block range (e.g., `19000000..19000100`)
+fn parse_block_range(s: &str) -> Result<RangeInclusive<BlockNumber>, String> {
+    let (from, to) = s
+        .split_once("..")
+        .ok_or("expected a block range (e.g., 19000000..19000100)")?;
+    let from: BlockNumber = from.parse().map_err(|_| "invalid start block")?;
+    let to: BlockNumber = to.parse().map_err(|_| "invalid end block")?;
+
+    if from > to {
+        return Err("block range is empty".to_string());
+    }
+
+    Ok(from..=to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sinks() {
+        let opts = Opts::parse_from([
+            "blocktop",
+            "--headless",
+            "--sink",
+            "ndjson",
+            "--sink",
+            "db",
+            "--sink",
+            "ndjson",
+        ]);
+        assert_eq!(opts.sinks(), vec![SinkSpec::Ndjson, SinkSpec::Database]);
+
+        let opts = Opts::parse_from(["blocktop", "--sink", "ndjson"]);
+        assert_eq!(opts.sinks(), vec![SinkSpec::Database, SinkSpec::Ndjson]);
+    }
+}
@@ -1,22 +1,96 @@
 //! Blockchain client communications
 #![allow(async_fn_in_trait)]
 use std::{
+    collections::HashMap,
+    fmt,
+    num::NonZeroUsize,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError,
+    },
 };
 
 use alloy::{
-    eips::BlockId,
-    primitives::{ChainId, TxHash},
+    eips::{eip2718::Encodable2718, BlockId},
+    network::{AnyRpcBlock, AnyTransactionReceipt},
+    primitives::{
+        keccak256, BlockHash, BlockNumber, Bytes, ChainId, TxHash, U64,
+    },
     providers::{IpcConnect, Provider, ProviderBuilder, WsConnect},
     pubsub::PubSubConnect,
-    rpc::types::{Block, Header, Transaction},
+    rpc::{
+        client::{BatchRequest, ClientBuilder},
+        types::{Block, Header, Transaction},
+    },
+    transports::TransportError,
 };
-use eyre::eyre;
-use futures::Stream;
-use log::{debug, info};
+use futures::{Stream, StreamExt};
+use log::{debug, info, warn};
+use lru::LruCache;
+use prometheus::IntCounter;
+use serde::Deserialize;
+use serde_json::{json, Value};
 use url::Url;
 
+use crate::{
+    l2::{AnyBlock, Rollup},
+    latency, rate_limit,
+    transport::{AuthWsConnect, Credentials},
+};
+
+/// Number of blocks (and, separately, of blocks' receipts) remembered by a
+/// [`CachedClient`]
+const CACHED_BLOCKS: usize = 256;
+/// Number of transactions remembered by a [`CachedClient`]
+const CACHED_TRANSACTIONS: usize = 4_096;
+/// Number of full blocks requested at once by [`Client::blocks`] (which are
+/// still yielded in the order that their headers arrived)
+const CONCURRENT_BLOCK_REQUESTS: usize = 8;
+/// JSON-RPC error code for methods that the node doesn't implement
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Result of communicating with a node
+pub type Result<T, E = ClientError> = std::result::Result<T, E>;
+
+/// Failure to retrieve something from a node
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    /// The node doesn't know of the requested object (e.g., a block that it
+    /// hasn't seen yet, or a transaction that has been dropped)
+    #[error("{0} not found by the RPC node")]
+    NotFound(&'static str),
+    /// The request didn't reach the node, or its response couldn't be decoded
+    #[error(transparent)]
+    Transport(#[from] TransportError),
+    /// The request can't be made of any node (e.g., an endpoint of an unknown
+    /// kind)
+    #[error("unsupported {0}")]
+    Unsupported(String),
+    /// There were no endpoints to connect to
+    #[error("no RPC endpoints")]
+    NoEndpoints,
+}
+
+impl ClientError {
+    /// Whether this is a [`ClientError::NotFound`]
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Self::NotFound(_))
+    }
+
+    /// Whether the request never reached the node (e.g., as the connection to
+    /// it has been lost), as opposed to the node having refused it
+    pub fn is_disconnected(&self) -> bool {
+        matches!(self, Self::Transport(e) if e.is_transport_error())
+    }
+
+    /// Whether the node responded to the request with an error (e.g., as it
+    /// doesn't support the requested method or block tag)
+    pub fn is_refused(&self) -> bool {
+        matches!(self, Self::Transport(e) if e.as_error_resp().is_some())
+    }
+}
+
 pub type NightmareProvider = alloy::providers::fillers::FillProvider<
     alloy::providers::fillers::JoinFill<
         alloy::providers::Identity,
@@ -34,28 +108,300 @@ pub type NightmareProvider = alloy::providers::fillers::FillProvider<
     alloy::providers::RootProvider,
 >;
 
+/// The subset of a pending block needed to compare it against the block that
+/// eventually lands
+///
+/// Pending blocks lack fields (e.g., the hash) that a full [`Block`] requires,
+/// so these are requested with transaction hashes only.
+#[derive(Clone, Debug, Deserialize)]
+struct PendingBlock {
+    number: U64,
+    transactions: Vec<TxHash>,
+}
+
+/// Optional parts of the JSON-RPC API that a node was found to support when
+/// it was connected to
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Capabilities {
+    /// The `txpool` namespace
+    pub txpool: bool,
+    /// The `debug` namespace (specifically `debug_traceTransaction`)
+    pub debug: bool,
+    /// `eth_getBlockReceipts`
+    pub block_receipts: bool,
+}
+
+impl Capabilities {
+    /// Find out which optional parts of the JSON-RPC API the node behind the
+    /// given provider supports
+    ///
+    /// Each is probed with a cheap request (e.g., for a transaction that
+    /// doesn't exist), so any response other than one saying that the method
+    /// isn't supported counts as support.
+    pub async fn probe(provider: &NightmareProvider) -> Self {
+        let (txpool, debug, block_receipts) = tokio::join!(
+            supports(provider, "txpool_status", json!([])),
+            supports(provider, "debug_traceTransaction", json!([TxHash::ZERO])),
+            supports(provider, "eth_getBlockReceipts", json!(["earliest"])),
+        );
+        let this = Self {
+            txpool,
+            debug,
+            block_receipts,
+        };
+        info!("Node capabilities: {this}");
+        this
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let supported: Vec<&str> = [
+            (self.txpool, "txpool"),
+            (self.debug, "debug"),
+            (self.block_receipts, "eth_getBlockReceipts"),
+        ]
+        .into_iter()
+        .filter_map(|(supported, name)| supported.then_some(name))
+        .collect();
+
+        if supported.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", supported.join(", "))
+        }
+    }
+}
+
+/// Whether the node supports the given JSON-RPC method, judging by its
+/// response to a request with the given parameters
+async fn supports(
+    provider: &NightmareProvider,
+    method: &'static str,
+    params: Value,
+) -> bool {
+    match provider
+        .raw_request::<_, Value>(method.into(), params)
+        .await
+    {
+        Ok(_) => true,
+        Err(e) => match e.as_error_resp() {
+            Some(payload) => !unsupported(payload.code, &payload.message),
+            None => {
+                debug!("Failed to probe for {method}: {e:?}");
+                false
+            }
+        },
+    }
+}
+
+/// Whether an error response with the given code and message means that the
+/// requested method isn't supported (or isn't exposed) by the node
+fn unsupported(code: i64, message: &str) -> bool {
+    let message = message.to_lowercase();
+    code == METHOD_NOT_FOUND
+        || [
+            "not supported",
+            "method not found",
+            "not whitelisted",
+            "not allowed",
+        ]
+        .iter()
+        .any(|phrase| message.contains(phrase))
+}
+
 /// Interface to an Ethereum node
 pub trait Client {
     /// The URL of the endpoint that this client is connected to
     fn url(&self) -> Url;
     /// The [`ChainId`] that this client is on
     fn chain_id(&self) -> ChainId;
-    /// Subscription stream yielding full [`Block`]s
+    /// Optional parts of the JSON-RPC API that the node supports
+    fn capabilities(&self) -> Capabilities;
+    /// Subscription stream yielding full blocks (see [`AnyBlock`])
+    ///
+    /// Blocks that the node fails to serve are skipped (and logged).
     async fn blocks(
         &self,
-    ) -> eyre::Result<Box<dyn Stream<Item = Block> + Unpin>>;
+    ) -> Result<Box<dyn Stream<Item = AnyBlock> + Send + Unpin>>;
     /// Subscription stream yielding only block [`Header`]s
     async fn block_headers(
         &self,
-    ) -> eyre::Result<Box<dyn Stream<Item = Header> + Unpin>>;
+    ) -> Result<Box<dyn Stream<Item = Header> + Send + Unpin>>;
     /// Subscription stream yielding pending transactions from the mempool
     async fn pending_transactions(
         &self,
-    ) -> eyre::Result<Box<dyn Stream<Item = Transaction> + Unpin>>;
+    ) -> Result<Box<dyn Stream<Item = Transaction> + Send + Unpin>>;
     /// Retrieve the [`Block`] associated with the given identifier
-    async fn block(&self, id: BlockId) -> eyre::Result<Block>;
+    async fn block(&self, id: BlockId) -> Result<Block>;
     /// Retrieve the [`Transaction`] associated with the given [`TxHash`]
-    async fn transaction(&self, hash: TxHash) -> eyre::Result<Transaction>;
+    async fn transaction(&self, hash: TxHash) -> Result<Transaction>;
+    /// Retrieve the EIP-2718 encoding of the transaction associated with the
+    /// given [`TxHash`] (i.e., as it would be broadcast)
+    async fn raw_transaction(&self, hash: TxHash) -> Result<Bytes>;
+    /// Retrieve the receipts for every transaction in the block associated
+    /// with the given identifier (including those of types that Ethereum
+    /// doesn't know, e.g. rollup deposits)
+    async fn block_receipts(
+        &self,
+        id: BlockId,
+    ) -> Result<Vec<AnyTransactionReceipt>>;
+    /// Retrieve the number of the node's current pending block along with the
+    /// hashes of the transactions it contains (in order)
+    async fn pending_block_transactions(
+        &self,
+    ) -> Result<(BlockNumber, Vec<TxHash>)>;
+    /// Retrieve the number of the latest finalized block
+    async fn finalized_block_number(&self) -> Result<BlockNumber>;
+    /// Retrieve the number of the latest safe block
+    async fn safe_block_number(&self) -> Result<BlockNumber>;
+}
+
+/// Ordered list of RPC endpoints serving the same chain, shared by every
+/// client so that they all fail over together
+#[derive(Debug)]
+pub struct Endpoints {
+    urls: Vec<Url>,
+    /// Presented to every endpoint (over Websockets)
+    credentials: Credentials,
+    /// Index of the endpoint currently in use
+    active: AtomicUsize,
+    failovers: Arc<IntCounter>,
+    /// What each endpoint was found to support when first connected to
+    capabilities: Mutex<HashMap<Url, Capabilities>>,
+}
+
+impl Endpoints {
+    /// Use the given primary endpoint, failing over to each of the given
+    /// fallbacks in turn (and then back to the primary), counting failovers
+    pub fn new(
+        primary: Url,
+        fallbacks: Vec<Url>,
+        credentials: Credentials,
+        failovers: Arc<IntCounter>,
+    ) -> Self {
+        Self {
+            urls: std::iter::once(primary).chain(fallbacks).collect(),
+            credentials,
+            active: AtomicUsize::new(0),
+            failovers,
+            capabilities: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The endpoint currently in use
+    pub fn active(&self) -> Url {
+        self.urls[self.active.load(Ordering::Relaxed)].clone()
+    }
+
+    /// Every endpoint, in the order they're failed over to
+    pub fn urls(&self) -> &[Url] {
+        &self.urls
+    }
+
+    /// Credentials presented to every endpoint
+    pub fn credentials(&self) -> &Credentials {
+        &self.credentials
+    }
+
+    /// What the given endpoint was found to support (if it's been connected
+    /// to before)
+    pub fn capabilities(&self, url: &Url) -> Option<Capabilities> {
+        self.capabilities
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(url)
+            .copied()
+    }
+
+    /// Whether there's any endpoint to fail over to
+    pub fn has_fallbacks(&self) -> bool {
+        self.urls.len() > 1
+    }
+
+    /// Stop using the given endpoint in favour of the next one, unless
+    /// something else has already failed over from it
+    pub fn fail_over(&self, from: &Url) {
+        let current = self.active.load(Ordering::Relaxed);
+        let next = (current + 1) % self.urls.len();
+
+        if next == current
+            || self.urls[current] != *from
+            || self
+                .active
+                .compare_exchange(
+                    current,
+                    next,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                )
+                .is_err()
+        {
+            return;
+        }
+
+        warn!(
+            "Failing over from RPC endpoint {} to {}",
+            redacted(from),
+            redacted(&self.urls[next])
+        );
+        self.failovers.inc();
+    }
+}
+
+/// Connection to whichever of the [`Endpoints`] is in use, as held by a
+/// service making requests on demand
+///
+/// Nothing is connected until a client is first asked for. Should a request
+/// fail to reach the node, the connection is dropped (failing over to the next
+/// endpoint), so that the next request reconnects.
+#[derive(Debug)]
+pub struct Connection {
+    endpoints: Arc<Endpoints>,
+    client: Option<(Url, AnyClient)>,
+}
+
+impl Connection {
+    pub fn new(endpoints: Arc<Endpoints>) -> Self {
+        Self {
+            endpoints,
+            client: None,
+        }
+    }
+
+    /// Client connected to the endpoint in use, connecting first if need be
+    pub async fn client(&mut self) -> Result<&AnyClient> {
+        let (_, client) = match self.client.take() {
+            Some(connected) => self.client.insert(connected),
+            None => {
+                let client = AnyClient::connect(&self.endpoints).await?;
+                self.client.insert((self.endpoints.active(), client))
+            }
+        };
+        Ok(client)
+    }
+
+    /// Take note of the given error from a request made via this connection,
+    /// dropping the connection if the request didn't reach the node
+    pub fn failed(&mut self, e: &ClientError) {
+        if !e.is_disconnected() {
+            return;
+        }
+
+        if let Some((url, _)) = self.client.take() {
+            warn!("Lost connection to {}: {e}", redacted(&url));
+            self.endpoints.fail_over(&url);
+        }
+    }
+}
+
+/// The given endpoint without any credentials (e.g., API keys) that its path
+/// or query might contain
+pub fn redacted(url: &Url) -> String {
+    match url.scheme() {
+        "ipc" => url.to_string(),
+        _ => url.origin().ascii_serialization(),
+    }
 }
 
 /// Client type that is generic over all supported transports
@@ -69,19 +415,83 @@ pub enum AnyClient {
 
 impl AnyClient {
     /// Parse the provided [`Url`] into the corresponding [`AnyClient`]
-    pub async fn new(url: Url) -> eyre::Result<Self> {
+    pub async fn new(url: Url) -> Result<Self> {
+        Self::with_credentials(url, &Credentials::default()).await
+    }
+
+    /// Parse the provided [`Url`] into the corresponding [`AnyClient`],
+    /// presenting the given [`Credentials`] to the node (which only applies
+    /// to Websockets)
+    pub async fn with_credentials(
+        url: Url,
+        credentials: &Credentials,
+    ) -> Result<Self> {
+        Self::open(url, credentials, None).await
+    }
+
+    /// Parse the provided [`Url`] into the corresponding [`AnyClient`], taking
+    /// the node to support the given [`Capabilities`] (if given) rather than
+    /// probing it for them
+    async fn open(
+        url: Url,
+        credentials: &Credentials,
+        capabilities: Option<Capabilities>,
+    ) -> Result<Self> {
         match url.scheme() {
-            "ws" | "wss" => Ok(AnyClient::Ws(WsClient::new(url).await?)),
-            "ipc" => Ok(AnyClient::Ipc(
-                IpcClient::new::<PathBuf>(
-                    url.to_string().strip_prefix("ipc://").unwrap().into(),
-                )
-                .await?,
+            "ws" | "wss" => Ok(AnyClient::Ws(
+                WsClient::new(url, credentials, capabilities).await?,
             )),
-            _ => Err(eyre!("Unsupported URL scheme")),
+            "ipc" => {
+                if !credentials.is_empty() {
+                    debug!("Not sending credentials over IPC");
+                }
+                Ok(AnyClient::Ipc(
+                    IpcClient::new(ipc_path(&url), capabilities).await?,
+                ))
+            }
+            scheme => {
+                Err(ClientError::Unsupported(format!("URL scheme {scheme}")))
+            }
         }
     }
 
+    /// Connect to the endpoint in use, failing over to the next for as long
+    /// as connecting fails (giving up once every endpoint has been tried)
+    pub async fn connect(endpoints: &Endpoints) -> Result<Self> {
+        let mut result = Err(ClientError::NoEndpoints);
+
+        for _ in 0..endpoints.urls.len() {
+            let url = endpoints.active();
+            result = Self::connect_to(endpoints, url.clone()).await;
+            match &result {
+                Ok(_) => break,
+                Err(e) => {
+                    warn!("Failed to connect to {}: {e}", redacted(&url));
+                    endpoints.fail_over(&url);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Connect to the given one of the [`Endpoints`] (whether it's in use or
+    /// not), probing it for its [`Capabilities`] only the first time
+    pub async fn connect_to(endpoints: &Endpoints, url: Url) -> Result<Self> {
+        let client = Self::open(
+            url.clone(),
+            &endpoints.credentials,
+            endpoints.capabilities(&url),
+        )
+        .await?;
+        endpoints
+            .capabilities
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(url, client.capabilities());
+        Ok(client)
+    }
+
     /// Handle to the internal Alloy provider
     pub fn provider(&self) -> &NightmareProvider {
         match self {
@@ -89,6 +499,14 @@ impl AnyClient {
             Self::Ipc(t) => t.provider(),
         }
     }
+
+    /// Retrieve the full block associated with the given identifier,
+    /// including whatever's peculiar to rollups about it (see [`AnyBlock`])
+    pub async fn any_block(&self, id: BlockId) -> Result<AnyBlock> {
+        any_block(self.provider(), id, Rollup::of(self.chain_id()))
+            .await?
+            .ok_or(ClientError::NotFound("block"))
+    }
 }
 
 impl Client for AnyClient {
@@ -106,9 +524,16 @@ impl Client for AnyClient {
         }
     }
 
+    fn capabilities(&self) -> Capabilities {
+        match self {
+            Self::Ws(t) => t.capabilities(),
+            Self::Ipc(t) => t.capabilities(),
+        }
+    }
+
     async fn blocks(
         &self,
-    ) -> eyre::Result<Box<dyn Stream<Item = Block> + Unpin>> {
+    ) -> Result<Box<dyn Stream<Item = AnyBlock> + Send + Unpin>> {
         Ok(match self {
             Self::Ws(t) => t.blocks().await?,
             Self::Ipc(t) => t.blocks().await?,
@@ -117,7 +542,7 @@ impl Client for AnyClient {
 
     async fn block_headers(
         &self,
-    ) -> eyre::Result<Box<dyn Stream<Item = Header> + Unpin>> {
+    ) -> Result<Box<dyn Stream<Item = Header> + Send + Unpin>> {
         Ok(match self {
             Self::Ws(t) => t.block_headers().await?,
             Self::Ipc(t) => t.block_headers().await?,
@@ -126,26 +551,247 @@ impl Client for AnyClient {
 
     async fn pending_transactions(
         &self,
-    ) -> eyre::Result<Box<dyn Stream<Item = Transaction> + Unpin>> {
+    ) -> Result<Box<dyn Stream<Item = Transaction> + Send + Unpin>> {
         Ok(match self {
             Self::Ws(t) => t.pending_transactions().await?,
             Self::Ipc(t) => t.pending_transactions().await?,
         })
     }
 
-    async fn block(&self, id: BlockId) -> eyre::Result<Block> {
+    async fn block(&self, id: BlockId) -> Result<Block> {
         Ok(match self {
             Self::Ws(t) => t.block(id).await?,
             Self::Ipc(t) => t.block(id).await?,
         })
     }
 
-    async fn transaction(&self, hash: TxHash) -> eyre::Result<Transaction> {
+    async fn transaction(&self, hash: TxHash) -> Result<Transaction> {
         Ok(match self {
             Self::Ws(t) => t.transaction(hash).await?,
             Self::Ipc(t) => t.transaction(hash).await?,
         })
     }
+
+    async fn raw_transaction(&self, hash: TxHash) -> Result<Bytes> {
+        Ok(match self {
+            Self::Ws(t) => t.raw_transaction(hash).await?,
+            Self::Ipc(t) => t.raw_transaction(hash).await?,
+        })
+    }
+
+    async fn block_receipts(
+        &self,
+        id: BlockId,
+    ) -> Result<Vec<AnyTransactionReceipt>> {
+        Ok(match self {
+            Self::Ws(t) => t.block_receipts(id).await?,
+            Self::Ipc(t) => t.block_receipts(id).await?,
+        })
+    }
+
+    async fn pending_block_transactions(
+        &self,
+    ) -> Result<(BlockNumber, Vec<TxHash>)> {
+        Ok(match self {
+            Self::Ws(t) => t.pending_block_transactions().await?,
+            Self::Ipc(t) => t.pending_block_transactions().await?,
+        })
+    }
+
+    async fn finalized_block_number(&self) -> Result<BlockNumber> {
+        Ok(match self {
+            Self::Ws(t) => t.finalized_block_number().await?,
+            Self::Ipc(t) => t.finalized_block_number().await?,
+        })
+    }
+
+    async fn safe_block_number(&self) -> Result<BlockNumber> {
+        Ok(match self {
+            Self::Ws(t) => t.safe_block_number().await?,
+            Self::Ipc(t) => t.safe_block_number().await?,
+        })
+    }
+}
+
+/// [`AnyClient`] that remembers the blocks, transactions, and receipts that it
+/// looks up in memory (up to a point)
+///
+/// Only lookups by hash are answered from memory, as what a block number (or
+/// tag) refers to can change (e.g., due to a reorg). Likewise, transactions
+/// are only remembered once they've been included in a block.
+#[derive(Clone, Debug)]
+pub struct CachedClient {
+    client: AnyClient,
+    cache: Arc<Mutex<RpcCache>>,
+}
+
+#[derive(Debug)]
+struct RpcCache {
+    blocks: LruCache<BlockHash, AnyBlock>,
+    transactions: LruCache<TxHash, Transaction>,
+    receipts: LruCache<BlockHash, Vec<AnyTransactionReceipt>>,
+}
+
+impl RpcCache {
+    fn remember_block(&mut self, block: &AnyBlock) {
+        for tx in block.block.transactions.txns() {
+            self.transactions.put(*tx.inner.tx_hash(), tx.clone());
+        }
+        self.blocks.put(block.block.header.hash, block.clone());
+    }
+}
+
+impl CachedClient {
+    pub fn new(client: AnyClient) -> Self {
+        let capacity = |n| NonZeroUsize::new(n).expect("zero cache capacity");
+        Self {
+            client,
+            cache: Arc::new(Mutex::new(RpcCache {
+                blocks: LruCache::new(capacity(CACHED_BLOCKS)),
+                transactions: LruCache::new(capacity(CACHED_TRANSACTIONS)),
+                receipts: LruCache::new(capacity(CACHED_BLOCKS)),
+            })),
+        }
+    }
+
+    /// Handle to the internal Alloy provider (which bypasses the cache)
+    pub fn provider(&self) -> &NightmareProvider {
+        self.client.provider()
+    }
+
+    /// Retrieve the full block associated with the given identifier (if the
+    /// node knows of it)
+    pub async fn block_by_id(&self, id: BlockId) -> Result<Option<AnyBlock>> {
+        if let BlockId::Hash(hash) = id {
+            if let Some(block) = self.cache().blocks.get(&hash.block_hash) {
+                return Ok(Some(block.clone()));
+            }
+        }
+
+        let block =
+            any_block(self.provider(), id, Rollup::of(self.chain_id())).await?;
+        if let Some(block) = &block {
+            self.cache().remember_block(block);
+        }
+        Ok(block)
+    }
+
+    /// Retrieve the headers of the ommers (i.e., uncles) of the given
+    /// (pre-Merge) block, as blocks without transactions
+    pub async fn ommers(&self, block: &Block) -> Result<Vec<Block>> {
+        let mut ommers = Vec::with_capacity(block.uncles.len());
+        for index in 0..block.uncles.len() as u64 {
+            if let Some(ommer) = self
+                .provider()
+                .get_uncle(block.header.hash.into(), index)
+                .await?
+            {
+                ommers.push(ommer);
+            }
+        }
+        Ok(ommers)
+    }
+
+    /* the cache only ever holds copies of what the node returned, so it's
+     * still sound to use should another thread have panicked holding it */
+    fn cache(&self) -> std::sync::MutexGuard<'_, RpcCache> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Client for CachedClient {
+    fn url(&self) -> Url {
+        self.client.url()
+    }
+
+    fn chain_id(&self) -> ChainId {
+        self.client.chain_id()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.client.capabilities()
+    }
+
+    async fn blocks(
+        &self,
+    ) -> Result<Box<dyn Stream<Item = AnyBlock> + Send + Unpin>> {
+        let cache = self.cache.clone();
+        Ok(Box::new(self.client.blocks().await?.inspect(
+            move |block| {
+                cache
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .remember_block(block);
+            },
+        )))
+    }
+
+    async fn block_headers(
+        &self,
+    ) -> Result<Box<dyn Stream<Item = Header> + Send + Unpin>> {
+        self.client.block_headers().await
+    }
+
+    async fn pending_transactions(
+        &self,
+    ) -> Result<Box<dyn Stream<Item = Transaction> + Send + Unpin>> {
+        self.client.pending_transactions().await
+    }
+
+    async fn block(&self, id: BlockId) -> Result<Block> {
+        self.block_by_id(id)
+            .await?
+            .map(|block| block.block)
+            .ok_or(ClientError::NotFound("block"))
+    }
+
+    async fn transaction(&self, hash: TxHash) -> Result<Transaction> {
+        if let Some(tx) = self.cache().transactions.get(&hash) {
+            return Ok(tx.clone());
+        }
+
+        let tx = self.client.transaction(hash).await?;
+        if tx.block_hash.is_some() {
+            self.cache().transactions.put(hash, tx.clone());
+        }
+        Ok(tx)
+    }
+
+    async fn raw_transaction(&self, hash: TxHash) -> Result<Bytes> {
+        self.client.raw_transaction(hash).await
+    }
+
+    async fn block_receipts(
+        &self,
+        id: BlockId,
+    ) -> Result<Vec<AnyTransactionReceipt>> {
+        let hash = match id {
+            BlockId::Hash(hash) => hash.block_hash,
+            BlockId::Number(_) => return self.client.block_receipts(id).await,
+        };
+
+        if let Some(receipts) = self.cache().receipts.get(&hash) {
+            return Ok(receipts.clone());
+        }
+
+        let receipts = self.client.block_receipts(id).await?;
+        self.cache().receipts.put(hash, receipts.clone());
+        Ok(receipts)
+    }
+
+    async fn pending_block_transactions(
+        &self,
+    ) -> Result<(BlockNumber, Vec<TxHash>)> {
+        self.client.pending_block_transactions().await
+    }
+
+    async fn finalized_block_number(&self) -> Result<BlockNumber> {
+        self.client.finalized_block_number().await
+    }
+
+    async fn safe_block_number(&self) -> Result<BlockNumber> {
+        self.client.safe_block_number().await
+    }
 }
 
 /// Websocket client
@@ -154,27 +800,53 @@ pub struct WsClient {
     url: Url,
     chain_id: ChainId,
     provider: Arc<NightmareProvider>,
+    capabilities: Capabilities,
+    /// Whether the node is (still) believed to support `eth_getBlockReceipts`
+    block_receipts_supported: Arc<AtomicBool>,
 }
 
 impl WsClient {
-    /// Produce a handle to a Websocket client given a [`Url`]
+    /// Produce a handle to a Websocket client given a [`Url`] (along with
+    /// any [`Credentials`] that the node requires)
     ///
-    /// This will query the [`ChainId`] upon successful connection to the node.
-    pub async fn new(url: Url) -> eyre::Result<Self> {
-        let provider = Arc::new(
-            ProviderBuilder::new()
-                .connect_ws(WsConnect::new(url.clone()))
-                .await?,
-        );
+    /// This will query the [`ChainId`] (and probe for [`Capabilities`], unless
+    /// they're given) upon successful connection to the node.
+    pub async fn new(
+        url: Url,
+        credentials: &Credentials,
+        capabilities: Option<Capabilities>,
+    ) -> Result<Self> {
+        /* the rate limit is applied outside of the timing, so that time spent
+         * throttled isn't mistaken for the node being slow */
+        let builder = ClientBuilder::default()
+            .layer(rate_limit::layer())
+            .layer(latency::layer());
+        let client = if credentials.is_empty() && url.username().is_empty() {
+            builder.pubsub(WsConnect::new(url.clone())).await?
+        } else {
+            builder
+                .pubsub(AuthWsConnect::new(url.clone(), credentials.clone()))
+                .await?
+        };
+        let provider = Arc::new(ProviderBuilder::new().connect_client(client));
         let chain_id = provider.get_chain_id().await?;
+        let capabilities = match capabilities {
+            Some(capabilities) => capabilities,
+            None => Capabilities::probe(&provider).await,
+        };
         info!(
             "Websockets client initialised (endpoint: {}, chain: {})",
-            url, chain_id
+            redacted(&url),
+            chain_id
         );
         Ok(Self {
             url,
             chain_id,
             provider,
+            capabilities,
+            block_receipts_supported: Arc::new(AtomicBool::new(
+                capabilities.block_receipts,
+            )),
         })
     }
 
@@ -192,16 +864,24 @@ impl Client for WsClient {
         self.chain_id
     }
 
+    fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
     async fn blocks(
         &self,
-    ) -> eyre::Result<Box<dyn Stream<Item = Block> + Unpin>> {
+    ) -> Result<Box<dyn Stream<Item = AnyBlock> + Send + Unpin>> {
         debug!("Subscribing to block stream...");
-        todo!()
+        Ok(full_blocks(
+            self.provider.clone(),
+            Rollup::of(self.chain_id),
+            self.provider.subscribe_blocks().await?.into_stream(),
+        ))
     }
 
     async fn block_headers(
         &self,
-    ) -> eyre::Result<Box<dyn Stream<Item = Header> + Unpin>> {
+    ) -> Result<Box<dyn Stream<Item = Header> + Send + Unpin>> {
         debug!("Subscribing to block header stream...");
         Ok(Box::new(
             self.provider.subscribe_blocks().await?.into_stream(),
@@ -210,7 +890,7 @@ impl Client for WsClient {
 
     async fn pending_transactions(
         &self,
-    ) -> eyre::Result<Box<dyn Stream<Item = Transaction> + Unpin>> {
+    ) -> Result<Box<dyn Stream<Item = Transaction> + Send + Unpin>> {
         debug!("Subscribing to pending transaction stream...");
         Ok(Box::new(
             self.provider
@@ -220,20 +900,84 @@ impl Client for WsClient {
         ))
     }
 
-    async fn block(&self, id: BlockId) -> eyre::Result<Block> {
+    async fn block(&self, id: BlockId) -> Result<Block> {
         debug!("Retrieving block {}...", id);
-        match self.provider.get_block(id).full().await? {
-            Some(t) => Ok(t),
-            None => Err(eyre!("No block")),
+        match any_block(&self.provider, id, Rollup::of(self.chain_id)).await? {
+            Some(t) => Ok(t.block),
+            None => Err(ClientError::NotFound("block")),
         }
     }
 
-    async fn transaction(&self, hash: TxHash) -> eyre::Result<Transaction> {
+    async fn transaction(&self, hash: TxHash) -> Result<Transaction> {
         debug!("Retrieving transaction {}...", hash);
         match self.provider.get_transaction_by_hash(hash).await? {
             Some(t) => Ok(t),
-            None => Err(eyre!("No block")),
+            None => Err(ClientError::NotFound("transaction")),
+        }
+    }
+
+    async fn raw_transaction(&self, hash: TxHash) -> Result<Bytes> {
+        debug!("Retrieving raw transaction {}...", hash);
+        raw_transaction(&self.provider, hash).await
+    }
+
+    async fn block_receipts(
+        &self,
+        id: BlockId,
+    ) -> Result<Vec<AnyTransactionReceipt>> {
+        debug!("Retrieving receipts for block {}...", id);
+        block_receipts(&self.provider, id, &self.block_receipts_supported).await
+    }
+
+    async fn pending_block_transactions(
+        &self,
+    ) -> Result<(BlockNumber, Vec<TxHash>)> {
+        debug!("Retrieving pending block...");
+        pending_block_transactions(&self.provider).await
+    }
+
+    async fn finalized_block_number(&self) -> Result<BlockNumber> {
+        debug!("Retrieving finalized block...");
+        match self.provider.get_block(BlockId::finalized()).await? {
+            Some(t) => Ok(t.header.number),
+            None => Err(ClientError::NotFound("finalized block")),
+        }
+    }
+
+    async fn safe_block_number(&self) -> Result<BlockNumber> {
+        debug!("Retrieving safe block...");
+        match self.provider.get_block(BlockId::safe()).await? {
+            Some(t) => Ok(t.header.number),
+            None => Err(ClientError::NotFound("safe block")),
+        }
+    }
+}
+
+/// Path of the Unix domain socket or Windows named pipe that the given
+/// `ipc://` [`Url`] refers to
+///
+/// Backslashes aren't valid in URLs, so named pipes are written with `.` as
+/// the host (e.g., `ipc://./pipe/geth.ipc` for `\\.\pipe\geth.ipc`).
+pub fn ipc_path(url: &Url) -> PathBuf {
+    match url.host_str() {
+        Some(".") => format!(r"\\.{}", url.path().replace('/', r"\")).into(),
+        _ => url
+            .as_str()
+            .strip_prefix("ipc://")
+            .unwrap_or(url.path())
+            .into(),
+    }
+}
+
+/// The `ipc://` [`Url`] referring to the given Unix domain socket or Windows
+/// named pipe (the inverse of [`ipc_path`])
+pub fn ipc_url(path: &Path) -> Result<Url, url::ParseError> {
+    let path = path.to_string_lossy();
+    match path.strip_prefix(r"\\.\").or(path.strip_prefix("//./")) {
+        Some(pipe) => {
+            Url::parse(&format!("ipc://./{}", pipe.replace('\\', "/")))
         }
+        None => Url::parse(&format!("ipc://{path}")),
     }
 }
 
@@ -242,20 +986,37 @@ pub struct IpcClient {
     path: PathBuf,
     chain_id: ChainId,
     provider: Arc<NightmareProvider>,
+    capabilities: Capabilities,
+    /// Whether the node is (still) believed to support `eth_getBlockReceipts`
+    block_receipts_supported: Arc<AtomicBool>,
 }
 
 impl IpcClient {
-    /// Produce a handle to an IPC client given a filepath to a Unix named pipe
+    /// Produce a handle to an IPC client given a filepath to a Unix domain
+    /// socket or a Windows named pipe (e.g., `\\.\pipe\geth.ipc`)
     ///
-    /// This will query the [`ChainId`] upon successful connection to the node.
+    /// This will query the [`ChainId`] (and probe for [`Capabilities`], unless
+    /// they're given) upon successful connection to the node.
     /// Note that this path does **not** contain an `ipc://` URI scheme prefix.
-    pub async fn new<P: AsRef<Path> + Clone>(path: P) -> eyre::Result<Self>
+    pub async fn new<P: AsRef<Path> + Clone>(
+        path: P,
+        capabilities: Option<Capabilities>,
+    ) -> Result<Self>
     where
         IpcConnect<P>: PubSubConnect,
     {
         let ipc = IpcConnect::new(path.clone());
-        let provider = Arc::new(ProviderBuilder::new().connect_ipc(ipc).await?);
+        let client = ClientBuilder::default()
+            .layer(rate_limit::layer())
+            .layer(latency::layer())
+            .pubsub(ipc)
+            .await?;
+        let provider = Arc::new(ProviderBuilder::new().connect_client(client));
         let chain_id = provider.get_chain_id().await?;
+        let capabilities = match capabilities {
+            Some(capabilities) => capabilities,
+            None => Capabilities::probe(&provider).await,
+        };
         info!(
             "IPC client initialised (endpoint: ipc://{}, chain: {})",
             path.clone().as_ref().display(),
@@ -265,6 +1026,10 @@ impl IpcClient {
             path: path.as_ref().into(),
             chain_id,
             provider,
+            capabilities,
+            block_receipts_supported: Arc::new(AtomicBool::new(
+                capabilities.block_receipts,
+            )),
         })
     }
 
@@ -276,23 +1041,31 @@ impl IpcClient {
 
 impl Client for IpcClient {
     fn url(&self) -> Url {
-        format!("ipc://{}", self.path.display()).parse().unwrap()
+        ipc_url(&self.path).unwrap()
     }
 
     fn chain_id(&self) -> ChainId {
         self.chain_id
     }
 
+    fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
     async fn blocks(
         &self,
-    ) -> eyre::Result<Box<dyn Stream<Item = Block> + Unpin>> {
+    ) -> Result<Box<dyn Stream<Item = AnyBlock> + Send + Unpin>> {
         debug!("Subscribing to block stream...");
-        todo!()
+        Ok(full_blocks(
+            self.provider.clone(),
+            Rollup::of(self.chain_id),
+            self.provider.subscribe_blocks().await?.into_stream(),
+        ))
     }
 
     async fn block_headers(
         &self,
-    ) -> eyre::Result<Box<dyn Stream<Item = Header> + Unpin>> {
+    ) -> Result<Box<dyn Stream<Item = Header> + Send + Unpin>> {
         debug!("Subscribing to block header stream...");
         Ok(Box::new(
             self.provider.subscribe_blocks().await?.into_stream(),
@@ -301,7 +1074,7 @@ impl Client for IpcClient {
 
     async fn pending_transactions(
         &self,
-    ) -> eyre::Result<Box<dyn Stream<Item = Transaction> + Unpin>> {
+    ) -> Result<Box<dyn Stream<Item = Transaction> + Send + Unpin>> {
         debug!("Subscribing to pending transaction stream...");
         Ok(Box::new(
             self.provider
@@ -311,19 +1084,373 @@ impl Client for IpcClient {
         ))
     }
 
-    async fn block(&self, id: BlockId) -> eyre::Result<Block> {
+    async fn block(&self, id: BlockId) -> Result<Block> {
         debug!("Retrieving block {}...", id);
-        match self.provider.get_block(id).full().await? {
-            Some(t) => Ok(t),
-            None => Err(eyre!("No block")),
+        match any_block(&self.provider, id, Rollup::of(self.chain_id)).await? {
+            Some(t) => Ok(t.block),
+            None => Err(ClientError::NotFound("block")),
         }
     }
 
-    async fn transaction(&self, hash: TxHash) -> eyre::Result<Transaction> {
+    async fn transaction(&self, hash: TxHash) -> Result<Transaction> {
         debug!("Retrieving transaction {}...", hash);
         match self.provider.get_transaction_by_hash(hash).await? {
             Some(t) => Ok(t),
-            None => Err(eyre!("No block")),
+            None => Err(ClientError::NotFound("transaction")),
+        }
+    }
+
+    async fn raw_transaction(&self, hash: TxHash) -> Result<Bytes> {
+        debug!("Retrieving raw transaction {}...", hash);
+        raw_transaction(&self.provider, hash).await
+    }
+
+    async fn block_receipts(
+        &self,
+        id: BlockId,
+    ) -> Result<Vec<AnyTransactionReceipt>> {
+        debug!("Retrieving receipts for block {}...", id);
+        block_receipts(&self.provider, id, &self.block_receipts_supported).await
+    }
+
+    async fn pending_block_transactions(
+        &self,
+    ) -> Result<(BlockNumber, Vec<TxHash>)> {
+        debug!("Retrieving pending block...");
+        pending_block_transactions(&self.provider).await
+    }
+
+    async fn finalized_block_number(&self) -> Result<BlockNumber> {
+        debug!("Retrieving finalized block...");
+        match self.provider.get_block(BlockId::finalized()).await? {
+            Some(t) => Ok(t.header.number),
+            None => Err(ClientError::NotFound("finalized block")),
+        }
+    }
+
+    async fn safe_block_number(&self) -> Result<BlockNumber> {
+        debug!("Retrieving safe block...");
+        match self.provider.get_block(BlockId::safe()).await? {
+            Some(t) => Ok(t.header.number),
+            None => Err(ClientError::NotFound("safe block")),
+        }
+    }
+}
+
+/// Stream the full block corresponding to each [`Header`] yielded by the
+/// given subscription, from a chain built with the given [`Rollup`] framework
+/// (if any)
+fn full_blocks(
+    provider: Arc<NightmareProvider>,
+    rollup: Option<Rollup>,
+    headers: impl Stream<Item = Header> + Send + 'static,
+) -> Box<dyn Stream<Item = AnyBlock> + Send + Unpin> {
+    Box::new(Box::pin(
+        headers
+            .map(move |header| {
+                let provider = provider.clone();
+                async move {
+                    let block =
+                        any_block(&provider, header.hash.into(), rollup).await;
+                    (header.hash, block)
+                }
+            })
+            .buffered(CONCURRENT_BLOCK_REQUESTS)
+            .filter_map(|(hash, block)| async move {
+                match block {
+                    Ok(Some(block)) => Some(block),
+                    Ok(None) => {
+                        warn!("RPC node has no block {hash}, skipping it");
+                        None
+                    }
+                    Err(e) => {
+                        warn!("Failed to retrieve block {hash} from RPC, skipping it: {e:?}");
+                        None
+                    }
+                }
+            }),
+    ))
+}
+
+/// Retrieve the full block associated with the given identifier (if the node
+/// knows of it), from a chain built with the given [`Rollup`] framework (if
+/// any)
+///
+/// Unlike [`Provider::get_block`], this tolerates transactions of types that
+/// Ethereum doesn't know.
+async fn any_block(
+    provider: &NightmareProvider,
+    id: BlockId,
+    rollup: Option<Rollup>,
+) -> Result<Option<AnyBlock>> {
+    let block = match id {
+        BlockId::Hash(hash) => {
+            provider
+                .raw_request::<_, Option<AnyRpcBlock>>(
+                    "eth_getBlockByHash".into(),
+                    (hash.block_hash, true),
+                )
+                .await?
+        }
+        BlockId::Number(number) => {
+            provider
+                .raw_request::<_, Option<AnyRpcBlock>>(
+                    "eth_getBlockByNumber".into(),
+                    (number, true),
+                )
+                .await?
+        }
+    };
+    Ok(block.map(|block| AnyBlock::new(block, rollup)))
+}
+
+/// Retrieve the receipts of every transaction in the block associated with
+/// the given identifier
+///
+/// Should the node not support `eth_getBlockReceipts` (which is remembered via
+/// the given flag), the receipts are requested in a single batch of
+/// `eth_getTransactionReceipt` requests instead.
+async fn block_receipts(
+    provider: &NightmareProvider,
+    id: BlockId,
+    supported: &AtomicBool,
+) -> Result<Vec<AnyTransactionReceipt>> {
+    if supported.load(Ordering::Relaxed) {
+        match provider
+            .raw_request::<_, Option<Vec<AnyTransactionReceipt>>>(
+                "eth_getBlockReceipts".into(),
+                (id,),
+            )
+            .await
+        {
+            Ok(Some(receipts)) => return Ok(receipts),
+            Ok(None) => return Err(ClientError::NotFound("block")),
+            Err(e)
+                if e.as_error_resp()
+                    .is_some_and(|e| unsupported(e.code, &e.message)) =>
+            {
+                warn!("Node doesn't support eth_getBlockReceipts, batching receipt requests instead: {e}");
+                supported.store(false, Ordering::Relaxed);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let block = provider
+        .get_block(id)
+        .await?
+        .ok_or(ClientError::NotFound("block"))?;
+    if block.transactions.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut batch = BatchRequest::new(provider.client());
+    let waiters = block
+        .transactions
+        .hashes()
+        .map(|hash| {
+            batch.add_call::<_, Option<AnyTransactionReceipt>>(
+                "eth_getTransactionReceipt",
+                &(hash,),
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    batch.send().await?;
+
+    let mut receipts = Vec::with_capacity(waiters.len());
+    for waiter in waiters {
+        receipts.push(waiter.await?.ok_or(ClientError::NotFound("receipt"))?);
+    }
+    Ok(receipts)
+}
+
+/// Retrieve the EIP-2718 encoding of the transaction associated with the
+/// given [`TxHash`]
+///
+/// Should the node not support `eth_getRawTransactionByHash`, the transaction
+/// is retrieved in full instead and re-encoded (see [`reencode`]).
+async fn raw_transaction(
+    provider: &NightmareProvider,
+    hash: TxHash,
+) -> Result<Bytes> {
+    match provider.get_raw_transaction_by_hash(hash).await {
+        Ok(Some(raw)) => return Ok(raw),
+        Ok(None) => return Err(ClientError::NotFound("transaction")),
+        Err(e)
+            if e.as_error_resp()
+                .is_some_and(|e| unsupported(e.code, &e.message)) =>
+        {
+            debug!("Node doesn't support eth_getRawTransactionByHash, re-encoding transaction {hash} instead: {e}");
         }
+        Err(e) => return Err(e.into()),
+    }
+
+    match provider.get_transaction_by_hash(hash).await? {
+        Some(tx) => reencode(&tx),
+        None => Err(ClientError::NotFound("transaction")),
+    }
+}
+
+/// EIP-2718 encoding of the given [`Transaction`] (with its signature), so
+/// long as it hashes to the transaction's hash (i.e., nothing was lost in
+/// decoding it)
+fn reencode(tx: &Transaction) -> Result<Bytes> {
+    let raw = Bytes::from(tx.inner.inner().encoded_2718());
+    if keccak256(&raw) == *tx.inner.tx_hash() {
+        Ok(raw)
+    } else {
+        Err(ClientError::Unsupported(format!(
+            "re-encoding of transaction {}",
+            tx.inner.tx_hash()
+        )))
+    }
+}
+
+async fn pending_block_transactions(
+    provider: &NightmareProvider,
+) -> Result<(BlockNumber, Vec<TxHash>)> {
+    match provider
+        .raw_request::<_, Option<PendingBlock>>(
+            "eth_getBlockByNumber".into(),
+            ("pending", false),
+        )
+        .await?
+    {
+        Some(t) => Ok((t.number.to(), t.transactions)),
+        None => Err(ClientError::NotFound("pending block")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        consensus::{
+            transaction::Recovered, SignableTransaction, Signed, TxEnvelope,
+            TxLegacy,
+        },
+        primitives::{Address, Signature, TxKind, U256},
+        rpc::json_rpc::ErrorPayload,
+        transports::TransportErrorKind,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_failover() {
+        let primary = Url::parse("wss://eth.example.com/v3/key").unwrap();
+        let fallback = Url::parse("ipc:///tmp/geth.ipc").unwrap();
+        let failovers =
+            Arc::new(IntCounter::new("failovers", "failovers").unwrap());
+        let endpoints = Endpoints::new(
+            primary.clone(),
+            vec![fallback.clone()],
+            Credentials::default(),
+            failovers.clone(),
+        );
+        assert!(endpoints.has_fallbacks());
+        assert_eq!(endpoints.active(), primary);
+        assert_eq!(redacted(&primary), "wss://eth.example.com");
+
+        endpoints.fail_over(&primary);
+        assert_eq!(endpoints.active(), fallback);
+        /* already failed over from the primary */
+        endpoints.fail_over(&primary);
+        assert_eq!(endpoints.active(), fallback);
+        endpoints.fail_over(&fallback);
+        assert_eq!(endpoints.active(), primary);
+        assert_eq!(failovers.get(), 2);
+
+        let single = Endpoints::new(
+            primary.clone(),
+            vec![],
+            Credentials::default(),
+            failovers.clone(),
+        );
+        single.fail_over(&primary);
+        assert_eq!(single.active(), primary);
+        assert_eq!(failovers.get(), 2);
+    }
+
+    #[test]
+    fn test_capabilities() {
+        assert!(unsupported(
+            METHOD_NOT_FOUND,
+            "the method debug_traceTransaction does not exist/is not available"
+        ));
+        assert!(unsupported(-32000, "Method not whitelisted"));
+        assert!(!unsupported(-32000, "transaction not found"));
+
+        assert_eq!(Capabilities::default().to_string(), "none");
+        let capabilities = Capabilities {
+            debug: true,
+            block_receipts: true,
+            ..Default::default()
+        };
+        assert_eq!(capabilities.to_string(), "debug, eth_getBlockReceipts");
+    }
+
+    #[test]
+    fn test_disconnection() {
+        assert!(ClientError::from(TransportErrorKind::backend_gone())
+            .is_disconnected());
+        assert!(!ClientError::from(TransportError::ErrorResp(ErrorPayload {
+            code: -32000,
+            message: "execution reverted".into(),
+            data: None,
+        }))
+        .is_disconnected());
+        assert!(!ClientError::NotFound("block").is_disconnected());
+    }
+
+    #[test]
+    fn test_ipc_paths() {
+        let socket = Url::parse("ipc:///tmp/anvil.ipc").unwrap();
+        assert_eq!(ipc_path(&socket), PathBuf::from("/tmp/anvil.ipc"));
+        assert_eq!(ipc_url(&ipc_path(&socket)).unwrap(), socket);
+
+        let pipe = ipc_url(Path::new(r"\\.\pipe\geth.ipc")).unwrap();
+        assert_eq!(pipe.as_str(), "ipc://./pipe/geth.ipc");
+        assert_eq!(ipc_path(&pipe), PathBuf::from(r"\\.\pipe\geth.ipc"));
+        assert_eq!(ipc_url(Path::new("//./pipe/geth.ipc")).unwrap(), pipe);
+    }
+
+    #[test]
+    fn test_reencode() {
+        let legacy = TxLegacy {
+            chain_id: Some(1),
+            nonce: 7,
+            gas_price: 1_000_000_000,
+            gas_limit: 21_000,
+            to: TxKind::Call(Address::with_last_byte(1)),
+            value: U256::from(1),
+            input: Bytes::new(),
+        };
+        let transaction = |signed: Signed<TxLegacy>| Transaction {
+            inner: Recovered::new_unchecked(
+                TxEnvelope::Legacy(signed),
+                Address::with_last_byte(2),
+            ),
+            block_hash: None,
+            block_number: None,
+            transaction_index: None,
+            effective_gas_price: None,
+        };
+
+        let signed = legacy.clone().into_signed(Signature::test_signature());
+        let hash = *signed.hash();
+        let raw = reencode(&transaction(signed)).unwrap();
+        assert_eq!(keccak256(&raw), hash);
+        assert_eq!(raw[0], 0xf8);
+
+        /* anything lost along the way changes the hash */
+        let signed = Signed::new_unchecked(
+            legacy,
+            Signature::test_signature(),
+            TxHash::ZERO,
+        );
+        assert!(matches!(
+            reencode(&transaction(signed)),
+            Err(ClientError::Unsupported(_))
+        ));
     }
 }
@@ -0,0 +1,170 @@
+//! User configuration file (e.g., `~/.config/blocktop/config.toml`)
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs,
+    num::{NonZeroU32, NonZeroU64},
+    path::{Path, PathBuf},
+};
+
+use alloy::primitives::Address;
+use eyre::eyre;
+use serde::Deserialize;
+use url::Url;
+
+use crate::{
+    sinks::{
+        alerts::{AlertRule, NotifierSpec},
+        SinkSpec,
+    },
+    ui::{
+        keymap::{Action, Key, Keymap, Preset},
+        theme::ThemeName,
+    },
+};
+
+/// Settings loaded from the configuration file
+///
+/// Every field is optional; options given on the command line take
+/// precedence.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Default RPC endpoint
+    pub rpc: Option<Url>,
+    /// Default RPC endpoints to fail over to (in order)
+    pub fallback_rpcs: Vec<Url>,
+    /// File containing a hex-encoded secret to sign JWTs for RPC nodes with
+    pub rpc_jwt_secret: Option<PathBuf>,
+    /// Headers to send when connecting to RPC nodes (e.g., API keys)
+    pub rpc_headers: BTreeMap<String, String>,
+    /// Default consensus layer node HTTP API
+    pub cl_rpc: Option<Url>,
+    /// Default Sourcify server
+    pub sourcify_url: Option<Url>,
+    /// Etherscan API key
+    pub etherscan_api_key: Option<String>,
+    /// Default database
+    pub db: Option<PathBuf>,
+    /// Maximum number of requests per second made to RPC nodes
+    pub rpc_rate_limit: Option<NonZeroU32>,
+    /// Number of requests that may be made to RPC nodes at once, despite the
+    /// rate limit
+    pub rpc_burst: Option<NonZeroU32>,
+    /// Default interval between TUI ticks while new blocks are arriving (in
+    /// milliseconds)
+    #[serde(alias = "tick_ms")]
+    pub tick_rate: Option<NonZeroU64>,
+    /// Default colour scheme
+    pub theme: Option<ThemeName>,
+    /// Show the latest block in the terminal's title
+    pub set_title: bool,
+    /// Addresses whose balances and nonces are recorded as blocks arrive (in
+    /// addition to any watched from within the TUI)
+    pub watchlist: Vec<Address>,
+    /// Default number of blocks a watched address's transaction may remain
+    /// pending for before it's flagged as stuck
+    pub stuck_after: Option<u64>,
+    /// Default sinks to write indexed data to (e.g., `["db", "ndjson"]`)
+    pub sinks: Vec<SinkSpec>,
+    pub alerts: AlertConfig,
+    pub keys: KeyConfig,
+}
+
+/// Alerting section of the configuration file
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertConfig {
+    /// Conditions to raise alerts upon (e.g., `"base-fee:50"`)
+    pub rules: Vec<AlertRule>,
+    /// Where to send alerts (e.g., `"desktop"`)
+    pub notify: Vec<NotifierSpec>,
+}
+
+/// Keybinding section of the configuration file
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyConfig {
+    /// Base set of keybindings
+    pub preset: Preset,
+    /// Additional bindings (e.g., `"Ctrl+d" = "down"`), which replace any of
+    /// the preset's bindings for the same key
+    pub bindings: HashMap<Key, Action>,
+}
+
+impl Config {
+    /// Location of the configuration file, respecting `$XDG_CONFIG_HOME`
+    pub fn default_path() -> Option<PathBuf> {
+        env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                env::var_os("HOME")
+                    .map(|home| PathBuf::from(home).join(".config"))
+            })
+            .map(|dir| dir.join("blocktop").join("config.toml"))
+    }
+
+    /// Read the configuration file at the given path
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| eyre!("Failed to read {}: {e}", path.display()))?;
+        toml::from_str(&contents)
+            .map_err(|e| eyre!("Failed to parse {}: {e}", path.display()))
+    }
+
+    /// Read the configuration file at the default path, if there is one
+    pub fn load_default() -> eyre::Result<Self> {
+        match Self::default_path() {
+            Some(path) if path.exists() => Self::load(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// The [`Keymap`] described by this configuration
+    pub fn keymap(&self) -> Keymap {
+        Keymap::new(self.keys.preset).with_bindings(&self.keys.bindings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let config: Config = toml::from_str(
+            r#"
+            rpc = "ws://localhost:8546"
+            tick_rate = 100
+            watchlist = ["0x0000000000000000000000000000000000000001"]
+
+            [alerts]
+            rules = ["base-fee:50"]
+            notify = ["desktop"]
+
+            [keys]
+            preset = "vim"
+
+            [keys.bindings]
+            "Ctrl+d" = "down"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.rpc, Some("ws://localhost:8546".parse().unwrap()));
+        assert_eq!(config.db, None);
+        assert_eq!(config.tick_rate, NonZeroU64::new(100));
+        assert!(toml::from_str::<Config>("tick_rate = 0").is_err());
+        assert_eq!(config.watchlist, vec![Address::with_last_byte(1)]);
+        assert_eq!(
+            config.alerts.rules,
+            vec![AlertRule::BaseFee(50_000_000_000)]
+        );
+        assert_eq!(config.alerts.notify, vec![NotifierSpec::Desktop]);
+        assert_eq!(config.keys.preset, Preset::Vim);
+        assert_eq!(
+            config.keymap().action("Ctrl+d".parse::<Key>().unwrap()),
+            Some(Action::Down)
+        );
+        assert!(toml::from_str::<Config>("colour = \"red\"").is_err());
+    }
+}
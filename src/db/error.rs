@@ -0,0 +1,86 @@
+//! Errors arising from reading and writing the database
+use std::{
+    num::{ParseIntError, TryFromIntError},
+    time::Duration,
+};
+
+use alloy::{
+    hex::FromHexError,
+    primitives::{ruint::ParseError, SignatureError},
+};
+
+/// Result of a database operation
+pub type Result<T, E = DbError> = std::result::Result<T, E>;
+
+/// Failure to read from or write to the [`Database`](super::Database)
+#[derive(Debug, thiserror::Error)]
+pub enum DbError {
+    /// The requested object isn't stored (so might yet be retrieved from the
+    /// RPC node instead)
+    #[error("{0} not found in the database")]
+    NotFound(&'static str),
+    /// A value couldn't be converted to or from its stored form (e.g., a
+    /// transaction missing its block, or a corrupted row)
+    #[error("malformed {0}")]
+    Decode(String),
+    /// No connection became available in time
+    #[error("timed out waiting for a database connection: {0}")]
+    PoolTimeout(#[from] r2d2::Error),
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+    /// The request can't be answered by this database (e.g., a query that
+    /// would modify it, or a kind of object it doesn't know how to store)
+    #[error("unsupported {0}")]
+    Unsupported(String),
+    /// A query was abandoned for running for longer than it was allowed to
+    #[error("query abandoned after {0:?}")]
+    Deadline(Duration),
+}
+
+impl DbError {
+    /// Whether this is a [`DbError::NotFound`]
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Self::NotFound(_))
+    }
+
+    /// Whether this is a [`DbError::Unsupported`]
+    pub fn is_unsupported(&self) -> bool {
+        matches!(self, Self::Unsupported(_))
+    }
+}
+
+impl From<FromHexError> for DbError {
+    fn from(e: FromHexError) -> Self {
+        Self::Decode(e.to_string())
+    }
+}
+
+impl From<ParseError> for DbError {
+    fn from(e: ParseError) -> Self {
+        Self::Decode(e.to_string())
+    }
+}
+
+impl From<SignatureError> for DbError {
+    fn from(e: SignatureError) -> Self {
+        Self::Decode(e.to_string())
+    }
+}
+
+impl From<ParseIntError> for DbError {
+    fn from(e: ParseIntError) -> Self {
+        Self::Decode(e.to_string())
+    }
+}
+
+impl From<TryFromIntError> for DbError {
+    fn from(e: TryFromIntError) -> Self {
+        Self::Decode(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for DbError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Decode(e.to_string())
+    }
+}
@@ -0,0 +1,1015 @@
+//! Block headers, along with the statistics derived from them as each block
+//! is indexed
+use std::{collections::HashSet, ops::RangeInclusive, sync::Arc};
+
+use alloy::{
+    consensus::Transaction as _,
+    hex::FromHexError,
+    primitives::{BlockHash, BlockNumber, ChainId, U256},
+    rpc::types::{eth::Header, Block},
+};
+use log::{debug, info};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, Error, Row, Statement, ToSql};
+
+use crate::utils;
+
+use super::{receipts::Receipts, Database, DbError, Result};
+
+/// Length of the periods summarised by [`HourlyStats`]
+pub(super) const SECONDS_PER_HOUR: u64 = 3_600;
+
+pub(super) const INSERT_BLOCK_HEADER_SQL: &str =
+    "INSERT OR IGNORE INTO block_headers (
+        inserted_at,
+        hash,
+        number,
+        parent_hash,
+        ommers_hash,
+        beneficiary,
+        state_root,
+        transactions_root,
+        receipts_root,
+        logs_bloom,
+        difficulty,
+        gas_limit,
+        gas_used,
+        timestamp,
+        extra_data,
+        mix_hash,
+        nonce,
+        base_fee_per_gas,
+        withdrawals_root,
+        blob_gas_used,
+        excess_blob_gas,
+        parent_beacon_block_root,
+        requests_hash,
+        blob_base_fee,
+        inserted_seq
+    ) VALUES (
+        TIME('now'),
+        ?1,
+        ?2,
+        ?3,
+        ?4,
+        ?5,
+        ?6,
+        ?7,
+        ?8,
+        ?9,
+        ?10,
+        ?11,
+        ?12,
+        ?13,
+        ?14,
+        ?15,
+        ?16,
+        ?17,
+        ?18,
+        ?19,
+        ?20,
+        ?21,
+        ?22,
+        ?23,
+        (SELECT COALESCE(MAX(inserted_seq), 0) + 1 FROM block_headers)
+    )";
+
+/// Adds the given amounts to the totals of an hour of [`HourlyStats`]
+/// (negative amounts being used to remove orphaned blocks)
+pub(super) const UPDATE_HOURLY_STATS_SQL: &str = "INSERT INTO hourly_stats (
+        hour,
+        blocks,
+        gas_used,
+        base_fee,
+        transactions
+    ) VALUES (?1, ?2, ?3, ?4, ?5)
+    ON CONFLICT(hour) DO UPDATE SET
+        blocks = blocks + excluded.blocks,
+        gas_used = gas_used + excluded.gas_used,
+        base_fee = base_fee + excluded.base_fee,
+        transactions = transactions + excluded.transactions";
+
+pub(super) const INSERT_FEE_STATS_SQL: &str =
+    "INSERT OR IGNORE INTO fee_stats (
+        block_hash,
+        block_number,
+        p10,
+        p50,
+        p90
+    ) VALUES (?1, ?2, ?3, ?4, ?5)";
+
+/// Percentiles of the priority fees (in wei) paid by the transactions in a
+/// single block
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FeeStats {
+    pub block_hash: BlockHash,
+    pub block_number: BlockNumber,
+    pub p10: u64,
+    pub p50: u64,
+    pub p90: u64,
+}
+
+impl FeeStats {
+    /// Compute the priority fee percentiles of the given [`Block`]
+    ///
+    /// Returns `None` if the block contains no transactions.
+    pub fn from_block(block: &Block) -> Option<Self> {
+        let base_fee = block.header.base_fee_per_gas.unwrap_or_default();
+        let mut tips: Vec<u64> = block
+            .transactions
+            .txns()
+            .map(|tx| {
+                tx.effective_tip_per_gas(base_fee)
+                    .unwrap_or_default()
+                    .try_into()
+                    .unwrap_or(u64::MAX)
+            })
+            .collect();
+
+        if tips.is_empty() {
+            return None;
+        }
+
+        tips.sort_unstable();
+        /* nearest-rank percentile */
+        let percentile = |p: usize| tips[(tips.len() - 1) * p / 100];
+
+        Some(Self {
+            block_hash: block.header.hash,
+            block_number: block.header.number,
+            p10: percentile(10),
+            p50: percentile(50),
+            p90: percentile(90),
+        })
+    }
+}
+
+/// Totals over the canonical blocks timestamped within a single hour
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct HourlyStats {
+    /// Start of the hour (as a UNIX timestamp)
+    pub hour: u64,
+    pub blocks: u64,
+    pub gas_used: u64,
+    /// Sum of each block's base fee (in wei per gas)
+    pub base_fee: u64,
+    pub transactions: u64,
+}
+
+impl HourlyStats {
+    /// Mean gas used per block
+    pub fn average_gas_used(&self) -> u64 {
+        self.gas_used.checked_div(self.blocks).unwrap_or_default()
+    }
+
+    /// Mean base fee (in wei per gas) per block
+    pub fn average_base_fee(&self) -> u64 {
+        self.base_fee.checked_div(self.blocks).unwrap_or_default()
+    }
+
+    /// Mean number of transactions included per second over the hour
+    pub fn throughput(&self) -> f64 {
+        self.transactions as f64 / SECONDS_PER_HOUR as f64
+    }
+}
+
+/// Flattened representation of a block [`Header`] as stored by each storage
+/// backend
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct HeaderRow {
+    pub hash: String,
+    pub number: u64,
+    pub parent_hash: String,
+    pub ommers_hash: String,
+    pub beneficiary: String,
+    pub state_root: String,
+    pub transactions_root: String,
+    pub receipts_root: String,
+    pub logs_bloom: String,
+    pub difficulty: u64,
+    pub gas_limit: u64,
+    pub gas_used: u64,
+    pub timestamp: u64,
+    pub extra_data: Vec<u8>,
+    pub mix_hash: String,
+    pub nonce: String,
+    /// Zero if absent
+    pub base_fee_per_gas: u64,
+    pub withdrawals_root: String,
+    /// Zero if absent
+    pub blob_gas_used: u64,
+    /// Zero if absent
+    pub excess_blob_gas: u64,
+    pub parent_beacon_block_root: String,
+    pub requests_hash: String,
+    /// `None` if absent, or if the blob schedule of the chain isn't known
+    /// (derived from `excess_blob_gas`, so not needed to reconstruct the
+    /// header)
+    pub blob_base_fee: Option<u64>,
+}
+
+impl HeaderRow {
+    /// Flatten a block [`Header`] of the chain with the given ID (if known)
+    pub fn from_header(header: &Header, chain_id: Option<ChainId>) -> Self {
+        Self {
+            hash: header.hash.to_string(),
+            number: header.number,
+            parent_hash: header.parent_hash.to_string(),
+            ommers_hash: header.ommers_hash.to_string(),
+            beneficiary: header.beneficiary.to_string(),
+            state_root: header.state_root.to_string(),
+            transactions_root: header.transactions_root.to_string(),
+            receipts_root: header.receipts_root.to_string(),
+            logs_bloom: header.logs_bloom.to_string(),
+            difficulty: header.difficulty.saturating_to(),
+            gas_limit: header.gas_limit,
+            gas_used: header.gas_used,
+            timestamp: header.timestamp,
+            extra_data: header.extra_data.to_vec(),
+            mix_hash: header.mix_hash.to_string(),
+            nonce: header.nonce.to_string(),
+            base_fee_per_gas: header.base_fee_per_gas.unwrap_or_default(),
+            withdrawals_root: header
+                .withdrawals_root
+                .unwrap_or_default()
+                .to_string(),
+            blob_gas_used: header.blob_gas_used.unwrap_or_default(),
+            excess_blob_gas: header.excess_blob_gas.unwrap_or_default(),
+            parent_beacon_block_root: header
+                .parent_beacon_block_root
+                .unwrap_or_default()
+                .to_string(),
+            requests_hash: header.requests_hash.unwrap_or_default().to_string(),
+            blob_base_fee: chain_id
+                .and_then(|chain_id| utils::blob_base_fee(header, chain_id))
+                .map(|fee| fee.try_into().unwrap_or(u64::MAX)),
+        }
+    }
+
+    /// Reconstruct the block [`Header`] this row represents
+    pub fn into_header(self) -> Result<Header> {
+        let mut header = Header::new(alloy::consensus::Header {
+            parent_hash: self.parent_hash.parse()?,
+            ommers_hash: self.ommers_hash.parse()?,
+            beneficiary: self.beneficiary.parse()?,
+            state_root: self.state_root.parse()?,
+            transactions_root: self.transactions_root.parse()?,
+            receipts_root: self.receipts_root.parse()?,
+            logs_bloom: self.logs_bloom.parse()?,
+            difficulty: U256::from(self.difficulty),
+            number: self.number,
+            gas_limit: self.gas_limit,
+            gas_used: self.gas_used,
+            timestamp: self.timestamp,
+            extra_data: self.extra_data.into(),
+            mix_hash: self.mix_hash.parse()?,
+            nonce: self.nonce.parse()?,
+            base_fee_per_gas: match self.base_fee_per_gas {
+                0 => None,
+                x => Some(x),
+            },
+            withdrawals_root: match self.withdrawals_root.as_str() {
+                "" => None,
+                x => Some(x.parse()?),
+            },
+            blob_gas_used: match self.blob_gas_used {
+                0 => None,
+                x => Some(x),
+            },
+            excess_blob_gas: match self.excess_blob_gas {
+                0 => None,
+                x => Some(x),
+            },
+            parent_beacon_block_root: match self
+                .parent_beacon_block_root
+                .as_str()
+            {
+                "" => None,
+                x => Some(x.parse()?),
+            },
+            requests_hash: match self.requests_hash.as_str() {
+                "" => None,
+                x => Some(x.parse()?),
+            },
+        });
+        header.hash = self.hash.parse()?;
+        Ok(header)
+    }
+}
+
+/// Reads and writes block headers (see [`super::Database::headers`])
+#[derive(Clone, Debug)]
+pub struct Headers {
+    conn_pool: Arc<Pool<SqliteConnectionManager>>,
+}
+
+impl Headers {
+    pub(super) fn new(conn_pool: Arc<Pool<SqliteConnectionManager>>) -> Self {
+        Self { conn_pool }
+    }
+
+    /// Retrieve the (canonical) block [`Header`] with the lowest number (if
+    /// it exists)
+    pub fn earliest(&self) -> Result<Option<Header>> {
+        self.first_preferring_canonical("TRUE", "number ASC", &[])
+    }
+
+    /// Retrieve the block [`Header`] with the highest number (if it exists)
+    ///
+    /// Orphaned blocks are only considered if nothing else is stored. If
+    /// several headers share the highest number (e.g., due to a reorg), the
+    /// most recently inserted one is considered the latest.
+    pub fn latest(&self) -> Result<Option<Header>> {
+        self.first_preferring_canonical(
+            "TRUE",
+            "number DESC, inserted_seq DESC",
+            &[],
+        )
+    }
+
+    /// Retrieve the first block [`Header`] satisfying the given predicate in
+    /// the given order, only considering orphaned blocks if no others do
+    ///
+    /// Canonical and orphaned blocks are queried for separately (rather than
+    /// ordering by `orphaned`) so that the ordering is served by the
+    /// `block_headers_orphaned_number_inserted_seq` index.
+    fn first_preferring_canonical(
+        &self,
+        predicate: &str,
+        order: &str,
+        params: &[&dyn ToSql],
+    ) -> Result<Option<Header>> {
+        let conn = self.conn_pool.get()?;
+        for orphaned in [false, true] {
+            match conn.query_row(
+                format!(
+                    "SELECT * FROM block_headers
+                        WHERE orphaned = {} AND {predicate}
+                        ORDER BY {order} LIMIT 1",
+                    orphaned as u8
+                )
+                .as_str(),
+                params,
+                |row| Ok(row_to_header(row)),
+            ) {
+                Ok(t) => return Ok(Some(t?)),
+                Err(Error::QueryReturnedNoRows) => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Retrieves the block [`Header`] with the given [`BlockHash`] (if it
+    /// exists)
+    pub fn by_hash(&self, hash: BlockHash) -> Result<Option<Header>> {
+        debug!("Block header {} requested from database...", hash);
+        match self.conn_pool.get()?.query_row(
+            format!("SELECT * FROM block_headers WHERE hash = '{}'", hash)
+                .as_str(),
+            [],
+            |row| Ok(row_to_header(row)),
+        ) {
+            Ok(t) => Ok(Some(t?)),
+            Err(e) => match e {
+                Error::QueryReturnedNoRows => Ok(None),
+                _ => Err(e.into()),
+            },
+        }
+    }
+
+    /// Retrieves the block [`Header`] with the given [`BlockNumber`] (if it
+    /// exists)
+    ///
+    /// If several headers share this number (e.g., due to a reorg), the most
+    /// recently inserted one that hasn't been orphaned is returned.
+    pub fn by_number(&self, number: BlockNumber) -> Result<Option<Header>> {
+        debug!("Block header #{} requested from database...", number,);
+        self.first_preferring_canonical(
+            "number = ?",
+            "inserted_seq DESC",
+            &[&number],
+        )
+    }
+
+    /// Retrieves every block [`Header`] with the given [`BlockNumber`] along
+    /// with whether it's been orphaned, canonical block first
+    pub fn all_by_number(
+        &self,
+        number: BlockNumber,
+    ) -> Result<Vec<(Header, bool)>> {
+        let conn = self.conn_pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT * FROM block_headers WHERE orphaned = ? AND number = ?
+                ORDER BY inserted_seq DESC",
+        )?;
+        let mut headers = vec![];
+        for orphaned in [false, true] {
+            for header in
+                stmt.query_and_then((orphaned, number), row_to_header)?
+            {
+                headers.push((header?, orphaned));
+            }
+        }
+        Ok(headers)
+    }
+
+    /// Retrieves up to `limit` block [`Header`]s stored before the one with the
+    /// given [`BlockHash`] (i.e., with a lower number, or inserted earlier at
+    /// the same number), in ascending order
+    pub fn before(&self, hash: BlockHash, limit: usize) -> Result<Vec<Header>> {
+        let conn = self.conn_pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT * FROM block_headers
+            WHERE (number, inserted_seq) <
+                (SELECT number, inserted_seq FROM block_headers WHERE hash = ?)
+            ORDER BY number DESC, inserted_seq DESC LIMIT ?",
+        )?;
+        let mut headers = stmt
+            .query_and_then(params![hash.to_string(), limit], row_to_header)?
+            .collect::<Result<Vec<_>>>()?;
+        headers.reverse();
+        Ok(headers)
+    }
+
+    /// Retrieves up to `limit` block [`Header`]s stored after the one with the
+    /// given [`BlockHash`] (i.e., with a higher number, or inserted later at
+    /// the same number), in ascending order
+    pub fn after(&self, hash: BlockHash, limit: usize) -> Result<Vec<Header>> {
+        self.conn_pool
+            .get()?
+            .prepare(
+                "SELECT * FROM block_headers
+                WHERE (number, inserted_seq) > (
+                    SELECT number, inserted_seq FROM block_headers
+                    WHERE hash = ?
+                )
+                ORDER BY number ASC, inserted_seq ASC LIMIT ?",
+            )?
+            .query_and_then(params![hash.to_string(), limit], row_to_header)?
+            .collect()
+    }
+
+    pub fn hashes(&self) -> Result<Vec<BlockHash>> {
+        let conn = self.conn_pool.get()?;
+        let mut stmt = conn.prepare("SELECT hash FROM block_headers")?;
+        let hash_strings: Vec<String> = stmt
+            .query_and_then([], |row| row.get::<&str, String>("hash"))?
+            .collect::<Result<Vec<String>, rusqlite::Error>>()?;
+        let hashes: Vec<BlockHash> = hash_strings
+            .iter()
+            .map(|s| s.parse())
+            .collect::<Result<Vec<BlockHash>, FromHexError>>(
+        )?;
+        Ok(hashes)
+    }
+
+    /// Ranges of [`BlockNumber`]s missing between the lowest and highest
+    /// (canonical) blocks stored, highest first
+    pub fn gaps(&self) -> Result<Vec<RangeInclusive<BlockNumber>>> {
+        let conn = self.conn_pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT number + 1 AS gap_start, next - 1 AS gap_end FROM (
+                SELECT number, LEAD(number) OVER (ORDER BY number) AS next
+                FROM (
+                    SELECT DISTINCT number FROM block_headers
+                    WHERE orphaned = 0
+                )
+            )
+            WHERE next > number + 1
+            ORDER BY number DESC",
+        )?;
+        let gaps = stmt
+            .query_map([], |row| {
+                Ok(row.get::<_, BlockNumber>("gap_start")?
+                    ..=row.get::<_, BlockNumber>("gap_end")?)
+            })?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        Ok(gaps)
+    }
+
+    /// Retrieves the hashes of up to `limit` of the most recent orphaned
+    /// blocks
+    pub fn recent_orphaned_hashes(
+        &self,
+        limit: usize,
+    ) -> Result<HashSet<BlockHash>> {
+        self.conn_pool
+            .get()?
+            .prepare(
+                "SELECT hash FROM block_headers WHERE orphaned = 1
+                    ORDER BY number DESC LIMIT ?",
+            )?
+            .query_and_then([limit], |row| {
+                Ok::<BlockHash, DbError>(row.get::<_, String>(0)?.parse()?)
+            })?
+            .collect()
+    }
+
+    /// Flag the blocks with the given [`BlockHash`]es as having lost a reorg
+    pub fn mark_orphaned(&self, hashes: &[BlockHash]) -> Result<()> {
+        let mut conn = self.conn_pool.get()?;
+        let tx = conn.transaction()?;
+        {
+            let mut select = tx.prepare(
+                "SELECT timestamp, gas_used, base_fee_per_gas,
+                    (SELECT COUNT(*) FROM transactions WHERE block_hash = ?1)
+                    FROM block_headers WHERE hash = ?1 AND orphaned = 0",
+            )?;
+            let mut update_stats = tx.prepare(UPDATE_HOURLY_STATS_SQL)?;
+            let mut statement = tx.prepare(
+                "UPDATE block_headers SET orphaned = 1 WHERE hash = ?",
+            )?;
+            hashes.iter().try_for_each(|hash| {
+                /* take back whatever the block added to its hour's stats */
+                let canonical = select
+                    .query_and_then([hash.to_string()], |row| {
+                        Ok::<_, DbError>((
+                            row.get::<usize, u64>(0)?,
+                            row.get::<usize, i64>(1)?,
+                            row.get::<usize, Option<i64>>(2)?,
+                            row.get::<usize, i64>(3)?,
+                        ))
+                    })?
+                    .next()
+                    .transpose()?;
+                if let Some((timestamp, gas_used, base_fee, transactions)) =
+                    canonical
+                {
+                    update_stats.execute(params![
+                        timestamp / SECONDS_PER_HOUR * SECONDS_PER_HOUR,
+                        -1,
+                        -gas_used,
+                        -base_fee.unwrap_or_default(),
+                        -transactions,
+                    ])?;
+                    Receipts::take_back_fees(&tx, *hash)?;
+                }
+                statement.execute([hash.to_string()])?;
+                Ok::<(), DbError>(())
+            })?;
+        }
+        tx.commit()?;
+        info!("Marked {} blocks as orphaned", hashes.len());
+        Ok(())
+    }
+
+    /// Retrieves the [`FeeStats`] of up to `limit` of the most recent blocks,
+    /// in ascending order of block number
+    pub fn recent_fee_stats(&self, limit: usize) -> Result<Vec<FeeStats>> {
+        let conn = self.conn_pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT * FROM fee_stats ORDER BY block_number DESC LIMIT ?",
+        )?;
+        let mut stats = stmt
+            .query_and_then([limit], |row| {
+                Ok::<FeeStats, DbError>(FeeStats {
+                    block_hash: row
+                        .get::<&str, String>("block_hash")?
+                        .parse()?,
+                    block_number: row.get("block_number")?,
+                    p10: row.get("p10")?,
+                    p50: row.get("p50")?,
+                    p90: row.get("p90")?,
+                })
+            })?
+            .collect::<Result<Vec<FeeStats>>>()?;
+        stats.reverse();
+        Ok(stats)
+    }
+
+    /// Retrieves the [`HourlyStats`] of up to `limit` of the most recent hours
+    /// in which blocks were indexed, in ascending order
+    pub fn hourly_stats(&self, limit: usize) -> Result<Vec<HourlyStats>> {
+        let conn = self.conn_pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT * FROM hourly_stats WHERE blocks > 0
+                ORDER BY hour DESC LIMIT ?",
+        )?;
+        let mut stats = stmt
+            .query_and_then([limit], |row| {
+                Ok::<HourlyStats, DbError>(HourlyStats {
+                    hour: row.get("hour")?,
+                    blocks: row.get("blocks")?,
+                    gas_used: row.get("gas_used")?,
+                    base_fee: row.get("base_fee")?,
+                    transactions: row.get("transactions")?,
+                })
+            })?
+            .collect::<Result<Vec<HourlyStats>>>()?;
+        stats.reverse();
+        Ok(stats)
+    }
+
+    /// Retrieves the blob base fees (in wei) of up to `limit` of the most
+    /// recent blocks, in ascending order of block number
+    pub fn recent_blob_base_fees(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<(BlockNumber, u64)>> {
+        let conn = self.conn_pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT number, blob_base_fee FROM block_headers
+            WHERE blob_base_fee IS NOT NULL
+            ORDER BY number DESC, inserted_seq DESC LIMIT ?",
+        )?;
+        let mut fees = stmt
+            .query_map([limit], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        fees.reverse();
+        Ok(fees)
+    }
+
+    /// Write a block [`Header`] to the database
+    pub fn add(&self, header: &Header) -> Result<()> {
+        let mut conn = self.conn_pool.get()?;
+        let tx = conn.transaction()?;
+        {
+            let chain_id = Database::read_chain_id(&tx)?;
+            let mut statement = tx.prepare(INSERT_BLOCK_HEADER_SQL)?;
+            Self::insert(&mut statement, header, chain_id)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Execute a prepared [`INSERT_BLOCK_HEADER_SQL`] statement for the given
+    /// block [`Header`] of the chain with the given ID (if known)
+    pub(super) fn insert(
+        statement: &mut Statement,
+        header: &Header,
+        chain_id: Option<ChainId>,
+    ) -> Result<bool> {
+        let row = HeaderRow::from_header(header, chain_id);
+        let inserted = statement.execute(params![
+            row.hash,
+            row.number,
+            row.parent_hash,
+            row.ommers_hash,
+            row.beneficiary,
+            row.state_root,
+            row.transactions_root,
+            row.receipts_root,
+            row.logs_bloom,
+            row.difficulty,
+            row.gas_limit,
+            row.gas_used,
+            row.timestamp,
+            row.extra_data,
+            row.mix_hash,
+            row.nonce,
+            row.base_fee_per_gas,
+            row.withdrawals_root,
+            row.blob_gas_used,
+            row.excess_blob_gas,
+            row.parent_beacon_block_root,
+            row.requests_hash,
+            row.blob_base_fee,
+        ])?;
+        debug!("Wrote block header {} to the database", header.hash);
+        Ok(inserted > 0)
+    }
+}
+
+pub(super) fn row_to_header(row: &Row) -> Result<Header> {
+    HeaderRow {
+        hash: row.get("hash")?,
+        number: row.get("number")?,
+        parent_hash: row.get("parent_hash")?,
+        ommers_hash: row.get("ommers_hash")?,
+        beneficiary: row.get("beneficiary")?,
+        state_root: row.get("state_root")?,
+        transactions_root: row.get("transactions_root")?,
+        receipts_root: row.get("receipts_root")?,
+        logs_bloom: row.get("logs_bloom")?,
+        difficulty: row.get("difficulty")?,
+        gas_limit: row.get("gas_limit")?,
+        gas_used: row.get("gas_used")?,
+        timestamp: row.get("timestamp")?,
+        extra_data: row.get("extra_data")?,
+        mix_hash: row.get("mix_hash")?,
+        nonce: row.get("nonce")?,
+        base_fee_per_gas: row.get("base_fee_per_gas")?,
+        withdrawals_root: row.get("withdrawals_root")?,
+        blob_gas_used: row.get("blob_gas_used")?,
+        excess_blob_gas: row.get("excess_blob_gas")?,
+        parent_beacon_block_root: row.get("parent_beacon_block_root")?,
+        requests_hash: row.get("requests_hash")?,
+        blob_base_fee: row.get("blob_base_fee")?,
+    }
+    .into_header()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::TxHash;
+
+    use super::*;
+    use crate::db::{test_transaction, Database, Location};
+
+    #[test]
+    fn test_latest_block_header() {
+        let header = Header::default();
+        let creation_result = Database::new(Location::Memory);
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        let insertion_result = db.headers().add(&header);
+        assert!(insertion_result.is_ok());
+        let retrieval_result = db.headers().latest();
+        assert!(retrieval_result.is_ok());
+        let perhaps_latest_header = retrieval_result.unwrap();
+        assert!(perhaps_latest_header.is_some());
+    }
+
+    #[test]
+    fn test_fee_stats() {
+        let txs = (1..=10)
+            .map(|i| {
+                test_transaction(
+                    TxHash::with_last_byte(i as u8),
+                    BlockHash::ZERO,
+                    0,
+                    i - 1,
+                    |row| {
+                        row.max_fee_per_gas = i * 1_000;
+                        row.max_priority_fee_per_gas = Some(i);
+                    },
+                )
+            })
+            .collect();
+        let block = Block::new(
+            Header::default(),
+            alloy::rpc::types::BlockTransactions::Full(txs),
+        );
+        let stats = FeeStats::from_block(&block);
+        assert_eq!(
+            stats,
+            Some(FeeStats {
+                block_hash: block.header.hash,
+                block_number: 0,
+                p10: 1,
+                p50: 5,
+                p90: 9,
+            })
+        );
+        assert_eq!(FeeStats::from_block(&Block::default()), None);
+
+        let creation_result = Database::new(Location::Memory);
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        assert!(db.add_block_atomic(&block).is_ok());
+        let retrieval_result = db.headers().recent_fee_stats(10);
+        assert!(retrieval_result.is_ok());
+        assert_eq!(retrieval_result.unwrap(), vec![stats.unwrap()]);
+    }
+
+    #[test]
+    fn test_hourly_stats() {
+        let block = |number: u8, timestamp, txs: u8| {
+            let mut header = Header::new(alloy::consensus::Header {
+                number: number as u64,
+                timestamp,
+                gas_used: 1_000,
+                base_fee_per_gas: Some(number as u64),
+                ..Default::default()
+            });
+            header.hash = BlockHash::with_last_byte(number);
+            let txs = (0..txs)
+                .map(|i| {
+                    test_transaction(
+                        TxHash::left_padding_from(&[number, i]),
+                        header.hash,
+                        number as u64,
+                        i as u64,
+                        |_| {},
+                    )
+                })
+                .collect();
+            Block::new(header, alloy::rpc::types::BlockTransactions::Full(txs))
+        };
+        let creation_result = Database::new(Location::Memory);
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        assert!(db.add_block_atomic(&block(1, 7_200, 2)).is_ok());
+        assert!(db.add_block_atomic(&block(2, 7_212, 4)).is_ok());
+        assert!(db.add_block_atomic(&block(2, 7_212, 4)).is_ok());
+        assert!(db.add_block_atomic(&block(3, 10_800, 0)).is_ok());
+        let retrieval_result = db.headers().hourly_stats(10);
+        assert!(retrieval_result.is_ok());
+        let stats = retrieval_result.unwrap();
+        assert_eq!(
+            stats,
+            vec![
+                HourlyStats {
+                    hour: 7_200,
+                    blocks: 2,
+                    gas_used: 2_000,
+                    base_fee: 3,
+                    transactions: 6,
+                },
+                HourlyStats {
+                    hour: 10_800,
+                    blocks: 1,
+                    gas_used: 1_000,
+                    base_fee: 3,
+                    transactions: 0,
+                },
+            ]
+        );
+        assert_eq!(stats[0].average_gas_used(), 1_000);
+        assert_eq!(stats[0].average_base_fee(), 1);
+        assert_eq!(db.headers().hourly_stats(1).unwrap(), vec![stats[1]]);
+
+        /* orphaned blocks no longer count, however often they're marked */
+        let orphan = BlockHash::with_last_byte(2);
+        assert!(db.headers().mark_orphaned(&[orphan]).is_ok());
+        assert!(db.headers().mark_orphaned(&[orphan]).is_ok());
+        assert_eq!(
+            db.headers().hourly_stats(10).unwrap()[0],
+            HourlyStats {
+                hour: 7_200,
+                blocks: 1,
+                gas_used: 1_000,
+                base_fee: 1,
+                transactions: 2,
+            }
+        );
+        assert!(db
+            .headers()
+            .mark_orphaned(&[BlockHash::with_last_byte(3)])
+            .is_ok());
+        assert_eq!(db.headers().hourly_stats(10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_blob_base_fees() {
+        let header = |number, excess_blob_gas| {
+            let mut header = Header::new(alloy::consensus::Header {
+                number,
+                timestamp: 1_710_338_135, /* Cancun */
+                excess_blob_gas,
+                ..Default::default()
+            });
+            header.hash = BlockHash::with_last_byte(number as u8);
+            header
+        };
+        let creation_result = Database::new(Location::Memory);
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        /* not knowing the chain, its blob schedule isn't known either */
+        assert!(db.headers().add(&header(1, Some(0))).is_ok());
+        assert!(db.set_chain_id(1).is_ok());
+        assert!(db.headers().add(&header(2, None)).is_ok());
+        assert!(db.headers().add(&header(3, Some(0))).is_ok());
+        assert!(db.headers().add(&header(4, Some(10_000_000))).is_ok());
+        let retrieval_result = db.headers().recent_blob_base_fees(10);
+        assert!(retrieval_result.is_ok());
+        /* e^(10000000 / 3338477) is roughly 19.9 */
+        assert_eq!(retrieval_result.unwrap(), vec![(3, 1), (4, 19)]);
+    }
+
+    #[test]
+    fn test_latest_block_header_tiebreak() {
+        let creation_result = Database::new(Location::Memory);
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        let header = |hash: u8| Header {
+            hash: BlockHash::with_last_byte(hash),
+            inner: alloy::consensus::Header {
+                number: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(db.headers().add(&header(1)).is_ok());
+        assert!(db.headers().add(&header(2)).is_ok());
+        /* re-inserting a known header must not make it the latest again */
+        assert!(db.headers().add(&header(1)).is_ok());
+
+        for _ in 0..8 {
+            let latest_result = db.headers().latest();
+            assert!(latest_result.is_ok());
+            assert_eq!(
+                latest_result.unwrap().map(|header| header.hash),
+                Some(header(2).hash)
+            );
+        }
+        assert_eq!(
+            db.headers().by_number(1).unwrap().map(|header| header.hash),
+            Some(header(2).hash)
+        );
+    }
+
+    #[test]
+    fn test_headers_before_and_after() {
+        let creation_result = Database::new(Location::Memory);
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        let header = |number, hash: u8| Header {
+            hash: BlockHash::with_last_byte(hash),
+            inner: alloy::consensus::Header {
+                number,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        /* block 2 was reorged (and stored out of order) */
+        for (number, hash) in [(1, 1), (3, 3), (2, 2), (2, 0x82), (4, 4)] {
+            assert!(db.headers().add(&header(number, hash)).is_ok());
+        }
+        let hashes = |headers: Vec<Header>| {
+            headers
+                .iter()
+                .map(|header| header.hash[31])
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            hashes(db.headers().before(header(3, 3).hash, 10).unwrap()),
+            vec![1, 2, 0x82]
+        );
+        assert_eq!(
+            hashes(db.headers().before(header(3, 3).hash, 2).unwrap()),
+            vec![2, 0x82]
+        );
+        assert_eq!(
+            hashes(db.headers().after(header(2, 2).hash, 2).unwrap()),
+            vec![0x82, 3]
+        );
+        assert!(db
+            .headers()
+            .after(header(4, 4).hash, 10)
+            .unwrap()
+            .is_empty());
+        assert!(db
+            .headers()
+            .before(header(5, 5).hash, 10)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_orphaned_blocks() {
+        let creation_result = Database::new(Location::Memory);
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        let header = |hash: u8| Header {
+            hash: BlockHash::with_last_byte(hash),
+            inner: alloy::consensus::Header {
+                number: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(db.headers().add(&header(1)).is_ok());
+        assert!(db.headers().add(&header(2)).is_ok());
+        assert!(db.headers().mark_orphaned(&[header(2).hash]).is_ok());
+
+        /* the canonical block wins even though it was inserted first */
+        assert_eq!(
+            db.headers().by_number(1).unwrap().map(|header| header.hash),
+            Some(header(1).hash)
+        );
+        assert_eq!(
+            db.headers().latest().unwrap().map(|header| header.hash),
+            Some(header(1).hash)
+        );
+        assert_eq!(
+            db.headers().recent_orphaned_hashes(10).unwrap(),
+            HashSet::from([header(2).hash])
+        );
+        assert_eq!(
+            db.headers()
+                .all_by_number(1)
+                .unwrap()
+                .into_iter()
+                .map(|(header, orphaned)| (header.hash, orphaned))
+                .collect::<Vec<_>>(),
+            vec![(header(1).hash, false), (header(2).hash, true)]
+        );
+    }
+
+    #[test]
+    fn test_block_gaps() {
+        let db = Database::new(Location::Memory).unwrap();
+        let header = |number: BlockNumber| Header {
+            hash: BlockHash::with_last_byte(number as u8),
+            inner: alloy::consensus::Header {
+                number,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(db.headers().gaps().unwrap(), vec![]);
+
+        for number in [3, 4, 6, 10, 11] {
+            assert!(db.headers().add(&header(number)).is_ok());
+        }
+        assert_eq!(db.headers().gaps().unwrap(), vec![7..=9, 5..=5]);
+
+        /* an orphaned block doesn't fill its height */
+        assert!(db.headers().mark_orphaned(&[header(6).hash]).is_ok());
+        assert_eq!(db.headers().gaps().unwrap(), vec![5..=9]);
+    }
+}
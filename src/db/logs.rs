@@ -0,0 +1,394 @@
+//! Event logs from transaction receipts
+use std::{fmt, iter::zip, str::FromStr, sync::Arc};
+
+use alloy::{
+    hex::{FromHex, FromHexError},
+    primitives::{
+        keccak256, Address, BlockHash, BlockNumber, Bytes, LogData, B256,
+    },
+    rpc::types::Log,
+};
+use log::debug;
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, Row, Statement};
+
+use super::{DbError, Result};
+
+const INSERT_LOG_SQL: &str = "INSERT OR IGNORE INTO logs (
+        block_hash,
+        block_number,
+        transaction_hash,
+        transaction_index,
+        log_index,
+        address,
+        topic0,
+        topic1,
+        topic2,
+        topic3,
+        data
+    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)";
+
+/// Records that a log of the given transaction mentions the given address
+/// (which is lowercased, as the addresses of existing logs were when they
+/// were first recorded)
+const INSERT_LOG_ADDRESS_SQL: &str = "INSERT OR IGNORE INTO log_addresses (
+        address,
+        block_number,
+        block_hash,
+        transaction_hash
+    ) VALUES (lower(?1), ?2, ?3, ?4)";
+
+/// Search for event logs by signature over a range of blocks
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LogQuery {
+    /// First topic of the log (i.e., the event signature hash)
+    pub topic0: B256,
+    /// Only match logs emitted by this contract
+    pub address: Option<Address>,
+    /// Lowest block number to search (inclusive)
+    pub from_block: Option<BlockNumber>,
+    /// Highest block number to search (inclusive)
+    pub to_block: Option<BlockNumber>,
+}
+
+impl fmt::Display for LogQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.topic0)?;
+
+        if let Some(address) = self.address {
+            write!(f, " {address}")?;
+        }
+
+        if self.from_block.is_some() || self.to_block.is_some() {
+            write!(f, " ")?;
+            if let Some(from) = self.from_block {
+                write!(f, "{from}")?;
+            }
+            write!(f, "..")?;
+            if let Some(to) = self.to_block {
+                write!(f, "{to}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for LogQuery {
+    type Err = String;
+
+    /// Parses `<topic0> [address] [from..to]`, where either end of the block
+    /// range may be omitted and `topic0` may be given either as a hash or as
+    /// the event signature itself (e.g., `Transfer(address,address,uint256)`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let topic0 = words.next().ok_or("missing event signature")?;
+        let mut query = Self {
+            topic0: if topic0.contains('(') {
+                keccak256(topic0)
+            } else {
+                topic0.parse().map_err(|_| "invalid event signature hash")?
+            },
+            ..Default::default()
+        };
+
+        for word in words {
+            if let Some((from, to)) = word.split_once("..") {
+                let bound = |x: &str| {
+                    (!x.is_empty())
+                        .then(|| x.parse().map_err(|_| "invalid block range"))
+                        .transpose()
+                };
+                query.from_block = bound(from)?;
+                query.to_block = bound(to)?;
+            } else if query.address.is_none() {
+                query.address =
+                    Some(word.parse().map_err(|_| "invalid address")?);
+            } else {
+                return Err("too many arguments".to_string());
+            }
+        }
+
+        Ok(query)
+    }
+}
+
+/// Flattened representation of a [`Log`] as stored by each storage backend
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LogRow {
+    pub block_hash: String,
+    pub block_number: u64,
+    pub transaction_hash: String,
+    pub transaction_index: u64,
+    pub log_index: u64,
+    pub address: String,
+    /// Absent topics are `None`
+    pub topics: [Option<String>; 4],
+    /// Hexadecimal representation of the (non-indexed) log data
+    pub data: String,
+}
+
+impl LogRow {
+    /// Flatten a (mined) [`Log`]
+    ///
+    /// Fails if the log is missing any information about its enclosing block
+    /// or transaction.
+    pub fn from_log(log: &Log) -> Result<Self> {
+        let mut topics: [Option<String>; 4] = Default::default();
+        zip(topics.iter_mut(), log.topics())
+            .for_each(|(slot, topic)| *slot = Some(topic.to_string()));
+
+        Ok(Self {
+            block_hash: log
+                .block_hash
+                .ok_or(DbError::Decode("log is missing block hash".into()))?
+                .to_string(),
+            block_number: log
+                .block_number
+                .ok_or(DbError::Decode("log is missing block number".into()))?,
+            transaction_hash: log
+                .transaction_hash
+                .ok_or(DbError::Decode(
+                    "log is missing transaction hash".into(),
+                ))?
+                .to_string(),
+            transaction_index: log.transaction_index.ok_or(DbError::Decode(
+                "log is missing transaction index".into(),
+            ))?,
+            log_index: log
+                .log_index
+                .ok_or(DbError::Decode("log is missing log index".into()))?,
+            address: log.address().to_string(),
+            topics,
+            data: log.data().data.to_string(),
+        })
+    }
+
+    /// Reconstruct the [`Log`] this row was flattened from
+    pub fn into_log(self) -> Result<Log> {
+        let topics = self
+            .topics
+            .iter()
+            .flatten()
+            .map(|topic| topic.parse())
+            .collect::<Result<Vec<B256>, FromHexError>>()?;
+        Ok(Log {
+            inner: alloy::primitives::Log {
+                address: self.address.parse()?,
+                data: LogData::new(topics, Bytes::from_hex(&self.data)?)
+                    .ok_or(DbError::Decode("log has too many topics".into()))?,
+            },
+            block_hash: Some(self.block_hash.parse()?),
+            block_number: Some(self.block_number),
+            block_timestamp: None,
+            transaction_hash: Some(self.transaction_hash.parse()?),
+            transaction_index: Some(self.transaction_index),
+            log_index: Some(self.log_index),
+            removed: false,
+        })
+    }
+}
+
+/// Reads and writes event logs (see [`super::Database::logs`])
+#[derive(Clone, Debug)]
+pub struct Logs {
+    conn_pool: Arc<Pool<SqliteConnectionManager>>,
+}
+
+impl Logs {
+    pub(super) fn new(conn_pool: Arc<Pool<SqliteConnectionManager>>) -> Self {
+        Self { conn_pool }
+    }
+
+    /// Retrieves up to `limit` of the most recent [`Log`]s matching the given
+    /// [`LogQuery`]
+    pub fn by_topic(&self, query: &LogQuery, limit: usize) -> Result<Vec<Log>> {
+        debug!("Logs matching {} requested from database...", query);
+        self.conn_pool
+            .get()?
+            .prepare(
+                "SELECT * FROM logs
+                    WHERE topic0 = ?1
+                        AND (?2 IS NULL OR address = ?2)
+                        AND block_number BETWEEN ?3 AND ?4
+                    ORDER BY block_number DESC, log_index ASC LIMIT ?5",
+            )?
+            .query_and_then(
+                params![
+                    query.topic0.to_string(),
+                    query.address.map(|address| address.to_string()),
+                    query.from_block.unwrap_or_default(),
+                    query.to_block.unwrap_or(i64::MAX as u64),
+                    limit
+                ],
+                row_to_log,
+            )?
+            .collect()
+    }
+
+    /// Retrieves every [`Log`] emitted within the [`Block`] with the given
+    /// [`BlockHash`] (in order)
+    pub fn by_block_hash(&self, hash: BlockHash) -> Result<Vec<Log>> {
+        self.conn_pool.get()?.prepare(
+            "SELECT * FROM logs WHERE block_hash = ? ORDER BY log_index ASC",
+        )?
+            .query_and_then([hash.to_string()], row_to_log)?
+            .collect()
+    }
+
+    /// Write each (mined) [`Log`] to the database within a single SQLite
+    /// transaction
+    pub fn add(&self, logs: &[Log]) -> Result<()> {
+        let mut conn = self.conn_pool.get()?;
+        let tx = conn.transaction()?;
+        Self::write(&tx, logs)?;
+        tx.commit()?;
+        debug!("Wrote {} logs to the database", logs.len());
+        Ok(())
+    }
+
+    /// Write each (mined) [`Log`] using the given connection (e.g., within a
+    /// transaction)
+    pub(super) fn write(
+        conn: &rusqlite::Connection,
+        logs: &[Log],
+    ) -> Result<()> {
+        let mut statement = conn.prepare(INSERT_LOG_SQL)?;
+        let mut addresses = conn.prepare(INSERT_LOG_ADDRESS_SQL)?;
+        logs.iter().try_for_each(|log| {
+            Self::insert(&mut statement, &mut addresses, log)
+        })
+    }
+
+    /// Execute a prepared [`INSERT_LOG_SQL`] statement for the given [`Log`],
+    /// along with a prepared [`INSERT_LOG_ADDRESS_SQL`] statement for each
+    /// address it mentions
+    pub(super) fn insert(
+        statement: &mut Statement,
+        addresses: &mut Statement,
+        log: &Log,
+    ) -> Result<()> {
+        let row = LogRow::from_log(log)?;
+        mentioned_addresses(log).try_for_each(|address| {
+            addresses.execute(params![
+                address.to_string(),
+                row.block_number,
+                row.block_hash,
+                row.transaction_hash,
+            ])?;
+            Ok::<(), DbError>(())
+        })?;
+        statement.execute(params![
+            row.block_hash,
+            row.block_number,
+            row.transaction_hash,
+            row.transaction_index,
+            row.log_index,
+            row.address,
+            row.topics[0],
+            row.topics[1],
+            row.topics[2],
+            row.topics[3],
+            row.data,
+        ])?;
+        Ok(())
+    }
+}
+
+/// Addresses that the given [`Log`] mentions: its emitter, and any indexed
+/// event arguments that are addresses (padded to a full word)
+fn mentioned_addresses(log: &Log) -> impl Iterator<Item = Address> + '_ {
+    std::iter::once(log.address()).chain(
+        log.topics()
+            .iter()
+            .skip(1)
+            .filter(|topic| topic[..12].iter().all(|byte| *byte == 0))
+            .map(|topic| Address::from_word(*topic)),
+    )
+}
+
+pub(super) fn row_to_log(row: &Row) -> Result<Log> {
+    LogRow {
+        block_hash: row.get("block_hash")?,
+        block_number: row.get("block_number")?,
+        transaction_hash: row.get("transaction_hash")?,
+        transaction_index: row.get("transaction_index")?,
+        log_index: row.get("log_index")?,
+        address: row.get("address")?,
+        topics: [
+            row.get("topic0")?,
+            row.get("topic1")?,
+            row.get("topic2")?,
+            row.get("topic3")?,
+        ],
+        data: row.get("data")?,
+    }
+    .into_log()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::TxHash;
+
+    use super::*;
+    use crate::db::{Database, Location};
+
+    #[test]
+    fn test_logs_by_topic() {
+        let creation_result = Database::new(Location::Memory);
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        let topic0 = B256::with_last_byte(0xee);
+        let log = |number: u64, address: Address| Log {
+            inner: alloy::primitives::Log {
+                address,
+                data: LogData::new_unchecked(
+                    vec![topic0, B256::with_last_byte(1)],
+                    Bytes::from(vec![0xca, 0xfe]),
+                ),
+            },
+            block_hash: Some(BlockHash::with_last_byte(number as u8)),
+            block_number: Some(number),
+            transaction_hash: Some(TxHash::with_last_byte(number as u8)),
+            transaction_index: Some(0),
+            log_index: Some(0),
+            ..Default::default()
+        };
+        let logs = vec![
+            log(1, Address::with_last_byte(1)),
+            log(2, Address::with_last_byte(2)),
+            log(3, Address::with_last_byte(1)),
+        ];
+        assert!(db.logs().add(&logs).is_ok());
+        assert!(db.logs().add(&logs).is_ok());
+
+        let all = db.logs().by_topic(
+            &LogQuery {
+                topic0,
+                ..Default::default()
+            },
+            10,
+        );
+        assert!(all.is_ok());
+        assert_eq!(
+            all.unwrap(),
+            logs.iter().rev().cloned().collect::<Vec<_>>()
+        );
+
+        let query: LogQuery =
+            format!("{topic0} {} 2..", Address::with_last_byte(1))
+                .parse()
+                .unwrap();
+        let filtered = db.logs().by_topic(&query, 10);
+        assert!(filtered.is_ok());
+        assert_eq!(filtered.unwrap(), vec![logs[2].clone()]);
+
+        assert_eq!(
+            "Transfer(address,address,uint256) 10.."
+                .parse::<LogQuery>()
+                .map(|query| query.topic0),
+            Ok(keccak256("Transfer(address,address,uint256)"))
+        );
+    }
+}
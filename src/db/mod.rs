@@ -0,0 +1,1963 @@
+//! SQLite database interaction for storing indexed blockchain data
+//!
+//! Each kind of chain object is read and written through its own repository
+//! (see [`Database::headers`], [`Database::transactions`],
+//! [`Database::receipts`], and [`Database::logs`]), all of which share the
+//! [`Database`]'s connection pool. Anything spanning several of them (e.g.,
+//! whole blocks) is handled by the [`Database`] itself.
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::RangeInclusive,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    eips::{BlockId, BlockNumberOrTag},
+    json_abi::JsonAbi,
+    primitives::{Address, BlockHash, BlockNumber, ChainId, TxHash, U256},
+    rpc::types::{eth::Header, Block, Log, Transaction},
+};
+use log::{debug, error, info};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{
+    fallible_iterator::FallibleIterator, params, types::ValueRef, Batch,
+};
+use serde::{Deserialize, Serialize};
+
+pub use self::{
+    error::{DbError, Result},
+    headers::{FeeStats, HeaderRow, Headers, HourlyStats},
+    logs::{LogQuery, LogRow, Logs},
+    receipts::{
+        BlockFees, FeeTotals, GasByRecipient, ReceiptStatus, Receipts,
+        RecipientGas,
+    },
+    schema::{JournalMode, Pragmas, Synchronous},
+    transactions::{
+        Activity, ActivityBucket, CalldataPattern, SelectorStats,
+        TransactionRow, Transactions,
+    },
+};
+use self::{
+    headers::{
+        INSERT_BLOCK_HEADER_SQL, INSERT_FEE_STATS_SQL, SECONDS_PER_HOUR,
+        UPDATE_HOURLY_STATS_SQL,
+    },
+    transactions::INSERT_TRANSACTION_SQL,
+};
+use crate::{
+    l2::{DepositTransaction, L1Origin, L2Block, OtherTransaction},
+    verification::VerifiedSource,
+};
+
+mod error;
+mod headers;
+mod logs;
+mod receipts;
+mod schema;
+mod transactions;
+
+const CONN_GET_TIMEOUT_MILLIS: u64 = 1_000; /* 1 second */
+const CONN_IDLE_TIMEOUT_MILLIS: u64 = 1_000; /* 1 second */
+/// Number of SQLite virtual machine instructions between checks of whether a
+/// query has overrun its deadline
+const QUERY_PROGRESS_INTERVAL: i32 = 10_000;
+
+pub const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Represents where to store a [`Database`]
+#[derive(Clone, Debug, Default)]
+pub enum Location {
+    /// On-disk at the given filepath
+    Disk(PathBuf),
+    /// In-memory (the default)
+    #[default]
+    Memory,
+}
+
+/// How final a block is
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FinalityStatus {
+    /// Not yet safe (i.e., could still be reorged out by a proposer)
+    Unsafe,
+    /// Attested to by a majority of validators, but not yet finalized
+    Safe,
+    Finalized,
+}
+
+impl fmt::Display for FinalityStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unsafe => write!(f, "unsafe"),
+            Self::Safe => write!(f, "safe"),
+            Self::Finalized => write!(f, "finalized"),
+        }
+    }
+}
+
+/// The latest safe and finalized blocks, as reported by the node
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize,
+)]
+pub struct Finality {
+    pub safe: BlockNumber,
+    pub finalized: BlockNumber,
+}
+
+impl Finality {
+    /// How final the (canonical) block with the given [`BlockNumber`] is
+    pub fn status(&self, number: BlockNumber) -> FinalityStatus {
+        if number <= self.finalized {
+            FinalityStatus::Finalized
+        } else if number <= self.safe {
+            FinalityStatus::Safe
+        } else {
+            FinalityStatus::Unsafe
+        }
+    }
+}
+
+/// Where an execution block sits on the beacon chain, as reported by a
+/// consensus layer node
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize,
+)]
+pub struct BeaconBlock {
+    /// Hash of the execution block carried by the beacon block
+    pub block_hash: BlockHash,
+    pub block_number: BlockNumber,
+    pub slot: u64,
+    pub epoch: u64,
+    /// Index of the validator that proposed the beacon block
+    pub proposer_index: u64,
+}
+
+/// Balance and nonce of a watched account as of a particular block
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize,
+)]
+pub struct AccountSnapshot {
+    pub address: Address,
+    pub block_number: BlockNumber,
+    /// Balance (in wei)
+    pub balance: U256,
+    pub nonce: u64,
+}
+
+/// What a [`Bookmark`] points to
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum BookmarkTarget {
+    Block {
+        number: BlockNumber,
+        hash: BlockHash,
+    },
+    Transaction(TxHash),
+    Address(Address),
+}
+
+impl BookmarkTarget {
+    /// Name of this kind of target (as stored in the database)
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Block { .. } => "block",
+            Self::Transaction(_) => "transaction",
+            Self::Address(_) => "address",
+        }
+    }
+
+    /// Hash or address of the target (as stored in the database)
+    fn key(&self) -> String {
+        match self {
+            Self::Block { hash, .. } => hash.to_string(),
+            Self::Transaction(hash) => hash.to_string(),
+            Self::Address(address) => address.to_string(),
+        }
+    }
+}
+
+/// Block, transaction, or address saved from within the TUI so as to be
+/// returned to later
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Bookmark {
+    pub target: BookmarkTarget,
+    pub note: Option<String>,
+    /// When the bookmark was made (as a UNIX timestamp)
+    pub created_at: u64,
+}
+
+/// Everything indexed about a single block
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct IndexedBlock {
+    pub block: Block,
+    pub logs: Vec<Log>,
+    pub receipt_statuses: Vec<ReceiptStatus>,
+    /// What's peculiar to rollups about the block (if anything)
+    #[serde(default, skip_serializing_if = "L2Block::is_empty")]
+    pub l2: L2Block,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub beacon: Option<BeaconBlock>,
+    /// Transactions of the local block template for the block's number
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub template: Vec<TxHash>,
+}
+
+/// Everything recorded about the indexed chain as a whole, rather than about
+/// any one block (everything else that's derived from indexed blocks, such
+/// as fee statistics, is derived afresh as they're written)
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ChainState {
+    #[serde(default)]
+    pub chain_id: Option<ChainId>,
+    pub genesis_hash: Option<BlockHash>,
+    pub finality: Option<Finality>,
+    /// Cached verified source code, by contract address
+    #[serde(default)]
+    pub contract_sources: Vec<(Address, VerifiedSource)>,
+    #[serde(default)]
+    pub account_snapshots: Vec<AccountSnapshot>,
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+}
+
+/// Rows returned by an arbitrary (read-only) SQL query, with every value
+/// rendered as text
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    /// Whether there were more rows than were retrieved
+    pub truncated: bool,
+}
+
+/// Handle to the SQLite database storing indexed chain data
+#[derive(Clone, Debug)]
+pub struct Database {
+    /// Connection pool
+    pub conn_pool: Arc<Pool<SqliteConnectionManager>>,
+}
+
+impl Database {
+    /// Creates a new [`Database`] instance at the given [`Location`]
+    ///
+    /// This will initialise the database with the necessary schema in an
+    /// idempotent fashion as well as handle any (unlikely to occur) connection
+    /// timeouts.
+    pub fn new(location: Location) -> Result<Self> {
+        Self::with_pragmas(location, Pragmas::default())
+    }
+
+    /// Creates a new [`Database`] instance at the given [`Location`], tuning
+    /// every connection with the provided [`Pragmas`] if it's on-disk
+    pub fn with_pragmas(location: Location, pragmas: Pragmas) -> Result<Self> {
+        let this = Self {
+            conn_pool: Arc::new(
+                Pool::builder()
+                    .connection_timeout(Duration::from_millis(
+                        CONN_GET_TIMEOUT_MILLIS,
+                    ))
+                    .idle_timeout(Some(Duration::from_millis(
+                        CONN_IDLE_TIMEOUT_MILLIS,
+                    )))
+                    .build(match location {
+                        Location::Memory => SqliteConnectionManager::memory()
+                            .with_init(|conn| {
+                                Transactions::register_functions(conn)
+                            }),
+                        Location::Disk(path) => SqliteConnectionManager::file(
+                            path,
+                        )
+                        .with_init(move |conn| {
+                            pragmas.apply(conn)?;
+                            Transactions::register_functions(conn)
+                        }),
+                    })?,
+            ),
+        };
+        schema::initialise(&this.conn_pool)?;
+        Ok(this)
+    }
+
+    /// Size of the database (in bytes), excluding any write-ahead log
+    pub fn size(&self) -> Result<u64> {
+        Ok(self.conn_pool.get()?.query_row(
+            "SELECT page_count * page_size
+                FROM pragma_page_count(), pragma_page_size()",
+            [],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Block headers, along with the statistics derived from them as each
+    /// block is indexed
+    pub fn headers(&self) -> Headers {
+        Headers::new(self.conn_pool.clone())
+    }
+
+    /// Transactions, as included in their blocks
+    pub fn transactions(&self) -> Transactions {
+        Transactions::new(self.conn_pool.clone())
+    }
+
+    /// Receipt statuses, along with the fees paid by each block as per its
+    /// receipts
+    pub fn receipts(&self) -> Receipts {
+        Receipts::new(self.conn_pool.clone())
+    }
+
+    /// Event logs from transaction receipts
+    pub fn logs(&self) -> Logs {
+        Logs::new(self.conn_pool.clone())
+    }
+
+    pub fn block_by_transaction_hash(
+        &self,
+        hash: TxHash,
+    ) -> Result<Option<Block>> {
+        debug!(
+            "Associated block for transaction {} requested from database...",
+            hash
+        );
+
+        match self.transactions().by_hash(hash)? {
+            Some(tx) => match tx.block_hash {
+                Some(block_hash) => self.block_by_hash(block_hash),
+                None => Ok(None),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve the [`Block`] with the highest number (if it exists)
+    ///
+    /// See [`Headers::latest`] for how ties are broken.
+    pub fn latest_block(&self) -> Result<Option<Block>> {
+        match self.headers().latest()? {
+            Some(latest_header) => self.block_by_hash(latest_header.hash),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieves the block with the associated hash (if it exists)
+    pub fn block_by_hash(&self, hash: BlockHash) -> Result<Option<Block>> {
+        debug!("Block {} requested from database...", hash);
+
+        match self.headers().by_hash(hash).inspect_err(|e| {
+            error!("Failed to retrieve block header from the database: {e:?}")
+        })? {
+            Some(header) => Ok(Some(Block::new(header, alloy::rpc::types::BlockTransactions::Full(
+                self.transactions().by_block_hash(hash).inspect_err(|e| error!("Failed to retrieve associated transactions from the database: {e:?}"))?
+            )))),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieves the block with the associated number (if it exists)
+    pub fn block_by_number(
+        &self,
+        number: BlockNumber,
+    ) -> Result<Option<Block>> {
+        debug!("Block #{} requested from database...", number);
+
+        match self.headers().by_number(number).inspect_err(|e| {
+            error!("Failed to retrieve block header from the database: {e:?}")
+        })? {
+            Some(header) => {
+                let hash = header.hash;
+                Ok(Some(Block::new(header, alloy::rpc::types::BlockTransactions::Full(
+                    self.transactions().by_block_hash(hash).inspect_err(|e| error!("Failed to retrieve associated transactions from the database: {e:?}"))?
+                ))))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieves every canonical [`Block`] within the given range of
+    /// [`BlockNumber`]s (in order), reading their headers and their
+    /// transactions with a single query each
+    ///
+    /// Transactions of types that can't be reconstructed are left out.
+    pub fn canonical_blocks(
+        &self,
+        range: RangeInclusive<BlockNumber>,
+    ) -> Result<Vec<Block>> {
+        let conn = self.conn_pool.get()?;
+        let bounds = params![range.start(), range.end()];
+        let mut header_stmt = conn.prepare(
+            "SELECT * FROM block_headers
+                WHERE orphaned = 0 AND number BETWEEN ?1 AND ?2
+                ORDER BY number ASC, inserted_seq ASC",
+        )?;
+        let headers = header_stmt
+            .query_and_then(bounds, headers::row_to_header)?
+            .collect::<Result<Vec<Header>>>()?;
+        let mut tx_stmt = conn.prepare(
+            "SELECT transactions.* FROM transactions
+                JOIN block_headers
+                    ON block_headers.hash = transactions.block_hash
+                WHERE block_headers.orphaned = 0
+                    AND block_headers.number BETWEEN ?1 AND ?2
+                ORDER BY transactions.position ASC",
+        )?;
+        let mut txs: HashMap<BlockHash, Vec<Transaction>> = HashMap::new();
+        for tx in transactions::collect_known(
+            tx_stmt.query_and_then(bounds, transactions::row_to_transaction)?,
+        )? {
+            txs.entry(tx.block_hash.unwrap_or_default())
+                .or_default()
+                .push(tx);
+        }
+
+        Ok(headers
+            .into_iter()
+            .map(|header| {
+                let txs = txs.remove(&header.hash).unwrap_or_default();
+                Block::new(
+                    header,
+                    alloy::rpc::types::BlockTransactions::Full(txs),
+                )
+            })
+            .collect())
+    }
+
+    /// Retrieves the [`Block`] matching the given [`BlockId`] (if it exists)
+    pub fn block(&self, id: BlockId) -> Result<Option<Block>> {
+        match id {
+            BlockId::Hash(h) => self.block_by_hash(h.into()),
+            BlockId::Number(t) => match t {
+                BlockNumberOrTag::Number(n) => self.block_by_number(n),
+                BlockNumberOrTag::Latest => self.latest_block(),
+                other => {
+                    Err(DbError::Unsupported(format!("block tag {other}")))
+                }
+            },
+        }
+    }
+
+    /// Write a [`Block`] to the database within a single SQLite transaction
+    ///
+    /// The header and every transaction are committed together (or not at
+    /// all), and the transaction insertion statement is prepared once and
+    /// reused for each row. The block's
+    /// [`FeeStats`] are written, and its [`HourlyStats`] updated, as part of
+    /// the same SQLite transaction.
+    pub fn add_block_atomic(&self, block: &Block) -> Result<()> {
+        let mut conn = self.conn_pool.get()?;
+        let tx = conn.transaction()?;
+        let chain_id = Self::read_chain_id(&tx)?;
+        Self::write_block(&tx, block, chain_id)?;
+        tx.commit()?;
+        info!(
+            "Wrote block {} to the database ({} transactions)",
+            block.header.hash,
+            block.transactions.len()
+        );
+        Ok(())
+    }
+
+    /// Write a [`Block`] of the chain with the given ID (along with its
+    /// [`FeeStats`] and [`HourlyStats`]) using the given connection (e.g.,
+    /// within a transaction)
+    fn write_block(
+        conn: &rusqlite::Connection,
+        block: &Block,
+        chain_id: Option<ChainId>,
+    ) -> Result<()> {
+        let mut header_statement = conn.prepare(INSERT_BLOCK_HEADER_SQL)?;
+        let inserted =
+            Headers::insert(&mut header_statement, &block.header, chain_id)?;
+
+        let mut tx_statement = conn.prepare(INSERT_TRANSACTION_SQL)?;
+        block.transactions.txns().try_for_each(|transaction| {
+            Transactions::insert(&mut tx_statement, transaction)
+        })?;
+
+        /* duplicates mustn't be counted twice */
+        if inserted {
+            conn.execute(
+                UPDATE_HOURLY_STATS_SQL,
+                params![
+                    block.header.timestamp / SECONDS_PER_HOUR
+                        * SECONDS_PER_HOUR,
+                    1,
+                    block.header.gas_used,
+                    block.header.base_fee_per_gas.unwrap_or_default(),
+                    block.transactions.len(),
+                ],
+            )?;
+        }
+
+        if let Some(stats) = FeeStats::from_block(block) {
+            conn.execute(
+                INSERT_FEE_STATS_SQL,
+                params![
+                    stats.block_hash.to_string(),
+                    stats.block_number,
+                    stats.p10,
+                    stats.p50,
+                    stats.p90,
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Run a single read-only SQL query (e.g., one entered into the SQL
+    /// console), retrieving up to `limit` rows
+    ///
+    /// Statements that would modify the database (or that don't return rows,
+    /// such as transaction control) are refused, as is more than a single
+    /// statement. As a further safeguard, the
+    /// query runs with SQLite's `query_only` pragma set. Queries still running
+    /// after the given timeout are abandoned with [`DbError::Deadline`].
+    pub fn query(
+        &self,
+        sql: &str,
+        limit: usize,
+        timeout: Duration,
+    ) -> Result<QueryResult> {
+        let conn = self.conn_pool.get()?;
+        let mut statements = Batch::new(&conn, sql);
+        let mut stmt = statements
+            .next()?
+            .ok_or(DbError::Unsupported("empty query".into()))?;
+        if statements.next()?.is_some() {
+            return Err(DbError::Unsupported(
+                "query of more than a single statement".into(),
+            ));
+        }
+        if !stmt.readonly() || stmt.column_count() == 0 {
+            return Err(DbError::Unsupported(
+                "query that isn't read-only".into(),
+            ));
+        }
+
+        let columns: Vec<String> =
+            stmt.column_names().into_iter().map(String::from).collect();
+        conn.pragma_update(None, "query_only", true)?;
+        let deadline = Instant::now() + timeout;
+        conn.progress_handler(
+            QUERY_PROGRESS_INTERVAL,
+            Some(move || Instant::now() > deadline),
+        );
+        let result = (|| {
+            let mut rows = stmt.query([])?;
+            let mut result = QueryResult {
+                columns,
+                ..Default::default()
+            };
+            while let Some(row) = rows.next()? {
+                if result.rows.len() == limit {
+                    result.truncated = true;
+                    break;
+                }
+                result.rows.push(
+                    (0..result.columns.len())
+                        .map(|i| Ok(Self::render_value(row.get_ref(i)?)))
+                        .collect::<Result<Vec<String>>>()?,
+                );
+            }
+            Ok(result)
+        })()
+        .map_err(|e| match e {
+            DbError::Sqlite(rusqlite::Error::SqliteFailure(e, _))
+                if e.code == rusqlite::ErrorCode::OperationInterrupted =>
+            {
+                DbError::Deadline(timeout)
+            }
+            e => e,
+        });
+        conn.progress_handler(0, None::<fn() -> bool>);
+        conn.pragma_update(None, "query_only", false)?;
+        result
+    }
+
+    /// Render a raw SQLite value as per the `sqlite3` shell (albeit with
+    /// blobs in hex)
+    fn render_value(value: ValueRef) -> String {
+        match value {
+            ValueRef::Null => "NULL".to_string(),
+            ValueRef::Integer(x) => x.to_string(),
+            ValueRef::Real(x) => x.to_string(),
+            ValueRef::Text(text) => String::from_utf8_lossy(text).into_owned(),
+            ValueRef::Blob(blob) => alloy::hex::encode_prefixed(blob),
+        }
+    }
+
+    /// Retrieves every user-defined address label
+    pub fn labels(&self) -> Result<Vec<(Address, String)>> {
+        self.conn_pool
+            .get()?
+            .prepare("SELECT address, label FROM labels")?
+            .query_and_then([], |row| {
+                Ok::<(Address, String), DbError>((
+                    row.get::<usize, String>(0)?.parse()?,
+                    row.get(1)?,
+                ))
+            })?
+            .collect()
+    }
+
+    /// Write a user-defined label for the given [`Address`], replacing any
+    /// existing one
+    pub fn set_label(&self, address: Address, label: &str) -> Result<()> {
+        self.conn_pool.get()?.execute(
+            "INSERT OR REPLACE INTO labels (address, label) VALUES (?1, ?2)",
+            params![address.to_string(), label],
+        )?;
+        Ok(())
+    }
+
+    /// Remove the user-defined label for the given [`Address`] (if any)
+    pub fn remove_label(&self, address: Address) -> Result<()> {
+        self.conn_pool.get()?.execute(
+            "DELETE FROM labels WHERE address = ?",
+            [address.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// Record the latest safe and finalized blocks
+    pub fn set_finality(&self, finality: &Finality) -> Result<()> {
+        Self::write_finality(&*self.conn_pool.get()?, finality)?;
+        debug!("Recorded {finality:?}");
+        Ok(())
+    }
+
+    /// [`Database::set_finality`] using the given connection (e.g., within a
+    /// transaction)
+    fn write_finality(
+        conn: &rusqlite::Connection,
+        finality: &Finality,
+    ) -> Result<()> {
+        conn.execute(
+            "INSERT OR REPLACE INTO finality (tag, number)
+                VALUES ('safe', ?1), ('finalized', ?2)",
+            params![finality.safe, finality.finalized],
+        )?;
+        Ok(())
+    }
+
+    /// Retrieve the latest safe and finalized blocks (if ever recorded)
+    pub fn finality(&self) -> Result<Option<Finality>> {
+        let conn = self.conn_pool.get()?;
+        let mut stmt = conn.prepare("SELECT tag, number FROM finality")?;
+        let mut rows = stmt.query([])?;
+        let mut finality = None;
+
+        while let Some(row) = rows.next()? {
+            let finality = finality.get_or_insert_with(Finality::default);
+            match row.get::<_, String>(0)?.as_str() {
+                "safe" => finality.safe = row.get(1)?,
+                "finalized" => finality.finalized = row.get(1)?,
+                _ => {}
+            }
+        }
+
+        Ok(finality)
+    }
+
+    /// Record the hash of the genesis block of the indexed chain
+    pub fn set_genesis_hash(&self, hash: BlockHash) -> Result<()> {
+        Self::write_genesis_hash(&*self.conn_pool.get()?, hash)
+    }
+
+    /// [`Database::set_genesis_hash`] using the given connection (e.g., within a
+    /// transaction)
+    fn write_genesis_hash(
+        conn: &rusqlite::Connection,
+        hash: BlockHash,
+    ) -> Result<()> {
+        conn.execute(
+            "INSERT OR REPLACE INTO chain_info (key, value)
+                VALUES ('genesis_hash', ?)",
+            [hash.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// Record the ID of the indexed chain (which determines, e.g., how the
+    /// blob base fees of its blocks are derived)
+    pub fn set_chain_id(&self, chain_id: ChainId) -> Result<()> {
+        Self::write_chain_id(&*self.conn_pool.get()?, chain_id)
+    }
+
+    /// [`Database::set_chain_id`] using the given connection (e.g., within a
+    /// transaction)
+    fn write_chain_id(
+        conn: &rusqlite::Connection,
+        chain_id: ChainId,
+    ) -> Result<()> {
+        conn.execute(
+            "INSERT OR REPLACE INTO chain_info (key, value)
+                VALUES ('chain_id', ?)",
+            [chain_id.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// Retrieve the ID of the indexed chain (if ever recorded)
+    pub fn chain_id(&self) -> Result<Option<ChainId>> {
+        Self::read_chain_id(&*self.conn_pool.get()?)
+    }
+
+    /// [`Database::chain_id`] using the given connection (e.g., within a
+    /// transaction)
+    pub(super) fn read_chain_id(
+        conn: &rusqlite::Connection,
+    ) -> Result<Option<ChainId>> {
+        let mut stmt = conn.prepare_cached(
+            "SELECT value FROM chain_info WHERE key = 'chain_id'",
+        )?;
+        let mut rows = stmt.query([])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get::<_, String>(0)?.parse()?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve the hash of the genesis block of the indexed chain (if ever
+    /// recorded)
+    pub fn genesis_hash(&self) -> Result<Option<BlockHash>> {
+        let conn = self.conn_pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT value FROM chain_info WHERE key = 'genesis_hash'",
+        )?;
+        let mut rows = stmt.query([])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get::<_, String>(0)?.parse()?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve everything recorded about the indexed chain as a whole (see
+    /// [`ChainState`])
+    pub fn chain_state(&self) -> Result<ChainState> {
+        let conn = self.conn_pool.get()?;
+        let contract_sources = conn
+            .prepare("SELECT * FROM contract_sources")?
+            .query_and_then([], |row| {
+                Ok::<(Address, VerifiedSource), DbError>((
+                    row.get::<_, String>("address")?.parse()?,
+                    row_to_verified_source(row)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        let account_snapshots = conn
+            .prepare(
+                "SELECT address, block_number, balance, nonce
+                        FROM account_snapshots ORDER BY block_number",
+            )?
+            .query_and_then([], |row| {
+                Ok::<AccountSnapshot, DbError>(AccountSnapshot {
+                    address: row.get::<usize, String>(0)?.parse()?,
+                    block_number: row.get(1)?,
+                    balance: row.get::<usize, String>(2)?.parse()?,
+                    nonce: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ChainState {
+            chain_id: self.chain_id()?,
+            genesis_hash: self.genesis_hash()?,
+            finality: self.finality()?,
+            contract_sources,
+            account_snapshots,
+            bookmarks: self.bookmarks()?,
+        })
+    }
+
+    /// Write the given [`ChainState`] along with every [`IndexedBlock`]
+    /// yielded by the given iterator within a single SQLite transaction,
+    /// returning the number of blocks written
+    ///
+    /// Should the iterator yield an error, nothing at all is written.
+    pub fn import<E: From<DbError>>(
+        &self,
+        state: &ChainState,
+        blocks: impl IntoIterator<Item = std::result::Result<IndexedBlock, E>>,
+    ) -> std::result::Result<usize, E> {
+        let mut conn = self.conn_pool.get().map_err(DbError::from)?;
+        let tx = conn.transaction().map_err(DbError::from)?;
+        let mut count = 0;
+
+        /* first, as the chain ID determines how blocks are written */
+        Self::write_chain_state(&tx, state)?;
+        let chain_id = Self::read_chain_id(&tx)?;
+
+        for block in blocks {
+            Self::write_indexed_block(&tx, &block?, chain_id)?;
+            count += 1;
+        }
+
+        tx.commit().map_err(DbError::from)?;
+        info!("Imported {count} blocks into the database");
+        Ok(count)
+    }
+
+    /// Write an [`IndexedBlock`] of the chain with the given ID using the
+    /// given connection (e.g., within a transaction)
+    fn write_indexed_block(
+        conn: &rusqlite::Connection,
+        block: &IndexedBlock,
+        chain_id: Option<ChainId>,
+    ) -> Result<()> {
+        Self::write_block(conn, &block.block, chain_id)?;
+        if !block.l2.is_empty() {
+            Self::write_l2_block(conn, &block.l2)?;
+        }
+        Logs::write(conn, &block.logs)?;
+        Receipts::write_statuses(conn, &block.receipt_statuses)?;
+        if let Some(beacon) = &block.beacon {
+            Self::write_beacon_block(conn, beacon)?;
+        }
+        if !block.template.is_empty() {
+            Self::write_block_template(
+                conn,
+                block.block.header.number,
+                &block.template,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Write a [`ChainState`] using the given connection (e.g., within a
+    /// transaction)
+    fn write_chain_state(
+        conn: &rusqlite::Connection,
+        state: &ChainState,
+    ) -> Result<()> {
+        if let Some(chain_id) = state.chain_id {
+            Self::write_chain_id(conn, chain_id)?;
+        }
+        if let Some(hash) = state.genesis_hash {
+            Self::write_genesis_hash(conn, hash)?;
+        }
+        if let Some(finality) = &state.finality {
+            Self::write_finality(conn, finality)?;
+        }
+        state
+            .contract_sources
+            .iter()
+            .try_for_each(|(address, source)| {
+                Self::write_contract_source(conn, *address, source)
+            })?;
+        state.account_snapshots.iter().try_for_each(|snapshot| {
+            Self::write_account_snapshot(conn, snapshot)
+        })?;
+        state
+            .bookmarks
+            .iter()
+            .try_for_each(|bookmark| Self::write_bookmark(conn, bookmark))
+    }
+
+    /// Delete everything indexed from the chain (e.g., once it's been reset),
+    /// keeping only what the user entered themselves: labels, the watch list,
+    /// and bookmarks of addresses
+    pub fn clear_chain_data(&self) -> Result<()> {
+        let mut conn = self.conn_pool.get()?;
+        let tx = conn.transaction()?;
+        tx.execute_batch(
+            "DELETE FROM block_headers;
+            DELETE FROM transactions;
+            DELETE FROM logs;
+            DELETE FROM log_addresses;
+            DELETE FROM fee_stats;
+            DELETE FROM template_transactions;
+            DELETE FROM receipt_statuses;
+            DELETE FROM finality;
+            DELETE FROM beacon_blocks;
+            DELETE FROM contract_sources;
+            DELETE FROM account_snapshots;
+            DELETE FROM bookmarks WHERE kind != 'address';
+            DELETE FROM block_fees;
+            DELETE FROM fee_totals;
+            DELETE FROM hourly_stats;
+            DELETE FROM chain_info;
+            DELETE FROM l1_origins;
+            DELETE FROM deposit_transactions;
+            DELETE FROM other_transactions;",
+        )?;
+        tx.commit()?;
+        info!("Cleared indexed chain data from the database");
+        Ok(())
+    }
+
+    /// Copy the database to a sibling file whose name is tagged with the
+    /// given suffix (e.g., `blocktop.db` becomes `blocktop.<suffix>.db`),
+    /// returning the path of the copy
+    pub fn archive(&self, suffix: &str) -> Result<PathBuf> {
+        let conn = self.conn_pool.get()?;
+        let path =
+            PathBuf::from(conn.path().filter(|path| !path.is_empty()).ok_or(
+                DbError::Unsupported(
+                    "archiving of in-memory databases".to_string(),
+                ),
+            )?);
+        let mut name = path.file_stem().unwrap_or_default().to_os_string();
+        name.push(format!(".{suffix}"));
+        if let Some(extension) = path.extension() {
+            name.push(".");
+            name.push(extension);
+        }
+        let archive = path.with_file_name(name);
+        conn.execute(
+            "VACUUM INTO ?",
+            [archive.to_string_lossy().into_owned()],
+        )?;
+        info!("Archived the database to {}", archive.display());
+        Ok(archive)
+    }
+
+    /// Record (or overwrite) where an execution block sits on the beacon
+    /// chain
+    pub fn add_beacon_block(&self, block: &BeaconBlock) -> Result<()> {
+        Self::write_beacon_block(&*self.conn_pool.get()?, block)?;
+        debug!("Recorded {block:?}");
+        Ok(())
+    }
+
+    /// Record (or overwrite) where an execution block sits on the beacon
+    /// chain using the given connection (e.g., within a transaction)
+    fn write_beacon_block(
+        conn: &rusqlite::Connection,
+        block: &BeaconBlock,
+    ) -> Result<()> {
+        conn.execute(
+            "INSERT OR REPLACE INTO beacon_blocks (
+                block_hash,
+                block_number,
+                slot,
+                epoch,
+                proposer_index
+            ) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                block.block_hash.to_string(),
+                block.block_number,
+                block.slot,
+                block.epoch,
+                block.proposer_index
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Retrieve where the execution block with the given [`BlockHash`] sits
+    /// on the beacon chain (if this was ever recorded)
+    pub fn beacon_block(&self, hash: BlockHash) -> Result<Option<BeaconBlock>> {
+        let conn = self.conn_pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT block_number, slot, epoch, proposer_index
+                FROM beacon_blocks WHERE block_hash = ?",
+        )?;
+        let mut rows = stmt.query([hash.to_string()])?;
+
+        match rows.next()? {
+            Some(row) => Ok(Some(BeaconBlock {
+                block_hash: hash,
+                block_number: row.get(0)?,
+                slot: row.get(1)?,
+                epoch: row.get(2)?,
+                proposer_index: row.get(3)?,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    /// Record (or overwrite) what's peculiar to rollups about a single L2
+    /// block (i.e., its L1 origin, deposit transactions, and transactions of
+    /// unknown types) atomically
+    pub fn add_l2_block(&self, block: &L2Block) -> Result<()> {
+        let mut conn = self.conn_pool.get()?;
+        let tx = conn.transaction()?;
+        Self::write_l2_block(&tx, block)?;
+        tx.commit()?;
+        debug!(
+            "Recorded L2 block {} ({} deposits, {} of unknown types)",
+            block.block_hash,
+            block.deposits.len(),
+            block.others.len()
+        );
+        Ok(())
+    }
+
+    /// Record (or overwrite) what's peculiar to rollups about a single L2
+    /// block using the given connection (e.g., within a transaction)
+    fn write_l2_block(
+        conn: &rusqlite::Connection,
+        block: &L2Block,
+    ) -> Result<()> {
+        if let Some(origin) = &block.l1_origin {
+            conn.execute(
+                "INSERT OR REPLACE INTO l1_origins (
+                    block_hash,
+                    l1_number,
+                    l1_hash,
+                    l1_timestamp,
+                    sequence_number
+                ) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    origin.block_hash.to_string(),
+                    origin.number,
+                    origin.hash.map(|hash| hash.to_string()),
+                    origin.timestamp,
+                    origin.sequence_number
+                ],
+            )?;
+        }
+        {
+            let mut statement = conn.prepare(
+                "INSERT OR REPLACE INTO deposit_transactions (
+                    hash,
+                    block_hash,
+                    block_number,
+                    position,
+                    source_hash,
+                    from_address,
+                    to_address,
+                    mint,
+                    value,
+                    gas_limit,
+                    is_system_tx,
+                    input
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            )?;
+            for deposit in &block.deposits {
+                statement.execute(params![
+                    deposit.hash.to_string(),
+                    deposit.block_hash.to_string(),
+                    deposit.block_number,
+                    deposit.position,
+                    deposit.source_hash.to_string(),
+                    deposit.from.to_string(),
+                    deposit.to.map(|to| to.to_string()),
+                    deposit.mint.to_string(),
+                    deposit.value.to_string(),
+                    deposit.gas_limit,
+                    deposit.is_system_tx,
+                    deposit.input.to_string()
+                ])?;
+            }
+        }
+        {
+            let mut statement = conn.prepare(
+                "INSERT OR REPLACE INTO other_transactions (
+                    hash,
+                    block_hash,
+                    block_number,
+                    position,
+                    type,
+                    from_address,
+                    fields
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+            for other in &block.others {
+                statement.execute(params![
+                    other.hash.to_string(),
+                    other.block_hash.to_string(),
+                    other.block_number,
+                    other.position,
+                    other.tx_type,
+                    other.from.to_string(),
+                    serde_json::to_string(&other.fields)?
+                ])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Retrieve what's peculiar to rollups about the L2 block with the given
+    /// [`BlockHash`] (so far as it's been recorded)
+    pub fn l2_block(&self, hash: BlockHash) -> Result<L2Block> {
+        let conn = self.conn_pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT l1_number, l1_hash, l1_timestamp, sequence_number
+                FROM l1_origins WHERE block_hash = ?",
+        )?;
+        let mut rows = stmt.query([hash.to_string()])?;
+        let l1_origin = match rows.next()? {
+            Some(row) => Some(L1Origin {
+                block_hash: hash,
+                number: row.get(0)?,
+                hash: row
+                    .get::<_, Option<String>>(1)?
+                    .map(|hash| hash.parse())
+                    .transpose()?,
+                timestamp: row.get(2)?,
+                sequence_number: row.get(3)?,
+            }),
+            None => None,
+        };
+        drop(rows);
+
+        let mut stmt = conn.prepare(
+            "SELECT * FROM deposit_transactions WHERE block_hash = ?
+                ORDER BY position",
+        )?;
+        let deposits = stmt
+            .query_and_then([hash.to_string()], |row| {
+                Ok::<_, DbError>(DepositTransaction {
+                    hash: row.get::<_, String>("hash")?.parse()?,
+                    block_hash: hash,
+                    block_number: row.get("block_number")?,
+                    position: row.get("position")?,
+                    source_hash: row
+                        .get::<_, String>("source_hash")?
+                        .parse()?,
+                    from: row.get::<_, String>("from_address")?.parse()?,
+                    to: row
+                        .get::<_, Option<String>>("to_address")?
+                        .map(|to| to.parse())
+                        .transpose()?,
+                    mint: row.get::<_, String>("mint")?.parse()?,
+                    value: row.get::<_, String>("value")?.parse()?,
+                    gas_limit: row.get("gas_limit")?,
+                    is_system_tx: row.get("is_system_tx")?,
+                    input: row.get::<_, String>("input")?.parse()?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT * FROM other_transactions WHERE block_hash = ?
+                ORDER BY position",
+        )?;
+        let others = stmt
+            .query_and_then([hash.to_string()], |row| {
+                Ok::<_, DbError>(OtherTransaction {
+                    hash: row.get::<_, String>("hash")?.parse()?,
+                    block_hash: hash,
+                    block_number: row.get("block_number")?,
+                    position: row.get("position")?,
+                    tx_type: row.get("type")?,
+                    from: row.get::<_, String>("from_address")?.parse()?,
+                    fields: serde_json::from_str(
+                        &row.get::<_, String>("fields")?,
+                    )?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(L2Block {
+            block_hash: hash,
+            l1_origin,
+            deposits,
+            others,
+        })
+    }
+
+    /// Cache the verified source code of the contract at the given
+    /// [`Address`], replacing any already cached
+    pub fn add_contract_source(
+        &self,
+        address: Address,
+        source: &VerifiedSource,
+    ) -> Result<()> {
+        Self::write_contract_source(&*self.conn_pool.get()?, address, source)
+    }
+
+    /// [`Database::add_contract_source`] using the given connection (e.g., within a
+    /// transaction)
+    fn write_contract_source(
+        conn: &rusqlite::Connection,
+        address: Address,
+        source: &VerifiedSource,
+    ) -> Result<()> {
+        conn.execute(
+            "INSERT OR REPLACE INTO contract_sources (
+                address,
+                verifier,
+                name,
+                language,
+                compiler_version,
+                exact_match,
+                abi,
+                sources
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                address.to_string(),
+                source.verifier,
+                source.name,
+                source.language,
+                source.compiler_version,
+                source.exact,
+                serde_json::to_string(&source.abi)?,
+                serde_json::to_string(&source.sources)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Retrieve the cached verified source code of the contract at the given
+    /// [`Address`] (if any)
+    pub fn contract_source(
+        &self,
+        address: Address,
+    ) -> Result<Option<VerifiedSource>> {
+        let conn = self.conn_pool.get()?;
+        let mut stmt =
+            conn.prepare("SELECT * FROM contract_sources WHERE address = ?")?;
+        let mut rows = stmt.query([address.to_string()])?;
+
+        match rows.next()? {
+            Some(row) => Ok(Some(row_to_verified_source(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieves the ABI of every contract whose source code is cached
+    pub fn contract_abis(&self) -> Result<Vec<(Address, JsonAbi)>> {
+        self.conn_pool
+            .get()?
+            .prepare("SELECT address, abi FROM contract_sources")?
+            .query_and_then([], |row| {
+                Ok::<(Address, JsonAbi), DbError>((
+                    row.get::<usize, String>(0)?.parse()?,
+                    serde_json::from_str(&row.get::<usize, String>(1)?)?,
+                ))
+            })?
+            .collect()
+    }
+
+    /// Retrieves every address on the watch list
+    pub fn watched_addresses(&self) -> Result<Vec<Address>> {
+        self.conn_pool
+            .get()?
+            .prepare("SELECT address FROM watched_addresses")?
+            .query_and_then([], |row| {
+                Ok::<Address, DbError>(row.get::<usize, String>(0)?.parse()?)
+            })?
+            .collect()
+    }
+
+    /// Add the given [`Address`] to the watch list (if it isn't already)
+    pub fn watch_address(&self, address: Address) -> Result<()> {
+        self.conn_pool.get()?.execute(
+            "INSERT OR IGNORE INTO watched_addresses (address) VALUES (?)",
+            [address.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// Remove the given [`Address`] from the watch list (keeping its
+    /// recorded balances and nonces)
+    pub fn unwatch_address(&self, address: Address) -> Result<()> {
+        self.conn_pool.get()?.execute(
+            "DELETE FROM watched_addresses WHERE address = ?",
+            [address.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// Record (or overwrite) the balance and nonce of an account as of a
+    /// particular block
+    pub fn add_account_snapshot(
+        &self,
+        snapshot: &AccountSnapshot,
+    ) -> Result<()> {
+        Self::write_account_snapshot(&*self.conn_pool.get()?, snapshot)
+    }
+
+    /// [`Database::add_account_snapshot`] using the given connection (e.g., within a
+    /// transaction)
+    fn write_account_snapshot(
+        conn: &rusqlite::Connection,
+        snapshot: &AccountSnapshot,
+    ) -> Result<()> {
+        conn.execute(
+            "INSERT OR REPLACE INTO account_snapshots (
+                address,
+                block_number,
+                balance,
+                nonce
+            ) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                snapshot.address.to_string(),
+                snapshot.block_number,
+                snapshot.balance.to_string(),
+                snapshot.nonce
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Retrieves (up to) the given number of the most recent balances and
+    /// nonces recorded for the given [`Address`], oldest first
+    pub fn account_snapshots(
+        &self,
+        address: Address,
+        limit: usize,
+    ) -> Result<Vec<AccountSnapshot>> {
+        let conn = self.conn_pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT block_number, balance, nonce FROM account_snapshots
+                WHERE address = ?1 ORDER BY block_number DESC LIMIT ?2",
+        )?;
+        let mut snapshots = stmt
+            .query_and_then(params![address.to_string(), limit], |row| {
+                Ok::<AccountSnapshot, DbError>(AccountSnapshot {
+                    address,
+                    block_number: row.get(0)?,
+                    balance: row.get::<usize, String>(1)?.parse()?,
+                    nonce: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        snapshots.reverse();
+        Ok(snapshots)
+    }
+
+    /// Retrieves every [`Bookmark`], most recent first
+    pub fn bookmarks(&self) -> Result<Vec<Bookmark>> {
+        self.conn_pool
+            .get()?
+            .prepare(
+                "SELECT kind, target, block_number, note, created_at
+                    FROM bookmarks ORDER BY created_at DESC, rowid DESC",
+            )?
+            .query_and_then([], |row| {
+                let target = row.get::<usize, String>(1)?;
+                Ok::<Bookmark, DbError>(Bookmark {
+                    target: match row.get::<usize, String>(0)?.as_str() {
+                        "block" => BookmarkTarget::Block {
+                            number: row.get(2)?,
+                            hash: target.parse()?,
+                        },
+                        "transaction" => {
+                            BookmarkTarget::Transaction(target.parse()?)
+                        }
+                        "address" => BookmarkTarget::Address(target.parse()?),
+                        other => {
+                            return Err(DbError::Decode(format!(
+                                "bookmark of unknown kind {other}"
+                            )))
+                        }
+                    },
+                    note: row.get(3)?,
+                    created_at: row.get(4)?,
+                })
+            })?
+            .collect()
+    }
+
+    /// Save the given [`Bookmark`], replacing any existing bookmark of the
+    /// same target
+    pub fn add_bookmark(&self, bookmark: &Bookmark) -> Result<()> {
+        Self::write_bookmark(&*self.conn_pool.get()?, bookmark)
+    }
+
+    /// [`Database::add_bookmark`] using the given connection (e.g., within a
+    /// transaction)
+    fn write_bookmark(
+        conn: &rusqlite::Connection,
+        bookmark: &Bookmark,
+    ) -> Result<()> {
+        let block_number = match bookmark.target {
+            BookmarkTarget::Block { number, .. } => Some(number),
+            _ => None,
+        };
+        conn.execute(
+            "INSERT OR REPLACE INTO bookmarks (
+                kind,
+                target,
+                block_number,
+                note,
+                created_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                bookmark.target.kind(),
+                bookmark.target.key(),
+                block_number,
+                bookmark.note,
+                bookmark.created_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Remove the bookmark of the given [`BookmarkTarget`] (if any)
+    pub fn remove_bookmark(&self, target: &BookmarkTarget) -> Result<()> {
+        self.conn_pool.get()?.execute(
+            "DELETE FROM bookmarks WHERE kind = ?1 AND target = ?2",
+            params![target.kind(), target.key()],
+        )?;
+        Ok(())
+    }
+
+    /// Retrieves the hashes of the transactions (in order) of the most recent
+    /// local block template for the given [`BlockNumber`]
+    ///
+    /// If no template was recorded for this block, the returned vector is
+    /// guaranteed to have a length of zero.
+    pub fn block_template(&self, number: BlockNumber) -> Result<Vec<TxHash>> {
+        self.conn_pool
+            .get()?
+            .prepare(
+                "SELECT transaction_hash FROM template_transactions
+                    WHERE block_number = ? ORDER BY position ASC",
+            )?
+            .query_and_then([number], |row| {
+                Ok::<TxHash, DbError>(row.get::<usize, String>(0)?.parse()?)
+            })?
+            .collect()
+    }
+
+    /// Replace the local block template for the given [`BlockNumber`]
+    pub fn set_block_template(
+        &self,
+        number: BlockNumber,
+        transactions: &[TxHash],
+    ) -> Result<()> {
+        let mut conn = self.conn_pool.get()?;
+        let tx = conn.transaction()?;
+        Self::write_block_template(&tx, number, transactions)?;
+        tx.commit()?;
+        debug!(
+            "Wrote template for block #{} ({} transactions) to the database",
+            number,
+            transactions.len()
+        );
+        Ok(())
+    }
+
+    /// Replace the local block template for the given [`BlockNumber`] using
+    /// the given connection (e.g., within a transaction)
+    fn write_block_template(
+        conn: &rusqlite::Connection,
+        number: BlockNumber,
+        transactions: &[TxHash],
+    ) -> Result<()> {
+        conn.execute(
+            "DELETE FROM template_transactions WHERE block_number = ?",
+            [number],
+        )?;
+        let mut statement = conn.prepare(
+            "INSERT OR IGNORE INTO template_transactions (
+                block_number,
+                transaction_hash,
+                position
+            ) VALUES (?1, ?2, ?3)",
+        )?;
+        transactions.iter().enumerate().try_for_each(|(i, hash)| {
+            statement.execute(params![number, hash.to_string(), i])?;
+            Ok::<(), DbError>(())
+        })
+    }
+}
+
+fn row_to_verified_source(row: &rusqlite::Row) -> Result<VerifiedSource> {
+    Ok(VerifiedSource {
+        verifier: row.get("verifier")?,
+        name: row.get("name")?,
+        language: row.get("language")?,
+        compiler_version: row.get("compiler_version")?,
+        exact: row.get("exact_match")?,
+        abi: serde_json::from_str(&row.get::<_, String>("abi")?)?,
+        sources: serde_json::from_str(&row.get::<_, String>("sources")?)?,
+    })
+}
+
+/// Type 2 [`Transaction`] with the given hash, at the given position within
+/// the given block, from and to the zero address with no value or input,
+/// after applying the given changes to its row
+#[cfg(test)]
+pub(crate) fn test_transaction(
+    hash: TxHash,
+    block_hash: BlockHash,
+    block_number: BlockNumber,
+    position: u64,
+    changes: impl FnOnce(&mut TransactionRow),
+) -> Transaction {
+    let mut row = TransactionRow {
+        hash: hash.to_string(),
+        block_hash: block_hash.to_string(),
+        block_number,
+        position,
+        from_address: Address::ZERO.to_string(),
+        tx_type: 2,
+        to_address: Address::ZERO.to_string(),
+        value: "0".to_string(),
+        input: "0x".to_string(),
+        max_priority_fee_per_gas: Some(1),
+        ..Default::default()
+    };
+    changes(&mut row);
+    row.into_transaction().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::{Bytes, B256},
+        serde::OtherFields,
+    };
+
+    use serde_json::json;
+
+    use crate::{
+        storage::Storage, test_utils::eip1559_transaction,
+        verification::SourceFile,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_latest_block() {
+        let block = Block::default();
+        let creation_result = Database::new(Location::Memory);
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        let insertion_result = db.add_block_atomic(&block);
+        assert!(insertion_result.is_ok());
+        let retrieval_result = db.latest_block();
+        assert!(retrieval_result.is_ok());
+        let perhaps_latest_block = retrieval_result.unwrap();
+        assert!(perhaps_latest_block.is_some());
+    }
+
+    #[test]
+    fn test_add_block_atomic() {
+        let mut header = Header::new(alloy::consensus::Header {
+            number: 1,
+            base_fee_per_gas: Some(1),
+            ..Default::default()
+        });
+        header.hash = BlockHash::with_last_byte(1);
+        let hashes: Vec<TxHash> = (1..=3).map(TxHash::with_last_byte).collect();
+        let transaction = |i: usize| {
+            let mut tx =
+                eip1559_transaction(hashes[i], Address::ZERO, i as u64);
+            tx["blockHash"] = json!(header.hash);
+            tx["blockNumber"] = json!("0x1");
+            tx["transactionIndex"] = json!(format!("{i:#x}"));
+            tx
+        };
+        let block = |transactions: Vec<serde_json::Value>| {
+            Block::new(
+                header.clone(),
+                alloy::rpc::types::BlockTransactions::Full(
+                    serde_json::from_value(json!(transactions)).unwrap(),
+                ),
+            )
+        };
+
+        let db = Database::new(Location::Memory).unwrap();
+        assert!(db
+            .add_block_atomic(&block((0..3).map(transaction).collect()))
+            .is_ok());
+        let stored = db.block_by_hash(header.hash).unwrap().unwrap();
+        assert_eq!(stored.transactions.hashes().collect::<Vec<_>>(), hashes);
+        assert_eq!(db.headers().recent_fee_stats(10).unwrap().len(), 1);
+        assert_eq!(db.headers().hourly_stats(10).unwrap().len(), 1);
+
+        /* the last transaction can't be written, as it's missing its block */
+        let mut transactions: Vec<_> = (0..3).map(transaction).collect();
+        transactions[2].as_object_mut().unwrap().remove("blockHash");
+        let db = Database::new(Location::Memory).unwrap();
+        assert!(db.add_block_atomic(&block(transactions)).is_err());
+        assert_eq!(db.headers().by_hash(header.hash).unwrap(), None);
+        for hash in hashes {
+            assert_eq!(db.transactions().by_hash(hash).unwrap(), None);
+        }
+        assert!(db.headers().recent_fee_stats(10).unwrap().is_empty());
+        assert!(db.headers().hourly_stats(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_canonical_blocks() {
+        let block = |number: u8, hash: u8, txs: u8| {
+            let mut header = Header::new(alloy::consensus::Header {
+                number: number as u64,
+                ..Default::default()
+            });
+            header.hash = BlockHash::with_last_byte(hash);
+            let txs = (0..txs)
+                .map(|i| {
+                    test_transaction(
+                        TxHash::left_padding_from(&[hash, i]),
+                        header.hash,
+                        number as u64,
+                        i as u64,
+                        |_| {},
+                    )
+                })
+                .collect();
+            Block::new(header, alloy::rpc::types::BlockTransactions::Full(txs))
+        };
+        let db = Database::new(Location::Memory).unwrap();
+        for block in [
+            block(1, 1, 2),
+            block(2, 2, 1),
+            block(2, 0x22, 3),
+            block(3, 3, 0),
+        ] {
+            db.add_block_atomic(&block).unwrap();
+        }
+        db.mark_orphaned(&[BlockHash::with_last_byte(0x22)])
+            .unwrap();
+
+        let blocks = db.canonical_blocks(1..=2).unwrap();
+        assert_eq!(
+            blocks
+                .iter()
+                .map(|block| (block.header.hash, block.transactions.len()))
+                .collect::<Vec<_>>(),
+            vec![
+                (BlockHash::with_last_byte(1), 2),
+                (BlockHash::with_last_byte(2), 1),
+            ]
+        );
+        assert_eq!(
+            blocks[0]
+                .transactions
+                .txns()
+                .nth(1)
+                .unwrap()
+                .transaction_index,
+            Some(1)
+        );
+        assert!(db.canonical_blocks(4..=10).unwrap().is_empty());
+
+        assert_eq!(Storage::builder_stats(&db, 1..=3).unwrap()[0].blocks, 3);
+        assert!(Storage::builder_stats(
+            &db,
+            0..=crate::stats::MAX_BUILDER_STATS_BLOCKS
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_duplicate_blocks_ignored() {
+        let block = Block::default();
+        let creation_result = Database::new(Location::Memory);
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        assert!(db.add_block_atomic(&block).is_ok());
+        assert!(db.add_block_atomic(&block).is_ok());
+        assert!(db.add_block_atomic(&block).is_ok());
+        let hashes_result = db.all_block_hashes();
+        assert!(hashes_result.is_ok());
+        assert_eq!(hashes_result.unwrap(), vec![block.header.hash]);
+    }
+
+    #[test]
+    fn test_query() {
+        const TIMEOUT: Duration = Duration::from_secs(5);
+
+        let creation_result = Database::new(Location::Memory);
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        assert!(db.set_label(Address::with_last_byte(1), "Alice").is_ok());
+        assert!(db.set_label(Address::with_last_byte(2), "Bob").is_ok());
+
+        let result = db
+            .query(
+                "SELECT label, NULL AS missing, x'beef' FROM labels",
+                10,
+                TIMEOUT,
+            )
+            .unwrap();
+        assert_eq!(result.columns, vec!["label", "missing", "x'beef'"]);
+        assert_eq!(
+            result.rows,
+            vec![
+                vec!["Alice", "NULL", "0xbeef"],
+                vec!["Bob", "NULL", "0xbeef"]
+            ]
+        );
+        assert!(!result.truncated);
+
+        let result = db.query("SELECT * FROM labels", 1, TIMEOUT).unwrap();
+        assert_eq!(result.rows.len(), 1);
+        assert!(result.truncated);
+
+        assert!(matches!(
+            db.query("DELETE FROM labels", 10, TIMEOUT),
+            Err(DbError::Unsupported(_))
+        ));
+        assert!(matches!(
+            db.query("BEGIN", 10, TIMEOUT),
+            Err(DbError::Unsupported(_))
+        ));
+        assert!(matches!(
+            db.query("SELECT 1; DELETE FROM labels", 10, TIMEOUT),
+            Err(DbError::Unsupported(_))
+        ));
+        assert!(matches!(
+            db.query("SELECT * FROM nonexistent", 10, TIMEOUT),
+            Err(DbError::Sqlite(_))
+        ));
+        assert_eq!(db.labels().unwrap().len(), 2);
+        assert!(matches!(
+            db.query(
+                "WITH RECURSIVE forever(x) AS
+                    (SELECT 1 UNION ALL SELECT x + 1 FROM forever)
+                SELECT count(*) FROM forever",
+                10,
+                Duration::from_millis(50)
+            ),
+            Err(DbError::Deadline(_))
+        ));
+
+        /* connections go back to the pool writable (and without a deadline) */
+        assert!(db.remove_label(Address::with_last_byte(2)).is_ok());
+    }
+
+    #[test]
+    fn test_labels() {
+        let creation_result = Database::new(Location::Memory);
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        let address = Address::with_last_byte(1);
+        assert!(db.set_label(address, "Alice").is_ok());
+        assert!(db.set_label(address, "Bob").is_ok());
+        let labels_result = db.labels();
+        assert!(labels_result.is_ok());
+        assert_eq!(labels_result.unwrap(), vec![(address, "Bob".to_string())]);
+        assert!(db.remove_label(address).is_ok());
+        assert_eq!(db.labels().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_watch_list() {
+        let db = Database::new(Location::Memory).unwrap();
+        let address = Address::with_last_byte(1);
+        db.watch_address(address).unwrap();
+        db.watch_address(address).unwrap();
+        assert_eq!(db.watched_addresses().unwrap(), vec![address]);
+
+        for (block_number, balance) in [(2, 500), (1, 1_000), (3, 250)] {
+            db.add_account_snapshot(&AccountSnapshot {
+                address,
+                block_number,
+                balance: U256::from(balance),
+                nonce: block_number,
+            })
+            .unwrap();
+        }
+        let snapshots = db.account_snapshots(address, 2).unwrap();
+        assert_eq!(
+            snapshots
+                .iter()
+                .map(|snapshot| (snapshot.block_number, snapshot.balance))
+                .collect::<Vec<_>>(),
+            vec![(2, U256::from(500)), (3, U256::from(250))]
+        );
+
+        db.unwatch_address(address).unwrap();
+        assert_eq!(db.watched_addresses().unwrap(), Vec::<Address>::new());
+        assert_eq!(db.account_snapshots(address, 8).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_bookmarks() {
+        let db = Database::new(Location::Memory).unwrap();
+        let block = BookmarkTarget::Block {
+            number: 1,
+            hash: BlockHash::with_last_byte(1),
+        };
+        let address = BookmarkTarget::Address(Address::with_last_byte(2));
+        db.add_bookmark(&Bookmark {
+            target: block,
+            note: None,
+            created_at: 1,
+        })
+        .unwrap();
+        db.add_bookmark(&Bookmark {
+            target: address,
+            note: Some("Bob".to_string()),
+            created_at: 2,
+        })
+        .unwrap();
+        db.add_bookmark(&Bookmark {
+            target: block,
+            note: Some("Big reorg".to_string()),
+            created_at: 3,
+        })
+        .unwrap();
+        assert_eq!(
+            db.bookmarks()
+                .unwrap()
+                .into_iter()
+                .map(|bookmark| (bookmark.target, bookmark.note))
+                .collect::<Vec<_>>(),
+            vec![
+                (block, Some("Big reorg".to_string())),
+                (address, Some("Bob".to_string()))
+            ]
+        );
+
+        db.remove_bookmark(&block).unwrap();
+        assert_eq!(db.bookmarks().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_finality() {
+        let db = Database::new(Location::Memory).unwrap();
+        assert_eq!(db.finality().unwrap(), None);
+
+        let finality = Finality {
+            safe: 100,
+            finalized: 64,
+        };
+        db.set_finality(&finality).unwrap();
+        db.set_finality(&Finality {
+            safe: 132,
+            ..finality
+        })
+        .unwrap();
+
+        let finality = db.finality().unwrap().unwrap();
+        assert_eq!(finality.safe, 132);
+        assert_eq!(finality.status(64), FinalityStatus::Finalized);
+        assert_eq!(finality.status(65), FinalityStatus::Safe);
+        assert_eq!(finality.status(132), FinalityStatus::Safe);
+        assert_eq!(finality.status(133), FinalityStatus::Unsafe);
+    }
+
+    #[test]
+    fn test_clear_chain_data() {
+        let db = Database::new(Location::Memory).unwrap();
+        assert_eq!(db.genesis_hash().unwrap(), None);
+        let genesis = BlockHash::with_last_byte(1);
+        db.set_genesis_hash(genesis).unwrap();
+        assert_eq!(db.genesis_hash().unwrap(), Some(genesis));
+
+        let block = Block::default();
+        let address = Address::with_last_byte(2);
+        db.add_block_atomic(&block).unwrap();
+        db.set_label(address, "alice").unwrap();
+        db.add_bookmark(&Bookmark {
+            target: BookmarkTarget::Address(address),
+            note: None,
+            created_at: 0,
+        })
+        .unwrap();
+
+        db.clear_chain_data().unwrap();
+        assert!(db.all_block_hashes().unwrap().is_empty());
+        assert_eq!(db.genesis_hash().unwrap(), None);
+        assert_eq!(db.labels().unwrap().len(), 1);
+        assert_eq!(
+            db.bookmarks()
+                .unwrap()
+                .iter()
+                .map(|bookmark| bookmark.target)
+                .collect::<Vec<_>>(),
+            vec![BookmarkTarget::Address(address)]
+        );
+
+        /* there's nowhere to archive an in-memory database to */
+        assert!(db.archive("old").is_err());
+    }
+
+    #[test]
+    fn test_beacon_block() {
+        let db = Database::new(Location::Memory).unwrap();
+        let hash = BlockHash::with_last_byte(1);
+        assert_eq!(db.beacon_block(hash).unwrap(), None);
+
+        let block = BeaconBlock {
+            block_hash: hash,
+            block_number: 20_000_000,
+            slot: 9_200_000,
+            epoch: 287_500,
+            proposer_index: 12_345,
+        };
+        db.add_beacon_block(&block).unwrap();
+        db.add_beacon_block(&block).unwrap();
+        assert_eq!(db.beacon_block(hash).unwrap(), Some(block));
+        assert_eq!(db.beacon_block(BlockHash::ZERO).unwrap(), None);
+    }
+
+    #[test]
+    fn test_l2_block() {
+        let db = Database::new(Location::Memory).unwrap();
+        let hash = BlockHash::with_last_byte(1);
+        assert!(db.l2_block(hash).unwrap().is_empty());
+
+        let deposit = |position: u64, to| DepositTransaction {
+            hash: TxHash::with_last_byte(position as u8),
+            block_hash: hash,
+            block_number: 16,
+            position,
+            source_hash: B256::with_last_byte(9),
+            from: Address::with_last_byte(1),
+            to,
+            mint: U256::from(position * 1_000),
+            value: U256::from(1),
+            gas_limit: 1_000_000,
+            is_system_tx: position == 0,
+            input: Bytes::from_static(&[0x44, 0x0a]),
+        };
+        let block = L2Block {
+            block_hash: hash,
+            l1_origin: Some(L1Origin {
+                block_hash: hash,
+                number: 0x1234,
+                hash: Some(B256::with_last_byte(7)),
+                timestamp: Some(1_700_000_000),
+                sequence_number: Some(3),
+            }),
+            /* stored out of order, but retrieved in order */
+            deposits: vec![
+                deposit(1, None),
+                deposit(0, Some(Address::with_last_byte(2))),
+            ],
+            others: vec![OtherTransaction {
+                hash: TxHash::with_last_byte(2),
+                block_hash: hash,
+                block_number: 16,
+                position: 2,
+                tx_type: 0x6a,
+                from: Address::with_last_byte(3),
+                fields: OtherFields::default()
+                    .with_value("nonce".to_string(), "0x1")
+                    .unwrap(),
+            }],
+        };
+        db.add_l2_block(&block).unwrap();
+        db.add_l2_block(&block).unwrap();
+
+        let stored = db.l2_block(hash).unwrap();
+        assert_eq!(stored.l1_origin, block.l1_origin);
+        assert_eq!(
+            stored.deposits,
+            vec![block.deposits[1].clone(), block.deposits[0].clone()]
+        );
+        assert_eq!(stored.others, block.others);
+        assert!(db.l2_block(BlockHash::ZERO).unwrap().is_empty());
+
+        db.clear_chain_data().unwrap();
+        assert!(db.l2_block(hash).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_contract_source() {
+        let db = Database::new(Location::Memory).unwrap();
+        let address = Address::with_last_byte(1);
+        assert_eq!(db.contract_source(address).unwrap(), None);
+
+        let source = VerifiedSource {
+            verifier: "Sourcify".to_string(),
+            name: "Token".to_string(),
+            language: "Solidity".to_string(),
+            compiler_version: "0.8.20+commit.a1b79de6".to_string(),
+            exact: None,
+            abi: serde_json::from_str(
+                r#"[{"type": "function", "name": "totalSupply", "stateMutability": "view", "inputs": [], "outputs": [{"name": "", "type": "uint256"}]}]"#,
+            )
+            .unwrap(),
+            sources: vec![SourceFile {
+                path: "src/Token.sol".to_string(),
+                content: "contract Token {}".to_string(),
+            }],
+        };
+        db.add_contract_source(address, &source).unwrap();
+        assert_eq!(db.contract_source(address).unwrap(), Some(source.clone()));
+        assert_eq!(db.contract_abis().unwrap(), vec![(address, source.abi)]);
+    }
+}
@@ -0,0 +1,805 @@
+//! Outcomes of executing transactions, as per their receipts, along with the
+//! fees paid by each block
+use std::{collections::HashSet, ops::RangeInclusive, sync::Arc};
+
+use alloy::{
+    consensus::{Transaction as _, TxReceipt},
+    network::ReceiptResponse,
+    primitives::{Address, BlockHash, BlockNumber, TxHash, U256},
+    rpc::types::{eth::Header, Log, Transaction, TransactionReceipt},
+};
+use log::debug;
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, Row};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    headers::row_to_header, transactions::row_to_transaction, DbError, Result,
+};
+
+const INSERT_RECEIPT_STATUS_SQL: &str =
+    "INSERT OR REPLACE INTO receipt_statuses (
+        block_hash,
+        transaction_hash,
+        success,
+        gas_used,
+        revert_reason,
+        l1_fee
+    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)";
+
+/// Outcome of executing a mined transaction, as per its receipt
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ReceiptStatus {
+    pub block_hash: BlockHash,
+    pub transaction_hash: TxHash,
+    /// Whether the transaction executed successfully (as opposed to
+    /// reverting)
+    pub success: bool,
+    pub gas_used: u64,
+    /// Why the transaction reverted (if it did and this is known)
+    pub revert_reason: Option<String>,
+    /// Fee (in wei) paid for posting the transaction to L1, on rollups that
+    /// report it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub l1_fee: Option<U256>,
+}
+
+impl ReceiptStatus {
+    /// Summarise the given [`TransactionReceipt`] (of any transaction type),
+    /// without any revert reason or L1 fee
+    ///
+    /// Fails if the receipt is missing its enclosing block.
+    pub fn from_receipt<T: TxReceipt<Log = Log>>(
+        receipt: &TransactionReceipt<T>,
+    ) -> Result<Self> {
+        Ok(Self {
+            block_hash: receipt.block_hash.ok_or(DbError::Decode(
+                "receipt is missing block hash".into(),
+            ))?,
+            transaction_hash: receipt.transaction_hash,
+            success: ReceiptResponse::status(receipt),
+            gas_used: receipt.gas_used,
+            revert_reason: None,
+            l1_fee: None,
+        })
+    }
+}
+
+/// Total fees (in wei) paid by the transactions in a single block, as per
+/// their receipts
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct BlockFees {
+    pub block_hash: BlockHash,
+    pub block_number: BlockNumber,
+    /// Base fee burnt (i.e., base fee per gas times gas used)
+    pub burnt: U256,
+    /// Priority fees paid to the block's builder
+    pub priority_fees: U256,
+}
+
+impl BlockFees {
+    /// Sum the fees paid by each of the given transactions, paired with the
+    /// gas they used (as per their receipts), within the given block
+    pub fn compute<'a>(
+        header: &Header,
+        transactions: impl IntoIterator<Item = (&'a Transaction, u64)>,
+    ) -> Self {
+        let base_fee = header.base_fee_per_gas.unwrap_or_default();
+        let (burnt, priority_fees) = transactions.into_iter().fold(
+            (U256::ZERO, U256::ZERO),
+            |(burnt, priority_fees), (tx, gas_used)| {
+                let tip =
+                    tx.effective_tip_per_gas(base_fee).unwrap_or_default();
+                (
+                    burnt + U256::from(base_fee) * U256::from(gas_used),
+                    priority_fees + U256::from(tip) * U256::from(gas_used),
+                )
+            },
+        );
+        Self {
+            block_hash: header.hash,
+            block_number: header.number,
+            burnt,
+            priority_fees,
+        }
+    }
+}
+
+/// [`BlockFees`] summed across every canonical block indexed so far
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FeeTotals {
+    /// Range of the blocks whose fees are known (if any are)
+    pub blocks: Option<RangeInclusive<BlockNumber>>,
+    /// Number of blocks whose fees are known
+    pub count: usize,
+    pub burnt: U256,
+    pub priority_fees: U256,
+}
+
+impl FeeTotals {
+    /// Count the given [`BlockFees`] towards the totals
+    fn add(&mut self, fees: &BlockFees) {
+        let number = fees.block_number;
+        self.blocks = Some(match self.blocks.take() {
+            Some(blocks) => {
+                (*blocks.start()).min(number)..=(*blocks.end()).max(number)
+            }
+            None => number..=number,
+        });
+        self.count += 1;
+        self.burnt += fees.burnt;
+        self.priority_fees += fees.priority_fees;
+    }
+
+    /// Stop counting the given [`BlockFees`] towards the totals (leaving the
+    /// range of blocks as it was)
+    fn remove(&mut self, fees: &BlockFees) {
+        self.count = self.count.saturating_sub(1);
+        self.burnt = self.burnt.saturating_sub(fees.burnt);
+        self.priority_fees =
+            self.priority_fees.saturating_sub(fees.priority_fees);
+    }
+}
+
+/// Gas used by the transactions sent to a single address
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RecipientGas {
+    /// Recipient of the transactions ([`Address::ZERO`] for contract
+    /// creations)
+    pub to: Address,
+    pub gas_used: u64,
+    pub transactions: u64,
+}
+
+/// Gas used across a range of canonical blocks, broken down by recipient
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GasByRecipient {
+    /// Gas used by every transaction (with an indexed receipt) in the range
+    pub total_gas_used: u64,
+    /// The heaviest recipients, in descending order of gas used
+    pub recipients: Vec<RecipientGas>,
+}
+
+/// Reads and writes receipt statuses and block fees (see
+/// [`super::Database::receipts`])
+#[derive(Clone, Debug)]
+pub struct Receipts {
+    conn_pool: Arc<Pool<SqliteConnectionManager>>,
+}
+
+impl Receipts {
+    pub(super) fn new(conn_pool: Arc<Pool<SqliteConnectionManager>>) -> Self {
+        Self { conn_pool }
+    }
+
+    /// Retrieves the [`ReceiptStatus`] of each transaction in the block with
+    /// the given [`BlockHash`] (so far as they've been indexed)
+    pub fn statuses_by_block_hash(
+        &self,
+        hash: BlockHash,
+    ) -> Result<Vec<ReceiptStatus>> {
+        self.conn_pool
+            .get()?
+            .prepare("SELECT * FROM receipt_statuses WHERE block_hash = ?")?
+            .query_and_then([hash.to_string()], |row| {
+                Ok::<ReceiptStatus, DbError>(ReceiptStatus {
+                    block_hash: row
+                        .get::<&str, String>("block_hash")?
+                        .parse()?,
+                    transaction_hash: row
+                        .get::<&str, String>("transaction_hash")?
+                        .parse()?,
+                    success: row.get("success")?,
+                    gas_used: row.get("gas_used")?,
+                    revert_reason: row.get("revert_reason")?,
+                    l1_fee: row
+                        .get::<&str, Option<String>>("l1_fee")?
+                        .map(|fee| fee.parse())
+                        .transpose()?,
+                })
+            })?
+            .collect()
+    }
+
+    /// Write each [`ReceiptStatus`] to the database within a single SQLite
+    /// transaction
+    ///
+    /// The [`BlockFees`] of each block the statuses belong to are recomputed
+    /// as part of the same SQLite transaction.
+    pub fn add_statuses(&self, statuses: &[ReceiptStatus]) -> Result<()> {
+        let mut conn = self.conn_pool.get()?;
+        let tx = conn.transaction()?;
+        Self::write_statuses(&tx, statuses)?;
+        tx.commit()?;
+        debug!("Wrote {} receipt statuses to the database", statuses.len());
+        Ok(())
+    }
+
+    /// Write each [`ReceiptStatus`] (recomputing the [`BlockFees`] of each
+    /// block they belong to) using the given connection (e.g., within a
+    /// transaction)
+    pub(super) fn write_statuses(
+        conn: &rusqlite::Connection,
+        statuses: &[ReceiptStatus],
+    ) -> Result<()> {
+        let mut statement = conn.prepare(INSERT_RECEIPT_STATUS_SQL)?;
+        statuses.iter().try_for_each(|status| {
+            statement.execute(params![
+                status.block_hash.to_string(),
+                status.transaction_hash.to_string(),
+                status.success,
+                status.gas_used,
+                status.revert_reason,
+                status.l1_fee.map(|fee| fee.to_string()),
+            ])?;
+            Ok::<(), DbError>(())
+        })?;
+
+        let block_hashes: HashSet<BlockHash> =
+            statuses.iter().map(|status| status.block_hash).collect();
+        block_hashes
+            .into_iter()
+            .try_for_each(|hash| Self::update_block_fees(conn, hash))
+    }
+
+    /// Recompute the [`BlockFees`] of the block with the given [`BlockHash`]
+    /// from its indexed transactions and receipt statuses, updating the
+    /// running [`FeeTotals`] to match (if the block is canonical)
+    ///
+    /// Does nothing if the block itself hasn't been indexed.
+    fn update_block_fees(
+        conn: &rusqlite::Connection,
+        hash: BlockHash,
+    ) -> Result<()> {
+        let header = match conn
+            .prepare("SELECT * FROM block_headers WHERE hash = ?")?
+            .query_and_then([hash.to_string()], row_to_header)?
+            .next()
+            .transpose()?
+        {
+            Some(header) => header,
+            None => return Ok(()),
+        };
+
+        let mut stmt = conn.prepare(
+            "SELECT transactions.*, receipt_statuses.gas_used AS gas_used
+                FROM receipt_statuses JOIN transactions
+                ON transactions.hash = receipt_statuses.transaction_hash
+                AND transactions.block_hash = receipt_statuses.block_hash
+                WHERE receipt_statuses.block_hash = ?",
+        )?;
+        let transactions = stmt
+            .query_and_then([hash.to_string()], |row| {
+                Ok::<(Transaction, u64), DbError>((
+                    row_to_transaction(row)?,
+                    row.get("gas_used")?,
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        let fees = BlockFees::compute(
+            &header,
+            transactions.iter().map(|(tx, gas_used)| (tx, *gas_used)),
+        );
+
+        let canonical: bool = conn.query_row(
+            "SELECT orphaned = 0 FROM block_headers WHERE hash = ?",
+            [hash.to_string()],
+            |row| row.get(0),
+        )?;
+        if canonical {
+            /* the totals are read before the new fees are written, lest
+             * they're summed afresh to include them already */
+            let mut totals = Self::totals(conn)?;
+            if let Some(previous) = Self::fees_of(conn, hash)? {
+                totals.remove(&previous);
+            }
+            totals.add(&fees);
+            Self::store_totals(conn, &totals)?;
+        }
+
+        conn.execute(
+            "INSERT OR REPLACE INTO block_fees (
+                block_hash,
+                block_number,
+                burnt,
+                priority_fees
+            ) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                fees.block_hash.to_string(),
+                fees.block_number,
+                fees.burnt.to_string(),
+                fees.priority_fees.to_string(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Take the [`BlockFees`] of the (canonical, but about to be orphaned)
+    /// block with the given [`BlockHash`] back out of the running
+    /// [`FeeTotals`]
+    pub(super) fn take_back_fees(
+        conn: &rusqlite::Connection,
+        hash: BlockHash,
+    ) -> Result<()> {
+        let fees = match Self::fees_of(conn, hash)? {
+            Some(fees) => fees,
+            None => return Ok(()),
+        };
+        let mut totals = Self::totals(conn)?;
+        totals.remove(&fees);
+
+        /* the range only shrinks if the block was at either end of it */
+        totals.blocks = match totals.blocks.take() {
+            Some(blocks) if totals.count > 0 => {
+                let end = |order| {
+                    conn.query_row(
+                        &format!(
+                            "SELECT block_fees.block_number FROM block_fees
+                                JOIN block_headers
+                                ON block_headers.hash = block_fees.block_hash
+                                WHERE block_headers.orphaned = 0
+                                AND block_fees.block_hash != ?
+                                ORDER BY block_fees.block_number {order}
+                                LIMIT 1"
+                        ),
+                        [hash.to_string()],
+                        |row| row.get::<usize, BlockNumber>(0),
+                    )
+                };
+                let first = if *blocks.start() == fees.block_number {
+                    end("ASC")?
+                } else {
+                    *blocks.start()
+                };
+                let last = if *blocks.end() == fees.block_number {
+                    end("DESC")?
+                } else {
+                    *blocks.end()
+                };
+                Some(first..=last)
+            }
+            _ => None,
+        };
+        Self::store_totals(conn, &totals)
+    }
+
+    /// Retrieves the [`BlockFees`] of the block with the given [`BlockHash`]
+    /// (if its receipts have been indexed)
+    pub fn block_fees(&self, hash: BlockHash) -> Result<Option<BlockFees>> {
+        let conn = self.conn_pool.get()?;
+        Self::fees_of(&conn, hash)
+    }
+
+    fn fees_of(
+        conn: &rusqlite::Connection,
+        hash: BlockHash,
+    ) -> Result<Option<BlockFees>> {
+        conn.prepare("SELECT * FROM block_fees WHERE block_hash = ?")?
+            .query_and_then([hash.to_string()], row_to_block_fees)?
+            .next()
+            .transpose()
+    }
+
+    /// Retrieves the [`BlockFees`] of up to `limit` of the most recent
+    /// canonical blocks, in ascending order of block number
+    pub fn recent_block_fees(&self, limit: usize) -> Result<Vec<BlockFees>> {
+        let conn = self.conn_pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT block_fees.* FROM block_fees JOIN block_headers
+                ON block_headers.hash = block_fees.block_hash
+                WHERE block_headers.orphaned = 0
+                ORDER BY block_fees.block_number DESC LIMIT ?",
+        )?;
+        let mut fees = stmt
+            .query_and_then([limit], row_to_block_fees)?
+            .collect::<Result<Vec<BlockFees>>>()?;
+        fees.reverse();
+        Ok(fees)
+    }
+
+    /// Retrieves the [`BlockFees`] of every canonical block indexed so far,
+    /// as summed whilst they were written
+    pub fn fee_totals(&self) -> Result<FeeTotals> {
+        let conn = self.conn_pool.get()?;
+        Self::totals(&conn)
+    }
+
+    /// Retrieves the running [`FeeTotals`], summing them afresh should they
+    /// not have been kept yet (i.e., in a database indexed before they were)
+    fn totals(conn: &rusqlite::Connection) -> Result<FeeTotals> {
+        let kept = conn
+            .prepare("SELECT * FROM fee_totals")?
+            .query_and_then([], |row| {
+                Ok::<FeeTotals, DbError>(FeeTotals {
+                    blocks: match (
+                        row.get::<&str, Option<BlockNumber>>("first_block")?,
+                        row.get::<&str, Option<BlockNumber>>("last_block")?,
+                    ) {
+                        (Some(first), Some(last)) => Some(first..=last),
+                        _ => None,
+                    },
+                    count: row.get("blocks")?,
+                    burnt: row.get::<&str, String>("burnt")?.parse()?,
+                    priority_fees: row
+                        .get::<&str, String>("priority_fees")?
+                        .parse()?,
+                })
+            })?
+            .next()
+            .transpose()?;
+        if let Some(totals) = kept {
+            return Ok(totals);
+        }
+
+        conn.prepare(
+            "SELECT block_fees.* FROM block_fees JOIN block_headers
+                ON block_headers.hash = block_fees.block_hash
+                WHERE block_headers.orphaned = 0",
+        )?
+        .query_and_then([], row_to_block_fees)?
+        .try_fold(FeeTotals::default(), |mut totals, fees| {
+            totals.add(&fees?);
+            Ok(totals)
+        })
+    }
+
+    fn store_totals(
+        conn: &rusqlite::Connection,
+        totals: &FeeTotals,
+    ) -> Result<()> {
+        conn.execute(
+            "INSERT OR REPLACE INTO fee_totals (
+                id,
+                first_block,
+                last_block,
+                blocks,
+                burnt,
+                priority_fees
+            ) VALUES (0, ?1, ?2, ?3, ?4, ?5)",
+            params![
+                totals.blocks.as_ref().map(|blocks| *blocks.start()),
+                totals.blocks.as_ref().map(|blocks| *blocks.end()),
+                totals.count,
+                totals.burnt.to_string(),
+                totals.priority_fees.to_string(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Sums the gas used (as per indexed receipts) by the transactions of
+    /// the canonical blocks in the given range, retrieving up to `limit` of
+    /// the recipients that used the most
+    pub fn gas_by_recipient(
+        &self,
+        blocks: RangeInclusive<BlockNumber>,
+        limit: usize,
+    ) -> Result<GasByRecipient> {
+        const RECEIPTS: &str = "FROM block_headers
+            JOIN transactions
+                ON transactions.block_hash = block_headers.hash
+            JOIN receipt_statuses
+                ON receipt_statuses.block_hash = transactions.block_hash
+                AND receipt_statuses.transaction_hash = transactions.hash
+            WHERE block_headers.number BETWEEN ?1 AND ?2
+                AND block_headers.orphaned = 0";
+        let conn = self.conn_pool.get()?;
+        let range = (*blocks.start(), *blocks.end());
+        let total_gas_used = conn.query_row(
+            &format!(
+                "SELECT COALESCE(SUM(receipt_statuses.gas_used), 0) {RECEIPTS}"
+            ),
+            range,
+            |row| row.get(0),
+        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT transactions.to_address,
+                SUM(receipt_statuses.gas_used) AS gas_used,
+                COUNT(*) {RECEIPTS}
+                GROUP BY transactions.to_address
+                ORDER BY gas_used DESC, transactions.to_address LIMIT ?3"
+        ))?;
+        let recipients = stmt
+            .query_and_then((range.0, range.1, limit), |row| {
+                Ok::<RecipientGas, DbError>(RecipientGas {
+                    to: row.get::<usize, String>(0)?.parse()?,
+                    gas_used: row.get(1)?,
+                    transactions: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<RecipientGas>>>()?;
+        Ok(GasByRecipient {
+            total_gas_used,
+            recipients,
+        })
+    }
+}
+
+fn row_to_block_fees(row: &Row) -> Result<BlockFees> {
+    Ok(BlockFees {
+        block_hash: row.get::<&str, String>("block_hash")?.parse()?,
+        block_number: row.get("block_number")?,
+        burnt: row.get::<&str, String>("burnt")?.parse()?,
+        priority_fees: row.get::<&str, String>("priority_fees")?.parse()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::rpc::types::Block;
+
+    use super::*;
+    use crate::db::{test_transaction, Database, Location};
+
+    #[test]
+    fn test_receipt_statuses() {
+        let creation_result = Database::new(Location::Memory);
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        let status = |i: u8, revert_reason: Option<&str>| ReceiptStatus {
+            block_hash: BlockHash::with_last_byte(1),
+            transaction_hash: TxHash::with_last_byte(i),
+            success: revert_reason.is_none(),
+            gas_used: 21_000,
+            revert_reason: revert_reason.map(str::to_string),
+            l1_fee: Some(U256::from(i)),
+        };
+        let statuses = vec![
+            status(1, None),
+            status(2, Some("Ownable: caller is not the owner")),
+        ];
+        assert!(db.receipts().add_statuses(&statuses).is_ok());
+        assert!(db.receipts().add_statuses(&statuses).is_ok());
+        let retrieval_result = db
+            .receipts()
+            .statuses_by_block_hash(BlockHash::with_last_byte(1));
+        assert!(retrieval_result.is_ok());
+        assert_eq!(retrieval_result.unwrap(), statuses);
+        assert!(db
+            .receipts()
+            .statuses_by_block_hash(BlockHash::ZERO)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_block_fees() {
+        let mut header = Header::new(alloy::consensus::Header {
+            number: 1,
+            base_fee_per_gas: Some(10),
+            ..Default::default()
+        });
+        header.hash = BlockHash::with_last_byte(1);
+        let tx = |i: u8, max_priority_fee_per_gas| {
+            test_transaction(
+                TxHash::with_last_byte(i),
+                header.hash,
+                1,
+                i as u64 - 1,
+                |row| {
+                    row.max_fee_per_gas = 100;
+                    row.max_priority_fee_per_gas =
+                        Some(max_priority_fee_per_gas);
+                },
+            )
+        };
+        /* the second transaction's tip is capped by its max fee */
+        let block = Block::new(
+            header.clone(),
+            alloy::rpc::types::BlockTransactions::Full(vec![
+                tx(1, 3),
+                tx(2, 200),
+            ]),
+        );
+        let status = |i: u8, gas_used| ReceiptStatus {
+            block_hash: header.hash,
+            transaction_hash: TxHash::with_last_byte(i),
+            success: true,
+            gas_used,
+            revert_reason: None,
+            l1_fee: None,
+        };
+
+        let creation_result = Database::new(Location::Memory);
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        assert!(db.add_block_atomic(&block).is_ok());
+        assert_eq!(db.receipts().block_fees(header.hash).unwrap(), None);
+        assert_eq!(db.receipts().fee_totals().unwrap(), FeeTotals::default());
+
+        assert!(db.receipts().add_statuses(&[status(1, 21_000)]).is_ok());
+        assert!(db.receipts().add_statuses(&[status(2, 50_000)]).is_ok());
+        let fees = BlockFees {
+            block_hash: header.hash,
+            block_number: 1,
+            burnt: U256::from(710_000),
+            priority_fees: U256::from(3 * 21_000 + 90 * 50_000),
+        };
+        assert_eq!(db.receipts().block_fees(header.hash).unwrap(), Some(fees));
+        assert_eq!(db.receipts().recent_block_fees(10).unwrap(), vec![fees]);
+        assert_eq!(
+            db.receipts().fee_totals().unwrap(),
+            FeeTotals {
+                blocks: Some(1..=1),
+                count: 1,
+                burnt: fees.burnt,
+                priority_fees: fees.priority_fees,
+            }
+        );
+
+        assert!(db.headers().mark_orphaned(&[header.hash]).is_ok());
+        assert!(db.receipts().recent_block_fees(10).unwrap().is_empty());
+        assert_eq!(db.receipts().fee_totals().unwrap().count, 0);
+    }
+
+    #[test]
+    fn test_fee_totals() {
+        let block = |number: u64| {
+            let mut header = Header::new(alloy::consensus::Header {
+                number,
+                base_fee_per_gas: Some(10),
+                ..Default::default()
+            });
+            header.hash = BlockHash::with_last_byte(number as u8);
+            let tx = test_transaction(
+                TxHash::with_last_byte(number as u8),
+                header.hash,
+                number,
+                0,
+                |row| row.max_fee_per_gas = 100,
+            );
+            Block::new(
+                header,
+                alloy::rpc::types::BlockTransactions::Full(vec![tx]),
+            )
+        };
+        let status = |number: u64| ReceiptStatus {
+            block_hash: BlockHash::with_last_byte(number as u8),
+            transaction_hash: TxHash::with_last_byte(number as u8),
+            success: true,
+            gas_used: 1_000,
+            revert_reason: None,
+            l1_fee: None,
+        };
+        let totals = |blocks, count: usize| FeeTotals {
+            blocks,
+            count,
+            burnt: U256::from(10_000 * count),
+            priority_fees: U256::from(1_000 * count),
+        };
+
+        let db = Database::new(Location::Memory).unwrap();
+        for number in 1..=3 {
+            assert!(db.add_block_atomic(&block(number)).is_ok());
+            assert!(db.receipts().add_statuses(&[status(number)]).is_ok());
+        }
+        /* rewriting a block's statuses doesn't count its fees twice */
+        assert!(db.receipts().add_statuses(&[status(2)]).is_ok());
+        assert_eq!(db.receipts().fee_totals().unwrap(), totals(Some(1..=3), 3));
+
+        assert!(db
+            .headers()
+            .mark_orphaned(&[BlockHash::with_last_byte(3)])
+            .is_ok());
+        assert_eq!(db.receipts().fee_totals().unwrap(), totals(Some(1..=2), 2));
+        assert!(db
+            .headers()
+            .mark_orphaned(&[BlockHash::with_last_byte(1)])
+            .is_ok());
+        assert_eq!(db.receipts().fee_totals().unwrap(), totals(Some(2..=2), 1));
+
+        /* totals that weren't kept (e.g., by an older version) are summed
+         * afresh */
+        db.conn_pool
+            .get()
+            .unwrap()
+            .execute("DELETE FROM fee_totals", [])
+            .unwrap();
+        assert_eq!(db.receipts().fee_totals().unwrap(), totals(Some(2..=2), 1));
+        assert!(db.add_block_atomic(&block(4)).is_ok());
+        assert!(db.receipts().add_statuses(&[status(4)]).is_ok());
+        assert_eq!(db.receipts().fee_totals().unwrap(), totals(Some(2..=4), 2));
+
+        assert!(db
+            .headers()
+            .mark_orphaned(&[BlockHash::with_last_byte(2)])
+            .is_ok());
+        assert!(db
+            .headers()
+            .mark_orphaned(&[BlockHash::with_last_byte(4)])
+            .is_ok());
+        assert_eq!(db.receipts().fee_totals().unwrap(), FeeTotals::default());
+    }
+
+    #[test]
+    fn test_gas_by_recipient() {
+        let mut header = Header::new(alloy::consensus::Header {
+            number: 1,
+            ..Default::default()
+        });
+        header.hash = BlockHash::with_last_byte(1);
+        let (busy, quiet) = (Address::with_last_byte(2), Address::ZERO);
+        let tx = |i: u8, to: Address| {
+            test_transaction(
+                TxHash::with_last_byte(i),
+                header.hash,
+                1,
+                i as u64 - 1,
+                |row| row.to_address = to.to_string(),
+            )
+        };
+        let block = Block::new(
+            header.clone(),
+            alloy::rpc::types::BlockTransactions::Full(vec![
+                tx(1, busy),
+                tx(2, quiet),
+                tx(3, busy),
+                tx(4, busy),
+            ]),
+        );
+        let status = |i: u8, gas_used| ReceiptStatus {
+            block_hash: header.hash,
+            transaction_hash: TxHash::with_last_byte(i),
+            success: true,
+            gas_used,
+            revert_reason: None,
+            l1_fee: None,
+        };
+
+        let creation_result = Database::new(Location::Memory);
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        assert!(db.add_block_atomic(&block).is_ok());
+        assert_eq!(
+            db.receipts().gas_by_recipient(1..=1, 10).unwrap(),
+            GasByRecipient::default()
+        );
+
+        /* the fourth transaction's receipt is yet to be indexed */
+        assert!(db
+            .receipts()
+            .add_statuses(&[
+                status(1, 30_000),
+                status(2, 50_000),
+                status(3, 40_000),
+            ])
+            .is_ok());
+        assert_eq!(
+            db.receipts().gas_by_recipient(1..=1, 10).unwrap(),
+            GasByRecipient {
+                total_gas_used: 120_000,
+                recipients: vec![
+                    RecipientGas {
+                        to: busy,
+                        gas_used: 70_000,
+                        transactions: 2,
+                    },
+                    RecipientGas {
+                        to: quiet,
+                        gas_used: 50_000,
+                        transactions: 1,
+                    },
+                ],
+            }
+        );
+        assert_eq!(
+            db.receipts()
+                .gas_by_recipient(1..=1, 1)
+                .unwrap()
+                .recipients
+                .len(),
+            1
+        );
+        assert_eq!(
+            db.receipts().gas_by_recipient(2..=3, 10).unwrap(),
+            GasByRecipient::default()
+        );
+
+        assert!(db.headers().mark_orphaned(&[header.hash]).is_ok());
+        assert_eq!(
+            db.receipts().gas_by_recipient(1..=1, 10).unwrap(),
+            GasByRecipient::default()
+        );
+    }
+}
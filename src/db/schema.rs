@@ -0,0 +1,515 @@
+//! Schema of the SQLite database, and the tuning of its connections
+use std::{fmt, iter::zip};
+
+use log::info;
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{Error, Params};
+
+use super::{DbError, Result};
+
+const DEFAULT_CACHE_SIZE_KIB: u64 = 64 * 1024; /* 64 MiB */
+const DEFAULT_PAGE_SIZE: u64 = 4_096; /* 4 KiB */
+
+/// Schema migrations applied (in order) on top of the base schema
+///
+/// A database that has had the first `n` migrations applied has a
+/// `user_version` of `n`. Only ever append to this list.
+const MIGRATIONS: &[&str] = &[
+    /* 1: indexes on hot query columns */
+    "CREATE INDEX IF NOT EXISTS block_headers_hash ON block_headers(hash);
+    CREATE INDEX IF NOT EXISTS block_headers_number ON block_headers(number);
+    CREATE INDEX IF NOT EXISTS transactions_hash ON transactions(hash);
+    CREATE INDEX IF NOT EXISTS transactions_block_hash
+        ON transactions(block_hash);
+    CREATE INDEX IF NOT EXISTS transactions_from_address
+        ON transactions(from_address);
+    CREATE INDEX IF NOT EXISTS transactions_to_address
+        ON transactions(to_address);",
+    /* 2: deduplicate rows and enforce uniqueness going forward */
+    "DELETE FROM block_headers WHERE rowid NOT IN (
+        SELECT MIN(rowid) FROM block_headers GROUP BY hash
+    );
+    DELETE FROM transactions WHERE rowid NOT IN (
+        SELECT MIN(rowid) FROM transactions GROUP BY hash, block_hash
+    );
+    DROP INDEX IF EXISTS block_headers_hash;
+    CREATE UNIQUE INDEX block_headers_hash ON block_headers(hash);
+    CREATE UNIQUE INDEX transactions_hash_block_hash
+        ON transactions(hash, block_hash);",
+    /* 3: event logs from transaction receipts */
+    "CREATE TABLE IF NOT EXISTS logs (
+        inserted_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        block_hash TEXT NOT NULL,
+        block_number INTEGER NOT NULL,
+        transaction_hash TEXT NOT NULL,
+        transaction_index INTEGER NOT NULL,
+        log_index INTEGER NOT NULL,
+        address TEXT NOT NULL,
+        topic0 TEXT,
+        topic1 TEXT,
+        topic2 TEXT,
+        topic3 TEXT,
+        data TEXT NOT NULL
+    );
+    CREATE UNIQUE INDEX logs_block_hash_log_index
+        ON logs(block_hash, log_index);
+    CREATE INDEX logs_topic0_block_number ON logs(topic0, block_number);
+    CREATE INDEX logs_address ON logs(address);",
+    /* 4: per-block priority fee percentiles */
+    "CREATE TABLE IF NOT EXISTS fee_stats (
+        block_hash TEXT NOT NULL UNIQUE,
+        block_number INTEGER NOT NULL,
+        p10 INTEGER NOT NULL,
+        p50 INTEGER NOT NULL,
+        p90 INTEGER NOT NULL
+    );
+    CREATE INDEX fee_stats_block_number ON fee_stats(block_number);",
+    /* 5: pending block templates retrieved from a local node */
+    "CREATE TABLE IF NOT EXISTS template_transactions (
+        block_number INTEGER NOT NULL,
+        transaction_hash TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        UNIQUE(block_number, transaction_hash)
+    );",
+    /* 6: user-defined address labels */
+    "CREATE TABLE IF NOT EXISTS labels (
+        address TEXT PRIMARY KEY,
+        label TEXT NOT NULL
+    );",
+    /* 7: monotonic insertion order for disambiguating blocks at the same
+     * height */
+    "ALTER TABLE block_headers ADD COLUMN inserted_seq INTEGER;
+    UPDATE block_headers SET inserted_seq = rowid;
+    CREATE INDEX block_headers_number_inserted_seq
+        ON block_headers(number, inserted_seq);",
+    /* 8: blob base fee derived from each block's excess blob gas (left NULL
+     * for blocks indexed beforehand) */
+    "ALTER TABLE block_headers ADD COLUMN blob_base_fee INTEGER;",
+    /* 9: whether each transaction succeeded (and, if not, why) as per its
+     * receipt */
+    "CREATE TABLE IF NOT EXISTS receipt_statuses (
+        block_hash TEXT NOT NULL,
+        transaction_hash TEXT NOT NULL,
+        success INTEGER NOT NULL,
+        gas_used INTEGER NOT NULL,
+        revert_reason TEXT,
+        UNIQUE(block_hash, transaction_hash)
+    );",
+    /* 10: blocks that lost a reorg */
+    "ALTER TABLE block_headers ADD COLUMN orphaned INTEGER NOT NULL DEFAULT 0;
+    CREATE INDEX block_headers_orphaned_number
+        ON block_headers(number) WHERE orphaned = 1;",
+    /* 11: latest safe and finalized blocks (keyed by block tag) */
+    "CREATE TABLE IF NOT EXISTS finality (
+        tag TEXT PRIMARY KEY,
+        number INTEGER NOT NULL
+    );",
+    /* 12: slots, epochs, and proposers of the beacon blocks that carried
+     * each execution block */
+    "CREATE TABLE IF NOT EXISTS beacon_blocks (
+        block_hash TEXT PRIMARY KEY,
+        block_number INTEGER NOT NULL,
+        slot INTEGER NOT NULL,
+        epoch INTEGER NOT NULL,
+        proposer_index INTEGER NOT NULL
+    );
+    CREATE INDEX beacon_blocks_slot ON beacon_blocks(slot);
+    CREATE INDEX beacon_blocks_proposer_index
+        ON beacon_blocks(proposer_index);",
+    /* 13: verified source code (and ABIs) of contracts, as retrieved from
+     * Sourcify or Etherscan */
+    "CREATE TABLE IF NOT EXISTS contract_sources (
+        address TEXT PRIMARY KEY,
+        verifier TEXT NOT NULL,
+        name TEXT NOT NULL,
+        language TEXT NOT NULL,
+        compiler_version TEXT NOT NULL,
+        exact_match INTEGER,
+        abi TEXT NOT NULL,
+        sources TEXT NOT NULL
+    );",
+    /* 14: addresses on the watch list, and their balances and nonces as of
+     * each block indexed whilst they were watched */
+    "CREATE TABLE IF NOT EXISTS watched_addresses (
+        address TEXT PRIMARY KEY
+    );
+    CREATE TABLE IF NOT EXISTS account_snapshots (
+        address TEXT NOT NULL,
+        block_number INTEGER NOT NULL,
+        balance TEXT NOT NULL,
+        nonce INTEGER NOT NULL,
+        UNIQUE(address, block_number)
+    );",
+    /* 15: blocks, transactions, and addresses bookmarked from the TUI */
+    "CREATE TABLE IF NOT EXISTS bookmarks (
+        kind TEXT NOT NULL,
+        target TEXT NOT NULL,
+        block_number INTEGER,
+        note TEXT,
+        created_at INTEGER NOT NULL,
+        UNIQUE(kind, target)
+    );",
+    /* 16: base fee burnt and priority fees paid by each block (in wei), as
+     * per its indexed receipts */
+    "CREATE TABLE IF NOT EXISTS block_fees (
+        block_hash TEXT NOT NULL UNIQUE,
+        block_number INTEGER NOT NULL,
+        burnt TEXT NOT NULL,
+        priority_fees TEXT NOT NULL
+    );
+    CREATE INDEX block_fees_block_number ON block_fees(block_number);",
+    /* 17: per-hour totals over canonical blocks (by block timestamp),
+     * backfilled from whatever's already been indexed */
+    "CREATE TABLE IF NOT EXISTS hourly_stats (
+        hour INTEGER PRIMARY KEY,
+        blocks INTEGER NOT NULL,
+        gas_used INTEGER NOT NULL,
+        base_fee INTEGER NOT NULL,
+        transactions INTEGER NOT NULL
+    );
+    INSERT INTO hourly_stats (hour, blocks, gas_used, base_fee, transactions)
+        SELECT
+            timestamp / 3600 * 3600,
+            COUNT(*),
+            SUM(gas_used),
+            SUM(COALESCE(base_fee_per_gas, 0)),
+            SUM((SELECT COUNT(*) FROM transactions
+                WHERE transactions.block_hash = block_headers.hash))
+        FROM block_headers WHERE orphaned = 0 GROUP BY 1;",
+    /* 18: facts about the indexed chain as a whole (e.g., its genesis hash),
+     * for noticing should the RPC node switch to a different chain */
+    "CREATE TABLE IF NOT EXISTS chain_info (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );",
+    /* 19: what's peculiar to rollups: the L1 block that each L2 block was
+     * derived from, OP Stack deposit transactions, and the fee that each
+     * transaction paid for posting it to L1 (in wei) */
+    "CREATE TABLE IF NOT EXISTS l1_origins (
+        block_hash TEXT PRIMARY KEY,
+        l1_number INTEGER NOT NULL,
+        l1_hash TEXT,
+        l1_timestamp INTEGER,
+        sequence_number INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS deposit_transactions (
+        hash TEXT NOT NULL,
+        block_hash TEXT NOT NULL,
+        block_number INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        source_hash TEXT NOT NULL,
+        from_address TEXT NOT NULL,
+        to_address TEXT,
+        mint TEXT NOT NULL,
+        value TEXT NOT NULL,
+        gas_limit INTEGER NOT NULL,
+        is_system_tx INTEGER NOT NULL,
+        input TEXT NOT NULL,
+        PRIMARY KEY (hash, block_hash)
+    );
+    CREATE INDEX IF NOT EXISTS deposit_transactions_block_hash
+        ON deposit_transactions(block_hash);
+    ALTER TABLE receipt_statuses ADD COLUMN l1_fee TEXT;",
+    /* 20: transactions of types that nothing here knows, kept with their
+     * EIP-2718 type and (as JSON) every other field served for them */
+    "CREATE TABLE IF NOT EXISTS other_transactions (
+        hash TEXT NOT NULL,
+        block_hash TEXT NOT NULL,
+        block_number INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        type INTEGER NOT NULL,
+        from_address TEXT NOT NULL,
+        fields TEXT NOT NULL,
+        PRIMARY KEY (hash, block_hash)
+    );
+    CREATE INDEX IF NOT EXISTS other_transactions_block_hash
+        ON other_transactions(block_hash);",
+    /* 21: canonical (or else orphaned) blocks in order of number */
+    "CREATE INDEX IF NOT EXISTS block_headers_orphaned_number_inserted_seq
+        ON block_headers(orphaned, number, inserted_seq);",
+    /* 22: running totals of the fees paid by canonical blocks (in wei), kept
+     * as a single row that's summed afresh from block_fees whilst missing */
+    "CREATE TABLE IF NOT EXISTS fee_totals (
+        id INTEGER PRIMARY KEY CHECK (id = 0),
+        first_block INTEGER,
+        last_block INTEGER,
+        blocks INTEGER NOT NULL,
+        burnt TEXT NOT NULL,
+        priority_fees TEXT NOT NULL
+    );",
+    /* 23: the transactions whose logs mention each address (as emitter or
+     * indexed event argument), backfilled from whatever's already been
+     * indexed */
+    "CREATE TABLE IF NOT EXISTS log_addresses (
+        address TEXT NOT NULL,
+        block_number INTEGER NOT NULL,
+        block_hash TEXT NOT NULL,
+        transaction_hash TEXT NOT NULL,
+        UNIQUE(address, block_hash, transaction_hash)
+    );
+    CREATE INDEX log_addresses_address_block_number
+        ON log_addresses(address, block_number);
+    INSERT OR IGNORE INTO log_addresses
+        SELECT lower(address), block_number, block_hash, transaction_hash
+        FROM logs;
+    INSERT OR IGNORE INTO log_addresses
+        SELECT '0x' || substr(lower(topic1), 27), block_number, block_hash,
+            transaction_hash
+        FROM logs WHERE topic1 LIKE '0x000000000000000000000000%';
+    INSERT OR IGNORE INTO log_addresses
+        SELECT '0x' || substr(lower(topic2), 27), block_number, block_hash,
+            transaction_hash
+        FROM logs WHERE topic2 LIKE '0x000000000000000000000000%';
+    INSERT OR IGNORE INTO log_addresses
+        SELECT '0x' || substr(lower(topic3), 27), block_number, block_hash,
+            transaction_hash
+        FROM logs WHERE topic3 LIKE '0x000000000000000000000000%';",
+];
+
+/// SQLite journaling mode (see <https://sqlite.org/pragma.html#pragma_journal_mode>)
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum JournalMode {
+    Delete,
+    Truncate,
+    Persist,
+    Memory,
+    #[default]
+    Wal,
+    Off,
+}
+
+impl fmt::Display for JournalMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Delete => write!(f, "DELETE"),
+            Self::Truncate => write!(f, "TRUNCATE"),
+            Self::Persist => write!(f, "PERSIST"),
+            Self::Memory => write!(f, "MEMORY"),
+            Self::Wal => write!(f, "WAL"),
+            Self::Off => write!(f, "OFF"),
+        }
+    }
+}
+
+/// SQLite synchronisation level (see <https://sqlite.org/pragma.html#pragma_synchronous>)
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum Synchronous {
+    Off,
+    #[default]
+    Normal,
+    Full,
+    Extra,
+}
+
+impl fmt::Display for Synchronous {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Off => write!(f, "OFF"),
+            Self::Normal => write!(f, "NORMAL"),
+            Self::Full => write!(f, "FULL"),
+            Self::Extra => write!(f, "EXTRA"),
+        }
+    }
+}
+
+/// Connection tuning applied to on-disk [`Database`](super::Database)s
+///
+/// In-memory databases ignore these settings entirely.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Pragmas {
+    pub journal_mode: JournalMode,
+    pub synchronous: Synchronous,
+    /// Page cache size per connection, in KiB
+    pub cache_size_kib: u64,
+    /// Page size in bytes (only takes effect when the file is first created)
+    pub page_size: u64,
+}
+
+impl Default for Pragmas {
+    fn default() -> Self {
+        Self {
+            journal_mode: JournalMode::default(),
+            synchronous: Synchronous::default(),
+            cache_size_kib: DEFAULT_CACHE_SIZE_KIB,
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+}
+
+impl Pragmas {
+    pub(super) fn apply(
+        &self,
+        conn: &rusqlite::Connection,
+    ) -> Result<(), Error> {
+        conn.pragma_update(None, "page_size", self.page_size)?;
+        conn.pragma_update(
+            None,
+            "journal_mode",
+            self.journal_mode.to_string(),
+        )?;
+        conn.pragma_update(None, "synchronous", self.synchronous.to_string())?;
+        /* negative values are interpreted by SQLite as KiB rather than pages */
+        conn.pragma_update(None, "cache_size", -(self.cache_size_kib as i64))?;
+        Ok(())
+    }
+}
+
+/// Create the base schema (if it doesn't already exist) and apply any
+/// outstanding [`MIGRATIONS`]
+pub(super) fn initialise(
+    conn_pool: &Pool<SqliteConnectionManager>,
+) -> Result<()> {
+    transact_many(
+        conn_pool,
+        vec![
+            "CREATE TABLE IF NOT EXISTS block_headers (
+        inserted_at TIMESTAMP,
+        hash STRING,
+        number INTEGER,
+        parent_hash STRING,
+        ommers_hash STRING,
+        beneficiary STRING,
+        state_root STRING,
+        transactions_root STRING,
+        receipts_root STRING,
+        logs_bloom STRING,
+        difficulty INTEGER,
+        gas_limit INTEGER,
+        gas_used INTEGER,
+        timestamp TIMESTAMP,
+        extra_data BLOB,
+        mix_hash STRING,
+        nonce INTEGER,
+        base_fee_per_gas INTEGER,
+        withdrawals_root STRING,
+        blob_gas_used INTEGER,
+        excess_blob_gas INTEGER,
+        parent_beacon_block_root STRING,
+        requests_hash INTEGER
+    )"
+            .to_string(),
+            "CREATE TABLE IF NOT EXISTS transactions (
+            hash TEXT,
+            block_hash TEXT,
+            block_number INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            from_address TEXT,
+            type INTEGER NOT NULL,
+
+            -- Legacy
+            chain_id INTEGER,
+            nonce INTEGER,
+            gas_price INTEGER,
+            gas_limit INTEGER,
+            to_address TEXT,
+            value TEXT,
+            input BLOB,
+
+            -- EIP-1559
+            max_fee_per_gas INTEGER,
+            max_priority_fee_per_gas INTEGER
+        )"
+            .to_string(),
+        ],
+        vec![(), ()],
+    )?;
+    migrate(conn_pool)
+}
+
+/// Apply any [`MIGRATIONS`] that haven't yet been applied to this database
+///
+/// Progress is tracked via SQLite's `user_version` pragma, so this is
+/// idempotent.
+fn migrate(conn_pool: &Pool<SqliteConnectionManager>) -> Result<()> {
+    let mut conn = conn_pool.get()?;
+    let version: usize =
+        conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+        info!("Applied database migration #{}", i + 1);
+    }
+
+    Ok(())
+}
+
+fn transact_many<P>(
+    conn_pool: &Pool<SqliteConnectionManager>,
+    sqls: Vec<String>,
+    params: Vec<P>,
+) -> Result<()>
+where
+    P: Params,
+{
+    let mut conn = conn_pool.get()?;
+    let tx = conn.transaction()?;
+    {
+        zip(sqls, params).try_for_each(|(st, px)| {
+            let mut statement = tx.prepare(&st)?;
+            statement.execute(px)?;
+            Ok::<(), DbError>(())
+        })?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Database, Location};
+
+    #[test]
+    fn test_disk_pragmas() {
+        let path = std::env::temp_dir()
+            .join(format!("blocktop-test-pragmas-{}.db", std::process::id()));
+        let creation_result = Database::with_pragmas(
+            Location::Disk(path.clone()),
+            Pragmas::default(),
+        );
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        let conn = db.conn_pool.get().unwrap();
+        let journal_mode: String = conn
+            .pragma_query_value(None, "journal_mode", |row| row.get(0))
+            .unwrap();
+        assert_eq!(journal_mode, "wal");
+        drop(conn);
+        drop(db);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_migrations() {
+        let creation_result = Database::new(Location::Memory);
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        let conn = db.conn_pool.get().unwrap();
+        let version: usize = conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+        let plan: String = conn
+            .query_row(
+                "EXPLAIN QUERY PLAN SELECT * FROM transactions WHERE hash = ?",
+                ["0x00"],
+                |row| row.get(3),
+            )
+            .unwrap();
+        assert!(plan.contains("transactions_hash"));
+        let plan: String = conn
+            .query_row(
+                "EXPLAIN QUERY PLAN SELECT * FROM block_headers
+                    WHERE orphaned = 0 AND TRUE
+                    ORDER BY number DESC, inserted_seq DESC LIMIT 1",
+                [],
+                |row| row.get(3),
+            )
+            .unwrap();
+        assert!(plan.contains("block_headers_orphaned_number_inserted_seq"));
+    }
+}
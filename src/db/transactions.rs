@@ -0,0 +1,1096 @@
+//! Transactions, as included in their blocks
+use std::{fmt, ops::RangeInclusive, str::FromStr, sync::Arc};
+
+use alloy::{
+    consensus::{
+        transaction::Recovered, Signed, Transaction as TraitTransaction,
+        TxEip1559, TxEip2930, TxEip4844, TxEip4844Variant, TxEip7702,
+        TxEnvelope, TxLegacy,
+    },
+    hex::{FromHex, FromHexError},
+    primitives::{
+        Address, BlockHash, BlockNumber, Bytes, Selector, Signature, TxHash,
+        TxKind, U256,
+    },
+    rpc::types::Transaction,
+};
+use log::{debug, warn};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{functions::FunctionFlags, params, Row, Statement};
+
+use super::{headers::Headers, DbError, Result, SECONDS_PER_DAY};
+
+pub(super) const INSERT_TRANSACTION_SQL: &str =
+    "INSERT OR IGNORE INTO transactions (
+        hash,
+        block_hash,
+        block_number,
+        position,
+        from_address,
+        type,
+        chain_id,
+        nonce,
+        gas_price,
+        gas_limit,
+        to_address,
+        value,
+        input,
+        max_fee_per_gas,
+        max_priority_fee_per_gas
+    ) VALUES(
+        ?1,
+        ?2,
+        ?3,
+        ?4,
+        ?5,
+        ?6,
+        ?7,
+        ?8,
+        ?9,
+        ?10,
+        ?11,
+        ?12,
+        ?13,
+        ?14,
+        ?15
+    )";
+
+/// Byte pattern matched against transaction input data
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CalldataPattern {
+    /// Input data begins with the given bytes (e.g., a function selector)
+    Prefix(Bytes),
+    /// Input data contains the given bytes at any offset
+    Contains(Bytes),
+}
+
+impl CalldataPattern {
+    /// The raw bytes being searched for
+    pub fn bytes(&self) -> &Bytes {
+        match self {
+            Self::Prefix(bytes) | Self::Contains(bytes) => bytes,
+        }
+    }
+
+    /// Whether the given input data matches this pattern
+    pub fn matches(&self, input: &Bytes) -> bool {
+        match self {
+            Self::Prefix(bytes) => input.starts_with(bytes),
+            Self::Contains(bytes) if bytes.is_empty() => true,
+            Self::Contains(bytes) => input
+                .windows(bytes.len())
+                .any(|window| window == bytes.as_ref()),
+        }
+    }
+}
+
+impl fmt::Display for CalldataPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Prefix(bytes) => write!(f, "^{bytes}"),
+            Self::Contains(bytes) => write!(f, "{bytes}"),
+        }
+    }
+}
+
+impl FromStr for CalldataPattern {
+    type Err = FromHexError;
+
+    /// Parses hexadecimal strings (with or without a `0x` prefix), treating a
+    /// leading `^` as anchoring the pattern to the start of the input data
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.strip_prefix('^') {
+            Some(rest) => Ok(Self::Prefix(Bytes::from_hex(rest)?)),
+            None => Ok(Self::Contains(Bytes::from_hex(s)?)),
+        }
+    }
+}
+
+/// Usage of a single function selector across a range of canonical blocks
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SelectorStats {
+    /// Leading four bytes of the calldata (or `None` for transactions with
+    /// less calldata than that, such as plain transfers)
+    pub selector: Option<Selector>,
+    pub transactions: u64,
+    /// Gas used by those transactions whose receipts have been indexed
+    pub gas_used: u64,
+}
+
+/// Period over which an address's activity is totalled
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ActivityBucket {
+    Block,
+    Day,
+}
+
+/// Transactions sent from or to a single address within a single
+/// [`ActivityBucket`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Activity {
+    /// Block number, or start of the day (as a UNIX timestamp)
+    pub bucket: u64,
+    pub transactions: u64,
+    /// Ether (in wei) moved by those transactions
+    pub value: U256,
+}
+
+/// Flattened representation of a [`Transaction`] as stored by each storage
+/// backend
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TransactionRow {
+    pub hash: String,
+    pub block_hash: String,
+    pub block_number: u64,
+    pub position: u64,
+    pub from_address: String,
+    pub tx_type: u64,
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub gas_price: u64,
+    pub gas_limit: u64,
+    /// The zero address for contract creations
+    pub to_address: String,
+    /// Decimal representation of the value transferred (in wei)
+    pub value: String,
+    /// Hexadecimal representation of the input data
+    pub input: String,
+    pub max_fee_per_gas: u64,
+    pub max_priority_fee_per_gas: Option<u64>,
+}
+
+impl TransactionRow {
+    /// Flatten a (mined) [`Transaction`]
+    ///
+    /// Fails if the transaction is missing any information about its
+    /// enclosing block.
+    pub fn from_transaction(transaction: &Transaction) -> Result<Self> {
+        let tx_info = transaction.info();
+
+        let to = match &transaction.inner.inner() {
+            TxEnvelope::Legacy(t) => match t.tx().to {
+                TxKind::Create => Address::ZERO,
+                TxKind::Call(a) => a,
+            },
+            TxEnvelope::Eip2930(t) => match t.tx().to {
+                TxKind::Create => Address::ZERO,
+                TxKind::Call(a) => a,
+            },
+            TxEnvelope::Eip1559(t) => match t.tx().to {
+                TxKind::Create => Address::ZERO,
+                TxKind::Call(a) => a,
+            },
+            TxEnvelope::Eip4844(t) => match t.tx() {
+                TxEip4844Variant::TxEip4844(tx_eip4844) => tx_eip4844.to,
+                TxEip4844Variant::TxEip4844WithSidecar(
+                    tx_eip4844_with_sidecar,
+                ) => tx_eip4844_with_sidecar.tx.to,
+            },
+            TxEnvelope::Eip7702(t) => t.tx().to,
+        };
+        let tx_type: u8 = transaction.inner.tx_type().into();
+
+        let (Some(hash), Some(block_hash), Some(block_number), Some(index)) = (
+            tx_info.hash,
+            tx_info.block_hash,
+            tx_info.block_number,
+            tx_info.index,
+        ) else {
+            return Err(DbError::Decode(
+                "transaction is missing its enclosing block".into(),
+            ));
+        };
+
+        Ok(Self {
+            hash: hash.to_string(),
+            block_hash: block_hash.to_string(),
+            block_number,
+            position: index,
+            from_address: transaction.inner.signer().to_string(),
+            tx_type: tx_type.into(),
+            chain_id: transaction.chain_id().unwrap_or(1),
+            nonce: transaction.nonce(),
+            gas_price: transaction.gas_price().unwrap_or_default() as u64,
+            gas_limit: transaction.gas_limit(),
+            to_address: to.to_string(),
+            value: transaction.value().to_string(),
+            input: transaction.input().to_string(),
+            max_fee_per_gas: transaction.max_fee_per_gas() as u64,
+            max_priority_fee_per_gas: transaction
+                .max_priority_fee_per_gas()
+                .map(|x| x as u64),
+        })
+    }
+
+    /// Reconstruct the [`Transaction`] this row represents
+    ///
+    /// Note that signatures aren't stored, so the returned transaction
+    /// carries a placeholder signature.
+    pub fn into_transaction(self) -> Result<Transaction> {
+        let hash = self.hash.parse()?;
+        let chain_id = self.chain_id;
+        let nonce = self.nonce;
+        let gas_price = self.gas_price;
+        let gas_limit = self.gas_limit;
+        let to: Address = self.to_address.parse()?;
+        let value: U256 = self.value.parse()?;
+        let input: Bytes = Bytes::from_hex(&self.input)?;
+        let max_fee_per_gas = self.max_fee_per_gas;
+        let tx_type = self.tx_type;
+        /* only legacy and EIP-2930 transactions lack one */
+        let max_priority_fee_per_gas = || {
+            self.max_priority_fee_per_gas.ok_or_else(|| {
+                DbError::Decode(format!(
+                    "type {tx_type} transaction {hash} has no max priority \
+                    fee per gas"
+                ))
+            })
+        };
+
+        let envelope: TxEnvelope = match tx_type {
+            0 => TxEnvelope::Legacy(Signed::new_unchecked(
+                TxLegacy {
+                    chain_id: Some(chain_id),
+                    nonce,
+                    gas_price: gas_price.into(),
+                    gas_limit,
+                    to: match to {
+                        Address::ZERO => TxKind::Create,
+                        t => TxKind::Call(t),
+                    },
+                    value,
+                    input,
+                },
+                Signature::test_signature(),
+                hash,
+            )),
+            1 => TxEnvelope::Eip2930(Signed::new_unchecked(
+                TxEip2930 {
+                    chain_id,
+                    nonce,
+                    gas_price: gas_price.into(),
+                    gas_limit,
+                    to: match to {
+                        Address::ZERO => TxKind::Create,
+                        t => TxKind::Call(t),
+                    },
+                    value,
+                    access_list: vec![].into(), /* TODO(jmcph4): support access lists */
+                    input,
+                },
+                Signature::test_signature(),
+                hash,
+            )),
+            2 => TxEnvelope::Eip1559(Signed::new_unchecked(
+                TxEip1559 {
+                    chain_id,
+                    nonce,
+                    gas_limit,
+                    max_fee_per_gas: max_fee_per_gas.into(),
+                    max_priority_fee_per_gas: max_priority_fee_per_gas()?
+                        .into(),
+                    to: match to {
+                        Address::ZERO => TxKind::Create,
+                        t => TxKind::Call(t),
+                    },
+                    value,
+                    access_list: vec![].into(), /* TODO(jmcph4): support access lists */
+                    input,
+                },
+                Signature::test_signature(),
+                hash,
+            )),
+            3 => TxEnvelope::Eip4844(Signed::new_unchecked(
+                TxEip4844Variant::TxEip4844(TxEip4844 {
+                    chain_id,
+                    nonce,
+                    gas_limit,
+                    max_fee_per_gas: max_fee_per_gas.into(),
+                    max_priority_fee_per_gas: max_priority_fee_per_gas()?
+                        .into(),
+                    to,
+                    value,
+                    access_list: vec![].into(), /* TODO(jmcph4): support access lists */
+                    blob_versioned_hashes: vec![],
+                    max_fee_per_blob_gas: 0,
+                    input,
+                }),
+                Signature::test_signature(),
+                hash,
+            )),
+            4 => TxEnvelope::Eip7702(Signed::new_unchecked(
+                TxEip7702 {
+                    chain_id,
+                    nonce,
+                    gas_limit,
+                    max_fee_per_gas: max_fee_per_gas.into(),
+                    max_priority_fee_per_gas: max_priority_fee_per_gas()?
+                        .into(),
+                    to,
+                    value,
+                    access_list: vec![].into(), /* TODO(jmcph4): support access lists */
+                    authorization_list: vec![], /* TODO(jmcph4): support auth lists */
+                    input,
+                },
+                Signature::test_signature(),
+                hash,
+            )),
+            other => {
+                return Err(DbError::Unsupported(format!(
+                    "EIP-2718 transaction type {other}"
+                )))
+            }
+        };
+
+        Ok(Transaction {
+            inner: Recovered::new_unchecked(
+                envelope,
+                self.from_address.parse()?,
+            ),
+            block_hash: Some(self.block_hash.parse()?),
+            block_number: Some(self.block_number),
+            transaction_index: Some(self.position),
+            effective_gas_price: None, /* deprecated */
+        })
+    }
+}
+
+/// Reads and writes transactions (see [`super::Database::transactions`])
+#[derive(Clone, Debug)]
+pub struct Transactions {
+    conn_pool: Arc<Pool<SqliteConnectionManager>>,
+}
+
+impl Transactions {
+    pub(super) fn new(conn_pool: Arc<Pool<SqliteConnectionManager>>) -> Self {
+        Self { conn_pool }
+    }
+
+    /// Retrieves the transaction with the associated hash (if it exists)
+    pub fn by_hash(&self, hash: TxHash) -> Result<Option<Transaction>> {
+        debug!("Transaction {} requested from database...", hash);
+        match self.conn_pool.get()?.query_row(
+            "SELECT * FROM transactions WHERE hash = ?",
+            [hash.to_string()],
+            |row| Ok(row_to_transaction(row)),
+        ) {
+            Ok(t) => Ok(Some(t?)),
+            Err(e) => match e {
+                rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                _ => Err(e.into()),
+            },
+        }
+    }
+
+    /// Counts the [`Transaction`]s associated with the [`Block`] with the
+    /// given [`BlockHash`]
+    pub fn count_by_block_hash(&self, hash: BlockHash) -> Result<usize> {
+        Ok(self.conn_pool.get()?.query_row(
+            "SELECT COUNT(*) FROM transactions WHERE block_hash = ?",
+            [hash.to_string()],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Retrieves all of the [`Transaction`]s associated with the [`Block`]
+    /// with the given [`BlockHash`]
+    ///
+    /// If there are no such transactions in the database, the returned vector
+    /// is guaranteed to have a length of zero. Transactions of types that
+    /// can't be reconstructed are left out.
+    #[allow(clippy::let_and_return)] /* clippy gets this wrong */
+    pub fn by_block_hash(&self, hash: BlockHash) -> Result<Vec<Transaction>> {
+        let conn = self.conn_pool.get()?;
+        let mut stmt =
+            conn.prepare("SELECT * FROM transactions WHERE block_hash = ?")?;
+        let txs = collect_known(
+            stmt.query_and_then([hash.to_string()], row_to_transaction)?,
+        );
+        txs
+    }
+
+    /// Retrieves all of the [`Transaction`]s associated with the [`Block`]
+    /// with the given [`BlockNumber`]
+    ///
+    /// If there are no such transactions in the database, the returned vector
+    /// is guaranteed to have a length of zero. Transactions of types that
+    /// can't be reconstructed are left out.
+    #[allow(clippy::let_and_return)] /* clippy gets this wrong */
+    pub fn by_block_number(
+        &self,
+        number: BlockNumber,
+    ) -> Result<Vec<Transaction>> {
+        let hash = Headers::new(self.conn_pool.clone())
+            .by_number(number)?
+            .ok_or(DbError::NotFound("block"))?
+            .hash;
+        let conn = self.conn_pool.get()?;
+        let mut stmt =
+            conn.prepare("SELECT * FROM transactions WHERE block_hash = ?")?;
+        let txs = collect_known(
+            stmt.query_and_then([hash.to_string()], row_to_transaction)?,
+        );
+        txs
+    }
+
+    /// Retrieves up to `limit` of the most recent [`Transaction`]s whose
+    /// input data matches the given [`CalldataPattern`]
+    pub fn by_input_pattern(
+        &self,
+        pattern: &CalldataPattern,
+        limit: usize,
+    ) -> Result<Vec<Transaction>> {
+        debug!(
+            "Transactions matching calldata pattern {:?} requested from database...",
+            pattern
+        );
+        let needle = alloy::hex::encode(pattern.bytes());
+        let (sql, param) = match pattern {
+            CalldataPattern::Prefix(_) => (
+                "SELECT * FROM transactions WHERE input LIKE ?1
+                    ORDER BY block_number DESC, position ASC LIMIT ?2",
+                format!("0x{needle}%"),
+            ),
+            CalldataPattern::Contains(_) => (
+                "SELECT * FROM transactions WHERE calldata_contains(input, ?1)
+                    ORDER BY block_number DESC, position ASC LIMIT ?2",
+                needle,
+            ),
+        };
+        self.conn_pool
+            .get()?
+            .prepare(sql)?
+            .query_and_then(params![param, limit], row_to_transaction)?
+            .collect()
+    }
+
+    /// Define the SQL functions that queries of transactions rely on upon the
+    /// given connection
+    ///
+    /// `calldata_contains(input, needle)` is whether the given hex-encoded
+    /// (`0x`-prefixed) input data contains the given hex-encoded bytes,
+    /// unlike a plain string search, only matching whole bytes.
+    pub(super) fn register_functions(
+        conn: &rusqlite::Connection,
+    ) -> rusqlite::Result<()> {
+        conn.create_scalar_function(
+            "calldata_contains",
+            2,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| {
+                let input = ctx.get_raw(0).as_bytes().unwrap_or_default();
+                let needle = ctx.get_raw(1).as_bytes().unwrap_or_default();
+                let hex = input.strip_prefix(b"0x").unwrap_or(input);
+                Ok(needle.is_empty()
+                    || (0..hex.len())
+                        .step_by(2)
+                        .any(|i| hex[i..].starts_with(needle)))
+            },
+        )
+    }
+
+    /// Retrieves up to `limit` of the most recent [`Transaction`]s involving
+    /// the given [`Address`], whether as sender, recipient, or within the
+    /// event logs they emitted
+    ///
+    /// Event logs are found via the addresses recorded for them as they're
+    /// indexed, so no logs are ever scanned.
+    pub fn account_history(
+        &self,
+        address: Address,
+        limit: usize,
+    ) -> Result<Vec<Transaction>> {
+        debug!("History of account {address} requested from database...");
+        let conn = self.conn_pool.get()?;
+        let txs = conn
+            .prepare(
+                "SELECT * FROM transactions WHERE from_address = ?1
+                UNION SELECT * FROM transactions WHERE to_address = ?1
+                UNION SELECT transactions.* FROM log_addresses
+                    JOIN transactions
+                        ON transactions.hash = log_addresses.transaction_hash
+                        AND transactions.block_hash = log_addresses.block_hash
+                    WHERE log_addresses.address = lower(?1)
+                ORDER BY block_number DESC, position ASC LIMIT ?2",
+            )?
+            .query_and_then(
+                params![address.to_string(), limit],
+                row_to_transaction,
+            )?
+            .collect::<Result<Vec<Transaction>>>()?;
+        Ok(txs)
+    }
+
+    /// Counts the transactions of the canonical blocks in the given range by
+    /// function selector, along with the gas they used (as per indexed
+    /// receipts), retrieving up to `limit` of the most called selectors
+    pub fn selector_stats(
+        &self,
+        blocks: RangeInclusive<BlockNumber>,
+        limit: usize,
+    ) -> Result<Vec<SelectorStats>> {
+        let conn = self.conn_pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT CASE WHEN length(transactions.input) >= 10
+                    THEN lower(substr(transactions.input, 1, 10)) END
+                    AS selector,
+                COUNT(*) AS count,
+                COALESCE(SUM(receipt_statuses.gas_used), 0)
+            FROM block_headers
+            JOIN transactions
+                ON transactions.block_hash = block_headers.hash
+            LEFT JOIN receipt_statuses
+                ON receipt_statuses.block_hash = transactions.block_hash
+                AND receipt_statuses.transaction_hash = transactions.hash
+            WHERE block_headers.number BETWEEN ?1 AND ?2
+                AND block_headers.orphaned = 0
+            GROUP BY selector
+            ORDER BY count DESC, selector LIMIT ?3",
+        )?;
+        let stats = stmt
+            .query_and_then((*blocks.start(), *blocks.end(), limit), |row| {
+                Ok::<SelectorStats, DbError>(SelectorStats {
+                    selector: row
+                        .get::<usize, Option<String>>(0)?
+                        .map(|selector| selector.parse())
+                        .transpose()?,
+                    transactions: row.get(1)?,
+                    gas_used: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<SelectorStats>>>()?;
+        Ok(stats)
+    }
+
+    /// Totals the transactions sent from or to the given address by
+    /// [`ActivityBucket`], retrieving up to `limit` of the most recent
+    /// buckets in which it was active, in ascending order
+    pub fn address_activity(
+        &self,
+        address: Address,
+        bucket: ActivityBucket,
+        limit: usize,
+    ) -> Result<Vec<Activity>> {
+        let conn = self.conn_pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT block_headers.number, block_headers.timestamp,
+                transactions.value
+            FROM transactions JOIN block_headers
+                ON block_headers.hash = transactions.block_hash
+            WHERE block_headers.orphaned = 0
+                AND (transactions.from_address = ?1
+                    OR transactions.to_address = ?1)
+            ORDER BY block_headers.number DESC",
+        )?;
+        let mut rows = stmt.query([address.to_string()])?;
+        let mut activity: Vec<Activity> = vec![];
+
+        while let Some(row) = rows.next()? {
+            let key = match bucket {
+                ActivityBucket::Block => row.get(0)?,
+                ActivityBucket::Day => {
+                    row.get::<usize, u64>(1)? / SECONDS_PER_DAY
+                        * SECONDS_PER_DAY
+                }
+            };
+            let value: U256 = row
+                .get::<usize, Option<String>>(2)?
+                .map(|value| value.parse())
+                .transpose()?
+                .unwrap_or_default();
+            if let Some(last) =
+                activity.last_mut().filter(|last| last.bucket == key)
+            {
+                last.transactions += 1;
+                last.value += value;
+                continue;
+            }
+            if activity.len() == limit {
+                break;
+            }
+            activity.push(Activity {
+                bucket: key,
+                transactions: 1,
+                value,
+            });
+        }
+
+        activity.reverse();
+        Ok(activity)
+    }
+
+    /// Write a [`Transaction`] to the database
+    pub fn add(&self, transaction: &Transaction) -> Result<()> {
+        let mut conn = self.conn_pool.get()?;
+        let tx = conn.transaction()?;
+        {
+            let mut statement = tx.prepare(INSERT_TRANSACTION_SQL)?;
+            Self::insert(&mut statement, transaction)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Write each transaction to the database
+    pub fn add_all(&self, transactions: Vec<Transaction>) -> Result<()> {
+        transactions.iter().try_for_each(|tx| self.add(tx))
+    }
+
+    /// Execute a prepared [`INSERT_TRANSACTION_SQL`] statement for the given
+    /// [`Transaction`]
+    pub(super) fn insert(
+        statement: &mut Statement,
+        transaction: &Transaction,
+    ) -> Result<()> {
+        let row = TransactionRow::from_transaction(transaction)?;
+        statement.execute(params![
+            row.hash,
+            row.block_hash,
+            row.block_number,
+            row.position,
+            row.from_address,
+            row.tx_type,
+            row.chain_id,
+            row.nonce,
+            row.gas_price,
+            row.gas_limit,
+            row.to_address,
+            row.value,
+            row.input,
+            row.max_fee_per_gas,
+            row.max_priority_fee_per_gas,
+        ])?;
+        Ok(())
+    }
+}
+
+/// Collect the transactions read from a block's rows, leaving out (and
+/// logging) those of types that can't be reconstructed rather than failing on
+/// them, so that one exotic transaction doesn't stop the rest of its block
+/// from being read
+pub(super) fn collect_known(
+    txs: impl Iterator<Item = Result<Transaction>>,
+) -> Result<Vec<Transaction>> {
+    txs.filter(|tx| match tx {
+        Err(e) if e.is_unsupported() => {
+            warn!("Skipping stored transaction ({e})");
+            false
+        }
+        _ => true,
+    })
+    .collect()
+}
+
+pub(super) fn row_to_transaction(row: &Row) -> Result<Transaction> {
+    TransactionRow {
+        hash: row.get("hash")?,
+        block_hash: row.get("block_hash")?,
+        block_number: row.get("block_number")?,
+        position: row.get("position")?,
+        from_address: row.get("from_address")?,
+        tx_type: row.get("type")?,
+        chain_id: row.get("chain_id")?,
+        nonce: row.get("nonce")?,
+        gas_price: row.get("gas_price")?,
+        gas_limit: row.get("gas_limit")?,
+        to_address: row.get("to_address")?,
+        value: row.get("value")?,
+        input: row.get("input")?,
+        max_fee_per_gas: row.get("max_fee_per_gas")?,
+        max_priority_fee_per_gas: row.get("max_priority_fee_per_gas")?,
+    }
+    .into_transaction()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::{LogData, B256},
+        rpc::types::{eth::Header, Block, Log},
+    };
+
+    use super::*;
+    use crate::db::{test_transaction, Database, Location, ReceiptStatus};
+
+    #[test]
+    fn test_calldata_pattern_parsing() {
+        assert_eq!(
+            "^0xa9059cbb".parse::<CalldataPattern>(),
+            Ok(CalldataPattern::Prefix(Bytes::from(vec![
+                0xa9, 0x05, 0x9c, 0xbb
+            ])))
+        );
+        assert_eq!(
+            "dead".parse::<CalldataPattern>(),
+            Ok(CalldataPattern::Contains(Bytes::from(vec![0xde, 0xad])))
+        );
+        assert!("0xzz".parse::<CalldataPattern>().is_err());
+        assert!(CalldataPattern::Contains(Bytes::from(vec![0xad, 0xbe]))
+            .matches(&Bytes::from(vec![0xde, 0xad, 0xbe, 0xef])));
+        assert!(!CalldataPattern::Prefix(Bytes::from(vec![0xad, 0xbe]))
+            .matches(&Bytes::from(vec![0xde, 0xad, 0xbe, 0xef])));
+    }
+
+    #[test]
+    fn test_by_input_pattern() {
+        let block = |number: u8, input: &str| {
+            let mut header = Header::new(alloy::consensus::Header {
+                number: number as u64,
+                ..Default::default()
+            });
+            header.hash = BlockHash::with_last_byte(number);
+            let tx = test_transaction(
+                TxHash::with_last_byte(number),
+                header.hash,
+                number as u64,
+                0,
+                |row| row.input = input.to_string(),
+            );
+            Block::new(
+                header,
+                alloy::rpc::types::BlockTransactions::Full(vec![tx]),
+            )
+        };
+        let db = Database::new(Location::Memory).unwrap();
+        db.add_block_atomic(&block(1, "0x12abcd")).unwrap();
+        /* contains the pattern's digits, but not its bytes */
+        db.add_block_atomic(&block(2, "0x0abcd0")).unwrap();
+
+        let pattern = "abcd".parse::<CalldataPattern>().unwrap();
+        let found = db.transactions().by_input_pattern(&pattern, 1).unwrap();
+        assert_eq!(
+            found
+                .iter()
+                .map(|tx| *tx.inner.tx_hash())
+                .collect::<Vec<_>>(),
+            vec![TxHash::with_last_byte(1)]
+        );
+        let pattern = "^0x12".parse::<CalldataPattern>().unwrap();
+        assert_eq!(
+            db.transactions()
+                .by_input_pattern(&pattern, 10)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_selector_stats() {
+        let mut header = Header::new(alloy::consensus::Header {
+            number: 1,
+            ..Default::default()
+        });
+        header.hash = BlockHash::with_last_byte(1);
+        let tx = |i: u8, input: &str| {
+            test_transaction(
+                TxHash::with_last_byte(i),
+                header.hash,
+                1,
+                i as u64 - 1,
+                |row| {
+                    row.to_address = Address::with_last_byte(1).to_string();
+                    row.input = input.to_string();
+                },
+            )
+        };
+        let block = Block::new(
+            header.clone(),
+            alloy::rpc::types::BlockTransactions::Full(vec![
+                tx(1, "0xa9059cbb0000"),
+                tx(2, "0x"),
+                tx(3, "0xa9059cbb"),
+                tx(4, "0x095ea7b3"),
+            ]),
+        );
+        let transfer: Selector = "0xa9059cbb".parse().unwrap();
+
+        let creation_result = Database::new(Location::Memory);
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        assert!(db.add_block_atomic(&block).is_ok());
+        assert!(db
+            .receipts()
+            .add_statuses(&[ReceiptStatus {
+                block_hash: header.hash,
+                transaction_hash: TxHash::with_last_byte(1),
+                success: true,
+                gas_used: 40_000,
+                revert_reason: None,
+                l1_fee: None,
+            }])
+            .is_ok());
+
+        let stats = db.transactions().selector_stats(1..=1, 2).unwrap();
+        assert_eq!(
+            stats,
+            vec![
+                SelectorStats {
+                    selector: Some(transfer),
+                    transactions: 2,
+                    gas_used: 40_000,
+                },
+                /* plain transfers sort before any selector */
+                SelectorStats {
+                    selector: None,
+                    transactions: 1,
+                    gas_used: 0,
+                },
+            ]
+        );
+        assert!(db
+            .transactions()
+            .selector_stats(2..=3, 10)
+            .unwrap()
+            .is_empty());
+
+        assert!(db.headers().mark_orphaned(&[header.hash]).is_ok());
+        assert!(db
+            .transactions()
+            .selector_stats(1..=1, 10)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_missing_priority_fee() {
+        let row = |tx_type| TransactionRow {
+            hash: TxHash::with_last_byte(1).to_string(),
+            block_hash: BlockHash::with_last_byte(1).to_string(),
+            from_address: Address::ZERO.to_string(),
+            tx_type,
+            to_address: Address::with_last_byte(1).to_string(),
+            value: "0".to_string(),
+            input: "0x".to_string(),
+            max_priority_fee_per_gas: None,
+            ..Default::default()
+        };
+
+        assert!(row(0).into_transaction().is_ok());
+        for tx_type in 2..=4 {
+            assert!(matches!(
+                row(tx_type).into_transaction(),
+                Err(DbError::Decode(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_unsupported_types_skipped() {
+        let mut header = Header::new(alloy::consensus::Header {
+            number: 1,
+            ..Default::default()
+        });
+        header.hash = BlockHash::with_last_byte(1);
+        let row = |i: u8, tx_type| TransactionRow {
+            hash: TxHash::with_last_byte(i).to_string(),
+            block_hash: header.hash.to_string(),
+            block_number: 1,
+            position: i as u64,
+            from_address: Address::ZERO.to_string(),
+            tx_type,
+            to_address: Address::with_last_byte(1).to_string(),
+            value: "0".to_string(),
+            input: "0x".to_string(),
+            max_priority_fee_per_gas: Some(1),
+            ..Default::default()
+        };
+        let db = Database::new(Location::Memory).unwrap();
+        db.add_block_atomic(&Block::new(
+            header.clone(),
+            alloy::rpc::types::BlockTransactions::Full(vec![row(0, 2)
+                .into_transaction()
+                .unwrap()]),
+        ))
+        .unwrap();
+
+        /* as written by some other tool sharing the database */
+        let exotic = row(1, 0x7e);
+        db.conn_pool
+            .get()
+            .unwrap()
+            .execute(
+                INSERT_TRANSACTION_SQL,
+                params![
+                    exotic.hash,
+                    exotic.block_hash,
+                    exotic.block_number,
+                    exotic.position,
+                    exotic.from_address,
+                    exotic.tx_type,
+                    exotic.chain_id,
+                    exotic.nonce,
+                    exotic.gas_price,
+                    exotic.gas_limit,
+                    exotic.to_address,
+                    exotic.value,
+                    exotic.input,
+                    exotic.max_fee_per_gas,
+                    exotic.max_priority_fee_per_gas,
+                ],
+            )
+            .unwrap();
+
+        let txs = db.transactions().by_block_hash(header.hash).unwrap();
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].info().hash, Some(TxHash::with_last_byte(0)));
+        assert_eq!(db.transactions().by_block_number(1).unwrap().len(), 1);
+        assert!(db
+            .transactions()
+            .by_hash(TxHash::with_last_byte(1))
+            .unwrap_err()
+            .is_unsupported());
+    }
+
+    #[test]
+    fn test_address_activity() {
+        let address = Address::with_last_byte(7);
+        let block = |number: u64, timestamp, txs: &[(u8, Address, u64)]| {
+            let mut header = Header::new(alloy::consensus::Header {
+                number,
+                timestamp,
+                ..Default::default()
+            });
+            header.hash = BlockHash::with_last_byte(number as u8);
+            let txs = txs
+                .iter()
+                .enumerate()
+                .map(|(position, (i, to, value))| {
+                    test_transaction(
+                        TxHash::with_last_byte(*i),
+                        header.hash,
+                        number,
+                        position as u64,
+                        |row| {
+                            row.to_address = to.to_string();
+                            row.value = value.to_string();
+                        },
+                    )
+                })
+                .collect();
+            Block::new(header, alloy::rpc::types::BlockTransactions::Full(txs))
+        };
+        let other = Address::with_last_byte(8);
+        let blocks = [
+            block(1, 10, &[(1, address, 5), (2, other, 1), (3, address, 2)]),
+            block(2, SECONDS_PER_DAY - 1, &[(4, other, 1)]),
+            block(3, SECONDS_PER_DAY, &[(5, address, 1)]),
+        ];
+
+        let creation_result = Database::new(Location::Memory);
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        blocks
+            .iter()
+            .for_each(|block| assert!(db.add_block_atomic(block).is_ok()));
+
+        let activity = |bucket, transactions, value| Activity {
+            bucket,
+            transactions,
+            value: U256::from(value),
+        };
+        assert_eq!(
+            db.transactions()
+                .address_activity(address, ActivityBucket::Block, 10)
+                .unwrap(),
+            vec![activity(1, 2, 7), activity(3, 1, 1)]
+        );
+        assert_eq!(
+            db.transactions()
+                .address_activity(address, ActivityBucket::Day, 10)
+                .unwrap(),
+            vec![activity(0, 2, 7), activity(SECONDS_PER_DAY, 1, 1)]
+        );
+        assert_eq!(
+            db.transactions()
+                .address_activity(address, ActivityBucket::Block, 1)
+                .unwrap(),
+            vec![activity(3, 1, 1)]
+        );
+        assert!(db
+            .transactions()
+            .address_activity(
+                Address::with_last_byte(9),
+                ActivityBucket::Block,
+                10
+            )
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_account_history() {
+        let creation_result = Database::new(Location::Memory);
+        assert!(creation_result.is_ok());
+        let db = creation_result.unwrap();
+        let account = Address::with_last_byte(0xaa);
+        let other = Address::with_last_byte(0xbb);
+        let header = |number: u8| Header {
+            hash: BlockHash::with_last_byte(number),
+            inner: alloy::consensus::Header {
+                number: number as u64,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let tx = |number: u8, from: Address| {
+            test_transaction(
+                TxHash::with_last_byte(number),
+                BlockHash::with_last_byte(number),
+                number as u64,
+                0,
+                |row| row.from_address = from.to_string(),
+            )
+        };
+        /* an ERC-20 transfer to the account */
+        let log = |number: u8| Log {
+            inner: alloy::primitives::Log {
+                address: other,
+                data: LogData::new_unchecked(
+                    vec![B256::with_last_byte(0xee), account.into_word()],
+                    Bytes::new(),
+                ),
+            },
+            block_hash: Some(BlockHash::with_last_byte(number)),
+            block_number: Some(number as u64),
+            transaction_hash: Some(TxHash::with_last_byte(number)),
+            transaction_index: Some(0),
+            log_index: Some(0),
+            ..Default::default()
+        };
+
+        assert!(db.headers().add(&header(1)).is_ok());
+        assert!(db.headers().add(&header(2)).is_ok());
+        assert!(db.headers().add(&header(3)).is_ok());
+        assert!(db
+            .transactions()
+            .add_all(vec![tx(1, account), tx(2, other), tx(3, other)])
+            .is_ok());
+        assert!(db.logs().add(&[log(2), log(3)]).is_ok());
+
+        let history_result = db.transactions().account_history(account, 10);
+        assert!(history_result.is_ok());
+        assert_eq!(
+            history_result
+                .unwrap()
+                .iter()
+                .map(|tx| tx.info().hash)
+                .collect::<Vec<_>>(),
+            vec![
+                Some(TxHash::with_last_byte(3)),
+                Some(TxHash::with_last_byte(2)),
+                Some(TxHash::with_last_byte(1))
+            ]
+        );
+        assert_eq!(
+            db.transactions().account_history(account, 1).unwrap().len(),
+            1
+        );
+        assert_eq!(
+            db.transactions().account_history(other, 10).unwrap().len(),
+            2
+        );
+    }
+}
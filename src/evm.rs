@@ -0,0 +1,224 @@
+//! Disassembly of EVM bytecode
+use std::{collections::BTreeSet, fmt};
+
+use alloy::primitives::{Bytes, Selector};
+
+const PUSH1: u8 = 0x60;
+const PUSH4: u8 = 0x63;
+const PUSH32: u8 = 0x7f;
+const EQ: u8 = 0x14;
+
+/// A single instruction of disassembled bytecode
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Instruction {
+    /// Position of the opcode within the code
+    pub offset: usize,
+    pub opcode: u8,
+    /// Bytes pushed by a `PUSH` (which may be cut short by the end of the
+    /// code)
+    pub immediate: Bytes,
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:06x}  {}", self.offset, mnemonic(self.opcode))?;
+
+        if !self.immediate.is_empty() {
+            write!(f, " {}", self.immediate)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Split the given bytecode into [`Instruction`]s
+///
+/// Everything is treated as code, so any data appended to it (e.g., Solidity
+/// metadata) disassembles into nonsense.
+pub fn disassemble(code: &[u8]) -> Vec<Instruction> {
+    let mut instructions = vec![];
+    let mut offset = 0;
+
+    while let Some(&opcode) = code.get(offset) {
+        let size = match opcode {
+            PUSH1..=PUSH32 => (opcode - PUSH1 + 1) as usize,
+            _ => 0,
+        };
+        let end = (offset + 1 + size).min(code.len());
+        instructions.push(Instruction {
+            offset,
+            opcode,
+            immediate: Bytes::copy_from_slice(&code[offset + 1..end]),
+        });
+        offset += 1 + size;
+    }
+
+    instructions
+}
+
+/// Function selectors that the given bytecode dispatches on
+///
+/// Solidity and Vyper compare the selector of the calldata against each of
+/// the contract's functions in turn, so these are found by looking for
+/// `PUSH4`s that are compared for equality.
+pub fn selectors(code: &[u8]) -> Vec<Selector> {
+    let instructions = disassemble(code);
+    let selectors: BTreeSet<Selector> = instructions
+        .windows(2)
+        .filter(|pair| pair[0].opcode == PUSH4 && pair[1].opcode == EQ)
+        .filter_map(|pair| Selector::try_from(&pair[0].immediate[..]).ok())
+        .collect();
+    selectors.into_iter().collect()
+}
+
+/// Name of the given opcode (as of the Prague hard fork)
+pub fn mnemonic(opcode: u8) -> &'static str {
+    const PUSH: [&str; 32] = [
+        "PUSH1", "PUSH2", "PUSH3", "PUSH4", "PUSH5", "PUSH6", "PUSH7", "PUSH8",
+        "PUSH9", "PUSH10", "PUSH11", "PUSH12", "PUSH13", "PUSH14", "PUSH15",
+        "PUSH16", "PUSH17", "PUSH18", "PUSH19", "PUSH20", "PUSH21", "PUSH22",
+        "PUSH23", "PUSH24", "PUSH25", "PUSH26", "PUSH27", "PUSH28", "PUSH29",
+        "PUSH30", "PUSH31", "PUSH32",
+    ];
+    const DUP: [&str; 16] = [
+        "DUP1", "DUP2", "DUP3", "DUP4", "DUP5", "DUP6", "DUP7", "DUP8", "DUP9",
+        "DUP10", "DUP11", "DUP12", "DUP13", "DUP14", "DUP15", "DUP16",
+    ];
+    const SWAP: [&str; 16] = [
+        "SWAP1", "SWAP2", "SWAP3", "SWAP4", "SWAP5", "SWAP6", "SWAP7", "SWAP8",
+        "SWAP9", "SWAP10", "SWAP11", "SWAP12", "SWAP13", "SWAP14", "SWAP15",
+        "SWAP16",
+    ];
+    const LOG: [&str; 5] = ["LOG0", "LOG1", "LOG2", "LOG3", "LOG4"];
+
+    match opcode {
+        0x00 => "STOP",
+        0x01 => "ADD",
+        0x02 => "MUL",
+        0x03 => "SUB",
+        0x04 => "DIV",
+        0x05 => "SDIV",
+        0x06 => "MOD",
+        0x07 => "SMOD",
+        0x08 => "ADDMOD",
+        0x09 => "MULMOD",
+        0x0a => "EXP",
+        0x0b => "SIGNEXTEND",
+        0x10 => "LT",
+        0x11 => "GT",
+        0x12 => "SLT",
+        0x13 => "SGT",
+        0x14 => "EQ",
+        0x15 => "ISZERO",
+        0x16 => "AND",
+        0x17 => "OR",
+        0x18 => "XOR",
+        0x19 => "NOT",
+        0x1a => "BYTE",
+        0x1b => "SHL",
+        0x1c => "SHR",
+        0x1d => "SAR",
+        0x20 => "KECCAK256",
+        0x30 => "ADDRESS",
+        0x31 => "BALANCE",
+        0x32 => "ORIGIN",
+        0x33 => "CALLER",
+        0x34 => "CALLVALUE",
+        0x35 => "CALLDATALOAD",
+        0x36 => "CALLDATASIZE",
+        0x37 => "CALLDATACOPY",
+        0x38 => "CODESIZE",
+        0x39 => "CODECOPY",
+        0x3a => "GASPRICE",
+        0x3b => "EXTCODESIZE",
+        0x3c => "EXTCODECOPY",
+        0x3d => "RETURNDATASIZE",
+        0x3e => "RETURNDATACOPY",
+        0x3f => "EXTCODEHASH",
+        0x40 => "BLOCKHASH",
+        0x41 => "COINBASE",
+        0x42 => "TIMESTAMP",
+        0x43 => "NUMBER",
+        0x44 => "PREVRANDAO",
+        0x45 => "GASLIMIT",
+        0x46 => "CHAINID",
+        0x47 => "SELFBALANCE",
+        0x48 => "BASEFEE",
+        0x49 => "BLOBHASH",
+        0x4a => "BLOBBASEFEE",
+        0x50 => "POP",
+        0x51 => "MLOAD",
+        0x52 => "MSTORE",
+        0x53 => "MSTORE8",
+        0x54 => "SLOAD",
+        0x55 => "SSTORE",
+        0x56 => "JUMP",
+        0x57 => "JUMPI",
+        0x58 => "PC",
+        0x59 => "MSIZE",
+        0x5a => "GAS",
+        0x5b => "JUMPDEST",
+        0x5c => "TLOAD",
+        0x5d => "TSTORE",
+        0x5e => "MCOPY",
+        0x5f => "PUSH0",
+        0x60..=0x7f => PUSH[(opcode - 0x60) as usize],
+        0x80..=0x8f => DUP[(opcode - 0x80) as usize],
+        0x90..=0x9f => SWAP[(opcode - 0x90) as usize],
+        0xa0..=0xa4 => LOG[(opcode - 0xa0) as usize],
+        0xf0 => "CREATE",
+        0xf1 => "CALL",
+        0xf2 => "CALLCODE",
+        0xf3 => "RETURN",
+        0xf4 => "DELEGATECALL",
+        0xf5 => "CREATE2",
+        0xfa => "STATICCALL",
+        0xfd => "REVERT",
+        0xfe => "INVALID",
+        0xff => "SELFDESTRUCT",
+        _ => "UNKNOWN",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::hex;
+
+    use super::*;
+
+    #[test]
+    fn test_disassemble() {
+        /* the start of a Solidity dispatcher, cut off mid-PUSH */
+        let code = hex::decode("6080604052600436106100").unwrap();
+        let lines: Vec<String> =
+            disassemble(&code).iter().map(|i| i.to_string()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "000000  PUSH1 0x80",
+                "000002  PUSH1 0x40",
+                "000004  MSTORE",
+                "000005  PUSH1 0x04",
+                "000007  CALLDATASIZE",
+                "000008  LT",
+                "000009  PUSH2 0x00",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_selectors() {
+        /* DUP1 PUSH4 a9059cbb EQ PUSH2 0x0042 JUMPI DUP1 PUSH4 70a08231 GT
+         * DUP1 PUSH4 095ea7b3 EQ */
+        let code =
+            hex::decode("8063a9059cbb1461004257806370a08231118063095ea7b314")
+                .unwrap();
+        assert_eq!(
+            selectors(&code),
+            vec![
+                Selector::from(hex!("095ea7b3")),
+                Selector::from(hex!("a9059cbb")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,431 @@
+//! Dumps of indexed data in formats suited to analysis elsewhere (e.g., in a
+//! spreadsheet or a dataframe library)
+use std::{fmt, io::Write, ops::RangeInclusive};
+
+use alloy::{
+    consensus::Transaction as AbstractTransaction,
+    primitives::BlockNumber,
+    rpc::types::{Block, Log, Transaction},
+};
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+
+use crate::storage::Storage;
+
+/// Kinds of indexed data that can be exported
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum Table {
+    Blocks,
+    Transactions,
+    Logs,
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Blocks => write!(f, "blocks"),
+            Self::Transactions => write!(f, "transactions"),
+            Self::Logs => write!(f, "logs"),
+        }
+    }
+}
+
+impl Table {
+    fn columns(&self) -> &'static [&'static str] {
+        match self {
+            Self::Blocks => &[
+                "number",
+                "hash",
+                "parent_hash",
+                "timestamp",
+                "beneficiary",
+                "gas_used",
+                "gas_limit",
+                "base_fee_per_gas",
+                "blob_gas_used",
+                "excess_blob_gas",
+                "transaction_count",
+                "extra_data",
+            ],
+            Self::Transactions => &[
+                "block_number",
+                "block_hash",
+                "transaction_index",
+                "hash",
+                "type",
+                "from",
+                "to",
+                "nonce",
+                "value",
+                "gas_limit",
+                "max_fee_per_gas",
+                "max_priority_fee_per_gas",
+                "input",
+            ],
+            Self::Logs => &[
+                "block_number",
+                "block_hash",
+                "transaction_hash",
+                "transaction_index",
+                "log_index",
+                "address",
+                "topic0",
+                "topic1",
+                "topic2",
+                "topic3",
+                "data",
+            ],
+        }
+    }
+}
+
+/// Formats that indexed data can be exported in
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum Format {
+    /// Comma-separated values, with a header row
+    Csv,
+    /// A single array of objects
+    #[default]
+    Json,
+    /// One object per line
+    Ndjson,
+    /// Apache Parquet, with each column either an unsigned integer or a
+    /// string (requires building with the `parquet` feature)
+    Parquet,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Csv => write!(f, "csv"),
+            Self::Json => write!(f, "json"),
+            Self::Ndjson => write!(f, "ndjson"),
+            Self::Parquet => write!(f, "parquet"),
+        }
+    }
+}
+
+/// Write every row of the given [`Table`] within the given range of blocks
+/// (skipping any that aren't indexed) in the given [`Format`], returning the
+/// number of rows written
+pub fn export(
+    storage: &impl Storage,
+    table: Table,
+    range: RangeInclusive<BlockNumber>,
+    format: Format,
+    out: &mut impl Write,
+) -> eyre::Result<usize> {
+    let rows = range
+        .filter_map(|number| storage.block_by_number(number).transpose())
+        .map(|block| {
+            let block = block?;
+            Ok(match table {
+                Table::Blocks => vec![block_row(&block)],
+                Table::Transactions => {
+                    block.transactions.txns().map(transaction_row).collect()
+                }
+                Table::Logs => storage
+                    .logs_by_block_hash(block.header.hash)?
+                    .iter()
+                    .map(log_row)
+                    .collect(),
+            })
+        })
+        .flat_map(|rows: eyre::Result<Vec<Vec<Value>>>| match rows {
+            Ok(rows) => rows.into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
+        });
+    write_rows(table.columns(), rows, format, out)
+}
+
+/// Write the given rows (each holding a value for every one of the given
+/// columns) in the given [`Format`], returning the number of rows written
+pub fn write_rows(
+    columns: &[&str],
+    rows: impl IntoIterator<Item = eyre::Result<Vec<Value>>>,
+    format: Format,
+    out: &mut impl Write,
+) -> eyre::Result<usize> {
+    let mut count = 0;
+
+    match format {
+        Format::Csv => writeln!(out, "{}", columns.join(","))?,
+        Format::Json => write!(out, "[")?,
+        Format::Ndjson => {}
+        Format::Parquet => {
+            let rows = rows.into_iter().collect::<eyre::Result<Vec<_>>>()?;
+            write_parquet(columns, &rows, out)?;
+            out.flush()?;
+            return Ok(rows.len());
+        }
+    }
+
+    for row in rows {
+        let row = row?;
+        match format {
+            Format::Csv => writeln!(
+                out,
+                "{}",
+                row.iter().map(csv_field).collect::<Vec<_>>().join(",")
+            )?,
+            Format::Json | Format::Ndjson | Format::Parquet => {
+                let object: Map<String, Value> = columns
+                    .iter()
+                    .map(|column| column.to_string())
+                    .zip(row)
+                    .collect();
+                if format == Format::Json {
+                    write!(out, "{}\n  ", if count == 0 { "" } else { "," })?;
+                }
+                write!(out, "{}", Value::Object(object))?;
+                if format == Format::Ndjson {
+                    writeln!(out)?;
+                }
+            }
+        }
+        count += 1;
+    }
+
+    if format == Format::Json {
+        writeln!(out, "{}]", if count == 0 { "" } else { "\n" })?;
+    }
+    out.flush()?;
+    Ok(count)
+}
+
+/// Numbers that may not fit in a JSON number are written as strings
+pub fn big_number(x: impl Into<u128>) -> Value {
+    let x = x.into();
+    u64::try_from(x).map_or_else(|_| json!(x.to_string()), |x| json!(x))
+}
+
+fn block_row(block: &Block) -> Vec<Value> {
+    let header = &block.header;
+    vec![
+        json!(header.number),
+        json!(header.hash),
+        json!(header.parent_hash),
+        json!(header.timestamp),
+        json!(header.beneficiary),
+        json!(header.gas_used),
+        json!(header.gas_limit),
+        json!(header.base_fee_per_gas),
+        json!(header.blob_gas_used),
+        json!(header.excess_blob_gas),
+        json!(block.transactions.len()),
+        json!(header.extra_data),
+    ]
+}
+
+fn transaction_row(tx: &Transaction) -> Vec<Value> {
+    vec![
+        json!(tx.block_number),
+        json!(tx.block_hash),
+        json!(tx.transaction_index),
+        json!(tx.inner.tx_hash()),
+        json!(u8::from(tx.inner.tx_type())),
+        json!(tx.as_recovered().signer()),
+        json!(tx.to()),
+        json!(tx.nonce()),
+        json!(tx.value().to_string()),
+        json!(tx.gas_limit()),
+        big_number(tx.max_fee_per_gas()),
+        tx.max_priority_fee_per_gas()
+            .map_or(Value::Null, big_number),
+        json!(tx.input()),
+    ]
+}
+
+fn log_row(log: &Log) -> Vec<Value> {
+    let topics = log.topics();
+    vec![
+        json!(log.block_number),
+        json!(log.block_hash),
+        json!(log.transaction_hash),
+        json!(log.transaction_index),
+        json!(log.log_index),
+        json!(log.address()),
+        json!(topics.first()),
+        json!(topics.get(1)),
+        json!(topics.get(2)),
+        json!(topics.get(3)),
+        json!(log.data().data),
+    ]
+}
+
+/// Write the given rows as a single Parquet row group
+///
+/// Columns holding nothing but integers (or nulls) are typed as such, and
+/// everything else is written as strings, so every row is needed up front.
+#[cfg(feature = "parquet")]
+fn write_parquet(
+    columns: &[&str],
+    rows: &[Vec<Value>],
+    out: &mut impl Write,
+) -> eyre::Result<()> {
+    use std::sync::Arc;
+
+    use arrow_array::{ArrayRef, RecordBatch, StringArray, UInt64Array};
+    use arrow_schema::{DataType, Field, Schema};
+    use parquet::arrow::ArrowWriter;
+
+    let (fields, arrays): (Vec<Field>, Vec<ArrayRef>) = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let values = rows.iter().map(move |row| &row[i]);
+            if values
+                .clone()
+                .all(|value| value.is_null() || value.is_u64())
+            {
+                (
+                    Field::new(*column, DataType::UInt64, true),
+                    Arc::new(UInt64Array::from_iter(values.map(Value::as_u64)))
+                        as ArrayRef,
+                )
+            } else {
+                (
+                    Field::new(*column, DataType::Utf8, true),
+                    Arc::new(StringArray::from_iter(values.map(text)))
+                        as ArrayRef,
+                )
+            }
+        })
+        .unzip();
+    let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)?;
+
+    /* the writer must be `Send` (which standard output isn't) */
+    let mut buffer = vec![];
+    let mut writer = ArrowWriter::try_new(&mut buffer, batch.schema(), None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    out.write_all(&buffer)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "parquet"))]
+fn write_parquet(
+    _columns: &[&str],
+    _rows: &[Vec<Value>],
+    _out: &mut impl Write,
+) -> eyre::Result<()> {
+    Err(eyre::eyre!(
+        "Parquet exports require building with the `parquet` feature"
+    ))
+}
+
+/// The given value as text (if it isn't null)
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Render a single CSV field, quoting it if necessary
+fn csv_field(value: &Value) -> String {
+    let s = text(value).unwrap_or_default();
+
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{primitives::BlockHash, rpc::types::Header};
+
+    use super::*;
+    use crate::db::{Database, Location};
+
+    #[test]
+    fn test_export_blocks() {
+        let db = Database::new(Location::Memory).unwrap();
+        for number in [1, 3] {
+            db.headers()
+                .add(&Header {
+                    hash: BlockHash::with_last_byte(number as u8),
+                    inner: alloy::consensus::Header {
+                        number,
+                        gas_used: 21_000,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let mut csv = vec![];
+        assert_eq!(
+            export(&db, Table::Blocks, 1..=3, Format::Csv, &mut csv).unwrap(),
+            2
+        );
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], Table::Blocks.columns().join(","));
+        assert!(lines[1]
+            .starts_with(&format!("1,{},", BlockHash::with_last_byte(1))));
+        assert!(lines[2].starts_with("3,"));
+
+        let mut json = vec![];
+        export(&db, Table::Blocks, 1..=3, Format::Json, &mut json).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json[1]["number"], json!(3));
+        assert_eq!(json[1]["gas_used"], json!(21_000));
+        assert_eq!(json[1]["base_fee_per_gas"], Value::Null);
+
+        let mut empty = vec![];
+        export(&db, Table::Logs, 1..=3, Format::Json, &mut empty).unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&empty).unwrap(), json!([]));
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let rows = vec![
+            Ok(vec![json!(1), json!("0xab"), Value::Null]),
+            Ok(vec![json!(2), Value::Null, json!(u64::MAX)]),
+        ];
+        let path = std::env::temp_dir().join("blocktop-test-export.parquet");
+        let mut parquet = std::fs::File::create(&path).unwrap();
+        assert_eq!(
+            write_rows(&["a", "b", "c"], rows, Format::Parquet, &mut parquet)
+                .unwrap(),
+            2
+        );
+
+        let reader =
+            SerializedFileReader::new(std::fs::File::open(&path).unwrap())
+                .unwrap();
+        let _ = std::fs::remove_file(&path);
+        let metadata = reader.metadata();
+        assert_eq!(metadata.file_metadata().num_rows(), 2);
+        let schema = metadata.file_metadata().schema_descr();
+        assert_eq!(
+            (0..3)
+                .map(|i| schema.column(i).physical_type().to_string())
+                .collect::<Vec<_>>(),
+            vec!["INT64", "BYTE_ARRAY", "INT64"]
+        );
+    }
+
+    #[cfg(not(feature = "parquet"))]
+    #[test]
+    fn test_parquet_unavailable() {
+        let mut parquet = vec![];
+        assert!(write_rows(&["a"], [], Format::Parquet, &mut parquet).is_err());
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field(&json!(42)), "42");
+        assert_eq!(csv_field(&Value::Null), "");
+        assert_eq!(csv_field(&json!("a,\"b\"")), "\"a,\"\"b\"\"\"");
+    }
+}
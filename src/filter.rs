@@ -0,0 +1,521 @@
+//! Boolean expressions over transactions (e.g., `to == 0xdead...beef &&
+//! value > 1 ether`)
+use std::{fmt, str::FromStr};
+
+use alloy::{
+    consensus::{Transaction as AbstractTransaction, Typed2718},
+    primitives::{utils::parse_units, Address, Bytes, U256},
+    rpc::types::Transaction,
+};
+
+use crate::utils::useful_gas_price;
+
+/// Property of a transaction that can be compared against
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Field {
+    From,
+    /// Recipient (or `null` for contract creations)
+    To,
+    /// Amount of ether transferred (in wei)
+    Value,
+    Nonce,
+    /// Gas limit
+    Gas,
+    /// Maximum fee per gas (in wei)
+    GasPrice,
+    /// First four bytes of the calldata
+    Selector,
+    /// Transaction type (e.g., `2` for EIP-1559 transactions)
+    Type,
+    /// Number of the block the transaction was included in
+    Block,
+}
+
+impl Field {
+    /// Whether this field can be compared with `<`, `>`, etc.
+    fn is_ordered(self) -> bool {
+        !matches!(self, Self::From | Self::To | Self::Selector)
+    }
+
+    fn value(self, tx: &Transaction) -> Value {
+        match self {
+            Self::From => Value::Address(Some(tx.as_recovered().signer())),
+            Self::To => Value::Address(tx.to()),
+            Self::Value => Value::Number(tx.value()),
+            Self::Nonce => Value::Number(U256::from(tx.nonce())),
+            Self::Gas => Value::Number(U256::from(tx.gas_limit())),
+            Self::GasPrice => Value::Number(U256::from(useful_gas_price(tx))),
+            Self::Selector => Value::Bytes(Bytes::copy_from_slice(
+                tx.input().get(..4).unwrap_or_default(),
+            )),
+            Self::Type => Value::Number(U256::from(tx.ty())),
+            Self::Block => {
+                Value::Number(U256::from(tx.block_number.unwrap_or_default()))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::From => "from",
+                Self::To => "to",
+                Self::Value => "value",
+                Self::Nonce => "nonce",
+                Self::Gas => "gas",
+                Self::GasPrice => "gas_price",
+                Self::Selector => "selector",
+                Self::Type => "type",
+                Self::Block => "block",
+            }
+        )
+    }
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "from" => Ok(Self::From),
+            "to" => Ok(Self::To),
+            "value" => Ok(Self::Value),
+            "nonce" => Ok(Self::Nonce),
+            "gas" => Ok(Self::Gas),
+            "gas_price" => Ok(Self::GasPrice),
+            "selector" => Ok(Self::Selector),
+            "type" => Ok(Self::Type),
+            "block" => Ok(Self::Block),
+            other => Err(format!("unknown field: {other}")),
+        }
+    }
+}
+
+/// Literal that a [`Field`] is compared against
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Value {
+    /// An address (or `null`)
+    Address(Option<Address>),
+    /// An amount (already converted to wei, if given in other units)
+    Number(U256),
+    Bytes(Bytes),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Address(Some(address)) => write!(f, "{address}"),
+            Self::Address(None) => write!(f, "null"),
+            Self::Number(x) => write!(f, "{x}"),
+            Self::Bytes(bytes) => write!(f, "{bytes}"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Eq => "==",
+                Self::Ne => "!=",
+                Self::Lt => "<",
+                Self::Le => "<=",
+                Self::Gt => ">",
+                Self::Ge => ">=",
+            }
+        )
+    }
+}
+
+/// Expression that a transaction either matches or doesn't (e.g.,
+/// `to == 0xdead...beef && value > 1 ether`)
+///
+/// Comparisons of [`Field`]s against literals can be combined with `&&`, `||`,
+/// `!`, and parentheses. Numbers may be given in decimal or hex, optionally
+/// followed by a unit (`wei`, `gwei`, or `ether`).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Filter {
+    Compare(Field, Comparison, Value),
+    Not(Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+}
+
+impl Filter {
+    /// Whether the given [`Transaction`] satisfies this filter
+    pub fn matches(&self, tx: &Transaction) -> bool {
+        match self {
+            Self::Compare(field, comparison, expected) => {
+                let actual = field.value(tx);
+                match comparison {
+                    Comparison::Eq => actual == *expected,
+                    Comparison::Ne => actual != *expected,
+                    ordering => match (actual, expected) {
+                        (Value::Number(actual), Value::Number(expected)) => {
+                            match ordering {
+                                Comparison::Lt => actual < *expected,
+                                Comparison::Le => actual <= *expected,
+                                Comparison::Gt => actual > *expected,
+                                _ => actual >= *expected,
+                            }
+                        }
+                        _ => false,
+                    },
+                }
+            }
+            Self::Not(inner) => !inner.matches(tx),
+            Self::And(lhs, rhs) => lhs.matches(tx) && rhs.matches(tx),
+            Self::Or(lhs, rhs) => lhs.matches(tx) || rhs.matches(tx),
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Compare(field, comparison, value) => {
+                write!(f, "{field} {comparison} {value}")
+            }
+            Self::Not(inner) => write!(f, "!({inner})"),
+            Self::And(lhs, rhs) => write!(f, "({lhs} && {rhs})"),
+            Self::Or(lhs, rhs) => write!(f, "({lhs} || {rhs})"),
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenise(s)?,
+            position: 0,
+        };
+        let filter = parser.disjunction()?;
+
+        match parser.peek() {
+            None => Ok(filter),
+            Some(token) => Err(format!("unexpected {token}")),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Token {
+    /// A field name, unit, literal, or `null`
+    Word(String),
+    Compare(Comparison),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Word(word) => write!(f, "`{word}`"),
+            Self::Compare(comparison) => write!(f, "`{comparison}`"),
+            Self::And => write!(f, "`&&`"),
+            Self::Or => write!(f, "`||`"),
+            Self::Not => write!(f, "`!`"),
+            Self::Open => write!(f, "`(`"),
+            Self::Close => write!(f, "`)`"),
+        }
+    }
+}
+
+fn tokenise(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '&' if chars.next_if_eq(&'&').is_some() => Token::And,
+            '|' if chars.next_if_eq(&'|').is_some() => Token::Or,
+            '=' if chars.next_if_eq(&'=').is_some() => {
+                Token::Compare(Comparison::Eq)
+            }
+            '!' if chars.next_if_eq(&'=').is_some() => {
+                Token::Compare(Comparison::Ne)
+            }
+            '!' => Token::Not,
+            '<' if chars.next_if_eq(&'=').is_some() => {
+                Token::Compare(Comparison::Le)
+            }
+            '<' => Token::Compare(Comparison::Lt),
+            '>' if chars.next_if_eq(&'=').is_some() => {
+                Token::Compare(Comparison::Ge)
+            }
+            '>' => Token::Compare(Comparison::Gt),
+            c if c.is_ascii_alphanumeric() || c == '_' || c == '.' => {
+                let mut word = c.to_string();
+                while let Some(c) = chars.next_if(|c| {
+                    c.is_ascii_alphanumeric() || *c == '_' || *c == '.'
+                }) {
+                    word.push(c);
+                }
+                Token::Word(word)
+            }
+            other => return Err(format!("unexpected `{other}`")),
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser over [`Token`]s, where `!` binds tightest and
+/// `||` loosest
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token, String> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or("unexpected end of filter")?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn disjunction(&mut self) -> Result<Filter, String> {
+        let mut filter = self.conjunction()?;
+
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            filter =
+                Filter::Or(Box::new(filter), Box::new(self.conjunction()?));
+        }
+
+        Ok(filter)
+    }
+
+    fn conjunction(&mut self) -> Result<Filter, String> {
+        let mut filter = self.negation()?;
+
+        while self.peek() == Some(&Token::And) {
+            self.position += 1;
+            filter = Filter::And(Box::new(filter), Box::new(self.negation()?));
+        }
+
+        Ok(filter)
+    }
+
+    fn negation(&mut self) -> Result<Filter, String> {
+        match self.next()? {
+            Token::Not => Ok(Filter::Not(Box::new(self.negation()?))),
+            Token::Open => {
+                let filter = self.disjunction()?;
+                match self.next()? {
+                    Token::Close => Ok(filter),
+                    other => Err(format!("expected `)`, found {other}")),
+                }
+            }
+            Token::Word(word) => {
+                let field: Field = word.parse()?;
+                let comparison = match self.next()? {
+                    Token::Compare(comparison) => comparison,
+                    other => {
+                        return Err(format!(
+                            "expected a comparison, found {other}"
+                        ))
+                    }
+                };
+
+                if !field.is_ordered()
+                    && !matches!(comparison, Comparison::Eq | Comparison::Ne)
+                {
+                    return Err(format!("`{field}` can't be ordered"));
+                }
+
+                let value = self.value(field)?;
+                Ok(Filter::Compare(field, comparison, value))
+            }
+            other => Err(format!("expected a field, found {other}")),
+        }
+    }
+
+    fn value(&mut self, field: Field) -> Result<Value, String> {
+        let literal = match self.next()? {
+            Token::Word(word) => word,
+            other => return Err(format!("expected a value, found {other}")),
+        };
+
+        match field {
+            Field::From | Field::To => match literal.as_str() {
+                "null" if field == Field::To => Ok(Value::Address(None)),
+                address => Ok(Value::Address(Some(
+                    address
+                        .parse()
+                        .map_err(|_| format!("invalid address: {address}"))?,
+                ))),
+            },
+            Field::Selector => {
+                let selector: Bytes = literal
+                    .parse()
+                    .map_err(|_| format!("invalid selector: {literal}"))?;
+                if selector.len() == 4 {
+                    Ok(Value::Bytes(selector))
+                } else {
+                    Err(format!("selectors are four bytes, not {literal}"))
+                }
+            }
+            _ => {
+                let unit = match self.peek() {
+                    Some(Token::Word(unit))
+                        if matches!(
+                            unit.as_str(),
+                            "wei" | "gwei" | "ether"
+                        ) =>
+                    {
+                        let unit = unit.clone();
+                        self.position += 1;
+                        unit
+                    }
+                    _ => "wei".to_string(),
+                };
+
+                if let Some(hex) = literal.strip_prefix("0x") {
+                    let x = U256::from_str_radix(hex, 16)
+                        .map_err(|_| format!("invalid number: {literal}"))?;
+                    return if unit == "wei" {
+                        Ok(Value::Number(x))
+                    } else {
+                        Err(format!(
+                            "units can't follow hex numbers ({literal})"
+                        ))
+                    };
+                }
+
+                Ok(Value::Number(
+                    parse_units(&literal, unit.as_str())
+                        .map_err(|_| format!("invalid number: {literal}"))?
+                        .get_absolute(),
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        consensus::{Signed, TxEip1559, TxEnvelope},
+        primitives::{Signature, TxKind, B256},
+    };
+
+    use super::*;
+
+    fn transaction(to: Option<Address>, value: U256) -> Transaction {
+        let tx = TxEip1559 {
+            to: to.map(TxKind::Call).unwrap_or(TxKind::Create),
+            value,
+            nonce: 7,
+            input: vec![0xa9, 0x05, 0x9c, 0xbb, 0x00].into(),
+            ..Default::default()
+        };
+        Transaction {
+            inner: alloy::consensus::transaction::Recovered::new_unchecked(
+                TxEnvelope::Eip1559(Signed::new_unchecked(
+                    tx,
+                    Signature::test_signature(),
+                    B256::ZERO,
+                )),
+                Address::with_last_byte(1),
+            ),
+            block_hash: None,
+            block_number: Some(100),
+            transaction_index: None,
+            effective_gas_price: None,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let filter: Filter =
+            "to == 0x000000000000000000000000000000000000dEaD && value > 1 ether"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            filter,
+            Filter::And(
+                Box::new(Filter::Compare(
+                    Field::To,
+                    Comparison::Eq,
+                    Value::Address(Some(
+                        "0x000000000000000000000000000000000000dEaD"
+                            .parse()
+                            .unwrap()
+                    ))
+                )),
+                Box::new(Filter::Compare(
+                    Field::Value,
+                    Comparison::Gt,
+                    Value::Number(U256::from(10u128.pow(18)))
+                ))
+            )
+        );
+        assert!("to > 0x000000000000000000000000000000000000dEaD"
+            .parse::<Filter>()
+            .is_err());
+        assert!("value >".parse::<Filter>().is_err());
+        assert!("(nonce == 1".parse::<Filter>().is_err());
+        assert!("colour == red".parse::<Filter>().is_err());
+        assert!("selector == 0xa9".parse::<Filter>().is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        let dead: Address = "0x000000000000000000000000000000000000dEaD"
+            .parse()
+            .unwrap();
+        let tx = transaction(Some(dead), U256::from(2u128 * 10u128.pow(18)));
+        let matches =
+            |filter: &str| filter.parse::<Filter>().unwrap().matches(&tx);
+
+        assert!(matches(
+            "to == 0x000000000000000000000000000000000000dEaD && value > 1 ether"
+        ));
+        assert!(!matches("value > 2 ether"));
+        assert!(matches("value >= 2000000000 gwei"));
+        assert!(matches(
+            "from == 0x0000000000000000000000000000000000000001"
+        ));
+        assert!(matches("selector == 0xa9059cbb && !(nonce < 7)"));
+        assert!(matches("to == null || block == 100"));
+        assert!(!matches("to == null || type != 2"));
+        assert!("to == null"
+            .parse::<Filter>()
+            .unwrap()
+            .matches(&transaction(None, U256::ZERO)));
+    }
+}
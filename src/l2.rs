@@ -0,0 +1,533 @@
+//! Rollups (OP Stack and Arbitrum chains), whose blocks carry transactions of
+//! types that Ethereum doesn't know along with details of where they sit on L1,
+//! and transactions of types that nothing here knows
+use std::fmt;
+
+use alloy::{
+    consensus::transaction::Recovered,
+    network::{
+        AnyHeader, AnyRpcBlock, AnyTransactionReceipt, AnyTxEnvelope,
+        UnknownTxEnvelope,
+    },
+    primitives::{
+        address, Address, BlockHash, BlockNumber, Bytes, ChainId, TxHash, B256,
+        U256, U64,
+    },
+    rpc::types::{Block, BlockTransactions, Transaction},
+    serde::{OtherFields, WithOtherFields},
+    sol,
+    sol_types::SolCall,
+};
+use log::{debug, warn};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// EIP-2718 type of OP Stack deposit transactions (i.e., those that
+/// originated on L1)
+pub const DEPOSIT_TX_TYPE: u8 = 0x7e;
+
+/// OP Stack predeploy that each block's first deposit writes the attributes
+/// of its L1 origin to
+const L1_BLOCK_ADDRESS: Address =
+    address!("0x4200000000000000000000000000000000000015");
+
+sol! {
+    /* Bedrock's (ABI-encoded) L1 attributes */
+    function setL1BlockValues(
+        uint64 _number,
+        uint64 _timestamp,
+        uint256 _basefee,
+        bytes32 _hash,
+        uint64 _sequenceNumber,
+        bytes32 _batcherHash,
+        uint256 _l1FeeOverhead,
+        uint256 _l1FeeScalar
+    );
+    /* Ecotone's and Isthmus' (tightly packed) L1 attributes */
+    function setL1BlockValuesEcotone();
+    function setL1BlockValuesIsthmus();
+}
+
+/// Rollup framework that a chain is built with
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Rollup {
+    OpStack,
+    Arbitrum,
+}
+
+impl Rollup {
+    /// The rollup framework that the chain with the given [`ChainId`] is
+    /// known to be built with (if any)
+    pub fn of(chain_id: ChainId) -> Option<Self> {
+        match chain_id {
+            /* OP Mainnet, Base, Zora, Mode, their testnets, and the local
+             * devnet */
+            10 | 8453 | 7777777 | 34443 | 11155420 | 84532 | 999999999
+            | 919 | 901 => Some(Self::OpStack),
+            /* Arbitrum One, Nova, Sepolia, and the local testnode */
+            42161 | 42170 | 421614 | 412346 => Some(Self::Arbitrum),
+            _ => None,
+        }
+    }
+
+    /// Fee (in wei) paid for posting the transaction with the given receipt
+    /// to L1 (if the node reports it)
+    pub fn l1_fee(&self, receipt: &AnyTransactionReceipt) -> Option<U256> {
+        match self {
+            Self::OpStack => field(&receipt.other, "l1Fee"),
+            /* charged as part of the gas used, at the same price */
+            Self::Arbitrum => {
+                field::<U64>(&receipt.other, "gasUsedForL1").map(|gas| {
+                    U256::from(gas) * U256::from(receipt.effective_gas_price)
+                })
+            }
+        }
+    }
+}
+
+impl fmt::Display for Rollup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::OpStack => write!(f, "OP Stack"),
+            Self::Arbitrum => write!(f, "Arbitrum"),
+        }
+    }
+}
+
+/// An OP Stack deposit transaction, which was initiated on L1 (or by the
+/// sequencer, to record the L1 origin) and so carries no signature
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DepositTransaction {
+    pub hash: TxHash,
+    pub block_hash: BlockHash,
+    pub block_number: BlockNumber,
+    /// Index of the transaction within its block
+    pub position: u64,
+    /// Uniquely identifies the L1 event that the deposit came from
+    pub source_hash: B256,
+    pub from: Address,
+    /// Recipient (`None` for contract creations)
+    pub to: Option<Address>,
+    /// ETH (in wei) minted on L2, having been locked on L1
+    pub mint: U256,
+    pub value: U256,
+    pub gas_limit: u64,
+    /// Whether this is a (pre-Regolith) system transaction, which uses no gas
+    pub is_system_tx: bool,
+    pub input: Bytes,
+}
+
+impl DepositTransaction {
+    /// Interpret the given transaction of an unknown type as a deposit sent
+    /// from the given [`Address`], within the given block
+    fn from_unknown(
+        tx: &UnknownTxEnvelope,
+        from: Address,
+        block_hash: BlockHash,
+        block_number: BlockNumber,
+        position: u64,
+    ) -> Option<Self> {
+        let fields = &tx.inner.fields;
+        Some(Self {
+            hash: tx.hash,
+            block_hash,
+            block_number,
+            position,
+            source_hash: field(fields, "sourceHash")?,
+            from,
+            to: field(fields, "to"),
+            mint: field(fields, "mint").unwrap_or_default(),
+            value: field(fields, "value").unwrap_or_default(),
+            gas_limit: field::<U64>(fields, "gas")?.to(),
+            is_system_tx: field(fields, "isSystemTx").unwrap_or_default(),
+            input: field(fields, "input").unwrap_or_default(),
+        })
+    }
+}
+
+/// A transaction of a type that neither Ethereum nor the chain's rollup
+/// framework is known to use (or that couldn't be made sense of), kept as it
+/// was served
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct OtherTransaction {
+    pub hash: TxHash,
+    pub block_hash: BlockHash,
+    pub block_number: BlockNumber,
+    /// Index of the transaction within its block
+    pub position: u64,
+    /// EIP-2718 type
+    pub tx_type: u8,
+    pub from: Address,
+    /// Every other field of the transaction, as served by the node
+    pub fields: OtherFields,
+}
+
+/// The L1 block that an L2 block was derived from (or, on Arbitrum, the
+/// latest L1 block that the sequencer had seen)
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize,
+)]
+pub struct L1Origin {
+    /// Hash of the L2 block
+    pub block_hash: BlockHash,
+    pub number: BlockNumber,
+    /// Hash of the L1 block (not reported by Arbitrum)
+    pub hash: Option<BlockHash>,
+    pub timestamp: Option<u64>,
+    /// Position of the L2 block among those derived from the same L1 block
+    /// (OP Stack only)
+    pub sequence_number: Option<u64>,
+}
+
+impl L1Origin {
+    /// Decode the L1 attributes written by the first deposit of the L2 block
+    /// with the given [`BlockHash`] (in any of the OP Stack's formats)
+    fn from_attributes(block_hash: BlockHash, input: &[u8]) -> Option<Self> {
+        let selector = input.get(..4)?;
+        if selector == setL1BlockValuesCall::SELECTOR {
+            let call = setL1BlockValuesCall::abi_decode(input).ok()?;
+            Some(Self {
+                block_hash,
+                number: call._number,
+                hash: Some(call._hash),
+                timestamp: Some(call._timestamp),
+                sequence_number: Some(call._sequenceNumber),
+            })
+        } else if selector == setL1BlockValuesEcotoneCall::SELECTOR
+            || selector == setL1BlockValuesIsthmusCall::SELECTOR
+        {
+            let u64_at = |offset: usize| {
+                input
+                    .get(offset..offset + 8)
+                    .map(|bytes| u64::from_be_bytes(bytes.try_into().unwrap()))
+            };
+            Some(Self {
+                block_hash,
+                number: u64_at(28)?,
+                hash: Some(B256::from_slice(input.get(100..132)?)),
+                timestamp: Some(u64_at(20)?),
+                sequence_number: Some(u64_at(12)?),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// Everything peculiar to rollups (or otherwise unknown to Ethereum) about a
+/// single L2 block
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct L2Block {
+    pub block_hash: BlockHash,
+    pub l1_origin: Option<L1Origin>,
+    /// OP Stack deposit transactions (in order)
+    pub deposits: Vec<DepositTransaction>,
+    /// Transactions of unknown types (in order)
+    pub others: Vec<OtherTransaction>,
+}
+
+impl L2Block {
+    /// Whether there's nothing rollup-specific about the block (e.g., as it's
+    /// not from a rollup at all)
+    pub fn is_empty(&self) -> bool {
+        self.l1_origin.is_none()
+            && self.deposits.is_empty()
+            && self.others.is_empty()
+    }
+}
+
+/// A block served by any EVM chain, split into the Ethereum [`Block`] (which
+/// holds only the transactions of types that Ethereum knows) and whatever's
+/// peculiar to rollups
+#[derive(Clone, Debug)]
+pub struct AnyBlock {
+    pub block: Block,
+    pub l2: L2Block,
+}
+
+impl AnyBlock {
+    /// Split the given full block, as served by a chain built with the given
+    /// [`Rollup`] framework (if any)
+    ///
+    /// Transactions of types that neither Ethereum nor the rollup are known
+    /// to use are kept as [`OtherTransaction`]s.
+    pub fn new(block: AnyRpcBlock, rollup: Option<Rollup>) -> Self {
+        let WithOtherFields {
+            inner: block,
+            other,
+        } = block.0;
+        let block = block.map_header(|header| {
+            header.map(|header| {
+                header
+                    .try_into_header()
+                    .unwrap_or_else(AnyHeader::into_header_with_defaults)
+            })
+        });
+        let (hash, number) = (block.header.hash, block.header.number);
+        let mut l2 = L2Block {
+            block_hash: hash,
+            ..Default::default()
+        };
+        if rollup == Some(Rollup::Arbitrum) {
+            l2.l1_origin =
+                field::<U64>(&other, "l1BlockNumber").map(|l1_number| {
+                    L1Origin {
+                        block_hash: hash,
+                        number: l1_number.to(),
+                        ..Default::default()
+                    }
+                });
+        }
+
+        let mut transactions = vec![];
+        for (position, tx) in block.transactions.into_transactions().enumerate()
+        {
+            let Transaction {
+                inner,
+                block_hash,
+                block_number,
+                transaction_index,
+                effective_gas_price,
+            } = tx.into_inner();
+            let (envelope, from) = inner.into_parts();
+            let unknown = match envelope {
+                AnyTxEnvelope::Ethereum(envelope) => {
+                    transactions.push(Transaction {
+                        inner: Recovered::new_unchecked(envelope, from),
+                        block_hash,
+                        block_number,
+                        transaction_index,
+                        effective_gas_price,
+                    });
+                    continue;
+                }
+                AnyTxEnvelope::Unknown(unknown) => unknown,
+            };
+            let (tx_hash, ty) = (unknown.hash, unknown.inner.ty.0);
+            let position = transaction_index.unwrap_or(position as u64);
+            let deposit =
+                if rollup == Some(Rollup::OpStack) && ty == DEPOSIT_TX_TYPE {
+                    let deposit = DepositTransaction::from_unknown(
+                        &unknown, from, hash, number, position,
+                    );
+                    if deposit.is_none() {
+                        warn!("Deposit transaction {tx_hash} is malformed");
+                    }
+                    deposit
+                } else {
+                    debug!("Transaction {tx_hash} is of unknown type {ty:#x}");
+                    None
+                };
+            let Some(deposit) = deposit else {
+                l2.others.push(OtherTransaction {
+                    hash: tx_hash,
+                    block_hash: hash,
+                    block_number: number,
+                    position,
+                    tx_type: ty,
+                    from,
+                    fields: unknown.inner.fields,
+                });
+                continue;
+            };
+            /* the sequencer's deposit of the L1 attributes always comes
+             * first */
+            if position == 0 && deposit.to == Some(L1_BLOCK_ADDRESS) {
+                l2.l1_origin = L1Origin::from_attributes(hash, &deposit.input);
+            }
+            l2.deposits.push(deposit);
+        }
+
+        Self {
+            block: Block {
+                header: block.header,
+                uncles: block.uncles,
+                transactions: BlockTransactions::Full(transactions),
+                withdrawals: block.withdrawals,
+            },
+            l2,
+        }
+    }
+}
+
+/// Deserialize the given field of a JSON-RPC object (if it's present and
+/// well-formed)
+fn field<T: DeserializeOwned>(fields: &OtherFields, key: &str) -> Option<T> {
+    fields.get_deserialized(key).and_then(Result::ok)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::hex;
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_utils::eip1559_transaction;
+
+    /// Ecotone L1 attributes for L1 block #0x1234 (sequence number 3)
+    fn ecotone_attributes(l1_hash: B256) -> Bytes {
+        let mut input = setL1BlockValuesEcotoneCall::SELECTOR.to_vec();
+        input.extend([0; 8]); /* fee scalars */
+        input.extend(3u64.to_be_bytes());
+        input.extend(1_700_000_000u64.to_be_bytes());
+        input.extend(0x1234u64.to_be_bytes());
+        input.extend([0; 64]); /* base fees */
+        input.extend(l1_hash);
+        input.extend([0; 32]); /* batcher hash */
+        input.into()
+    }
+
+    fn op_block(l1_hash: B256) -> AnyRpcBlock {
+        let hash = BlockHash::with_last_byte(1);
+        let zero = B256::ZERO;
+        let mut eip1559 = eip1559_transaction(
+            TxHash::with_last_byte(2),
+            Address::with_last_byte(1),
+            0,
+        );
+        for (field, value) in [
+            ("blockHash", json!(hash)),
+            ("blockNumber", json!("0x10")),
+            ("transactionIndex", json!("0x1")),
+            ("chainId", json!("0xa")),
+            ("maxFeePerGas", json!("0x2")),
+            ("to", json!(Address::with_last_byte(2))),
+            ("value", json!("0x1")),
+        ] {
+            eip1559[field] = value;
+        }
+        serde_json::from_value(json!({
+            "hash": hash,
+            "parentHash": zero,
+            "sha3Uncles": zero,
+            "miner": Address::ZERO,
+            "stateRoot": zero,
+            "transactionsRoot": zero,
+            "receiptsRoot": zero,
+            "logsBloom": format!("0x{}", "0".repeat(512)),
+            "difficulty": "0x0",
+            "number": "0x10",
+            "gasLimit": "0x1c9c380",
+            "gasUsed": "0x0",
+            "timestamp": "0x6553f100",
+            "extraData": "0x",
+            "mixHash": zero,
+            "nonce": "0x0000000000000000",
+            "baseFeePerGas": "0x1",
+            "uncles": [],
+            "transactions": [
+                {
+                    "type": "0x7e",
+                    "hash": TxHash::with_last_byte(1),
+                    "blockHash": hash,
+                    "blockNumber": "0x10",
+                    "transactionIndex": "0x0",
+                    "from": "0xdeaddeaddeaddeaddeaddeaddeaddeaddead0001",
+                    "to": L1_BLOCK_ADDRESS,
+                    "sourceHash": B256::with_last_byte(9),
+                    "mint": "0x0",
+                    "value": "0x0",
+                    "gas": "0xf4240",
+                    "gasPrice": "0x0",
+                    "nonce": "0x10",
+                    "isSystemTx": false,
+                    "input": ecotone_attributes(l1_hash),
+                    "depositReceiptVersion": "0x1",
+                },
+                eip1559,
+            ],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_selectors() {
+        assert_eq!(setL1BlockValuesCall::SELECTOR, hex!("015d8eb9"));
+        assert_eq!(setL1BlockValuesEcotoneCall::SELECTOR, hex!("440a5e20"));
+        assert_eq!(setL1BlockValuesIsthmusCall::SELECTOR, hex!("098999be"));
+    }
+
+    #[test]
+    fn test_op_stack_block() {
+        let l1_hash = B256::with_last_byte(7);
+        let AnyBlock { block, l2 } =
+            AnyBlock::new(op_block(l1_hash), Rollup::of(10));
+
+        assert_eq!(block.transactions.len(), 1);
+        assert_eq!(
+            block.transactions.txns().next().unwrap().transaction_index,
+            Some(1)
+        );
+        assert_eq!(l2.deposits.len(), 1);
+        let deposit = &l2.deposits[0];
+        assert_eq!(deposit.position, 0);
+        assert_eq!(deposit.gas_limit, 1_000_000);
+        assert_eq!(deposit.source_hash, B256::with_last_byte(9));
+        assert_eq!(
+            l2.l1_origin,
+            Some(L1Origin {
+                block_hash: block.header.hash,
+                number: 0x1234,
+                hash: Some(l1_hash),
+                timestamp: Some(1_700_000_000),
+                sequence_number: Some(3),
+            })
+        );
+
+        /* the same block from a chain that isn't known to be a rollup, whose
+         * deposit is kept as it was served */
+        let AnyBlock { block, l2 } = AnyBlock::new(op_block(l1_hash), None);
+        assert_eq!(block.transactions.len(), 1);
+        assert!(l2.l1_origin.is_none() && l2.deposits.is_empty());
+        assert_eq!(l2.others.len(), 1);
+        let other = &l2.others[0];
+        assert_eq!(
+            (other.hash, other.position, other.tx_type),
+            (TxHash::with_last_byte(1), 0, DEPOSIT_TX_TYPE)
+        );
+        assert_eq!(
+            other
+                .fields
+                .get_deserialized::<B256>("sourceHash")
+                .unwrap()
+                .ok(),
+            Some(B256::with_last_byte(9))
+        );
+    }
+
+    #[test]
+    fn test_l1_fee() {
+        let receipt = |other: serde_json::Value| -> AnyTransactionReceipt {
+            let mut receipt = json!({
+                "type": "0x2",
+                "status": "0x1",
+                "cumulativeGasUsed": "0x5208",
+                "logs": [],
+                "logsBloom": format!("0x{}", "0".repeat(512)),
+                "transactionHash": TxHash::ZERO,
+                "transactionIndex": "0x1",
+                "blockHash": BlockHash::ZERO,
+                "blockNumber": "0x10",
+                "gasUsed": "0x5208",
+                "effectiveGasPrice": "0x3",
+                "from": Address::ZERO,
+                "to": Address::ZERO,
+                "contractAddress": null,
+            });
+            receipt
+                .as_object_mut()
+                .unwrap()
+                .extend(other.as_object().unwrap().clone());
+            serde_json::from_value(receipt).unwrap()
+        };
+
+        assert_eq!(
+            Rollup::OpStack.l1_fee(&receipt(json!({"l1Fee": "0x64"}))),
+            Some(U256::from(100))
+        );
+        assert_eq!(
+            Rollup::Arbitrum.l1_fee(&receipt(json!({"gasUsedForL1": "0xa"}))),
+            Some(U256::from(30))
+        );
+        assert_eq!(Rollup::OpStack.l1_fee(&receipt(json!({}))), None);
+    }
+}
@@ -0,0 +1,114 @@
+//! Times every request made to RPC nodes, by method, recording whether each
+//! one failed
+use std::{
+    sync::{Arc, OnceLock},
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use alloy::{
+    rpc::json_rpc::{RequestPacket, ResponsePacket},
+    transports::{TransportError, TransportFut},
+};
+use eyre::eyre;
+use tower::{Layer, Service};
+
+use crate::metrics::Metrics;
+
+/// Metrics that every client in the process reports to (if installed)
+static METRICS: OnceLock<Arc<Metrics>> = OnceLock::new();
+
+/// Report the requests made by every client from now on to the given
+/// [`Metrics`]
+pub fn install(metrics: Arc<Metrics>) -> eyre::Result<()> {
+    METRICS
+        .set(metrics)
+        .map_err(|_| eyre!("Latency metrics already installed"))
+}
+
+/// Transport layer reporting each request to the installed [`Metrics`] (if
+/// any)
+pub fn layer() -> LatencyLayer {
+    LatencyLayer {
+        metrics: METRICS.get().cloned(),
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LatencyLayer {
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl<S> Layer<S> for LatencyLayer {
+    type Service = LatencyService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LatencyService {
+            inner,
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+/// Transport that times each request (or batch of requests) that it passes
+/// on
+#[derive(Clone, Debug)]
+pub struct LatencyService<S> {
+    inner: S,
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl<S> Service<RequestPacket> for LatencyService<S>
+where
+    S: Service<
+            RequestPacket,
+            Response = ResponsePacket,
+            Error = TransportError,
+            Future = TransportFut<'static>,
+        > + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let metrics = self.metrics.clone();
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            let Some(metrics) = metrics else {
+                return inner.call(request).await;
+            };
+
+            let start = Instant::now();
+            let response = inner.call(request.clone()).await;
+            let duration = start.elapsed();
+
+            /* each request in a batch is attributed the whole batch's
+             * duration, as that's how long its caller waited */
+            request.requests().iter().for_each(|request| {
+                let failed = match &response {
+                    Ok(response) => response
+                        .responses()
+                        .iter()
+                        .find(|response| response.id == *request.id())
+                        .is_some_and(|response| response.is_error()),
+                    Err(_) => true,
+                };
+                metrics.observe_rpc(request.method(), duration, failed);
+            });
+
+            response
+        })
+    }
+}
@@ -0,0 +1,314 @@
+//! Log sinks for headless operation
+use std::{
+    fmt,
+    io::Write,
+    net::{TcpStream, ToSocketAddrs, UdpSocket},
+    str::FromStr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use chrono::{SecondsFormat, Utc};
+use clap::ValueEnum;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use pretty_env_logger::env_logger;
+use serde_json::json;
+
+const APP_NAME: &str = "blocktop";
+const SYSLOG_FACILITY_DAEMON: u8 = 3;
+const DEFAULT_SYSLOG_PORT: u16 = 514;
+/// How long connecting (or writing) to a syslog server over TCP may take
+/// before giving up, so that an unresponsive server can't hang logging
+const SYSLOG_TCP_TIMEOUT: Duration = Duration::from_secs(2);
+/// Minimum delay between attempts at reconnecting to a syslog server over TCP
+const SYSLOG_RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How logs written to standard error are formatted
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// Transport protocol used to reach a syslog server
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SyslogProtocol {
+    Udp,
+    Tcp,
+}
+
+/// Address of a remote syslog server (e.g., `udp://logs.example.com:514`)
+///
+/// If no scheme is given, UDP is assumed. If no port is given, the standard
+/// syslog port (514) is assumed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SyslogAddr {
+    pub protocol: SyslogProtocol,
+    pub host: String,
+    pub port: u16,
+}
+
+impl fmt::Display for SyslogAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.protocol {
+            SyslogProtocol::Udp => {
+                write!(f, "udp://{}:{}", self.host, self.port)
+            }
+            SyslogProtocol::Tcp => {
+                write!(f, "tcp://{}:{}", self.host, self.port)
+            }
+        }
+    }
+}
+
+impl FromStr for SyslogAddr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (protocol, rest) = if let Some(rest) = s.strip_prefix("udp://") {
+            (SyslogProtocol::Udp, rest)
+        } else if let Some(rest) = s.strip_prefix("tcp://") {
+            (SyslogProtocol::Tcp, rest)
+        } else if s.contains("://") {
+            return Err("Unsupported syslog transport".to_string());
+        } else {
+            (SyslogProtocol::Udp, s)
+        };
+
+        let (host, port) = match rest.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse()
+                    .map_err(|_| "Invalid syslog port".to_string())?,
+            ),
+            None => (rest, DEFAULT_SYSLOG_PORT),
+        };
+
+        if host.is_empty() {
+            return Err("Missing syslog host".to_string());
+        }
+
+        Ok(Self {
+            protocol,
+            host: host.to_string(),
+            port,
+        })
+    }
+}
+
+enum SyslogTransport {
+    Udp(UdpSocket),
+    Tcp {
+        stream: Option<TcpStream>,
+        /// When connecting was last attempted
+        attempted: Instant,
+    },
+}
+
+/// [`Log`] implementation emitting RFC 5424 messages to a remote syslog
+/// server
+pub struct SyslogLogger {
+    addr: SyslogAddr,
+    hostname: String,
+    level: LevelFilter,
+    transport: Mutex<SyslogTransport>,
+}
+
+impl SyslogLogger {
+    /// Connect to the syslog server at the given [`SyslogAddr`]
+    pub fn connect(addr: SyslogAddr, level: LevelFilter) -> eyre::Result<Self> {
+        let target = (addr.host.as_str(), addr.port);
+        let transport = match addr.protocol {
+            SyslogProtocol::Udp => {
+                let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+                socket.connect(target)?;
+                SyslogTransport::Udp(socket)
+            }
+            SyslogProtocol::Tcp => SyslogTransport::Tcp {
+                stream: Some(connect_tcp(&addr)?),
+                attempted: Instant::now(),
+            },
+        };
+
+        Ok(Self {
+            addr,
+            hostname: hostname(),
+            level,
+            transport: Mutex::new(transport),
+        })
+    }
+
+    fn format(&self, record: &Record) -> String {
+        let severity = match record.level() {
+            Level::Error => 3,
+            Level::Warn => 4,
+            Level::Info => 6,
+            Level::Debug | Level::Trace => 7,
+        };
+        format!(
+            "<{}>1 {} {} {} {} - - {}: {}",
+            SYSLOG_FACILITY_DAEMON * 8 + severity,
+            Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+            self.hostname,
+            APP_NAME,
+            std::process::id(),
+            record.target(),
+            record.args()
+        )
+    }
+}
+
+impl Log for SyslogLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let message = self.format(record);
+        let Ok(mut transport) = self.transport.lock() else {
+            return;
+        };
+
+        /* there's nowhere sensible to report failures to, so drop them */
+        match &mut *transport {
+            SyslogTransport::Udp(socket) => {
+                let _ = socket.send(message.as_bytes());
+            }
+            SyslogTransport::Tcp { stream, attempted } => {
+                /* RFC 6587 octet-counted framing */
+                let frame = format!("{} {}", message.len(), message);
+
+                /* records logged while the server is unreachable are
+                 * dropped rather than each waiting on a fresh attempt */
+                if stream.is_none()
+                    && attempted.elapsed() >= SYSLOG_RECONNECT_INTERVAL
+                {
+                    *attempted = Instant::now();
+                    *stream = connect_tcp(&self.addr).ok();
+                }
+
+                if let Some(conn) = stream {
+                    if conn.write_all(frame.as_bytes()).is_err() {
+                        *stream = None;
+                    }
+                }
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut transport) = self.transport.lock() {
+            if let SyslogTransport::Tcp {
+                stream: Some(stream),
+                ..
+            } = &mut *transport
+            {
+                let _ = stream.flush();
+            }
+        }
+    }
+}
+
+/// Connect to the syslog server at the given [`SyslogAddr`] over TCP, giving
+/// up on each of its addresses after [`SYSLOG_TCP_TIMEOUT`]
+fn connect_tcp(addr: &SyslogAddr) -> std::io::Result<TcpStream> {
+    let mut last_error = None;
+    for target in (addr.host.as_str(), addr.port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&target, SYSLOG_TCP_TIMEOUT) {
+            Ok(stream) => {
+                stream.set_write_timeout(Some(SYSLOG_TCP_TIMEOUT))?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("no addresses for {}", addr.host),
+        )
+    }))
+}
+
+/// Forwards every record to each of its constituent loggers
+struct TeeLogger {
+    loggers: Vec<Box<dyn Log>>,
+}
+
+impl Log for TeeLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.loggers.iter().any(|logger| logger.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        self.loggers
+            .iter()
+            .filter(|logger| logger.enabled(record.metadata()))
+            .for_each(|logger| logger.log(record));
+    }
+
+    fn flush(&self) {
+        self.loggers.iter().for_each(|logger| logger.flush());
+    }
+}
+
+/// Install the global logger
+///
+/// Logs are always written to standard error (filtered according to
+/// `RUST_LOG`) in the given [`LogFormat`] and, if a [`SyslogAddr`] is
+/// provided, additionally forwarded to that syslog server at the same
+/// verbosity.
+pub fn init(format: LogFormat, syslog: Option<SyslogAddr>) -> eyre::Result<()> {
+    let mut builder = match format {
+        LogFormat::Text => pretty_env_logger::formatted_timed_builder(),
+        LogFormat::Json => {
+            let mut builder = env_logger::Builder::new();
+            builder.format(|buf, record| {
+                writeln!(
+                    buf,
+                    "{}",
+                    json!({
+                        "timestamp": Utc::now()
+                            .to_rfc3339_opts(SecondsFormat::Millis, true),
+                        "level": record.level().as_str(),
+                        "target": record.target(),
+                        "message": record.args().to_string(),
+                    })
+                )
+            });
+            builder
+        }
+    };
+    let stderr = builder.parse_default_env().build();
+    let level = stderr.filter();
+    let mut loggers: Vec<Box<dyn Log>> = vec![Box::new(stderr)];
+
+    if let Some(ref addr) = syslog {
+        loggers.push(Box::new(SyslogLogger::connect(addr.clone(), level)?));
+    }
+
+    log::set_boxed_logger(Box::new(TeeLogger { loggers }))?;
+    log::set_max_level(level);
+
+    if let Some(addr) = syslog {
+        log::info!("Forwarding logs to syslog server at {addr}");
+    }
+
+    Ok(())
+}
+
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "-".to_string())
+}
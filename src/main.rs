@@ -1,27 +1,93 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
-use alloy::primitives::Address;
-use clap::Parser;
-use client::{AnyClient, Client};
-use log::warn;
+use alloy::{
+    eips::{BlockHashOrNumber, BlockId},
+    json_abi::JsonAbi,
+    primitives::{keccak256, Address, Selector, TxHash, B256},
+    providers::Provider,
+};
+use clap::{CommandFactory, FromArgMatches};
+use client::{AnyClient, Client, Endpoints};
+use crossterm::event::{
+    DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste,
+    EnableMouseCapture,
+};
+use eyre::eyre;
+use log::{info, warn};
 use metrics::Metrics;
 use serde::Deserialize;
-use services::metrics::MetricsService;
+use services::{
+    http::{HttpService, Routes},
+    Supervisor,
+};
 
 use crate::{
-    cli::Opts,
+    cli::{Command, Opts},
+    config::Config,
     db::{Database, Location},
-    services::blockchain::BlockchainService,
-    ui::run,
+    l2::AnyBlock,
+    logging::LogFormat,
+    rate_limit::RateLimiter,
+    services::{
+        beacon::BeaconService,
+        blobs::BlobService,
+        blockchain::{
+            logs_and_statuses, BlockchainService, IndexerState, ResetPolicy,
+        },
+        contracts::ContractService,
+        devchain::DevChainService,
+        mempool::MempoolService,
+        race::RaceService,
+        rawtx::RawTransactionService,
+        retrieval::RetrievalService,
+        signals::SignalService,
+        simulator::SimulatorService,
+        template::TemplateService,
+        tracer::TracerService,
+        tracker::{await_finality, await_inclusion, broadcast, TrackerService},
+        txpool::TxpoolService,
+        watchlist::{WatchlistService, DEFAULT_STUCK_AFTER},
+    },
+    sinks::{AlertSink, AnySink, FilterSink, NdjsonSink, SinkSpec},
+    storage::{AnyStorage, PostgresDatabase, Storage},
+    transport::Credentials,
+    ui::{run, theme::Theme, Services, Settings, Startup, DEFAULT_TICK_RATE},
+    verification::{AnyVerifier, Etherscan, Sourcify},
 };
 
+pub mod abi;
 pub mod cli;
 pub mod client;
+pub mod config;
 pub mod db;
+pub mod evm;
+pub mod export;
+pub mod filter;
+pub mod l2;
+pub mod latency;
+pub mod logging;
 pub mod metrics;
+pub mod query;
+pub mod rate_limit;
 pub mod services;
+pub mod sinks;
+pub mod snapshot;
+pub mod stats;
+pub mod storage;
+#[cfg(test)]
+mod test_utils;
+pub mod tls;
+pub mod transport;
 pub mod ui;
 pub mod utils;
+pub mod verification;
 
 #[allow(dead_code)]
 #[derive(Clone, Debug, Deserialize)]
@@ -34,56 +100,282 @@ struct LabelEntry {
     pub name_tag: Option<String>,
 }
 
+/// Exit status of `--await-tx` (or `send`) when the transaction isn't
+/// finalized (or included) in time
+const AWAIT_TIMEOUT_EXIT_CODE: i32 = 2;
 const LABELS_JSON_DATA: &str = include_str!("../assets/labels/mainnet.json");
+const EVENT_SIGNATURES_JSON_DATA: &str =
+    include_str!("../assets/signatures/events.json");
+const FUNCTION_SIGNATURES_JSON_DATA: &str =
+    include_str!("../assets/signatures/functions.json");
 
 lazy_static::lazy_static! {
-    static ref ADDRESS_LABELS: HashMap<Address, String> = {
-        let labels: Vec<LabelEntry> = serde_json::from_str(LABELS_JSON_DATA).expect("Invalid JSON data for address labels");
-        labels.iter().filter(|label| label.name_tag.is_some()).map(|label| (label.address, label.name_tag.clone().unwrap())).collect()
+    static ref ADDRESS_LABELS: RwLock<HashMap<Address, String>> =
+        RwLock::new(parse_labels(LABELS_JSON_DATA).expect("Invalid JSON data for address labels"));
+    /// ABIs of verified contracts (for decoding calldata and logs)
+    static ref CONTRACT_ABIS: RwLock<HashMap<Address, JsonAbi>> =
+        RwLock::new(HashMap::new());
+    static ref EVENT_SIGNATURES: HashMap<B256, String> = {
+        let signatures: Vec<String> = serde_json::from_str(EVENT_SIGNATURES_JSON_DATA).expect("Invalid JSON data for event signatures");
+        signatures.into_iter().map(|signature| (keccak256(&signature), signature)).collect()
+    };
+    static ref FUNCTION_SIGNATURES: HashMap<Selector, String> = {
+        let signatures: Vec<String> = serde_json::from_str(FUNCTION_SIGNATURES_JSON_DATA).expect("Invalid JSON data for function signatures");
+        signatures.into_iter().map(|signature| (Selector::from_slice(&keccak256(&signature)[..4]), signature)).collect()
     };
 }
 
-/// Retrieve an initial block from the endpoint so that upon UI startup there's data to render
+/// Parse address labels from JSON in the same format as the bundled label data
+fn parse_labels(json: &str) -> eyre::Result<HashMap<Address, String>> {
+    let labels: Vec<LabelEntry> = serde_json::from_str(json)?;
+    Ok(labels
+        .into_iter()
+        .filter_map(|label| Some((label.address, label.name_tag?)))
+        .collect())
+}
+
+/// Load additional address labels from the given file, overriding any
+/// existing labels for the same addresses
+fn load_labels(path: &Path) -> eyre::Result<()> {
+    let labels = parse_labels(&std::fs::read_to_string(path)?)
+        .map_err(|e| eyre!("Invalid label file {}: {e}", path.display()))?;
+    let count = labels.len();
+    ADDRESS_LABELS
+        .write()
+        .map_err(|_| eyre!("Address labels lock poisoned"))?
+        .extend(labels);
+    info!("Loaded {} address labels from {}", count, path.display());
+    Ok(())
+}
+
+/// Retrieve an initial block (either the one given, the one containing the
+/// given transaction, or else the latest) from the endpoint so that there's
+/// data to render
 #[allow(clippy::needless_question_mark)] /* clippy gets this wrong */
-async fn populate_db(opts: &Opts, db: &mut Database) -> eyre::Result<()> {
-    let rpc = opts.rpc.clone();
-    let perhaps_block = opts.block;
-    let perhaps_tx = opts.transaction;
-    let client = AnyClient::new(rpc).await?;
-
-    match (perhaps_block, perhaps_tx) {
-        (Some(block), None) => {
-            Ok(db.add_block(&client.block(block.into()).await?)?)
+async fn populate_db(
+    perhaps_block: Option<BlockHashOrNumber>,
+    perhaps_tx: Option<TxHash>,
+    endpoints: &Endpoints,
+    db: &AnyStorage,
+) -> eyre::Result<()> {
+    let client = AnyClient::connect(endpoints).await?;
+
+    /* if the chain was reset, leave it to the indexer to deal with that
+     * before writing anything from the new chain */
+    if let Some(previous) = db
+        .sqlite()
+        .map(|db| db.genesis_hash())
+        .transpose()?
+        .flatten()
+    {
+        let genesis = client.block(BlockId::number(0)).await?.header.hash;
+        if genesis != previous {
+            warn!(
+                "Chain was reset (genesis {previous} is now {genesis}), so \
+                not writing an initial block"
+            );
+            return Ok(());
         }
+    }
+
+    let id: BlockId = match (perhaps_block, perhaps_tx) {
+        (Some(block), None) => block.into(),
         (None, Some(tx_hash)) => {
             let tx = client.transaction(tx_hash).await?;
-            /* recall that we *must* have at least one *block* in the db at all times */
-            db.add_block(&client.block(tx.block_hash.unwrap().into()).await?)?;
-            Ok(())
+            tx.block_hash
+                .ok_or(eyre!("Transaction {tx_hash} is still pending"))?
+                .into()
         }
-        _ => Ok(db.add_block(
-            &client
-                .block(alloy::eips::BlockNumberOrTag::Latest.into())
-                .await?,
-        )?),
+        _ => alloy::eips::BlockNumberOrTag::Latest.into(),
+    };
+    let AnyBlock { block, l2 } = client.any_block(id).await?;
+    let receipts = client.block_receipts(block.header.hash.into()).await?;
+    let (logs, statuses) = logs_and_statuses(&receipts, client.chain_id());
+    db.set_chain_id(client.chain_id())?;
+    db.add_block(&block)?;
+    if !l2.is_empty() {
+        db.add_l2_block(&l2)?;
     }
+    db.add_logs(&logs)?;
+    db.add_receipt_statuses(&statuses)?;
+    Ok(())
+}
+
+/// Print the block or transaction specified on the command line as JSON
+async fn print_object(opts: &Opts, endpoints: &Endpoints) -> eyre::Result<()> {
+    let client = AnyClient::connect(endpoints).await?;
+    let json = match (opts.block, opts.transaction) {
+        (Some(block), _) => {
+            serde_json::to_string_pretty(&client.block(block.into()).await?)?
+        }
+        (None, Some(tx_hash)) => {
+            serde_json::to_string_pretty(&client.transaction(tx_hash).await?)?
+        }
+        (None, None) => return Err(eyre!("No block or transaction specified")),
+    };
+    println!("{json}");
+    Ok(())
 }
 
 fn main() -> eyre::Result<()> {
-    let opts: Opts = Opts::parse();
+    let matches = Opts::command().get_matches();
+    let opts = Opts::from_arg_matches(&matches)?;
+    let config = match opts.config {
+        Some(ref path) => Config::load(path)?,
+        None => Config::load_default()?,
+    };
+    let opts = opts.with_config(&matches, &config);
+
+    if !opts.headless && opts.log_syslog.is_some() {
+        return Err(eyre!("Syslog output is only supported in headless mode"));
+    }
+
+    if !opts.headless && opts.log_format == LogFormat::Json {
+        return Err(eyre!(
+            "JSON log output is only supported in headless mode"
+        ));
+    }
 
     if opts.headless {
-        pretty_env_logger::init_timed();
+        logging::init(opts.log_format, opts.log_syslog.clone())?;
+    }
+
+    if !opts.headless && opts.sinks.contains(&SinkSpec::Ndjson) {
+        return Err(eyre!(
+            "The NDJSON sink is only supported in headless mode"
+        ));
+    }
+
+    if !opts.headless && opts.filter.is_some() {
+        return Err(eyre!("Filters are only supported in headless mode"));
     }
 
-    if opts.headless && opts.db.is_none() {
+    /* alerts are otherwise only logged, which the TUI doesn't do */
+    if !opts.headless && !opts.alert.is_empty() && opts.notify.is_empty() {
+        return Err(eyre!(
+            "Alerts require a notifier (see --notify) outside of headless mode"
+        ));
+    }
+
+    if opts.headless && !opts.is_one_shot() && opts.db.is_none() {
         warn!("Headless mode without specifying an on-disk database. All data will be lost on exit.");
     }
 
-    let mut db: Database = Database::new(match opts.db {
-        Some(ref file) => Location::Disk(file.to_path_buf()),
-        None => Location::Memory,
-    })?;
+    if opts.no_default_labels {
+        ADDRESS_LABELS
+            .write()
+            .map_err(|_| eyre!("Address labels lock poisoned"))?
+            .clear();
+    }
+
+    opts.labels.iter().try_for_each(|path| load_labels(path))?;
+
+    let metrics = Arc::new(Metrics::new());
+    latency::install(metrics.clone())?;
+
+    if let Some(rate) = opts.rpc_rate_limit {
+        rate_limit::install(RateLimiter::new(
+            rate,
+            opts.rpc_burst.unwrap_or(rate),
+            metrics.throttled_rpc_requests.clone(),
+        ))?;
+    }
+
+    let endpoints = Arc::new(Endpoints::new(
+        opts.rpc.clone(),
+        opts.fallback_rpc.clone(),
+        Credentials::new(opts.rpc_jwt_secret.as_deref(), &opts.rpc_header)?,
+        metrics.rpc_failovers.clone(),
+    ));
+
+    /* every service runs as a task on this one runtime, which needs to be
+     * entered for them to be spawned */
+    let runtime = tokio::runtime::Runtime::new()?;
+    let _guard = runtime.enter();
+
+    if let Some(hash) = opts.await_tx {
+        let finalized = runtime.block_on(async {
+            await_finality(
+                &AnyClient::connect(&endpoints).await?,
+                hash,
+                opts.await_timeout.map(Duration::from_secs),
+                &metrics,
+                |status| println!("{status}"),
+            )
+            .await
+        })?;
+        std::process::exit(if finalized {
+            0
+        } else {
+            AWAIT_TIMEOUT_EXIT_CODE
+        });
+    }
+
+    if let Some(Command::Send { raw, timeout }) = &opts.command {
+        let included = runtime.block_on(async {
+            let client = AnyClient::connect(&endpoints).await?;
+            let hash = broadcast(&client, raw, &metrics).await?;
+            println!("{hash}");
+
+            if !await_inclusion(
+                &client,
+                hash,
+                timeout.map(Duration::from_secs),
+                &metrics,
+                |status| println!("{status}"),
+            )
+            .await?
+            {
+                return Ok::<_, eyre::Report>(false);
+            }
+
+            let receipt = client
+                .provider()
+                .get_transaction_receipt(hash)
+                .await?
+                .ok_or(eyre!("Receipt for {hash} is unavailable"))?;
+            println!("{}", serde_json::to_string_pretty(&receipt)?);
+            Ok(true)
+        })?;
+        std::process::exit(if included { 0 } else { AWAIT_TIMEOUT_EXIT_CODE });
+    }
+
+    if opts.command.is_none() && opts.is_one_shot() {
+        return runtime.block_on(print_object(&opts, &endpoints));
+    }
+
+    let db: AnyStorage = match opts.db {
+        Some(ref spec)
+            if AnyStorage::is_postgres_uri(&spec.to_string_lossy()) =>
+        {
+            if !opts.headless && opts.command.is_none() {
+                return Err(eyre!(
+                    "PostgreSQL storage is only supported in headless mode"
+                ));
+            }
+            AnyStorage::Postgres(PostgresDatabase::new(
+                &spec.to_string_lossy(),
+            )?)
+        }
+        Some(ref file) => AnyStorage::Sqlite(Database::with_pragmas(
+            Location::Disk(file.to_path_buf()),
+            opts.pragmas(),
+        )?),
+        None => AnyStorage::Sqlite(Database::with_pragmas(
+            Location::Memory,
+            opts.pragmas(),
+        )?),
+    };
+
+    if let Some(Command::Query { query, json }) = &opts.command {
+        let found = runtime.block_on(query.run(&db, &endpoints))?;
+        if *json {
+            println!("{}", serde_json::to_string_pretty(&found)?);
+        } else {
+            println!("{}", found.text());
+        }
+        return Ok(());
+    }
 
     if opts.list_block_hashes {
         db.all_block_hashes()?
@@ -91,32 +383,317 @@ fn main() -> eyre::Result<()> {
             .for_each(|hash| println!("{hash}"));
     }
 
-    let metrics = Arc::new(Metrics::new());
+    if let Some(path) = &opts.dump_snapshot {
+        let range = opts.snapshot_range.clone();
+        let count = if path.as_os_str() == "-" {
+            snapshot::dump(&db, range, &mut std::io::stdout().lock())?
+        } else {
+            snapshot::dump(
+                &db,
+                range,
+                &mut BufWriter::new(File::create(path)?),
+            )?
+        };
+        info!("Dumped {count} blocks to snapshot");
+        return Ok(());
+    }
 
-    /* wet the database */
-    tokio::task::block_in_place(|| {
-        tokio::runtime::Runtime::new()
-            .unwrap()
-            .block_on(async { populate_db(&opts, &mut db).await })
-    })?;
+    if let Some(path) = &opts.load_snapshot {
+        let count = if path.as_os_str() == "-" {
+            snapshot::load(&db, std::io::stdin().lock())?
+        } else {
+            snapshot::load(&db, BufReader::new(File::open(path)?))?
+        };
+        info!("Loaded {count} blocks from snapshot");
+        return Ok(());
+    }
 
-    let blockchain =
-        BlockchainService::spawn(opts.rpc.clone(), db.clone(), metrics.clone());
+    if let Some(Command::Export {
+        table,
+        from,
+        to,
+        format,
+        output,
+    }) = &opts.command
+    {
+        if from > to {
+            return Err(eyre!("Block range {from}..{to} is empty"));
+        }
 
-    if opts.metrics {
-        let _metrics_service = MetricsService::spawn(
-            ([0, 0, 0, 0], opts.port().unwrap()).into(),
-            metrics.clone(),
+        let (table, range, format) = (*table, *from..=*to, *format);
+        let count = match output {
+            Some(path) => export::export(
+                &db,
+                table,
+                range,
+                format,
+                &mut BufWriter::new(File::create(path)?),
+            )?,
+            None => export::export(
+                &db,
+                table,
+                range,
+                format,
+                &mut std::io::stdout().lock(),
+            )?,
+        };
+        info!("Exported {count} {table}");
+        return Ok(());
+    }
+
+    if let Some(range) = opts.builder_stats.clone() {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&db.builder_stats(range)?)?
         );
+        return Ok(());
+    }
+
+    /* wet the database, which the TUI doesn't wait for so as to appear
+     * straight away however slow the RPC node is (without the RPC node,
+     * whatever was indexed previously is shown, while the indexer keeps
+     * trying to reconnect in the background) */
+    let startup = Arc::new(RwLock::new(Startup::default()));
+    let populating = {
+        let (block, transaction) = (opts.block, opts.transaction);
+        let (endpoints, db, startup) =
+            (endpoints.clone(), db.clone(), startup.clone());
+        async move {
+            let populated =
+                populate_db(block, transaction, &endpoints, &db).await;
+            if let Err(e) = &populated {
+                warn!("Failed to retrieve initial block from RPC node, starting offline: {e:?}");
+            }
+            if let Ok(mut startup) = startup.write() {
+                *startup = match populated {
+                    Ok(()) => Startup::Done,
+                    Err(e) => Startup::Failed(format!("{e:#}")),
+                };
+            }
+        }
+    };
+    if opts.headless {
+        runtime.block_on(populating);
+    } else {
+        runtime.spawn(populating);
+    }
+
+    let mut sinks = opts
+        .sinks()
+        .iter()
+        .map(|spec| AnySink::new(spec, &db))
+        .collect::<eyre::Result<Vec<_>>>()?;
+
+    /* a filter narrows down the summaries that would otherwise be printed
+     * to just the matching transactions (both being headless-only, as
+     * checked above) */
+    if let Some(filter) = opts.filter.clone() {
+        sinks.push(AnySink::Filter(FilterSink::new(
+            filter,
+            opts.log_format == LogFormat::Json,
+            metrics.filter_matches.clone(),
+        )));
+    } else if opts.log_format == LogFormat::Json {
+        sinks.push(AnySink::Ndjson(NdjsonSink::summaries()));
+    }
+
+    if !opts.alert.is_empty() {
+        if opts.notify.is_empty() {
+            warn!("No notifiers given, so alerts will only be logged");
+        }
+
+        sinks.push(AnySink::Alerts(AlertSink::new(
+            opts.alert.clone(),
+            opts.notify.clone(),
+        )));
     }
 
+    /* services are shut down in reverse order of registration, so signals
+     * are still handled (i.e., a second one forces an exit) until the end */
+    let mut supervisor = Supervisor::default();
+    let (signals, handle) = SignalService::spawn();
+    supervisor.register(handle);
+
+    if let Some(local_node) = opts.local_node.clone() {
+        supervisor.register(TemplateService::spawn(
+            local_node,
+            db.clone(),
+            metrics.clone(),
+        ));
+    }
+
+    if let Some(cl_rpc) = opts.cl_rpc.clone() {
+        supervisor.register(BeaconService::spawn(
+            cl_rpc,
+            db.clone(),
+            metrics.clone(),
+        ));
+    }
+
+    if opts.metrics || opts.serve {
+        let tls = match (&opts.tls_cert, &opts.tls_key) {
+            (Some(cert), Some(key)) => Some(tls::acceptor(cert, key)?),
+            _ => None,
+        };
+        let routes = Routes {
+            metrics: opts.metrics.then(|| metrics.registry.clone()),
+            api: opts.serve.then(|| db.clone()),
+        };
+        supervisor.register(HttpService::spawn(
+            opts.socket_addr()
+                .expect("invariant violated: HTTP services must have a port"),
+            tls,
+            routes,
+        )?);
+    }
+
+    let indexer =
+        Arc::new(IndexerState::new(endpoints.clone()).with_reset_policy(
+            match opts.on_chain_reset {
+                Some(action) => ResetPolicy::Act(action),
+                /* there's nobody to ask when headless */
+                None if opts.headless => ResetPolicy::Stop,
+                None => ResetPolicy::Ask,
+            },
+        ));
+    supervisor.register(BlockchainService::spawn(
+        sinks,
+        metrics.clone(),
+        indexer.clone(),
+    ));
+
     if !opts.headless {
+        let (tracker, handle) =
+            TrackerService::spawn(endpoints.clone(), metrics.clone());
+        supervisor.register(handle);
+        let (devchain, handle) =
+            DevChainService::spawn(endpoints.clone(), metrics.clone());
+        supervisor.register(handle);
+        let (tracer, handle) =
+            TracerService::spawn(endpoints.clone(), metrics.clone());
+        supervisor.register(handle);
+        let (simulator, handle) = SimulatorService::spawn(endpoints.clone());
+        supervisor.register(handle);
+        let (rawtx, handle) =
+            RawTransactionService::spawn(endpoints.clone(), metrics.clone());
+        supervisor.register(handle);
+        let (mempool, handle) =
+            MempoolService::spawn(endpoints.clone(), metrics.clone());
+        supervisor.register(handle);
+        let (txpool, handle) =
+            TxpoolService::spawn(endpoints.clone(), metrics.clone());
+        supervisor.register(handle);
+        let race = endpoints.has_fallbacks().then(|| {
+            let (race, handle) =
+                RaceService::spawn(endpoints.clone(), metrics.clone());
+            supervisor.register(handle);
+            race
+        });
+        let blobs = opts.cl_rpc.clone().map(|cl_rpc| {
+            let (blobs, handle) = BlobService::spawn(cl_rpc, metrics.clone());
+            supervisor.register(handle);
+            blobs
+        });
+        let db = db
+            .sqlite()
+            .expect("invariant violated: TUI requires SQLite")
+            .clone();
+        /* Sourcify is preferred as it needs no API key */
+        let verifiers = opts
+            .sourcify
+            .clone()
+            .map(|url| AnyVerifier::Sourcify(Sourcify::new(url)))
+            .into_iter()
+            .chain(
+                opts.etherscan_api_key
+                    .clone()
+                    .map(|key| AnyVerifier::Etherscan(Etherscan::new(key))),
+            )
+            .collect();
+        let (retrieval, handle) = RetrievalService::spawn(
+            endpoints.clone(),
+            db.clone(),
+            metrics.clone(),
+        );
+        supervisor.register(handle);
+        let (contracts, handle) = ContractService::spawn(
+            endpoints.clone(),
+            verifiers,
+            db.clone(),
+            metrics.clone(),
+        );
+        supervisor.register(handle);
+        /* addresses watched from within the TUI are remembered across
+         * sessions, unlike those given as options */
+        let (watchlist, handle) = WatchlistService::spawn(
+            endpoints.clone(),
+            &indexer,
+            db.clone(),
+            opts.watch.iter().copied().chain(db.watched_addresses()?),
+            metrics.clone(),
+        );
+        supervisor.register(handle);
+        let services = Services {
+            metrics: opts.metrics.then(|| metrics.clone()),
+            rpc_health: metrics.rpc_health.clone(),
+            startup,
+            tracker,
+            indexer,
+            devchain,
+            tracer,
+            simulator,
+            rawtx,
+            retrieval,
+            contracts,
+            blobs,
+            mempool,
+            txpool,
+            race,
+            watchlist,
+            signals,
+        };
+        let settings = Settings {
+            keymap: config.keymap(),
+            theme: Theme::new(opts.theme.unwrap_or_default()),
+            tick_rate: opts
+                .tick_rate
+                .map(|tick_rate| Duration::from_millis(tick_rate.get()))
+                .unwrap_or(DEFAULT_TICK_RATE),
+            set_title: opts.set_title,
+            stuck_after: opts.stuck_after.unwrap_or(DEFAULT_STUCK_AFTER),
+        };
         let terminal = ratatui::init();
-        let result = run(terminal, &db, opts.block, opts.transaction);
+        crossterm::execute!(
+            std::io::stdout(),
+            EnableMouseCapture,
+            EnableBracketedPaste
+        )?;
+        /* the TUI loop blocks on terminal events, so it gets a thread of its
+         * own rather than tying up one of the runtime's workers */
+        let ui = tokio::task::spawn_blocking(move || {
+            run(
+                terminal,
+                &db,
+                opts.block,
+                opts.transaction,
+                services,
+                settings,
+            )
+        });
+        let result = runtime
+            .block_on(ui)
+            .unwrap_or_else(|_| Err(eyre!("TUI panicked")));
+        /* as with restoring the terminal itself, this is best effort */
+        let _ = crossterm::execute!(
+            std::io::stdout(),
+            DisableMouseCapture,
+            DisableBracketedPaste
+        );
         ratatui::restore();
+        supervisor.shutdown()?;
         result
     } else {
-        let _ = blockchain.join();
-        Ok(())
+        supervisor.wait(&signals);
+        supervisor.shutdown()
     }
 }
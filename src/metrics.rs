@@ -1,12 +1,151 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, VecDeque},
+    future::IntoFuture,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
 
-use prometheus::{IntGauge, Opts, Registry};
+use alloy::rpc::types::Block;
+
+use prometheus::{
+    Gauge, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec,
+    IntGauge, Opts, Registry,
+};
+
+use crate::utils::BuilderIdentity;
+
+/// How far back [`RpcHealth`] looks
+pub const RPC_HEALTH_WINDOW: Duration = Duration::from_secs(300);
+/// Maximum number of requests remembered by [`RpcHealth`] at once (the oldest
+/// are forgotten first)
+const MAX_RPC_SAMPLES: usize = 16_384;
+
+/// A single request made to the RPC node
+#[derive(Clone, Debug)]
+struct RpcSample {
+    at: Instant,
+    method: String,
+    duration: Duration,
+    failed: bool,
+}
+
+/// Latency and error rate of a single RPC method over the
+/// [`RPC_HEALTH_WINDOW`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MethodHealth {
+    pub method: String,
+    pub requests: usize,
+    pub failures: usize,
+    pub p50: Duration,
+    pub p95: Duration,
+}
+
+impl MethodHealth {
+    /// Proportion of requests that failed
+    pub fn error_rate(&self) -> f64 {
+        self.failures as f64 / self.requests.max(1) as f64
+    }
+}
+
+/// The most recent requests made to the RPC node, from which percentiles
+/// can be taken (unlike Prometheus histograms, which accumulate forever)
+#[derive(Debug, Default)]
+pub struct RpcHealth {
+    samples: VecDeque<RpcSample>,
+}
+
+impl RpcHealth {
+    fn record(
+        &mut self,
+        method: &str,
+        duration: Duration,
+        failed: bool,
+        now: Instant,
+    ) {
+        self.samples.push_back(RpcSample {
+            at: now,
+            method: method.to_string(),
+            duration,
+            failed,
+        });
+        while self.samples.len() > MAX_RPC_SAMPLES
+            || self.samples.front().is_some_and(|sample| {
+                now.saturating_duration_since(sample.at) > RPC_HEALTH_WINDOW
+            })
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Health of each method requested within the [`RPC_HEALTH_WINDOW`]
+    /// before the given time, most requested first
+    pub fn summary(&self, now: Instant) -> Vec<MethodHealth> {
+        let mut methods: HashMap<&str, (Vec<Duration>, usize)> = HashMap::new();
+        self.samples
+            .iter()
+            .filter(|sample| {
+                now.saturating_duration_since(sample.at) <= RPC_HEALTH_WINDOW
+            })
+            .for_each(|sample| {
+                let (durations, failures) =
+                    methods.entry(&sample.method).or_default();
+                durations.push(sample.duration);
+                *failures += sample.failed as usize;
+            });
+
+        let mut summary: Vec<MethodHealth> = methods
+            .into_iter()
+            .map(|(method, (mut durations, failures))| {
+                durations.sort();
+                /* nearest rank */
+                let percentile = |p: usize| {
+                    durations[(durations.len() * p).div_ceil(100).max(1) - 1]
+                };
+                MethodHealth {
+                    method: method.to_string(),
+                    requests: durations.len(),
+                    failures,
+                    p50: percentile(50),
+                    p95: percentile(95),
+                }
+            })
+            .collect();
+        summary.sort_by(|a, b| {
+            b.requests.cmp(&a.requests).then(a.method.cmp(&b.method))
+        });
+        summary
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct Metrics {
     pub rpc_requests: Arc<IntGauge>,
     pub blocks_added: Arc<IntGauge>,
     pub failed_rpc_requests: Arc<IntGauge>,
+    pub throttled_rpc_requests: Arc<IntCounter>,
+    pub rpc_failovers: Arc<IntCounter>,
+    pub reorg_count: Arc<IntGauge>,
+    pub max_reorg_depth: Arc<IntGauge>,
+    pub missing_blocks: Arc<IntGauge>,
+    pub chain_head: Arc<IntGauge>,
+    pub head_lag_seconds: Arc<Gauge>,
+    pub db_size_bytes: Arc<IntGauge>,
+    pub mempool_size: Arc<IntGauge>,
+    pub rpc_requests_by_method: Arc<IntCounterVec>,
+    pub rpc_request_seconds: Arc<HistogramVec>,
+    pub rpc_errors_by_method: Arc<IntCounterVec>,
+    /// Requests made to the RPC node over the most recent few minutes
+    pub rpc_health: Arc<Mutex<RpcHealth>>,
+    pub block_processing_seconds: Arc<Histogram>,
+    pub block_base_fee_wei: Arc<Gauge>,
+    pub block_gas_used_ratio: Arc<Gauge>,
+    pub block_transactions: Arc<IntGauge>,
+    pub block_blob_gas_used: Arc<IntGauge>,
+    pub blocks_by_builder: Arc<IntCounterVec>,
+    pub filter_matches: Arc<IntCounter>,
+    pub ui_frame_render_seconds: Arc<Histogram>,
+    pub ui_tick_seconds: Arc<Histogram>,
+    pub ui_db_query_seconds: Arc<Histogram>,
     pub registry: Arc<Registry>,
 }
 
@@ -27,6 +166,129 @@ impl Metrics {
             "The number of requests made to the RPC node that have received an error response",
         ))
         .expect("Invalid rpc_requests gauge definition");
+        let throttled_rpc_requests = IntCounter::with_opts(Opts::new(
+            "throttled_rpc_requests",
+            "The number of requests to the RPC node that were delayed by the rate limit",
+        ))
+        .expect("Invalid throttled_rpc_requests counter definition");
+        let rpc_failovers = IntCounter::with_opts(Opts::new(
+            "rpc_failovers",
+            "The number of times that the RPC endpoint in use was abandoned for the next one",
+        ))
+        .expect("Invalid rpc_failovers counter definition");
+        let reorg_count = IntGauge::with_opts(Opts::new(
+            "reorg_count",
+            "The number of chain reorganisations observed",
+        ))
+        .expect("Invalid reorg_count gauge definition");
+        let max_reorg_depth = IntGauge::with_opts(Opts::new(
+            "max_reorg_depth",
+            "The number of blocks orphaned by the deepest chain reorganisation observed",
+        ))
+        .expect("Invalid max_reorg_depth gauge definition");
+        let missing_blocks = IntGauge::with_opts(Opts::new(
+            "missing_blocks",
+            "The number of blocks missing between the lowest and highest indexed blocks",
+        ))
+        .expect("Invalid missing_blocks gauge definition");
+        let chain_head = IntGauge::with_opts(Opts::new(
+            "chain_head",
+            "The number of the latest block announced by the RPC node",
+        ))
+        .expect("Invalid chain_head gauge definition");
+        let head_lag_seconds = Gauge::with_opts(Opts::new(
+            "head_lag_seconds",
+            "How far behind wall-clock time the latest block was when it was indexed",
+        ))
+        .expect("Invalid head_lag_seconds gauge definition");
+        let db_size_bytes = IntGauge::with_opts(Opts::new(
+            "db_size_bytes",
+            "The size of the SQLite database",
+        ))
+        .expect("Invalid db_size_bytes gauge definition");
+        let mempool_size = IntGauge::with_opts(Opts::new(
+            "mempool_size",
+            "The number of pending transactions currently remembered",
+        ))
+        .expect("Invalid mempool_size gauge definition");
+        let rpc_requests_by_method = IntCounterVec::new(
+            Opts::new(
+                "rpc_requests_by_method",
+                "The number of requests made to the RPC node, by method",
+            ),
+            &["method"],
+        )
+        .expect("Invalid rpc_requests_by_method counter definition");
+        let rpc_request_seconds = HistogramVec::new(
+            HistogramOpts::new(
+                "rpc_request_seconds",
+                "The time taken by requests made to the RPC node, by method",
+            ),
+            &["method"],
+        )
+        .expect("Invalid rpc_request_seconds histogram definition");
+        let rpc_errors_by_method = IntCounterVec::new(
+            Opts::new(
+                "rpc_errors_by_method",
+                "The number of requests made to the RPC node that failed, by method",
+            ),
+            &["method"],
+        )
+        .expect("Invalid rpc_errors_by_method counter definition");
+        let block_processing_seconds =
+            Histogram::with_opts(HistogramOpts::new(
+                "block_processing_seconds",
+                "The time taken to index a single block (including its receipts)",
+            ))
+            .expect("Invalid block_processing_seconds histogram definition");
+        let block_base_fee_wei = Gauge::with_opts(Opts::new(
+            "block_base_fee_wei",
+            "The base fee per gas of the latest block",
+        ))
+        .expect("Invalid block_base_fee_wei gauge definition");
+        let block_gas_used_ratio = Gauge::with_opts(Opts::new(
+            "block_gas_used_ratio",
+            "The proportion of the latest block's gas limit that was used",
+        ))
+        .expect("Invalid block_gas_used_ratio gauge definition");
+        let block_transactions = IntGauge::with_opts(Opts::new(
+            "block_transactions",
+            "The number of transactions in the latest block",
+        ))
+        .expect("Invalid block_transactions gauge definition");
+        let block_blob_gas_used = IntGauge::with_opts(Opts::new(
+            "block_blob_gas_used",
+            "The blob gas used by the latest block",
+        ))
+        .expect("Invalid block_blob_gas_used gauge definition");
+        let blocks_by_builder = IntCounterVec::new(
+            Opts::new(
+                "blocks_by_builder",
+                "The number of new blocks observed, by builder",
+            ),
+            &["builder"],
+        )
+        .expect("Invalid blocks_by_builder counter definition");
+        let filter_matches = IntCounter::with_opts(Opts::new(
+            "filter_matches",
+            "The number of indexed transactions that matched the filter",
+        ))
+        .expect("Invalid filter_matches counter definition");
+        let ui_frame_render_seconds = Histogram::with_opts(HistogramOpts::new(
+            "ui_frame_render_seconds",
+            "The time taken to render a single frame of the TUI",
+        ))
+        .expect("Invalid ui_frame_render_seconds histogram definition");
+        let ui_tick_seconds = Histogram::with_opts(HistogramOpts::new(
+            "ui_tick_seconds",
+            "The time taken to process a single tick of the TUI",
+        ))
+        .expect("Invalid ui_tick_seconds histogram definition");
+        let ui_db_query_seconds = Histogram::with_opts(HistogramOpts::new(
+            "ui_db_query_seconds",
+            "The time taken by database queries issued from the TUI thread",
+        ))
+        .expect("Invalid ui_db_query_seconds histogram definition");
         let registry = Registry::new();
         registry
             .register(Box::new(rpc_requests.clone()))
@@ -37,18 +299,244 @@ impl Metrics {
         registry
             .register(Box::new(failed_rpc_requests.clone()))
             .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(reorg_count.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(max_reorg_depth.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(missing_blocks.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(chain_head.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(head_lag_seconds.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(db_size_bytes.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(mempool_size.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(throttled_rpc_requests.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(rpc_failovers.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(rpc_requests_by_method.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(rpc_request_seconds.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(rpc_errors_by_method.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(block_processing_seconds.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(block_base_fee_wei.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(block_gas_used_ratio.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(block_transactions.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(block_blob_gas_used.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(blocks_by_builder.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(filter_matches.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(ui_frame_render_seconds.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(ui_tick_seconds.clone()))
+            .expect("Invalid metrics registry definition");
+        registry
+            .register(Box::new(ui_db_query_seconds.clone()))
+            .expect("Invalid metrics registry definition");
 
         Self {
             rpc_requests: Arc::new(rpc_requests),
             blocks_added: Arc::new(blocks_added),
             failed_rpc_requests: Arc::new(failed_rpc_requests),
+            throttled_rpc_requests: Arc::new(throttled_rpc_requests),
+            rpc_failovers: Arc::new(rpc_failovers),
+            reorg_count: Arc::new(reorg_count),
+            max_reorg_depth: Arc::new(max_reorg_depth),
+            missing_blocks: Arc::new(missing_blocks),
+            chain_head: Arc::new(chain_head),
+            head_lag_seconds: Arc::new(head_lag_seconds),
+            db_size_bytes: Arc::new(db_size_bytes),
+            mempool_size: Arc::new(mempool_size),
+            rpc_requests_by_method: Arc::new(rpc_requests_by_method),
+            rpc_request_seconds: Arc::new(rpc_request_seconds),
+            rpc_errors_by_method: Arc::new(rpc_errors_by_method),
+            rpc_health: Arc::new(Mutex::new(RpcHealth::default())),
+            block_processing_seconds: Arc::new(block_processing_seconds),
+            block_base_fee_wei: Arc::new(block_base_fee_wei),
+            block_gas_used_ratio: Arc::new(block_gas_used_ratio),
+            block_transactions: Arc::new(block_transactions),
+            block_blob_gas_used: Arc::new(block_blob_gas_used),
+            blocks_by_builder: Arc::new(blocks_by_builder),
+            filter_matches: Arc::new(filter_matches),
+            ui_frame_render_seconds: Arc::new(ui_frame_render_seconds),
+            ui_tick_seconds: Arc::new(ui_tick_seconds),
+            ui_db_query_seconds: Arc::new(ui_db_query_seconds),
             registry: Arc::new(registry),
         }
     }
 }
 
+impl Metrics {
+    /// Report the conditions of the chain as of the given (new) block
+    pub fn observe_block(&self, block: &Block) {
+        let header = &block.header;
+        self.block_base_fee_wei
+            .set(header.base_fee_per_gas.unwrap_or_default() as f64);
+        if header.gas_limit > 0 {
+            self.block_gas_used_ratio
+                .set(header.gas_used as f64 / header.gas_limit as f64);
+        }
+        self.block_transactions.set(block.transactions.len() as i64);
+        self.block_blob_gas_used
+            .set(header.blob_gas_used.unwrap_or_default() as i64);
+        self.blocks_by_builder
+            .with_label_values(&[BuilderIdentity::from(
+                header.extra_data.clone(),
+            )
+            .to_string()])
+            .inc();
+    }
+
+    /// Count a request made to the RPC node (using the given method) that
+    /// took the given time, and whether it failed
+    pub fn observe_rpc(&self, method: &str, duration: Duration, failed: bool) {
+        self.rpc_requests.inc();
+        self.rpc_requests_by_method
+            .with_label_values(&[method])
+            .inc();
+        self.rpc_request_seconds
+            .with_label_values(&[method])
+            .observe(duration.as_secs_f64());
+        if failed {
+            self.rpc_errors_by_method.with_label_values(&[method]).inc();
+        }
+        self.rpc_health
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .record(method, duration, failed, Instant::now());
+    }
+
+    /// Health of each method requested of the RPC node over the most recent
+    /// [`RPC_HEALTH_WINDOW`]
+    pub fn rpc_health(&self) -> Vec<MethodHealth> {
+        self.rpc_health
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .summary(Instant::now())
+    }
+
+    /// Make the given request (using the given method) to an HTTP API that
+    /// isn't reached via an RPC client (e.g., the consensus layer's), and so
+    /// isn't observed by the [`crate::latency`] layer, counting and timing it
+    pub async fn rpc<R: IntoFuture<Output = Result<T, E>>, T, E>(
+        &self,
+        method: &str,
+        request: R,
+    ) -> R::Output {
+        let start = Instant::now();
+        let output = request.await;
+        self.observe_rpc(method, start.elapsed(), output.is_err());
+        output
+    }
+}
+
 impl Default for Metrics {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloy::rpc::types::Header;
+
+    use super::*;
+
+    #[test]
+    fn test_observe_block() {
+        let metrics = Metrics::new();
+        let block = Block::empty(Header::new(alloy::consensus::Header {
+            gas_used: 15_000_000,
+            gas_limit: 30_000_000,
+            base_fee_per_gas: Some(7),
+            extra_data: "beaverbuild.org".as_bytes().to_vec().into(),
+            ..Default::default()
+        }));
+        metrics.observe_block(&block);
+        metrics.observe_block(&block);
+
+        assert_eq!(metrics.block_base_fee_wei.get(), 7.0);
+        assert_eq!(metrics.block_gas_used_ratio.get(), 0.5);
+        assert_eq!(
+            metrics
+                .blocks_by_builder
+                .with_label_values(&["beaverbuild"])
+                .get(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_rpc_health() {
+        let mut health = RpcHealth::default();
+        let start = Instant::now();
+        (1..=20).for_each(|i| {
+            health.record(
+                "eth_getBlockByHash",
+                Duration::from_millis(i),
+                i == 20,
+                start,
+            )
+        });
+        health.record("eth_chainId", Duration::from_millis(1), false, start);
+
+        assert_eq!(
+            health.summary(start),
+            vec![
+                MethodHealth {
+                    method: "eth_getBlockByHash".to_string(),
+                    requests: 20,
+                    failures: 1,
+                    p50: Duration::from_millis(10),
+                    p95: Duration::from_millis(19),
+                },
+                MethodHealth {
+                    method: "eth_chainId".to_string(),
+                    requests: 1,
+                    failures: 0,
+                    p50: Duration::from_millis(1),
+                    p95: Duration::from_millis(1),
+                },
+            ]
+        );
+        assert_eq!(health.summary(start)[0].error_rate(), 0.05);
+
+        /* requests age out of the window */
+        let later = start + RPC_HEALTH_WINDOW + Duration::from_secs(1);
+        assert!(health.summary(later).is_empty());
+        health.record("eth_chainId", Duration::from_millis(2), false, later);
+        assert_eq!(health.samples.len(), 1);
+    }
+}
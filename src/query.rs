@@ -0,0 +1,240 @@
+//! Ad hoc lookups of blocks and transactions from the command line
+use alloy::{
+    consensus::Transaction as AbstractTransaction,
+    eips::BlockHashOrNumber,
+    primitives::{Bytes, TxHash},
+    rpc::types::{Block, Transaction},
+};
+use chrono::{TimeZone, Utc};
+use clap::Subcommand;
+use log::debug;
+use serde::Serialize;
+
+use crate::{
+    client::{AnyClient, Client, Endpoints},
+    storage::Storage,
+    utils::{to_ether, to_gwei},
+};
+
+/// Objects that can be looked up
+#[derive(Clone, Debug, Subcommand)]
+pub enum Query {
+    /// Look up a block by hash or number
+    Block { id: BlockHashOrNumber },
+    /// Look up a transaction by hash
+    #[clap(alias = "transaction")]
+    Tx { hash: TxHash },
+    /// Retrieve the EIP-2718 encoding of a transaction (e.g., to broadcast it
+    /// again) from the RPC node
+    #[clap(name = "rawtx", alias = "raw-tx")]
+    RawTx { hash: TxHash },
+}
+
+/// The result of a [`Query`]
+#[derive(Clone, Debug, Serialize)]
+#[serde(untagged)]
+pub enum Found {
+    Block(Box<Block>),
+    Transaction(Box<Transaction>),
+    RawTransaction(Bytes),
+}
+
+impl Query {
+    /// Look this up in the given storage, falling back to the RPC node at the
+    /// given [`Endpoints`] if it isn't indexed
+    pub async fn run(
+        &self,
+        storage: &impl Storage,
+        endpoints: &Endpoints,
+    ) -> eyre::Result<Found> {
+        if let Some(found) = self.indexed(storage)? {
+            return Ok(found);
+        }
+
+        debug!("{self:?} isn't indexed, asking the RPC node");
+        let client = AnyClient::connect(endpoints).await?;
+        Ok(match self {
+            Self::Block { id } => {
+                Found::Block(Box::new(client.block((*id).into()).await?))
+            }
+            Self::Tx { hash } => {
+                Found::Transaction(Box::new(client.transaction(*hash).await?))
+            }
+            Self::RawTx { hash } => {
+                Found::RawTransaction(client.raw_transaction(*hash).await?)
+            }
+        })
+    }
+
+    fn indexed(&self, storage: &impl Storage) -> eyre::Result<Option<Found>> {
+        let found = match self {
+            Self::Block {
+                id: BlockHashOrNumber::Hash(hash),
+            } => storage
+                .block_by_hash(*hash)?
+                .map(Box::new)
+                .map(Found::Block),
+            Self::Block {
+                id: BlockHashOrNumber::Number(number),
+            } => storage
+                .block_by_number(*number)?
+                .map(Box::new)
+                .map(Found::Block),
+            Self::Tx { hash } => storage
+                .transaction(*hash)?
+                .map(Box::new)
+                .map(Found::Transaction),
+            /* signatures aren't indexed, so only the node can say */
+            Self::RawTx { .. } => None,
+        };
+        Ok(found)
+    }
+}
+
+impl Found {
+    /// Labelled fields summarising this object
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Self::Block(block) => {
+                let header = &block.header;
+                vec![
+                    ("Number", header.number.to_string()),
+                    ("Hash", header.hash.to_string()),
+                    ("Parent", header.parent_hash.to_string()),
+                    (
+                        "Time",
+                        Utc.timestamp_opt(header.timestamp as i64, 0)
+                            .single()
+                            .map_or(header.timestamp.to_string(), |time| {
+                                time.to_rfc3339()
+                            }),
+                    ),
+                    ("Fee recipient", header.beneficiary.to_string()),
+                    (
+                        "Gas used",
+                        format!("{} / {}", header.gas_used, header.gas_limit),
+                    ),
+                    (
+                        "Base fee",
+                        header
+                            .base_fee_per_gas
+                            .map_or("-".to_string(), |fee| {
+                                format!("{} gwei", to_gwei(fee as f64))
+                            }),
+                    ),
+                    ("Transactions", block.transactions.len().to_string()),
+                ]
+            }
+            Self::Transaction(tx) => vec![
+                ("Hash", tx.inner.tx_hash().to_string()),
+                (
+                    "Block",
+                    match (tx.block_number, tx.block_hash) {
+                        (Some(number), Some(hash)) => {
+                            format!("#{number} ({hash})")
+                        }
+                        _ => "pending".to_string(),
+                    },
+                ),
+                (
+                    "Index",
+                    tx.transaction_index
+                        .map_or("-".to_string(), |index| index.to_string()),
+                ),
+                ("From", tx.as_recovered().signer().to_string()),
+                (
+                    "To",
+                    tx.to().map_or("(contract creation)".to_string(), |to| {
+                        to.to_string()
+                    }),
+                ),
+                ("Nonce", tx.nonce().to_string()),
+                ("Value", format!("{} ETH", to_ether(tx.value()))),
+                ("Gas limit", tx.gas_limit().to_string()),
+                (
+                    "Max fee",
+                    format!("{} gwei", to_gwei(tx.max_fee_per_gas() as f64)),
+                ),
+                ("Input", tx.input().to_string()),
+            ],
+            Self::RawTransaction(raw) => vec![("Raw", raw.to_string())],
+        }
+    }
+
+    /// Render this object as aligned `Field: value` lines (or, for a raw
+    /// transaction, as bare hex that can be piped elsewhere)
+    pub fn text(&self) -> String {
+        if let Self::RawTransaction(raw) = self {
+            return raw.to_string();
+        }
+        let fields = self.fields();
+        let width =
+            fields.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        fields
+            .iter()
+            .map(|(name, value)| {
+                format!(
+                    "{:width$} {value}",
+                    format!("{name}:"),
+                    width = width + 1
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{primitives::BlockHash, rpc::types::Header};
+
+    use super::*;
+    use crate::db::{Database, Location};
+
+    #[test]
+    fn test_indexed_block() {
+        let db = Database::new(Location::Memory).unwrap();
+        let hash = BlockHash::with_last_byte(7);
+        db.headers()
+            .add(&Header {
+                hash,
+                inner: alloy::consensus::Header {
+                    number: 7,
+                    gas_used: 21_000,
+                    gas_limit: 30_000_000,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .unwrap();
+
+        for id in [BlockHashOrNumber::Number(7), BlockHashOrNumber::Hash(hash)]
+        {
+            let text =
+                Query::Block { id }.indexed(&db).unwrap().unwrap().text();
+            assert!(text.starts_with("Number:        7\n"));
+            assert!(text.contains(&format!("Hash:          {hash}")));
+            assert!(text.contains("Gas used:      21000 / 30000000"));
+        }
+
+        assert!(Query::Block {
+            id: BlockHashOrNumber::Number(8)
+        }
+        .indexed(&db)
+        .unwrap()
+        .is_none());
+    }
+
+    #[test]
+    fn test_raw_transaction() {
+        let db = Database::new(Location::Memory).unwrap();
+        assert!(Query::RawTx { hash: TxHash::ZERO }
+            .indexed(&db)
+            .unwrap()
+            .is_none());
+
+        let found = Found::RawTransaction(Bytes::from_static(&[0x02, 0xf8]));
+        assert_eq!(found.text(), "0x02f8");
+        assert_eq!(serde_json::to_string(&found).unwrap(), "\"0x02f8\"");
+    }
+}
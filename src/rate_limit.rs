@@ -0,0 +1,191 @@
+//! Limits the rate of requests made to RPC nodes (e.g., to stay within the
+//! quota of a hosted provider)
+use std::{
+    num::NonZeroU32,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    rpc::json_rpc::{RequestPacket, ResponsePacket},
+    transports::{TransportError, TransportFut},
+};
+use eyre::eyre;
+use prometheus::IntCounter;
+use tower::{Layer, Service};
+
+/// Rate limiter shared by every client in the process (if one is installed)
+static RATE_LIMITER: OnceLock<Arc<RateLimiter>> = OnceLock::new();
+
+/// Token bucket limiting the rate of requests
+///
+/// Requests beyond the burst size borrow tokens that have yet to accrue, and
+/// wait for them to do so, which queues them in the order they were made.
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Requests permitted per second (on average)
+    rate: f64,
+    /// Requests permitted at once after a lull
+    burst: f64,
+    bucket: Mutex<Bucket>,
+    throttled: Arc<IntCounter>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Negative whilst requests are waiting
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    /// Permit the given number of requests per second on average, and up to
+    /// the given burst of requests at once, counting those that have to
+    /// wait
+    pub fn new(
+        rate: NonZeroU32,
+        burst: NonZeroU32,
+        throttled: Arc<IntCounter>,
+    ) -> Self {
+        Self {
+            rate: rate.get().into(),
+            burst: burst.get().into(),
+            bucket: Mutex::new(Bucket {
+                tokens: burst.get().into(),
+                updated: Instant::now(),
+            }),
+            throttled,
+        }
+    }
+
+    /// Take tokens for the given number of requests, returning how long to
+    /// wait before making them
+    fn reserve(&self, requests: usize, now: Instant) -> Duration {
+        /* the bucket is left consistent at every step, so a panic elsewhere
+         * whilst holding the lock doesn't matter */
+        let mut bucket =
+            self.bucket.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = now.max(bucket.updated);
+        let elapsed = (now - bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+        bucket.updated = now;
+        bucket.tokens -= requests as f64;
+
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.rate)
+        }
+    }
+
+    /// Wait until the given number of requests may be made
+    pub async fn acquire(&self, requests: usize) {
+        let wait = self.reserve(requests, Instant::now());
+
+        if !wait.is_zero() {
+            self.throttled.inc_by(requests as u64);
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Limit the rate of requests made by every client from now on
+pub fn install(limiter: RateLimiter) -> eyre::Result<()> {
+    RATE_LIMITER
+        .set(Arc::new(limiter))
+        .map_err(|_| eyre!("Rate limiter already installed"))
+}
+
+/// Transport layer subjecting requests to the installed [`RateLimiter`] (if
+/// any)
+pub fn layer() -> RateLimitLayer {
+    RateLimitLayer {
+        limiter: RATE_LIMITER.get().cloned(),
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RateLimitLayer {
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+/// Transport that waits on a [`RateLimiter`] before passing each request (or
+/// batch of requests) on
+#[derive(Clone, Debug)]
+pub struct RateLimitService<S> {
+    inner: S,
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+impl<S> Service<RequestPacket> for RateLimitService<S>
+where
+    S: Service<
+            RequestPacket,
+            Response = ResponsePacket,
+            Error = TransportError,
+            Future = TransportFut<'static>,
+        > + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let limiter = self.limiter.clone();
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            if let Some(limiter) = limiter {
+                limiter.acquire(request.len()).await;
+            }
+            inner.call(request).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve() {
+        let limiter = RateLimiter::new(
+            NonZeroU32::new(10).unwrap(),
+            NonZeroU32::new(5).unwrap(),
+            Arc::new(IntCounter::new("throttled", "throttled").unwrap()),
+        );
+        let start = Instant::now();
+
+        assert_eq!(limiter.reserve(5, start), Duration::ZERO);
+        assert_eq!(limiter.reserve(1, start), Duration::from_millis(100));
+        assert_eq!(limiter.reserve(2, start), Duration::from_millis(300));
+
+        /* tokens accrue whilst the queue drains, but no more than the burst
+         * size can build up */
+        let later = start + Duration::from_secs(10);
+        assert_eq!(limiter.reserve(5, later), Duration::ZERO);
+        assert_eq!(limiter.reserve(1, later), Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,409 @@
+//! Follows the beacon chain via a consensus layer node's HTTP API, recording
+//! the slot, epoch, and proposer of each execution block
+use std::{sync::Arc, time::Duration};
+
+use alloy::{
+    eips::eip4844::VERSIONED_HASH_VERSION_KZG,
+    primitives::{BlockHash, BlockNumber, Bytes, B256},
+};
+use eyre::eyre;
+use log::{debug, error, info};
+use reqwest::StatusCode;
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::{
+    client::redacted,
+    db::BeaconBlock,
+    metrics::Metrics,
+    services::ServiceHandle,
+    storage::{AnyStorage, Storage},
+};
+
+const POLL_INTERVAL_MILLIS: u64 = 4_000; /* a third of a mainnet slot */
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+/// Most slots retrieved at once to catch up on those missed between polls
+/// (e.g., whilst the node was unreachable)
+const MAX_CATCH_UP_SLOTS: u64 = 64;
+
+/// Handle to the beacon chain following service
+#[derive(Clone, Debug)]
+pub struct BeaconService {
+    client: BeaconClient,
+}
+
+impl BeaconService {
+    /// Spawn a new instance of the beacon chain following service
+    ///
+    /// Polls the consensus layer node reachable at the provided [`Url`] for
+    /// new beacon blocks, recording where each execution block sits on the
+    /// beacon chain to the provided [`AnyStorage`].
+    pub fn spawn(
+        cl_rpc: Url,
+        db: AnyStorage,
+        metrics: Arc<Metrics>,
+    ) -> ServiceHandle {
+        ServiceHandle::spawn("beacon", move |mut shutdown| async move {
+            let this = Self {
+                client: BeaconClient::new(cl_rpc),
+            };
+            let mut interval = tokio::time::interval(Duration::from_millis(
+                POLL_INTERVAL_MILLIS,
+            ));
+            let mut slots_per_epoch = None;
+            let mut last_slot: Option<u64> = None;
+
+            loop {
+                tokio::select! {
+                    _ = shutdown.changed() => return Ok(()),
+                    _ = interval.tick() => {}
+                }
+
+                let slots_per_epoch = match slots_per_epoch {
+                    Some(t) => t,
+                    None => match metrics
+                        .rpc("beacon_getSpec", this.client.spec())
+                        .await
+                    {
+                        Ok(spec) => {
+                            info!(
+                                "Following the beacon chain via {} ({} slots per epoch)",
+                                redacted(&this.client.url),
+                                spec.slots_per_epoch
+                            );
+                            *slots_per_epoch.insert(spec.slots_per_epoch)
+                        }
+                        Err(e) => {
+                            error!("Failed to retrieve chain specification from consensus layer node: {e:?}");
+                            metrics.failed_rpc_requests.inc();
+                            continue;
+                        }
+                    },
+                };
+
+                let head = match metrics
+                    .rpc("beacon_getBlockHeader", this.client.head_slot())
+                    .await
+                {
+                    Ok(t) => t,
+                    Err(e) => {
+                        error!("Failed to retrieve head slot from consensus layer node: {e:?}");
+                        metrics.failed_rpc_requests.inc();
+                        continue;
+                    }
+                };
+                let from = last_slot
+                    .map_or(head, |slot| slot + 1)
+                    .max(head.saturating_sub(MAX_CATCH_UP_SLOTS - 1));
+
+                for slot in from..=head {
+                    let block = match metrics
+                        .rpc("beacon_getBlindedBlock", this.client.block(slot))
+                        .await
+                    {
+                        Ok(t) => t,
+                        Err(e) => {
+                            error!("Failed to retrieve beacon block for slot {slot}: {e:?}");
+                            metrics.failed_rpc_requests.inc();
+                            break;
+                        }
+                    };
+                    last_slot = Some(slot);
+
+                    /* empty slots, and those from before the merge, don't
+                     * carry execution blocks */
+                    let Some(block) =
+                        block.and_then(|block| block.beacon(slots_per_epoch))
+                    else {
+                        continue;
+                    };
+                    db.add_beacon_block(&block).inspect_err(|e| {
+                        error!(
+                            "Failed to write beacon block to database: {e:?}"
+                        )
+                    })?;
+                    debug!(
+                        "Block #{} was proposed in slot {} by validator {}",
+                        block.block_number, block.slot, block.proposer_index
+                    );
+                }
+            }
+        })
+    }
+}
+
+/// Minimal client for the standard beacon node HTTP API
+#[derive(Clone, Debug)]
+pub struct BeaconClient {
+    url: Url,
+    client: reqwest::Client,
+}
+
+/// Every response from the beacon node API wraps its payload like this
+#[derive(Debug, Deserialize)]
+struct Envelope<T> {
+    data: T,
+}
+
+/// The parts of the chain specification that matter here
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub struct Spec {
+    #[serde(deserialize_with = "decimal")]
+    pub slots_per_epoch: u64,
+    #[serde(deserialize_with = "decimal")]
+    pub seconds_per_slot: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Genesis {
+    #[serde(deserialize_with = "decimal")]
+    genesis_time: u64,
+}
+
+/// Blob (and its KZG commitment and proof) as sent alongside a beacon block
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct BlobSidecar {
+    /// Position of the blob within its beacon block
+    #[serde(deserialize_with = "decimal")]
+    pub index: u64,
+    pub blob: Bytes,
+    pub kzg_commitment: Bytes,
+    pub kzg_proof: Bytes,
+}
+
+impl BlobSidecar {
+    /// Hash by which transactions refer to this blob (as per EIP-4844)
+    pub fn versioned_hash(&self) -> B256 {
+        let mut hash: [u8; 32] = Sha256::digest(&self.kzg_commitment).into();
+        hash[0] = VERSIONED_HASH_VERSION_KZG;
+        hash.into()
+    }
+
+    /// Length of the blob without any trailing zeroes (i.e., padding)
+    pub fn data_len(&self) -> usize {
+        self.blob
+            .iter()
+            .rposition(|&byte| byte != 0)
+            .map_or(0, |i| i + 1)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct HeaderResponse {
+    header: SignedHeader,
+}
+
+#[derive(Debug, Deserialize)]
+struct SignedHeader {
+    message: HeaderMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct HeaderMessage {
+    #[serde(deserialize_with = "decimal")]
+    slot: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct SignedBlindedBlock {
+    message: BlindedBlock,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlindedBlock {
+    #[serde(deserialize_with = "decimal")]
+    slot: u64,
+    #[serde(deserialize_with = "decimal")]
+    proposer_index: u64,
+    body: BlindedBlockBody,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlindedBlockBody {
+    /* absent prior to the merge */
+    execution_payload_header: Option<PayloadHeader>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PayloadHeader {
+    block_hash: BlockHash,
+    #[serde(deserialize_with = "decimal")]
+    block_number: BlockNumber,
+}
+
+impl SignedBlindedBlock {
+    /// Where the execution block carried by this beacon block (if any) sits
+    /// on the beacon chain
+    fn beacon(self, slots_per_epoch: u64) -> Option<BeaconBlock> {
+        let payload = self.message.body.execution_payload_header?;
+        Some(BeaconBlock {
+            block_hash: payload.block_hash,
+            block_number: payload.block_number,
+            slot: self.message.slot,
+            epoch: self.message.slot / slots_per_epoch,
+            proposer_index: self.message.proposer_index,
+        })
+    }
+}
+
+impl BeaconClient {
+    pub fn new(mut url: Url) -> Self {
+        /* so that paths are joined onto the end of any existing path */
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+
+        Self {
+            url,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+                .build()
+                .expect("invariant violated: invalid HTTP client settings"),
+        }
+    }
+
+    /// Retrieve the payload at the given path, or `None` if the node doesn't
+    /// have it
+    async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+    ) -> eyre::Result<Option<T>> {
+        let response = self.client.get(self.url.join(path)?).send().await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            response
+                .error_for_status()?
+                .json::<Envelope<T>>()
+                .await?
+                .data,
+        ))
+    }
+
+    pub async fn spec(&self) -> eyre::Result<Spec> {
+        let spec: Spec = self
+            .get("eth/v1/config/spec")
+            .await?
+            .ok_or(eyre!("Consensus layer node has no chain specification"))?;
+
+        if spec.slots_per_epoch == 0 || spec.seconds_per_slot == 0 {
+            return Err(eyre!("Invalid chain specification: {spec:?}"));
+        }
+
+        Ok(spec)
+    }
+
+    /// Time of the beacon chain's genesis (in seconds since the Unix epoch)
+    pub async fn genesis_time(&self) -> eyre::Result<u64> {
+        let genesis: Genesis = self
+            .get("eth/v1/beacon/genesis")
+            .await?
+            .ok_or(eyre!("Consensus layer node has no genesis"))?;
+        Ok(genesis.genesis_time)
+    }
+
+    async fn head_slot(&self) -> eyre::Result<u64> {
+        let head: HeaderResponse = self
+            .get("eth/v1/beacon/headers/head")
+            .await?
+            .ok_or(eyre!("Consensus layer node has no head block"))?;
+        Ok(head.header.message.slot)
+    }
+
+    /// Retrieve the (blinded) beacon block proposed in the given slot, or
+    /// `None` if the slot was missed
+    async fn block(
+        &self,
+        slot: u64,
+    ) -> eyre::Result<Option<SignedBlindedBlock>> {
+        self.get(&format!("eth/v1/beacon/blinded_blocks/{slot}"))
+            .await
+    }
+
+    /// Retrieve the blobs sent alongside the beacon block proposed in the
+    /// given slot, or `None` if the slot was missed (or the node has since
+    /// pruned them)
+    pub async fn blob_sidecars(
+        &self,
+        slot: u64,
+    ) -> eyre::Result<Option<Vec<BlobSidecar>>> {
+        self.get(&format!("eth/v1/beacon/blob_sidecars/{slot}"))
+            .await
+    }
+}
+
+/// The beacon node API represents integers as decimal strings
+fn decimal<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<u64, D::Error> {
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_beacon_block() {
+        let block: Envelope<SignedBlindedBlock> = serde_json::from_str(
+            r#"{
+                "version": "deneb",
+                "data": {
+                    "message": {
+                        "slot": "9200001",
+                        "proposer_index": "12345",
+                        "parent_root": "0x0000000000000000000000000000000000000000000000000000000000000000",
+                        "body": {
+                            "execution_payload_header": {
+                                "block_hash": "0x0000000000000000000000000000000000000000000000000000000000000001",
+                                "block_number": "20000000"
+                            }
+                        }
+                    },
+                    "signature": "0x00"
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            block.data.beacon(32),
+            Some(BeaconBlock {
+                block_hash: BlockHash::with_last_byte(1),
+                block_number: 20_000_000,
+                slot: 9_200_001,
+                epoch: 287_500,
+                proposer_index: 12_345,
+            })
+        );
+
+        let block: SignedBlindedBlock = serde_json::from_str(
+            r#"{"message": {"slot": "1", "proposer_index": "2", "body": {}}}"#,
+        )
+        .unwrap();
+        assert_eq!(block.beacon(32), None);
+    }
+
+    #[test]
+    fn test_versioned_hash() {
+        /* the commitment to the empty blob */
+        let sidecar = BlobSidecar {
+            index: 0,
+            blob: Bytes::from(vec![0; 131_072]),
+            kzg_commitment: "0xc00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000".parse().unwrap(),
+            kzg_proof: Bytes::new(),
+        };
+        assert_eq!(
+            sidecar.versioned_hash(),
+            "0x010657f37554c781402a22917dee2f75def7ab966d7b770905398eba3c444014"
+                .parse::<B256>()
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! Retrieves the blobs of EIP-4844 transactions on demand from a consensus
+//! layer node (as blob data never makes it into execution blocks)
+use std::{collections::HashMap, sync::Arc};
+
+use alloy::primitives::{TxHash, B256};
+use log::debug;
+use url::Url;
+
+use crate::{
+    metrics::Metrics,
+    services::{
+        beacon::{BeaconClient, BlobSidecar, Spec},
+        requests::{next_request, Fetched, RequestCache},
+        ServiceHandle,
+    },
+};
+
+/// Maximum number of transactions whose blobs are remembered at once (as
+/// each blob is 128 KiB)
+const MAX_TRANSACTIONS: usize = 16;
+
+/// A blob-carrying transaction to retrieve the blobs of
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BlobRequest {
+    pub hash: TxHash,
+    /// Versioned hashes of the transaction's blobs (in order)
+    pub versioned_hashes: Vec<B256>,
+    /// Timestamp of the block that included the transaction
+    pub timestamp: u64,
+}
+
+/// What's known about the blobs of a transaction (every blob of which, if
+/// known, is in the same order as its versioned hashes)
+pub type Blobs = Fetched<Vec<BlobSidecar>>;
+
+/// Handle to the blob retrieval service
+#[derive(Clone, Debug)]
+pub struct BlobService {
+    blobs: RequestCache<TxHash, Vec<BlobSidecar>, BlobRequest>,
+}
+
+impl BlobService {
+    /// Spawn a new instance of the blob retrieval service
+    ///
+    /// Transactions passed to [`BlobService::blobs`] have their blobs
+    /// retrieved (once each, unless the node couldn't be asked) from the
+    /// consensus layer node reachable at the provided [`Url`].
+    pub fn spawn(cl_rpc: Url, metrics: Arc<Metrics>) -> (Self, ServiceHandle) {
+        let (blobs, mut rx) = RequestCache::new("Blob", MAX_TRANSACTIONS);
+        let this = Self {
+            blobs: blobs.clone(),
+        };
+
+        let handle = ServiceHandle::spawn(
+            "blobs",
+            move |mut shutdown| async move {
+                let client = BeaconClient::new(cl_rpc);
+                /* the chain's timing is fetched lazily (and until it succeeds)
+                 * so that an unreachable node only fails individual requests */
+                let mut timing: Option<(Spec, u64)> = None;
+
+                while let Some(request) =
+                    next_request::<BlobRequest>(&mut shutdown, &mut rx).await
+                {
+                    let hash = request.hash;
+                    match fetch(&client, &mut timing, &request, &metrics).await
+                    {
+                        Ok(fetched) => blobs.set(hash, fetched)?,
+                        Err(e) => {
+                            debug!("Failed to retrieve blobs of transaction {hash}: {e:?}");
+                            metrics.failed_rpc_requests.inc();
+                            blobs.fail(hash, e.to_string())?;
+                        }
+                    }
+                }
+
+                Ok(())
+            },
+        );
+
+        (this, handle)
+    }
+
+    /// Blobs of the given transaction, retrieving them first if they haven't
+    /// been already (or if retrieving them failed a while ago)
+    pub fn blobs(&self, request: BlobRequest) -> eyre::Result<Blobs> {
+        self.blobs.get(request.hash, || request)
+    }
+}
+
+/// Retrieve the blobs of the given transaction from the beacon block of the
+/// slot that its block was proposed in
+async fn fetch(
+    client: &BeaconClient,
+    timing: &mut Option<(Spec, u64)>,
+    request: &BlobRequest,
+    metrics: &Metrics,
+) -> eyre::Result<Blobs> {
+    let (spec, genesis_time) = match *timing {
+        Some(t) => t,
+        None => *timing.insert((
+            metrics.rpc("beacon_getSpec", client.spec()).await?,
+            metrics
+                .rpc("beacon_getGenesis", client.genesis_time())
+                .await?,
+        )),
+    };
+    let Some(since_genesis) = request.timestamp.checked_sub(genesis_time)
+    else {
+        return Ok(Blobs::Unavailable(
+            "block predates the beacon chain".to_string(),
+        ));
+    };
+    let slot = since_genesis / spec.seconds_per_slot;
+    let Some(sidecars) = metrics
+        .rpc("beacon_getBlobSidecars", client.blob_sidecars(slot))
+        .await?
+    else {
+        return Ok(Blobs::Unavailable(format!(
+            "node has no blobs for slot {slot} (they may have been pruned)"
+        )));
+    };
+
+    Ok(match match_sidecars(sidecars, &request.versioned_hashes) {
+        Some(blobs) => Blobs::Known(blobs),
+        /* nodes return an empty list once they've pruned a slot's blobs */
+        None => Blobs::Unavailable(format!(
+            "node is missing blobs from slot {slot} (they may have been pruned)"
+        )),
+    })
+}
+
+/// Pick out the sidecars of the blobs with the given versioned hashes (in
+/// order) from those of an entire block, if they're all there
+fn match_sidecars(
+    sidecars: Vec<BlobSidecar>,
+    versioned_hashes: &[B256],
+) -> Option<Vec<BlobSidecar>> {
+    let mut sidecars: HashMap<B256, BlobSidecar> = sidecars
+        .into_iter()
+        .map(|sidecar| (sidecar.versioned_hash(), sidecar))
+        .collect();
+    versioned_hashes
+        .iter()
+        .map(|hash| sidecars.remove(hash))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::Bytes;
+
+    use super::*;
+
+    #[test]
+    fn test_match_sidecars() {
+        let sidecar = |index: u64| BlobSidecar {
+            index,
+            blob: Bytes::new(),
+            kzg_commitment: Bytes::from(vec![index as u8; 48]),
+            kzg_proof: Bytes::new(),
+        };
+        let block = vec![sidecar(0), sidecar(1), sidecar(2)];
+
+        let matched = match_sidecars(
+            block.clone(),
+            &[sidecar(2).versioned_hash(), sidecar(0).versioned_hash()],
+        )
+        .unwrap();
+        assert_eq!(
+            matched
+                .iter()
+                .map(|sidecar| sidecar.index)
+                .collect::<Vec<_>>(),
+            vec![2, 0]
+        );
+        assert_eq!(match_sidecars(block, &[sidecar(3).versioned_hash()]), None);
+        assert_eq!(match_sidecars(vec![], &[]), Some(vec![]));
+    }
+}
@@ -1,73 +1,1621 @@
 //! Indexing service for EVM chains
 use std::{
-    sync::Arc,
-    thread::{self, JoinHandle},
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    future::Future,
+    ops::{Range, RangeInclusive},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use alloy::providers::Provider;
+use alloy::{
+    eips::BlockId,
+    network::AnyTransactionReceipt,
+    primitives::{BlockHash, BlockNumber, ChainId, TxHash},
+    providers::Provider,
+    rpc::types::{Block, Header, Log, Transaction},
+    sol_types::decode_revert_reason,
+};
 use eyre::eyre;
-use futures::StreamExt;
-use log::{debug, error};
-use tokio::runtime::Builder;
-use url::Url;
+use futures::{stream::FuturesOrdered, StreamExt};
+use log::{debug, error, info, warn};
+use prometheus::HistogramTimer;
+use tokio::sync::watch;
 
 use crate::{
-    client::{AnyClient, Client},
-    db::Database,
+    client::{AnyClient, CachedClient, Client, Endpoints},
+    db::{Database, Finality, ReceiptStatus},
+    l2::{AnyBlock, L2Block, Rollup},
     metrics::Metrics,
+    services::ServiceHandle,
+    sinks::{AnySink, Sink},
+    storage::Storage,
 };
 
-const NUM_WORKERS: usize = 1;
+/// Delay before the first attempt to reconnect to the RPC node
+const INITIAL_RECONNECT_DELAY_MILLIS: u64 = 1_000; /* 1 second */
+/// Upper bound on the (exponentially increasing) delay between reconnection
+/// attempts
+const MAX_RECONNECT_DELAY_MILLIS: u64 = 30_000; /* 30 seconds */
+/// Number of recent blocks remembered for detecting reorgs (i.e., the
+/// deepest reorg that can be measured exactly)
+const REORG_WINDOW: usize = 128;
+/// Interval between scans of the database for missing blocks
+const GAP_SCAN_INTERVAL: Duration = Duration::from_secs(10);
+/// Maximum number of missing blocks indexed per scan (so that new blocks
+/// aren't held up for long)
+const MAX_GAP_FILL: usize = 32;
+/// Maximum number of blocks skipped by the header subscription that are
+/// indexed before the new head (any older ones are left to gap scans)
+const MAX_CATCH_UP: u64 = 256;
+/// Maximum number of blocks fetched from the RPC node at once (each along with
+/// its receipts)
+const FETCH_CONCURRENCY: usize = 8;
+/// Number of consecutive failures of the RPC endpoint in use after which the
+/// next one is tried instead (if there is one)
+const FAILOVER_AFTER_FAILURES: u32 = 3;
+/// How long the block subscription may go without yielding a block before
+/// the RPC endpoint in use is abandoned for the next one (if there is one)
+const STALL_TIMEOUT: Duration = Duration::from_secs(60);
+/// Number of attempts at retrieving the receipts of a block before indexing
+/// it without its logs and receipt statuses (which are then retried on each
+/// gap scan)
+const RECEIPT_ATTEMPTS: u32 = 3;
+/// Delay between attempts at retrieving the receipts of a block
+const RECEIPT_RETRY_DELAY: Duration = Duration::from_millis(500);
+/// Maximum number of blocks awaiting their receipts remembered at once
+const MAX_UNRECEIPTED: usize = 256;
+/// Maximum number of reverted transactions of a block replayed at once (to
+/// recover their revert reasons)
+const REVERT_REPLAY_CONCURRENCY: usize = 8;
+
+/// What to do with previously indexed data upon finding that the RPC node is
+/// serving a different chain (e.g., a development chain that was restarted)
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum ResetAction {
+    /// Delete it
+    Wipe,
+    /// Copy the database aside (tagged with the old genesis hash) and then
+    /// delete it
+    Archive,
+}
+
+impl fmt::Display for ResetAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Wipe => write!(f, "wipe"),
+            Self::Archive => write!(f, "archive"),
+        }
+    }
+}
+
+/// How the indexer responds to the chain being reset
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ResetPolicy {
+    /// Stop indexing (e.g., when there's nobody to ask)
+    #[default]
+    Stop,
+    /// Pause indexing until told what to do via [`IndexerState::resolve_reset`]
+    Ask,
+    /// Carry out the given action straight away
+    Act(ResetAction),
+}
+
+/// A change of the chain served by the RPC node, as told by its genesis block
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChainReset {
+    /// Genesis hash of the chain that was indexed until now
+    pub previous: BlockHash,
+    /// Genesis hash of the chain now served
+    pub current: BlockHash,
+    /// Why the last attempt at dealing with the reset failed (if it did)
+    pub failure: Option<String>,
+}
+
+/// A chain reorganisation observed by the indexer
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Reorg {
+    /// Number of the first block of the new chain
+    pub number: BlockNumber,
+    /// Number of previously indexed blocks that were orphaned
+    pub depth: u64,
+    /// When the reorg was observed
+    pub observed_at: Instant,
+}
+
+/// A change to the indexed chain data announced by the indexer
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChainEvent {
+    /// A block was indexed in full (i.e., along with its logs and receipt
+    /// statuses)
+    Block {
+        number: BlockNumber,
+        hash: BlockHash,
+    },
+    /// Previously indexed blocks were orphaned
+    Reorg(Reorg),
+    /// The latest safe and finalized blocks changed
+    Finality(Finality),
+}
+
+/// Health of the indexing service, shared with whatever reports on it (e.g.,
+/// the TUI)
+#[derive(Debug)]
+pub struct IndexerState {
+    /// RPC endpoints that the service indexes from
+    pub endpoints: Arc<Endpoints>,
+    connected: AtomicBool,
+    /// Zero until the service has first connected
+    chain_id: AtomicU64,
+    last_reorg: Mutex<Option<Reorg>>,
+    /// Most recent change to the indexed chain data (if any)
+    events: watch::Sender<Option<ChainEvent>>,
+    /// Genesis hash of the chain being indexed (once known)
+    genesis: Mutex<Option<BlockHash>>,
+    reset_policy: ResetPolicy,
+    /// Chain reset awaiting a decision (if any)
+    pending_reset: Mutex<Option<ChainReset>>,
+    /// Decision on the pending chain reset (once made)
+    resolution: watch::Sender<Option<ResetAction>>,
+}
+
+impl IndexerState {
+    pub fn new(endpoints: Arc<Endpoints>) -> Self {
+        Self {
+            endpoints,
+            connected: AtomicBool::new(false),
+            chain_id: AtomicU64::new(0),
+            last_reorg: Mutex::new(None),
+            events: watch::Sender::new(None),
+            genesis: Mutex::new(None),
+            reset_policy: ResetPolicy::default(),
+            pending_reset: Mutex::new(None),
+            resolution: watch::Sender::new(None),
+        }
+    }
+
+    /// Respond to the chain being reset as per the given [`ResetPolicy`]
+    pub fn with_reset_policy(mut self, reset_policy: ResetPolicy) -> Self {
+        self.reset_policy = reset_policy;
+        self
+    }
+
+    /// Subscribe to changes to the indexed chain data
+    ///
+    /// Only the most recent change is retained, so a subscriber that falls
+    /// behind sees the changes since it last checked as a single one.
+    pub fn subscribe(&self) -> watch::Receiver<Option<ChainEvent>> {
+        self.events.subscribe()
+    }
+
+    fn announce(&self, event: ChainEvent) {
+        /* there needn't be any subscribers (e.g., when headless) */
+        self.events.send_replace(Some(event));
+    }
+
+    /// The most recent reorg observed (if any)
+    pub fn last_reorg(&self) -> Option<Reorg> {
+        *self.last_reorg.lock().unwrap()
+    }
+
+    /// Chain ID reported by the RPC node (if the service has ever connected)
+    pub fn chain_id(&self) -> Option<ChainId> {
+        match self.chain_id.load(Ordering::Relaxed) {
+            0 => None,
+            chain_id => Some(chain_id),
+        }
+    }
+
+    /// Whether the service is currently subscribed to the RPC node
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    fn set_connected(&self, connected: bool) {
+        self.connected.store(connected, Ordering::Relaxed);
+    }
+
+    /// Genesis hash of the chain being indexed (if it's been checked)
+    pub fn genesis(&self) -> Option<BlockHash> {
+        *self.genesis.lock().unwrap()
+    }
+
+    /// Wait for the given (now pending) chain reset to be dealt with via
+    /// [`IndexerState::resolve_reset`]
+    async fn resolution(&self, reset: ChainReset) -> ResetAction {
+        let mut resolution = self.resolution.subscribe();
+        *self.pending_reset.lock().unwrap() = Some(reset);
+
+        loop {
+            let action = *resolution.borrow_and_update();
+            if let Some(action) = action {
+                self.resolution.send_replace(None);
+                return action;
+            }
+            /* the sender is held right here, so can't have been dropped */
+            let _ = resolution.changed().await;
+        }
+    }
+
+    /// Chain reset that indexing is paused on until
+    /// [`IndexerState::resolve_reset`] is called (if any)
+    pub fn pending_reset(&self) -> Option<ChainReset> {
+        self.pending_reset.lock().unwrap().clone()
+    }
+
+    /// Carry out the given action on the pending chain reset, resuming
+    /// indexing
+    pub fn resolve_reset(&self, action: ResetAction) -> eyre::Result<()> {
+        if self.pending_reset().is_none() {
+            return Err(eyre!("The chain hasn't been reset"));
+        }
+
+        self.resolution.send_replace(Some(action));
+        Ok(())
+    }
+}
+
+/// The most recent blocks of the canonical chain, as seen by the indexer
+#[derive(Debug, Default)]
+struct RecentChain {
+    blocks: VecDeque<(BlockNumber, BlockHash)>,
+}
+
+impl RecentChain {
+    /// Number of the most recent block remembered
+    fn head(&self) -> Option<BlockNumber> {
+        self.blocks.back().map(|&(number, _)| number)
+    }
+
+    /// Whether the given header's parent isn't the block remembered at that
+    /// height
+    fn forks(&self, header: &Header) -> bool {
+        self.blocks.iter().any(|&(number, hash)| {
+            number + 1 == header.number && hash != header.parent_hash
+        })
+    }
+
+    /// Extend the chain with the given header, returning the hashes of the
+    /// remembered blocks that it orphans
+    fn push(&mut self, header: &Header) -> Vec<BlockHash> {
+        if self.blocks.iter().any(|&(_, hash)| hash == header.hash) {
+            return vec![];
+        }
+
+        let orphaned = match self
+            .blocks
+            .iter()
+            .position(|&(_, hash)| hash == header.parent_hash)
+        {
+            Some(i) => {
+                self.blocks.drain(i + 1..).map(|(_, hash)| hash).collect()
+            }
+            /* the first block, a gap (e.g., after reconnecting), or a reorg
+             * deeper than we remember (which we can only underestimate) */
+            None => self
+                .blocks
+                .drain(..)
+                .filter(|&(number, _)| number >= header.number)
+                .map(|(_, hash)| hash)
+                .collect(),
+        };
+
+        if self.blocks.len() == REORG_WINDOW {
+            self.blocks.pop_front();
+        }
+
+        self.blocks.push_back((header.number, header.hash));
+        orphaned
+    }
+}
+
+/// A block along with everything else indexed about it, ready to be written
+struct Fetched {
+    block: Block,
+    /// What's peculiar to rollups about the block (if anything)
+    l2: L2Block,
+    logs: Vec<Log>,
+    statuses: Vec<ReceiptStatus>,
+    /// Headers of the block's ommers (i.e., uncles), which are stored as
+    /// orphaned blocks
+    ommers: Vec<Block>,
+    /// Times the block from being requested until it's been written
+    _timer: HistogramTimer,
+}
+
+/// Why the service stopped following the chain
+#[derive(Debug)]
+enum Interruption {
+    /// The RPC node is unreachable (or dropped the subscription), so it's
+    /// worth reconnecting
+    Disconnected(eyre::Report),
+    /// The block subscription stopped yielding blocks (without being
+    /// dropped)
+    Stalled,
+    /// A required sink failed
+    Fatal(eyre::Report),
+    /// The service was asked to shut down
+    Shutdown,
+}
 
 /// Handle to the blockchain indexing service
 #[derive(Clone, Debug)]
 pub struct BlockchainService {
-    client: AnyClient,
+    client: CachedClient,
+    /// Blocks indexed without their logs and receipt statuses (as their
+    /// receipts couldn't be retrieved at the time), oldest first
+    unreceipted: Arc<Mutex<VecDeque<Block>>>,
+    /// Gaps in the indexed blocks that the RPC node couldn't fill (which are
+    /// left alone, so that each is only warned about once)
+    unfillable: Arc<Mutex<HashSet<RangeInclusive<BlockNumber>>>>,
 }
 
 impl BlockchainService {
-    /// Spawn a new instance of the indexing service on its own OS thread
+    /// Spawn a new instance of the indexing service
     ///
-    /// Connects to the RPC node reachable at the provided [`Url`] and indexes
-    /// data to the provided [`Database`].
+    /// Connects to the RPC node reachable at the [`Endpoints`] given by the
+    /// [`IndexerState`] and writes
+    /// indexed data to each of the provided [`AnySink`]s. Failing to write to
+    /// a sink stops the service only if that sink is [`Sink::required`].
+    /// Whenever the RPC node is unreachable, the service reconnects with
+    /// exponential backoff, reporting as much via the given [`IndexerState`].
+    /// Should the endpoint in use fail repeatedly (or its subscription stall
+    /// whilst there are others to try), the service fails over to the next.
     ///
-    /// Note that joining on the returned thread handle will never yield.
+    /// Upon being asked to shut down, the service stops between blocks (so
+    /// that every block written is written in full). It also stops of its
+    /// own accord if a required sink fails.
     pub fn spawn(
-        rpc: Url,
-        db: Database,
+        sinks: Vec<AnySink>,
         metrics: Arc<Metrics>,
-    ) -> JoinHandle<eyre::Result<Self>> {
-        thread::spawn(move || {
-            let runtime = Builder::new_multi_thread()
-                .worker_threads(NUM_WORKERS)
-                .enable_all()
-                .build()
-                .unwrap();
-
-            runtime.block_on(async {
-                let this = Self {
-                    client: AnyClient::new(rpc).await?,
+        state: Arc<IndexerState>,
+    ) -> ServiceHandle {
+        ServiceHandle::spawn("blockchain", move |mut shutdown| async move {
+            let mut delay = INITIAL_RECONNECT_DELAY_MILLIS;
+            let mut chain = RecentChain::default();
+            let mut failures = 0;
+            let unreceipted = Arc::new(Mutex::new(VecDeque::new()));
+            let unfillable = Arc::new(Mutex::new(HashSet::new()));
+
+            loop {
+                let client = tokio::select! {
+                    _ = shutdown.changed() => return Ok(()),
+                    client = AnyClient::connect(&state.endpoints) => client,
                 };
-                while let Some(header) =
-                    this.client.block_headers().await.inspect_err(|e| error!("Failed to acquire block header stream from RPC: {e:?}"))?.next().await
-                {
-                    metrics.rpc_requests.inc();
-                    let block = this
-                        .client
-                        .provider()
-                        .get_block_by_hash(
-                            header.hash,
+                let url = client.as_ref().ok().map(Client::url);
+                let interruption = match client {
+                    Ok(client) => {
+                        let this = Self {
+                            client: CachedClient::new(client),
+                            unreceipted: unreceipted.clone(),
+                            unfillable: unfillable.clone(),
+                        };
+                        this.follow(
+                            &sinks,
+                            &metrics,
+                            &state,
+                            &mut chain,
+                            &mut delay,
+                            &mut shutdown,
                         )
-                        .await.inspect_err(|e| {error!("Failed to retrieve block by hash from RPC: {e:?}"); metrics.failed_rpc_requests.inc();})?
-                        .ok_or(eyre!("No such block"))?;
-                    db.add_block(&block).inspect_err(|e| {
-                        error!("Failed to write block to database: {e:?}")
-                    })?;
-                    metrics.blocks_added.inc();
-                    debug!("Saved header: {}", &header.hash);
-                }
-                Ok(this)
+                        .await
+                    }
+                    Err(e) => Interruption::Disconnected(e.into()),
+                };
+                /* the endpoint was healthy if it got as far as subscribing */
+                if state.is_connected() {
+                    failures = 0;
+                }
+                state.set_connected(false);
+
+                match interruption {
+                    Interruption::Disconnected(e) => {
+                        failures += 1;
+                        if let Some(url) = url
+                            .as_ref()
+                            .filter(|_| failures >= FAILOVER_AFTER_FAILURES)
+                        {
+                            state.endpoints.fail_over(url);
+                            failures = 0;
+                        }
+                        warn!("Lost connection to RPC node ({e}), reconnecting in {delay}ms...");
+                        tokio::select! {
+                            _ = shutdown.changed() => return Ok(()),
+                            _ = tokio::time::sleep(Duration::from_millis(delay)) => {}
+                        }
+                        delay = (delay * 2).min(MAX_RECONNECT_DELAY_MILLIS);
+                    }
+                    Interruption::Stalled => {
+                        warn!(
+                            "No blocks from RPC node in {}s",
+                            STALL_TIMEOUT.as_secs()
+                        );
+                        if let Some(url) = &url {
+                            state.endpoints.fail_over(url);
+                        }
+                        failures = 0;
+                    }
+                    Interruption::Fatal(e) => return Err(e),
+                    Interruption::Shutdown => return Ok(()),
+                }
+            }
+        })
+    }
+
+    /// Index each new block until something goes wrong (or the service is
+    /// asked to shut down)
+    async fn follow(
+        &self,
+        sinks: &[AnySink],
+        metrics: &Metrics,
+        state: &IndexerState,
+        chain: &mut RecentChain,
+        delay: &mut u64,
+        shutdown: &mut watch::Receiver<bool>,
+    ) -> Interruption {
+        let mut blocks = match self.client.blocks().await {
+            Ok(blocks) => blocks,
+            Err(e) => {
+                error!("Failed to acquire block stream from RPC: {e:?}");
+                return Interruption::Disconnected(e.into());
+            }
+        };
+        info!("Subscribed to new blocks");
+        state
+            .chain_id
+            .store(self.client.chain_id(), Ordering::Relaxed);
+        state.set_connected(true);
+        *delay = INITIAL_RECONNECT_DELAY_MILLIS;
+        if let Err(interruption) = self
+            .check_genesis(sinks, metrics, state, chain, shutdown)
+            .await
+        {
+            return interruption;
+        }
+        /* only once any reset has been dealt with, as clearing the database
+         * forgets the chain's ID */
+        if let Err(e) =
+            sinks
+                .iter()
+                .filter_map(AnySink::storage)
+                .try_for_each(|storage| {
+                    storage.set_chain_id(self.client.chain_id())
+                })
+        {
+            return Interruption::Fatal(e);
+        }
+        let mut finality = None;
+        let mut gap_scan = tokio::time::interval(GAP_SCAN_INTERVAL);
+        /* receipts are fetched concurrently, but blocks are written in the
+         * order that they arrived, each after marking whatever it orphaned */
+        let mut fetches = FuturesOrdered::new();
+        let mut orphans: VecDeque<Vec<BlockHash>> = VecDeque::new();
+        let mut last_block = tokio::time::Instant::now();
+
+        loop {
+            let block = tokio::select! {
+                _ = shutdown.changed() => return Interruption::Shutdown,
+                block = blocks.next(), if fetches.len() < FETCH_CONCURRENCY => match block {
+                    Some(block) => block,
+                    None => break,
+                },
+                _ = tokio::time::sleep_until(last_block + STALL_TIMEOUT),
+                    if state.endpoints.has_fallbacks() => return Interruption::Stalled,
+                Some(fetched) = fetches.next() => {
+                    let orphaned = orphans.pop_front().unwrap_or_default();
+                    if let Err(interruption) = self
+                        .settle(fetched, &orphaned, sinks, metrics, state)
+                        .await
+                    {
+                        return interruption;
+                    }
+
+                    if let Some(latest) = self
+                        .finality(metrics)
+                        .await
+                        .filter(|latest| finality != Some(*latest))
+                    {
+                        for sink in sinks {
+                            if let Err(e) = sink.set_finality(&latest).await {
+                                if let Err(e) = fail(sink, "finality", e) {
+                                    return Interruption::Fatal(e);
+                                }
+                            }
+                        }
+                        finality = Some(latest);
+                        state.announce(ChainEvent::Finality(latest));
+                    }
+                    continue;
+                }
+                _ = gap_scan.tick() => {
+                    record_db_size(sinks, metrics);
+                    if let Err(interruption) =
+                        self.fill_receipts(sinks, metrics, shutdown).await
+                    {
+                        return interruption;
+                    }
+                    if let Err(interruption) =
+                        self.fill_gaps(sinks, metrics, state, shutdown).await
+                    {
+                        return interruption;
+                    }
+                    continue;
+                }
+            };
+            last_block = tokio::time::Instant::now();
+            let header = block.block.header.clone();
+            metrics.chain_head.set(header.number as i64);
+            /* the chain going backwards may well be a development chain having
+             * been reset, which is checked for once everything in flight has
+             * been written out */
+            if chain.head().is_some_and(|head| header.number < head) {
+                if let Err(interruption) = self
+                    .flush(&mut fetches, &mut orphans, sinks, metrics, state)
+                    .await
+                {
+                    return interruption;
+                }
+                if let Err(interruption) = self
+                    .check_genesis(sinks, metrics, state, chain, shutdown)
+                    .await
+                {
+                    return interruption;
+                }
+                last_block = tokio::time::Instant::now();
+            }
+            /* the subscription skipped some blocks (e.g., whilst reconnecting
+             * or lagging), so index those first to keep the index contiguous
+             * (which means writing out every block already in flight) */
+            if let Some(head) = chain
+                .head()
+                .or_else(|| indexed_head(sinks))
+                .filter(|head| header.number > head + 1)
+            {
+                if let Err(interruption) = self
+                    .flush(&mut fetches, &mut orphans, sinks, metrics, state)
+                    .await
+                {
+                    return interruption;
+                }
+
+                let from =
+                    (head + 1).max(header.number.saturating_sub(MAX_CATCH_UP));
+                if let Err(interruption) = self
+                    .catch_up(
+                        from..header.number,
+                        sinks,
+                        metrics,
+                        state,
+                        chain,
+                        shutdown,
+                    )
+                    .await
+                {
+                    return interruption;
+                }
+            }
+
+            orphans
+                .push_back(self.observe(&header, chain, metrics, state).await);
+            let timer = metrics.block_processing_seconds.start_timer();
+            fetches.push_back(self.fetch_receipts(block, timer, metrics));
+            debug!("Fetching receipts for block {}", &header.hash);
+        }
+
+        /* the node may well still serve whatever's already in flight */
+        if let Err(interruption) = self
+            .flush(&mut fetches, &mut orphans, sinks, metrics, state)
+            .await
+        {
+            return interruption;
+        }
+
+        Interruption::Disconnected(eyre!("Block stream ended"))
+    }
+
+    /// Write out every block in flight (in the order that they arrived), each
+    /// after marking whatever it orphaned
+    async fn flush<F>(
+        &self,
+        fetches: &mut FuturesOrdered<F>,
+        orphans: &mut VecDeque<Vec<BlockHash>>,
+        sinks: &[AnySink],
+        metrics: &Metrics,
+        state: &IndexerState,
+    ) -> Result<(), Interruption>
+    where
+        F: Future<Output = Fetched>,
+    {
+        while let Some(fetched) = fetches.next().await {
+            let orphaned = orphans.pop_front().unwrap_or_default();
+            self.settle(fetched, &orphaned, sinks, metrics, state)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Check that the RPC node serves the same chain as was indexed before
+    /// (as told by the genesis hash recorded in the SQLite database sink, if
+    /// there is one), dealing with the chain having been reset as per the
+    /// [`ResetPolicy`] of the given [`IndexerState`]
+    async fn check_genesis(
+        &self,
+        sinks: &[AnySink],
+        metrics: &Metrics,
+        state: &IndexerState,
+        chain: &mut RecentChain,
+        shutdown: &mut watch::Receiver<bool>,
+    ) -> Result<(), Interruption> {
+        let Some(genesis) = self
+            .fetch_block(BlockId::number(0), metrics)
+            .await?
+            .map(|block| block.block.header.hash)
+        else {
+            warn!("RPC node has no genesis block");
+            return Ok(());
+        };
+        match sinks
+            .iter()
+            .find_map(|sink| sink.storage())
+            .and_then(|storage| storage.sqlite())
+        {
+            Some(db) => {
+                if reconcile_genesis(db, genesis, state, shutdown).await? {
+                    *chain = RecentChain::default();
+                }
+            }
+            None => *state.genesis.lock().unwrap() = Some(genesis),
+        }
+        Ok(())
+    }
+
+    /// Retrieve the full block with the given [`BlockId`] (if the node knows
+    /// of it)
+    async fn fetch_block(
+        &self,
+        id: BlockId,
+        metrics: &Metrics,
+    ) -> Result<Option<AnyBlock>, Interruption> {
+        self.client.block_by_id(id).await.map_err(|e| {
+            error!("Failed to retrieve block from RPC: {e:?}");
+            metrics.failed_rpc_requests.inc();
+            Interruption::Disconnected(e.into())
+        })
+    }
+
+    /// Retrieve the full block with the given [`BlockId`] along with its logs
+    /// and receipt statuses (if the node knows of it)
+    async fn fetch(
+        &self,
+        id: BlockId,
+        metrics: &Metrics,
+    ) -> Result<Option<Fetched>, Interruption> {
+        let timer = metrics.block_processing_seconds.start_timer();
+        match self.fetch_block(id, metrics).await? {
+            Some(block) => {
+                Ok(Some(self.fetch_receipts(block, timer, metrics).await))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve the logs and receipt statuses of the given block
+    ///
+    /// Should its receipts be unavailable, the block is returned without them
+    /// (and remembered so that they're retrieved later on).
+    async fn fetch_receipts(
+        &self,
+        block: AnyBlock,
+        timer: HistogramTimer,
+        metrics: &Metrics,
+    ) -> Fetched {
+        let AnyBlock { block, l2 } = block;
+        let (logs, statuses) = match self.receipts(&block, metrics).await {
+            Some(receipts) => self.digest(&block, &receipts).await,
+            None => {
+                warn!(
+                    "Indexing block #{} without its logs and receipt statuses \
+                    for now",
+                    block.header.number
+                );
+                self.await_receipts(block.clone());
+                (vec![], vec![])
+            }
+        };
+        let ommers = self.ommers(&block, metrics).await;
+
+        Fetched {
+            block,
+            l2,
+            logs,
+            statuses,
+            ommers,
+            _timer: timer,
+        }
+    }
+
+    /// Retrieve the headers of the ommers of the given block (if it has any,
+    /// as only pre-Merge blocks may)
+    ///
+    /// Should they be unavailable, the block is indexed without them.
+    async fn ommers(&self, block: &Block, metrics: &Metrics) -> Vec<Block> {
+        if block.uncles.is_empty() {
+            return vec![];
+        }
+
+        self.client.ommers(block).await.unwrap_or_else(|e| {
+            warn!(
+                "Indexing block #{} without its ommers: {e:?}",
+                block.header.number
+            );
+            metrics.failed_rpc_requests.inc();
+            vec![]
+        })
+    }
+
+    /// Retrieve the receipts of the given block, trying a few times before
+    /// giving up
+    async fn receipts(
+        &self,
+        block: &Block,
+        metrics: &Metrics,
+    ) -> Option<Vec<AnyTransactionReceipt>> {
+        for attempt in 1..=RECEIPT_ATTEMPTS {
+            match self.client.block_receipts(block.header.hash.into()).await {
+                Ok(receipts) => return Some(receipts),
+                Err(e) => {
+                    warn!(
+                        "Failed to retrieve receipts for block #{} from RPC \
+                        (attempt {attempt} of {RECEIPT_ATTEMPTS}): {e:?}",
+                        block.header.number
+                    );
+                    metrics.failed_rpc_requests.inc();
+                }
+            }
+            if attempt < RECEIPT_ATTEMPTS {
+                tokio::time::sleep(RECEIPT_RETRY_DELAY).await;
+            }
+        }
+        None
+    }
+
+    /// Extract the logs and receipt statuses from the given receipts of the
+    /// given block, recovering the revert reasons of failed transactions
+    async fn digest(
+        &self,
+        block: &Block,
+        receipts: &[AnyTransactionReceipt],
+    ) -> (Vec<Log>, Vec<ReceiptStatus>) {
+        let (logs, mut statuses) =
+            logs_and_statuses(receipts, self.client.chain_id());
+        let reverted: HashSet<TxHash> = statuses
+            .iter()
+            .filter(|status| !status.success)
+            .map(|status| status.transaction_hash)
+            .collect();
+        /* each replay is a round trip to the node, so they're made
+         * concurrently rather than holding up indexing one by one */
+        let replays: Vec<_> = block
+            .transactions
+            .txns()
+            .filter(|tx| reverted.contains(tx.inner.tx_hash()))
+            .map(|tx| async move {
+                (
+                    *tx.inner.tx_hash(),
+                    self.revert_reason(tx, block.header.number).await,
+                )
             })
+            .collect();
+        let reasons: HashMap<TxHash, Option<String>> =
+            futures::stream::iter(replays)
+                .buffer_unordered(REVERT_REPLAY_CONCURRENCY)
+                .collect()
+                .await;
+        for status in statuses.iter_mut() {
+            if let Some(reason) = reasons.get(&status.transaction_hash) {
+                status.revert_reason = reason.clone();
+            }
+        }
+        (logs, statuses)
+    }
+
+    /// Remember the given block as awaiting its receipts
+    fn await_receipts(&self, block: Block) {
+        let mut unreceipted = self.unreceipted.lock().unwrap();
+        if unreceipted.len() >= MAX_UNRECEIPTED {
+            if let Some(dropped) = unreceipted.pop_front() {
+                warn!(
+                    "Giving up on the logs and receipt statuses of block #{}",
+                    dropped.header.number
+                );
+            }
+        }
+        unreceipted.push_back(block);
+    }
+
+    /// Index the logs and receipt statuses of the blocks that were indexed
+    /// without them, stopping at the first whose receipts are still
+    /// unavailable
+    async fn fill_receipts(
+        &self,
+        sinks: &[AnySink],
+        metrics: &Metrics,
+        shutdown: &watch::Receiver<bool>,
+    ) -> Result<(), Interruption> {
+        loop {
+            stop_if_asked(shutdown)?;
+            let Some(block) = self.unreceipted.lock().unwrap().pop_front()
+            else {
+                return Ok(());
+            };
+            let Some(receipts) = self.receipts(&block, metrics).await else {
+                self.unreceipted.lock().unwrap().push_front(block);
+                return Ok(());
+            };
+            let (logs, statuses) = self.digest(&block, &receipts).await;
+            write_receipts(&logs, &statuses, sinks).await?;
+            info!(
+                "Indexed the logs and receipt statuses of block #{}",
+                block.header.number
+            );
+        }
+    }
+
+    /// Write out the given newly fetched head of the chain (having first
+    /// marked whatever it orphaned as such)
+    async fn settle(
+        &self,
+        fetched: Fetched,
+        orphaned: &[BlockHash],
+        sinks: &[AnySink],
+        metrics: &Metrics,
+        state: &IndexerState,
+    ) -> Result<(), Interruption> {
+        mark_orphaned(sinks, state, orphaned).await?;
+        write(&fetched, sinks, metrics, state).await?;
+        metrics.observe_block(&fetched.block);
+        metrics.head_lag_seconds.set(
+            SystemTime::now()
+                .duration_since(
+                    UNIX_EPOCH
+                        + Duration::from_secs(fetched.block.header.timestamp),
+                )
+                .unwrap_or_default()
+                .as_secs_f64(),
+        );
+        debug!("Saved header: {}", &fetched.block.header.hash);
+        Ok(())
+    }
+
+    /// Index the given range of blocks (in order), which the header
+    /// subscription skipped over
+    async fn catch_up(
+        &self,
+        range: Range<BlockNumber>,
+        sinks: &[AnySink],
+        metrics: &Metrics,
+        state: &IndexerState,
+        chain: &mut RecentChain,
+        shutdown: &watch::Receiver<bool>,
+    ) -> Result<(), Interruption> {
+        info!(
+            "Catching up on {} blocks missed by the subscription...",
+            range.end - range.start
+        );
+        let mut blocks = futures::stream::iter(range.clone())
+            .map(|number| self.fetch(number.into(), metrics))
+            .buffered(FETCH_CONCURRENCY);
+
+        for number in range {
+            stop_if_asked(shutdown)?;
+            let fetched = match blocks.next().await {
+                Some(fetched) => fetched?,
+                None => break,
+            };
+            let fetched = match fetched {
+                Some(fetched) => fetched,
+                None => {
+                    warn!("RPC node has no block #{number} to catch up on");
+                    break;
+                }
+            };
+            let orphaned = self
+                .observe(&fetched.block.header, chain, metrics, state)
+                .await;
+            mark_orphaned(sinks, state, &orphaned).await?;
+            write(&fetched, sinks, metrics, state).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Index (up to [`MAX_GAP_FILL`]) blocks missing from the database sink
+    /// (e.g., due to a dropped connection or a restart), most recent first
+    async fn fill_gaps(
+        &self,
+        sinks: &[AnySink],
+        metrics: &Metrics,
+        state: &IndexerState,
+        shutdown: &watch::Receiver<bool>,
+    ) -> Result<(), Interruption> {
+        let storage = match sinks.iter().find_map(|sink| sink.storage()) {
+            Some(storage) => storage,
+            None => return Ok(()),
+        };
+        let gaps = match storage.block_gaps() {
+            Ok(gaps) => gaps,
+            Err(e) => {
+                warn!("Failed to find gaps in indexed blocks: {e:?}");
+                return Ok(());
+            }
+        };
+        let mut missing: u64 =
+            gaps.iter().map(|gap| gap.end() - gap.start() + 1).sum();
+        metrics.missing_blocks.set(missing as i64);
+
+        let fillable: Vec<RangeInclusive<BlockNumber>> = {
+            let mut unfillable = self.unfillable.lock().unwrap();
+            /* forget gaps that have since been filled (or reshaped) */
+            unfillable.retain(|gap| gaps.contains(gap));
+            gaps.into_iter()
+                .filter(|gap| !unfillable.contains(gap))
+                .collect()
+        };
+        let fillable_count: u64 =
+            fillable.iter().map(|gap| gap.end() - gap.start() + 1).sum();
+        if fillable_count > 0 {
+            info!("Filling gaps of {fillable_count} missing blocks...");
+        }
+
+        let numbers: Vec<BlockNumber> = fillable
+            .iter()
+            .flat_map(|gap| gap.clone().rev())
+            .take(MAX_GAP_FILL)
+            .collect();
+        let mut blocks = futures::stream::iter(numbers.clone())
+            .map(|number| self.fetch(number.into(), metrics))
+            .buffered(FETCH_CONCURRENCY);
+
+        for number in numbers {
+            stop_if_asked(shutdown)?;
+            match blocks.next().await {
+                Some(fetched) => match fetched? {
+                    Some(fetched) => {
+                        write(&fetched, sinks, metrics, state).await?
+                    }
+                    None => {
+                        /* e.g., a development chain that has since been
+                         * reset, or a pruned node; as the gap is filled from
+                         * the top down, what's left of it is exactly what
+                         * the next scan finds */
+                        if let Some(gap) =
+                            fillable.iter().find(|gap| gap.contains(&number))
+                        {
+                            warn!(
+                                "RPC node has no block #{number} to fill gap \
+                                with, leaving blocks #{}..=#{number} missing",
+                                gap.start()
+                            );
+                            self.unfillable
+                                .lock()
+                                .unwrap()
+                                .insert(*gap.start()..=number);
+                        }
+                        break;
+                    }
+                },
+                None => break,
+            }
+            missing -= 1;
+            metrics.missing_blocks.set(missing as i64);
+        }
+
+        Ok(())
+    }
+
+    /// Ask the node for its latest safe and finalized blocks (if it knows of
+    /// any, as e.g. development chains may not)
+    async fn finality(&self, metrics: &Metrics) -> Option<Finality> {
+        match tokio::try_join!(
+            self.client.safe_block_number(),
+            self.client.finalized_block_number()
+        ) {
+            Ok((safe, finalized)) => Some(Finality { safe, finalized }),
+            Err(e) => {
+                debug!("Failed to retrieve finality from RPC: {e:?}");
+                metrics.failed_rpc_requests.inc();
+                None
+            }
+        }
+    }
+
+    /// Replay the given (reverted) transaction on top of its parent block in
+    /// order to recover its revert reason
+    ///
+    /// This is best effort: transactions earlier in the same block may have
+    /// changed the state that the transaction reverted on, in which case the
+    /// replay either succeeds or reverts for a different reason.
+    async fn revert_reason(
+        &self,
+        tx: &Transaction,
+        number: BlockNumber,
+    ) -> Option<String> {
+        let e = self
+            .client
+            .provider()
+            .call(tx.clone().into_request())
+            .block(number.saturating_sub(1).into())
+            .await
+            .err()?;
+        let data = e
+            .as_error_resp()?
+            .as_revert_data()
+            .filter(|data| !data.is_empty())?;
+        /* fall back to the raw data (e.g., for custom errors) */
+        decode_revert_reason(&data).or(Some(data.to_string()))
+    }
+
+    /// Check whether the given (new) header reorganises the chain, recording
+    /// the reorg if so
+    ///
+    /// Returns the hashes of the blocks orphaned by the reorg (if any).
+    async fn observe(
+        &self,
+        header: &Header,
+        chain: &mut RecentChain,
+        metrics: &Metrics,
+        state: &IndexerState,
+    ) -> Vec<BlockHash> {
+        /* walk back along the new chain until it meets the one we know */
+        let mut ancestry = vec![header.clone()];
+        while ancestry.len() < REORG_WINDOW
+            && chain.forks(ancestry.last().unwrap())
+        {
+            let parent_hash = ancestry.last().unwrap().parent_hash;
+            match self.client.block_by_id(parent_hash.into()).await {
+                Ok(Some(parent)) => ancestry.push(parent.block.header),
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("Failed to retrieve reorged block from RPC: {e:?}");
+                    metrics.failed_rpc_requests.inc();
+                    break;
+                }
+            }
+        }
+
+        let orphaned: Vec<BlockHash> = ancestry
+            .iter()
+            .rev()
+            .flat_map(|header| chain.push(header))
+            .collect();
+        let depth = orphaned.len() as u64;
+
+        if depth > 0 {
+            let number = ancestry.last().unwrap().number;
+            warn!("Observed reorg of depth {depth} at block {number}");
+            metrics.reorg_count.inc();
+            if depth as i64 > metrics.max_reorg_depth.get() {
+                metrics.max_reorg_depth.set(depth as i64);
+            }
+            *state.last_reorg.lock().unwrap() = Some(Reorg {
+                number,
+                depth,
+                observed_at: Instant::now(),
+            });
+        }
+
+        orphaned
+    }
+}
+
+/// Stop (between blocks) if the service has been asked to shut down
+fn stop_if_asked(shutdown: &watch::Receiver<bool>) -> Result<(), Interruption> {
+    if *shutdown.borrow() {
+        Err(Interruption::Shutdown)
+    } else {
+        Ok(())
+    }
+}
+
+/// Mark the blocks with the given hashes as orphaned in each sink, announcing
+/// the reorg via the given [`IndexerState`]
+async fn mark_orphaned(
+    sinks: &[AnySink],
+    state: &IndexerState,
+    orphaned: &[BlockHash],
+) -> Result<(), Interruption> {
+    if orphaned.is_empty() {
+        return Ok(());
+    }
+
+    for sink in sinks {
+        if let Err(e) = sink.mark_orphaned(orphaned).await {
+            fail(sink, "orphaned blocks", e).map_err(Interruption::Fatal)?;
+        }
+    }
+
+    if let Some(reorg) = state.last_reorg() {
+        state.announce(ChainEvent::Reorg(reorg));
+    }
+
+    Ok(())
+}
+
+/// Write the given [`Fetched`] block (along with anything rollup-specific, its
+/// logs, and its receipt statuses) to each sink, announcing it via the given
+/// [`IndexerState`]
+async fn write(
+    fetched: &Fetched,
+    sinks: &[AnySink],
+    metrics: &Metrics,
+    state: &IndexerState,
+) -> Result<(), Interruption> {
+    let Fetched {
+        block,
+        l2,
+        logs,
+        statuses,
+        ommers,
+        _timer: _,
+    } = fetched;
+
+    for sink in sinks {
+        if let Err(e) = sink.add_block(block).await {
+            fail(sink, "block", e).map_err(Interruption::Fatal)?;
+        }
+    }
+    metrics.blocks_added.inc();
+
+    if !l2.is_empty() {
+        for sink in sinks {
+            if let Err(e) = sink.add_l2_block(l2).await {
+                fail(sink, "L2 block", e).map_err(Interruption::Fatal)?;
+            }
+        }
+    }
+
+    write_receipts(logs, statuses, sinks).await?;
+    write_ommers(ommers, sinks).await?;
+
+    state.announce(ChainEvent::Block {
+        number: block.header.number,
+        hash: block.header.hash,
+    });
+    Ok(())
+}
+
+/// Write the given ommers (of a single block) to the database sink as
+/// orphaned blocks
+///
+/// Unlike blocks orphaned by reorgs observed whilst following the chain,
+/// these aren't announced (nor passed on to any other sink, as they were
+/// never the head of the chain).
+async fn write_ommers(
+    ommers: &[Block],
+    sinks: &[AnySink],
+) -> Result<(), Interruption> {
+    if ommers.is_empty() {
+        return Ok(());
+    }
+
+    let hashes: Vec<BlockHash> =
+        ommers.iter().map(|ommer| ommer.header.hash).collect();
+    for sink in sinks.iter().filter(|sink| sink.storage().is_some()) {
+        for ommer in ommers {
+            if let Err(e) = sink.add_block(ommer).await {
+                fail(sink, "ommer", e).map_err(Interruption::Fatal)?;
+            }
+        }
+        if let Err(e) = sink.mark_orphaned(&hashes).await {
+            fail(sink, "ommers", e).map_err(Interruption::Fatal)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Write the given logs and receipt statuses (of a single block) to each sink
+async fn write_receipts(
+    logs: &[Log],
+    statuses: &[ReceiptStatus],
+    sinks: &[AnySink],
+) -> Result<(), Interruption> {
+    for sink in sinks {
+        if let Err(e) = sink.add_logs(logs).await {
+            fail(sink, "logs", e).map_err(Interruption::Fatal)?;
+        }
+    }
+
+    for sink in sinks {
+        if let Err(e) = sink.add_receipt_statuses(statuses).await {
+            fail(sink, "receipt statuses", e).map_err(Interruption::Fatal)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Extract the logs and receipt statuses (sans revert reasons) from the given
+/// receipts of a block on the chain with the given ID
+pub fn logs_and_statuses(
+    receipts: &[AnyTransactionReceipt],
+    chain_id: ChainId,
+) -> (Vec<Log>, Vec<ReceiptStatus>) {
+    let logs = receipts
+        .iter()
+        .flat_map(|receipt| receipt.inner.inner.logs().to_vec())
+        .collect();
+
+    let rollup = Rollup::of(chain_id);
+    let statuses = receipts
+        .iter()
+        .filter_map(|receipt| {
+            match ReceiptStatus::from_receipt(&receipt.inner) {
+                Ok(mut status) => {
+                    status.l1_fee =
+                        rollup.and_then(|rollup| rollup.l1_fee(receipt));
+                    Some(status)
+                }
+                Err(e) => {
+                    warn!("Skipping malformed receipt: {e:?}");
+                    None
+                }
+            }
         })
+        .collect();
+    (logs, statuses)
+}
+
+/// Compare the given genesis hash of the chain served by the RPC node with
+/// the one recorded in the given [`Database`], dealing with the chain having
+/// been reset as per the [`ResetPolicy`] of the given [`IndexerState`] and
+/// returning whether it was
+async fn reconcile_genesis(
+    db: &Database,
+    genesis: BlockHash,
+    state: &IndexerState,
+    shutdown: &mut watch::Receiver<bool>,
+) -> Result<bool, Interruption> {
+    let previous = db
+        .genesis_hash()
+        .map_err(|e| Interruption::Fatal(e.into()))?;
+    let reset = match previous.filter(|previous| *previous != genesis) {
+        Some(previous) => {
+            warn!("Chain was reset (genesis {previous} is now {genesis})");
+            let mut reset = ChainReset {
+                previous,
+                current: genesis,
+                failure: None,
+            };
+
+            loop {
+                let action = match state.reset_policy {
+                    ResetPolicy::Act(action) => action,
+                    ResetPolicy::Stop => {
+                        return Err(Interruption::Fatal(eyre!(
+                            "Chain was reset (genesis {previous} is now \
+                            {genesis}), so refusing to mix the two chains' \
+                            data (see --on-chain-reset)"
+                        )))
+                    }
+                    ResetPolicy::Ask => {
+                        info!("Indexing paused until the reset is dealt with");
+                        tokio::select! {
+                            _ = shutdown.changed() => {
+                                return Err(Interruption::Shutdown)
+                            }
+                            action = state.resolution(reset.clone()) => action,
+                        }
+                    }
+                };
+
+                match reset_db(db, action, previous) {
+                    Ok(()) => break,
+                    Err(e) if state.reset_policy == ResetPolicy::Ask => {
+                        error!("Failed to {action} the database: {e:?}");
+                        reset.failure =
+                            Some(format!("failed to {action}: {e}"));
+                    }
+                    Err(e) => return Err(Interruption::Fatal(e.into())),
+                }
+            }
+
+            *state.pending_reset.lock().unwrap() = None;
+            true
+        }
+        None => false,
+    };
+
+    if previous != Some(genesis) {
+        db.set_genesis_hash(genesis)
+            .map_err(|e| Interruption::Fatal(e.into()))?;
+    }
+    *state.genesis.lock().unwrap() = Some(genesis);
+    Ok(reset)
+}
+
+/// Carry out the given [`ResetAction`] on the given [`Database`], which was
+/// indexing the chain with the given genesis hash
+fn reset_db(
+    db: &Database,
+    action: ResetAction,
+    genesis: BlockHash,
+) -> crate::db::Result<()> {
+    if action == ResetAction::Archive {
+        /* tagged with just enough of the genesis hash to tell chains apart */
+        db.archive(&genesis.to_string()[..10])?;
+    }
+    db.clear_chain_data()
+}
+
+/// Report the size of the database sink (if there is one and it's SQLite)
+fn record_db_size(sinks: &[AnySink], metrics: &Metrics) {
+    if let Some(db) = sinks
+        .iter()
+        .find_map(|sink| sink.storage())
+        .and_then(|storage| storage.sqlite())
+    {
+        match db.size() {
+            Ok(size) => metrics.db_size_bytes.set(size as i64),
+            Err(e) => warn!("Failed to determine database size: {e:?}"),
+        }
+    }
+}
+
+/// Number of the latest block in the database sink (if there is one), for
+/// resuming where a previous run left off
+fn indexed_head(sinks: &[AnySink]) -> Option<BlockNumber> {
+    let storage = sinks.iter().find_map(|sink| sink.storage())?;
+    match storage.latest_block_header() {
+        Ok(header) => header.map(|header| header.number),
+        Err(e) => {
+            warn!("Failed to retrieve latest indexed block: {e:?}");
+            None
+        }
+    }
+}
+
+/// Report a failed write to the given sink, propagating the error only if the
+/// sink is required
+fn fail(sink: &AnySink, what: &str, e: eyre::Report) -> eyre::Result<()> {
+    if sink.required() {
+        error!("Failed to write {what} to {} sink: {e:?}", sink.name());
+        Err(e)
+    } else {
+        warn!("Failed to write {what} to {} sink: {e:?}", sink.name());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(number: BlockNumber, fork: u8, parent_fork: u8) -> Header {
+        let mut header = Header::new(alloy::consensus::Header {
+            number,
+            parent_hash: BlockHash::with_last_byte(
+                (number - 1) as u8 | parent_fork,
+            ),
+            ..Default::default()
+        });
+        header.hash = BlockHash::with_last_byte(number as u8 | fork);
+        header
+    }
+
+    #[test]
+    fn test_reorg_depth() {
+        let mut chain = RecentChain::default();
+        assert!(chain.push(&header(1, 0, 0)).is_empty());
+        assert!(chain.push(&header(2, 0, 0)).is_empty());
+        assert!(chain.push(&header(3, 0, 0)).is_empty());
+        assert!(chain.push(&header(4, 0, 0)).is_empty());
+        assert!(chain.push(&header(4, 0, 0)).is_empty());
+
+        /* blocks 3 and 4 replaced by a competing chain forked from block 2 */
+        assert!(!chain.forks(&header(4, 0x80, 0)));
+        assert!(chain.forks(&header(4, 0x80, 0x80)));
+        assert!(!chain.forks(&header(3, 0x80, 0)));
+        assert_eq!(
+            chain.push(&header(3, 0x80, 0)),
+            vec![header(3, 0, 0).hash, header(4, 0, 0).hash]
+        );
+        assert!(chain.push(&header(4, 0x80, 0x80)).is_empty());
+        assert!(chain.push(&header(5, 0x80, 0x80)).is_empty());
+
+        /* a gap (e.g., after reconnecting) isn't a reorg */
+        assert!(chain.push(&header(9, 0, 0)).is_empty());
+        assert_eq!(chain.head(), Some(9));
+    }
+
+    fn state() -> IndexerState {
+        IndexerState::new(Arc::new(Endpoints::new(
+            "ws://localhost:8546".parse().unwrap(),
+            vec![],
+            Default::default(),
+            Arc::new(
+                prometheus::IntCounter::new("failovers", "failovers").unwrap(),
+            ),
+        )))
+    }
+
+    /// A database recording the given genesis hash, with a block indexed
+    fn indexed(genesis: BlockHash) -> Database {
+        let db = Database::new(crate::db::Location::Memory).unwrap();
+        db.set_genesis_hash(genesis).unwrap();
+        db.headers().add(&header(1, 0, 0)).unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_genesis() {
+        let (old, new) = (BlockHash::repeat_byte(1), BlockHash::repeat_byte(2));
+        let (_tx, mut shutdown) = watch::channel(false);
+
+        /* the same chain as before */
+        let db = indexed(old);
+        let state = state();
+        assert!(!reconcile_genesis(&db, old, &state, &mut shutdown)
+            .await
+            .unwrap());
+        assert!(db.latest_block_header().unwrap().is_some());
+
+        /* by default, a reset chain stops the indexer, touching nothing */
+        assert!(matches!(
+            reconcile_genesis(&db, new, &state, &mut shutdown).await,
+            Err(Interruption::Fatal(_))
+        ));
+        assert!(db.latest_block_header().unwrap().is_some());
+        assert_eq!(db.genesis_hash().unwrap(), Some(old));
+
+        /* or else wipes the database straight away */
+        let state =
+            state.with_reset_policy(ResetPolicy::Act(ResetAction::Wipe));
+        assert!(reconcile_genesis(&db, new, &state, &mut shutdown)
+            .await
+            .unwrap());
+        assert!(db.latest_block_header().unwrap().is_none());
+        assert_eq!(db.genesis_hash().unwrap(), Some(new));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_reset() {
+        let (old, new) = (BlockHash::repeat_byte(1), BlockHash::repeat_byte(2));
+        let (_tx, mut shutdown) = watch::channel(false);
+        let db = indexed(old);
+        let state = state().with_reset_policy(ResetPolicy::Ask);
+        assert!(state.pending_reset().is_none());
+        assert!(state.resolve_reset(ResetAction::Wipe).is_err());
+
+        let user = async {
+            let reset = loop {
+                match state.pending_reset() {
+                    Some(reset) => break reset,
+                    None => tokio::task::yield_now().await,
+                }
+            };
+            assert_eq!((reset.previous, reset.current), (old, new));
+            /* nothing's been touched while waiting on the user */
+            assert!(db.latest_block_header().unwrap().is_some());
+            state.resolve_reset(ResetAction::Wipe).unwrap();
+        };
+        let (reset, ()) = tokio::join!(
+            reconcile_genesis(&db, new, &state, &mut shutdown),
+            user
+        );
+
+        assert!(reset.unwrap());
+        assert!(state.pending_reset().is_none());
+        assert!(db.latest_block_header().unwrap().is_none());
+        assert_eq!(db.genesis_hash().unwrap(), Some(new));
+    }
+
+    #[test]
+    fn test_chain_events() {
+        let state = state();
+        /* announcements without subscribers go nowhere */
+        state.announce(ChainEvent::Finality(Finality::default()));
+
+        let mut events = state.subscribe();
+        assert!(!events.has_changed().unwrap());
+        for number in [1, 2] {
+            state.announce(ChainEvent::Block {
+                number,
+                hash: BlockHash::with_last_byte(number as u8),
+            });
+        }
+
+        /* changes since last checked are coalesced into the latest one */
+        assert!(events.has_changed().unwrap());
+        assert_eq!(
+            *events.borrow_and_update(),
+            Some(ChainEvent::Block {
+                number: 2,
+                hash: BlockHash::with_last_byte(2)
+            })
+        );
+        assert!(!events.has_changed().unwrap());
+    }
+
+    #[test]
+    fn test_logs_and_statuses() {
+        let receipt: AnyTransactionReceipt =
+            serde_json::from_value(serde_json::json!({
+                "type": "0x2",
+                "status": "0x0",
+                "cumulativeGasUsed": "0x5208",
+                "logs": [{
+                    "address": alloy::primitives::Address::ZERO,
+                    "topics": [],
+                    "data": "0x",
+                    "blockHash": BlockHash::ZERO,
+                    "blockNumber": "0x10",
+                    "transactionHash": BlockHash::with_last_byte(1),
+                    "transactionIndex": "0x0",
+                    "logIndex": "0x0",
+                    "removed": false,
+                }],
+                "logsBloom": format!("0x{}", "0".repeat(512)),
+                "transactionHash": BlockHash::with_last_byte(1),
+                "transactionIndex": "0x0",
+                "blockHash": BlockHash::ZERO,
+                "blockNumber": "0x10",
+                "gasUsed": "0x5208",
+                "effectiveGasPrice": "0x3",
+                "from": alloy::primitives::Address::ZERO,
+                "to": alloy::primitives::Address::ZERO,
+                "contractAddress": null,
+                "l1Fee": "0x64",
+            }))
+            .unwrap();
+
+        let (logs, statuses) = logs_and_statuses(&[receipt], 10);
+        assert_eq!(logs.len(), 1);
+        assert_eq!(statuses.len(), 1);
+        assert!(!statuses[0].success);
+        assert_eq!(statuses[0].gas_used, 0x5208);
+        assert_eq!(
+            statuses[0].l1_fee,
+            Some(alloy::primitives::U256::from(100))
+        );
+        assert_eq!(statuses[0].revert_reason, None);
+    }
+
+    #[tokio::test]
+    async fn test_write_ommers() {
+        let db = indexed(BlockHash::repeat_byte(1));
+        let sinks = [AnySink::Database(crate::storage::AnyStorage::Sqlite(
+            db.clone(),
+        ))];
+        let ommer = Block {
+            header: header(1, 0x80, 0),
+            ..Default::default()
+        };
+
+        let hash = ommer.header.hash;
+
+        write_ommers(&[ommer], &sinks).await.unwrap();
+
+        /* the ommer is stored, but the canonical block keeps its height */
+        assert!(db.headers().by_hash(hash).unwrap().is_some());
+        assert_eq!(
+            db.headers().by_number(1).unwrap().map(|header| header.hash),
+            Some(header(1, 0, 0).hash)
+        );
+        assert!(db
+            .headers()
+            .recent_orphaned_hashes(1)
+            .unwrap()
+            .contains(&hash));
+        /* nor is it counted in its hour's stats */
+        assert!(db
+            .headers()
+            .hourly_stats(1)
+            .unwrap()
+            .iter()
+            .all(|stats| stats.blocks == 0));
     }
 }
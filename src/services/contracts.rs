@@ -0,0 +1,319 @@
+//! Retrieves the code and storage of contracts on demand (and looks up their
+//! verified source code, if a verifier is configured)
+use std::sync::Arc;
+
+use alloy::{
+    primitives::{Address, Bytes, ChainId, Selector, B256},
+    providers::Provider,
+};
+use log::{debug, warn};
+use tokio::sync::mpsc;
+
+use crate::{
+    abi,
+    client::{Client, ClientError, Connection, Endpoints},
+    db::Database,
+    evm,
+    metrics::Metrics,
+    services::{
+        requests::{next_request, Fetched, RequestCache},
+        ServiceHandle,
+    },
+    verification::{AnyVerifier, VerifiedSource, Verifier},
+};
+
+/// Maximum number of contracts remembered at once
+const MAX_CONTRACTS: usize = 64;
+/// Maximum number of storage slots remembered at once
+const MAX_STORAGE_SLOTS: usize = 256;
+
+/// What's known about the source code of a contract
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Verification {
+    /// No verifier is configured
+    Unchecked,
+    /// Still being looked up
+    Pending,
+    /// None of the verifiers (named here) know of the contract
+    Unverified(String),
+    Verified(Box<VerifiedSource>),
+    /// None of the verifiers could be asked
+    Failed(String),
+}
+
+/// The code deployed at an address
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Contract {
+    /// Deployed (i.e., runtime) bytecode, which is empty for externally owned
+    /// accounts
+    pub code: Bytes,
+    /// Function selectors that the code dispatches on
+    pub selectors: Vec<Selector>,
+    pub source: Verification,
+}
+
+#[derive(Clone, Debug)]
+enum Request {
+    Code(Address),
+    Storage(Address, B256),
+    /// Outcome of looking up the source code of the contract at the given
+    /// [`Address`] (sent by the task that looked it up)
+    Source(Address, Verification),
+}
+
+type Contracts = RequestCache<Address, Contract, Request>;
+type Storage = RequestCache<(Address, B256), B256, Request>;
+
+/// Handle to the contract inspection service
+#[derive(Clone, Debug)]
+pub struct ContractService {
+    contracts: Contracts,
+    storage: Storage,
+}
+
+impl ContractService {
+    /// Spawn a new instance of the contract inspection service
+    ///
+    /// Code and storage are retrieved using the node reachable via the
+    /// provided [`Endpoints`]. The source code of every contract (i.e.,
+    /// address with code) is also looked up using each of the provided
+    /// [`AnyVerifier`]s in turn, with any found cached in the [`Database`]
+    /// (and its ABI used for decoding calldata and logs).
+    pub fn spawn(
+        endpoints: Arc<Endpoints>,
+        verifiers: Vec<AnyVerifier>,
+        db: Database,
+        metrics: Arc<Metrics>,
+    ) -> (Self, ServiceHandle) {
+        let (requests, mut rx) = mpsc::unbounded_channel();
+        let contracts = RequestCache::with_queue(
+            "Contract",
+            MAX_CONTRACTS,
+            requests.clone(),
+        );
+        let storage = RequestCache::with_queue(
+            "Storage",
+            MAX_STORAGE_SLOTS,
+            requests.clone(),
+        );
+        let verifiers: Arc<[AnyVerifier]> = verifiers.into();
+        let this = Self {
+            contracts: contracts.clone(),
+            storage: storage.clone(),
+        };
+
+        let handle = ServiceHandle::spawn(
+            "contracts",
+            move |mut shutdown| async move {
+                let mut connection = Connection::new(endpoints);
+
+                while let Some(request) =
+                    next_request(&mut shutdown, &mut rx).await
+                {
+                    match request {
+                        Request::Code(address) => {
+                            fetch_contract(
+                                &mut connection,
+                                &verifiers,
+                                &db,
+                                address,
+                                &contracts,
+                                &requests,
+                                &metrics,
+                            )
+                            .await?
+                        }
+                        Request::Source(address, source) => {
+                            add_source(&db, address, source, &contracts)?
+                        }
+                        Request::Storage(address, slot) => {
+                            let value = match connection.client().await {
+                                Ok(client) => client
+                                    .provider()
+                                    .get_storage_at(address, slot.into())
+                                    .await
+                                    .map_err(ClientError::from),
+                                Err(e) => Err(e),
+                            };
+                            match value {
+                                Ok(t) => storage.set(
+                                    (address, slot),
+                                    Fetched::Known(B256::from(t)),
+                                )?,
+                                Err(e) => {
+                                    debug!("Failed to read slot {slot} of {address}: {e:?}");
+                                    metrics.failed_rpc_requests.inc();
+                                    connection.failed(&e);
+                                    storage
+                                        .fail((address, slot), e.to_string())?;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                Ok(())
+            },
+        );
+
+        (this, handle)
+    }
+
+    /// The [`Contract`] deployed at the given [`Address`], retrieving it
+    /// first if it hasn't been already (or if retrieving it failed a while
+    /// ago)
+    pub fn contract(
+        &self,
+        address: Address,
+    ) -> eyre::Result<Fetched<Contract>> {
+        self.contracts.get(address, || Request::Code(address))
+    }
+
+    /// Read the given storage slot of the contract at the given [`Address`]
+    /// (again, if it's been read before), unless it's still being read
+    pub fn read_storage(
+        &self,
+        address: Address,
+        slot: B256,
+    ) -> eyre::Result<()> {
+        self.storage
+            .refresh((address, slot), || Request::Storage(address, slot))?;
+        Ok(())
+    }
+
+    /// Latest value read from the given storage slot of the contract at the
+    /// given [`Address`] (or `None` if it hasn't been read)
+    pub fn storage(
+        &self,
+        address: Address,
+        slot: B256,
+    ) -> eyre::Result<Option<Fetched<B256>>> {
+        self.storage.peek(&(address, slot))
+    }
+}
+
+/// Retrieve the code deployed at the given [`Address`], then start looking up
+/// its source code
+///
+/// The code is made available straight away, whereas the source code is
+/// looked up by a task of its own (which sends it back as a
+/// [`Request::Source`]), as verifiers can be slow. Source code is only looked
+/// up if it isn't already cached in the database.
+async fn fetch_contract(
+    connection: &mut Connection,
+    verifiers: &Arc<[AnyVerifier]>,
+    db: &Database,
+    address: Address,
+    contracts: &Contracts,
+    requests: &mpsc::UnboundedSender<Request>,
+    metrics: &Metrics,
+) -> eyre::Result<()> {
+    let code = match connection.client().await {
+        Ok(client) => client
+            .provider()
+            .get_code_at(address)
+            .await
+            .map(|code| (code, client.chain_id()))
+            .map_err(ClientError::from),
+        Err(e) => Err(e),
+    };
+    let (code, chain_id) = match code {
+        Ok(t) => t,
+        Err(e) => {
+            debug!("Failed to retrieve code of {address}: {e:?}");
+            metrics.failed_rpc_requests.inc();
+            connection.failed(&e);
+            return contracts.fail(address, e.to_string());
+        }
+    };
+    let cached = if code.is_empty() {
+        None
+    } else {
+        db.contract_source(address)
+            .inspect_err(|e| {
+                warn!("Failed to read cached source of {address}: {e:?}")
+            })
+            .ok()
+            .flatten()
+    };
+    let contract = Contract {
+        selectors: evm::selectors(&code),
+        source: match cached {
+            Some(source) => Verification::Verified(Box::new(source)),
+            None if code.is_empty() || verifiers.is_empty() => {
+                Verification::Unchecked
+            }
+            None => Verification::Pending,
+        },
+        code,
+    };
+    let pending = contract.source == Verification::Pending;
+    contracts.set(address, Fetched::Known(contract))?;
+
+    if pending {
+        let (verifiers, requests) = (verifiers.clone(), requests.clone());
+        tokio::spawn(async move {
+            let source = look_up_source(&verifiers, chain_id, address).await;
+            /* the service may have since stopped */
+            requests.send(Request::Source(address, source)).ok();
+        });
+    }
+    Ok(())
+}
+
+/// Record the outcome of looking up the source code of the contract at the
+/// given [`Address`], caching it in the [`Database`] if it was found
+fn add_source(
+    db: &Database,
+    address: Address,
+    source: Verification,
+    contracts: &Contracts,
+) -> eyre::Result<()> {
+    if let Verification::Verified(source) = &source {
+        if let Err(e) = db.add_contract_source(address, source) {
+            warn!("Failed to cache source of {address}: {e:?}");
+        }
+        abi::set_contract_abi(address, source.abi.clone());
+    }
+
+    /* the contract may have since been forgotten */
+    match contracts.peek(&address)? {
+        Some(Fetched::Known(mut contract)) => {
+            contract.source = source;
+            contracts.set(address, Fetched::Known(contract))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Ask each verifier in turn for the source code of the contract at the
+/// given [`Address`], until one has it
+async fn look_up_source(
+    verifiers: &[AnyVerifier],
+    chain_id: ChainId,
+    address: Address,
+) -> Verification {
+    let mut unverified = vec![];
+    let mut error = None;
+
+    for verifier in verifiers {
+        match verifier.verified_source(chain_id, address).await {
+            Ok(Some(source)) => {
+                return Verification::Verified(Box::new(source))
+            }
+            Ok(None) => unverified.push(verifier.name()),
+            Err(e) => {
+                debug!(
+                    "Failed to look up source of {address} on {}: {e:?}",
+                    verifier.name()
+                );
+                error = Some(format!("{}: {e}", verifier.name()));
+            }
+        }
+    }
+
+    match error {
+        Some(e) if unverified.is_empty() => Verification::Failed(e),
+        _ => Verification::Unverified(unverified.join(" or ")),
+    }
+}
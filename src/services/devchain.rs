@@ -0,0 +1,333 @@
+//! Conveniences for local development chains (e.g., Anvil and Hardhat)
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use alloy::{
+    primitives::{Address, BlockNumber, ChainId, U256},
+    providers::Provider,
+    rpc::client::BatchRequest,
+};
+use eyre::eyre;
+use futures::StreamExt;
+use log::{debug, error, info, warn};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::{
+    sync::{mpsc, watch},
+    time,
+};
+
+use crate::{
+    client::{AnyClient, Client, ClientError, Endpoints, Result},
+    metrics::Metrics,
+    services::ServiceHandle,
+};
+
+/// Chain IDs used by local development chains (i.e., Anvil and Hardhat, and
+/// Ganache and `geth --dev`, respectively)
+const DEV_CHAIN_IDS: [ChainId; 2] = [31_337, 1_337];
+/// How often account balances are refreshed in the absence of new blocks
+const POLL_INTERVAL_MILLIS: u64 = 5_000; /* 5 seconds */
+/// Delay between attempts at (re)connecting to the RPC node
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Whether the given [`ChainId`] belongs to a local development chain
+pub fn is_dev_chain(chain_id: ChainId) -> bool {
+    DEV_CHAIN_IDS.contains(&chain_id)
+}
+
+/// Where an Anvil node in fork mode forked from
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Fork {
+    /// RPC endpoint of the forked chain
+    pub url: String,
+    pub block_number: Option<BlockNumber>,
+}
+
+/// The subset of the response to `anvil_nodeInfo` describing the fork (if
+/// any)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NodeInfo {
+    fork_config: ForkConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ForkConfig {
+    fork_url: Option<String>,
+    fork_block_number: Option<BlockNumber>,
+}
+
+/// Something that the development chain service can be asked to do
+#[derive(Clone, Copy, Debug)]
+enum Request {
+    Mine,
+    ResetFork(Option<BlockNumber>),
+}
+
+/// What's known about the development chain being indexed
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DevChainInfo {
+    pub chain_id: ChainId,
+    /// Whether each transaction is mined into its own block as soon as it's
+    /// received (if the node says)
+    pub automine: Option<bool>,
+    /// Accounts that the node holds the keys of (i.e., pre-funded accounts),
+    /// along with their balances (in wei)
+    pub accounts: Vec<(Address, U256)>,
+    /// Where the node forked from (if it's an Anvil node in fork mode)
+    pub fork: Option<Fork>,
+}
+
+impl DevChainInfo {
+    /// Ask the node about itself, in two round trips: one for its accounts,
+    /// and a single batch for everything else
+    ///
+    /// Only failing to reach the node is an error, as most of what's asked
+    /// is only answered by some kinds of development chain.
+    async fn fetch(client: &AnyClient, metrics: &Metrics) -> Result<Self> {
+        let provider = client.provider();
+        let addresses = match provider.get_accounts().await {
+            Ok(addresses) => addresses,
+            Err(e) => {
+                metrics.failed_rpc_requests.inc();
+                let e = ClientError::from(e);
+                if e.is_disconnected() {
+                    return Err(e);
+                }
+                warn!("Failed to retrieve dev chain accounts: {e:?}");
+                vec![]
+            }
+        };
+
+        let mut batch = BatchRequest::new(provider.client());
+        let balances = addresses
+            .iter()
+            .map(|address| {
+                batch
+                    .add_call::<_, U256>("eth_getBalance", &(address, "latest"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        /* whichever dialect the node speaks */
+        let anvil_automine =
+            batch.add_call::<_, bool>("anvil_getAutomine", &[(); 0])?;
+        let hardhat_automine =
+            batch.add_call::<_, bool>("hardhat_getAutomine", &[(); 0])?;
+        /* only Anvil knows where it forked from */
+        let node_info =
+            batch.add_call::<_, NodeInfo>("anvil_nodeInfo", &[(); 0])?;
+        batch.send().await.inspect_err(|_| {
+            metrics.failed_rpc_requests.inc();
+        })?;
+
+        let mut accounts = Vec::with_capacity(addresses.len());
+        for (address, balance) in addresses.into_iter().zip(balances) {
+            match balance.await {
+                Ok(balance) => accounts.push((address, balance)),
+                Err(e) => {
+                    warn!("Failed to retrieve balance of {address}: {e:?}");
+                    metrics.failed_rpc_requests.inc();
+                }
+            }
+        }
+        let automine = match (anvil_automine.await, hardhat_automine.await) {
+            (Ok(automine), _) | (_, Ok(automine)) => Some(automine),
+            _ => None,
+        };
+        let fork = node_info.await.ok().and_then(|info| {
+            Some(Fork {
+                url: info.fork_config.fork_url?,
+                block_number: info.fork_config.fork_block_number,
+            })
+        });
+
+        Ok(Self {
+            chain_id: client.chain_id(),
+            automine,
+            accounts,
+            fork,
+        })
+    }
+}
+
+/// Keep the given [`DevChainInfo`] up to date, and handle the given
+/// [`Request`]s, until the connection to the development chain via the given
+/// client is lost or the service is asked to stop (returning whether it was)
+async fn follow(
+    client: &AnyClient,
+    info: &RwLock<Option<DevChainInfo>>,
+    requests: &mut mpsc::UnboundedReceiver<Request>,
+    metrics: &Metrics,
+    shutdown: &mut watch::Receiver<bool>,
+) -> eyre::Result<bool> {
+    let mut headers = match client.block_headers().await {
+        Ok(headers) => headers,
+        Err(e) => {
+            warn!("Failed to subscribe to the dev chain's blocks: {e:?}");
+            return Ok(false);
+        }
+    };
+    let mut interval =
+        time::interval(Duration::from_millis(POLL_INTERVAL_MILLIS));
+
+    loop {
+        let latest = match DevChainInfo::fetch(client, metrics).await {
+            Ok(latest) => latest,
+            Err(e) => {
+                debug!("Failed to retrieve dev chain info: {e:?}");
+                return Ok(false);
+            }
+        };
+        *info.write().map_err(|_| eyre!("Dev chain lock poisoned"))? =
+            Some(latest.clone());
+
+        tokio::select! {
+            _ = shutdown.changed() => return Ok(true),
+            Some(request) = requests.recv() => {
+                let result = match request {
+                    Request::Mine => client
+                        .provider()
+                        .raw_request::<_, serde_json::Value>(
+                            "evm_mine".into(),
+                            [(); 0],
+                        )
+                        .await,
+                    Request::ResetFork(block_number) => client
+                        .provider()
+                        .raw_request::<_, serde_json::Value>(
+                            "anvil_reset".into(),
+                            [json!({
+                                "forking": {
+                                    "jsonRpcUrl": latest
+                                        .fork
+                                        .as_ref()
+                                        .map(|fork| &fork.url),
+                                    "blockNumber": block_number,
+                                }
+                            })],
+                        )
+                        .await,
+                };
+                match result {
+                    Ok(_) => info!("Dev chain handled {request:?}"),
+                    Err(e) => {
+                        error!("Dev chain failed to handle {request:?}: {e:?}");
+                        metrics.failed_rpc_requests.inc();
+                    }
+                }
+            }
+            header = headers.next() => {
+                if header.is_none() {
+                    return Ok(false);
+                }
+            }
+            _ = interval.tick() => {}
+        }
+    }
+}
+
+/// Handle to the development chain service
+#[derive(Clone, Debug)]
+pub struct DevChainService {
+    info: Arc<RwLock<Option<DevChainInfo>>>,
+    requests: mpsc::UnboundedSender<Request>,
+}
+
+impl DevChainService {
+    /// Spawn a new instance of the development chain service
+    ///
+    /// If the node reachable via the provided [`Endpoints`] turns out not to
+    /// be a development chain, the service stops straight away. Otherwise, it
+    /// keeps [`DevChainService::info`] up to date, mines a block upon each
+    /// call to [`DevChainService::mine`], and re-forks upon each call to
+    /// [`DevChainService::reset_fork`]. Should the connection to the node be
+    /// lost (e.g., as it was restarted), it reconnects and checks afresh
+    /// whether it's a development chain.
+    pub fn spawn(
+        endpoints: Arc<Endpoints>,
+        metrics: Arc<Metrics>,
+    ) -> (Self, ServiceHandle) {
+        let (requests, mut rx) = mpsc::unbounded_channel();
+        let this = Self {
+            info: Arc::new(RwLock::new(None)),
+            requests,
+        };
+        let info = this.info.clone();
+
+        let handle =
+            ServiceHandle::spawn("devchain", move |mut shutdown| async move {
+                loop {
+                    let client = match AnyClient::connect(&endpoints).await {
+                        Ok(client) => client,
+                        Err(e) => {
+                            debug!("Failed to connect to RPC: {e:?}");
+                            tokio::select! {
+                                _ = shutdown.changed() => return Ok(()),
+                                _ = time::sleep(RECONNECT_DELAY) => continue,
+                            }
+                        }
+                    };
+
+                    if !is_dev_chain(client.chain_id()) {
+                        return Ok(());
+                    }
+
+                    info!("Connected to a dev chain ({})", client.chain_id());
+                    let stopped = follow(
+                        &client,
+                        &info,
+                        &mut rx,
+                        &metrics,
+                        &mut shutdown,
+                    )
+                    .await?;
+                    *info
+                        .write()
+                        .map_err(|_| eyre!("Dev chain lock poisoned"))? = None;
+                    if stopped {
+                        return Ok(());
+                    }
+
+                    /* the node may well have been restarted as a different
+                     * chain, so find out afresh */
+                    warn!("Lost connection to the dev chain, reconnecting...");
+                    tokio::select! {
+                        _ = shutdown.changed() => return Ok(()),
+                        _ = time::sleep(RECONNECT_DELAY) => {}
+                    }
+                }
+            });
+
+        (this, handle)
+    }
+
+    /// Ask the development chain to mine a new block
+    pub fn mine(&self) -> eyre::Result<()> {
+        self.requests
+            .send(Request::Mine)
+            .map_err(|_| eyre!("Not connected to a dev chain"))
+    }
+
+    /// Ask the (forked) development chain to fork again from its fork source
+    /// at the given block (or the latest one)
+    pub fn reset_fork(
+        &self,
+        block_number: Option<BlockNumber>,
+    ) -> eyre::Result<()> {
+        if self.info().and_then(|info| info.fork).is_none() {
+            return Err(eyre!("Not connected to a forked Anvil node"));
+        }
+
+        self.requests
+            .send(Request::ResetFork(block_number))
+            .map_err(|_| eyre!("Not connected to a dev chain"))
+    }
+
+    /// What's known about the development chain (if connected to one)
+    pub fn info(&self) -> Option<DevChainInfo> {
+        self.info.read().ok().and_then(|info| info.clone())
+    }
+}
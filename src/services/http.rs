@@ -0,0 +1,287 @@
+//! HTTP endpoints for metrics and indexed data
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use alloy::primitives::BlockNumber;
+
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use log::{error, info, warn};
+use prometheus::{Encoder, Registry, TextEncoder};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::task::JoinSet;
+use tokio_rustls::TlsAcceptor;
+
+use crate::{
+    services::ServiceHandle,
+    stats::MAX_BUILDER_STATS_BLOCKS,
+    storage::{AnyStorage, Storage},
+    utils::status_line,
+};
+
+/// How long in-flight requests are given to complete upon shutdown
+const SHUTDOWN_GRACE_PERIOD_MILLIS: u64 = 5_000; /* 5 seconds */
+
+/// Endpoints served by the [`HttpService`]
+#[derive(Clone, Debug, Default)]
+pub struct Routes {
+    /// Prometheus metrics (i.e., `/metrics`)
+    pub metrics: Option<Arc<Registry>>,
+    /// Queries over indexed chain data (i.e., `/api/...`)
+    pub api: Option<AnyStorage>,
+}
+
+#[derive(Clone, Debug)]
+pub struct HttpService {
+    pub routes: Routes,
+}
+
+impl HttpService {
+    /// Spawn a new instance of the HTTP service
+    ///
+    /// The listening socket is bound before this returns, so that failing to
+    /// bind is reported to the caller rather than lost on the service task.
+    /// If a [`TlsAcceptor`] is provided, requests are served over HTTPS.
+    pub fn spawn(
+        socket: SocketAddr,
+        tls: Option<TlsAcceptor>,
+        routes: Routes,
+    ) -> eyre::Result<ServiceHandle> {
+        let listener = std::net::TcpListener::bind(socket)?;
+        listener.set_nonblocking(true)?;
+        let scheme = if tls.is_some() { "https" } else { "http" };
+
+        if routes.metrics.is_some() {
+            info!("Serving metrics on {scheme}://{socket}/metrics");
+        }
+
+        if routes.api.is_some() {
+            info!("Serving API on {scheme}://{socket}/api");
+            info!("Serving status line on {scheme}://{socket}/statusline");
+        }
+
+        Ok(ServiceHandle::spawn(
+            "http",
+            move |mut shutdown| async move {
+                let this = Self { routes };
+                let listener = TcpListener::from_std(listener)?;
+                let mut connections = JoinSet::new();
+
+                loop {
+                    let stream = tokio::select! {
+                        _ = shutdown.changed() => break,
+                        accepted = listener.accept() => match accepted {
+                            Ok((stream, _)) => stream,
+                            Err(e) => {
+                                /* usually transient (e.g., file descriptor
+                                 * exhaustion), so keep serving */
+                                warn!("Failed to accept HTTP connection: {e:?}");
+                                continue;
+                            }
+                        },
+                    };
+                    let routes = this.routes.clone();
+                    let tls = tls.clone();
+
+                    connections.spawn(async move {
+                        match tls {
+                            Some(acceptor) => match acceptor.accept(stream).await {
+                                Ok(stream) => {
+                                    serve_connection(stream, routes)
+                                        .await
+                                }
+                                Err(e) => warn!(
+                                    "Failed TLS handshake on HTTP connection: {e:?}"
+                                ),
+                            },
+                            None => {
+                                serve_connection(stream, routes).await
+                            }
+                        }
+                    });
+
+                    /* reap connections that have already finished */
+                    while connections.try_join_next().is_some() {}
+                }
+
+                info!("Shutting down HTTP service...");
+                let drained = tokio::time::timeout(
+                    Duration::from_millis(SHUTDOWN_GRACE_PERIOD_MILLIS),
+                    async { while connections.join_next().await.is_some() {} },
+                )
+                .await;
+
+                if drained.is_err() {
+                    warn!(
+                        "Aborting {} in-flight HTTP connections",
+                        connections.len()
+                    );
+                    connections.shutdown().await;
+                }
+
+                Ok(())
+            },
+        ))
+    }
+}
+
+async fn serve_connection<S>(stream: S, routes: Routes)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let service = service_fn(move |req| route(req, routes.clone()));
+
+    if let Err(e) = http1::Builder::new()
+        .serve_connection(TokioIo::new(stream), service)
+        .await
+    {
+        warn!("Failed to serve HTTP connection: {e:?}");
+    }
+}
+
+async fn route(
+    req: Request<hyper::body::Incoming>,
+    routes: Routes,
+) -> Result<Response<String>, std::convert::Infallible> {
+    match (req.uri().path(), routes.metrics, routes.api) {
+        ("/metrics", Some(registry), _) => serve_metrics(registry),
+        ("/api/builders", _, Some(db)) => {
+            serve_builder_stats(req.uri().query().unwrap_or_default(), db).await
+        }
+        ("/statusline", _, Some(db)) => serve_status_line(db).await,
+        _ => Ok(response(StatusCode::NOT_FOUND, "Not Found".to_string())),
+    }
+}
+
+fn response(status: StatusCode, body: String) -> Response<String> {
+    Response::builder()
+        .status(status)
+        .body(body)
+        .inspect_err(|e| error!("Failed to construct HTTP response: {e:?}"))
+        .unwrap()
+}
+
+/// Serves `/api/builders?from=<number>&to=<number>`
+async fn serve_builder_stats(
+    query: &str,
+    db: AnyStorage,
+) -> Result<Response<String>, std::convert::Infallible> {
+    let param = |name: &str| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == name)
+            .and_then(|(_, value)| value.parse::<BlockNumber>().ok())
+    };
+    let (from, to) = match (param("from"), param("to")) {
+        (Some(from), Some(to)) if from <= to => (from, to),
+        _ => {
+            return Ok(response(
+                StatusCode::BAD_REQUEST,
+                "Expected a block range (e.g., ?from=19000000&to=19000100)"
+                    .to_string(),
+            ))
+        }
+    };
+
+    if to - from >= MAX_BUILDER_STATS_BLOCKS {
+        return Ok(response(
+            StatusCode::BAD_REQUEST,
+            format!("Block range may span at most {MAX_BUILDER_STATS_BLOCKS} blocks"),
+        ));
+    }
+
+    let stats =
+        tokio::task::spawn_blocking(move || db.builder_stats(from..=to))
+            .await
+            .map_err(eyre::Report::from)
+            .and_then(|stats| Ok(serde_json::to_string(&stats?)?));
+
+    Ok(match stats {
+        Ok(body) => Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "application/json")
+            .body(body)
+            .inspect_err(|e| error!("Failed to construct HTTP response: {e:?}"))
+            .unwrap(),
+        Err(e) => {
+            error!("Failed to compute builder statistics: {e:?}");
+            response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to compute builder statistics".to_string(),
+            )
+        }
+    })
+}
+
+/// Serves `/statusline` (e.g., for tmux's `status-right`)
+async fn serve_status_line(
+    db: AnyStorage,
+) -> Result<Response<String>, std::convert::Infallible> {
+    let header = tokio::task::spawn_blocking(move || db.latest_block_header())
+        .await
+        .map_err(eyre::Report::from)
+        .and_then(|header| header);
+
+    Ok(match header {
+        Ok(Some(header)) => {
+            response(StatusCode::OK, format!("{}\n", status_line(&header)))
+        }
+        Ok(None) => response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "No blocks indexed yet".to_string(),
+        ),
+        Err(e) => {
+            error!("Failed to retrieve latest block header: {e:?}");
+            response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to retrieve latest block header".to_string(),
+            )
+        }
+    })
+}
+
+fn serve_metrics(
+    registry: Arc<Registry>,
+) -> Result<Response<String>, std::convert::Infallible> {
+    let encoder = TextEncoder::new();
+    let metric_families = registry.gather();
+
+    match encoder.encode_to_string(&metric_families) {
+        Ok(metrics_text) => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", encoder.format_type())
+            .body(metrics_text)
+            .inspect_err(|e| {
+                error!("Failed to construct metrics response: {e:?}")
+            })
+            .unwrap()),
+        Err(_) => Ok(response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to encode metrics".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::Metrics;
+
+    #[test]
+    fn test_shutdown() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _guard = runtime.enter();
+        let spawn_result = HttpService::spawn(
+            ([127, 0, 0, 1], 0).into(),
+            None,
+            Routes {
+                metrics: Some(Metrics::new().registry),
+                api: None,
+            },
+        );
+        assert!(spawn_result.is_ok());
+        let handle = spawn_result.unwrap();
+        assert!(handle.shutdown().is_ok());
+    }
+}
@@ -0,0 +1,469 @@
+//! Follows the node's mempool, remembering when (and as of which block) each
+//! pending transaction was first seen
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use alloy::{
+    consensus::Transaction as AbstractTransaction,
+    primitives::{BlockNumber, TxHash},
+    providers::Provider,
+    rpc::types::Transaction,
+};
+use eyre::eyre;
+use futures::{stream, Stream, StreamExt};
+use log::{debug, info, warn};
+use serde_json::{json, Value};
+use tokio::sync::watch;
+
+use crate::{
+    client::{AnyClient, Client, Endpoints},
+    export::big_number,
+    metrics::Metrics,
+    services::{txpool::TxpoolContent, ServiceHandle},
+};
+
+/// Maximum number of pending transactions remembered at once (the longest
+/// pending are forgotten first)
+const MAX_PENDING: usize = 16_384;
+/// How often the node's transaction pool is polled when pending transactions
+/// can't be subscribed to
+const TXPOOL_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// How long a transaction is remembered for without being included (as it
+/// has most likely been dropped or replaced by then), in line with geth's
+/// default lifetime for transactions in its pool
+const MAX_PENDING_AGE: Duration = Duration::from_secs(3 * 60 * 60);
+/// Delay before the first attempt to reconnect to the RPC node
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+/// Upper bound on the (exponentially increasing) delay between reconnection
+/// attempts
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Columns of an exported mempool snapshot
+pub const COLUMNS: [&str; 11] = [
+    "hash",
+    "first_seen_ms",
+    "type",
+    "from",
+    "to",
+    "nonce",
+    "value",
+    "gas_limit",
+    "max_fee_per_gas",
+    "max_priority_fee_per_gas",
+    "input_size",
+];
+
+/// A transaction observed in the mempool
+#[derive(Clone, Debug)]
+pub struct PendingTransaction {
+    pub transaction: Transaction,
+    pub first_seen: SystemTime,
+    /// Latest block when first seen (unless no block had arrived yet)
+    pub first_seen_block: Option<BlockNumber>,
+}
+
+impl PendingTransaction {
+    /// Flatten this transaction into a row of [`COLUMNS`]
+    pub fn row(&self) -> Vec<Value> {
+        let tx = &self.transaction;
+        let first_seen = self
+            .first_seen
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        vec![
+            json!(tx.inner.tx_hash()),
+            json!(first_seen.as_millis() as u64),
+            json!(u8::from(tx.inner.tx_type())),
+            json!(tx.as_recovered().signer()),
+            json!(tx.to()),
+            json!(tx.nonce()),
+            json!(tx.value().to_string()),
+            json!(tx.gas_limit()),
+            big_number(tx.max_fee_per_gas()),
+            tx.max_priority_fee_per_gas()
+                .map_or(Value::Null, big_number),
+            json!(tx.input().len()),
+        ]
+    }
+}
+
+#[derive(Debug, Default)]
+struct Mempool {
+    pending: HashMap<TxHash, PendingTransaction>,
+    /// Hashes in the order they were first seen (possibly including some
+    /// that have since been included)
+    order: VecDeque<TxHash>,
+    /// Latest block announced by the node
+    head: Option<BlockNumber>,
+    /// Why the mempool can't be followed (if it can't)
+    error: Option<String>,
+}
+
+impl Mempool {
+    fn insert(&mut self, transaction: Transaction, now: SystemTime) {
+        let hash = *transaction.inner.tx_hash();
+
+        if self.pending.contains_key(&hash) {
+            return;
+        }
+
+        self.pending.insert(
+            hash,
+            PendingTransaction {
+                transaction,
+                first_seen: now,
+                first_seen_block: self.head,
+            },
+        );
+        self.order.push_back(hash);
+
+        while self.pending.len() > MAX_PENDING {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.pending.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Forget the transactions with the given [`TxHash`]es (e.g., because
+    /// they've been included in a block)
+    fn remove(&mut self, hashes: impl IntoIterator<Item = TxHash>) {
+        hashes.into_iter().for_each(|hash| {
+            self.pending.remove(&hash);
+        });
+
+        if self.order.len() > 2 * self.pending.len().max(MAX_PENDING) {
+            self.order.retain(|hash| self.pending.contains_key(hash));
+        }
+    }
+
+    /// Forget the transactions that were first seen more than
+    /// [`MAX_PENDING_AGE`] before the given time
+    fn evict(&mut self, now: SystemTime) {
+        let Some(cutoff) = now.checked_sub(MAX_PENDING_AGE) else {
+            return;
+        };
+        while let Some(oldest) = self.order.front() {
+            match self.pending.get(oldest) {
+                Some(tx) if tx.first_seen >= cutoff => break,
+                Some(_) => {
+                    self.pending.remove(oldest);
+                }
+                None => {}
+            }
+            self.order.pop_front();
+        }
+    }
+}
+
+/// Why the service stopped following the mempool
+enum Interruption {
+    /// The RPC node is unreachable (or dropped a subscription), so it's worth
+    /// reconnecting
+    Disconnected(eyre::Report),
+    /// The node supports neither pending transaction subscriptions nor the
+    /// `txpool` namespace
+    Unsupported(eyre::Report),
+    Fatal(eyre::Report),
+}
+
+/// Follow the mempool of the node that the given client is connected to
+/// until something goes wrong
+///
+/// Nodes that can't stream pending transactions but do support the `txpool`
+/// namespace have their transaction pool polled instead.
+async fn follow(
+    client: &AnyClient,
+    mempool: &RwLock<Mempool>,
+    metrics: &Metrics,
+) -> Interruption {
+    let mut headers = match client.block_headers().await {
+        Ok(headers) => headers,
+        Err(e) => return Interruption::Disconnected(e.into()),
+    };
+    let (mut pending, polling): (
+        Box<dyn Stream<Item = Transaction> + Send + Unpin>,
+        bool,
+    ) = match client.pending_transactions().await {
+        Ok(pending) => (pending, false),
+        Err(e) if client.capabilities().txpool => {
+            info!("Polling the transaction pool instead of subscribing to pending transactions: {e}");
+            (Box::new(stream::empty()), true)
+        }
+        Err(e) => return Interruption::Unsupported(e.into()),
+    };
+    let mut poll = tokio::time::interval(TXPOOL_POLL_INTERVAL);
+
+    loop {
+        tokio::select! {
+            Some(tx) = pending.next() => match mempool.write() {
+                Ok(mut mempool) => {
+                    mempool.insert(tx, SystemTime::now());
+                    metrics.mempool_size.set(mempool.pending.len() as i64);
+                }
+                Err(_) => {
+                    return Interruption::Fatal(eyre!("Mempool lock poisoned"))
+                }
+            },
+            _ = poll.tick(), if polling => {
+                let content = match client.provider().raw_request::<_, TxpoolContent>(
+                            "txpool_content".into(),
+                            [(); 0],
+                        )
+                    .await
+                {
+                    Ok(content) => content,
+                    Err(e) => {
+                        debug!("Failed to retrieve transaction pool from RPC: {e:?}");
+                        metrics.failed_rpc_requests.inc();
+                        continue;
+                    }
+                };
+                match mempool.write() {
+                    Ok(mut mempool) => {
+                        let now = SystemTime::now();
+                        content
+                            .pending
+                            .into_values()
+                            .flat_map(HashMap::into_values)
+                            .for_each(|tx| mempool.insert(tx, now));
+                        metrics.mempool_size.set(mempool.pending.len() as i64);
+                    }
+                    Err(_) => {
+                    return Interruption::Fatal(eyre!("Mempool lock poisoned"))
+                }
+                }
+            }
+            Some(header) = headers.next() => {
+                match mempool.write() {
+                    Ok(mut mempool) => mempool.head = Some(header.number),
+                    Err(_) => {
+                    return Interruption::Fatal(eyre!("Mempool lock poisoned"))
+                }
+                }
+                let hashes: Vec<TxHash> = match client.provider().get_block_by_hash(header.hash)
+                    .await
+                {
+                    Ok(Some(block)) => block.transactions.hashes().collect(),
+                    Ok(None) => continue,
+                    Err(e) => {
+                        debug!("Failed to retrieve block from RPC: {e:?}");
+                        metrics.failed_rpc_requests.inc();
+                        continue;
+                    }
+                };
+                match mempool.write() {
+                    Ok(mut mempool) => {
+                        mempool.remove(hashes);
+                        mempool.evict(SystemTime::now());
+                        metrics.mempool_size.set(mempool.pending.len() as i64);
+                    }
+                    Err(_) => {
+                    return Interruption::Fatal(eyre!("Mempool lock poisoned"))
+                }
+                }
+            }
+            else => {
+                return Interruption::Disconnected(eyre!("Subscription ended"))
+            }
+        }
+    }
+}
+
+/// Handle to the mempool service
+#[derive(Clone, Debug)]
+pub struct MempoolService {
+    mempool: Arc<RwLock<Mempool>>,
+    /// Whether the mempool has been asked to be followed yet
+    wanted: watch::Sender<bool>,
+}
+
+impl MempoolService {
+    /// Spawn a new instance of the mempool service
+    ///
+    /// Once [`MempoolService::follow`] is first called, subscribes to the
+    /// pending transactions of the node reachable via the provided
+    /// [`Endpoints`], forgetting each as it's included in a block (or once
+    /// it's been pending for too long). Whenever the node is unreachable, the
+    /// service reconnects with exponential backoff (failing over to the next
+    /// endpoint, if there is one). Nodes that support neither this
+    /// subscription nor the `txpool` namespace are reported via
+    /// [`MempoolService::snapshot`].
+    pub fn spawn(
+        endpoints: Arc<Endpoints>,
+        metrics: Arc<Metrics>,
+    ) -> (Self, ServiceHandle) {
+        let this = Self {
+            mempool: Arc::new(RwLock::new(Mempool::default())),
+            wanted: watch::Sender::new(false),
+        };
+        let mempool = this.mempool.clone();
+        let mut wanted = this.wanted.subscribe();
+
+        let handle = ServiceHandle::spawn(
+            "mempool",
+            move |mut shutdown| async move {
+                tokio::select! {
+                    _ = shutdown.changed() => return Ok(()),
+                    result = wanted.wait_for(|wanted| *wanted) => {
+                        if result.is_err() {
+                            return Ok(());
+                        }
+                    }
+                }
+                let mut delay = INITIAL_RECONNECT_DELAY;
+
+                loop {
+                    let client = tokio::select! {
+                        _ = shutdown.changed() => return Ok(()),
+                        client = AnyClient::connect(&endpoints) => client,
+                    };
+                    let (url, interruption) = match client {
+                        Ok(client) => {
+                            delay = INITIAL_RECONNECT_DELAY;
+                            tokio::select! {
+                                _ = shutdown.changed() => return Ok(()),
+                                interruption = follow(&client, &mempool, &metrics) => {
+                                    (Some(client.url()), interruption)
+                                }
+                            }
+                        }
+                        Err(e) => (None, Interruption::Disconnected(e.into())),
+                    };
+
+                    match interruption {
+                        Interruption::Disconnected(e) => {
+                            if let Some(url) = &url {
+                                endpoints.fail_over(url);
+                            }
+                            warn!(
+                                "Lost the mempool ({e}), reconnecting in {}ms...",
+                                delay.as_millis()
+                            );
+                            tokio::select! {
+                                _ = shutdown.changed() => return Ok(()),
+                                _ = tokio::time::sleep(delay) => {}
+                            }
+                            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                        }
+                        Interruption::Unsupported(e) => {
+                            warn!("Stopped following the mempool: {e:?}");
+                            mempool
+                                .write()
+                                .map_err(|_| eyre!("Mempool lock poisoned"))?
+                                .error = Some(e.to_string());
+                            return Ok(());
+                        }
+                        Interruption::Fatal(e) => return Err(e),
+                    }
+                }
+            },
+        );
+
+        (this, handle)
+    }
+
+    /// Start following the mempool (unless it's already being followed),
+    /// returning whether it already was
+    pub fn follow(&self) -> bool {
+        self.wanted.send_replace(true)
+    }
+
+    /// Every transaction currently pending, in the order they were first seen
+    pub fn snapshot(&self) -> eyre::Result<Vec<PendingTransaction>> {
+        let mempool = self
+            .mempool
+            .read()
+            .map_err(|_| eyre!("Mempool lock poisoned"))?;
+
+        if let Some(e) = &mempool.error {
+            return Err(eyre!("Mempool unavailable ({e})"));
+        }
+
+        Ok(mempool
+            .order
+            .iter()
+            .filter_map(|hash| mempool.pending.get(hash).cloned())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::Address;
+
+    use super::*;
+    use crate::test_utils::eip1559_transaction;
+
+    fn transaction(nonce: u64) -> Transaction {
+        serde_json::from_value(eip1559_transaction(
+            TxHash::left_padding_from(&nonce.to_be_bytes()),
+            Address::with_last_byte(1),
+            nonce,
+        ))
+        .unwrap()
+    }
+
+    fn hashes(mempool: &Mempool) -> Vec<TxHash> {
+        mempool
+            .order
+            .iter()
+            .filter(|hash| mempool.pending.contains_key(*hash))
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn test_mempool() {
+        let mut mempool = Mempool {
+            head: Some(7),
+            ..Default::default()
+        };
+        let now = SystemTime::now();
+        mempool.insert(transaction(1), now);
+        mempool.insert(transaction(2), now);
+        /* transactions seen again keep when they were first seen */
+        mempool.insert(transaction(1), now + Duration::from_secs(1));
+        assert_eq!(
+            hashes(&mempool),
+            vec![TxHash::with_last_byte(1), TxHash::with_last_byte(2)]
+        );
+        let first = &mempool.pending[&TxHash::with_last_byte(1)];
+        assert_eq!((first.first_seen, first.first_seen_block), (now, Some(7)));
+
+        mempool.remove([TxHash::with_last_byte(1)]);
+        assert_eq!(hashes(&mempool), vec![TxHash::with_last_byte(2)]);
+    }
+
+    #[test]
+    fn test_mempool_eviction() {
+        let mut mempool = Mempool::default();
+        let now = SystemTime::now();
+        mempool.insert(transaction(1), now - MAX_PENDING_AGE * 2);
+        mempool.insert(transaction(2), now - MAX_PENDING_AGE);
+        mempool.insert(transaction(3), now);
+        mempool.remove([TxHash::with_last_byte(2)]);
+
+        mempool.evict(now);
+        assert_eq!(hashes(&mempool), vec![TxHash::with_last_byte(3)]);
+        assert_eq!(mempool.order.len(), 1);
+    }
+
+    #[test]
+    fn test_mempool_capacity() {
+        let mut mempool = Mempool::default();
+        let now = SystemTime::now();
+        (0..MAX_PENDING as u64 + 2)
+            .for_each(|nonce| mempool.insert(transaction(nonce), now));
+
+        /* the longest pending are forgotten first */
+        assert_eq!(mempool.pending.len(), MAX_PENDING);
+        assert!(!mempool.pending.contains_key(&TxHash::with_last_byte(1)));
+        assert!(mempool.pending.contains_key(&TxHash::with_last_byte(2)));
+    }
+}
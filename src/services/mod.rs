@@ -1,3 +1,163 @@
 //! Services
+use std::{future::Future, time::Duration};
+
+use eyre::eyre;
+use log::{error, info, warn};
+use tokio::{runtime::Handle, sync::watch, task::JoinHandle};
+
+use self::signals::SignalService;
+
+pub mod beacon;
+pub mod blobs;
 pub mod blockchain;
-pub mod metrics;
+pub mod contracts;
+pub mod devchain;
+pub mod http;
+pub mod mempool;
+pub mod race;
+pub mod rawtx;
+pub mod requests;
+pub mod retrieval;
+pub mod signals;
+pub mod simulator;
+pub mod template;
+pub mod tracer;
+pub mod tracker;
+pub mod txpool;
+pub mod watchlist;
+
+/// How often [`Supervisor::wait`] checks on things
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Handle to a service running as a task on the shared Tokio runtime that can
+/// be asked to shut down
+#[derive(Debug)]
+pub struct ServiceHandle {
+    name: &'static str,
+    shutdown: watch::Sender<bool>,
+    runtime: Handle,
+    task: JoinHandle<eyre::Result<()>>,
+}
+
+impl ServiceHandle {
+    /// Spawn the given service body as a task on the current Tokio runtime
+    ///
+    /// The body is handed a [`watch::Receiver`] that changes to `true` once
+    /// the service has been asked to shut down.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of the context of a Tokio runtime.
+    pub fn spawn<F, Fut>(name: &'static str, f: F) -> Self
+    where
+        F: FnOnce(watch::Receiver<bool>) -> Fut,
+        Fut: Future<Output = eyre::Result<()>> + Send + 'static,
+    {
+        let (shutdown, rx) = watch::channel(false);
+        let runtime = Handle::current();
+        Self {
+            name,
+            shutdown,
+            task: runtime.spawn(f(rx)),
+            runtime,
+        }
+    }
+
+    /// Name of this service (for diagnostics)
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Whether the service has stopped (of its own accord or otherwise)
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Ask the service to shut down and wait for it to do so
+    ///
+    /// Must not be called from within an asynchronous context, as this
+    /// blocks the calling thread.
+    pub fn shutdown(self) -> eyre::Result<()> {
+        /* the service may have already exited and dropped its receiver */
+        let _ = self.shutdown.send(true);
+        self.runtime
+            .block_on(self.task)
+            .map_err(|_| eyre!("{} service panicked", self.name))?
+    }
+}
+
+/// Owns the handles of running services so that they can be shut down
+/// together
+#[derive(Debug, Default)]
+pub struct Supervisor {
+    services: Vec<ServiceHandle>,
+}
+
+impl Supervisor {
+    /// Take ownership of a running service
+    pub fn register(&mut self, service: ServiceHandle) {
+        info!("Supervising {} service", service.name());
+        self.services.push(service);
+    }
+
+    /// Block until either the process has been asked to terminate (as per
+    /// the given [`SignalService`]) or any supervised service stops of its
+    /// own accord
+    pub fn wait(&self, signals: &SignalService) {
+        while !signals.received() {
+            if let Some(service) =
+                self.services.iter().find(|service| service.is_finished())
+            {
+                warn!("{} service stopped, shutting down...", service.name());
+                return;
+            }
+
+            std::thread::sleep(WAIT_POLL_INTERVAL);
+        }
+    }
+
+    /// Shut down every supervised service (in reverse order of
+    /// registration), reporting the first failure
+    ///
+    /// A failing service doesn't prevent the remaining ones from being shut
+    /// down.
+    pub fn shutdown(self) -> eyre::Result<()> {
+        let mut result = Ok(());
+
+        for service in self.services.into_iter().rev() {
+            let name = service.name();
+            if let Err(e) = service.shutdown() {
+                error!("Failed to shut down {name} service: {e:?}");
+                result = result.and(Err(e));
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wait_for_stopped_service() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _guard = runtime.enter();
+        let mut supervisor = Supervisor::default();
+        supervisor.register(ServiceHandle::spawn(
+            "patient",
+            |mut shutdown| async move {
+                let _ = shutdown.changed().await;
+                Ok(())
+            },
+        ));
+        supervisor.register(ServiceHandle::spawn("failing", |_| async {
+            Err(eyre!("failed"))
+        }));
+
+        /* returns once the failing service stops, despite no signal */
+        supervisor.wait(&SignalService::default());
+        assert!(supervisor.shutdown().is_err());
+    }
+}
@@ -0,0 +1,279 @@
+//! Races every configured RPC endpoint against each other, following each
+//! one's chain head to see which delivers new blocks first
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    primitives::{BlockHash, BlockNumber},
+    providers::Provider,
+};
+use eyre::eyre;
+use futures::{future, StreamExt};
+use log::{debug, warn};
+use url::Url;
+
+use crate::{
+    client::{redacted, AnyClient, Client, Endpoints},
+    metrics::Metrics,
+    services::ServiceHandle,
+};
+
+/// Number of the most recent blocks whose first arrival is remembered (so
+/// that later arrivals of the same block can be timed against it)
+const MAX_ARRIVALS: usize = 256;
+/// How long to wait before reconnecting to an endpoint that's gone away
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How a single endpoint is faring in the race
+#[derive(Clone, Debug, Default)]
+pub struct EndpointRace {
+    /// The endpoint, without any credentials
+    pub url: String,
+    /// Latest block announced by the endpoint
+    pub head: Option<(BlockNumber, BlockHash)>,
+    /// Round-trip time of the most recent request
+    pub latency: Option<Duration>,
+    /// Number of blocks announced by the endpoint
+    pub blocks: u64,
+    /// Number of blocks that the endpoint announced before any other
+    pub first: u64,
+    /// Total time by which the endpoint trailed the first to announce each
+    /// of the other blocks it announced
+    delay: Duration,
+    /// Why the endpoint can't be followed right now (if it can't)
+    pub error: Option<String>,
+    /// Whether this is the endpoint currently in use
+    pub active: bool,
+}
+
+impl EndpointRace {
+    /// Mean time by which this endpoint trailed the first to announce each
+    /// block that it wasn't first to announce
+    pub fn mean_delay(&self) -> Option<Duration> {
+        let delayed = self.blocks - self.first;
+        (delayed > 0).then(|| self.delay / delayed as u32)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Race {
+    endpoints: Vec<EndpointRace>,
+    /// When each of the most recent blocks was first announced
+    arrivals: HashMap<BlockHash, Instant>,
+    /// Hashes in the order they were first announced
+    order: VecDeque<BlockHash>,
+}
+
+impl Race {
+    /// Record the announcement of the given block by the endpoint at the
+    /// given index at the given time
+    fn record(
+        &mut self,
+        i: usize,
+        number: BlockNumber,
+        hash: BlockHash,
+        now: Instant,
+    ) {
+        let first = match self.arrivals.get(&hash) {
+            Some(arrival) => Err(now.saturating_duration_since(*arrival)),
+            None => {
+                self.arrivals.insert(hash, now);
+                self.order.push_back(hash);
+                while self.order.len() > MAX_ARRIVALS {
+                    if let Some(oldest) = self.order.pop_front() {
+                        self.arrivals.remove(&oldest);
+                    }
+                }
+                Ok(())
+            }
+        };
+
+        let endpoint = &mut self.endpoints[i];
+        endpoint.head = Some((number, hash));
+        endpoint.blocks += 1;
+        endpoint.error = None;
+        match first {
+            Ok(()) => endpoint.first += 1,
+            Err(delay) => endpoint.delay += delay,
+        }
+    }
+}
+
+/// Follow the chain head of a single endpoint (the one at the given index)
+/// until something goes wrong
+async fn follow(
+    i: usize,
+    url: Url,
+    endpoints: &Endpoints,
+    race: &RwLock<Race>,
+    metrics: &Metrics,
+) -> eyre::Report {
+    let client = match AnyClient::connect_to(endpoints, url).await {
+        Ok(client) => client,
+        Err(e) => return e.into(),
+    };
+    let mut headers = match client.block_headers().await {
+        Ok(headers) => headers,
+        Err(e) => return e.into(),
+    };
+
+    while let Some(header) = headers.next().await {
+        let now = Instant::now();
+        match race.write() {
+            Ok(mut race) => race.record(i, header.number, header.hash, now),
+            Err(_) => return eyre!("Race lock poisoned"),
+        }
+
+        /* announcements carry no timing, so time a request instead */
+        let start = Instant::now();
+        let latency = match client.provider().get_block_number().await {
+            Ok(_) => Some(start.elapsed()),
+            Err(e) => {
+                debug!("Failed to retrieve block number from RPC: {e:?}");
+                metrics.failed_rpc_requests.inc();
+                None
+            }
+        };
+        match race.write() {
+            Ok(mut race) => race.endpoints[i].latency = latency,
+            Err(_) => return eyre!("Race lock poisoned"),
+        }
+    }
+
+    eyre!("Subscription ended")
+}
+
+/// Handle to the endpoint race service
+#[derive(Clone, Debug)]
+pub struct RaceService {
+    endpoints: Arc<Endpoints>,
+    race: Arc<RwLock<Race>>,
+}
+
+impl RaceService {
+    /// Spawn a new instance of the endpoint race service
+    ///
+    /// Every one of the provided [`Endpoints`] (not just the one in use) is
+    /// followed independently, reconnecting to any that go away.
+    pub fn spawn(
+        endpoints: Arc<Endpoints>,
+        metrics: Arc<Metrics>,
+    ) -> (Self, ServiceHandle) {
+        let this = Self {
+            endpoints: endpoints.clone(),
+            race: Arc::new(RwLock::new(Race {
+                endpoints: endpoints
+                    .urls()
+                    .iter()
+                    .map(|url| EndpointRace {
+                        url: redacted(url),
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            })),
+        };
+        let race = this.race.clone();
+
+        let handle =
+            ServiceHandle::spawn("race", move |mut shutdown| async move {
+                let racers =
+                    endpoints.urls().iter().enumerate().map(|(i, url)| {
+                        let (endpoints, race, metrics) =
+                            (&endpoints, &race, &metrics);
+                        async move {
+                            loop {
+                                let e = follow(
+                                    i,
+                                    url.clone(),
+                                    endpoints,
+                                    race,
+                                    metrics,
+                                )
+                                .await;
+                                warn!(
+                                    "Stopped following {}: {e:?}",
+                                    redacted(url)
+                                );
+                                race.write()
+                                    .map_err(|_| eyre!("Race lock poisoned"))?
+                                    .endpoints[i]
+                                    .error = Some(e.to_string());
+                                tokio::time::sleep(RECONNECT_INTERVAL).await;
+                            }
+                        }
+                    });
+
+                tokio::select! {
+                    _ = shutdown.changed() => Ok(()),
+                    result = future::try_join_all(racers) => {
+                        result.map(|_: Vec<()>| ())
+                    }
+                }
+            });
+
+        (this, handle)
+    }
+
+    /// How each endpoint is faring, in the order they were configured
+    pub fn endpoints(&self) -> eyre::Result<Vec<EndpointRace>> {
+        let active = self.endpoints.active();
+        let mut endpoints = self
+            .race
+            .read()
+            .map_err(|_| eyre!("Race lock poisoned"))?
+            .endpoints
+            .clone();
+        endpoints
+            .iter_mut()
+            .zip(self.endpoints.urls())
+            .for_each(|(endpoint, url)| endpoint.active = *url == active);
+        Ok(endpoints)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_race() {
+        let mut race = Race {
+            endpoints: vec![EndpointRace::default(); 2],
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let (a, b) =
+            (BlockHash::with_last_byte(1), BlockHash::with_last_byte(2));
+
+        race.record(0, 1, a, start);
+        race.record(1, 1, a, start + Duration::from_millis(30));
+        race.record(1, 2, b, start + Duration::from_millis(100));
+        race.record(0, 2, b, start + Duration::from_millis(110));
+
+        let [fast, slow] = &race.endpoints[..] else {
+            panic!("expected two endpoints");
+        };
+        assert_eq!((fast.blocks, fast.first), (2, 1));
+        assert_eq!((slow.blocks, slow.first), (2, 1));
+        assert_eq!(fast.head, Some((2, b)));
+        assert_eq!(fast.mean_delay(), Some(Duration::from_millis(10)));
+        assert_eq!(slow.mean_delay(), Some(Duration::from_millis(30)));
+        assert_eq!(EndpointRace::default().mean_delay(), None);
+
+        /* only so many arrivals are remembered */
+        (0..MAX_ARRIVALS as u64 + 1).for_each(|n| {
+            race.record(
+                0,
+                n + 3,
+                BlockHash::left_padding_from(&n.to_be_bytes()),
+                start,
+            )
+        });
+        assert_eq!(race.arrivals.len(), MAX_ARRIVALS);
+        assert!(!race.arrivals.contains_key(&a));
+    }
+}
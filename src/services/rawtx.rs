@@ -0,0 +1,76 @@
+//! Retrieves the EIP-2718 encodings of individual transactions on demand (as
+//! signatures aren't indexed, these can't be rebuilt from the database)
+use std::sync::Arc;
+
+use alloy::primitives::{Bytes, TxHash};
+use log::debug;
+
+use crate::{
+    client::{Client, Connection, Endpoints},
+    metrics::Metrics,
+    services::{
+        requests::{next_request, Fetched, RequestCache},
+        ServiceHandle,
+    },
+};
+
+/// Maximum number of raw transactions remembered at once
+const MAX_RAW_TRANSACTIONS: usize = 64;
+
+/// Handle to the raw transaction retrieval service
+#[derive(Clone, Debug)]
+pub struct RawTransactionService {
+    raw: RequestCache<TxHash, Bytes>,
+}
+
+impl RawTransactionService {
+    /// Spawn a new instance of the raw transaction retrieval service
+    ///
+    /// Transactions passed to [`RawTransactionService::raw`] have their
+    /// encodings retrieved (once each, unless the node couldn't be asked)
+    /// from the node reachable via the provided [`Endpoints`].
+    pub fn spawn(
+        endpoints: Arc<Endpoints>,
+        metrics: Arc<Metrics>,
+    ) -> (Self, ServiceHandle) {
+        let (raw, mut rx) =
+            RequestCache::new("Raw transaction", MAX_RAW_TRANSACTIONS);
+        let this = Self { raw: raw.clone() };
+
+        let handle = ServiceHandle::spawn(
+            "rawtx",
+            move |mut shutdown| async move {
+                let mut connection = Connection::new(endpoints);
+
+                while let Some(hash) =
+                    next_request(&mut shutdown, &mut rx).await
+                {
+                    let result = match connection.client().await {
+                        Ok(client) => client.raw_transaction(hash).await,
+                        Err(e) => Err(e),
+                    };
+                    match result {
+                        Ok(bytes) => raw.set(hash, Fetched::Known(bytes))?,
+                        Err(e) => {
+                            debug!("Failed to retrieve raw transaction {hash}: {e:?}");
+                            metrics.failed_rpc_requests.inc();
+                            connection.failed(&e);
+                            raw.fail(hash, e.to_string())?;
+                        }
+                    }
+                }
+
+                Ok(())
+            },
+        );
+
+        (this, handle)
+    }
+
+    /// Encoding of the transaction with the given [`TxHash`], retrieving it
+    /// first if it hasn't been already (or if retrieving it failed a while
+    /// ago)
+    pub fn raw(&self, hash: TxHash) -> eyre::Result<Fetched<Bytes>> {
+        self.raw.get(hash, || hash)
+    }
+}
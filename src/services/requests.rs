@@ -0,0 +1,239 @@
+//! Remembering what services retrieve on demand
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::{Arc, RwLock, RwLockWriteGuard},
+    time::{Duration, Instant},
+};
+
+use eyre::eyre;
+use tokio::sync::{mpsc, watch};
+
+/// How long a failure that may not last (e.g., the node being unreachable)
+/// is reported for before what failed is asked for again
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// What's known about something retrieved from the node
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Fetched<T> {
+    /// Still being retrieved
+    Pending,
+    /// Couldn't be retrieved
+    Unavailable(String),
+    Known(T),
+}
+
+/// What's remembered about something retrieved on demand
+#[derive(Clone, Debug)]
+struct Entry<V> {
+    fetched: Fetched<V>,
+    /// When it may be asked for again, if retrieving it failed for reasons
+    /// that may not last
+    retry_at: Option<Instant>,
+}
+
+/// Everything a service has retrieved on demand (keyed by `K`), along with
+/// the queue of requests (of type `R`) to the service
+///
+/// At most `capacity` entries are remembered at once, beyond which all but
+/// those still pending are forgotten.
+#[derive(Clone, Debug)]
+pub struct RequestCache<K, V, R = K> {
+    /// Name of the service (for diagnostics)
+    name: &'static str,
+    capacity: usize,
+    entries: Arc<RwLock<HashMap<K, Entry<V>>>>,
+    requests: mpsc::UnboundedSender<R>,
+}
+
+impl<K: Eq + Hash, V: Clone, R> RequestCache<K, V, R> {
+    /// Empty cache of the service with the given name, along with the
+    /// receiving end of the service's queue of requests
+    pub fn new(
+        name: &'static str,
+        capacity: usize,
+    ) -> (Self, mpsc::UnboundedReceiver<R>) {
+        let (requests, rx) = mpsc::unbounded_channel();
+        (Self::with_queue(name, capacity, requests), rx)
+    }
+
+    /// Empty cache of the service with the given name, whose requests are
+    /// sent to the given queue (e.g., as it's shared with another cache)
+    pub fn with_queue(
+        name: &'static str,
+        capacity: usize,
+        requests: mpsc::UnboundedSender<R>,
+    ) -> Self {
+        Self {
+            name,
+            capacity,
+            entries: Arc::new(RwLock::new(HashMap::new())),
+            requests,
+        }
+    }
+
+    /// What's known about the given key, asking the service for it (with
+    /// the request built by `request`) first if it hasn't been already (or
+    /// if retrieving it failed a while ago)
+    pub fn get(
+        &self,
+        key: K,
+        request: impl FnOnce() -> R,
+    ) -> eyre::Result<Fetched<V>> {
+        let mut entries = self.entries()?;
+
+        if let Some(entry) = entries.get(&key).filter(|entry| {
+            entry
+                .retry_at
+                .is_none_or(|retry_at| Instant::now() < retry_at)
+        }) {
+            return Ok(entry.fetched.clone());
+        }
+
+        self.request(&mut entries, key, request())
+    }
+
+    /// Ask the service for the given key afresh (with the request built by
+    /// `request`), unless it's still being retrieved
+    pub fn refresh(
+        &self,
+        key: K,
+        request: impl FnOnce() -> R,
+    ) -> eyre::Result<Fetched<V>> {
+        let mut entries = self.entries()?;
+
+        if entries
+            .get(&key)
+            .is_some_and(|entry| matches!(entry.fetched, Fetched::Pending))
+        {
+            return Ok(Fetched::Pending);
+        }
+
+        self.request(&mut entries, key, request())
+    }
+
+    /// What's known about the given key (or `None` if it hasn't been asked
+    /// for), without asking for it
+    pub fn peek(&self, key: &K) -> eyre::Result<Option<Fetched<V>>> {
+        Ok(self.entries()?.get(key).map(|entry| entry.fetched.clone()))
+    }
+
+    /// Remember what the service retrieved (or found can't be retrieved) for
+    /// the given key
+    pub fn set(&self, key: K, fetched: Fetched<V>) -> eyre::Result<()> {
+        self.entries()?.insert(
+            key,
+            Entry {
+                fetched,
+                retry_at: None,
+            },
+        );
+        Ok(())
+    }
+
+    /// Remember that retrieving the given key failed with the given error
+    /// (for reasons that may not last), so that it's asked for again once
+    /// next wanted after [`RETRY_DELAY`]
+    pub fn fail(&self, key: K, error: String) -> eyre::Result<()> {
+        self.entries()?.insert(
+            key,
+            Entry {
+                fetched: Fetched::Unavailable(error),
+                retry_at: Some(Instant::now() + RETRY_DELAY),
+            },
+        );
+        Ok(())
+    }
+
+    fn entries(
+        &self,
+    ) -> eyre::Result<RwLockWriteGuard<'_, HashMap<K, Entry<V>>>> {
+        self.entries
+            .write()
+            .map_err(|_| eyre!("{} lock poisoned", self.name))
+    }
+
+    fn request(
+        &self,
+        entries: &mut HashMap<K, Entry<V>>,
+        key: K,
+        request: R,
+    ) -> eyre::Result<Fetched<V>> {
+        if entries.len() >= self.capacity {
+            entries
+                .retain(|_, entry| matches!(entry.fetched, Fetched::Pending));
+        }
+
+        self.requests
+            .send(request)
+            .map_err(|_| eyre!("{} service has stopped", self.name))?;
+        entries.insert(
+            key,
+            Entry {
+                fetched: Fetched::Pending,
+                retry_at: None,
+            },
+        );
+        Ok(Fetched::Pending)
+    }
+}
+
+/// The next request sent to a service (or `None` once it's been asked to
+/// shut down, or every handle to it is gone)
+pub async fn next_request<R>(
+    shutdown: &mut watch::Receiver<bool>,
+    requests: &mut mpsc::UnboundedReceiver<R>,
+) -> Option<R> {
+    tokio::select! {
+        _ = shutdown.changed() => None,
+        request = requests.recv() => request,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_cache() {
+        let (cache, mut rx) = RequestCache::<u8, u8>::new("Test", 2);
+
+        assert_eq!(cache.get(1, || 1).unwrap(), Fetched::Pending);
+        assert_eq!(cache.get(1, || 1).unwrap(), Fetched::Pending);
+        assert_eq!(rx.try_recv(), Ok(1));
+        assert!(rx.try_recv().is_err());
+
+        cache.set(1, Fetched::Known(10)).unwrap();
+        assert_eq!(cache.get(1, || 1).unwrap(), Fetched::Known(10));
+        cache
+            .set(2, Fetched::Unavailable("pruned".to_string()))
+            .unwrap();
+        assert_eq!(
+            cache.get(2, || 2).unwrap(),
+            Fetched::Unavailable("pruned".to_string())
+        );
+        assert!(rx.try_recv().is_err());
+
+        /* failures that may not last are only reported for a while */
+        cache.fail(3, "unreachable".to_string()).unwrap();
+        assert_eq!(
+            cache.get(3, || 3).unwrap(),
+            Fetched::Unavailable("unreachable".to_string())
+        );
+        assert!(rx.try_recv().is_err());
+        cache.entries().unwrap().get_mut(&3).unwrap().retry_at =
+            Some(Instant::now());
+        assert_eq!(cache.get(3, || 3).unwrap(), Fetched::Pending);
+        assert_eq!(rx.try_recv(), Ok(3));
+
+        /* only what's still pending survives being over capacity */
+        assert_eq!(cache.peek(&1).unwrap(), None);
+        assert_eq!(cache.peek(&3).unwrap(), Some(Fetched::Pending));
+
+        assert_eq!(cache.refresh(3, || 3).unwrap(), Fetched::Pending);
+        assert!(rx.try_recv().is_err());
+        cache.set(3, Fetched::Known(30)).unwrap();
+        assert_eq!(cache.refresh(3, || 3).unwrap(), Fetched::Pending);
+        assert_eq!(rx.try_recv(), Ok(3));
+    }
+}
@@ -0,0 +1,122 @@
+//! Retrieves blocks (and the blocks of transactions) that haven't been
+//! indexed from the RPC node on demand, writing them to the database
+use std::sync::Arc;
+
+use alloy::{eips::BlockHashOrNumber, primitives::TxHash};
+use eyre::eyre;
+use log::{debug, info};
+
+use crate::{
+    client::{AnyClient, Client, ClientError, Connection, Endpoints},
+    db::Database,
+    l2::AnyBlock,
+    metrics::Metrics,
+    services::{
+        blockchain::logs_and_statuses,
+        requests::{next_request, Fetched, RequestCache},
+        ServiceHandle,
+    },
+};
+
+/// Maximum number of retrievals remembered at once
+const MAX_RETRIEVALS: usize = 64;
+
+/// Something to retrieve from the RPC node
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Retrieval {
+    Block(BlockHashOrNumber),
+    /// The block containing the given transaction
+    Transaction(TxHash),
+}
+
+/// Handle to the retrieval service
+#[derive(Clone, Debug)]
+pub struct RetrievalService {
+    retrievals: RequestCache<Retrieval, ()>,
+}
+
+impl RetrievalService {
+    /// Spawn a new instance of the retrieval service
+    ///
+    /// Whatever is passed to [`RetrievalService::retrieve`] is retrieved
+    /// (once each, unless retrieving it failed) from the node reachable via
+    /// the provided [`Endpoints`] and written to the given [`Database`].
+    pub fn spawn(
+        endpoints: Arc<Endpoints>,
+        db: Database,
+        metrics: Arc<Metrics>,
+    ) -> (Self, ServiceHandle) {
+        let (retrievals, mut rx) =
+            RequestCache::new("Retrieval", MAX_RETRIEVALS);
+        let this = Self {
+            retrievals: retrievals.clone(),
+        };
+
+        let handle =
+            ServiceHandle::spawn("retrieval", move |mut shutdown| async move {
+                let mut connection = Connection::new(endpoints);
+
+                while let Some(retrieval) =
+                    next_request(&mut shutdown, &mut rx).await
+                {
+                    let result = match connection.client().await {
+                        Ok(client) => retrieve(client, &db, retrieval).await,
+                        Err(e) => Err(e.into()),
+                    };
+                    match result {
+                        Ok(()) => {
+                            retrievals.set(retrieval, Fetched::Known(()))?
+                        }
+                        Err(e) => {
+                            debug!("Failed to retrieve {retrieval:?}: {e:?}");
+                            metrics.failed_rpc_requests.inc();
+                            if let Some(e) = e.downcast_ref::<ClientError>() {
+                                connection.failed(e);
+                            }
+                            retrievals.fail(retrieval, format!("{e:#}"))?;
+                        }
+                    }
+                }
+
+                Ok(())
+            });
+
+        (this, handle)
+    }
+
+    /// Progress of retrieving the given [`Retrieval`], asking for it first if
+    /// it hasn't been already (or if retrieving it failed a while ago)
+    pub fn retrieve(&self, retrieval: Retrieval) -> eyre::Result<Fetched<()>> {
+        self.retrievals.get(retrieval, || retrieval)
+    }
+}
+
+/// Retrieve the block of the given [`Retrieval`] (along with its logs and
+/// receipt statuses), writing it to the given [`Database`]
+async fn retrieve(
+    client: &AnyClient,
+    db: &Database,
+    retrieval: Retrieval,
+) -> eyre::Result<()> {
+    let id = match retrieval {
+        Retrieval::Block(id) => id,
+        Retrieval::Transaction(hash) => client
+            .transaction(hash)
+            .await?
+            .block_hash
+            .ok_or(eyre!("transaction {hash} is still pending"))?
+            .into(),
+    };
+    let AnyBlock { block, l2 } = client.any_block(id.into()).await?;
+    let receipts = client.block_receipts(block.header.hash.into()).await?;
+    let (logs, statuses) = logs_and_statuses(&receipts, client.chain_id());
+    db.set_chain_id(client.chain_id())?;
+    db.add_block_atomic(&block)?;
+    if !l2.is_empty() {
+        db.add_l2_block(&l2)?;
+    }
+    db.logs().add(&logs)?;
+    db.receipts().add_statuses(&statuses)?;
+    info!("Retrieved block #{} on demand", block.header.number);
+    Ok(())
+}
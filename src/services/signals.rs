@@ -0,0 +1,77 @@
+//! Listens for requests to terminate the process
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use log::warn;
+
+use crate::services::ServiceHandle;
+
+/// Exit status when forced to exit by a second termination signal (i.e.,
+/// that of a process killed by SIGINT)
+const FORCED_EXIT_CODE: i32 = 130;
+
+/// Handle to the signal handling service
+#[derive(Clone, Debug, Default)]
+pub struct SignalService {
+    received: Arc<AtomicBool>,
+}
+
+impl SignalService {
+    /// Spawn a new instance of the signal handling service
+    ///
+    /// The first SIGINT or SIGTERM (or Ctrl+C, on platforms without these)
+    /// is merely recorded, so that everything else can shut down gracefully.
+    /// Should a second arrive before then, the process exits immediately.
+    pub fn spawn() -> (Self, ServiceHandle) {
+        let this = Self::default();
+        let received = this.received.clone();
+
+        let handle = ServiceHandle::spawn(
+            "signals",
+            move |mut shutdown| async move {
+                loop {
+                    tokio::select! {
+                        _ = shutdown.changed() => return Ok(()),
+                        signal = terminated() => {
+                            signal?;
+                            if received.swap(true, Ordering::Relaxed) {
+                                warn!("Received second termination signal, exiting immediately");
+                                std::process::exit(FORCED_EXIT_CODE);
+                            }
+                            warn!("Received termination signal, shutting down...");
+                        }
+                    }
+                }
+            },
+        );
+
+        (this, handle)
+    }
+
+    /// Whether the process has been asked to terminate
+    pub fn received(&self) -> bool {
+        self.received.load(Ordering::Relaxed)
+    }
+}
+
+/// Wait for the next termination signal
+#[cfg(unix)]
+async fn terminated() -> eyre::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        _ = interrupt.recv() => {}
+        _ = terminate.recv() => {}
+    }
+    Ok(())
+}
+
+/// Wait for the next termination signal
+#[cfg(not(unix))]
+async fn terminated() -> eyre::Result<()> {
+    Ok(tokio::signal::ctrl_c().await?)
+}
@@ -0,0 +1,268 @@
+//! Re-executes individual transactions on demand via `eth_call`, both where
+//! they were originally included and against the latest state
+use std::sync::Arc;
+
+use alloy::{
+    eips::BlockId,
+    primitives::{Bytes, TxHash},
+    providers::Provider,
+    rpc::types::{Transaction, TransactionRequest},
+    sol_types::decode_revert_reason,
+    transports::TransportError,
+};
+use log::debug;
+
+use crate::{
+    client::{self, AnyClient, Connection, Endpoints},
+    services::{
+        requests::{next_request, Fetched, RequestCache},
+        ServiceHandle,
+    },
+};
+
+/// Maximum number of simulated transactions remembered at once
+const MAX_SIMULATIONS: usize = 64;
+
+/// Result of executing a transaction via `eth_call` against a single block
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CallOutcome {
+    /// Executed successfully, returning the given data
+    Returned(Bytes),
+    /// Reverted, with the given (decoded, if possible) reason
+    Reverted(Option<String>),
+    /// Couldn't be executed at all (e.g., the state is unavailable)
+    Failed(String),
+}
+
+impl CallOutcome {
+    fn from_result(result: Result<Bytes, TransportError>) -> Self {
+        match result {
+            Ok(data) => Self::Returned(data),
+            Err(e) => match e.as_error_resp() {
+                Some(resp) if resp.message.contains("revert") => {
+                    Self::Reverted(resp.as_revert_data().and_then(|data| {
+                        (!data.is_empty()).then(|| {
+                            /* fall back to the raw data (e.g., for custom
+                             * errors) */
+                            decode_revert_reason(&data)
+                                .unwrap_or(data.to_string())
+                        })
+                    }))
+                }
+                _ => Self::Failed(e.to_string()),
+            },
+        }
+    }
+}
+
+/// What was learnt from re-executing a transaction
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Simulation {
+    /// Executing on top of the parent of the transaction's block (and so
+    /// without whatever preceded it within that block)
+    pub parent: CallOutcome,
+    /// Executing on top of the latest block
+    pub latest: CallOutcome,
+    /// Gas the transaction would use now (unless it would revert)
+    pub gas_estimate: Option<u64>,
+}
+
+/// What's known about the simulation of a transaction (which is only
+/// unavailable if the node couldn't be reached, as calls that the node itself
+/// fails are part of the [`Simulation`])
+pub type Simulated = Fetched<Simulation>;
+
+/// Call corresponding to the given [`Transaction`]
+///
+/// Its nonce and fees are left out so that only its execution is simulated
+/// (rather than whether it could still be included).
+fn call_request(tx: &Transaction) -> TransactionRequest {
+    let mut request = tx.clone().into_request();
+    request.nonce = None;
+    request.gas_price = None;
+    request.max_fee_per_gas = None;
+    request.max_priority_fee_per_gas = None;
+    request
+}
+
+/// Re-execute the given (mined) [`Transaction`] at its parent block and at
+/// the latest block
+///
+/// Only failing to reach the node at all is an error, as calls that the node
+/// itself fails are part of the [`Simulation`].
+async fn simulate(
+    client: &AnyClient,
+    tx: &Transaction,
+) -> client::Result<Simulation> {
+    let request = call_request(tx);
+    let call = |block: BlockId| {
+        let request = request.clone();
+        async move {
+            match client.provider().call(request).block(block).await {
+                Err(e) if e.is_transport_error() => Err(e),
+                result => Ok(CallOutcome::from_result(result)),
+            }
+        }
+    };
+    let parent = match tx.block_number {
+        Some(number) => call(number.saturating_sub(1).into()).await?,
+        None => CallOutcome::Failed("transaction isn't mined".to_string()),
+    };
+    let latest = call(BlockId::latest()).await?;
+
+    /* estimating gas for a call that reverts only says as much again */
+    let gas_estimate = match latest {
+        CallOutcome::Returned(_) => {
+            let mut request = request.clone();
+            request.gas = None;
+            client
+                .provider()
+                .estimate_gas(request)
+                .await
+                .inspect_err(|e| {
+                    debug!("Failed to estimate gas for simulated call: {e:?}")
+                })
+                .ok()
+        }
+        _ => None,
+    };
+
+    Ok(Simulation {
+        parent,
+        latest,
+        gas_estimate,
+    })
+}
+
+/// Handle to the transaction simulation service
+#[derive(Clone, Debug)]
+pub struct SimulatorService {
+    simulations: RequestCache<TxHash, Simulation, Transaction>,
+}
+
+impl SimulatorService {
+    /// Spawn a new instance of the transaction simulation service
+    ///
+    /// Transactions passed to [`SimulatorService::simulate`] are re-executed
+    /// (once each, unless the node couldn't be reached) using the node
+    /// reachable via the provided [`Endpoints`].
+    pub fn spawn(endpoints: Arc<Endpoints>) -> (Self, ServiceHandle) {
+        let (simulations, mut rx) =
+            RequestCache::new("Simulator", MAX_SIMULATIONS);
+        let this = Self {
+            simulations: simulations.clone(),
+        };
+
+        let handle =
+            ServiceHandle::spawn("simulator", move |mut shutdown| async move {
+                let mut connection = Connection::new(endpoints);
+
+                while let Some(tx) =
+                    next_request::<Transaction>(&mut shutdown, &mut rx).await
+                {
+                    let hash = *tx.inner.tx_hash();
+                    let result = match connection.client().await {
+                        Ok(client) => simulate(client, &tx).await,
+                        Err(e) => Err(e),
+                    };
+                    match result {
+                        Ok(simulation) => {
+                            simulations.set(hash, Fetched::Known(simulation))?
+                        }
+                        Err(e) => {
+                            debug!("Failed to simulate transaction: {e:?}");
+                            connection.failed(&e);
+                            simulations.fail(hash, e.to_string())?;
+                        }
+                    }
+                }
+
+                Ok(())
+            });
+
+        (this, handle)
+    }
+
+    /// Simulation of the given [`Transaction`], simulating it first if it
+    /// hasn't been already (or if the node couldn't be reached a while ago)
+    pub fn simulate(&self, tx: &Transaction) -> eyre::Result<Simulated> {
+        self.simulations.get(*tx.inner.tx_hash(), || tx.clone())
+    }
+
+    /// Simulate the given [`Transaction`] afresh (e.g., as the latest block
+    /// has since changed), unless it's still being simulated
+    pub fn resimulate(&self, tx: &Transaction) -> eyre::Result<Simulated> {
+        self.simulations.refresh(*tx.inner.tx_hash(), || tx.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::Address,
+        rpc::json_rpc::ErrorPayload,
+        sol_types::{Revert, SolError},
+    };
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_utils::eip1559_transaction;
+
+    #[test]
+    fn test_call_outcome() {
+        let revert = |data: Option<serde_json::Value>| {
+            TransportError::ErrorResp(ErrorPayload {
+                code: 3,
+                message: "execution reverted".into(),
+                data: data.map(|data| {
+                    serde_json::value::to_raw_value(&data).unwrap()
+                }),
+            })
+        };
+        let reason =
+            Revert::from("Ownable: caller is not the owner").abi_encode();
+
+        assert_eq!(
+            CallOutcome::from_result(Ok(Bytes::from_static(&[1]))),
+            CallOutcome::Returned(Bytes::from_static(&[1]))
+        );
+        assert_eq!(
+            CallOutcome::from_result(Err(revert(Some(json!(Bytes::from(
+                reason
+            )))))),
+            CallOutcome::Reverted(Some(
+                "revert: Ownable: caller is not the owner".to_string()
+            ))
+        );
+        assert_eq!(
+            CallOutcome::from_result(Err(revert(None))),
+            CallOutcome::Reverted(None)
+        );
+        assert!(matches!(
+            CallOutcome::from_result(Err(TransportError::ErrorResp(
+                ErrorPayload {
+                    code: -32000,
+                    message: "missing trie node".into(),
+                    data: None,
+                }
+            ))),
+            CallOutcome::Failed(_)
+        ));
+    }
+
+    #[test]
+    fn test_call_request() {
+        let mut tx = eip1559_transaction(
+            TxHash::with_last_byte(1),
+            Address::with_last_byte(2),
+            7,
+        );
+        tx["blockNumber"] = json!("0x10");
+        let tx: Transaction = serde_json::from_value(tx).unwrap();
+        let request = call_request(&tx);
+        assert_eq!(request.nonce, None);
+        assert_eq!(request.max_fee_per_gas, None);
+        assert_eq!(request.max_priority_fee_per_gas, None);
+        assert_eq!(request.gas, Some(21_000));
+    }
+}
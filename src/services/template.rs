@@ -0,0 +1,201 @@
+//! Tracks the pending block template of a local builder or validator node
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use alloy::{
+    primitives::{BlockHash, BlockNumber, TxHash},
+    rpc::types::Block,
+};
+use log::{debug, error};
+use url::Url;
+
+use crate::{
+    client::{AnyClient, Client},
+    metrics::Metrics,
+    services::ServiceHandle,
+    storage::{AnyStorage, Storage},
+};
+
+const POLL_INTERVAL_MILLIS: u64 = 1_000; /* 1 second */
+
+/// Handle to the block template tracking service
+#[derive(Clone, Debug)]
+pub struct TemplateService {
+    client: AnyClient,
+}
+
+impl TemplateService {
+    /// Spawn a new instance of the template tracking service
+    ///
+    /// Periodically retrieves the pending block from the (local) node
+    /// reachable at the provided [`Url`] and records its transaction set to
+    /// the provided [`AnyStorage`], so that it can later be compared against
+    /// the block that actually lands at that height.
+    pub fn spawn(
+        local_node: Url,
+        db: AnyStorage,
+        metrics: Arc<Metrics>,
+    ) -> ServiceHandle {
+        ServiceHandle::spawn("template", move |mut shutdown| async move {
+            let this = Self {
+                client: AnyClient::new(local_node).await?,
+            };
+            let mut interval = tokio::time::interval(Duration::from_millis(
+                POLL_INTERVAL_MILLIS,
+            ));
+            let mut last_template: Option<(BlockNumber, Vec<TxHash>)> = None;
+
+            loop {
+                tokio::select! {
+                    _ = shutdown.changed() => return Ok(()),
+                    _ = interval.tick() => {}
+                }
+                let template = match this
+                    .client
+                    .pending_block_transactions()
+                    .await
+                {
+                    Ok(t) => t,
+                    Err(e) => {
+                        /* not every node exposes a pending block, so keep trying */
+                        error!("Failed to retrieve pending block from local node: {e:?}");
+                        metrics.failed_rpc_requests.inc();
+                        continue;
+                    }
+                };
+
+                if last_template.as_ref() == Some(&template) {
+                    continue;
+                }
+
+                db.set_block_template(template.0, &template.1).inspect_err(
+                    |e| {
+                        error!(
+                            "Failed to write block template to database: {e:?}"
+                        )
+                    },
+                )?;
+                debug!(
+                    "Saved template for block #{} ({} transactions)",
+                    template.0,
+                    template.1.len()
+                );
+                last_template = Some(template);
+            }
+        })
+    }
+}
+
+/// What became of a single transaction from a local block template
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TemplateTxStatus {
+    /// Included in the landed block at the given position
+    Landed(usize),
+    /// Included in a different block
+    Elsewhere(BlockNumber),
+    /// Not (yet) included in any indexed block
+    NotIncluded,
+}
+
+/// Comparison between a local block template and the block that actually
+/// landed at the same height
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TemplateDiff {
+    /// Hash of the landed block
+    pub block_hash: BlockHash,
+    /// Each templated transaction (in template order) and what became of it
+    pub transactions: Vec<(TxHash, TemplateTxStatus)>,
+    /// Number of transactions in the landed block that weren't templated
+    pub unexpected: usize,
+}
+
+impl TemplateDiff {
+    /// Compare the given template against the landed [`Block`], using
+    /// `lookup` to find the block number of transactions that landed
+    /// elsewhere
+    pub fn new(
+        template: Vec<TxHash>,
+        block: &Block,
+        lookup: impl Fn(TxHash) -> Option<BlockNumber>,
+    ) -> Self {
+        let landed: HashMap<TxHash, usize> = block
+            .transactions
+            .hashes()
+            .enumerate()
+            .map(|(i, hash)| (hash, i))
+            .collect();
+        let transactions: Vec<(TxHash, TemplateTxStatus)> = template
+            .into_iter()
+            .map(|hash| {
+                let status = match landed.get(&hash) {
+                    Some(i) => TemplateTxStatus::Landed(*i),
+                    None => lookup(hash)
+                        .map(TemplateTxStatus::Elsewhere)
+                        .unwrap_or(TemplateTxStatus::NotIncluded),
+                };
+                (hash, status)
+            })
+            .collect();
+        let templated = transactions
+            .iter()
+            .filter(|(_, status)| matches!(status, TemplateTxStatus::Landed(_)))
+            .count();
+
+        Self {
+            block_hash: block.header.hash,
+            transactions,
+            unexpected: landed.len() - templated,
+        }
+    }
+
+    /// Whether a template was recorded for this block at all
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    /// Number of templated transactions that landed in the block
+    pub fn landed(&self) -> usize {
+        self.transactions
+            .iter()
+            .filter(|(_, status)| matches!(status, TemplateTxStatus::Landed(_)))
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::rpc::types::{BlockTransactions, Header};
+
+    use super::*;
+
+    #[test]
+    fn test_template_diff() {
+        let block = Block::new(
+            Header::default(),
+            BlockTransactions::Hashes(vec![
+                TxHash::with_last_byte(1),
+                TxHash::with_last_byte(2),
+                TxHash::with_last_byte(9),
+            ]),
+        );
+        let template = vec![
+            TxHash::with_last_byte(2),
+            TxHash::with_last_byte(1),
+            TxHash::with_last_byte(3),
+            TxHash::with_last_byte(4),
+        ];
+        let diff = TemplateDiff::new(template, &block, |hash| {
+            (hash == TxHash::with_last_byte(3)).then_some(42)
+        });
+        assert_eq!(
+            diff.transactions,
+            vec![
+                (TxHash::with_last_byte(2), TemplateTxStatus::Landed(1)),
+                (TxHash::with_last_byte(1), TemplateTxStatus::Landed(0)),
+                (TxHash::with_last_byte(3), TemplateTxStatus::Elsewhere(42)),
+                (TxHash::with_last_byte(4), TemplateTxStatus::NotIncluded),
+            ]
+        );
+        assert_eq!(diff.landed(), 2);
+        assert_eq!(diff.unexpected, 1);
+    }
+}
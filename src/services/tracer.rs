@@ -0,0 +1,349 @@
+//! Traces individual transactions on demand to find out who paid whom (and
+//! how much gas was refunded)
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use alloy::{
+    primitives::{Address, TxHash, I256, U256},
+    providers::Provider,
+};
+use log::{debug, warn};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    client::{self, AnyClient, Client, Connection, Endpoints},
+    metrics::Metrics,
+    services::{
+        requests::{next_request, Fetched, RequestCache},
+        ServiceHandle,
+    },
+};
+
+/// Maximum number of traced transactions remembered at once
+const MAX_TRACES: usize = 256;
+/// Delay before the first attempt to reconnect to the RPC node
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+/// Longest (exponentially increasing) delay between reconnection attempts,
+/// after which the transaction being traced is given up on
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(16);
+
+/// Change in the ETH balance of a single account over the course of a
+/// transaction
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BalanceChange {
+    pub address: Address,
+    /// Signed change in balance (in wei)
+    pub delta: I256,
+}
+
+/// What was learnt from tracing a transaction
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Trace {
+    /// Every account whose balance changed, greatest outflow first
+    pub balance_changes: Vec<BalanceChange>,
+    /// Gas refund counter at the end of execution, before the refund is
+    /// capped (if the opcode-level trace succeeded)
+    pub refund: Option<u64>,
+}
+
+/// What's known about the trace of a transaction (which may be unavailable
+/// because, e.g., the node doesn't support `debug_traceTransaction`)
+pub type Traced = Fetched<Trace>;
+
+/// Response to `debug_traceTransaction` with the prestate tracer in diff
+/// mode (only balances are of interest here)
+#[derive(Debug, Default, Deserialize)]
+struct StateDiff {
+    pre: BTreeMap<Address, AccountState>,
+    post: BTreeMap<Address, AccountState>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AccountState {
+    balance: Option<U256>,
+}
+
+/// Response to `debug_traceTransaction` with the default (opcode) tracer
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StructLogs {
+    struct_logs: Vec<StructLog>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct StructLog {
+    /// Omitted whilst zero
+    #[serde(default)]
+    refund: u64,
+}
+
+impl StateDiff {
+    fn balance_changes(&self) -> Vec<BalanceChange> {
+        let addresses: BTreeSet<&Address> =
+            self.pre.keys().chain(self.post.keys()).collect();
+        let mut changes: Vec<BalanceChange> = addresses
+            .into_iter()
+            .filter_map(|address| {
+                let before = self
+                    .pre
+                    .get(address)
+                    .and_then(|state| state.balance)
+                    .unwrap_or_default();
+                /* the post-state omits unchanged fields, and accounts that
+                 * were deleted altogether */
+                let after = match self.post.get(address) {
+                    Some(state) => state.balance.unwrap_or(before),
+                    None => U256::ZERO,
+                };
+                let delta = I256::from_raw(after) - I256::from_raw(before);
+                (!delta.is_zero()).then_some(BalanceChange {
+                    address: *address,
+                    delta,
+                })
+            })
+            .collect();
+        changes.sort_by_key(|change| change.delta);
+        changes
+    }
+}
+
+/// Ask the node for the balance changes caused by the transaction with the
+/// given [`TxHash`]
+async fn trace_balance_changes(
+    client: &AnyClient,
+    hash: TxHash,
+    metrics: &Metrics,
+) -> client::Result<Vec<BalanceChange>> {
+    let diff = client
+        .provider()
+        .raw_request::<_, StateDiff>(
+            "debug_traceTransaction".into(),
+            (
+                hash,
+                json!({
+                    "tracer": "prestateTracer",
+                    "tracerConfig": { "diffMode": true },
+                }),
+            ),
+        )
+        .await
+        .inspect_err(|_| metrics.failed_rpc_requests.inc())?;
+    Ok(diff.balance_changes())
+}
+
+/// Ask the node for the gas refund counter at the end of the transaction with
+/// the given [`TxHash`]
+async fn trace_refund(
+    client: &AnyClient,
+    hash: TxHash,
+    metrics: &Metrics,
+) -> client::Result<u64> {
+    let logs = client
+        .provider()
+        .raw_request::<_, StructLogs>(
+            "debug_traceTransaction".into(),
+            (
+                hash,
+                json!({
+                    "disableStack": true,
+                    "disableStorage": true,
+                    "enableMemory": false,
+                    "enableReturnData": false,
+                }),
+            ),
+        )
+        .await
+        .inspect_err(|_| metrics.failed_rpc_requests.inc())?;
+    Ok(logs.refund())
+}
+
+impl StructLogs {
+    fn refund(&self) -> u64 {
+        self.struct_logs.last().map_or(0, |log| log.refund)
+    }
+}
+
+/// Trace the transaction with the given [`TxHash`] using the node reachable
+/// via the given [`Connection`]
+///
+/// Only failing to reach the node at all is an error (so that it can be
+/// retried); the node refusing to trace the transaction makes it
+/// [`Traced::Unavailable`].
+async fn trace(
+    connection: &mut Connection,
+    hash: TxHash,
+    metrics: &Metrics,
+) -> client::Result<Traced> {
+    let client = connection.client().await?;
+    if !client.capabilities().debug {
+        return Ok(Traced::Unavailable(
+            "node doesn't support debug_traceTransaction".to_string(),
+        ));
+    }
+    let result = match trace_balance_changes(client, hash, metrics).await {
+        Ok(balance_changes) => Ok(Trace {
+            balance_changes,
+            refund: trace_refund(client, hash, metrics)
+                .await
+                .inspect_err(|e| {
+                    debug!(
+                        "Failed to trace refund of transaction {hash}: {e:?}"
+                    )
+                })
+                .ok(),
+        }),
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(trace) => Ok(Traced::Known(trace)),
+        Err(e) if e.is_disconnected() => {
+            connection.failed(&e);
+            Err(e)
+        }
+        Err(e) => {
+            debug!("Failed to trace transaction {hash}: {e:?}");
+            Ok(Traced::Unavailable(e.to_string()))
+        }
+    }
+}
+
+/// Handle to the transaction tracing service
+#[derive(Clone, Debug)]
+pub struct TracerService {
+    traces: RequestCache<TxHash, Trace>,
+}
+
+impl TracerService {
+    /// Spawn a new instance of the transaction tracing service
+    ///
+    /// Transactions passed to [`TracerService::trace`] are traced
+    /// (once each) using the node reachable via the provided [`Endpoints`].
+    /// If the node doesn't support the `debug` namespace, they're reported as
+    /// unavailable without asking it, whereas if it can't be reached, it's
+    /// retried (with exponential backoff) for a while first (and again once
+    /// the trace is next wanted, a while after giving up).
+    pub fn spawn(
+        endpoints: Arc<Endpoints>,
+        metrics: Arc<Metrics>,
+    ) -> (Self, ServiceHandle) {
+        let (traces, mut rx) = RequestCache::new("Tracer", MAX_TRACES);
+        let this = Self {
+            traces: traces.clone(),
+        };
+
+        let handle =
+            ServiceHandle::spawn("tracer", move |mut shutdown| async move {
+                let mut connection = Connection::new(endpoints);
+
+                while let Some(hash) =
+                    next_request(&mut shutdown, &mut rx).await
+                {
+                    let mut delay = INITIAL_RECONNECT_DELAY;
+                    loop {
+                        match trace(&mut connection, hash, &metrics).await {
+                            Ok(traced) => break traces.set(hash, traced)?,
+                            Err(e) if delay <= MAX_RECONNECT_DELAY => {
+                                warn!(
+                                    "Failed to reach the node to trace \
+                                    {hash} ({e}), retrying in {}ms...",
+                                    delay.as_millis()
+                                );
+                                tokio::select! {
+                                    _ = shutdown.changed() => return Ok(()),
+                                    _ = tokio::time::sleep(delay) => {}
+                                }
+                                delay *= 2;
+                            }
+                            Err(e) => {
+                                break traces.fail(hash, e.to_string())?
+                            }
+                        }
+                    }
+                }
+
+                Ok(())
+            });
+
+        (this, handle)
+    }
+
+    /// Trace of the transaction with the given [`TxHash`], tracing it first
+    /// if it hasn't been already (or if the node couldn't be reached a while
+    /// ago)
+    pub fn trace(&self, hash: TxHash) -> eyre::Result<Traced> {
+        self.traces.get(hash, || hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balance_changes() {
+        let sender = Address::repeat_byte(1);
+        let recipient = Address::repeat_byte(2);
+        let untouched = Address::repeat_byte(3);
+        let deleted = Address::repeat_byte(4);
+        let created = Address::repeat_byte(5);
+        let diff: StateDiff = serde_json::from_value(json!({
+            "pre": {
+                sender.to_string(): { "balance": "0x64", "nonce": 1 },
+                recipient.to_string(): { "balance": "0x0a" },
+                untouched.to_string(): { "balance": "0x01" },
+                deleted.to_string(): { "balance": "0x05" },
+            },
+            "post": {
+                sender.to_string(): { "balance": "0x32", "nonce": 2 },
+                recipient.to_string(): { "balance": "0x37" },
+                untouched.to_string(): { "nonce": 1 },
+                created.to_string(): { "balance": "0x05" },
+            },
+        }))
+        .unwrap();
+
+        assert_eq!(
+            diff.balance_changes(),
+            vec![
+                BalanceChange {
+                    address: sender,
+                    delta: I256::try_from(-50).unwrap(),
+                },
+                BalanceChange {
+                    address: deleted,
+                    delta: I256::try_from(-5).unwrap(),
+                },
+                BalanceChange {
+                    address: created,
+                    delta: I256::try_from(5).unwrap(),
+                },
+                BalanceChange {
+                    address: recipient,
+                    delta: I256::try_from(45).unwrap(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_refund() {
+        let logs: StructLogs = serde_json::from_value(json!({
+            "gas": 26_000,
+            "failed": false,
+            "returnValue": "",
+            "structLogs": [
+                { "pc": 0, "op": "PUSH1", "gas": 79_000, "depth": 1 },
+                { "pc": 2, "op": "SSTORE", "gas": 78_997, "depth": 1 },
+                { "pc": 3, "op": "STOP", "gas": 73_997, "depth": 1, "refund": 4_800 },
+            ],
+        }))
+        .unwrap();
+        assert_eq!(logs.refund(), 4_800);
+        assert_eq!(StructLogs::default().refund(), 0);
+    }
+}
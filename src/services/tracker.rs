@@ -0,0 +1,530 @@
+//! Follows individual transactions from the mempool through to finality,
+//! broadcasting them first if need be
+use std::{
+    collections::HashMap,
+    fmt, future,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use alloy::{
+    consensus::TxEnvelope,
+    eips::eip2718::Decodable2718,
+    primitives::{BlockNumber, Bytes, TxHash},
+    providers::Provider,
+};
+use eyre::eyre;
+use futures::StreamExt;
+use log::{debug, error, info, warn};
+use tokio::{
+    sync::mpsc,
+    time::{self, Instant},
+};
+
+use crate::{
+    client::{AnyClient, Client, ClientError, Endpoints},
+    db::ReceiptStatus,
+    metrics::Metrics,
+    services::ServiceHandle,
+};
+
+/// How often tracked transactions are checked in the absence of new blocks
+const POLL_INTERVAL_MILLIS: u64 = 1_000; /* 1 second */
+
+/// Where a transaction is on its way to finality
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TxStatus {
+    /// Not (or no longer) known to the node
+    #[default]
+    Unknown,
+    /// In the mempool
+    Pending,
+    /// Included in the given (not yet finalized) block
+    Included(BlockNumber),
+    /// Included in the given finalized block
+    Finalized(BlockNumber),
+}
+
+impl fmt::Display for TxStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unknown => write!(f, "unknown"),
+            Self::Pending => write!(f, "pending"),
+            Self::Included(number) => write!(f, "included in block #{number}"),
+            Self::Finalized(number) => {
+                write!(f, "finalized in block #{number}")
+            }
+        }
+    }
+}
+
+impl TxStatus {
+    /// Ask the node where the transaction with the given [`TxHash`] currently
+    /// is
+    pub async fn fetch(
+        client: &impl Client,
+        hash: TxHash,
+        metrics: &Metrics,
+    ) -> eyre::Result<Self> {
+        let block_number = match client.transaction(hash).await {
+            Ok(tx) => tx.block_number,
+            Err(e) if e.is_not_found() => return Ok(Self::Unknown),
+            Err(e) => {
+                metrics.failed_rpc_requests.inc();
+                return Err(e.into());
+            }
+        };
+
+        Ok(match block_number {
+            None => Self::Pending,
+            Some(number) => {
+                let finalized = client.finalized_block_number().await;
+                match finalized_or_unsupported(finalized)
+                    .inspect_err(|_| metrics.failed_rpc_requests.inc())?
+                {
+                    Some(finalized) if number <= finalized => {
+                        Self::Finalized(number)
+                    }
+                    _ => Self::Included(number),
+                }
+            }
+        })
+    }
+
+    /// Whether the transaction can no longer change status
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Finalized(_))
+    }
+
+    /// Whether the transaction has been included in a block
+    pub fn is_included(&self) -> bool {
+        matches!(self, Self::Included(_) | Self::Finalized(_))
+    }
+}
+
+/// The number of the latest finalized block from the given response, or
+/// `None` if the node doesn't support the `finalized` tag (as is the case for
+/// many development chains and L2s)
+fn finalized_or_unsupported(
+    response: Result<BlockNumber, ClientError>,
+) -> Result<Option<BlockNumber>, ClientError> {
+    match response {
+        Ok(number) => Ok(Some(number)),
+        Err(e) if e.is_not_found() || e.is_refused() => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// What became of a transaction passed to [`TrackerService::broadcast`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Broadcast {
+    /// Accepted by the node, but not yet included
+    Sent,
+    /// Refused by the node for the given reason
+    Rejected(String),
+    /// Included, with the given receipt
+    Included(ReceiptStatus),
+}
+
+/// Hash of the given signed, EIP-2718-encoded transaction
+///
+/// Fails if the given bytes aren't exactly one such transaction.
+pub fn raw_transaction_hash(raw: &[u8]) -> eyre::Result<TxHash> {
+    let mut buf = raw;
+    let envelope = TxEnvelope::decode_2718(&mut buf)
+        .map_err(|e| eyre!("Invalid raw transaction: {e}"))?;
+
+    if !buf.is_empty() {
+        return Err(eyre!("Invalid raw transaction: trailing bytes"));
+    }
+
+    Ok(*envelope.tx_hash())
+}
+
+/// Submit the given signed, EIP-2718-encoded transaction to the node,
+/// returning its hash
+pub async fn broadcast(
+    client: &AnyClient,
+    raw: &[u8],
+    metrics: &Metrics,
+) -> eyre::Result<TxHash> {
+    /* malformed transactions needn't be sent to find out as much */
+    raw_transaction_hash(raw)?;
+    let pending = client
+        .provider()
+        .send_raw_transaction(raw)
+        .await
+        .inspect_err(|_| metrics.failed_rpc_requests.inc())?;
+    Ok(*pending.tx_hash())
+}
+
+/// Follow the transaction with the given [`TxHash`] until it's finalized,
+/// calling `on_change` with each new status
+///
+/// Returns whether the transaction was finalized before the timeout (if any)
+/// elapsed.
+pub async fn await_finality(
+    client: &impl Client,
+    hash: TxHash,
+    timeout: Option<Duration>,
+    metrics: &Metrics,
+    on_change: impl FnMut(TxStatus),
+) -> eyre::Result<bool> {
+    await_status(
+        client,
+        hash,
+        timeout,
+        metrics,
+        on_change,
+        TxStatus::is_final,
+    )
+    .await
+}
+
+/// Follow the transaction with the given [`TxHash`] until it's included in a
+/// block, calling `on_change` with each new status
+///
+/// Returns whether the transaction was included before the timeout (if any)
+/// elapsed.
+pub async fn await_inclusion(
+    client: &impl Client,
+    hash: TxHash,
+    timeout: Option<Duration>,
+    metrics: &Metrics,
+    on_change: impl FnMut(TxStatus),
+) -> eyre::Result<bool> {
+    await_status(
+        client,
+        hash,
+        timeout,
+        metrics,
+        on_change,
+        TxStatus::is_included,
+    )
+    .await
+}
+
+/// Follow the transaction with the given [`TxHash`] until its status
+/// satisfies `done`, calling `on_change` with each new status
+async fn await_status(
+    client: &impl Client,
+    hash: TxHash,
+    timeout: Option<Duration>,
+    metrics: &Metrics,
+    mut on_change: impl FnMut(TxStatus),
+    done: fn(&TxStatus) -> bool,
+) -> eyre::Result<bool> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let mut headers = client.block_headers().await?;
+    let mut status = None;
+
+    loop {
+        /* a failed check (e.g., whilst reconnecting) is tried again next
+         * time round */
+        match TxStatus::fetch(client, hash, metrics).await {
+            Ok(new) => {
+                if status != Some(new) {
+                    on_change(new);
+                    status = Some(new);
+                }
+
+                if done(&new) {
+                    return Ok(true);
+                }
+            }
+            Err(e) => warn!("Failed to check on transaction {hash}: {e:?}"),
+        }
+
+        tokio::select! {
+            _ = async {
+                match deadline {
+                    Some(t) => time::sleep_until(t).await,
+                    None => future::pending().await,
+                }
+            } => return Ok(false),
+            Some(_) = headers.next() => {}
+            _ = time::sleep(Duration::from_millis(POLL_INTERVAL_MILLIS)) => {}
+        }
+    }
+}
+
+/// Summarise the receipt of the (included) transaction with the given
+/// [`TxHash`], if the node has it
+async fn fetch_receipt(
+    client: &AnyClient,
+    hash: TxHash,
+    metrics: &Metrics,
+) -> Option<ReceiptStatus> {
+    match client.provider().get_transaction_receipt(hash).await {
+        Ok(receipt) => receipt.and_then(|receipt| {
+            ReceiptStatus::from_receipt(&receipt)
+                .inspect_err(|e| debug!("Unusable receipt for {hash}: {e:?}"))
+                .ok()
+        }),
+        Err(e) => {
+            debug!("Failed to retrieve receipt for {hash}: {e:?}");
+            metrics.failed_rpc_requests.inc();
+            None
+        }
+    }
+}
+
+/// Something asked of the tracker service
+#[derive(Clone, Debug)]
+enum Request {
+    Track(TxHash),
+    /// Submit the given signed, EIP-2718-encoded transaction and then track
+    /// it
+    Broadcast(Bytes),
+}
+
+/// Handle to the transaction tracking service
+#[derive(Clone, Debug)]
+pub struct TrackerService {
+    statuses: Arc<RwLock<HashMap<TxHash, TxStatus>>>,
+    broadcasts: Arc<RwLock<HashMap<TxHash, Broadcast>>>,
+    requests: mpsc::UnboundedSender<Request>,
+}
+
+impl TrackerService {
+    /// Spawn a new instance of the transaction tracking service
+    ///
+    /// Transactions passed to [`TrackerService::track`] (or broadcast via
+    /// [`TrackerService::broadcast`]) are re-checked against the node
+    /// reachable via the provided [`Endpoints`] upon each new block (and
+    /// periodically otherwise) until they're finalized.
+    pub fn spawn(
+        endpoints: Arc<Endpoints>,
+        metrics: Arc<Metrics>,
+    ) -> (Self, ServiceHandle) {
+        let (requests, mut rx) = mpsc::unbounded_channel();
+        let this = Self {
+            statuses: Arc::new(RwLock::new(HashMap::new())),
+            broadcasts: Arc::new(RwLock::new(HashMap::new())),
+            requests,
+        };
+        let statuses = this.statuses.clone();
+        let broadcasts = this.broadcasts.clone();
+
+        let handle = ServiceHandle::spawn(
+            "tracker",
+            move |mut shutdown| async move {
+                /* don't bother connecting until there's something to track */
+                let mut next = tokio::select! {
+                    _ = shutdown.changed() => return Ok(()),
+                    request = rx.recv() => request,
+                };
+
+                let client = AnyClient::connect(&endpoints).await?;
+                let mut headers = client.block_headers().await?;
+                let mut interval =
+                    time::interval(Duration::from_millis(POLL_INTERVAL_MILLIS));
+
+                loop {
+                    let hash = match next.take() {
+                        Some(Request::Track(hash)) => Some(hash),
+                        Some(Request::Broadcast(raw)) => {
+                            match raw_transaction_hash(&raw) {
+                                Ok(hash) => {
+                                    let broadcast = match broadcast(
+                                        &client, &raw, &metrics,
+                                    )
+                                    .await
+                                    {
+                                        Ok(_) => {
+                                            info!(
+                                                "Broadcast transaction {hash}"
+                                            );
+                                            Broadcast::Sent
+                                        }
+                                        Err(e) => {
+                                            warn!("Failed to broadcast transaction {hash}: {e:?}");
+                                            Broadcast::Rejected(e.to_string())
+                                        }
+                                    };
+                                    broadcasts
+                                        .write()
+                                        .map_err(|_| {
+                                            eyre!("Tracker lock poisoned")
+                                        })?
+                                        .insert(hash, broadcast);
+                                    Some(hash)
+                                }
+                                Err(e) => {
+                                    warn!(
+                                        "Not broadcasting transaction: {e:?}"
+                                    );
+                                    None
+                                }
+                            }
+                        }
+                        None => None,
+                    };
+
+                    if let Some(hash) = hash {
+                        statuses
+                            .write()
+                            .map_err(|_| eyre!("Tracker lock poisoned"))?
+                            .entry(hash)
+                            .or_default();
+                    }
+
+                    let tracked: Vec<(TxHash, TxStatus)> = statuses
+                        .read()
+                        .map_err(|_| eyre!("Tracker lock poisoned"))?
+                        .iter()
+                        .filter(|(_, status)| !status.is_final())
+                        .map(|(hash, status)| (*hash, *status))
+                        .collect();
+
+                    for (hash, old) in tracked {
+                        let new = match TxStatus::fetch(&client, hash, &metrics)
+                            .await
+                        {
+                            Ok(t) => t,
+                            Err(e) => {
+                                error!("Failed to check on transaction {hash}: {e:?}");
+                                continue;
+                            }
+                        };
+
+                        if new != old {
+                            info!("Transaction {hash} is now {new}");
+                            statuses
+                                .write()
+                                .map_err(|_| eyre!("Tracker lock poisoned"))?
+                                .insert(hash, new);
+                        }
+
+                        /* the receipts of broadcast transactions are reported
+                         * as soon as they're included */
+                        let awaiting_receipt = new.is_included()
+                            && broadcasts
+                                .read()
+                                .map_err(|_| eyre!("Tracker lock poisoned"))?
+                                .get(&hash)
+                                == Some(&Broadcast::Sent);
+
+                        if awaiting_receipt {
+                            if let Some(receipt) =
+                                fetch_receipt(&client, hash, &metrics).await
+                            {
+                                broadcasts
+                                    .write()
+                                    .map_err(|_| {
+                                        eyre!("Tracker lock poisoned")
+                                    })?
+                                    .insert(hash, Broadcast::Included(receipt));
+                            }
+                        }
+                    }
+
+                    next = tokio::select! {
+                        _ = shutdown.changed() => break,
+                        Some(request) = rx.recv() => Some(request),
+                        Some(_) = headers.next() => None,
+                        _ = interval.tick() => None,
+                    };
+                }
+
+                Ok(())
+            },
+        );
+
+        (this, handle)
+    }
+
+    /// Start following the transaction with the given [`TxHash`]
+    pub fn track(&self, hash: TxHash) -> eyre::Result<()> {
+        self.requests
+            .send(Request::Track(hash))
+            .map_err(|_| eyre!("Tracker service has stopped"))
+    }
+
+    /// Submit the given signed, EIP-2718-encoded transaction to the node and
+    /// then follow it, returning its hash
+    ///
+    /// Whether the node accepted it is reported via
+    /// [`TrackerService::broadcasts`].
+    pub fn broadcast(&self, raw: Bytes) -> eyre::Result<TxHash> {
+        let hash = raw_transaction_hash(&raw)?;
+        self.requests
+            .send(Request::Broadcast(raw))
+            .map_err(|_| eyre!("Tracker service has stopped"))?;
+        Ok(hash)
+    }
+
+    /// The last known status of every tracked transaction
+    pub fn statuses(&self) -> HashMap<TxHash, TxStatus> {
+        self.statuses
+            .read()
+            .map(|statuses| statuses.clone())
+            .unwrap_or_default()
+    }
+
+    /// What's become of every transaction broadcast so far
+    pub fn broadcasts(&self) -> HashMap<TxHash, Broadcast> {
+        self.broadcasts
+            .read()
+            .map(|broadcasts| broadcasts.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        consensus::{SignableTransaction, TxLegacy},
+        eips::eip2718::Encodable2718,
+        primitives::{Signature, TxKind, U256},
+        rpc::json_rpc::ErrorPayload,
+        transports::{RpcError, TransportErrorKind},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_raw_transaction_hash() {
+        let tx = TxLegacy {
+            chain_id: Some(1),
+            nonce: 7,
+            gas_price: 1_000_000_000,
+            gas_limit: 21_000,
+            to: TxKind::Call(Default::default()),
+            value: U256::from(1),
+            input: Bytes::new(),
+        };
+        let signed =
+            tx.into_signed(Signature::new(U256::from(1), U256::from(1), false));
+        let envelope = TxEnvelope::from(signed);
+        let raw = envelope.encoded_2718();
+
+        assert_eq!(raw_transaction_hash(&raw).unwrap(), *envelope.tx_hash());
+        assert!(raw_transaction_hash(&raw[..raw.len() - 1]).is_err());
+        assert!(raw_transaction_hash(&[raw.as_slice(), &[0]].concat()).is_err());
+        assert!(raw_transaction_hash(&[]).is_err());
+    }
+
+    #[test]
+    fn test_finalized_or_unsupported() {
+        assert_eq!(finalized_or_unsupported(Ok(7)).unwrap(), Some(7));
+        assert_eq!(
+            finalized_or_unsupported(Err(ClientError::NotFound(
+                "finalized block"
+            )))
+            .unwrap(),
+            None
+        );
+        let refused = RpcError::ErrorResp(ErrorPayload {
+            code: -32602,
+            message: "unknown block tag".into(),
+            data: None,
+        });
+        assert_eq!(
+            finalized_or_unsupported(Err(refused.into())).unwrap(),
+            None
+        );
+        let disconnected = TransportErrorKind::custom_str("connection reset");
+        assert!(finalized_or_unsupported(Err(disconnected.into())).is_err());
+    }
+}
@@ -0,0 +1,499 @@
+//! Retrieves the node's transaction pool, pending and queued alike, via the
+//! `txpool` namespace
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use alloy::{
+    consensus::Transaction as _,
+    primitives::{Address, U64},
+    providers::Provider,
+    rpc::types::Transaction,
+};
+use eyre::eyre;
+use log::debug;
+use serde::Deserialize;
+use tokio::{sync::mpsc, time::Instant};
+
+use crate::{
+    client::{AnyClient, Client, Endpoints},
+    metrics::Metrics,
+    services::{requests::Fetched, ServiceHandle},
+};
+
+/// Minimum interval between retrievals of the whole transaction pool (which
+/// may be large), as opposed to its status or a single sender's transactions
+const FULL_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Response to `txpool_content`
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct TxpoolContent {
+    /// Transactions that could be included next, keyed by sender and then by
+    /// nonce
+    pub pending: HashMap<Address, HashMap<String, Transaction>>,
+    /// Transactions that can't be included yet (e.g., due to a nonce gap),
+    /// keyed likewise
+    #[serde(default)]
+    pub queued: HashMap<Address, HashMap<String, Transaction>>,
+}
+
+/// Response to `txpool_contentFrom`
+#[derive(Debug, Default, Deserialize)]
+struct TxpoolContentFrom {
+    /// Transactions of the sender that could be included next, keyed by nonce
+    pending: HashMap<String, Transaction>,
+    /// Transactions of the sender that can't be included yet, keyed likewise
+    #[serde(default)]
+    queued: HashMap<String, Transaction>,
+}
+
+/// Response to `txpool_status`
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+struct TxpoolStatus {
+    pending: U64,
+    queued: U64,
+}
+
+/// Which part of the transaction pool a transaction sits in
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Queue {
+    Pending,
+    Queued,
+}
+
+/// A transaction in the node's transaction pool
+#[derive(Clone, Debug)]
+pub struct TxpoolTransaction {
+    pub queue: Queue,
+    pub transaction: Transaction,
+}
+
+/// How many transactions a single sender has in the transaction pool
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SenderSummary {
+    pub sender: Address,
+    pub pending: usize,
+    pub queued: usize,
+}
+
+/// Snapshot of the node's transaction pool
+#[derive(Clone, Debug, Default)]
+pub struct Txpool {
+    /// Number of pending transactions, as reported by the node
+    pub pending: u64,
+    /// Number of queued transactions, as reported by the node
+    pub queued: u64,
+    /// Every transaction in the pool by sender, in order of nonce
+    transactions: HashMap<Address, Arc<[TxpoolTransaction]>>,
+}
+
+impl Txpool {
+    fn new(status: TxpoolStatus, content: TxpoolContent) -> Self {
+        let mut transactions: HashMap<Address, Vec<TxpoolTransaction>> =
+            HashMap::new();
+        [
+            (Queue::Pending, content.pending),
+            (Queue::Queued, content.queued),
+        ]
+        .into_iter()
+        .for_each(|(queue, senders)| {
+            senders.into_iter().for_each(|(sender, txs)| {
+                transactions.entry(sender).or_default().extend(
+                    txs.into_values().map(|transaction| TxpoolTransaction {
+                        queue,
+                        transaction,
+                    }),
+                )
+            })
+        });
+
+        Self {
+            pending: status.pending.to(),
+            queued: status.queued.to(),
+            transactions: transactions
+                .into_iter()
+                .map(|(sender, txs)| (sender, in_nonce_order(txs)))
+                .collect(),
+        }
+    }
+
+    /// This transaction pool with the given status, and with the given
+    /// sender's transactions in place of those previously retrieved (if
+    /// any are given)
+    fn updated(
+        &self,
+        status: TxpoolStatus,
+        sender: Option<(Address, TxpoolContentFrom)>,
+    ) -> Self {
+        let mut transactions = self.transactions.clone();
+        if let Some((sender, content)) = sender {
+            let txs: Vec<TxpoolTransaction> = [
+                (Queue::Pending, content.pending),
+                (Queue::Queued, content.queued),
+            ]
+            .into_iter()
+            .flat_map(|(queue, txs)| {
+                txs.into_values().map(move |transaction| TxpoolTransaction {
+                    queue,
+                    transaction,
+                })
+            })
+            .collect();
+            if txs.is_empty() {
+                transactions.remove(&sender);
+            } else {
+                transactions.insert(sender, in_nonce_order(txs));
+            }
+        }
+
+        Self {
+            pending: status.pending.to(),
+            queued: status.queued.to(),
+            transactions,
+        }
+    }
+
+    /// How many transactions each sender has in the pool, busiest first
+    pub fn senders(&self) -> Vec<SenderSummary> {
+        let mut senders: Vec<SenderSummary> = self
+            .transactions
+            .iter()
+            .map(|(sender, txs)| {
+                let pending =
+                    txs.iter().filter(|tx| tx.queue == Queue::Pending).count();
+                SenderSummary {
+                    sender: *sender,
+                    pending,
+                    queued: txs.len() - pending,
+                }
+            })
+            .collect();
+        senders.sort_by(|a, b| {
+            (b.pending + b.queued)
+                .cmp(&(a.pending + a.queued))
+                .then(a.sender.cmp(&b.sender))
+        });
+        senders
+    }
+
+    /// Transactions from the given sender, in order of nonce
+    pub fn transactions(&self, sender: Address) -> &[TxpoolTransaction] {
+        self.transactions
+            .get(&sender)
+            .map(|txs| &**txs)
+            .unwrap_or_default()
+    }
+}
+
+fn in_nonce_order(mut txs: Vec<TxpoolTransaction>) -> Arc<[TxpoolTransaction]> {
+    txs.sort_by_key(|tx| tx.transaction.nonce());
+    txs.into()
+}
+
+/// Retrieve as much of the transaction pool of the node that the given client
+/// is connected to as is due, on top of the given previously retrieved pool
+///
+/// The pool's status is retrieved each time, as are the transactions of the
+/// given sender (if any), whereas the whole pool is retrieved at most once
+/// every [`FULL_REFRESH_INTERVAL`] (as last done at the given instant).
+async fn fetch(
+    client: &AnyClient,
+    metrics: &Metrics,
+    previous: Option<&Arc<Txpool>>,
+    sender: Option<Address>,
+    last_full: &mut Option<Instant>,
+) -> Fetched<Arc<Txpool>> {
+    if !client.capabilities().txpool {
+        return Fetched::Unavailable(
+            "node doesn't support the txpool namespace".to_string(),
+        );
+    }
+
+    let provider = client.provider();
+    let status = match provider
+        .raw_request::<_, TxpoolStatus>("txpool_status".into(), [(); 0])
+        .await
+    {
+        Ok(status) => status,
+        Err(e) => {
+            metrics.failed_rpc_requests.inc();
+            return Fetched::Unavailable(e.to_string());
+        }
+    };
+
+    let previous = match previous {
+        Some(previous)
+            if last_full.is_some_and(|last_full| {
+                last_full.elapsed() < FULL_REFRESH_INTERVAL
+            }) =>
+        {
+            previous
+        }
+        _ => {
+            return match provider
+                .raw_request::<_, TxpoolContent>(
+                    "txpool_content".into(),
+                    [(); 0],
+                )
+                .await
+            {
+                Ok(content) => {
+                    *last_full = Some(Instant::now());
+                    Fetched::Known(Arc::new(Txpool::new(status, content)))
+                }
+                Err(e) => {
+                    metrics.failed_rpc_requests.inc();
+                    Fetched::Unavailable(e.to_string())
+                }
+            }
+        }
+    };
+
+    /* the sender's transactions are left as they were if the node won't
+     * serve them alone, until the next time the whole pool is retrieved */
+    let content = match sender {
+        Some(sender) => match provider
+            .raw_request::<_, TxpoolContentFrom>(
+                "txpool_contentFrom".into(),
+                [sender],
+            )
+            .await
+        {
+            Ok(content) => Some((sender, content)),
+            Err(e) => {
+                debug!("Failed to retrieve txpool content of {sender}: {e:?}");
+                metrics.failed_rpc_requests.inc();
+                None
+            }
+        },
+        None => None,
+    };
+    Fetched::Known(Arc::new(previous.updated(status, content)))
+}
+
+/// Handle to the transaction pool service
+#[derive(Clone, Debug)]
+pub struct TxpoolService {
+    txpool: Arc<RwLock<Option<Fetched<Arc<Txpool>>>>>,
+    /// Requests for refreshes, each naming the sender whose transactions are
+    /// of interest (if any)
+    requests: mpsc::UnboundedSender<Option<Address>>,
+}
+
+impl TxpoolService {
+    /// Spawn a new instance of the transaction pool service
+    ///
+    /// The transaction pool of the node reachable via the provided
+    /// [`Endpoints`] is refreshed each time [`TxpoolService::refresh`] is
+    /// called (retrieving the whole pool only every so often).
+    pub fn spawn(
+        endpoints: Arc<Endpoints>,
+        metrics: Arc<Metrics>,
+    ) -> (Self, ServiceHandle) {
+        let (requests, mut rx) = mpsc::unbounded_channel();
+        let this = Self {
+            txpool: Arc::new(RwLock::new(None)),
+            requests,
+        };
+        let txpool = this.txpool.clone();
+
+        let handle =
+            ServiceHandle::spawn("txpool", move |mut shutdown| async move {
+                /* connecting is put off until the pool is first asked for,
+                 * and retried upon failure */
+                let mut client: Option<AnyClient> = None;
+                let mut last_full = None;
+
+                loop {
+                    let mut sender = tokio::select! {
+                        _ = shutdown.changed() => break,
+                        Some(sender) = rx.recv() => sender,
+                        else => break,
+                    };
+
+                    /* refreshes asked for in the meantime are all answered by
+                     * this one (for whichever sender was asked for last) */
+                    while let Ok(latest) = rx.try_recv() {
+                        sender = latest;
+                    }
+
+                    if client.is_none() {
+                        client = match AnyClient::connect(&endpoints).await {
+                            Ok(client) => Some(client),
+                            Err(e) => {
+                                *txpool.write().map_err(|_| {
+                                    eyre!("Txpool lock poisoned")
+                                })? = Some(Fetched::Unavailable(e.to_string()));
+                                continue;
+                            }
+                        };
+                    }
+
+                    if let Some(client) = &client {
+                        let previous = match txpool
+                            .read()
+                            .map_err(|_| eyre!("Txpool lock poisoned"))?
+                            .clone()
+                        {
+                            Some(Fetched::Known(previous)) => Some(previous),
+                            _ => None,
+                        };
+                        let fetched = fetch(
+                            client,
+                            &metrics,
+                            previous.as_ref(),
+                            sender,
+                            &mut last_full,
+                        )
+                        .await;
+                        *txpool
+                            .write()
+                            .map_err(|_| eyre!("Txpool lock poisoned"))? =
+                            Some(fetched);
+                    }
+                }
+
+                Ok(())
+            });
+
+        (this, handle)
+    }
+
+    /// Retrieve the transaction pool afresh, including the transactions of
+    /// the given sender (if any) even if the rest of the pool isn't due to be
+    pub fn refresh(&self, sender: Option<Address>) -> eyre::Result<()> {
+        let mut txpool = self
+            .txpool
+            .write()
+            .map_err(|_| eyre!("Txpool lock poisoned"))?;
+
+        /* whatever was last retrieved remains until it's replaced */
+        if !matches!(*txpool, Some(Fetched::Known(_))) {
+            *txpool = Some(Fetched::Pending);
+        }
+
+        self.requests
+            .send(sender)
+            .map_err(|_| eyre!("Txpool service has stopped"))
+    }
+
+    /// The most recently retrieved transaction pool (if it's been asked for)
+    pub fn txpool(&self) -> Option<Fetched<Arc<Txpool>>> {
+        self.txpool.read().ok().and_then(|txpool| txpool.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::TxHash;
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_utils::eip1559_transaction;
+
+    fn transaction(from: Address, nonce: u64) -> serde_json::Value {
+        eip1559_transaction(TxHash::with_last_byte(nonce as u8), from, nonce)
+    }
+
+    #[test]
+    fn test_txpool() {
+        let busy = Address::with_last_byte(2);
+        let quiet = Address::with_last_byte(3);
+        let content: TxpoolContent = serde_json::from_value(json!({
+            "pending": {
+                busy.to_string(): {
+                    "5": transaction(busy, 5),
+                    "4": transaction(busy, 4),
+                },
+                quiet.to_string(): { "1": transaction(quiet, 1) },
+            },
+            "queued": {
+                busy.to_string(): { "7": transaction(busy, 7) },
+            },
+        }))
+        .unwrap();
+        let status: TxpoolStatus =
+            serde_json::from_value(json!({"pending": "0x3", "queued": "0x1"}))
+                .unwrap();
+        let txpool = Txpool::new(status, content);
+
+        assert_eq!((txpool.pending, txpool.queued), (3, 1));
+        assert_eq!(
+            txpool.senders(),
+            vec![
+                SenderSummary {
+                    sender: busy,
+                    pending: 2,
+                    queued: 1,
+                },
+                SenderSummary {
+                    sender: quiet,
+                    pending: 1,
+                    queued: 0,
+                },
+            ]
+        );
+        assert_eq!(
+            txpool
+                .transactions(busy)
+                .iter()
+                .map(|tx| (tx.transaction.nonce(), tx.queue))
+                .collect::<Vec<_>>(),
+            vec![(4, Queue::Pending), (5, Queue::Pending), (7, Queue::Queued)]
+        );
+        assert!(txpool.transactions(Address::ZERO).is_empty());
+    }
+
+    #[test]
+    fn test_txpool_updated() {
+        let busy = Address::with_last_byte(2);
+        let quiet = Address::with_last_byte(3);
+        let content: TxpoolContent = serde_json::from_value(json!({
+            "pending": {
+                busy.to_string(): {
+                    "4": transaction(busy, 4),
+                    "5": transaction(busy, 5),
+                },
+                quiet.to_string(): { "1": transaction(quiet, 1) },
+            },
+        }))
+        .unwrap();
+        let status = |pending: u64| TxpoolStatus {
+            pending: U64::from(pending),
+            queued: U64::ZERO,
+        };
+        let txpool = Txpool::new(status(3), content);
+
+        /* only the given sender's transactions are replaced */
+        let from: TxpoolContentFrom = serde_json::from_value(json!({
+            "pending": { "6": transaction(busy, 6), "5": transaction(busy, 5) },
+        }))
+        .unwrap();
+        let updated = txpool.updated(status(2), Some((busy, from)));
+        assert_eq!((updated.pending, updated.queued), (2, 0));
+        assert_eq!(
+            updated
+                .transactions(busy)
+                .iter()
+                .map(|tx| tx.transaction.nonce())
+                .collect::<Vec<_>>(),
+            vec![5, 6]
+        );
+        assert_eq!(updated.transactions(quiet).len(), 1);
+
+        /* senders left with nothing in the pool are dropped */
+        let updated =
+            updated.updated(status(2), Some((quiet, Default::default())));
+        assert_eq!(
+            updated
+                .senders()
+                .iter()
+                .map(|summary| summary.sender)
+                .collect::<Vec<_>>(),
+            vec![busy]
+        );
+        assert_eq!(txpool.updated(status(3), None).senders(), txpool.senders());
+    }
+}
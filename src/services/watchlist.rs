@@ -0,0 +1,459 @@
+//! Records the balances and nonces of watched addresses as blocks are indexed,
+//! and spots their pending transactions that are unlikely to be included
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, RwLock},
+};
+
+use alloy::{
+    consensus::Transaction as _,
+    primitives::{Address, BlockNumber, TxHash},
+    providers::Provider,
+};
+use eyre::eyre;
+use log::{debug, warn};
+use tokio::sync::mpsc;
+
+use crate::{
+    client::{AnyClient, Endpoints},
+    db::{AccountSnapshot, Database},
+    metrics::Metrics,
+    services::{
+        blockchain::{ChainEvent, IndexerState},
+        mempool::PendingTransaction,
+        ServiceHandle,
+    },
+};
+
+/// Number of blocks a transaction may remain pending for before it's deemed
+/// stuck (unless configured otherwise)
+pub const DEFAULT_STUCK_AFTER: u64 = 5;
+/// Percentage by which a replacement transaction's fees must exceed those of
+/// the transaction it replaces for nodes to accept it
+const REPLACEMENT_BUMP_PERCENT: u128 = 10;
+
+type Addresses = Arc<RwLock<BTreeSet<Address>>>;
+
+/// Fees (in wei per gas) offered by an EIP-1559 transaction
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Fees {
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+}
+
+impl Fees {
+    /// Fees likely to see a transaction included soon, given the latest base
+    /// fee and typical priority fee (allowing for the base fee to double)
+    pub fn market(base_fee: u128, tip: u128) -> Self {
+        Self {
+            max_fee_per_gas: base_fee.saturating_mul(2).saturating_add(tip),
+            max_priority_fee_per_gas: tip,
+        }
+    }
+
+    /// Fees with which to replace a transaction offering these fees: enough
+    /// of an increase for nodes to accept the replacement, and no less than
+    /// the given market fees
+    pub fn replacement(&self, market: &Self) -> Self {
+        let bump = |fee: u128| {
+            fee.saturating_add(
+                fee.saturating_mul(REPLACEMENT_BUMP_PERCENT).div_ceil(100),
+            )
+        };
+        let max_priority_fee_per_gas = bump(self.max_priority_fee_per_gas)
+            .max(market.max_priority_fee_per_gas);
+        Self {
+            max_fee_per_gas: bump(self.max_fee_per_gas)
+                .max(market.max_fee_per_gas)
+                .max(max_priority_fee_per_gas),
+            max_priority_fee_per_gas,
+        }
+    }
+}
+
+/// Why a pending transaction is unlikely to be included
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PendingIssue {
+    /// Can't be included until a transaction with the given (lower) nonce is
+    NonceGap { missing: u64 },
+    /// Has been pending for the given number of blocks
+    Stuck { blocks: u64 },
+}
+
+/// Pending transaction from a watched address that is unlikely to be
+/// included
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingWarning {
+    pub hash: TxHash,
+    pub from: Address,
+    pub nonce: u64,
+    pub issue: PendingIssue,
+    /// Suggested fees for a replacement (or, for a nonce gap, for the
+    /// transaction filling it)
+    pub replacement: Fees,
+}
+
+/// Spot the pending transactions sent from the given accounts (each with its
+/// confirmed nonce, if known) that either follow a gap in their sender's
+/// nonces or have been pending for at least `stuck_after` blocks as of the
+/// given head
+///
+/// Pending transactions whose nonces have already been used are ignored, as
+/// they'll never be included.
+pub fn pending_warnings(
+    pending: &[PendingTransaction],
+    accounts: impl IntoIterator<Item = (Address, Option<u64>)>,
+    head: BlockNumber,
+    market: &Fees,
+    stuck_after: u64,
+) -> Vec<PendingWarning> {
+    let accounts: HashMap<Address, Option<u64>> =
+        accounts.into_iter().collect();
+    let mut by_sender: HashMap<Address, Vec<&PendingTransaction>> =
+        HashMap::new();
+    pending.iter().for_each(|pending| {
+        let from = pending.transaction.as_recovered().signer();
+        if accounts.contains_key(&from) {
+            by_sender.entry(from).or_default().push(pending);
+        }
+    });
+
+    let mut warnings: Vec<PendingWarning> = by_sender
+        .into_iter()
+        .flat_map(|(from, mut txs)| {
+            txs.sort_by_key(|pending| pending.transaction.nonce());
+            let confirmed = accounts.get(&from).copied().flatten();
+            let mut expected = confirmed.or_else(|| {
+                txs.first().map(|pending| pending.transaction.nonce())
+            });
+            let mut missing = None;
+
+            txs.into_iter()
+                .filter(move |pending| {
+                    confirmed.is_none_or(|n| pending.transaction.nonce() >= n)
+                })
+                .filter_map(move |pending| {
+                    let tx = &pending.transaction;
+                    let nonce = tx.nonce();
+
+                    /* everything after a gap is held up by it */
+                    match expected {
+                        Some(next) if missing.is_none() && nonce > next => {
+                            missing = Some(next)
+                        }
+                        Some(next) if nonce == next => {
+                            expected = Some(next + 1)
+                        }
+                        _ => {}
+                    }
+
+                    let issue = match missing {
+                        Some(missing) => PendingIssue::NonceGap { missing },
+                        None => {
+                            let blocks =
+                                head.saturating_sub(pending.first_seen_block?);
+                            if blocks < stuck_after {
+                                return None;
+                            }
+                            PendingIssue::Stuck { blocks }
+                        }
+                    };
+                    let fees = Fees {
+                        max_fee_per_gas: tx.max_fee_per_gas(),
+                        max_priority_fee_per_gas: tx
+                            .max_priority_fee_per_gas()
+                            .unwrap_or(tx.max_fee_per_gas()),
+                    };
+
+                    Some(PendingWarning {
+                        hash: *tx.inner.tx_hash(),
+                        from,
+                        nonce,
+                        issue,
+                        replacement: fees.replacement(market),
+                    })
+                })
+        })
+        .collect();
+    warnings.sort_by_key(|warning| (warning.from, warning.nonce));
+    warnings
+}
+
+/// Handle to the watch list service
+#[derive(Clone, Debug)]
+pub struct WatchlistService {
+    addresses: Addresses,
+    /// Newly watched addresses, to be recorded straight away rather than
+    /// upon the next block
+    requests: mpsc::UnboundedSender<Address>,
+}
+
+impl WatchlistService {
+    /// Spawn a new instance of the watch list service, watching the given
+    /// addresses to begin with
+    ///
+    /// Each time the indexer (as per the given [`IndexerState`]) announces a
+    /// new block, the balance and nonce of every watched address as of that
+    /// block are retrieved from the node reachable via the provided
+    /// [`Endpoints`] and recorded in the [`Database`].
+    pub fn spawn(
+        endpoints: Arc<Endpoints>,
+        indexer: &IndexerState,
+        db: Database,
+        addresses: impl IntoIterator<Item = Address>,
+        metrics: Arc<Metrics>,
+    ) -> (Self, ServiceHandle) {
+        let (requests, mut rx) = mpsc::unbounded_channel();
+        let this = Self {
+            addresses: Arc::new(RwLock::new(addresses.into_iter().collect())),
+            requests,
+        };
+        let watched = this.addresses.clone();
+        let mut events = indexer.subscribe();
+
+        let handle = ServiceHandle::spawn(
+            "watchlist",
+            move |mut shutdown| async move {
+                /* connecting is put off until there's something to record,
+                 * and retried upon failure */
+                let mut client: Option<AnyClient> = None;
+
+                loop {
+                    let (addresses, block_number) = tokio::select! {
+                        _ = shutdown.changed() => break,
+                        Some(address) = rx.recv() => (vec![address], None),
+                        Ok(()) = events.changed() => {
+                            let event = *events.borrow_and_update();
+                            match event {
+                                Some(ChainEvent::Block { number, .. }) => {
+                                    let addresses: Vec<Address> = watched
+                                        .read()
+                                        .map_err(|_| {
+                                            eyre!("Watch list lock poisoned")
+                                        })?
+                                        .iter()
+                                        .copied()
+                                        .collect();
+                                    (addresses, Some(number))
+                                }
+                                _ => continue,
+                            }
+                        }
+                    };
+
+                    if addresses.is_empty() {
+                        continue;
+                    }
+
+                    if client.is_none() {
+                        client = AnyClient::connect(&endpoints)
+                            .await
+                            .inspect_err(|e| {
+                                warn!("Failed to connect to record watched accounts: {e:?}")
+                            })
+                            .ok();
+                    }
+
+                    if let Some(client) = &client {
+                        record(client, &db, &addresses, block_number, &metrics)
+                            .await;
+                    }
+                }
+
+                Ok(())
+            },
+        );
+
+        (this, handle)
+    }
+
+    /// Start watching the given [`Address`]
+    pub fn watch(&self, address: Address) -> eyre::Result<()> {
+        self.addresses
+            .write()
+            .map_err(|_| eyre!("Watch list lock poisoned"))?
+            .insert(address);
+        self.requests
+            .send(address)
+            .map_err(|_| eyre!("Watch list service has stopped"))
+    }
+
+    /// Stop watching the given [`Address`]
+    pub fn unwatch(&self, address: Address) -> eyre::Result<()> {
+        self.addresses
+            .write()
+            .map_err(|_| eyre!("Watch list lock poisoned"))?
+            .remove(&address);
+        Ok(())
+    }
+
+    /// Every watched [`Address`] (in order)
+    pub fn addresses(&self) -> Vec<Address> {
+        self.addresses
+            .read()
+            .map(|addresses| addresses.iter().copied().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Record the balance and nonce of each of the given addresses as of the
+/// given block (or the latest one)
+async fn record(
+    client: &AnyClient,
+    db: &Database,
+    addresses: &[Address],
+    block_number: Option<BlockNumber>,
+    metrics: &Metrics,
+) {
+    let provider = client.provider();
+    let block_number = match block_number {
+        Some(number) => number,
+        None => match provider.get_block_number().await {
+            Ok(number) => number,
+            Err(e) => {
+                warn!("Failed to retrieve latest block number: {e:?}");
+                metrics.failed_rpc_requests.inc();
+                return;
+            }
+        },
+    };
+
+    for &address in addresses {
+        let balance = provider.get_balance(address).number(block_number).await;
+        let nonce = provider
+            .get_transaction_count(address)
+            .number(block_number)
+            .await;
+        let snapshot = match (balance, nonce) {
+            (Ok(balance), Ok(nonce)) => AccountSnapshot {
+                address,
+                block_number,
+                balance,
+                nonce,
+            },
+            (Err(e), _) | (_, Err(e)) => {
+                debug!("Failed to retrieve {address} as of block #{block_number}: {e:?}");
+                metrics.failed_rpc_requests.inc();
+                continue;
+            }
+        };
+
+        if let Err(e) = db.add_account_snapshot(&snapshot) {
+            warn!("Failed to record {snapshot:?}: {e:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_utils::eip1559_transaction;
+
+    fn pending(
+        from: Address,
+        nonce: u64,
+        first_seen_block: u64,
+    ) -> PendingTransaction {
+        let mut transaction = eip1559_transaction(
+            TxHash::with_last_byte(nonce as u8),
+            from,
+            nonce,
+        );
+        transaction["maxPriorityFeePerGas"] = json!("0x5f5e100");
+        PendingTransaction {
+            transaction: serde_json::from_value(transaction).unwrap(),
+            first_seen: SystemTime::UNIX_EPOCH,
+            first_seen_block: Some(first_seen_block),
+        }
+    }
+
+    #[test]
+    fn test_replacement_fees() {
+        let fees = Fees {
+            max_fee_per_gas: 1_000,
+            max_priority_fee_per_gas: 15,
+        };
+        assert_eq!(
+            fees.replacement(&Fees::market(100, 10)),
+            Fees {
+                max_fee_per_gas: 1_100,
+                max_priority_fee_per_gas: 17,
+            }
+        );
+        assert_eq!(
+            fees.replacement(&Fees::market(1_000, 20)),
+            Fees {
+                max_fee_per_gas: 2_020,
+                max_priority_fee_per_gas: 20,
+            }
+        );
+    }
+
+    #[test]
+    fn test_pending_warnings() {
+        let watched = Address::with_last_byte(2);
+        let unknown = Address::with_last_byte(3);
+        let other = Address::with_last_byte(4);
+        let pending = [
+            pending(watched, 4, 100),
+            pending(watched, 5, 108),
+            pending(watched, 7, 108),
+            pending(watched, 8, 109),
+            pending(unknown, 9, 101),
+            pending(other, 1, 90),
+        ];
+        let market = Fees::market(1_000_000_000, 100_000_000);
+
+        let warnings = pending_warnings(
+            &pending,
+            [(watched, Some(5)), (unknown, None)],
+            110,
+            &market,
+            5,
+        );
+        let issues: Vec<_> = warnings
+            .iter()
+            .map(|warning| (warning.from, warning.nonce, warning.issue.clone()))
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                (watched, 7, PendingIssue::NonceGap { missing: 6 }),
+                (watched, 8, PendingIssue::NonceGap { missing: 6 }),
+                (unknown, 9, PendingIssue::Stuck { blocks: 9 }),
+            ]
+        );
+        assert_eq!(
+            warnings[0].replacement,
+            Fees {
+                max_fee_per_gas: 2_100_000_000,
+                max_priority_fee_per_gas: 110_000_000,
+            }
+        );
+
+        /* nothing is held up once the gap is filled */
+        assert_eq!(
+            pending_warnings(
+                &pending[..2],
+                [(watched, Some(5))],
+                113,
+                &market,
+                5
+            ),
+            vec![PendingWarning {
+                hash: TxHash::with_last_byte(5),
+                from: watched,
+                nonce: 5,
+                issue: PendingIssue::Stuck { blocks: 5 },
+                replacement: Fees {
+                    max_fee_per_gas: 2_100_000_000,
+                    max_priority_fee_per_gas: 110_000_000,
+                },
+            }]
+        );
+    }
+}
@@ -0,0 +1,79 @@
+//! Printing transactions that match a [`Filter`]
+use std::{
+    io::{self, Write},
+    sync::{Arc, Mutex},
+};
+
+use alloy::{
+    consensus::Transaction as AbstractTransaction,
+    rpc::types::{Block, Log},
+};
+use eyre::eyre;
+use prometheus::IntCounter;
+
+use super::{ndjson::transaction_summary, Sink};
+use crate::{filter::Filter, utils::to_ether};
+
+/// Writes each indexed transaction matching a [`Filter`] to standard output,
+/// as either a line of text or a JSON object (as per the `ndjson` sink's
+/// summaries)
+#[derive(Clone, Debug)]
+pub struct FilterSink {
+    filter: Arc<Filter>,
+    json: bool,
+    out: Arc<Mutex<io::Stdout>>,
+    matches: Arc<IntCounter>,
+}
+
+impl FilterSink {
+    pub fn new(filter: Filter, json: bool, matches: Arc<IntCounter>) -> Self {
+        Self {
+            filter: Arc::new(filter),
+            json,
+            out: Arc::new(Mutex::new(io::stdout())),
+            matches,
+        }
+    }
+}
+
+impl Sink for FilterSink {
+    fn name(&self) -> &'static str {
+        "filter"
+    }
+
+    async fn add_block(&self, block: &Block) -> eyre::Result<()> {
+        let mut out =
+            self.out.lock().map_err(|_| eyre!("Output lock poisoned"))?;
+
+        for tx in block
+            .transactions
+            .txns()
+            .filter(|tx| self.filter.matches(tx))
+        {
+            self.matches.inc();
+
+            if self.json {
+                serde_json::to_writer(&mut *out, &transaction_summary(tx))?;
+                writeln!(out)?;
+            } else {
+                writeln!(
+                    out,
+                    "#{} {} {} -> {} {} ETH",
+                    block.header.number,
+                    tx.inner.tx_hash(),
+                    tx.as_recovered().signer(),
+                    AbstractTransaction::to(tx)
+                        .map(|to| to.to_string())
+                        .unwrap_or("(contract creation)".to_string()),
+                    to_ether(AbstractTransaction::value(tx))
+                )?;
+            }
+        }
+
+        Ok(out.flush()?)
+    }
+
+    async fn add_logs(&self, _logs: &[Log]) -> eyre::Result<()> {
+        Ok(())
+    }
+}
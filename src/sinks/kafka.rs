@@ -0,0 +1,110 @@
+//! Kafka topics (requires the `kafka` feature)
+use std::{sync::Arc, time::Duration};
+
+use alloy::rpc::types::{Block, Log};
+use eyre::eyre;
+use rdkafka::{
+    producer::{FutureProducer, FutureRecord},
+    ClientConfig,
+};
+use serde_json::json;
+
+use super::{Outbox, Sink};
+use crate::l2::L2Block;
+
+const SEND_TIMEOUT_SECS: u64 = 10;
+
+/// Produces one JSON message (each with a `type` of `block`, `l2_block`, or
+/// `log`) per block, per L2 block, and per log to a Kafka topic, keyed by
+/// block hash
+///
+/// Messages are produced from a task of their own so that slow brokers
+/// don't hold up indexing.
+#[derive(Clone)]
+pub struct KafkaSink {
+    producer: Arc<FutureProducer>,
+    topic: Arc<str>,
+    outbox: Outbox<(String, serde_json::Value)>,
+}
+
+impl std::fmt::Debug for KafkaSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KafkaSink")
+            .field("topic", &self.topic)
+            .finish_non_exhaustive()
+    }
+}
+
+impl KafkaSink {
+    /// Connect to the given (comma-separated) bootstrap brokers
+    pub fn new(brokers: &str, topic: &str) -> eyre::Result<Self> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set(
+                "message.timeout.ms",
+                (SEND_TIMEOUT_SECS * 1_000).to_string(),
+            )
+            .create()?;
+        Ok(Self {
+            producer: Arc::new(producer),
+            topic: topic.into(),
+            outbox: Outbox::new("kafka"),
+        })
+    }
+
+    /// Queue the given message (keyed by the given key) to be produced
+    fn send(&self, key: String, value: serde_json::Value) -> eyre::Result<()> {
+        self.outbox.send((key, value), || {
+            let (producer, topic) = (self.producer.clone(), self.topic.clone());
+            move |(key, value): (String, serde_json::Value)| {
+                let (producer, topic) = (producer.clone(), topic.clone());
+                async move {
+                    let payload = serde_json::to_string(&value)?;
+                    producer
+                        .send(
+                            FutureRecord::to(&topic)
+                                .key(&key)
+                                .payload(&payload),
+                            Duration::from_secs(SEND_TIMEOUT_SECS),
+                        )
+                        .await
+                        .map_err(|(e, _)| {
+                            eyre!("Failed to produce Kafka message: {e}")
+                        })?;
+                    Ok(())
+                }
+            }
+        })
+    }
+}
+
+impl Sink for KafkaSink {
+    fn name(&self) -> &'static str {
+        "kafka"
+    }
+
+    async fn add_block(&self, block: &Block) -> eyre::Result<()> {
+        self.send(
+            block.header.hash.to_string(),
+            json!({ "type": "block", "data": block }),
+        )
+    }
+
+    async fn add_l2_block(&self, block: &L2Block) -> eyre::Result<()> {
+        self.send(
+            block.block_hash.to_string(),
+            json!({ "type": "l2_block", "data": block }),
+        )
+    }
+
+    async fn add_logs(&self, logs: &[Log]) -> eyre::Result<()> {
+        for log in logs {
+            self.send(
+                log.block_hash.unwrap_or_default().to_string(),
+                json!({ "type": "log", "data": log }),
+            )?;
+        }
+
+        Ok(())
+    }
+}
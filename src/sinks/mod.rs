@@ -0,0 +1,449 @@
+//! Destinations for indexed chain data
+#![allow(async_fn_in_trait)]
+use std::{
+    fmt,
+    future::Future,
+    str::FromStr,
+    sync::{Arc, OnceLock},
+};
+
+use alloy::{
+    primitives::BlockHash,
+    rpc::types::{Block, Log},
+};
+use eyre::eyre;
+use log::warn;
+use serde::Deserialize;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use url::Url;
+
+use crate::{
+    db::{Finality, ReceiptStatus},
+    l2::L2Block,
+    storage::{blocking, AnyStorage, Storage},
+};
+
+#[cfg(feature = "kafka")]
+pub use self::kafka::KafkaSink;
+pub use self::{
+    alerts::AlertSink, filter::FilterSink, ndjson::NdjsonSink,
+    webhook::WebhookSink,
+};
+
+pub mod alerts;
+pub mod filter;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod ndjson;
+pub mod webhook;
+
+/// Messages that may be queued for a sink's own task to deliver before
+/// writing to it starts failing (so that a sink that has fallen far behind
+/// doesn't grow without bound)
+const OUTBOX_CAPACITY: usize = 1024;
+
+/// Queue of messages delivered (in order) by a task of its own, spawned upon
+/// the first message, so that slow destinations don't hold up indexing
+#[derive(Debug)]
+pub struct Outbox<T> {
+    sink: &'static str,
+    queue: Arc<OnceLock<mpsc::Sender<T>>>,
+}
+
+impl<T> Clone for Outbox<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sink: self.sink,
+            queue: self.queue.clone(),
+        }
+    }
+}
+
+impl<T: Send + 'static> Outbox<T> {
+    /// Queue of messages for the sink with the given name
+    pub fn new(sink: &'static str) -> Self {
+        Self {
+            sink,
+            queue: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Queue the given message, spawning the task that delivers each message
+    /// using the function returned by `courier` if it's yet to be spawned
+    ///
+    /// Messages that can't be delivered are logged and dropped.
+    pub fn send<F, Fut>(
+        &self,
+        message: T,
+        courier: impl FnOnce() -> F,
+    ) -> eyre::Result<()>
+    where
+        F: Fn(T) -> Fut + Send + 'static,
+        Fut: Future<Output = eyre::Result<()>> + Send,
+    {
+        let sink = self.sink;
+        self.queue
+            .get_or_init(|| {
+                let deliver = courier();
+                let (queue, mut queued) = mpsc::channel(OUTBOX_CAPACITY);
+                tokio::spawn(async move {
+                    while let Some(message) = queued.recv().await {
+                        if let Err(e) = deliver(message).await {
+                            warn!("Failed to deliver to {sink} sink: {e:?}");
+                        }
+                    }
+                });
+                queue
+            })
+            .try_send(message)
+            .map_err(|e| match e {
+                TrySendError::Full(_) => {
+                    eyre!("Queue for {sink} sink is full; dropping message")
+                }
+                TrySendError::Closed(_) => {
+                    eyre!("Delivery to {sink} sink has stopped")
+                }
+            })
+    }
+}
+
+/// Something that newly indexed blocks (and their logs) are written to
+pub trait Sink {
+    /// Short name of this sink (for diagnostics)
+    fn name(&self) -> &'static str;
+    /// Whether failing to write to this sink should stop the indexer
+    fn required(&self) -> bool {
+        false
+    }
+    /// Write a newly indexed [`Block`]
+    async fn add_block(&self, block: &Block) -> eyre::Result<()>;
+    /// Write the [`Log`]s emitted by a newly indexed block
+    async fn add_logs(&self, logs: &[Log]) -> eyre::Result<()>;
+    /// Write what's peculiar to rollups about a newly indexed L2 block
+    /// (ignored by default)
+    async fn add_l2_block(&self, _block: &L2Block) -> eyre::Result<()> {
+        Ok(())
+    }
+    /// Write the [`ReceiptStatus`] of each transaction in a newly indexed
+    /// block (ignored by default)
+    async fn add_receipt_statuses(
+        &self,
+        _statuses: &[ReceiptStatus],
+    ) -> eyre::Result<()> {
+        Ok(())
+    }
+    /// Record that the previously written blocks with the given
+    /// [`BlockHash`]es lost a reorg (ignored by default)
+    async fn mark_orphaned(&self, _hashes: &[BlockHash]) -> eyre::Result<()> {
+        Ok(())
+    }
+    /// Record the latest safe and finalized blocks (ignored by default)
+    async fn set_finality(&self, _finality: &Finality) -> eyre::Result<()> {
+        Ok(())
+    }
+}
+
+impl Sink for AnyStorage {
+    fn name(&self) -> &'static str {
+        "database"
+    }
+
+    /// Everything else (e.g., the TUI) reads from the database, so it must
+    /// never fall behind
+    fn required(&self) -> bool {
+        true
+    }
+
+    async fn add_block(&self, block: &Block) -> eyre::Result<()> {
+        blocking(|| Storage::add_block(self, block))
+    }
+
+    async fn add_logs(&self, logs: &[Log]) -> eyre::Result<()> {
+        blocking(|| Storage::add_logs(self, logs))
+    }
+
+    async fn add_l2_block(&self, block: &L2Block) -> eyre::Result<()> {
+        blocking(|| Storage::add_l2_block(self, block))
+    }
+
+    async fn add_receipt_statuses(
+        &self,
+        statuses: &[ReceiptStatus],
+    ) -> eyre::Result<()> {
+        blocking(|| Storage::add_receipt_statuses(self, statuses))
+    }
+
+    async fn mark_orphaned(&self, hashes: &[BlockHash]) -> eyre::Result<()> {
+        blocking(|| Storage::mark_orphaned(self, hashes))
+    }
+
+    async fn set_finality(&self, finality: &Finality) -> eyre::Result<()> {
+        blocking(|| Storage::set_finality(self, finality))
+    }
+}
+
+/// Sink type that is generic over all supported sinks
+#[derive(Clone, Debug)]
+pub enum AnySink {
+    Database(AnyStorage),
+    Ndjson(NdjsonSink),
+    Webhook(WebhookSink),
+    Alerts(AlertSink),
+    Filter(FilterSink),
+    #[cfg(feature = "kafka")]
+    Kafka(KafkaSink),
+}
+
+impl AnySink {
+    /// Handle to the underlying [`AnyStorage`] (if this is the database sink)
+    pub fn storage(&self) -> Option<&AnyStorage> {
+        match self {
+            Self::Database(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// Construct the sink described by the given [`SinkSpec`], writing to
+    /// the given [`AnyStorage`] if it's the database sink
+    pub fn new(spec: &SinkSpec, db: &AnyStorage) -> eyre::Result<Self> {
+        Ok(match spec {
+            SinkSpec::Database => Self::Database(db.clone()),
+            SinkSpec::Ndjson => Self::Ndjson(NdjsonSink::stdout()),
+            SinkSpec::Webhook(url) => {
+                Self::Webhook(WebhookSink::new(url.clone()))
+            }
+            #[cfg(feature = "kafka")]
+            SinkSpec::Kafka { brokers, topic } => {
+                Self::Kafka(KafkaSink::new(brokers, topic)?)
+            }
+            #[cfg(not(feature = "kafka"))]
+            SinkSpec::Kafka { .. } => {
+                return Err(eyre!(
+                    "Kafka sinks require building with the `kafka` feature"
+                ))
+            }
+        })
+    }
+}
+
+impl Sink for AnySink {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Database(t) => t.name(),
+            Self::Ndjson(t) => t.name(),
+            Self::Webhook(t) => t.name(),
+            Self::Alerts(t) => t.name(),
+            Self::Filter(t) => t.name(),
+            #[cfg(feature = "kafka")]
+            Self::Kafka(t) => t.name(),
+        }
+    }
+
+    fn required(&self) -> bool {
+        match self {
+            Self::Database(t) => t.required(),
+            Self::Ndjson(t) => t.required(),
+            Self::Webhook(t) => t.required(),
+            Self::Alerts(t) => t.required(),
+            Self::Filter(t) => t.required(),
+            #[cfg(feature = "kafka")]
+            Self::Kafka(t) => t.required(),
+        }
+    }
+
+    async fn add_block(&self, block: &Block) -> eyre::Result<()> {
+        match self {
+            Self::Database(t) => Sink::add_block(t, block).await,
+            Self::Ndjson(t) => t.add_block(block).await,
+            Self::Webhook(t) => t.add_block(block).await,
+            Self::Alerts(t) => t.add_block(block).await,
+            Self::Filter(t) => t.add_block(block).await,
+            #[cfg(feature = "kafka")]
+            Self::Kafka(t) => t.add_block(block).await,
+        }
+    }
+
+    async fn add_logs(&self, logs: &[Log]) -> eyre::Result<()> {
+        match self {
+            Self::Database(t) => Sink::add_logs(t, logs).await,
+            Self::Ndjson(t) => t.add_logs(logs).await,
+            Self::Webhook(t) => t.add_logs(logs).await,
+            Self::Alerts(t) => t.add_logs(logs).await,
+            Self::Filter(t) => t.add_logs(logs).await,
+            #[cfg(feature = "kafka")]
+            Self::Kafka(t) => t.add_logs(logs).await,
+        }
+    }
+
+    async fn add_l2_block(&self, block: &L2Block) -> eyre::Result<()> {
+        match self {
+            Self::Database(t) => Sink::add_l2_block(t, block).await,
+            Self::Ndjson(t) => t.add_l2_block(block).await,
+            Self::Webhook(t) => t.add_l2_block(block).await,
+            Self::Alerts(t) => t.add_l2_block(block).await,
+            Self::Filter(t) => t.add_l2_block(block).await,
+            #[cfg(feature = "kafka")]
+            Self::Kafka(t) => t.add_l2_block(block).await,
+        }
+    }
+
+    async fn add_receipt_statuses(
+        &self,
+        statuses: &[ReceiptStatus],
+    ) -> eyre::Result<()> {
+        match self {
+            Self::Database(t) => Sink::add_receipt_statuses(t, statuses).await,
+            Self::Ndjson(t) => t.add_receipt_statuses(statuses).await,
+            Self::Webhook(t) => t.add_receipt_statuses(statuses).await,
+            Self::Alerts(t) => t.add_receipt_statuses(statuses).await,
+            Self::Filter(t) => t.add_receipt_statuses(statuses).await,
+            #[cfg(feature = "kafka")]
+            Self::Kafka(t) => t.add_receipt_statuses(statuses).await,
+        }
+    }
+
+    async fn mark_orphaned(&self, hashes: &[BlockHash]) -> eyre::Result<()> {
+        match self {
+            Self::Database(t) => Sink::mark_orphaned(t, hashes).await,
+            Self::Ndjson(t) => t.mark_orphaned(hashes).await,
+            Self::Webhook(t) => t.mark_orphaned(hashes).await,
+            Self::Alerts(t) => t.mark_orphaned(hashes).await,
+            Self::Filter(t) => t.mark_orphaned(hashes).await,
+            #[cfg(feature = "kafka")]
+            Self::Kafka(t) => t.mark_orphaned(hashes).await,
+        }
+    }
+
+    async fn set_finality(&self, finality: &Finality) -> eyre::Result<()> {
+        match self {
+            Self::Database(t) => Sink::set_finality(t, finality).await,
+            Self::Ndjson(t) => t.set_finality(finality).await,
+            Self::Webhook(t) => t.set_finality(finality).await,
+            Self::Alerts(t) => t.set_finality(finality).await,
+            Self::Filter(t) => t.set_finality(finality).await,
+            #[cfg(feature = "kafka")]
+            Self::Kafka(t) => t.set_finality(finality).await,
+        }
+    }
+}
+
+/// Which sink to enable (e.g., `db`, `ndjson`, a webhook URL such as
+/// `https://example.com/blocks`, or a Kafka topic such as
+/// `kafka://localhost:9092/blocks`)
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum SinkSpec {
+    /// The database given by `--db`
+    Database,
+    /// Newline-delimited JSON on standard output
+    Ndjson,
+    /// HTTP POST requests to the given URL
+    Webhook(Url),
+    /// Messages produced to the given topic of a Kafka cluster (reached via
+    /// the given comma-separated bootstrap brokers)
+    Kafka { brokers: String, topic: String },
+}
+
+impl fmt::Display for SinkSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Database => write!(f, "db"),
+            Self::Ndjson => write!(f, "ndjson"),
+            Self::Webhook(url) => write!(f, "{url}"),
+            Self::Kafka { brokers, topic } => {
+                write!(f, "kafka://{brokers}/{topic}")
+            }
+        }
+    }
+}
+
+impl FromStr for SinkSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "db" | "database" => Ok(Self::Database),
+            "ndjson" => Ok(Self::Ndjson),
+            s if s.starts_with("http://") || s.starts_with("https://") => {
+                Ok(Self::Webhook(s.parse().map_err(|_| "invalid webhook URL")?))
+            }
+            s if s.starts_with("kafka://") => {
+                let (brokers, topic) = s
+                    .trim_start_matches("kafka://")
+                    .split_once('/')
+                    .filter(|(brokers, topic)| {
+                        !brokers.is_empty() && !topic.is_empty()
+                    })
+                    .ok_or("expected kafka://<brokers>/<topic>")?;
+                Ok(Self::Kafka {
+                    brokers: brokers.to_string(),
+                    topic: topic.to_string(),
+                })
+            }
+            other => Err(format!("unknown sink: {other}")),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SinkSpec {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sink_specs() {
+        assert_eq!("db".parse(), Ok(SinkSpec::Database));
+        assert_eq!("ndjson".parse(), Ok(SinkSpec::Ndjson));
+        assert_eq!(
+            "https://example.com/blocks".parse(),
+            Ok(SinkSpec::Webhook(
+                "https://example.com/blocks".parse().unwrap()
+            ))
+        );
+        assert_eq!(
+            "kafka://a:9092,b:9092/blocks".parse(),
+            Ok(SinkSpec::Kafka {
+                brokers: "a:9092,b:9092".to_string(),
+                topic: "blocks".to_string(),
+            })
+        );
+        assert!("kafka://a:9092".parse::<SinkSpec>().is_err());
+        assert!("stdout".parse::<SinkSpec>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_outbox_drops_when_full() {
+        let outbox = Outbox::new("test");
+        let (release, released) = tokio::sync::oneshot::channel::<()>();
+        let released = Arc::new(tokio::sync::Mutex::new(Some(released)));
+        let courier = || {
+            move |_: usize| {
+                let released = released.clone();
+                async move {
+                    if let Some(released) = released.lock().await.take() {
+                        released.await.ok();
+                    }
+                    Ok(())
+                }
+            }
+        };
+
+        /* the first message is taken by the (stuck) courier */
+        outbox.send(0, courier).unwrap();
+        tokio::task::yield_now().await;
+        for n in 1..=OUTBOX_CAPACITY {
+            outbox.send(n, || |_| async { Ok(()) }).unwrap();
+        }
+        assert!(outbox.send(0, || |_| async { Ok(()) }).is_err());
+        release.send(()).unwrap();
+    }
+}
@@ -0,0 +1,151 @@
+//! Newline-delimited JSON output
+use std::{
+    io::{self, Write},
+    sync::{Arc, Mutex},
+};
+
+use alloy::{
+    consensus::Transaction as AbstractTransaction,
+    rpc::types::{Block, Log, Transaction},
+};
+use eyre::eyre;
+use serde_json::{json, Value};
+
+use super::Sink;
+use crate::{export::big_number, l2::L2Block, utils::BuilderIdentity};
+
+/// Writes one JSON object per line (each with a `type` of `block`, `l2_block`,
+/// or `log`) to standard output
+///
+/// In summary mode, only the salient fields of each block and of each of its
+/// transactions are written instead (with a `type` of either `block` or
+/// `transaction`), and L2 blocks and logs are skipped.
+#[derive(Clone, Debug)]
+pub struct NdjsonSink {
+    out: Arc<Mutex<io::Stdout>>,
+    summarise: bool,
+}
+
+impl NdjsonSink {
+    pub fn stdout() -> Self {
+        Self {
+            out: Arc::new(Mutex::new(io::stdout())),
+            summarise: false,
+        }
+    }
+
+    /// Write summaries of each block and transaction to standard output
+    pub fn summaries() -> Self {
+        Self {
+            summarise: true,
+            ..Self::stdout()
+        }
+    }
+
+    fn write_lines(
+        &self,
+        lines: impl IntoIterator<Item = serde_json::Value>,
+    ) -> eyre::Result<()> {
+        let mut out =
+            self.out.lock().map_err(|_| eyre!("Output lock poisoned"))?;
+
+        for line in lines {
+            serde_json::to_writer(&mut *out, &line)?;
+            writeln!(out)?;
+        }
+
+        Ok(out.flush()?)
+    }
+}
+
+impl Sink for NdjsonSink {
+    fn name(&self) -> &'static str {
+        "ndjson"
+    }
+
+    async fn add_block(&self, block: &Block) -> eyre::Result<()> {
+        if self.summarise {
+            return self
+                .write_lines(std::iter::once(block_summary(block)).chain(
+                    block.transactions.txns().map(transaction_summary),
+                ));
+        }
+
+        self.write_lines([json!({ "type": "block", "data": block })])
+    }
+
+    async fn add_l2_block(&self, block: &L2Block) -> eyre::Result<()> {
+        if self.summarise {
+            return Ok(());
+        }
+
+        self.write_lines([json!({ "type": "l2_block", "data": block })])
+    }
+
+    async fn add_logs(&self, logs: &[Log]) -> eyre::Result<()> {
+        if self.summarise {
+            return Ok(());
+        }
+
+        self.write_lines(
+            logs.iter().map(|log| json!({ "type": "log", "data": log })),
+        )
+    }
+}
+
+fn block_summary(block: &Block) -> Value {
+    let header = &block.header;
+    json!({
+        "type": "block",
+        "number": header.number,
+        "hash": header.hash,
+        "timestamp": header.timestamp,
+        "gas_used": header.gas_used,
+        "gas_limit": header.gas_limit,
+        "base_fee_per_gas": header.base_fee_per_gas,
+        "builder": BuilderIdentity::from(header.extra_data.clone()).to_string(),
+        "transaction_count": block.transactions.len(),
+    })
+}
+
+pub(crate) fn transaction_summary(tx: &Transaction) -> Value {
+    json!({
+        "type": "transaction",
+        "hash": tx.inner.tx_hash(),
+        "block_number": tx.block_number,
+        "transaction_index": tx.transaction_index,
+        "from": tx.as_recovered().signer(),
+        "to": tx.to(),
+        "nonce": tx.nonce(),
+        "value": tx.value().to_string(),
+        "gas_limit": tx.gas_limit(),
+        "max_fee_per_gas": big_number(tx.max_fee_per_gas()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{primitives::BlockHash, rpc::types::Header};
+
+    use super::*;
+
+    #[test]
+    fn test_block_summary() {
+        let block = Block::empty(Header {
+            hash: BlockHash::with_last_byte(1),
+            inner: alloy::consensus::Header {
+                number: 1,
+                gas_used: 21_000,
+                extra_data: "beaverbuild.org".as_bytes().to_vec().into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let summary = block_summary(&block);
+        assert_eq!(summary["type"], json!("block"));
+        assert_eq!(summary["number"], json!(1));
+        assert_eq!(summary["gas_used"], json!(21_000));
+        assert_eq!(summary["builder"], json!("beaverbuild"));
+        assert_eq!(summary["transaction_count"], json!(0));
+    }
+}
@@ -0,0 +1,73 @@
+//! HTTP webhooks
+use std::time::Duration;
+
+use alloy::rpc::types::{Block, Log};
+use serde_json::json;
+use url::Url;
+
+use super::{Outbox, Sink};
+use crate::l2::L2Block;
+
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+
+/// POSTs each block (and, separately, what's peculiar to rollups about it and
+/// its logs) as a JSON object with a `type` of `block`, `l2_block`, or `logs`
+/// to a URL
+///
+/// Requests are made from a task of their own so that a slow endpoint
+/// doesn't hold up indexing.
+#[derive(Clone, Debug)]
+pub struct WebhookSink {
+    url: Url,
+    client: reqwest::Client,
+    outbox: Outbox<serde_json::Value>,
+}
+
+impl WebhookSink {
+    pub fn new(url: Url) -> Self {
+        Self {
+            url,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+                .build()
+                .expect("invariant violated: invalid HTTP client settings"),
+            outbox: Outbox::new("webhook"),
+        }
+    }
+
+    /// Queue the given body to be POSTed
+    fn post(&self, body: serde_json::Value) -> eyre::Result<()> {
+        self.outbox.send(body, || {
+            let (client, url) = (self.client.clone(), self.url.clone());
+            move |body| {
+                let request = client.post(url.clone()).json(&body).send();
+                async move {
+                    request.await?.error_for_status()?;
+                    Ok(())
+                }
+            }
+        })
+    }
+}
+
+impl Sink for WebhookSink {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn add_block(&self, block: &Block) -> eyre::Result<()> {
+        self.post(json!({ "type": "block", "data": block }))
+    }
+
+    async fn add_l2_block(&self, block: &L2Block) -> eyre::Result<()> {
+        self.post(json!({ "type": "l2_block", "data": block }))
+    }
+
+    async fn add_logs(&self, logs: &[Log]) -> eyre::Result<()> {
+        if logs.is_empty() {
+            return Ok(());
+        }
+
+        self.post(json!({ "type": "logs", "data": logs }))
+    }
+}
@@ -0,0 +1,249 @@
+//! Portable snapshots of indexed data, so that one database can seed another
+//! without re-fetching everything from an RPC node
+//!
+//! A snapshot is gzipped NDJSON: a header line, then a line holding what's
+//! recorded about the chain as a whole (see [`ChainState`]), then one line
+//! per block holding everything indexed about it (see [`IndexedBlock`]).
+//! Whatever's derived from indexed blocks (e.g., fee statistics) isn't
+//! included, as it's derived afresh as they're loaded.
+use std::{
+    io::{BufRead, BufReader, Write},
+    ops::RangeInclusive,
+};
+
+use alloy::primitives::BlockNumber;
+use eyre::eyre;
+use flate2::{bufread::MultiGzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::{ChainState, IndexedBlock},
+    storage::Storage,
+};
+
+/// Version of the snapshot format written by this version of `blocktop`
+const VERSION: u64 = 2;
+/// Leading bytes of every gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[derive(Debug, Deserialize, Serialize)]
+struct SnapshotHeader {
+    version: u64,
+}
+
+/// Write every block within the given range (or every block stored, if
+/// there's no range) to a (gzipped) snapshot, returning the number of blocks
+/// written
+pub fn dump(
+    storage: &impl Storage,
+    range: Option<RangeInclusive<BlockNumber>>,
+    out: &mut impl Write,
+) -> eyre::Result<usize> {
+    let mut out = GzEncoder::new(out, Compression::default());
+    serde_json::to_writer(&mut out, &SnapshotHeader { version: VERSION })?;
+    writeln!(out)?;
+    serde_json::to_writer(&mut out, &storage.chain_state()?)?;
+    writeln!(out)?;
+
+    let range = match range {
+        Some(range) => range,
+        None => match (
+            storage.earliest_block_header()?,
+            storage.latest_block_header()?,
+        ) {
+            (Some(earliest), Some(latest)) => earliest.number..=latest.number,
+            _ => {
+                out.finish()?.flush()?;
+                return Ok(0);
+            }
+        },
+    };
+    let mut count = 0;
+
+    for number in range {
+        if let Some(block) = storage.indexed_block(number)? {
+            serde_json::to_writer(&mut out, &block)?;
+            writeln!(out)?;
+            count += 1;
+        }
+    }
+
+    out.finish()?.flush()?;
+    Ok(count)
+}
+
+/// Store everything in the given snapshot (whether gzipped or not) within a
+/// single transaction, returning the number of blocks stored
+pub fn load(
+    storage: &impl Storage,
+    mut input: impl BufRead,
+) -> eyre::Result<usize> {
+    let input: Box<dyn BufRead> = if input.fill_buf()?.starts_with(&GZIP_MAGIC)
+    {
+        Box::new(BufReader::new(MultiGzDecoder::new(input)))
+    } else {
+        Box::new(input)
+    };
+    let mut lines = input.lines();
+    let header: SnapshotHeader =
+        serde_json::from_str(&lines.next().ok_or(eyre!("Snapshot is empty"))??)
+            .map_err(|_| eyre!("Not a blocktop snapshot"))?;
+
+    if header.version != VERSION {
+        return Err(eyre!(
+            "Unsupported snapshot version {} (expected {VERSION})",
+            header.version
+        ));
+    }
+
+    let state: ChainState = serde_json::from_str(
+        &lines.next().ok_or(eyre!("Snapshot is truncated"))??,
+    )
+    .map_err(|e| eyre!("Malformed snapshot chain state: {e}"))?;
+    let blocks = lines
+        .enumerate()
+        .filter(|(_, line)| !line.as_ref().is_ok_and(String::is_empty))
+        .map(|(i, line)| {
+            serde_json::from_str::<IndexedBlock>(&line?)
+                .map_err(|e| eyre!("Malformed snapshot record {}: {e}", i + 1))
+        });
+    storage.import(&state, blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::{BlockHash, TxHash},
+        rpc::types::Header,
+    };
+
+    use std::io::Read;
+
+    use super::*;
+    use crate::{
+        db::{
+            BeaconBlock, Bookmark, BookmarkTarget, Database, Finality,
+            Location, ReceiptStatus,
+        },
+        l2::{L1Origin, L2Block},
+    };
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let source = Database::new(Location::Memory).unwrap();
+        for number in [5, 7] {
+            let hash = BlockHash::with_last_byte(number as u8);
+            source
+                .headers()
+                .add(&Header {
+                    hash,
+                    inner: alloy::consensus::Header {
+                        number,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .unwrap();
+            source
+                .add_receipt_statuses(&[ReceiptStatus {
+                    block_hash: hash,
+                    transaction_hash: TxHash::with_last_byte(number as u8),
+                    success: false,
+                    gas_used: 21_000,
+                    revert_reason: Some("nope".to_string()),
+                    l1_fee: None,
+                }])
+                .unwrap();
+        }
+        let l2 = L2Block {
+            block_hash: BlockHash::with_last_byte(7),
+            l1_origin: Some(L1Origin {
+                block_hash: BlockHash::with_last_byte(7),
+                number: 100,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        source.add_l2_block(&l2).unwrap();
+        source
+            .add_beacon_block(&BeaconBlock {
+                block_hash: BlockHash::with_last_byte(5),
+                block_number: 5,
+                slot: 10,
+                ..Default::default()
+            })
+            .unwrap();
+        source
+            .set_block_template(7, &[TxHash::with_last_byte(7)])
+            .unwrap();
+        source.set_chain_id(1).unwrap();
+        source
+            .set_genesis_hash(BlockHash::with_last_byte(0))
+            .unwrap();
+        source
+            .set_finality(&Finality {
+                safe: 7,
+                finalized: 5,
+            })
+            .unwrap();
+        source
+            .add_bookmark(&Bookmark {
+                target: BookmarkTarget::Transaction(TxHash::with_last_byte(5)),
+                note: None,
+                created_at: 1,
+            })
+            .unwrap();
+
+        let mut snapshot = vec![];
+        assert_eq!(dump(&source, None, &mut snapshot).unwrap(), 2);
+        let mut partial = vec![];
+        assert_eq!(dump(&source, Some(6..=9), &mut partial).unwrap(), 1);
+
+        assert!(snapshot.starts_with(&GZIP_MAGIC));
+
+        let destination = Database::new(Location::Memory).unwrap();
+        assert_eq!(load(&destination, snapshot.as_slice()).unwrap(), 2);
+        for number in [5, 7] {
+            let indexed = Storage::indexed_block(&destination, number).unwrap();
+            assert!(indexed.is_some());
+            assert_eq!(
+                indexed,
+                Storage::indexed_block(&source, number).unwrap()
+            );
+        }
+        assert_eq!(
+            destination.l2_block(BlockHash::with_last_byte(7)).unwrap(),
+            l2
+        );
+        assert_eq!(
+            destination.chain_state().unwrap(),
+            source.chain_state().unwrap()
+        );
+        /* derived afresh rather than carried */
+        assert_eq!(
+            destination
+                .headers()
+                .hourly_stats(10)
+                .unwrap()
+                .iter()
+                .map(|stats| stats.blocks)
+                .sum::<u64>(),
+            2
+        );
+
+        /* uncompressed snapshots are loaded too, but only in their entirety */
+        let mut plain = String::new();
+        MultiGzDecoder::new(snapshot.as_slice())
+            .read_to_string(&mut plain)
+            .unwrap();
+        plain.push_str("{\n");
+        let partial = Database::new(Location::Memory).unwrap();
+        assert!(load(&partial, plain.as_bytes()).is_err());
+        assert!(partial.block_by_number(5).unwrap().is_none());
+        assert!(partial.finality().unwrap().is_none());
+
+        assert!(load(&destination, "{}".as_bytes()).is_err());
+        assert!(load(&destination, r#"{"version":1}"#.as_bytes()).is_err());
+        assert!(load(&destination, r#"{"version":2}"#.as_bytes()).is_err());
+    }
+}
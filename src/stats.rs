@@ -0,0 +1,117 @@
+//! Aggregate statistics over indexed chain data
+use std::collections::HashMap;
+
+use alloy::{consensus::Transaction as _, primitives::U256, rpc::types::Block};
+use serde::Serialize;
+
+use crate::utils::BuilderIdentity;
+
+/// Maximum number of blocks that builder statistics may be aggregated over at
+/// once
+pub const MAX_BUILDER_STATS_BLOCKS: u64 = 10_000;
+
+/// Aggregate activity of a single block builder over a range of blocks
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct BuilderStats {
+    pub builder: String,
+    /// Number of blocks built
+    pub blocks: u64,
+    /// Total gas used across all blocks built
+    pub gas_used: u64,
+    /// Mean priority fee (in wei per gas) paid by included transactions
+    pub average_priority_fee: u128,
+    /// Total value (in wei) of transactions sent directly to the fee
+    /// recipient
+    pub coinbase_payments: U256,
+}
+
+impl BuilderStats {
+    /// Aggregate the given blocks by builder, in descending order of the
+    /// number of blocks built
+    pub fn aggregate<'a>(
+        blocks: impl IntoIterator<Item = &'a Block>,
+    ) -> Vec<Self> {
+        let mut builders: HashMap<String, (Self, u128, u128)> = HashMap::new();
+
+        for block in blocks {
+            let name = BuilderIdentity::from(block.header.extra_data.clone())
+                .to_string();
+            let base_fee = block.header.base_fee_per_gas.unwrap_or_default();
+            let (stats, total_tip, num_txs) =
+                builders.entry(name.clone()).or_insert_with(|| {
+                    (
+                        Self {
+                            builder: name,
+                            ..Default::default()
+                        },
+                        0,
+                        0,
+                    )
+                });
+
+            stats.blocks += 1;
+            stats.gas_used += block.header.gas_used;
+
+            for tx in block.transactions.txns() {
+                *total_tip +=
+                    tx.effective_tip_per_gas(base_fee).unwrap_or_default();
+                *num_txs += 1;
+
+                if tx.to() == Some(block.header.beneficiary) {
+                    stats.coinbase_payments += tx.value();
+                }
+            }
+        }
+
+        let mut stats: Vec<Self> = builders
+            .into_values()
+            .map(|(mut stats, total_tip, num_txs)| {
+                stats.average_priority_fee =
+                    total_tip.checked_div(num_txs).unwrap_or_default();
+                stats
+            })
+            .collect();
+        stats.sort_by(|a, b| {
+            b.blocks
+                .cmp(&a.blocks)
+                .then_with(|| a.builder.cmp(&b.builder))
+        });
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::rpc::types::{BlockTransactions, Header};
+
+    use super::*;
+
+    #[test]
+    fn test_aggregate() {
+        let block = |extra_data: &'static str, gas_used| {
+            Block::new(
+                Header {
+                    inner: alloy::consensus::Header {
+                        extra_data: extra_data.as_bytes().to_vec().into(),
+                        gas_used,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                BlockTransactions::Hashes(vec![]),
+            )
+        };
+        let blocks = [
+            block("Titan (titanbuilder.xyz)", 10),
+            block("beaverbuild.org", 20),
+            block("Titan (titanbuilder.xyz)", 30),
+        ];
+        let stats = BuilderStats::aggregate(&blocks);
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].builder, "Titan Builder");
+        assert_eq!(stats[0].blocks, 2);
+        assert_eq!(stats[0].gas_used, 40);
+        assert_eq!(stats[1].builder, "beaverbuild");
+        assert_eq!(stats[1].average_priority_fee, 0);
+    }
+}
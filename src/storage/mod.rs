@@ -0,0 +1,510 @@
+//! Storage backends for indexed chain data
+use std::ops::RangeInclusive;
+
+use alloy::{
+    primitives::{BlockHash, BlockNumber, ChainId, TxHash},
+    rpc::types::{eth::Header, Block, Log, Transaction},
+};
+use eyre::eyre;
+use tokio::runtime::RuntimeFlavor;
+
+use crate::{
+    db::{
+        BeaconBlock, ChainState, Database, Finality, IndexedBlock,
+        ReceiptStatus,
+    },
+    l2::L2Block,
+    stats::{BuilderStats, MAX_BUILDER_STATS_BLOCKS},
+};
+
+pub use self::postgres::PostgresDatabase;
+
+pub mod postgres;
+
+/// URI schemes that select the PostgreSQL backend
+const POSTGRES_SCHEMES: [&str; 2] = ["postgres://", "postgresql://"];
+
+/// Interface to a store of indexed chain data
+pub trait Storage {
+    /// Write a [`Block`] (and all of its transactions) atomically
+    fn add_block(&self, block: &Block) -> eyre::Result<()>;
+    /// Write each (mined) [`Log`] atomically
+    fn add_logs(&self, logs: &[Log]) -> eyre::Result<()>;
+    /// Write (or overwrite) each [`ReceiptStatus`] atomically
+    fn add_receipt_statuses(
+        &self,
+        statuses: &[ReceiptStatus],
+    ) -> eyre::Result<()>;
+    /// Flag the blocks with the given [`BlockHash`]es as having lost a reorg
+    fn mark_orphaned(&self, hashes: &[BlockHash]) -> eyre::Result<()>;
+    /// Record the latest safe and finalized blocks
+    fn set_finality(&self, finality: &Finality) -> eyre::Result<()>;
+    /// Record the ID of the indexed chain (which determines, e.g., how the
+    /// blob base fees of blocks written from then on are derived)
+    fn set_chain_id(&self, chain_id: ChainId) -> eyre::Result<()>;
+    /// Replace the local block template for the given [`BlockNumber`]
+    fn set_block_template(
+        &self,
+        number: BlockNumber,
+        transactions: &[TxHash],
+    ) -> eyre::Result<()>;
+    /// Record (or overwrite) where an execution block sits on the beacon
+    /// chain
+    fn add_beacon_block(&self, block: &BeaconBlock) -> eyre::Result<()>;
+    /// Record (or overwrite) what's peculiar to rollups about an L2 block
+    /// atomically
+    fn add_l2_block(&self, block: &L2Block) -> eyre::Result<()>;
+    /// Retrieve the (canonical) block [`Header`] with the lowest number (if
+    /// it exists)
+    fn earliest_block_header(&self) -> eyre::Result<Option<Header>>;
+    /// Retrieve the block [`Header`] with the highest number (if it exists)
+    fn latest_block_header(&self) -> eyre::Result<Option<Header>>;
+    /// Retrieve the [`Block`] with the given [`BlockHash`] (if it exists)
+    fn block_by_hash(&self, hash: BlockHash) -> eyre::Result<Option<Block>>;
+    /// Retrieve the [`Block`] with the given [`BlockNumber`] (if it exists)
+    fn block_by_number(
+        &self,
+        number: BlockNumber,
+    ) -> eyre::Result<Option<Block>>;
+    /// Retrieve the [`Transaction`] with the given [`TxHash`] (if it exists)
+    fn transaction(&self, hash: TxHash) -> eyre::Result<Option<Transaction>>;
+    /// Retrieve every [`Log`] emitted within the [`Block`] with the given
+    /// [`BlockHash`] (in order)
+    fn logs_by_block_hash(&self, hash: BlockHash) -> eyre::Result<Vec<Log>>;
+    /// Retrieve the [`ReceiptStatus`] of each transaction in the [`Block`]
+    /// with the given [`BlockHash`] (so far as they've been stored)
+    fn receipt_statuses_by_block_hash(
+        &self,
+        hash: BlockHash,
+    ) -> eyre::Result<Vec<ReceiptStatus>>;
+    /// Retrieve what's peculiar to rollups about the L2 block with the given
+    /// [`BlockHash`] (so far as it's been stored)
+    fn l2_block_by_hash(&self, hash: BlockHash) -> eyre::Result<L2Block>;
+    /// Retrieve where the block with the given [`BlockHash`] sits on the
+    /// beacon chain (if this was ever recorded)
+    fn beacon_block_by_hash(
+        &self,
+        hash: BlockHash,
+    ) -> eyre::Result<Option<BeaconBlock>>;
+    /// Retrieve the hashes of the transactions (in order) of the local block
+    /// template for the given [`BlockNumber`] (if any)
+    fn block_template(&self, number: BlockNumber) -> eyre::Result<Vec<TxHash>>;
+    /// Retrieve everything indexed about the (canonical) block with the
+    /// given [`BlockNumber`] (if it exists)
+    fn indexed_block(
+        &self,
+        number: BlockNumber,
+    ) -> eyre::Result<Option<IndexedBlock>> {
+        let block = match self.block_by_number(number)? {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+        let hash = block.header.hash;
+        Ok(Some(IndexedBlock {
+            logs: self.logs_by_block_hash(hash)?,
+            receipt_statuses: self.receipt_statuses_by_block_hash(hash)?,
+            l2: self.l2_block_by_hash(hash)?,
+            beacon: self.beacon_block_by_hash(hash)?,
+            template: self.block_template(number)?,
+            block,
+        }))
+    }
+    /// Retrieve everything recorded about the chain as a whole (see
+    /// [`ChainState`]), so far as this backend records it
+    fn chain_state(&self) -> eyre::Result<ChainState>;
+    /// Write the given [`ChainState`] along with every [`IndexedBlock`]
+    /// yielded by the given iterator atomically (so that nothing at all is
+    /// written should the iterator yield an error), returning the number of
+    /// blocks written
+    fn import(
+        &self,
+        state: &ChainState,
+        blocks: impl IntoIterator<Item = eyre::Result<IndexedBlock>>,
+    ) -> eyre::Result<usize>;
+    /// Retrieve the hashes of every stored block
+    fn all_block_hashes(&self) -> eyre::Result<Vec<BlockHash>>;
+    /// Retrieve the ranges of [`BlockNumber`]s missing between the lowest and
+    /// highest (canonical) stored blocks, highest first
+    fn block_gaps(&self) -> eyre::Result<Vec<RangeInclusive<BlockNumber>>>;
+    /// Retrieve every canonical [`Block`] stored within the given range of
+    /// [`BlockNumber`]s (in order)
+    fn canonical_blocks(
+        &self,
+        range: RangeInclusive<BlockNumber>,
+    ) -> eyre::Result<Vec<Block>>;
+    /// Aggregate [`BuilderStats`] over the stored blocks within the given
+    /// range of [`BlockNumber`]s, which may span at most
+    /// [`MAX_BUILDER_STATS_BLOCKS`] blocks
+    fn builder_stats(
+        &self,
+        range: RangeInclusive<BlockNumber>,
+    ) -> eyre::Result<Vec<BuilderStats>> {
+        if range.end().saturating_sub(*range.start())
+            >= MAX_BUILDER_STATS_BLOCKS
+        {
+            return Err(eyre!(
+                "Block range may span at most {MAX_BUILDER_STATS_BLOCKS} \
+                blocks"
+            ));
+        }
+        Ok(BuilderStats::aggregate(&self.canonical_blocks(range)?))
+    }
+}
+
+/// Run synchronous database code from (possibly) within the Tokio runtime
+///
+/// Queries may take a while (and the synchronous PostgreSQL client drives its
+/// own Tokio runtime internally, which panics if entered from within another
+/// runtime's worker thread), so the worker hands its other tasks off first.
+pub(crate) fn blocking<T>(
+    f: impl FnOnce() -> eyre::Result<T>,
+) -> eyre::Result<T> {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(f)
+        }
+        _ => f(),
+    }
+}
+
+/// Storage type that is generic over all supported backends
+#[derive(Clone, Debug)]
+pub enum AnyStorage {
+    /// SQLite (on-disk or in-memory)
+    Sqlite(Database),
+    /// PostgreSQL
+    Postgres(PostgresDatabase),
+}
+
+impl AnyStorage {
+    /// Whether the given database specifier refers to a PostgreSQL server
+    /// rather than a SQLite file
+    pub fn is_postgres_uri(s: &str) -> bool {
+        POSTGRES_SCHEMES.iter().any(|scheme| s.starts_with(scheme))
+    }
+
+    /// Handle to the underlying SQLite [`Database`] (if that's the backend in
+    /// use)
+    pub fn sqlite(&self) -> Option<&Database> {
+        match self {
+            Self::Sqlite(t) => Some(t),
+            Self::Postgres(_) => None,
+        }
+    }
+}
+
+impl Storage for AnyStorage {
+    fn add_block(&self, block: &Block) -> eyre::Result<()> {
+        match self {
+            Self::Sqlite(t) => Storage::add_block(t, block),
+            Self::Postgres(t) => t.add_block(block),
+        }
+    }
+
+    fn add_logs(&self, logs: &[Log]) -> eyre::Result<()> {
+        match self {
+            Self::Sqlite(t) => Storage::add_logs(t, logs),
+            Self::Postgres(t) => t.add_logs(logs),
+        }
+    }
+
+    fn add_receipt_statuses(
+        &self,
+        statuses: &[ReceiptStatus],
+    ) -> eyre::Result<()> {
+        match self {
+            Self::Sqlite(t) => Storage::add_receipt_statuses(t, statuses),
+            Self::Postgres(t) => t.add_receipt_statuses(statuses),
+        }
+    }
+
+    fn mark_orphaned(&self, hashes: &[BlockHash]) -> eyre::Result<()> {
+        match self {
+            Self::Sqlite(t) => Storage::mark_orphaned(t, hashes),
+            Self::Postgres(t) => t.mark_orphaned(hashes),
+        }
+    }
+
+    fn set_finality(&self, finality: &Finality) -> eyre::Result<()> {
+        match self {
+            Self::Sqlite(t) => Storage::set_finality(t, finality),
+            Self::Postgres(t) => t.set_finality(finality),
+        }
+    }
+
+    fn set_chain_id(&self, chain_id: ChainId) -> eyre::Result<()> {
+        match self {
+            Self::Sqlite(t) => Storage::set_chain_id(t, chain_id),
+            Self::Postgres(t) => t.set_chain_id(chain_id),
+        }
+    }
+
+    fn set_block_template(
+        &self,
+        number: BlockNumber,
+        transactions: &[TxHash],
+    ) -> eyre::Result<()> {
+        match self {
+            Self::Sqlite(t) => {
+                Storage::set_block_template(t, number, transactions)
+            }
+            Self::Postgres(t) => t.set_block_template(number, transactions),
+        }
+    }
+
+    fn add_beacon_block(&self, block: &BeaconBlock) -> eyre::Result<()> {
+        match self {
+            Self::Sqlite(t) => Storage::add_beacon_block(t, block),
+            Self::Postgres(t) => t.add_beacon_block(block),
+        }
+    }
+
+    fn add_l2_block(&self, block: &L2Block) -> eyre::Result<()> {
+        match self {
+            Self::Sqlite(t) => Storage::add_l2_block(t, block),
+            Self::Postgres(t) => t.add_l2_block(block),
+        }
+    }
+
+    fn earliest_block_header(&self) -> eyre::Result<Option<Header>> {
+        match self {
+            Self::Sqlite(t) => Storage::earliest_block_header(t),
+            Self::Postgres(t) => t.earliest_block_header(),
+        }
+    }
+
+    fn latest_block_header(&self) -> eyre::Result<Option<Header>> {
+        match self {
+            Self::Sqlite(t) => Storage::latest_block_header(t),
+            Self::Postgres(t) => t.latest_block_header(),
+        }
+    }
+
+    fn block_by_hash(&self, hash: BlockHash) -> eyre::Result<Option<Block>> {
+        match self {
+            Self::Sqlite(t) => Storage::block_by_hash(t, hash),
+            Self::Postgres(t) => t.block_by_hash(hash),
+        }
+    }
+
+    fn block_by_number(
+        &self,
+        number: BlockNumber,
+    ) -> eyre::Result<Option<Block>> {
+        match self {
+            Self::Sqlite(t) => Storage::block_by_number(t, number),
+            Self::Postgres(t) => t.block_by_number(number),
+        }
+    }
+
+    fn transaction(&self, hash: TxHash) -> eyre::Result<Option<Transaction>> {
+        match self {
+            Self::Sqlite(t) => Storage::transaction(t, hash),
+            Self::Postgres(t) => t.transaction(hash),
+        }
+    }
+
+    fn logs_by_block_hash(&self, hash: BlockHash) -> eyre::Result<Vec<Log>> {
+        match self {
+            Self::Sqlite(t) => Storage::logs_by_block_hash(t, hash),
+            Self::Postgres(t) => t.logs_by_block_hash(hash),
+        }
+    }
+
+    fn receipt_statuses_by_block_hash(
+        &self,
+        hash: BlockHash,
+    ) -> eyre::Result<Vec<ReceiptStatus>> {
+        match self {
+            Self::Sqlite(t) => Storage::receipt_statuses_by_block_hash(t, hash),
+            Self::Postgres(t) => t.receipt_statuses_by_block_hash(hash),
+        }
+    }
+
+    fn l2_block_by_hash(&self, hash: BlockHash) -> eyre::Result<L2Block> {
+        match self {
+            Self::Sqlite(t) => Storage::l2_block_by_hash(t, hash),
+            Self::Postgres(t) => t.l2_block_by_hash(hash),
+        }
+    }
+
+    fn beacon_block_by_hash(
+        &self,
+        hash: BlockHash,
+    ) -> eyre::Result<Option<BeaconBlock>> {
+        match self {
+            Self::Sqlite(t) => Storage::beacon_block_by_hash(t, hash),
+            Self::Postgres(t) => t.beacon_block_by_hash(hash),
+        }
+    }
+
+    fn block_template(&self, number: BlockNumber) -> eyre::Result<Vec<TxHash>> {
+        match self {
+            Self::Sqlite(t) => Storage::block_template(t, number),
+            Self::Postgres(t) => t.block_template(number),
+        }
+    }
+
+    fn chain_state(&self) -> eyre::Result<ChainState> {
+        match self {
+            Self::Sqlite(t) => Storage::chain_state(t),
+            Self::Postgres(t) => t.chain_state(),
+        }
+    }
+
+    fn import(
+        &self,
+        state: &ChainState,
+        blocks: impl IntoIterator<Item = eyre::Result<IndexedBlock>>,
+    ) -> eyre::Result<usize> {
+        match self {
+            Self::Sqlite(t) => Storage::import(t, state, blocks),
+            Self::Postgres(t) => t.import(state, blocks),
+        }
+    }
+
+    fn all_block_hashes(&self) -> eyre::Result<Vec<BlockHash>> {
+        match self {
+            Self::Sqlite(t) => Storage::all_block_hashes(t),
+            Self::Postgres(t) => t.all_block_hashes(),
+        }
+    }
+
+    fn block_gaps(&self) -> eyre::Result<Vec<RangeInclusive<BlockNumber>>> {
+        match self {
+            Self::Sqlite(t) => Storage::block_gaps(t),
+            Self::Postgres(t) => t.block_gaps(),
+        }
+    }
+
+    fn canonical_blocks(
+        &self,
+        range: RangeInclusive<BlockNumber>,
+    ) -> eyre::Result<Vec<Block>> {
+        match self {
+            Self::Sqlite(t) => Storage::canonical_blocks(t, range),
+            Self::Postgres(t) => t.canonical_blocks(range),
+        }
+    }
+}
+
+impl Storage for Database {
+    fn add_block(&self, block: &Block) -> eyre::Result<()> {
+        Ok(self.add_block_atomic(block)?)
+    }
+
+    fn add_logs(&self, logs: &[Log]) -> eyre::Result<()> {
+        Ok(self.logs().add(logs)?)
+    }
+
+    fn add_receipt_statuses(
+        &self,
+        statuses: &[ReceiptStatus],
+    ) -> eyre::Result<()> {
+        Ok(self.receipts().add_statuses(statuses)?)
+    }
+
+    fn mark_orphaned(&self, hashes: &[BlockHash]) -> eyre::Result<()> {
+        Ok(self.headers().mark_orphaned(hashes)?)
+    }
+
+    fn set_finality(&self, finality: &Finality) -> eyre::Result<()> {
+        Ok(self.set_finality(finality)?)
+    }
+
+    fn set_chain_id(&self, chain_id: ChainId) -> eyre::Result<()> {
+        Ok(self.set_chain_id(chain_id)?)
+    }
+
+    fn set_block_template(
+        &self,
+        number: BlockNumber,
+        transactions: &[TxHash],
+    ) -> eyre::Result<()> {
+        Ok(self.set_block_template(number, transactions)?)
+    }
+
+    fn add_beacon_block(&self, block: &BeaconBlock) -> eyre::Result<()> {
+        Ok(self.add_beacon_block(block)?)
+    }
+
+    fn add_l2_block(&self, block: &L2Block) -> eyre::Result<()> {
+        Ok(self.add_l2_block(block)?)
+    }
+
+    fn earliest_block_header(&self) -> eyre::Result<Option<Header>> {
+        Ok(self.headers().earliest()?)
+    }
+
+    fn latest_block_header(&self) -> eyre::Result<Option<Header>> {
+        Ok(self.headers().latest()?)
+    }
+
+    fn block_by_hash(&self, hash: BlockHash) -> eyre::Result<Option<Block>> {
+        Ok(self.block_by_hash(hash)?)
+    }
+
+    fn block_by_number(
+        &self,
+        number: BlockNumber,
+    ) -> eyre::Result<Option<Block>> {
+        Ok(self.block_by_number(number)?)
+    }
+
+    fn transaction(&self, hash: TxHash) -> eyre::Result<Option<Transaction>> {
+        Ok(self.transactions().by_hash(hash)?)
+    }
+
+    fn logs_by_block_hash(&self, hash: BlockHash) -> eyre::Result<Vec<Log>> {
+        Ok(self.logs().by_block_hash(hash)?)
+    }
+
+    fn receipt_statuses_by_block_hash(
+        &self,
+        hash: BlockHash,
+    ) -> eyre::Result<Vec<ReceiptStatus>> {
+        Ok(self.receipts().statuses_by_block_hash(hash)?)
+    }
+
+    fn l2_block_by_hash(&self, hash: BlockHash) -> eyre::Result<L2Block> {
+        Ok(self.l2_block(hash)?)
+    }
+
+    fn beacon_block_by_hash(
+        &self,
+        hash: BlockHash,
+    ) -> eyre::Result<Option<BeaconBlock>> {
+        Ok(self.beacon_block(hash)?)
+    }
+
+    fn block_template(&self, number: BlockNumber) -> eyre::Result<Vec<TxHash>> {
+        Ok(self.block_template(number)?)
+    }
+
+    fn chain_state(&self) -> eyre::Result<ChainState> {
+        Ok(self.chain_state()?)
+    }
+
+    fn import(
+        &self,
+        state: &ChainState,
+        blocks: impl IntoIterator<Item = eyre::Result<IndexedBlock>>,
+    ) -> eyre::Result<usize> {
+        self.import(state, blocks)
+    }
+
+    fn all_block_hashes(&self) -> eyre::Result<Vec<BlockHash>> {
+        Ok(self.headers().hashes()?)
+    }
+
+    fn block_gaps(&self) -> eyre::Result<Vec<RangeInclusive<BlockNumber>>> {
+        Ok(self.headers().gaps()?)
+    }
+
+    fn canonical_blocks(
+        &self,
+        range: RangeInclusive<BlockNumber>,
+    ) -> eyre::Result<Vec<Block>> {
+        Ok(self.canonical_blocks(range)?)
+    }
+}
@@ -0,0 +1,26 @@
+//! Fixtures shared by the tests of several modules
+use alloy::primitives::{Address, TxHash};
+use serde_json::{json, Value};
+
+/// A signed EIP-1559 transaction with the given hash, sender, and nonce (and
+/// otherwise arbitrary fields, which may be overridden), as a node serves it
+pub fn eip1559_transaction(hash: TxHash, from: Address, nonce: u64) -> Value {
+    json!({
+        "hash": hash,
+        "type": "0x2",
+        "chainId": "0x1",
+        "nonce": format!("{nonce:#x}"),
+        "gas": "0x5208",
+        "maxFeePerGas": "0x3b9aca00",
+        "maxPriorityFeePerGas": "0x1",
+        "to": "0x0000000000000000000000000000000000000001",
+        "value": "0x0",
+        "input": "0x",
+        "accessList": [],
+        "r": "0x1",
+        "s": "0x1",
+        "yParity": "0x0",
+        "v": "0x0",
+        "from": from,
+    })
+}
@@ -0,0 +1,35 @@
+//! TLS configuration for served HTTP endpoints
+use std::{path::Path, sync::Arc};
+
+use eyre::eyre;
+use tokio_rustls::{
+    rustls::{
+        pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+        ServerConfig,
+    },
+    TlsAcceptor,
+};
+
+/// Build a [`TlsAcceptor`] from a PEM-encoded certificate chain and private
+/// key
+pub fn acceptor(cert: &Path, key: &Path) -> eyre::Result<TlsAcceptor> {
+    let certs = CertificateDer::pem_file_iter(cert)
+        .map_err(|e| eyre!("Failed to read {}: {e}", cert.display()))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| eyre!("Failed to parse {}: {e}", cert.display()))?;
+
+    if certs.is_empty() {
+        return Err(eyre!("No certificates found in {}", cert.display()));
+    }
+
+    let key = PrivateKeyDer::from_pem_file(key)
+        .map_err(|e| eyre!("Failed to read {}: {e}", key.display()))?;
+    let config = ServerConfig::builder_with_provider(Arc::new(
+        tokio_rustls::rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()?
+    .with_no_client_auth()
+    .with_single_cert(certs, key)?;
+
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
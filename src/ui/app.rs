@@ -1,83 +1,570 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    io::BufWriter,
+    sync::{Arc, Mutex, PoisonError, RwLock},
+    time::{Duration, Instant},
+};
+
 use alloy::{
     consensus::Transaction as AbstractTransaction,
-    primitives::{Address, Bytes},
-    rpc::types::{Header, Transaction},
+    eips::HashOrNumber,
+    primitives::{
+        keccak256, Address, BlockHash, BlockNumber, Bytes, ChainId, TxHash,
+        B256, U256,
+    },
+    rpc::types::{Header, Log, Transaction},
 };
 use chrono::{TimeZone, Utc};
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{
-    layout::{Constraint, Layout, Rect},
-    style::{Color, Style, Stylize},
-    symbols,
+    layout::{Constraint, Layout, Margin, Position, Rect},
+    style::{Style, Stylize},
+    symbols::{self, Marker},
     text::{Line, Span, Text},
     widgets::{
-        Bar, BarChart, BarGroup, Block, Borders, List, ListItem, Paragraph,
+        Axis, Bar, BarChart, BarGroup, Block, Borders, Chart, Clear, Dataset,
+        GraphType, LineGauge, List, ListItem, Paragraph, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Sparkline, Wrap,
     },
     Frame,
 };
+use tokio::sync::watch;
+use url::Url;
 
 use crate::{
-    db::Database,
+    abi,
+    client::redacted,
+    db::{
+        AccountSnapshot, Activity, ActivityBucket, BeaconBlock, BlockFees,
+        Bookmark, BookmarkTarget, CalldataPattern, Database, DbError, FeeStats,
+        FeeTotals, Finality, GasByRecipient, HourlyStats, LogQuery,
+        ReceiptStatus, Result as DbResult, SelectorStats, SECONDS_PER_DAY,
+    },
+    evm::{self, Instruction},
+    export,
+    l2::{L2Block, OtherTransaction, Rollup},
+    metrics::{MethodHealth, Metrics, RpcHealth, RPC_HEALTH_WINDOW},
+    services::{
+        blobs::{BlobRequest, BlobService, Blobs},
+        blockchain::{ChainEvent, IndexerState, ResetAction},
+        contracts::{Contract, ContractService, Verification},
+        devchain::{DevChainInfo, DevChainService},
+        mempool::{self, MempoolService},
+        race::{EndpointRace, RaceService},
+        rawtx::RawTransactionService,
+        requests::Fetched,
+        retrieval::{Retrieval, RetrievalService},
+        simulator::{CallOutcome, Simulated, SimulatorService},
+        template::{TemplateDiff, TemplateTxStatus},
+        tracer::{Trace, Traced, TracerService},
+        tracker::{Broadcast, TrackerService, TxStatus},
+        txpool::TxpoolService,
+        watchlist::{
+            self, Fees, PendingIssue, PendingWarning, WatchlistService,
+            DEFAULT_STUCK_AFTER,
+        },
+    },
     utils::{
-        self, etherscan_block_url, etherscan_transaction_url, grab_range,
-        label_address, libmev_block_url, to_ether, to_gwei, useful_gas_price,
-        BuilderIdentity,
+        self, etherscan_address_url, etherscan_block_url,
+        etherscan_transaction_url, label_address, libmev_block_url, to_ether,
+        to_gwei, useful_gas_price, BuilderIdentity,
     },
 };
 
-use super::components::stateful_list::StatefulList;
+use super::{
+    clipboard::{self, Copied},
+    command::{Command, ExportFormat},
+    components::{
+        hex_view::{self, HexView},
+        prompt::Prompt,
+        source_view::SourceView,
+        sql_console::SqlConsole,
+        stateful_list::StatefulList,
+        transaction_list::{TransactionList, TransactionQuery},
+        txpool_view::TxpoolView,
+    },
+    history::History,
+    keymap::Action,
+    preimage::Preimage,
+    theme::Theme,
+    Startup,
+};
 
-#[derive(Copy, Clone, Debug)]
+/// Maximum number of transactions retrieved for a calldata filter
+const FILTER_RESULTS_LIMIT: usize = 1_000;
+/// Maximum number of rows retrieved by a query in the SQL console
+const SQL_ROWS_LIMIT: usize = 1_000;
+/// How long a query entered into the SQL console may run before it's
+/// abandoned (as the UI waits on it)
+const SQL_QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+/// Number of the most recently orphaned blocks marked as such in the blocks
+/// list
+const ORPHANED_BLOCKS_WINDOW: usize = 1_024;
+/// Maximum height (in rows of 16 bytes) of the selected event's data
+const MAX_LOG_DATA_ROWS: usize = 8;
+/// Maximum number of event logs retrieved for a log search
+const LOG_RESULTS_LIMIT: usize = 1_000;
+/// Number of recent blocks whose priority fee percentiles are plotted
+const FEE_STATS_WINDOW: usize = 64;
+/// Number of recent blocks whose priority fee percentiles are averaged into a
+/// tip suggestion
+const FEE_SUGGESTION_WINDOW: usize = 8;
+/// Width (in characters) of the priority fee panel in the default view
+const FEE_PANEL_WIDTH: u16 = 48;
+/// Number of preceding blocks that each block's transaction count is scaled
+/// against in the latest blocks list
+const TX_COUNT_WINDOW: usize = 32;
+/// Width (in characters) of the transaction count bar in the latest blocks
+/// list
+const TX_COUNT_BAR_WIDTH: usize = 8;
+/// Width (in characters) of the development chain panel in the default view
+const DEV_CHAIN_PANEL_WIDTH: u16 = 40;
+/// Width (in characters) of the storage panel in the contract view
+const STORAGE_PANEL_WIDTH: u16 = 72;
+/// How long a deep reorg remains announced for
+const REORG_BANNER_DURATION: Duration = Duration::from_secs(120);
+/// Maximum number of block headers held in the blocks list (any others are
+/// paged in from the database as the list is scrolled)
+const BLOCK_HEADERS_WINDOW: usize = 1_024;
+/// Number of block headers paged in from the database at a time
+const BLOCK_HEADERS_PAGE: usize = 128;
+/// Number of the most recent balances and nonces shown for each watched
+/// address
+const WATCHLIST_HISTORY: usize = 256;
+/// Most pending transaction warnings shown at once in the watch list view
+const MAX_PENDING_WARNINGS_SHOWN: u16 = 8;
+/// Number of the most recent blocks whose fees are charted in the stats view
+const STATS_WINDOW: usize = 256;
+/// Number of the most recent hours charted in the stats view
+const STATS_HOURS: usize = 168;
+/// Number of the heaviest gas consumers shown in the stats view
+const TOP_GAS_CONSUMERS: usize = 8;
+/// Number of the most recent days over which an account's activity is
+/// plotted
+const ACTIVITY_DAYS: usize = 90;
+/// Number of the most called function selectors listed in the selector
+/// leaderboard
+const TOP_SELECTORS: usize = 100;
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 pub enum View {
+    #[default]
     Default,
     Block,
     Transaction,
+    Filter,
+    Logs,
+    Template,
+    Contract,
+    Watchlist,
+    Bookmarks,
+    Stats,
+    Sql,
+    Txpool,
+    Selectors,
+    Endpoints,
+}
+
+/// What the transactions in the filter view were selected by
+#[derive(Clone, Debug, PartialEq)]
+pub enum TransactionFilter {
+    Calldata(CalldataPattern),
+    /// Involvement of the given address (i.e., its account history)
+    Account(Address),
+}
+
+/// Transactions sent from or to the address whose history is open, totalled
+/// per block and per day
+#[derive(Clone, Debug, Default)]
+pub struct AccountActivity {
+    pub address: Address,
+    /// Number and timestamp of the latest block when the totals were read
+    pub head: (BlockNumber, u64),
+    pub by_block: Vec<Activity>,
+    pub by_day: Vec<Activity>,
+}
+
+/// Block or transaction asked to be opened (e.g., on startup), which may
+/// have to be retrieved from the RPC node first
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Opening {
+    Block(HashOrNumber),
+    Transaction(TxHash),
+}
+
+impl fmt::Display for Opening {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Block(HashOrNumber::Hash(hash)) => write!(f, "Block {hash}"),
+            Self::Block(HashOrNumber::Number(number)) => {
+                write!(f, "Block #{number}")
+            }
+            Self::Transaction(hash) => write!(f, "Transaction {hash}"),
+        }
+    }
+}
+
+impl From<Opening> for Retrieval {
+    fn from(opening: Opening) -> Self {
+        match opening {
+            Opening::Block(id) => Self::Block(id),
+            Opening::Transaction(hash) => Self::Transaction(hash),
+        }
+    }
 }
 
-impl Default for View {
-    fn default() -> Self {
-        Self::Default
+/// Enough of the TUI's state to return to a previously visited page
+#[derive(Clone, Debug)]
+struct Page {
+    view: View,
+    block: alloy::rpc::types::Block,
+    transaction: Option<Transaction>,
+}
+
+/// What the contract view shows of the open contract's code
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum CodePane {
+    /// Hex dump of its bytecode
+    #[default]
+    Bytecode,
+    Disassembly,
+    /// Its verified source code
+    Source,
+}
+
+/// Time series plotted at the top of the default view
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum DashboardChart {
+    #[default]
+    GasUsed,
+    BaseFee,
+    BlobGasUsed,
+}
+
+impl DashboardChart {
+    const ALL: [Self; 3] = [Self::GasUsed, Self::BaseFee, Self::BlobGasUsed];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::GasUsed => "Gas used",
+            Self::BaseFee => "Base fee",
+            Self::BlobGasUsed => "Blob gas used",
+        }
     }
 }
 
+/// What the text currently being entered into the [`Prompt`] is for
 #[derive(Copy, Clone, Debug)]
+pub enum PromptKind {
+    CalldataFilter,
+    LogSearch,
+    TransactionFilter,
+    Command,
+    StorageSlot,
+    /// Note to save alongside a new bookmark of the given target
+    BookmarkNote(BookmarkTarget),
+    /// Query to run in the SQL console
+    Sql,
+    /// Signed transaction to broadcast
+    RawTransaction,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
 pub enum AddressDisplayMode {
     Raw,
+    #[default]
     Cooked,
 }
 
-impl Default for AddressDisplayMode {
-    fn default() -> Self {
-        Self::Cooked
-    }
-}
-
 #[derive(Clone, Debug)]
 pub struct App {
     pub title: String,
     pub should_quit: bool,
     pub block_headers: StatefulList<Header>,
-    pub transactions: StatefulList<alloy::rpc::types::eth::Transaction>,
+    /// Whether the blocks list extends to the latest block (as opposed to
+    /// the newest blocks having been paged out whilst scrolled back)
+    pub block_headers_at_head: bool,
+    pub transactions: TransactionList,
     pub view: View,
     pub address_display_mode: AddressDisplayMode,
     pub selected_block: alloy::rpc::types::Block,
-    pub selected_transaction: alloy::rpc::types::Transaction,
+    /// Transaction shown in the transaction view (or highlighted in the
+    /// block view), which there's none of should the block be empty
+    pub selected_transaction: Option<alloy::rpc::types::Transaction>,
+    pub prompt: Option<(PromptKind, Prompt)>,
+    pub filter: Option<TransactionFilter>,
+    pub filter_results: StatefulList<Transaction>,
+    pub log_query: Option<LogQuery>,
+    pub log_results: StatefulList<Log>,
+    pub metrics: Option<Arc<Metrics>>,
+    /// Addresses whose transactions are highlighted (i.e., those on the
+    /// watch list)
+    pub watched_addresses: HashSet<Address>,
+    pub watchlist: Option<WatchlistService>,
+    /// Watched addresses as shown in the watch list view
+    pub watchlist_entries: StatefulList<Address>,
+    /// Most recent balances and nonces recorded for each watched address
+    /// (oldest first) as of the last tick
+    pub account_snapshots: HashMap<Address, Vec<AccountSnapshot>>,
+    /// Pending transactions from watched addresses that are unlikely to be
+    /// included as of the last tick
+    pub pending_warnings: Vec<PendingWarning>,
+    /// Blocks a watched address's transaction may remain pending for before
+    /// it's flagged as stuck
+    pub stuck_after: u64,
+    /// Saved blocks, transactions, and addresses as shown in the bookmarks
+    /// view
+    pub bookmarks: StatefulList<Bookmark>,
+    pub status_message: Option<String>,
+    /// Most recent failure to read the chain data as of the last tick (shown
+    /// in the status bar until a later tick reads it successfully)
+    pub db_error: Option<String>,
+    pub transaction_counts: HashMap<BlockHash, usize>,
+    pub fee_stats: Vec<FeeStats>,
+    /// Blob base fees (in wei) of the most recent blocks
+    pub blob_base_fees: Vec<(BlockNumber, u64)>,
+    pub dashboard_chart: DashboardChart,
+    /// Whether the block view shows every header field
+    pub expand_header: bool,
+    /// Whether the block view shows the RLP encoding of the header instead
+    /// of its fields
+    pub raw_header: bool,
+    /// Whether the block view compares the open block with another block at
+    /// the same height
+    pub compare_fork: bool,
+    /// Latest safe and finalized blocks as of the last tick
+    pub finality: Option<Finality>,
+    /// Where the open block sits on the beacon chain (if known)
+    pub beacon_block: Option<BeaconBlock>,
+    /// What's peculiar to rollups about the open block (if anything)
+    pub l2_block: Option<L2Block>,
+    /// Hashes of recently orphaned blocks as of the last tick
+    pub orphaned: HashSet<BlockHash>,
+    /// Every block at the open block's height (along with whether it's been
+    /// orphaned) as of the last tick, canonical block first
+    pub fork_siblings: Vec<(Header, bool)>,
+    /// Preimage shown in the Keccak pane (if it's open)
+    pub preimage: Option<Preimage>,
+    /// Whether each new block is selected as it arrives (as opposed to the
+    /// selection staying put)
+    pub follow: bool,
+    pub template_diff: Option<TemplateDiff>,
+    pub template_transactions: StatefulList<(TxHash, TemplateTxStatus)>,
+    pub theme: Theme,
+    pub tracker: Option<TrackerService>,
+    /// Health of the indexer (if it's running)
+    pub indexer: Option<Arc<IndexerState>>,
+    /// Progress of retrieving the first block from the RPC node (until it's
+    /// been retrieved, or has failed to be)
+    pub startup: Option<Arc<RwLock<Startup>>>,
+    /// Why retrieving the first block failed (if it did)
+    pub startup_error: Option<String>,
+    /// What to open once it's been retrieved
+    pub opening: Option<Opening>,
+    /// Changes to the indexed chain data announced by the indexer (without
+    /// which the database is re-read upon every tick)
+    pub chain_events: Option<watch::Receiver<Option<ChainEvent>>>,
+    /// Genesis hash of the chain whose data is shown (once the indexer has
+    /// checked it)
+    pub genesis: Option<BlockHash>,
+    pub devchain: Option<DevChainService>,
+    /// What's known about the development chain being indexed (if it is one)
+    /// as of the last tick
+    pub dev_chain: Option<DevChainInfo>,
+    pub tracer: Option<TracerService>,
+    pub mempool: Option<MempoolService>,
+    /// Trace of the open transaction as of the last tick
+    pub trace: Option<(TxHash, Traced)>,
+    pub simulator: Option<SimulatorService>,
+    /// Transaction most recently asked to be simulated
+    pub simulated: Option<TxHash>,
+    /// Simulation of the open transaction (if asked for) as of the last tick
+    pub simulation: Option<(TxHash, Simulated)>,
+    pub rawtx: Option<RawTransactionService>,
+    /// Retrieves blocks and transactions that aren't indexed
+    pub retrieval: Option<RetrievalService>,
+    /// Transaction whose encoding is shown in place of its calldata (if any)
+    pub raw_shown: Option<TxHash>,
+    /// Encoding of the open transaction (if shown) as of the last tick
+    pub raw_transaction: Option<(TxHash, Fetched<Bytes>)>,
+    pub blob_service: Option<BlobService>,
+    /// Blobs of the open transaction as of the last tick (if any are open)
+    pub blobs: Option<(TxHash, Blobs)>,
+    /// Index of the blob shown in place of the open transaction's calldata
+    /// (if any)
+    pub open_blob: Option<(TxHash, usize)>,
+    pub contract_service: Option<ContractService>,
+    /// Address shown in the contract view
+    pub contract_address: Option<Address>,
+    /// Code of the contract being looked at (in the contract view or an
+    /// account's history) as of the last tick
+    pub contract: Option<(Address, Fetched<Contract>)>,
+    /// What the contract view shows of the contract's code
+    pub code_pane: CodePane,
+    /// Hex dump of the open contract's code
+    pub bytecode: HexView,
+    /// Disassembly of the open contract's code
+    pub instructions: StatefulList<Instruction>,
+    /// Verified source code of the open contract (once it's been retrieved)
+    pub source_view: SourceView,
+    /// Storage slots of the open contract read so far (most recent first),
+    /// along with their values as of the last tick
+    pub storage_slots: Vec<(B256, Fetched<B256>)>,
+    /// Size of the database (in bytes) as of the last tick
+    pub db_size: Option<u64>,
+    /// Last seen status of each tracked transaction
+    pub tracked: HashMap<TxHash, TxStatus>,
+    /// What's become of each transaction broadcast from the TUI as of the
+    /// last tick
+    pub broadcasts: HashMap<TxHash, Broadcast>,
+    /// Receipt status of each (indexed) transaction in the selected block
+    pub receipt_statuses: HashMap<TxHash, ReceiptStatus>,
+    /// Fees burnt and paid to the builder by the selected block (once its
+    /// receipts have been indexed)
+    pub block_fees: Option<BlockFees>,
+    /// Fees summed across every indexed block, as shown in the stats view
+    pub fee_totals: FeeTotals,
+    /// Fees of the most recent blocks, as charted in the stats view
+    pub recent_block_fees: Vec<BlockFees>,
+    /// Totals over the most recent hours, as charted in the stats view
+    pub hourly_stats: Vec<HourlyStats>,
+    /// Gas used over the most recent blocks by the heaviest recipients, as
+    /// shown in the stats view
+    pub gas_by_recipient: GasByRecipient,
+    /// Activity of the address whose history is open in the filter view
+    pub account_activity: Option<AccountActivity>,
+    /// Most called function selectors over the most recent blocks
+    pub selector_stats: StatefulList<SelectorStats>,
+    pub sql_console: SqlConsole,
+    pub txpool: Option<TxpoolService>,
+    /// Races the configured RPC endpoints against each other (if there's
+    /// more than one)
+    pub race: Option<RaceService>,
+    /// How each RPC endpoint is faring, as shown in the endpoints view
+    pub endpoint_races: Vec<EndpointRace>,
+    /// Recent RPC requests made by every service
+    pub rpc_health: Option<Arc<Mutex<RpcHealth>>>,
+    /// Latency and error rate of each RPC method, as shown in the endpoints
+    /// view
+    pub method_health: Vec<MethodHealth>,
+    pub txpool_view: TxpoolView,
+    /// Hex dump of the open transaction's calldata
+    pub calldata: HexView,
+    /// Hex dump of the selected event's data
+    pub log_data: HexView,
+    /// Whether navigation applies to the selected event's data rather than
+    /// the list of events
+    pub log_data_focused: bool,
+    /// Selected block as of the last tick (for noticing new selections)
+    ticked_block: Option<BlockHash>,
+    /// Where the current view's list was last drawn (for mouse support)
+    list_area: Rect,
+    history: History<Page>,
 }
 
 impl App {
     pub fn new(
         title: String,
         selected_block: alloy::rpc::types::Block,
-        selected_transaction: alloy::rpc::types::Transaction,
+        selected_transaction: Option<alloy::rpc::types::Transaction>,
     ) -> Self {
         Self {
             title,
             selected_block,
             selected_transaction,
             block_headers: StatefulList::with_items(vec![]),
-            transactions: StatefulList::with_items(vec![]),
+            block_headers_at_head: true,
+            transactions: TransactionList::default(),
             should_quit: false,
             view: View::default(),
             address_display_mode: AddressDisplayMode::default(),
+            prompt: None,
+            filter: None,
+            filter_results: StatefulList::with_items(vec![]),
+            log_query: None,
+            log_results: StatefulList::with_items(vec![]),
+            metrics: None,
+            watched_addresses: HashSet::new(),
+            watchlist: None,
+            watchlist_entries: StatefulList::with_items(vec![]),
+            account_snapshots: HashMap::new(),
+            pending_warnings: vec![],
+            stuck_after: DEFAULT_STUCK_AFTER,
+            bookmarks: StatefulList::with_items(vec![]),
+            status_message: None,
+            db_error: None,
+            transaction_counts: HashMap::new(),
+            fee_stats: vec![],
+            blob_base_fees: vec![],
+            dashboard_chart: DashboardChart::default(),
+            expand_header: false,
+            raw_header: false,
+            compare_fork: false,
+            finality: None,
+            beacon_block: None,
+            l2_block: None,
+            orphaned: HashSet::new(),
+            fork_siblings: vec![],
+            preimage: None,
+            follow: false,
+            template_diff: None,
+            template_transactions: StatefulList::with_items(vec![]),
+            theme: Theme::default(),
+            tracker: None,
+            indexer: None,
+            startup: None,
+            startup_error: None,
+            opening: None,
+            chain_events: None,
+            genesis: None,
+            devchain: None,
+            dev_chain: None,
+            tracer: None,
+            simulator: None,
+            simulated: None,
+            simulation: None,
+            rawtx: None,
+            retrieval: None,
+            raw_shown: None,
+            raw_transaction: None,
+            mempool: None,
+            trace: None,
+            blob_service: None,
+            blobs: None,
+            open_blob: None,
+            contract_service: None,
+            contract_address: None,
+            contract: None,
+            code_pane: CodePane::default(),
+            bytecode: HexView::default(),
+            instructions: StatefulList::with_items(vec![]),
+            source_view: SourceView::default(),
+            storage_slots: vec![],
+            db_size: None,
+            tracked: HashMap::new(),
+            broadcasts: HashMap::new(),
+            receipt_statuses: HashMap::new(),
+            block_fees: None,
+            fee_totals: FeeTotals::default(),
+            recent_block_fees: vec![],
+            hourly_stats: vec![],
+            gas_by_recipient: GasByRecipient::default(),
+            account_activity: None,
+            selector_stats: StatefulList::with_items(vec![]),
+            sql_console: SqlConsole::default(),
+            txpool: None,
+            race: None,
+            endpoint_races: vec![],
+            rpc_health: None,
+            method_health: vec![],
+            txpool_view: TxpoolView::default(),
+            calldata: HexView::default(),
+            log_data: HexView::default(),
+            log_data_focused: false,
+            ticked_block: None,
+            list_area: Rect::default(),
+            history: History::default(),
         }
     }
 
@@ -92,264 +579,4085 @@ impl App {
         self.should_quit = true
     }
 
+    /// Return to the previously visited page, falling back to the parent of
+    /// the current page if there's no history (e.g., when started with
+    /// `--tx`)
     pub fn on_esc(&mut self) {
+        if self.preimage.take().is_some() {
+            return;
+        }
+
+        /* a sender's transactions are drilled into within the same page */
+        if self.view == View::Txpool && self.txpool_view.close() {
+            return;
+        }
+
+        if let Some(page) = self.history.back(self.page()) {
+            self.restore(page);
+            return;
+        }
+
         match self.view {
             View::Default => self.should_quit = true,
             View::Block => self.view = View::Default,
             View::Transaction => self.view = View::Block,
+            View::Filter => self.view = View::Default,
+            View::Logs => self.view = View::Default,
+            View::Template => self.view = View::Block,
+            View::Contract => self.view = View::Default,
+            View::Watchlist => self.view = View::Default,
+            View::Bookmarks => self.view = View::Default,
+            View::Stats => self.view = View::Default,
+            View::Endpoints => self.view = View::Default,
+            View::Sql => self.view = View::Default,
+            View::Txpool => self.view = View::Default,
+            View::Selectors => self.view = View::Default,
         }
     }
 
-    pub fn on_key(&mut self, c: char) {
-        if c == 'q' {
-            self.should_quit = true;
+    pub fn on_prompt_char(&mut self, c: char) {
+        if let Some((_, prompt)) = self.prompt.as_mut() {
+            prompt.push(c);
         }
+    }
 
-        if c == 'r' {
-            self.toggle_address_display_mode();
+    /// Enter the given pasted text into the prompt (on a single line)
+    pub fn on_prompt_paste(&mut self, text: &str) {
+        if let Some((_, prompt)) = self.prompt.as_mut() {
+            text.chars()
+                .filter(|c| !c.is_control())
+                .for_each(|c| prompt.push(c));
         }
+    }
 
-        match self.view {
-            View::Block => {
-                if c == 'e' {
-                    webbrowser::open(
-                        etherscan_block_url(
-                            self.selected_block.clone().header.number,
-                        )
-                        .as_str(),
-                    )
-                    .unwrap()
-                }
+    pub fn on_prompt_backspace(&mut self) {
+        if let Some((_, prompt)) = self.prompt.as_mut() {
+            prompt.pop();
+        }
+    }
 
-                if c == 'l' {
-                    webbrowser::open(
-                        libmev_block_url(
-                            self.selected_block.clone().header.number,
-                        )
-                        .as_str(),
-                    )
-                    .unwrap()
-                }
-            }
-            View::Transaction => {
-                if c == 'e' {
-                    webbrowser::open(
-                        etherscan_transaction_url(
-                            self.selected_transaction
-                                .clone()
-                                .info()
-                                .hash
-                                .unwrap(),
-                        )
-                        .as_str(),
-                    )
-                    .unwrap()
+    /// Replace the SQL prompt's input with an earlier (or, if `back` is
+    /// false, later) query from the console's history
+    pub fn on_prompt_history(&mut self, back: bool) {
+        if let Some((PromptKind::Sql, prompt)) = self.prompt.as_mut() {
+            if back {
+                if let Some(query) = self.sql_console.recall_previous() {
+                    prompt.input = query.to_string();
                 }
+            } else {
+                prompt.input = self
+                    .sql_console
+                    .recall_next()
+                    .unwrap_or_default()
+                    .to_string();
             }
-            _ => {}
+            prompt.error = None;
         }
     }
 
-    pub fn on_enter(&mut self) {
-        if self.get_selected_header().is_some() {
-            self.view = View::Block;
+    pub fn on_prompt_cancel(&mut self) {
+        self.prompt = None;
+    }
+
+    pub fn on_prompt_submit(&mut self, db: &Database) {
+        let Some((kind, mut prompt)) = self.prompt.take() else {
+            return;
+        };
+
+        let result = match kind {
+            PromptKind::CalldataFilter => prompt
+                .input
+                .parse::<CalldataPattern>()
+                .map_err(|e| eyre::eyre!(e))
+                .and_then(|pattern| self.apply_calldata_filter(db, pattern)),
+            PromptKind::LogSearch => prompt
+                .input
+                .parse::<LogQuery>()
+                .map_err(|e| eyre::eyre!(e))
+                .and_then(|query| self.apply_log_search(db, query)),
+            PromptKind::TransactionFilter => prompt
+                .input
+                .parse::<TransactionQuery>()
+                .map_err(|e| eyre::eyre!(e))
+                .map(|query| self.transactions.set_query(query)),
+            PromptKind::Command => prompt
+                .input
+                .parse::<Command>()
+                .map_err(|e| eyre::eyre!(e))
+                .and_then(|command| self.execute_command(db, command)),
+            PromptKind::StorageSlot => prompt
+                .input
+                .trim()
+                .parse::<U256>()
+                .map_err(|_| eyre::eyre!("invalid storage slot"))
+                .and_then(|slot| self.read_storage(B256::from(slot))),
+            PromptKind::BookmarkNote(target) => {
+                let note = prompt.input.trim();
+                self.add_bookmark(
+                    db,
+                    target,
+                    (!note.is_empty()).then(|| note.to_string()),
+                )
+            }
+            PromptKind::Sql => self.run_sql(db, prompt.input.trim()),
+            PromptKind::RawTransaction => prompt
+                .input
+                .trim()
+                .parse::<Bytes>()
+                .map_err(|_| eyre::eyre!("invalid hex"))
+                .and_then(|raw| self.send_transaction(raw)),
+        };
+
+        if let Err(e) = result {
+            prompt.error = Some(e.to_string());
+            self.prompt = Some((kind, prompt));
         }
+    }
 
-        match self.view {
-            View::Default => {
-                if self.get_selected_header().is_some() {
-                    self.view = View::Block
+    fn execute_command(
+        &mut self,
+        db: &Database,
+        command: Command,
+    ) -> eyre::Result<()> {
+        match command {
+            Command::Block(id) => {
+                let block = self
+                    .timed_query(|| match id {
+                        HashOrNumber::Hash(hash) => db.block_by_hash(hash),
+                        HashOrNumber::Number(number) => {
+                            db.block_by_number(number)
+                        }
+                    })
+                    .and_then(|block| block.ok_or(DbError::NotFound("block")));
+                match block {
+                    Ok(block) => {
+                        self.remember();
+                        self.open_block(block);
+                    }
+                    Err(e) if e.is_not_found() => {
+                        self.retrieve(Opening::Block(id))?
+                    }
+                    Err(e) => return Err(e.into()),
                 }
             }
-            View::Block => {
-                if self.get_selected_transaction().is_some() {
-                    self.view = View::Transaction
+            Command::Transaction(hash) => {
+                let tx = self
+                    .timed_query(|| db.transactions().by_hash(hash))
+                    .and_then(|tx| tx.ok_or(DbError::NotFound("transaction")));
+                match tx {
+                    Ok(tx) => self.open_transaction(db, tx)?,
+                    Err(e) if e.is_not_found() => {
+                        self.retrieve(Opening::Transaction(hash))?
+                    }
+                    Err(e) => return Err(e.into()),
                 }
             }
-            _ => {}
+            Command::Filter(pattern) => {
+                self.apply_calldata_filter(db, pattern)?
+            }
+            Command::Account(address) => {
+                self.open_account_history(db, address)?
+            }
+            Command::Contract(address) => self.open_contract(address)?,
+            Command::Logs(query) => self.apply_log_search(db, query)?,
+            Command::Watch(address) => self.set_watched(db, address, true)?,
+            Command::Unwatch(address) => {
+                self.set_watched(db, address, false)?
+            }
+            Command::Label(address, label) => {
+                self.timed_query(|| db.set_label(address, &label))?;
+                utils::set_address_label(address, label.clone());
+                self.status_message =
+                    Some(format!("Labelled {address} as {label}"));
+            }
+            Command::Unlabel(address) => {
+                self.timed_query(|| db.remove_label(address))?;
+                utils::remove_address_label(&address);
+                self.status_message =
+                    Some(format!("Removed label for {address}"));
+            }
+            Command::Track(hash) => self.track(hash)?,
+            Command::Export(format) => {
+                let path = self.export(format)?;
+                self.status_message = Some(format!("Exported to {path}"));
+            }
+            Command::ExportMempool(format) => self.export_mempool(format)?,
+            Command::Keccak(preimage) => self.preimage = Some(preimage),
+            Command::ResetFork(block_number) => {
+                self.devchain
+                    .as_ref()
+                    .ok_or(eyre::eyre!("Not connected to a dev chain"))?
+                    .reset_fork(block_number)?;
+                self.status_message = Some(match block_number {
+                    Some(number) => format!("Re-forking at block #{number}..."),
+                    None => "Re-forking at the latest block...".to_string(),
+                });
+            }
+            Command::ResolveReset(action) => {
+                self.indexer
+                    .as_ref()
+                    .ok_or(eyre::eyre!("Not indexing"))?
+                    .resolve_reset(action)?;
+                self.status_message = Some(match action {
+                    ResetAction::Wipe => "Wiping indexed data...".to_string(),
+                    ResetAction::Archive => {
+                        "Archiving and then wiping indexed data...".to_string()
+                    }
+                });
+            }
+            Command::Sql(query) => {
+                if self.view != View::Sql {
+                    self.remember();
+                    self.view = View::Sql;
+                }
+                if let Some(query) = query {
+                    self.run_sql(db, &query)?;
+                }
+            }
+            Command::Quit => self.should_quit = true,
         }
+        Ok(())
     }
 
-    pub fn on_up(&mut self) {
-        match self.view {
-            View::Default => self.block_headers.previous(),
-            View::Block => self.transactions.previous(),
-            View::Transaction => {}
+    /// Add the given [`Address`] to the watch list (or remove it), which
+    /// persists across sessions
+    fn set_watched(
+        &mut self,
+        db: &Database,
+        address: Address,
+        watched: bool,
+    ) -> eyre::Result<()> {
+        let watchlist = self
+            .watchlist
+            .as_ref()
+            .ok_or(eyre::eyre!("Watch list unavailable"))?;
+
+        if watched {
+            self.timed_query(|| db.watch_address(address))?;
+            watchlist.watch(address)?;
+            self.watched_addresses.insert(address);
+            self.status_message = Some(format!("Watching {address}"));
+        } else {
+            self.timed_query(|| db.unwatch_address(address))?;
+            watchlist.unwatch(address)?;
+            self.watched_addresses.remove(&address);
+            self.status_message = Some(format!("No longer watching {address}"));
         }
-    }
 
-    pub fn on_down(&mut self) {
-        match self.view {
-            View::Default => self.block_headers.next(),
-            View::Block => self.transactions.next(),
-            View::Transaction => {}
+        if self.view == View::Watchlist {
+            self.load_watchlist(db);
         }
+        Ok(())
+    }
+
+    /// Save a [`Bookmark`] of the given target, which persists across
+    /// sessions
+    fn add_bookmark(
+        &mut self,
+        db: &Database,
+        target: BookmarkTarget,
+        note: Option<String>,
+    ) -> eyre::Result<()> {
+        self.timed_query(|| {
+            db.add_bookmark(&Bookmark {
+                target,
+                note,
+                created_at: Utc::now().timestamp() as u64,
+            })
+        })?;
+        self.status_message = Some(format!("Bookmarked {}", target.kind()));
+        self.load_bookmarks(db)
     }
 
-    pub fn on_tick(&mut self, db: &Database) {
-        let latest_header = db
-            .latest_block_header()
-            .unwrap()
-            .expect("invariant violated: must always have at least one header");
+    /// Bookmark whatever is selected, or remove its bookmark if it's already
+    /// bookmarked
+    fn toggle_bookmark(&mut self, db: &Database) -> eyre::Result<()> {
+        let Some(target) = self.selected_bookmark_target() else {
+            return Ok(());
+        };
 
-        if !self.block_headers.items.contains(&latest_header) {
-            self.block_headers.items.push(latest_header.clone());
+        if self
+            .timed_query(|| db.bookmarks())?
+            .iter()
+            .any(|bookmark| bookmark.target == target)
+        {
+            self.timed_query(|| db.remove_bookmark(&target))?;
+            self.status_message =
+                Some(format!("Removed bookmark of {}", target.kind()));
+            self.load_bookmarks(db)
+        } else {
+            self.prompt = Some((
+                PromptKind::BookmarkNote(target),
+                Prompt::new("Note (optional):"),
+            ));
+            Ok(())
         }
+    }
 
-        if let Some(selected_header) = self.get_selected_header() {
-            if !matches!(self.view, View::Block) {
-                if let Some(selected_block) =
-                    db.block_by_hash(selected_header.hash).unwrap()
-                {
-                    self.selected_block = selected_block;
-                    self.transactions = StatefulList::with_items(
-                        self.selected_block
-                            .transactions
-                            .clone()
-                            .into_transactions()
-                            .collect(),
-                    );
-                }
+    /// Re-read the bookmarks, keeping the selection where it was
+    fn load_bookmarks(&mut self, db: &Database) -> eyre::Result<()> {
+        let selected = self.bookmarks.state.selected();
+        let bookmarks = self.timed_query(|| db.bookmarks())?;
+        self.bookmarks.state.select(match selected {
+            _ if bookmarks.is_empty() => None,
+            Some(i) => Some(i.min(bookmarks.len() - 1)),
+            None => Some(0),
+        });
+        self.bookmarks.items = bookmarks;
+        Ok(())
+    }
+
+    /// Go to whatever the given [`BookmarkTarget`] points to
+    fn open_bookmark(
+        &mut self,
+        db: &Database,
+        target: BookmarkTarget,
+    ) -> eyre::Result<()> {
+        match target {
+            BookmarkTarget::Block { hash, .. } => {
+                let block = self
+                    .timed_query(|| db.block_by_hash(hash))?
+                    .ok_or(eyre::eyre!("block not indexed"))?;
+                self.remember();
+                self.open_block(block);
+                Ok(())
             }
+            BookmarkTarget::Transaction(hash) => {
+                let tx = self
+                    .timed_query(|| db.transactions().by_hash(hash))?
+                    .ok_or(eyre::eyre!("transaction not indexed"))?;
+                self.open_transaction(db, tx)
+            }
+            BookmarkTarget::Address(address) => {
+                self.open_account_history(db, address)
+            }
+        }
+    }
+
+    /// Run the given read-only query, showing its results in the SQL console
+    fn run_sql(&mut self, db: &Database, query: &str) -> eyre::Result<()> {
+        if query.is_empty() {
+            return Err(eyre::eyre!("empty query"));
         }
+        self.sql_console.record(query);
+        let result = self.timed_query(|| {
+            db.query(query, SQL_ROWS_LIMIT, SQL_QUERY_TIMEOUT)
+        })?;
+        self.sql_console.show(query.to_string(), result);
+        Ok(())
+    }
 
-        if let Some(selected_tx) = self.get_selected_transaction() {
-            if !matches!(self.view, View::Transaction) {
-                self.selected_transaction = selected_tx.clone();
+    /// Re-read the fees of the most recent blocks (and those summed across
+    /// every indexed block), the heaviest gas consumers over those same
+    /// blocks, and the most recent hourly totals
+    fn load_stats(&mut self, db: &Database) -> eyre::Result<()> {
+        self.fee_totals = self.timed_query(|| db.receipts().fee_totals())?;
+        self.recent_block_fees =
+            self.timed_query(|| db.receipts().recent_block_fees(STATS_WINDOW))?;
+        self.gas_by_recipient = match self.block_headers.items.last() {
+            Some(head) => {
+                let blocks = head.number.saturating_sub(STATS_WINDOW as u64 - 1)
+                    ..=head.number;
+                self.timed_query(|| {
+                    db.receipts().gas_by_recipient(blocks, TOP_GAS_CONSUMERS)
+                })?
             }
+            None => GasByRecipient::default(),
+        };
+        self.hourly_stats =
+            self.timed_query(|| db.headers().hourly_stats(STATS_HOURS))?;
+        Ok(())
+    }
+
+    /// Re-read how each RPC endpoint is faring and how each RPC method has
+    /// performed of late
+    fn load_endpoints(&mut self) -> eyre::Result<()> {
+        /* the race is run in memory, so there's no need to wait for blocks
+         * to be indexed */
+        if let Some(race) = &self.race {
+            self.endpoint_races = race.endpoints()?;
         }
+        if let Some(health) = &self.rpc_health {
+            self.method_health = health
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .summary(Instant::now());
+        }
+        Ok(())
     }
 
-    pub fn draw(&mut self, frame: &mut Frame) {
-        let app_box = Block::bordered()
-            .title(Line::from(self.title.clone()).centered())
-            .border_style(Color::Green);
-        frame.render_widget(app_box.clone(), frame.area());
+    /// Re-read the most called function selectors over the most recent
+    /// blocks, keeping the same selector selected where possible
+    fn load_selector_stats(&mut self, db: &Database) -> eyre::Result<()> {
+        let Some(head) = self.block_headers.items.last() else {
+            return Ok(());
+        };
+        let blocks =
+            head.number.saturating_sub(STATS_WINDOW as u64 - 1)..=head.number;
+        let stats = self.timed_query(|| {
+            db.transactions().selector_stats(blocks, TOP_SELECTORS)
+        })?;
+        let selected = self
+            .selector_stats
+            .state
+            .selected()
+            .and_then(|i| self.selector_stats.items.get(i))
+            .map(|stats| stats.selector);
+        self.selector_stats.state.select(
+            selected
+                .and_then(|selected| {
+                    stats.iter().position(|stats| stats.selector == selected)
+                })
+                .or((!stats.is_empty()).then_some(0)),
+        );
+        self.selector_stats.items = stats;
+        Ok(())
+    }
 
-        match self.view {
-            View::Default => {
-                let chunks =
-                    Layout::vertical([Constraint::Min(20), Constraint::Min(0)])
-                        .split(frame.area());
-                self.draw_latest_blocks_list(frame, chunks[1]);
-                self.draw_gas_barchart(frame, chunks[0], app_box);
-            }
-            View::Block => {
-                let chunks = Layout::vertical([
-                    Constraint::Length(1),
-                    Constraint::Min(0),
-                ])
-                .margin(1)
-                .split(frame.area());
-                self.draw_block_view(frame, chunks[1]);
-            }
-            View::Transaction => {
-                let chunks = Layout::vertical([
-                    Constraint::Length(1),
-                    Constraint::Min(0),
-                ])
-                .margin(1)
-                .split(frame.area());
-                self.draw_transaction_view(frame, chunks[1]);
-            }
+    /// Re-read the balances and nonces recorded for each watched address,
+    /// keeping the same address selected
+    fn load_watchlist(&mut self, db: &Database) {
+        let selected = self.get_selected_watchlist_entry();
+        let mut addresses: Vec<Address> =
+            self.watched_addresses.iter().copied().collect();
+        addresses.sort();
+
+        self.account_snapshots = addresses
+            .iter()
+            .filter_map(|address| {
+                self.timed_query(|| {
+                    db.account_snapshots(*address, WATCHLIST_HISTORY)
+                })
+                .ok()
+                .map(|snapshots| (*address, snapshots))
+            })
+            .collect();
+        self.watchlist_entries.state.select(
+            selected
+                .and_then(|selected| {
+                    addresses.iter().position(|address| *address == selected)
+                })
+                .or((!addresses.is_empty()).then_some(0)),
+        );
+        self.watchlist_entries.items = addresses;
+        self.pending_warnings = self.find_pending_warnings();
+    }
+
+    /// Check the mempool for transactions from watched addresses that are
+    /// held up by a nonce gap or have been pending for too long
+    fn find_pending_warnings(&self) -> Vec<PendingWarning> {
+        let (Some(mempool), Some(head)) =
+            (&self.mempool, self.block_headers.items.last())
+        else {
+            return vec![];
+        };
+        /* an unavailable mempool is reported upon trying to export it */
+        let Ok(pending) = mempool.snapshot() else {
+            return vec![];
+        };
+        let market = Fees::market(
+            head.base_fee_per_gas.unwrap_or_default() as u128,
+            self.fee_stats.last().map_or(0, |stats| stats.p50 as u128),
+        );
+
+        watchlist::pending_warnings(
+            &pending,
+            self.watchlist_entries.items.iter().map(|address| {
+                let nonce = self
+                    .account_snapshots
+                    .get(address)
+                    .and_then(|snapshots| snapshots.last())
+                    .map(|snapshot| snapshot.nonce);
+                (*address, nonce)
+            }),
+            head.number,
+            &market,
+            self.stuck_after,
+        )
+    }
+
+    /// Re-execute the open transaction (afresh, if it's been simulated
+    /// before)
+    fn simulate(&mut self) -> eyre::Result<()> {
+        let tx = self
+            .selected_transaction
+            .as_ref()
+            .ok_or(eyre::eyre!("no transaction to simulate"))?;
+        let simulated = self
+            .simulator
+            .as_ref()
+            .ok_or(eyre::eyre!("simulation unavailable"))?
+            .resimulate(tx)?;
+        self.simulated = Some(*tx.inner.tx_hash());
+        self.simulation = Some((*tx.inner.tx_hash(), simulated));
+        Ok(())
+    }
+
+    /// Start following the given transaction until it's finalized
+    fn track(&mut self, hash: TxHash) -> eyre::Result<()> {
+        self.tracker
+            .as_ref()
+            .ok_or(eyre::eyre!("transaction tracking unavailable"))?
+            .track(hash)?;
+        self.tracked.entry(hash).or_default();
+        self.status_message = Some(format!("Tracking {hash}"));
+        Ok(())
+    }
+
+    /// Broadcast the given signed transaction and follow it until it's
+    /// finalized
+    fn send_transaction(&mut self, raw: Bytes) -> eyre::Result<()> {
+        let hash = self
+            .tracker
+            .as_ref()
+            .ok_or(eyre::eyre!("transaction broadcasting unavailable"))?
+            .broadcast(raw)?;
+        self.tracked.entry(hash).or_default();
+        self.broadcasts.insert(hash, Broadcast::Sent);
+        self.status_message = Some(format!("Sending {hash}"));
+        Ok(())
+    }
+
+    /// Write the currently viewed block or transaction to a file in the
+    /// working directory, returning the path written to
+    fn export(&self, format: ExportFormat) -> eyre::Result<String> {
+        let (hash, contents) =
+            match (self.view, format, &self.selected_transaction) {
+                (View::Transaction, ExportFormat::Json, Some(tx)) => {
+                    (*tx.inner.tx_hash(), serde_json::to_string_pretty(tx)?)
+                }
+                (_, ExportFormat::Json, _) => (
+                    self.selected_block.header.hash,
+                    serde_json::to_string_pretty(&self.selected_block)?,
+                ),
+            };
+        let path = format!("{hash}.{format}");
+        fs::write(&path, contents)?;
+        Ok(path)
+    }
+
+    /// Write the pending transactions seen so far to a file in the working
+    /// directory
+    fn export_mempool(&mut self, format: export::Format) -> eyre::Result<()> {
+        let mempool = self
+            .mempool
+            .as_ref()
+            .ok_or(eyre::eyre!("Mempool unavailable"))?;
+        if !mempool.follow() {
+            self.status_message = Some(
+                "Now following the mempool, so export again shortly"
+                    .to_string(),
+            );
+            return Ok(());
         }
+        let pending = mempool.snapshot()?;
+        let path =
+            format!("mempool-{}.{format}", Utc::now().format("%Y%m%dT%H%M%S"));
+        let count = export::write_rows(
+            &mempool::COLUMNS,
+            pending.iter().map(|tx| Ok(tx.row())),
+            format,
+            &mut BufWriter::new(fs::File::create(&path)?),
+        )?;
+        self.status_message =
+            Some(format!("Exported {count} pending transactions to {path}"));
+        Ok(())
     }
 
-    fn draw_transaction_view(&mut self, frame: &mut Frame, area: Rect) {
-        self.draw_transaction_header_text(frame, area);
+    /// Switch to the block view for the given block
+    fn open_block(&mut self, block: alloy::rpc::types::Block) {
+        self.transactions.set_transactions(
+            block.transactions.clone().into_transactions().collect(),
+        );
+        self.selected_block = block;
+        self.view = View::Block;
     }
 
-    fn draw_transaction_header_text(&mut self, frame: &mut Frame, area: Rect) {
-        let tx = self.selected_transaction.clone();
-        let timestamp = self.selected_block.header.timestamp;
+    /// Switch to the transaction view for the given transaction, loading its
+    /// enclosing block so that returning to the block view is seamless
+    fn open_transaction(
+        &mut self,
+        db: &Database,
+        tx: Transaction,
+    ) -> eyre::Result<()> {
+        let block = self
+            .timed_query(|| {
+                db.block_by_hash(tx.block_hash.unwrap_or_default())
+            })?
+            .ok_or(eyre::eyre!("enclosing block not indexed"))?;
+        self.remember();
+        self.open_block(block);
+        if let Some(i) = tx.transaction_index {
+            self.transactions.select_index(i as usize);
+        }
+        self.selected_transaction = Some(tx);
+        self.calldata = HexView::default();
+        self.view = View::Transaction;
+        Ok(())
+    }
 
-        let chunks =
-            Layout::vertical([Constraint::Percentage(20), Constraint::Min(0)])
-                .split(area);
+    fn apply_calldata_filter(
+        &mut self,
+        db: &Database,
+        pattern: CalldataPattern,
+    ) -> eyre::Result<()> {
+        if pattern.bytes().is_empty() {
+            return Err(eyre::eyre!("empty pattern"));
+        }
 
-        let lines = vec![
-            Line::from(Span::styled(
-                format!("Transaction {}", tx.info().hash.unwrap()),
-                Style::new().bold(),
-            )),
-            Line::from(vec![
-                Span::styled("Timestamp: ", Style::new().bold()),
-                Span::raw(format!(
-                    "{} ({})",
-                    Utc.timestamp_opt(timestamp as i64, 0).unwrap(),
-                    timeago::Formatter::new()
-                        .convert(utils::duration_since_timestamp(timestamp))
-                )),
-            ]),
-            Line::from(vec![
-                Span::styled("From: ", Style::new().bold()),
-                Span::raw(format!("{}", tx.as_recovered().signer())),
-            ]),
-            Line::from(vec![
-                Span::styled("To:   ", Style::new().bold()),
-                match tx.to() {
-                    Some(addr) => Span::raw(
-                        label_address(&addr, false, self.address_display_mode)
-                            .to_string(),
-                    ),
-                    None => Span::raw(format!(
-                        "{} (CREATE)",
-                        label_address(
-                            &Address::ZERO,
-                            false,
-                            self.address_display_mode
-                        )
-                    )),
-                },
-            ]),
-            Line::from(vec![
-                Span::styled("Value: ", Style::new().bold()),
-                Span::raw(format!("{} Ether", to_ether(tx.value()))),
-            ]),
-            Line::from(vec![
-                Span::styled("Input: ", Style::new().bold()),
-                Span::raw(format!("({} bytes)", tx.input().len())),
-            ]),
-        ];
-        let transaction_header_text = Paragraph::new(Text::from(lines));
-        frame.render_widget(transaction_header_text, chunks[0]);
-        self.draw_hex_display(tx.input(), frame, chunks[1]);
+        self.filter_results =
+            StatefulList::with_items(self.timed_query(|| {
+                db.transactions()
+                    .by_input_pattern(&pattern, FILTER_RESULTS_LIMIT)
+            })?);
+        self.filter = Some(TransactionFilter::Calldata(pattern));
+        if self.view != View::Filter {
+            self.remember();
+            self.view = View::Filter;
+        }
+        Ok(())
     }
 
-    fn draw_block_view(&mut self, frame: &mut Frame, area: Rect) {
-        let chunks =
-            Layout::vertical([Constraint::Percentage(20), Constraint::Min(0)])
-                .split(area);
-        self.draw_block_header_text(frame, chunks[0]);
-        self.draw_transactions_list(frame, chunks[1]);
+    fn open_account_history(
+        &mut self,
+        db: &Database,
+        address: Address,
+    ) -> eyre::Result<()> {
+        self.filter_results =
+            StatefulList::with_items(self.timed_query(|| {
+                db.transactions()
+                    .account_history(address, FILTER_RESULTS_LIMIT)
+            })?);
+        self.filter = Some(TransactionFilter::Account(address));
+        self.load_account_activity(db, address)?;
+        if self.view != View::Filter {
+            self.remember();
+            self.view = View::Filter;
+        }
+        Ok(())
     }
 
-    fn draw_block_header_text(&mut self, frame: &mut Frame, area: Rect) {
-        let block = &self.selected_block;
-        let lines = vec![
-            Line::from(vec![Span::styled(
-                format!("Block #{} {}", block.header.number, block.header.hash),
-                Style::default().bold(),
-            )]),
-            Line::from(vec![
-                Span::styled("Timestamp: ", Style::new().bold()),
-                Span::raw(format!(
-                    "{} ({})",
-                    Utc.timestamp_opt(block.header.timestamp as i64, 0)
-                        .unwrap(),
-                    timeago::Formatter::new().convert(
-                        utils::duration_since_timestamp(block.header.timestamp)
-                    )
-                )),
-            ]),
-            Line::from(vec![
-                Span::styled("Gas Usage (wei): ", Style::new().bold()),
-                Span::raw(format!(
-                    "{}  / {} ({:.2}%)",
+    /// Re-read the totals of the transactions sent from or to the given
+    /// address over the most recent blocks and days
+    fn load_account_activity(
+        &mut self,
+        db: &Database,
+        address: Address,
+    ) -> eyre::Result<()> {
+        let head = self
+            .timed_query(|| db.headers().latest())?
+            .map(|header| (header.number, header.timestamp))
+            .unwrap_or_default();
+        let by_block = self.timed_query(|| {
+            db.transactions().address_activity(
+                address,
+                ActivityBucket::Block,
+                STATS_WINDOW,
+            )
+        })?;
+        let by_day = self.timed_query(|| {
+            db.transactions().address_activity(
+                address,
+                ActivityBucket::Day,
+                ACTIVITY_DAYS,
+            )
+        })?;
+        self.account_activity = Some(AccountActivity {
+            address,
+            head,
+            by_block,
+            by_day,
+        });
+        Ok(())
+    }
+
+    fn apply_log_search(
+        &mut self,
+        db: &Database,
+        query: LogQuery,
+    ) -> eyre::Result<()> {
+        self.log_results =
+            StatefulList::with_items(self.timed_query(|| {
+                db.logs().by_topic(&query, LOG_RESULTS_LIMIT)
+            })?);
+        self.log_query = Some(query);
+        self.log_data = HexView::default();
+        self.log_data_focused = false;
+        if self.view != View::Logs {
+            self.remember();
+            self.view = View::Logs;
+        }
+        Ok(())
+    }
+
+    /// Show the code and storage of the contract at the given [`Address`]
+    fn open_contract(&mut self, address: Address) -> eyre::Result<()> {
+        let service = self
+            .contract_service
+            .as_ref()
+            .ok_or(eyre::eyre!("Not connected to a node"))?;
+        self.contract = Some((address, service.contract(address)?));
+
+        if self.contract_address != Some(address) {
+            self.contract_address = Some(address);
+            self.bytecode = HexView::default();
+            self.instructions = StatefulList::with_items(vec![]);
+            self.source_view = SourceView::default();
+            self.storage_slots = vec![];
+        }
+
+        if self.view != View::Contract {
+            self.remember();
+            self.view = View::Contract;
+        }
+        Ok(())
+    }
+
+    /// Read the given storage slot of the open contract (again, if it's been
+    /// read before)
+    fn read_storage(&mut self, slot: B256) -> eyre::Result<()> {
+        let (Some(service), Some(address)) =
+            (&self.contract_service, self.contract_address)
+        else {
+            return Ok(());
+        };
+        service.read_storage(address, slot)?;
+        self.storage_slots.retain(|(read, _)| *read != slot);
+        self.storage_slots.insert(0, (slot, Fetched::Pending));
+        Ok(())
+    }
+
+    /// Code of the open contract (once it's been retrieved)
+    fn contract_code(&self) -> Bytes {
+        match &self.contract {
+            Some((address, Fetched::Known(contract)))
+                if Some(*address) == self.contract_address =>
+            {
+                contract.code.clone()
+            }
+            _ => Bytes::new(),
+        }
+    }
+
+    /// Show the open transaction's next blob in place of its calldata (or its
+    /// calldata again after the last blob)
+    fn next_blob(&mut self) {
+        let Some(hash) = self.selected_transaction_hash() else {
+            return;
+        };
+        let count = self
+            .selected_transaction
+            .as_ref()
+            .and_then(|tx| tx.blob_versioned_hashes())
+            .map_or(0, |hashes| hashes.len());
+
+        if count == 0 {
+            self.status_message =
+                Some("Transaction carries no blobs".to_string());
+        } else if self.blob_service.is_none() {
+            self.status_message =
+                Some("Inspecting blobs requires --cl-rpc".to_string());
+        } else {
+            self.open_blob = match self.open_blob_index() {
+                Some(i) => (i + 1 < count).then_some((hash, i + 1)),
+                None => Some((hash, 0)),
+            };
+            self.raw_shown = None;
+            self.calldata = HexView::default();
+        }
+    }
+
+    /// Show the open transaction's encoding in place of its calldata (or its
+    /// calldata again)
+    fn toggle_raw_transaction(&mut self) {
+        let Some(hash) = self.selected_transaction_hash() else {
+            return;
+        };
+        self.raw_shown = (!self.is_raw_shown()).then_some(hash);
+        self.open_blob = None;
+        self.calldata = HexView::default();
+    }
+
+    /// Whether the open transaction's encoding is shown
+    fn is_raw_shown(&self) -> bool {
+        self.raw_shown.is_some()
+            && self.raw_shown == self.selected_transaction_hash()
+    }
+
+    /// The open transaction's encoding (once it's been retrieved)
+    fn raw_transaction_bytes(&self) -> Option<&Bytes> {
+        match &self.raw_transaction {
+            Some((hash, Fetched::Known(raw)))
+                if Some(*hash) == self.selected_transaction_hash() =>
+            {
+                Some(raw)
+            }
+            _ => None,
+        }
+    }
+
+    /// Index of the blob of the open transaction that's shown (if any)
+    fn open_blob_index(&self) -> Option<usize> {
+        self.open_blob
+            .filter(|(hash, _)| Some(*hash) == self.selected_transaction_hash())
+            .map(|(_, i)| i)
+    }
+
+    /// Bytes shown in the transaction view's hex dump: either the open blob
+    /// (once it's been retrieved) or the calldata
+    fn hex_bytes(&self) -> Bytes {
+        if self.is_raw_shown() {
+            return self.raw_transaction_bytes().cloned().unwrap_or_default();
+        }
+        match self.open_blob_index() {
+            Some(i) => match &self.blobs {
+                Some((hash, Blobs::Known(sidecars)))
+                    if Some(*hash) == self.selected_transaction_hash() =>
+                {
+                    sidecars
+                        .get(i)
+                        .map(|sidecar| sidecar.blob.clone())
+                        .unwrap_or_default()
+                }
+                _ => Bytes::new(),
+            },
+            None => self
+                .selected_transaction
+                .as_ref()
+                .map(|tx| tx.input().clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Compare the selected block against the local block template recorded
+    /// for its height (if any)
+    fn load_template_diff(&mut self, db: &Database) -> eyre::Result<()> {
+        let template = self.timed_query(|| {
+            db.block_template(self.selected_block.header.number)
+        })?;
+        let diff = self.timed_query(|| {
+            TemplateDiff::new(template, &self.selected_block, |hash| {
+                db.transactions()
+                    .by_hash(hash)
+                    .ok()
+                    .flatten()
+                    .and_then(|tx| tx.block_number)
+            })
+        });
+        self.template_transactions =
+            StatefulList::with_items(diff.transactions.clone());
+        self.template_diff = Some(diff);
+        Ok(())
+    }
+
+    pub fn on_action(&mut self, db: &Database, action: Action) {
+        match action {
+            Action::Quit => self.on_quit(),
+            Action::Up => self.on_up(db),
+            Action::Down => self.on_down(db),
+            Action::PageUp => self.on_page_up(db),
+            Action::PageDown => self.on_page_down(db),
+            Action::First => self.on_first(),
+            Action::Last => self.on_last(db),
+            Action::Select => self.on_enter(db),
+            Action::Back => self.on_esc(),
+            Action::Forward => self.on_forward(),
+            Action::ToggleLabels => self.toggle_address_display_mode(),
+            Action::CommandPalette => {
+                self.prompt = Some((PromptKind::Command, Prompt::new(":")))
+            }
+            Action::FilterCalldata
+                if matches!(self.view, View::Default | View::Filter) =>
+            {
+                self.prompt = Some((
+                    PromptKind::CalldataFilter,
+                    Prompt::new("Calldata (hex, ^ anchors to start):"),
+                ))
+            }
+            Action::FilterTransactions if matches!(self.view, View::Block) => {
+                let mut prompt = Prompt::new(
+                    "Transactions (from: to: method: sort:gas-price|value|nonce):",
+                );
+                prompt.input = self.transactions.query.to_string();
+                self.prompt = Some((PromptKind::TransactionFilter, prompt))
+            }
+            Action::SearchLogs
+                if matches!(self.view, View::Default | View::Logs) =>
+            {
+                self.prompt = Some((
+                    PromptKind::LogSearch,
+                    Prompt::new("Logs (topic0 [address] [from..to]):"),
+                ))
+            }
+            Action::ChartGasUsed if matches!(self.view, View::Default) => {
+                self.dashboard_chart = DashboardChart::GasUsed
+            }
+            Action::ChartBaseFee if matches!(self.view, View::Default) => {
+                self.dashboard_chart = DashboardChart::BaseFee
+            }
+            Action::Mine => {
+                self.status_message = Some(
+                    match self.devchain.as_ref().map(|devchain| devchain.mine())
+                    {
+                        Some(Ok(())) => "Mining a block...".to_string(),
+                        Some(Err(e)) => e.to_string(),
+                        None => "Not connected to a dev chain".to_string(),
+                    },
+                )
+            }
+            Action::ExpandHeader if matches!(self.view, View::Block) => {
+                self.expand_header = !self.expand_header
+            }
+            Action::ToggleRaw if matches!(self.view, View::Block) => {
+                self.raw_header = !self.raw_header
+            }
+            Action::ToggleRaw if matches!(self.view, View::Transaction) => {
+                self.toggle_raw_transaction()
+            }
+            Action::ExportMempool => {
+                if let Err(e) = self.export_mempool(export::Format::Json) {
+                    self.status_message = Some(e.to_string());
+                }
+            }
+            Action::CompareFork if matches!(self.view, View::Block) => {
+                if self.fork_sibling().is_some() {
+                    self.compare_fork = !self.compare_fork
+                } else {
+                    self.status_message =
+                        Some("No other block at this height".to_string());
+                }
+            }
+            Action::ToggleFollow if matches!(self.view, View::Default) => {
+                self.follow = !self.follow;
+                if self.follow {
+                    if !self.block_headers_at_head {
+                        self.page_to_head(db);
+                    }
+                    self.block_headers.last();
+                }
+            }
+            Action::ChartBlobGas if matches!(self.view, View::Default) => {
+                self.dashboard_chart = DashboardChart::BlobGasUsed
+            }
+            Action::TrackTransaction
+                if matches!(self.view, View::Transaction) =>
+            {
+                if let Some(hash) = self.selected_transaction_hash() {
+                    if let Err(e) = self.track(hash) {
+                        self.status_message = Some(e.to_string());
+                    }
+                }
+            }
+            Action::Yank => self.yank(false),
+            Action::YankAddress => self.yank(true),
+            Action::OpenEtherscan => self.open_etherscan(),
+            Action::OpenLibmev if matches!(self.view, View::Block) => self
+                .open_url(&libmev_block_url(self.selected_block.header.number)),
+            Action::InspectBlobs if matches!(self.view, View::Transaction) => {
+                self.next_blob()
+            }
+            Action::OpenContract => {
+                let result = match (self.view, self.filter.clone()) {
+                    (View::Transaction, _) => {
+                        match self
+                            .selected_transaction
+                            .as_ref()
+                            .and_then(|tx| tx.to())
+                        {
+                            Some(address) => self.open_contract(address),
+                            None => Err(eyre::eyre!(
+                                "Transaction deploys a contract rather than calling one"
+                            )),
+                        }
+                    }
+                    (
+                        View::Filter,
+                        Some(TransactionFilter::Account(address)),
+                    ) => self.open_contract(address),
+                    _ => Ok(()),
+                };
+                if let Err(e) = result {
+                    self.status_message = Some(e.to_string());
+                }
+            }
+            Action::FocusLogData if matches!(self.view, View::Logs) => {
+                self.log_data_focused = !self.log_data_focused
+            }
+            Action::ToggleDisassembly
+                if matches!(self.view, View::Contract) =>
+            {
+                self.code_pane = match self.code_pane {
+                    CodePane::Disassembly => CodePane::Bytecode,
+                    _ => CodePane::Disassembly,
+                }
+            }
+            Action::ToggleSource if matches!(self.view, View::Contract) => {
+                self.code_pane = match self.code_pane {
+                    CodePane::Source => CodePane::Bytecode,
+                    _ => CodePane::Source,
+                }
+            }
+            Action::ReadStorage if matches!(self.view, View::Contract) => {
+                self.prompt = Some((
+                    PromptKind::StorageSlot,
+                    Prompt::new("Storage slot (decimal or 0x-prefixed hex):"),
+                ))
+            }
+            Action::WatchAddress => {
+                if let Some(address) = self.selected_address() {
+                    let watched = !self.watched_addresses.contains(&address);
+                    if let Err(e) = self.set_watched(db, address, watched) {
+                        self.status_message = Some(e.to_string());
+                    }
+                }
+            }
+            Action::Bookmark => {
+                if let Err(e) = self.toggle_bookmark(db) {
+                    self.status_message = Some(e.to_string());
+                }
+            }
+            Action::OpenBookmarks if self.view != View::Bookmarks => {
+                match self.load_bookmarks(db) {
+                    Ok(()) => {
+                        self.remember();
+                        self.view = View::Bookmarks;
+                    }
+                    Err(e) => self.status_message = Some(e.to_string()),
+                }
+            }
+            Action::SendTransaction => {
+                self.prompt =
+                    Some((PromptKind::RawTransaction, Prompt::new("Raw tx:")))
+            }
+            Action::Simulate if matches!(self.view, View::Transaction) => {
+                if let Err(e) = self.simulate() {
+                    self.status_message = Some(e.to_string());
+                }
+            }
+            Action::OpenTxpool if self.view != View::Txpool => {
+                match self
+                    .txpool
+                    .as_ref()
+                    .ok_or(eyre::eyre!("transaction pool unavailable"))
+                    .and_then(|txpool| txpool.refresh(None))
+                {
+                    Ok(()) => {
+                        self.remember();
+                        self.view = View::Txpool;
+                    }
+                    Err(e) => self.status_message = Some(e.to_string()),
+                }
+            }
+            Action::OpenSelectors if self.view != View::Selectors => {
+                match self.load_selector_stats(db) {
+                    Ok(()) => {
+                        self.remember();
+                        self.view = View::Selectors;
+                    }
+                    Err(e) => self.status_message = Some(e.to_string()),
+                }
+            }
+            Action::OpenEndpoints if self.view != View::Endpoints => {
+                match self.load_endpoints() {
+                    Ok(()) => {
+                        self.remember();
+                        self.view = View::Endpoints;
+                    }
+                    Err(e) => self.status_message = Some(e.to_string()),
+                }
+            }
+            Action::OpenStats if self.view != View::Stats => {
+                match self.load_stats(db) {
+                    Ok(()) => {
+                        self.remember();
+                        self.view = View::Stats;
+                    }
+                    Err(e) => self.status_message = Some(e.to_string()),
+                }
+            }
+            Action::OpenWatchlist if self.view != View::Watchlist => {
+                /* the mempool is only followed once it's needed */
+                if let Some(mempool) = &self.mempool {
+                    mempool.follow();
+                }
+                self.load_watchlist(db);
+                self.remember();
+                self.view = View::Watchlist;
+            }
+            Action::CompareTemplate if matches!(self.view, View::Block) => {
+                match self.load_template_diff(db) {
+                    Ok(()) if self.template_transactions.items.is_empty() => {
+                        self.status_message = Some(
+                            "No local template recorded for this block"
+                                .to_string(),
+                        )
+                    }
+                    Ok(()) => {
+                        self.remember();
+                        self.view = View::Template
+                    }
+                    Err(e) => self.status_message = Some(e.to_string()),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Copy the hash (or address) of whatever is selected to the clipboard
+    fn yank(&mut self, address: bool) {
+        let text = if let Some(preimage) = &self.preimage {
+            Some(preimage.hash().to_string())
+        } else if address {
+            self.selected_address().map(|address| address.to_string())
+        } else {
+            match self.view {
+                View::Default => {
+                    self.get_selected_header().map(|h| h.hash.to_string())
+                }
+                View::Block => {
+                    Some(self.selected_block.header.hash.to_string())
+                }
+                View::Transaction if self.is_raw_shown() => {
+                    self.raw_transaction_bytes().map(|raw| raw.to_string())
+                }
+                View::Transaction => self
+                    .selected_transaction_hash()
+                    .map(|hash| hash.to_string()),
+                View::Filter => self
+                    .get_selected_filter_result()
+                    .and_then(|tx| tx.info().hash)
+                    .map(|hash| hash.to_string()),
+                View::Logs => self
+                    .get_selected_log_result()
+                    .and_then(|log| log.transaction_hash)
+                    .map(|hash| hash.to_string()),
+                View::Template => self
+                    .get_selected_template_transaction()
+                    .map(|(hash, _)| hash.to_string()),
+                View::Contract => {
+                    self.contract_address.map(|address| address.to_string())
+                }
+                View::Watchlist => self
+                    .get_selected_watchlist_entry()
+                    .map(|address| address.to_string()),
+                View::Bookmarks => {
+                    self.get_selected_bookmark().map(|bookmark| match bookmark
+                        .target
+                    {
+                        BookmarkTarget::Block { hash, .. } => hash.to_string(),
+                        BookmarkTarget::Transaction(hash) => hash.to_string(),
+                        BookmarkTarget::Address(address) => address.to_string(),
+                    })
+                }
+                View::Stats | View::Endpoints => None,
+                View::Sql => {
+                    self.sql_console.selected_row().map(|row| row.join("\t"))
+                }
+                View::Selectors => self
+                    .get_selected_selector_stats()
+                    .and_then(|stats| stats.selector)
+                    .map(|selector| selector.to_string()),
+                View::Txpool => self
+                    .txpool_view
+                    .selected_hash()
+                    .map(|hash| hash.to_string())
+                    .or(self
+                        .txpool_view
+                        .selected_address()
+                        .map(|address| address.to_string())),
+            }
+        };
+
+        if let Some(text) = text {
+            let shortened = utils::shorten(&text, 80);
+            self.status_message = Some(match clipboard::copy(&text) {
+                Ok(Copied::Osc52(Some(failure))) => format!(
+                    "Copied {shortened} via the terminal (clipboard program \
+                    failed: {failure})"
+                ),
+                Ok(_) => format!("Copied {shortened}"),
+                Err(e) => format!("Failed to copy to clipboard: {e}"),
+            });
+        }
+    }
+
+    /// The most relevant address of whatever is selected (e.g., the sender
+    /// of the open transaction)
+    fn selected_address(&self) -> Option<Address> {
+        match self.view {
+            View::Default => {
+                self.get_selected_header().map(|header| header.beneficiary)
+            }
+            View::Block => Some(self.selected_block.header.beneficiary),
+            View::Transaction => self
+                .selected_transaction
+                .as_ref()
+                .map(|tx| tx.as_recovered().signer()),
+            View::Filter => self
+                .get_selected_filter_result()
+                .map(|tx| tx.as_recovered().signer()),
+            View::Logs => {
+                self.get_selected_log_result().map(|log| log.address())
+            }
+            View::Template => None,
+            View::Contract => self.contract_address,
+            View::Watchlist => self.get_selected_watchlist_entry(),
+            View::Bookmarks => {
+                self.get_selected_bookmark()
+                    .and_then(|bookmark| match bookmark.target {
+                        BookmarkTarget::Address(address) => Some(address),
+                        _ => None,
+                    })
+            }
+            View::Txpool => self.txpool_view.selected_address(),
+            View::Stats | View::Sql | View::Selectors | View::Endpoints => None,
+        }
+    }
+
+    /// What would be bookmarked given whatever is selected (e.g., the open
+    /// transaction)
+    fn selected_bookmark_target(&self) -> Option<BookmarkTarget> {
+        match self.view {
+            View::Default => {
+                self.get_selected_header()
+                    .map(|header| BookmarkTarget::Block {
+                        number: header.number,
+                        hash: header.hash,
+                    })
+            }
+            View::Block => Some(BookmarkTarget::Block {
+                number: self.selected_block.header.number,
+                hash: self.selected_block.header.hash,
+            }),
+            View::Transaction => self
+                .selected_transaction_hash()
+                .map(BookmarkTarget::Transaction),
+            View::Filter => self
+                .get_selected_filter_result()
+                .and_then(|tx| tx.info().hash)
+                .map(BookmarkTarget::Transaction),
+            View::Logs => self
+                .get_selected_log_result()
+                .and_then(|log| log.transaction_hash)
+                .map(BookmarkTarget::Transaction),
+            View::Template => self
+                .get_selected_template_transaction()
+                .map(|(hash, _)| BookmarkTarget::Transaction(*hash)),
+            View::Contract => {
+                self.contract_address.map(BookmarkTarget::Address)
+            }
+            View::Watchlist => self
+                .get_selected_watchlist_entry()
+                .map(BookmarkTarget::Address),
+            View::Bookmarks => {
+                self.get_selected_bookmark().map(|bookmark| bookmark.target)
+            }
+            View::Txpool => match self.txpool_view.selected_hash() {
+                Some(hash) => Some(BookmarkTarget::Transaction(hash)),
+                None => self
+                    .txpool_view
+                    .selected_address()
+                    .map(BookmarkTarget::Address),
+            },
+            View::Stats | View::Sql | View::Selectors | View::Endpoints => None,
+        }
+    }
+
+    fn open_etherscan(&mut self) {
+        let url = match self.view {
+            View::Block => {
+                Some(etherscan_block_url(self.selected_block.header.number))
+            }
+            View::Template => self
+                .get_selected_template_transaction()
+                .map(|(hash, _)| etherscan_transaction_url(*hash)),
+            View::Transaction => self
+                .selected_transaction_hash()
+                .map(etherscan_transaction_url),
+            View::Txpool => self
+                .txpool_view
+                .selected_hash()
+                .map(etherscan_transaction_url)
+                .or_else(|| self.selected_address().map(etherscan_address_url)),
+            View::Contract | View::Watchlist => {
+                self.selected_address().map(etherscan_address_url)
+            }
+            View::Bookmarks => {
+                self.get_selected_bookmark().map(|b| match b.target {
+                    BookmarkTarget::Block { number, .. } => {
+                        etherscan_block_url(number)
+                    }
+                    BookmarkTarget::Transaction(hash) => {
+                        etherscan_transaction_url(hash)
+                    }
+                    BookmarkTarget::Address(address) => {
+                        etherscan_address_url(address)
+                    }
+                })
+            }
+            _ => None,
+        };
+
+        if let Some(url) = url {
+            self.open_url(&url);
+        }
+    }
+
+    /// Open the given [`Url`] in the default browser, noting in the status
+    /// bar should there be no browser to open it in
+    fn open_url(&mut self, url: &Url) {
+        if let Err(e) = webbrowser::open(url.as_str()) {
+            self.status_message = Some(format!("Failed to open {url}: {e}"));
+        }
+    }
+
+    /// Go forward to the page most recently returned from (if any)
+    fn on_forward(&mut self) {
+        if let Some(page) = self.history.forward(self.page()) {
+            self.restore(page);
+        }
+    }
+
+    fn page(&self) -> Page {
+        Page {
+            view: self.view,
+            block: self.selected_block.clone(),
+            transaction: self.selected_transaction.clone(),
+        }
+    }
+
+    /// Record the current page in the history, as it's about to be navigated
+    /// away from
+    fn remember(&mut self) {
+        self.history.visit(self.page());
+    }
+
+    fn restore(&mut self, page: Page) {
+        if page.block.header.hash != self.selected_block.header.hash {
+            self.transactions.set_transactions(
+                page.block
+                    .transactions
+                    .clone()
+                    .into_transactions()
+                    .collect(),
+            );
+        }
+
+        if let Some(i) = page
+            .transaction
+            .as_ref()
+            .and_then(|tx| tx.transaction_index)
+        {
+            self.transactions.select_index(i as usize);
+        }
+
+        self.selected_block = page.block;
+        self.selected_transaction = page.transaction;
+        self.calldata = HexView::default();
+        self.view = page.view;
+    }
+
+    pub fn on_enter(&mut self, db: &Database) {
+        match self.view {
+            View::Default if self.get_selected_header().is_some() => {
+                self.remember();
+                self.view = View::Block
+            }
+            View::Block if self.get_selected_transaction().is_some() => {
+                self.remember();
+                self.calldata = HexView::default();
+                self.view = View::Transaction
+            }
+            View::Filter => {
+                if let Some(tx) = self.get_selected_filter_result().cloned() {
+                    if let Err(e) = self.open_transaction(db, tx) {
+                        self.status_message = Some(e.to_string());
+                    }
+                }
+            }
+            View::Watchlist => {
+                if let Some(address) = self.get_selected_watchlist_entry() {
+                    if let Err(e) = self.open_account_history(db, address) {
+                        self.status_message = Some(e.to_string());
+                    }
+                }
+            }
+            View::Bookmarks => {
+                if let Some(target) =
+                    self.get_selected_bookmark().map(|bookmark| bookmark.target)
+                {
+                    if let Err(e) = self.open_bookmark(db, target) {
+                        self.status_message = Some(e.to_string());
+                    }
+                }
+            }
+            View::Sql => {
+                self.sql_console.reset_recall();
+                self.prompt = Some((PromptKind::Sql, Prompt::new("SQL:")));
+            }
+            View::Txpool => {
+                self.txpool_view.open_selected();
+                /* the sender's transactions are worth having afresh */
+                if let (Some(sender), Some(txpool)) =
+                    (self.txpool_view.sender, &self.txpool)
+                {
+                    if let Err(e) = txpool.refresh(Some(sender)) {
+                        self.status_message = Some(e.to_string());
+                    }
+                }
+            }
+            View::Selectors => {
+                if let Some(selector) = self
+                    .get_selected_selector_stats()
+                    .and_then(|stats| stats.selector)
+                {
+                    let pattern = CalldataPattern::Prefix(
+                        Bytes::copy_from_slice(selector.as_slice()),
+                    );
+                    if let Err(e) = self.apply_calldata_filter(db, pattern) {
+                        self.status_message = Some(e.to_string());
+                    }
+                }
+            }
+            View::Logs | View::Template => {
+                let hash = match self.view {
+                    View::Logs => self
+                        .get_selected_log_result()
+                        .and_then(|log| log.transaction_hash),
+                    _ => self
+                        .get_selected_template_transaction()
+                        .filter(|(_, status)| {
+                            !matches!(status, TemplateTxStatus::NotIncluded)
+                        })
+                        .map(|(hash, _)| *hash),
+                };
+                if let Some(hash) = hash {
+                    let result = match self
+                        .timed_query(|| db.transactions().by_hash(hash))
+                        .and_then(|tx| {
+                            tx.ok_or(DbError::NotFound("transaction"))
+                        }) {
+                        Ok(tx) => self.open_transaction(db, tx),
+                        Err(e) if e.is_not_found() => {
+                            self.retrieve(Opening::Transaction(hash))
+                        }
+                        Err(e) => Err(e.into()),
+                    };
+                    if let Err(e) = result {
+                        self.status_message = Some(e.to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn on_mouse(&mut self, db: &Database, event: MouseEvent) {
+        match event.kind {
+            MouseEventKind::ScrollUp => self.on_up(db),
+            MouseEventKind::ScrollDown => self.on_down(db),
+            MouseEventKind::Down(MouseButton::Left) => {
+                self.on_click(db, Position::new(event.column, event.row))
+            }
+            MouseEventKind::Down(MouseButton::Right) => self.on_esc(),
+            _ => {}
+        }
+    }
+
+    /// Select the list item under the cursor, opening it if it was already
+    /// selected
+    fn on_click(&mut self, db: &Database, position: Position) {
+        /* skip the list's borders */
+        let rows = self.list_area.inner(Margin::new(1, 1));
+
+        if !rows.contains(position) {
+            return;
+        }
+
+        let row = (position.y - rows.y) as usize;
+        let already_selected = match self.view {
+            View::Default => self.block_headers.select_visible(row),
+            View::Block => self.transactions.rows.select_visible(row),
+            View::Transaction => None,
+            View::Filter => self.filter_results.select_visible(row),
+            View::Logs => self.log_results.select_visible(row),
+            View::Template => self.template_transactions.select_visible(row),
+            View::Contract if self.code_pane == CodePane::Disassembly => {
+                self.instructions.select_visible(row)
+            }
+            View::Contract => None,
+            View::Watchlist => self.watchlist_entries.select_visible(row),
+            View::Bookmarks => self.bookmarks.select_visible(row),
+            View::Stats | View::Endpoints => None,
+            View::Sql => self.sql_console.rows.select_visible(row),
+            View::Txpool => self.txpool_view.select_visible(row),
+            View::Selectors => self.selector_stats.select_visible(row),
+        };
+
+        if already_selected == Some(true) {
+            self.on_enter(db);
+        }
+    }
+
+    pub fn on_up(&mut self, db: &Database) {
+        match self.view {
+            View::Default => {
+                if self.block_headers.state.selected() == Some(0) {
+                    self.page_older_headers(db);
+                }
+                self.block_headers.previous()
+            }
+            View::Block => self.transactions.rows.previous(),
+            View::Transaction => self.calldata.previous_word(),
+            View::Filter => self.filter_results.previous(),
+            View::Logs if self.log_data_focused => {
+                self.log_data.previous_word()
+            }
+            View::Logs => {
+                self.log_results.previous();
+                self.log_data = HexView::default();
+            }
+            View::Template => self.template_transactions.previous(),
+            View::Contract if self.code_pane == CodePane::Disassembly => {
+                self.instructions.previous()
+            }
+            View::Contract if self.code_pane == CodePane::Source => {
+                self.source_view.previous()
+            }
+            View::Contract => self.bytecode.previous_word(),
+            View::Watchlist => self.watchlist_entries.previous(),
+            View::Bookmarks => self.bookmarks.previous(),
+            View::Stats | View::Endpoints => {}
+            View::Sql => self.sql_console.rows.previous(),
+            View::Txpool => self.txpool_view.previous(),
+            View::Selectors => self.selector_stats.previous(),
+        }
+    }
+
+    pub fn on_down(&mut self, db: &Database) {
+        match self.view {
+            View::Default => {
+                if self.block_headers.state.selected()
+                    == self.block_headers.items.len().checked_sub(1)
+                {
+                    self.page_newer_headers(db);
+                }
+                self.block_headers.next()
+            }
+            View::Block => self.transactions.rows.next(),
+            View::Transaction => {
+                self.calldata.next_word(self.hex_bytes().len())
+            }
+            View::Filter => self.filter_results.next(),
+            View::Logs if self.log_data_focused => {
+                self.log_data.next_word(self.selected_log_data().len())
+            }
+            View::Logs => {
+                self.log_results.next();
+                self.log_data = HexView::default();
+            }
+            View::Template => self.template_transactions.next(),
+            View::Contract if self.code_pane == CodePane::Disassembly => {
+                self.instructions.next()
+            }
+            View::Contract if self.code_pane == CodePane::Source => {
+                self.source_view.next()
+            }
+            View::Contract => {
+                self.bytecode.next_word(self.contract_code().len())
+            }
+            View::Watchlist => self.watchlist_entries.next(),
+            View::Bookmarks => self.bookmarks.next(),
+            View::Stats | View::Endpoints => {}
+            View::Sql => self.sql_console.rows.next(),
+            View::Txpool => self.txpool_view.next(),
+            View::Selectors => self.selector_stats.next(),
+        }
+    }
+
+    /// Number of items that fit in the most recently drawn list
+    fn page_size(&self) -> usize {
+        /* skip the list's borders */
+        (self.list_area.height.saturating_sub(2) as usize).max(1)
+    }
+
+    pub fn on_page_up(&mut self, db: &Database) {
+        let page = self.page_size();
+        match self.view {
+            View::Default => {
+                if self
+                    .block_headers
+                    .state
+                    .selected()
+                    .is_some_and(|i| i < page)
+                {
+                    self.page_older_headers(db);
+                }
+                self.block_headers.page_up(page)
+            }
+            View::Block => self.transactions.rows.page_up(page),
+            View::Transaction => self.calldata.page_up(page),
+            View::Filter => self.filter_results.page_up(page),
+            View::Logs if self.log_data_focused => {
+                self.log_data.page_up(MAX_LOG_DATA_ROWS)
+            }
+            View::Logs => {
+                self.log_results.page_up(page);
+                self.log_data = HexView::default();
+            }
+            View::Template => self.template_transactions.page_up(page),
+            View::Contract if self.code_pane == CodePane::Disassembly => {
+                self.instructions.page_up(page)
+            }
+            View::Contract if self.code_pane == CodePane::Source => {
+                self.source_view.page_up(page)
+            }
+            View::Contract => self.bytecode.page_up(page),
+            View::Watchlist => self.watchlist_entries.page_up(page),
+            View::Bookmarks => self.bookmarks.page_up(page),
+            View::Stats | View::Endpoints => {}
+            View::Sql => self.sql_console.rows.page_up(page),
+            View::Txpool => self.txpool_view.page_up(page),
+            View::Selectors => self.selector_stats.page_up(page),
+        }
+    }
+
+    pub fn on_page_down(&mut self, db: &Database) {
+        let page = self.page_size();
+        match self.view {
+            View::Default => {
+                if self
+                    .block_headers
+                    .state
+                    .selected()
+                    .is_some_and(|i| i + page >= self.block_headers.items.len())
+                {
+                    self.page_newer_headers(db);
+                }
+                self.block_headers.page_down(page)
+            }
+            View::Block => self.transactions.rows.page_down(page),
+            View::Transaction => {
+                self.calldata.page_down(self.hex_bytes().len(), page)
+            }
+            View::Filter => self.filter_results.page_down(page),
+            View::Logs if self.log_data_focused => self
+                .log_data
+                .page_down(self.selected_log_data().len(), MAX_LOG_DATA_ROWS),
+            View::Logs => {
+                self.log_results.page_down(page);
+                self.log_data = HexView::default();
+            }
+            View::Template => self.template_transactions.page_down(page),
+            View::Contract if self.code_pane == CodePane::Disassembly => {
+                self.instructions.page_down(page)
+            }
+            View::Contract if self.code_pane == CodePane::Source => {
+                self.source_view.page_down(page)
+            }
+            View::Contract => {
+                self.bytecode.page_down(self.contract_code().len(), page)
+            }
+            View::Watchlist => self.watchlist_entries.page_down(page),
+            View::Bookmarks => self.bookmarks.page_down(page),
+            View::Stats | View::Endpoints => {}
+            View::Sql => self.sql_console.rows.page_down(page),
+            View::Txpool => self.txpool_view.page_down(page),
+            View::Selectors => self.selector_stats.page_down(page),
+        }
+    }
+
+    pub fn on_first(&mut self) {
+        match self.view {
+            View::Default => self.block_headers.first(),
+            View::Block => self.transactions.rows.first(),
+            View::Transaction => self.calldata.first(self.hex_bytes().len()),
+            View::Filter => self.filter_results.first(),
+            View::Logs if self.log_data_focused => {
+                self.log_data.first(self.selected_log_data().len())
+            }
+            View::Logs => {
+                self.log_results.first();
+                self.log_data = HexView::default();
+            }
+            View::Template => self.template_transactions.first(),
+            View::Contract if self.code_pane == CodePane::Disassembly => {
+                self.instructions.first()
+            }
+            View::Contract if self.code_pane == CodePane::Source => {
+                self.source_view.first()
+            }
+            View::Contract => self.bytecode.first(self.contract_code().len()),
+            View::Watchlist => self.watchlist_entries.first(),
+            View::Bookmarks => self.bookmarks.first(),
+            View::Stats | View::Endpoints => {}
+            View::Sql => self.sql_console.rows.first(),
+            View::Txpool => self.txpool_view.first(),
+            View::Selectors => self.selector_stats.first(),
+        }
+    }
+
+    pub fn on_last(&mut self, db: &Database) {
+        match self.view {
+            View::Default => {
+                if !self.block_headers_at_head {
+                    self.page_to_head(db);
+                }
+                self.block_headers.last()
+            }
+            View::Block => self.transactions.rows.last(),
+            View::Transaction => self.calldata.last(self.hex_bytes().len()),
+            View::Filter => self.filter_results.last(),
+            View::Logs if self.log_data_focused => {
+                self.log_data.last(self.selected_log_data().len())
+            }
+            View::Logs => {
+                self.log_results.last();
+                self.log_data = HexView::default();
+            }
+            View::Template => self.template_transactions.last(),
+            View::Contract if self.code_pane == CodePane::Disassembly => {
+                self.instructions.last()
+            }
+            View::Contract if self.code_pane == CodePane::Source => {
+                self.source_view.last()
+            }
+            View::Contract => self.bytecode.last(self.contract_code().len()),
+            View::Watchlist => self.watchlist_entries.last(),
+            View::Bookmarks => self.bookmarks.last(),
+            View::Stats | View::Endpoints => {}
+            View::Sql => self.sql_console.rows.last(),
+            View::Txpool => self.txpool_view.last(),
+            View::Selectors => self.selector_stats.last(),
+        }
+    }
+
+    /// Refresh everything shown from the database and the background
+    /// services, returning whether any new chain data was found
+    pub fn on_tick(&mut self, db: &Database) -> bool {
+        if let Some(tracker) = &self.tracker {
+            for (hash, status) in tracker.statuses() {
+                if self.tracked.insert(hash, status) != Some(status) {
+                    self.status_message =
+                        Some(format!("Transaction {hash} is now {status}"));
+                }
+            }
+
+            for (hash, broadcast) in tracker.broadcasts() {
+                if self.broadcasts.get(&hash) == Some(&broadcast) {
+                    continue;
+                }
+
+                match &broadcast {
+                    Broadcast::Sent => {}
+                    Broadcast::Rejected(reason) => {
+                        self.status_message = Some(format!(
+                            "Transaction {hash} was rejected: {reason}"
+                        ))
+                    }
+                    Broadcast::Included(receipt) => {
+                        self.status_message = Some(format!(
+                            "Transaction {hash} {} using {} gas ({})",
+                            if receipt.success {
+                                "succeeded"
+                            } else {
+                                "reverted"
+                            },
+                            receipt.gas_used,
+                            self.tracked
+                                .get(&hash)
+                                .copied()
+                                .unwrap_or_default()
+                        ))
+                    }
+                }
+                self.broadcasts.insert(hash, broadcast);
+            }
+        }
+
+        self.dev_chain =
+            self.devchain.as_ref().and_then(|devchain| devchain.info());
+        self.trace = match (&self.tracer, self.view) {
+            (Some(tracer), View::Transaction) => {
+                self.selected_transaction_hash().and_then(|hash| {
+                    tracer
+                        .trace(hash)
+                        .inspect_err(|e| {
+                            self.status_message = Some(e.to_string())
+                        })
+                        .ok()
+                        .map(|traced| (hash, traced))
+                })
+            }
+            _ => None,
+        };
+        self.simulation =
+            match (&self.simulator, self.view, &self.selected_transaction) {
+                (Some(simulator), View::Transaction, Some(tx))
+                    if self.simulated == Some(*tx.inner.tx_hash()) =>
+                {
+                    simulator
+                        .simulate(tx)
+                        .inspect_err(|e| {
+                            self.status_message = Some(e.to_string())
+                        })
+                        .ok()
+                        .map(|simulated| (*tx.inner.tx_hash(), simulated))
+                }
+                _ => None,
+            };
+        self.raw_transaction = match (&self.rawtx, self.view) {
+            (Some(rawtx), View::Transaction) if self.is_raw_shown() => {
+                self.selected_transaction_hash().and_then(|hash| {
+                    rawtx
+                        .raw(hash)
+                        .inspect_err(|e| {
+                            self.status_message = Some(e.to_string())
+                        })
+                        .ok()
+                        .map(|fetched| (hash, fetched))
+                })
+            }
+            _ => None,
+        };
+        self.blobs =
+            match (&self.blob_service, self.view, &self.selected_transaction) {
+                (Some(service), View::Transaction, Some(tx))
+                    if self.open_blob_index().is_some() =>
+                {
+                    let hash = *tx.inner.tx_hash();
+                    service
+                        .blobs(BlobRequest {
+                            hash,
+                            versioned_hashes: tx
+                                .blob_versioned_hashes()
+                                .unwrap_or_default()
+                                .to_vec(),
+                            timestamp: self.selected_block.header.timestamp,
+                        })
+                        .inspect_err(|e| {
+                            self.status_message = Some(e.to_string())
+                        })
+                        .ok()
+                        .map(|blobs| (hash, blobs))
+                }
+                _ => None,
+            };
+
+        /* the recipients of opened transactions and the emitters of selected
+         * logs are looked up too, so that their ABIs can be used to decode
+         * calldata and logs */
+        let contract_address = match (self.view, &self.filter) {
+            (View::Contract, _) => self.contract_address,
+            (View::Transaction, _) => {
+                self.selected_transaction.as_ref().and_then(|tx| tx.to())
+            }
+            (View::Logs, _) => {
+                self.get_selected_log_result().map(|log| log.address())
+            }
+            (View::Filter, Some(TransactionFilter::Account(address))) => {
+                Some(*address)
+            }
+            _ => None,
+        };
+        self.contract = match (&self.contract_service, contract_address) {
+            (Some(service), Some(address)) => service
+                .contract(address)
+                .inspect_err(|e| self.status_message = Some(e.to_string()))
+                .ok()
+                .map(|contract| (address, contract)),
+            _ => None,
+        };
+
+        if let (Some(service), Some(address), View::Contract) =
+            (&self.contract_service, self.contract_address, self.view)
+        {
+            for (slot, value) in self.storage_slots.iter_mut() {
+                if let Ok(Some(latest)) = service.storage(address, *slot) {
+                    *value = latest;
+                }
+            }
+
+            let code = self.contract_code();
+            if self.instructions.items.is_empty() && !code.is_empty() {
+                self.instructions =
+                    StatefulList::with_items(evm::disassemble(&code));
+            }
+
+            if let Some((_, Fetched::Known(contract))) = &self.contract {
+                if let Verification::Verified(source) = &contract.source {
+                    if self.source_view.is_empty() {
+                        self.source_view = SourceView::new(
+                            &source.sources,
+                            &source.language,
+                            &self.theme,
+                        );
+                    }
+                }
+            }
+        }
+
+        /* whatever was shown of the previous chain is gone once the indexer
+         * has dealt with the chain being reset */
+        if let Some(genesis) =
+            self.indexer.as_ref().and_then(|indexer| indexer.genesis())
+        {
+            if self.genesis.is_some_and(|shown| shown != genesis) {
+                self.forget_chain();
+            }
+            self.genesis = Some(genesis);
+        }
+
+        /* everything below only changes as blocks are indexed (or as the
+         * selection changes), so spare the database otherwise (unless reading
+         * it failed last time, in which case it's all read again) */
+        let retry = self.db_error.take().is_some();
+        let indexed = self.chain_updated();
+        /* the first block isn't announced by the indexer, as it's retrieved
+         * separately */
+        let started = self.started();
+        let updated = indexed || retry || started;
+
+        if updated {
+            self.on_chain_update(db);
+        }
+
+        if started && self.selected_block.header.hash.is_zero() {
+            if let Some(Some(latest)) = self.tick_query(|| db.latest_block()) {
+                self.selected_block = latest;
+            }
+        }
+
+        if let Some(opening) = self.opening {
+            self.open_on_startup(db, opening);
+        }
+
+        if let Some(selected_hash) =
+            self.get_selected_header().map(|header| header.hash)
+        {
+            if matches!(self.view, View::Default)
+                && (updated || selected_hash != self.selected_block.header.hash)
+            {
+                if let Some(Some(selected_block)) =
+                    self.tick_query(|| db.block_by_hash(selected_hash))
+                {
+                    self.selected_block = selected_block;
+                    self.transactions.set_transactions(
+                        self.selected_block
+                            .transactions
+                            .clone()
+                            .into_transactions()
+                            .collect(),
+                    );
+                }
+            }
+        }
+
+        let block_hash = self.selected_block.header.hash;
+        let reselected = self.ticked_block != Some(block_hash);
+        self.ticked_block = Some(block_hash);
+
+        if updated || reselected {
+            let number = self.selected_block.header.number;
+            if let Some(siblings) =
+                self.tick_query(|| db.headers().all_by_number(number))
+            {
+                self.fork_siblings = siblings;
+            }
+        }
+
+        /* the beacon chain is followed separately from the indexer, so keep
+         * checking until the block turns up there */
+        if matches!(self.view, View::Block)
+            && self.beacon_block.map(|block| block.block_hash)
+                != Some(block_hash)
+        {
+            if let Some(beacon_block) =
+                self.tick_query(|| db.beacon_block(block_hash))
+            {
+                self.beacon_block = beacon_block;
+            }
+        }
+
+        /* rollup data is written just before the block is announced */
+        if updated || reselected {
+            if let Some(l2_block) = self.tick_query(|| db.l2_block(block_hash))
+            {
+                self.l2_block = Some(l2_block);
+            }
+        }
+
+        /* statuses are indexed after their block, so keep checking until
+         * every transaction has one */
+        if (updated || reselected)
+            && (self
+                .receipt_statuses
+                .values()
+                .any(|status| status.block_hash != block_hash)
+                || self.receipt_statuses.len()
+                    < self.selected_block.transactions.len())
+        {
+            if let Some(statuses) = self
+                .tick_query(|| db.receipts().statuses_by_block_hash(block_hash))
+            {
+                self.receipt_statuses = statuses
+                    .into_iter()
+                    .map(|status| (status.transaction_hash, status))
+                    .collect();
+            }
+            /* fees are computed from the receipts as they're indexed */
+            if let Some(fees) =
+                self.tick_query(|| db.receipts().block_fees(block_hash))
+            {
+                self.block_fees = fees;
+            }
+        }
+
+        if let (View::Txpool, Some(txpool)) = (self.view, &self.txpool) {
+            /* the pool changes most as blocks arrive */
+            if updated {
+                if let Err(e) = txpool.refresh(self.txpool_view.sender) {
+                    self.status_message = Some(e.to_string());
+                }
+            }
+            if let Some(fetched) = txpool.txpool() {
+                self.txpool_view.show(fetched);
+            }
+        }
+
+        if let (true, View::Filter, Some(TransactionFilter::Account(address))) =
+            (updated, self.view, &self.filter)
+        {
+            if let Err(e) = self.load_account_activity(db, *address) {
+                self.status_message = Some(e.to_string());
+            }
+        }
+
+        if matches!(self.view, View::Selectors) && updated {
+            if let Err(e) = self.load_selector_stats(db) {
+                self.status_message = Some(e.to_string());
+            }
+        }
+
+        if matches!(self.view, View::Endpoints) {
+            if let Err(e) = self.load_endpoints() {
+                self.status_message = Some(e.to_string());
+            }
+        }
+
+        if matches!(self.view, View::Stats) && updated {
+            if let Err(e) = self.load_stats(db) {
+                self.status_message = Some(e.to_string());
+            }
+        }
+
+        /* balances and nonces are recorded after their block is indexed, so
+         * keep checking until every watched address is up to date */
+        if matches!(self.view, View::Watchlist)
+            && (updated || self.watchlist_lags())
+        {
+            self.load_watchlist(db);
+        }
+
+        if matches!(self.view, View::Block | View::Template)
+            && self.template_diff.as_ref().map(|diff| diff.block_hash)
+                != Some(self.selected_block.header.hash)
+        {
+            let _ = self.load_template_diff(db);
+        }
+
+        /* the transaction view keeps showing whichever transaction was
+         * opened, while elsewhere it follows the selection (of which there's
+         * none in an empty block) */
+        if !matches!(self.view, View::Transaction) {
+            self.selected_transaction =
+                self.get_selected_transaction().cloned();
+        }
+
+        indexed
+    }
+
+    /// Whether the first block has been retrieved (or failed to be) since
+    /// this was last called
+    fn started(&mut self) -> bool {
+        let startup = match &self.startup {
+            Some(startup) => startup
+                .read()
+                .map(|startup| startup.clone())
+                .unwrap_or_else(|e| e.into_inner().clone()),
+            None => return false,
+        };
+
+        match startup {
+            Startup::Connecting => return false,
+            Startup::Failed(e) => self.startup_error = Some(e),
+            Startup::Done => {}
+        }
+        self.startup = None;
+        true
+    }
+
+    /// Open the given block or transaction (which isn't indexed) once it's
+    /// been retrieved from the RPC node
+    fn retrieve(&mut self, opening: Opening) -> eyre::Result<()> {
+        let Some(retrieval) = &self.retrieval else {
+            return Err(eyre::eyre!("{opening} is not indexed"));
+        };
+        if let Fetched::Unavailable(e) = retrieval.retrieve(opening.into())? {
+            return Err(eyre::eyre!(
+                "{opening} is not indexed and couldn't be retrieved ({e})"
+            ));
+        }
+        self.status_message =
+            Some(format!("{opening} is not indexed, so retrieving it..."));
+        self.opening = Some(opening);
+        Ok(())
+    }
+
+    /// Open the block or transaction asked to be opened on startup (or else
+    /// retrieved on demand), once it's been retrieved
+    fn open_on_startup(&mut self, db: &Database, opening: Opening) {
+        let opened = match opening {
+            Opening::Block(id) => self
+                .timed_query(|| match id {
+                    HashOrNumber::Hash(hash) => db.block_by_hash(hash),
+                    HashOrNumber::Number(number) => db.block_by_number(number),
+                })
+                .map_err(eyre::Report::from)
+                .map(|block| {
+                    block.map(|block| {
+                        self.remember();
+                        self.open_block(block);
+                    })
+                }),
+            Opening::Transaction(hash) => self
+                .timed_query(|| db.transactions().by_hash(hash))
+                .map_err(eyre::Report::from)
+                .and_then(|tx| {
+                    tx.map(|tx| self.open_transaction(db, tx)).transpose()
+                }),
+        };
+
+        match opened {
+            Ok(Some(())) => self.opening = None,
+            /* it might yet be retrieved */
+            Ok(None) if self.startup.is_some() => {}
+            Ok(None) => match self
+                .retrieval
+                .as_ref()
+                .map(|retrieval| retrieval.retrieve(opening.into()))
+            {
+                Some(Ok(Fetched::Pending)) => {}
+                Some(Ok(Fetched::Unavailable(e))) => {
+                    self.opening = None;
+                    self.status_message = Some(format!(
+                        "{opening} is not indexed and couldn't be retrieved \
+                        ({e})"
+                    ));
+                }
+                Some(Err(e)) => {
+                    self.opening = None;
+                    self.status_message = Some(e.to_string());
+                }
+                Some(Ok(Fetched::Known(()))) | None => {
+                    self.opening = None;
+                    self.status_message =
+                        Some(format!("{opening} is not indexed"));
+                }
+            },
+            Err(e) => {
+                self.opening = None;
+                self.status_message = Some(e.to_string());
+            }
+        }
+    }
+
+    /// Whether any watched address hasn't been recorded as of the latest
+    /// block
+    fn watchlist_lags(&self) -> bool {
+        let Some(head) = self.block_headers.items.last() else {
+            return false;
+        };
+
+        self.watchlist_entries.items.iter().any(|address| {
+            self.account_snapshots
+                .get(address)
+                .and_then(|snapshots| snapshots.last())
+                .is_none_or(|snapshot| snapshot.block_number < head.number)
+        })
+    }
+
+    /// Page older block headers into the blocks list from the database,
+    /// returning whether there were any
+    fn page_older_headers(&mut self, db: &Database) -> bool {
+        let first = match self.block_headers.items.first() {
+            Some(header) => header.hash,
+            None => return false,
+        };
+        let older = match self
+            .timed_query(|| db.headers().before(first, BLOCK_HEADERS_PAGE))
+        {
+            Ok(older) if !older.is_empty() => older,
+            _ => return false,
+        };
+
+        self.load_transaction_counts(db, &older);
+        self.block_headers.prepend(older);
+        self.trim_block_headers();
+        true
+    }
+
+    /// Page newer block headers into the blocks list from the database (if
+    /// any were paged out), returning whether there were any
+    fn page_newer_headers(&mut self, db: &Database) -> bool {
+        let last = match self.block_headers.items.last() {
+            Some(header) if !self.block_headers_at_head => header.hash,
+            _ => return false,
+        };
+        let newer = match self
+            .timed_query(|| db.headers().after(last, BLOCK_HEADERS_PAGE))
+        {
+            Ok(newer) => newer,
+            Err(_) => return false,
+        };
+
+        if newer.len() < BLOCK_HEADERS_PAGE {
+            self.block_headers_at_head = true;
+        }
+
+        if newer.is_empty() {
+            return false;
+        }
+
+        self.load_transaction_counts(db, &newer);
+        self.block_headers.items.extend(newer);
+        self.trim_block_headers();
+        true
+    }
+
+    /// Add the headers indexed since the blocks list was last at the head of
+    /// the chain, up to the given latest one
+    ///
+    /// Several blocks may well have been indexed since the last tick (e.g.,
+    /// whilst catching up), so only a direct child of the last header listed
+    /// is appended on its own. Failing that, the headers in between are
+    /// loaded too, or the whole list is replaced if there are more of them
+    /// than fit in a page.
+    fn append_newer_headers(&mut self, db: &Database, latest: &Header) {
+        let Some(last) = self.block_headers.items.last().map(|last| last.hash)
+        else {
+            return self.page_to_head(db);
+        };
+
+        if latest.parent_hash == last {
+            self.block_headers.items.push(latest.clone());
+            return;
+        }
+
+        match self.tick_query(|| db.headers().after(last, BLOCK_HEADERS_PAGE)) {
+            Some(newer) if newer.last() == Some(latest) => {
+                self.load_transaction_counts(db, &newer);
+                self.block_headers.items.extend(newer);
+            }
+            _ => self.page_to_head(db),
+        }
+    }
+
+    /// Replace the blocks list with the most recent page of block headers
+    fn page_to_head(&mut self, db: &Database) {
+        let latest = match self.timed_query(|| db.headers().latest()) {
+            Ok(Some(latest)) => latest,
+            _ => return,
+        };
+        let mut headers = self
+            .timed_query(|| {
+                db.headers().before(latest.hash, BLOCK_HEADERS_PAGE - 1)
+            })
+            .unwrap_or_default();
+        headers.push(latest);
+
+        self.transaction_counts.clear();
+        self.load_transaction_counts(db, &headers);
+        self.block_headers = StatefulList::with_items(headers);
+        self.block_headers_at_head = true;
+    }
+
+    /// Keep the blocks list within [`BLOCK_HEADERS_WINDOW`] by dropping
+    /// headers from whichever end is furthest from the selection
+    fn trim_block_headers(&mut self) {
+        let len = self.block_headers.items.len();
+        let excess = len.saturating_sub(BLOCK_HEADERS_WINDOW);
+
+        if excess == 0 {
+            return;
+        }
+
+        let position = self
+            .block_headers
+            .state
+            .selected()
+            .unwrap_or(self.block_headers.state.offset());
+        if position >= len / 2 {
+            self.block_headers.drain_front(excess);
+        } else {
+            self.block_headers.truncate(BLOCK_HEADERS_WINDOW);
+            self.block_headers_at_head = false;
+        }
+
+        let hashes: HashSet<BlockHash> = self
+            .block_headers
+            .items
+            .iter()
+            .map(|header| header.hash)
+            .collect();
+        self.transaction_counts
+            .retain(|hash, _| hashes.contains(hash));
+    }
+
+    /// Retrieve the transaction count of each of the given blocks (for the
+    /// blocks list)
+    fn load_transaction_counts(&mut self, db: &Database, headers: &[Header]) {
+        for header in headers {
+            if let Ok(count) = self.timed_query(|| {
+                db.transactions().count_by_block_hash(header.hash)
+            }) {
+                self.transaction_counts.insert(header.hash, count);
+            }
+        }
+    }
+
+    /// Drop everything read from the data of a chain that's since been wiped
+    /// from the database, going back to the (empty) dashboard
+    fn forget_chain(&mut self) {
+        self.block_headers = StatefulList::with_items(vec![]);
+        self.block_headers_at_head = true;
+        self.transactions = TransactionList::default();
+        self.transaction_counts.clear();
+        self.selected_block = Default::default();
+        self.selected_transaction = None;
+        self.orphaned.clear();
+        self.fee_stats.clear();
+        self.history = History::default();
+        self.view = View::Default;
+    }
+
+    /// Whether the indexer has announced any changes since this was last
+    /// called (or, if there's no indexer to listen to, always)
+    fn chain_updated(&mut self) -> bool {
+        match &mut self.chain_events {
+            Some(events) => {
+                let updated = events.has_changed().unwrap_or(false);
+                events.mark_unchanged();
+                updated
+            }
+            None => true,
+        }
+    }
+
+    /// Re-read everything derived from the indexed chain data as a whole
+    fn on_chain_update(&mut self, db: &Database) {
+        self.db_size = self.tick_query(|| db.size());
+        if let Some(finality) = self.tick_query(|| db.finality()) {
+            self.finality = finality;
+        }
+        if let Some(orphaned) = self.tick_query(|| {
+            db.headers().recent_orphaned_hashes(ORPHANED_BLOCKS_WINDOW)
+        }) {
+            self.orphaned = orphaned;
+        }
+
+        let Some(Some(latest_header)) =
+            self.tick_query(|| db.headers().latest())
+        else {
+            return;
+        };
+
+        if !self.block_headers.items.contains(&latest_header) {
+            if self.block_headers_at_head {
+                self.append_newer_headers(db, &latest_header);
+
+                if self.follow {
+                    self.block_headers.last();
+                }
+
+                self.trim_block_headers();
+            }
+
+            if let Some(fee_stats) = self
+                .tick_query(|| db.headers().recent_fee_stats(FEE_STATS_WINDOW))
+            {
+                self.fee_stats = fee_stats;
+            }
+
+            if let Some(blob_base_fees) = self.tick_query(|| {
+                db.headers().recent_blob_base_fees(FEE_STATS_WINDOW)
+            }) {
+                self.blob_base_fees = blob_base_fees;
+            }
+        }
+
+        if !self.transaction_counts.contains_key(&latest_header.hash) {
+            if let Some(count) = self.tick_query(|| {
+                db.transactions().count_by_block_hash(latest_header.hash)
+            }) {
+                self.transaction_counts.insert(latest_header.hash, count);
+            }
+        }
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame) {
+        let mut app_box = Block::bordered()
+            .title(Line::from(self.title.clone()).centered())
+            .title_bottom(self.status_bar())
+            .border_style(self.theme.border);
+
+        /* reorgs of a single block are routine, so only deeper ones are
+         * worth shouting about */
+        if let Some(reorg) = self
+            .indexer
+            .as_ref()
+            .and_then(|indexer| indexer.last_reorg())
+            .filter(|reorg| {
+                reorg.depth > 1
+                    && reorg.observed_at.elapsed() < REORG_BANNER_DURATION
+            })
+        {
+            app_box = app_box.title(
+                Line::from(format!(
+                    " Reorg: {} blocks orphaned at #{} ",
+                    reorg.depth, reorg.number
+                ))
+                .style(Style::new().fg(self.theme.error).bold().reversed())
+                .right_aligned(),
+            );
+        }
+
+        if let Some(reset) = self
+            .indexer
+            .as_ref()
+            .and_then(|indexer| indexer.pending_reset())
+        {
+            app_box = app_box.title(
+                Line::from(format!(
+                    " Chain reset (genesis {:.10}...): :wipe or :archive to \
+                    resume indexing{} ",
+                    reset.current.to_string(),
+                    reset
+                        .failure
+                        .map(|failure| format!(" ({failure})"))
+                        .unwrap_or_default()
+                ))
+                .style(Style::new().fg(self.theme.error).bold().reversed())
+                .right_aligned(),
+            );
+        }
+
+        frame.render_widget(app_box.clone(), frame.area());
+
+        match self.view {
+            View::Default => {
+                let chunks =
+                    Layout::vertical([Constraint::Min(20), Constraint::Min(0)])
+                        .split(frame.area());
+                let top = Layout::horizontal([
+                    Constraint::Min(0),
+                    Constraint::Length(FEE_PANEL_WIDTH),
+                    Constraint::Length(if self.dev_chain.is_some() {
+                        DEV_CHAIN_PANEL_WIDTH
+                    } else {
+                        0
+                    }),
+                ])
+                .margin(1)
+                .split(chunks[0]);
+                self.draw_latest_blocks_list(frame, chunks[1]);
+                let chart_block = Block::new().title(self.dashboard_title());
+                match self.dashboard_chart {
+                    DashboardChart::GasUsed => {
+                        self.draw_gas_barchart(frame, top[0], chart_block)
+                    }
+                    DashboardChart::BaseFee => self.draw_sparkline(
+                        frame,
+                        top[0],
+                        chart_block,
+                        |header| header.base_fee_per_gas.unwrap_or_default(),
+                    ),
+                    DashboardChart::BlobGasUsed => self.draw_sparkline(
+                        frame,
+                        top[0],
+                        chart_block,
+                        |header| header.blob_gas_used.unwrap_or_default(),
+                    ),
+                }
+                self.draw_fee_panel(frame, top[1]);
+                if let Some(dev_chain) = &self.dev_chain {
+                    self.draw_dev_chain_panel(dev_chain, frame, top[2]);
+                }
+            }
+            View::Block => {
+                let chunks = Layout::vertical([
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                ])
+                .margin(1)
+                .split(frame.area());
+                self.draw_block_view(frame, chunks[1]);
+            }
+            View::Transaction => {
+                let chunks = Layout::vertical([
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                ])
+                .margin(1)
+                .split(frame.area());
+                self.draw_transaction_view(frame, chunks[1]);
+            }
+            View::Filter => {
+                let chunks = Layout::vertical([
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                ])
+                .margin(1)
+                .split(frame.area());
+                match &self.filter {
+                    Some(TransactionFilter::Account(_)) => {
+                        let account = Layout::vertical([
+                            Constraint::Length(8),
+                            Constraint::Min(0),
+                        ])
+                        .split(chunks[1]);
+                        self.draw_account_activity(frame, account[0]);
+                        self.draw_filter_results_list(frame, account[1]);
+                    }
+                    _ => self.draw_filter_results_list(frame, chunks[1]),
+                }
+            }
+            View::Logs => {
+                let chunks = Layout::vertical([
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                ])
+                .margin(1)
+                .split(frame.area());
+                self.draw_log_results_list(frame, chunks[1]);
+            }
+            View::Template => {
+                let chunks = Layout::vertical([
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                ])
+                .margin(1)
+                .split(frame.area());
+                self.draw_template_list(frame, chunks[1]);
+            }
+            View::Contract => {
+                let chunks = Layout::vertical([
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                ])
+                .margin(1)
+                .split(frame.area());
+                self.draw_contract_view(frame, chunks[1]);
+            }
+            View::Watchlist => {
+                let chunks = Layout::vertical([
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                ])
+                .margin(1)
+                .split(frame.area());
+                self.draw_watchlist(frame, chunks[1]);
+            }
+            View::Bookmarks => {
+                let chunks = Layout::vertical([
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                ])
+                .margin(1)
+                .split(frame.area());
+                self.draw_bookmarks(frame, chunks[1]);
+            }
+            View::Stats => {
+                let chunks = Layout::vertical([
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                ])
+                .margin(1)
+                .split(frame.area());
+                self.draw_stats(frame, chunks[1]);
+            }
+            View::Sql => {
+                let chunks = Layout::vertical([
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                ])
+                .margin(1)
+                .split(frame.area());
+                /* the query and column headings sit above the rows */
+                let area = chunks[1];
+                self.list_area = Rect::new(
+                    area.x,
+                    area.y + 2,
+                    area.width,
+                    area.height.saturating_sub(2),
+                );
+                self.sql_console.draw(frame, chunks[1], &self.theme);
+            }
+            View::Txpool => {
+                let chunks = Layout::vertical([
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                ])
+                .margin(1)
+                .split(frame.area());
+                self.list_area = chunks[1];
+                self.txpool_view.draw(
+                    frame,
+                    chunks[1],
+                    &self.theme,
+                    self.address_display_mode,
+                );
+            }
+            View::Selectors => {
+                let chunks = Layout::vertical([
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                ])
+                .margin(1)
+                .split(frame.area());
+                self.draw_selectors(frame, chunks[1]);
+            }
+            View::Endpoints => {
+                let chunks = Layout::vertical([
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                ])
+                .margin(1)
+                .split(frame.area());
+                let chunks = Layout::vertical([
+                    Constraint::Length(
+                        self.endpoint_races.len().max(1) as u16 + 3,
+                    ),
+                    Constraint::Min(0),
+                ])
+                .split(chunks[1]);
+                self.draw_endpoints(frame, chunks[0]);
+                self.draw_rpc_health(frame, chunks[1]);
+            }
+        }
+
+        let area = frame.area();
+        let bottom_line = Rect::new(
+            area.x + 1,
+            area.bottom().saturating_sub(2),
+            area.width.saturating_sub(2),
+            1,
+        );
+
+        if let Some(preimage) = &self.preimage {
+            self.draw_preimage(preimage, frame, area);
+        }
+
+        if let Some((_, prompt)) = &self.prompt {
+            prompt.draw(frame, bottom_line, &self.theme);
+        } else if let Some(message) = &self.status_message {
+            frame.render_widget(Clear, bottom_line);
+            frame.render_widget(
+                Paragraph::new(Line::from(Span::styled(
+                    message.clone(),
+                    Style::new().italic(),
+                ))),
+                bottom_line,
+            );
+        }
+    }
+
+    /// Draw the Keccak pane (just above the bottom border of the app)
+    fn draw_preimage(
+        &self,
+        preimage: &Preimage,
+        frame: &mut Frame,
+        area: Rect,
+    ) {
+        let fields = preimage.fields();
+        let height = (fields.len() as u16 + 2).min(area.height);
+        let pane = Rect::new(
+            area.x + 1,
+            area.bottom().saturating_sub(height + 2),
+            area.width.saturating_sub(2),
+            height,
+        );
+        let lines: Vec<Line> = fields
+            .into_iter()
+            .map(|(label, value)| {
+                Line::from(vec![
+                    Span::styled(format!("{label:<14}"), Style::new().bold()),
+                    Span::raw(value),
+                ])
+            })
+            .collect();
+        frame.render_widget(Clear, pane);
+        frame.render_widget(
+            Paragraph::new(Text::from(lines)).block(
+                Block::bordered()
+                    .title(Line::from(" Keccak ").centered())
+                    .title_bottom(
+                        Line::from(" y: copy hash │ Esc: close ").centered(),
+                    )
+                    .border_style(self.theme.border),
+            ),
+            pane,
+        );
+    }
+
+    /// ID of the chain being indexed (once the indexer has connected to it)
+    fn chain_id(&self) -> Option<ChainId> {
+        self.indexer.as_ref().and_then(|indexer| indexer.chain_id())
+    }
+
+    /// Connection state, chain, sync lag, and database size, for the bottom
+    /// border of the app
+    fn status_bar(&self) -> Line<'static> {
+        let separator = Span::raw(" │ ");
+        let mut spans = vec![Span::raw(" ")];
+
+        if let Some(indexer) = &self.indexer {
+            spans.push(Span::raw(redacted(&indexer.endpoints.active())));
+            spans.push(separator.clone());
+            spans.push(Span::raw(match indexer.chain_id() {
+                Some(chain_id) => match Rollup::of(chain_id) {
+                    Some(rollup) => format!("chain {chain_id} ({rollup})"),
+                    None => format!("chain {chain_id}"),
+                },
+                None => "chain ?".to_string(),
+            }));
+            spans.push(separator.clone());
+            spans.push(if indexer.is_connected() {
+                Span::styled("live", Style::new().fg(self.theme.primary))
+            } else {
+                Span::styled(
+                    "offline (reconnecting...)",
+                    Style::new().fg(self.theme.error).bold(),
+                )
+            });
+            spans.push(separator.clone());
+        }
+
+        if let Some(latest) = self.block_headers.items.last() {
+            let lag = (Utc::now().timestamp() as u64)
+                .saturating_sub(latest.timestamp);
+            spans.push(Span::raw(format!("lag {}", format_lag(lag))));
+            spans.push(separator.clone());
+        }
+
+        spans.push(Span::raw(match self.db_size {
+            Some(size) => format!("db {}", format_bytes(size)),
+            None => "db ?".to_string(),
+        }));
+
+        if let Some(e) = &self.db_error {
+            spans.push(separator.clone());
+            spans.push(Span::styled(
+                format!("{e} (retrying...)"),
+                Style::new().fg(self.theme.error).bold(),
+            ));
+        }
+        spans.push(Span::raw(" "));
+        Line::from(spans).left_aligned()
+    }
+
+    /// Wrap a rendered transaction row, highlighting it if it involves a
+    /// watched address
+    fn transaction_list_item(
+        &self,
+        tx: &Transaction,
+        line: Line<'static>,
+    ) -> ListItem<'static> {
+        let watched =
+            self.watched_addresses.contains(&tx.as_recovered().signer())
+                || tx
+                    .to()
+                    .is_some_and(|to| self.watched_addresses.contains(&to));
+
+        if watched {
+            ListItem::new(line).style(Style::new().fg(self.theme.secondary))
+        } else {
+            ListItem::new(line)
+        }
+    }
+
+    fn draw_filter_results_list(&mut self, frame: &mut Frame, area: Rect) {
+        let transactions: Vec<ListItem> = self
+            .filter_results
+            .items
+            .iter()
+            .map(|tx| {
+                let mut line = transaction_line(tx, self.address_display_mode);
+                line.spans.insert(
+                    0,
+                    Span::styled(
+                        format!("{:<12}", tx.block_number.unwrap_or_default()),
+                        Style::new().italic(),
+                    ),
+                );
+                self.transaction_list_item(tx, line)
+            })
+            .collect();
+        let title = match &self.filter {
+            Some(TransactionFilter::Calldata(pattern)) => format!(
+                "Transactions matching {} ({})",
+                pattern,
+                self.filter_results.items.len()
+            ),
+            Some(TransactionFilter::Account(address)) => format!(
+                "Transactions involving {} ({}){}",
+                label_address(address, false, self.address_display_mode),
+                self.filter_results.items.len(),
+                match &self.contract {
+                    Some((fetched, Fetched::Known(contract)))
+                        if fetched == address && !contract.code.is_empty() =>
+                    {
+                        " (contract: press c to inspect)"
+                    }
+                    _ => "",
+                }
+            ),
+            None => "Transactions".to_string(),
+        };
+        let filter_results_list = List::new(transactions)
+            .block(
+                Block::bordered()
+                    .title(Line::from(title).centered())
+                    .border_style(self.theme.border),
+            )
+            .highlight_style(self.theme.highlight)
+            .highlight_symbol("> ");
+        self.list_area = area;
+        frame.render_stateful_widget(
+            filter_results_list,
+            area,
+            &mut self.filter_results.state,
+        );
+    }
+
+    /// Plot the number of transactions sent from or to the address whose
+    /// history is open, and the ether they moved, per block and per day
+    fn draw_account_activity(&self, frame: &mut Frame, area: Rect) {
+        let Some(activity) = &self.account_activity else {
+            return;
+        };
+        let columns = Layout::horizontal([
+            Constraint::Percentage(50),
+            Constraint::Percentage(50),
+        ])
+        .split(area);
+        let (number, timestamp) = activity.head;
+        let today = timestamp / SECONDS_PER_DAY * SECONDS_PER_DAY;
+
+        for (area, unit, sparse, latest, step, window) in [
+            (
+                columns[0],
+                "block",
+                &activity.by_block,
+                number,
+                1,
+                STATS_WINDOW,
+            ),
+            (
+                columns[1],
+                "day",
+                &activity.by_day,
+                today,
+                SECONDS_PER_DAY,
+                ACTIVITY_DAYS,
+            ),
+        ] {
+            let rows = Layout::vertical([Constraint::Fill(1); 2]).split(area);
+            /* one bucket per column (as far back as was read), with those in
+             * which the address was inactive filled in */
+            let width = (rows[0].width.saturating_sub(2) as usize).min(window);
+            let buckets: Vec<Activity> = (0..width as u64)
+                .rev()
+                .filter_map(|i| latest.checked_sub(i * step))
+                .map(|bucket| {
+                    sparse
+                        .iter()
+                        .find(|activity| activity.bucket == bucket)
+                        .copied()
+                        .unwrap_or(Activity {
+                            bucket,
+                            ..Default::default()
+                        })
+                })
+                .collect();
+            let transactions: Vec<u64> =
+                buckets.iter().map(|bucket| bucket.transactions).collect();
+            /* sparklines only take integers, so plot whole gwei */
+            let value: Vec<u64> = buckets
+                .iter()
+                .map(|bucket| {
+                    (bucket.value / U256::from(1_000_000_000u64))
+                        .saturating_to()
+                })
+                .collect();
+            let total_value: U256 =
+                buckets.iter().map(|bucket| bucket.value).sum();
+
+            for (area, title, total, data, colour) in [
+                (
+                    rows[0],
+                    format!("Transactions per {unit}"),
+                    format!("total: {}", transactions.iter().sum::<u64>()),
+                    &transactions,
+                    self.theme.primary,
+                ),
+                (
+                    rows[1],
+                    format!("Ether moved per {unit}"),
+                    format!("total: {:.6} ETH", to_ether(total_value)),
+                    &value,
+                    self.theme.tertiary,
+                ),
+            ] {
+                frame.render_widget(
+                    Sparkline::default()
+                        .block(
+                            Block::bordered()
+                                .title(Line::from(title).centered())
+                                .title_bottom(Line::from(total).right_aligned())
+                                .border_style(self.theme.border),
+                        )
+                        .data(data)
+                        .style(Style::new().fg(colour)),
+                    area,
+                );
+            }
+        }
+    }
+
+    fn draw_log_results_list(&mut self, frame: &mut Frame, area: Rect) {
+        let logs: Vec<ListItem> = self
+            .log_results
+            .items
+            .iter()
+            .map(|log| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:<12}", log.block_number.unwrap_or_default()),
+                        Style::new().italic(),
+                    ),
+                    Span::styled(
+                        format!("{:<6}", log.log_index.unwrap_or_default()),
+                        Style::new().bold(),
+                    ),
+                    Span::raw(format!(
+                        "{:<16}",
+                        utils::shorten_hash(
+                            &log.transaction_hash.unwrap_or_default()
+                        )
+                    )),
+                    Span::raw(format!(
+                        "{:<32}",
+                        label_address(
+                            &log.address(),
+                            true,
+                            self.address_display_mode
+                        )
+                    )),
+                    Span::raw(format!(
+                        "{:<44}",
+                        utils::label_log_event(
+                            &log.address(),
+                            &log.topic0().copied().unwrap_or_default(),
+                            true
+                        )
+                    )),
+                    Span::raw(format!(
+                        "{:<12}",
+                        format!("{} topics", log.topics().len())
+                    )),
+                    Span::raw(format!("({} bytes)", log.data().data.len())),
+                ]))
+            })
+            .collect();
+        let title = match &self.log_query {
+            Some(query) => format!(
+                "Events matching {} ({})",
+                query,
+                self.log_results.items.len()
+            ),
+            None => "Events".to_string(),
+        };
+        let data = self.selected_log_data();
+        let decoded = self.get_selected_log_result().and_then(|log| {
+            abi::decode_log(&log.address(), log.topics(), &log.data().data)
+        });
+        let chunks = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(
+                data.len().div_ceil(16).clamp(1, MAX_LOG_DATA_ROWS) as u16 + 2,
+            ),
+        ])
+        .split(area);
+        let log_results_list = List::new(logs)
+            .block(
+                Block::bordered()
+                    .title(Line::from(title).centered())
+                    .border_style(self.theme.border),
+            )
+            .highlight_style(self.theme.highlight)
+            .highlight_symbol("> ");
+        self.list_area = chunks[0];
+        frame.render_stateful_widget(
+            log_results_list,
+            chunks[0],
+            &mut self.log_results.state,
+        );
+        /* the data pane's border stands out while it has focus */
+        let border = if self.log_data_focused {
+            self.theme.primary
+        } else {
+            self.theme.border
+        };
+        self.log_data.draw(
+            &data,
+            frame,
+            chunks[1],
+            Block::bordered()
+                .title(
+                    Line::from(match decoded {
+                        Some(event) => format!(" Data: {event} "),
+                        None => " Data ".to_string(),
+                    })
+                    .centered(),
+                )
+                .border_style(border),
+            &self.theme,
+        );
+    }
+
+    fn draw_template_list(&mut self, frame: &mut Frame, area: Rect) {
+        let transactions: Vec<ListItem> = self
+            .template_transactions
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, (hash, status))| {
+                let (text, colour) = match status {
+                    TemplateTxStatus::Landed(position) => (
+                        format!("landed at position {position}"),
+                        self.theme.primary,
+                    ),
+                    TemplateTxStatus::Elsewhere(number) => (
+                        format!("included in block #{number}"),
+                        self.theme.secondary,
+                    ),
+                    TemplateTxStatus::NotIncluded => {
+                        ("not included".to_string(), self.theme.error)
+                    }
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{i:<6}"), Style::new().bold()),
+                    Span::raw(format!("{:<68}", hash)),
+                    Span::styled(text, Style::new().fg(colour)),
+                ]))
+            })
+            .collect();
+        let title = format!(
+            "Local template for block #{} ({} transactions)",
+            self.selected_block.header.number,
+            self.template_transactions.items.len()
+        );
+        let template_list = List::new(transactions)
+            .block(
+                Block::bordered()
+                    .title(Line::from(title).centered())
+                    .border_style(self.theme.border),
+            )
+            .highlight_style(self.theme.highlight)
+            .highlight_symbol("> ");
+        self.list_area = area;
+        frame.render_stateful_widget(
+            template_list,
+            area,
+            &mut self.template_transactions.state,
+        );
+    }
+
+    fn draw_transaction_view(&mut self, frame: &mut Frame, area: Rect) {
+        self.draw_transaction_header_text(frame, area);
+    }
+
+    fn draw_transaction_header_text(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(tx) = self.selected_transaction.clone() else {
+            frame.render_widget(
+                Paragraph::new(Span::styled(
+                    "No transaction selected (the block is empty)",
+                    Style::new().italic(),
+                )),
+                area,
+            );
+            return;
+        };
+        let timestamp = self.selected_block.header.timestamp;
+
+        let chunks =
+            Layout::vertical([Constraint::Percentage(20), Constraint::Min(0)])
+                .split(area);
+
+        let hash = *tx.inner.tx_hash();
+        let mut lines = vec![
+            Line::from(Span::styled(
+                format!("Transaction {hash}"),
+                Style::new().bold(),
+            )),
+            Line::from(vec![
+                Span::styled("Timestamp: ", Style::new().bold()),
+                Span::raw(format!(
+                    "{} ({})",
+                    Utc.timestamp_opt(timestamp as i64, 0)
+                        .single()
+                        .map_or(timestamp.to_string(), |time| time.to_string()),
+                    timeago::Formatter::new()
+                        .convert(utils::duration_since_timestamp(timestamp))
+                )),
+            ]),
+            Line::from(vec![
+                Span::styled("From: ", Style::new().bold()),
+                Span::raw(format!("{}", tx.as_recovered().signer())),
+            ]),
+            Line::from(vec![
+                Span::styled("To:   ", Style::new().bold()),
+                match tx.to() {
+                    Some(addr) => Span::raw(
+                        label_address(&addr, false, self.address_display_mode)
+                            .to_string(),
+                    ),
+                    None => Span::raw(format!(
+                        "{} (CREATE)",
+                        label_address(
+                            &Address::ZERO,
+                            false,
+                            self.address_display_mode
+                        )
+                    )),
+                },
+            ]),
+            Line::from(vec![
+                Span::styled("Value: ", Style::new().bold()),
+                Span::raw(format!("{} Ether", to_ether(tx.value()))),
+            ]),
+            Line::from(vec![
+                Span::styled("Input: ", Style::new().bold()),
+                Span::raw(format!("({} bytes)", tx.input().len())),
+            ]),
+        ];
+
+        if let Some(call) =
+            tx.to().and_then(|to| abi::decode_call(&to, tx.input()))
+        {
+            lines.push(Line::from(vec![
+                Span::styled("Call: ", Style::new().bold()),
+                Span::raw(call.to_string()),
+            ]));
+        }
+
+        if let Some(versioned_hashes) = tx
+            .blob_versioned_hashes()
+            .filter(|hashes| !hashes.is_empty())
+        {
+            lines.push(Line::from(vec![
+                Span::styled("Blobs: ", Style::new().bold()),
+                Span::raw(format!(
+                    "{} (press b to inspect)",
+                    versioned_hashes.len()
+                )),
+            ]));
+        }
+
+        if let Some(status) = self.receipt_statuses.get(&hash) {
+            lines.push(Line::from(vec![
+                Span::styled("Result: ", Style::new().bold()),
+                if status.success {
+                    Span::styled(
+                        format!("✓ Success ({} gas used)", status.gas_used),
+                        Style::new().fg(self.theme.primary),
+                    )
+                } else {
+                    Span::styled(
+                        format!(
+                            "✗ Reverted ({}) ({} gas used)",
+                            status
+                                .revert_reason
+                                .as_deref()
+                                .unwrap_or("no reason given"),
+                            status.gas_used
+                        ),
+                        Style::new().fg(self.theme.error).bold(),
+                    )
+                },
+            ]));
+        }
+
+        if let Some(status) = self.tracked.get(&hash) {
+            lines.push(Line::from(vec![
+                Span::styled("Status: ", Style::new().bold()),
+                Span::raw(status.to_string()),
+            ]));
+        }
+
+        let transaction_header_text = Paragraph::new(Text::from(lines));
+        frame.render_widget(transaction_header_text, chunks[0]);
+        let chunks = Layout::horizontal([
+            Constraint::Length(hex_view::WIDTH),
+            Constraint::Min(0),
+        ])
+        .split(chunks[1]);
+        /* page up and down move through the calldata a screen at a time */
+        self.list_area = chunks[0];
+        let title = match self.open_blob_index() {
+            _ if self.is_raw_shown() => match self
+                .raw_transaction
+                .as_ref()
+                .filter(|(raw_hash, _)| *raw_hash == hash)
+            {
+                Some((_, Fetched::Known(raw))) => format!(
+                    " Raw Transaction ({} bytes, keccak256 {}, y to copy) ",
+                    raw.len(),
+                    if keccak256(raw) == hash {
+                        "matches hash"
+                    } else {
+                        "doesn't match hash"
+                    }
+                ),
+                Some((_, Fetched::Unavailable(e))) => {
+                    format!(" Raw Transaction (unavailable: {e}) ")
+                }
+                _ => " Raw Transaction (retrieving...) ".to_string(),
+            },
+            Some(i) => format!(
+                " Blob {} of {} ",
+                i + 1,
+                tx.blob_versioned_hashes().map_or(0, |hashes| hashes.len())
+            ),
+            None => " Calldata ".to_string(),
+        };
+        let bytes = self.hex_bytes();
+        self.calldata.draw(
+            &bytes,
+            frame,
+            chunks[0],
+            Block::bordered()
+                .title(Line::from(title).centered())
+                .border_style(self.theme.border),
+            &self.theme,
+        );
+
+        if let Some(i) = self.open_blob_index() {
+            self.draw_blobs(&tx, i, frame, chunks[1]);
+            return;
+        }
+
+        let simulation = self
+            .simulation
+            .clone()
+            .filter(|(simulated, _)| *simulated == hash)
+            .map(|(_, simulation)| simulation);
+        let l1_fee = self
+            .receipt_statuses
+            .get(&hash)
+            .is_some_and(|status| status.l1_fee.is_some());
+        let chunks = Layout::vertical([
+            Constraint::Length(6),
+            Constraint::Length(if l1_fee { 9 } else { 8 }),
+            Constraint::Length(if simulation.is_some() { 7 } else { 0 }),
+            Constraint::Min(0),
+        ])
+        .split(chunks[1]);
+        self.draw_gas_breakdown(&tx, frame, chunks[0]);
+        self.draw_fee_breakdown(&tx, frame, chunks[1]);
+        if let Some(simulation) = simulation {
+            self.draw_simulation(&tx, &simulation, frame, chunks[2]);
+        }
+        self.draw_balance_changes(hash, frame, chunks[3]);
+    }
+
+    /// Draw the outcome of re-executing the open transaction
+    fn draw_simulation(
+        &self,
+        tx: &Transaction,
+        simulated: &Simulated,
+        frame: &mut Frame,
+        area: Rect,
+    ) {
+        let outcome = |outcome: &CallOutcome| match outcome {
+            CallOutcome::Returned(data) if data.is_empty() => Span::styled(
+                "✓ Success (no return data)",
+                Style::new().fg(self.theme.primary),
+            ),
+            CallOutcome::Returned(data) => Span::styled(
+                format!("✓ Success, returning {data}"),
+                Style::new().fg(self.theme.primary),
+            ),
+            CallOutcome::Reverted(reason) => Span::styled(
+                format!(
+                    "✗ Reverts ({})",
+                    reason.as_deref().unwrap_or("no reason given")
+                ),
+                Style::new().fg(self.theme.error).bold(),
+            ),
+            CallOutcome::Failed(e) => Span::styled(
+                format!("Unavailable ({e})"),
+                Style::new().fg(self.theme.muted),
+            ),
+        };
+        let lines = match simulated {
+            Simulated::Pending => {
+                vec![Line::from(Span::raw("Simulating..."))]
+            }
+            Simulated::Unavailable(e) => vec![Line::from(Span::styled(
+                format!("Unavailable ({e})"),
+                Style::new().fg(self.theme.muted),
+            ))],
+            Simulated::Known(simulation) => {
+                let gas_used = self
+                    .receipt_statuses
+                    .get(tx.inner.tx_hash())
+                    .map(|status| {
+                        format!(" (used {} when mined)", status.gas_used)
+                    })
+                    .unwrap_or_default();
+                vec![
+                    Line::from(vec![
+                        Span::styled("At parent block: ", Style::new().bold()),
+                        outcome(&simulation.parent),
+                    ]),
+                    Line::from(vec![
+                        Span::styled("Now: ", Style::new().bold()),
+                        outcome(&simulation.latest),
+                    ]),
+                    Line::from(vec![
+                        Span::styled("Gas estimate now: ", Style::new().bold()),
+                        Span::raw(match simulation.gas_estimate {
+                            Some(estimate) => format!("{estimate}{gas_used}"),
+                            None => "n/a".to_string(),
+                        }),
+                    ]),
+                ]
+            }
+        };
+        frame.render_widget(
+            Paragraph::new(Text::from(lines))
+                .wrap(Wrap { trim: false })
+                .block(
+                    Block::bordered()
+                        .title(Line::from(" Simulation ").centered())
+                        .border_style(self.theme.border),
+                ),
+            area,
+        );
+    }
+
+    /// Draw the versioned hash, KZG commitment, and KZG proof of each blob
+    /// of the open transaction, highlighting the open one
+    fn draw_blobs(
+        &self,
+        tx: &Transaction,
+        open: usize,
+        frame: &mut Frame,
+        area: Rect,
+    ) {
+        let hash = *tx.inner.tx_hash();
+        let row = |label: &str, value: String| {
+            Line::from(vec![
+                Span::styled(format!("{label:<12}"), Style::new().bold()),
+                Span::raw(value),
+            ])
+        };
+        let lines = match &self.blobs {
+            Some((fetched, Blobs::Known(sidecars))) if *fetched == hash => {
+                sidecars
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(i, sidecar)| {
+                        let style = if i == open {
+                            self.theme.highlight
+                        } else {
+                            Style::new().bold()
+                        };
+                        [
+                            Line::from(Span::styled(
+                                format!(
+                                    "Blob {} (index {} in its beacon block)",
+                                    i + 1,
+                                    sidecar.index
+                                ),
+                                style,
+                            )),
+                            row("Hash", sidecar.versioned_hash().to_string()),
+                            row(
+                                "Commitment",
+                                sidecar.kzg_commitment.to_string(),
+                            ),
+                            row("Proof", sidecar.kzg_proof.to_string()),
+                            row(
+                                "Data",
+                                format!(
+                                    "{} bytes before trailing zeroes",
+                                    sidecar.data_len()
+                                ),
+                            ),
+                            Line::default(),
+                        ]
+                    })
+                    .collect()
+            }
+            Some((fetched, Blobs::Unavailable(e))) if *fetched == hash => {
+                vec![Line::from(Span::styled(
+                    format!("Unavailable ({e})"),
+                    Style::new().fg(self.theme.error),
+                ))]
+            }
+            _ => vec![Line::from(Span::raw("Retrieving blobs..."))],
+        };
+        frame.render_widget(
+            Paragraph::new(Text::from(lines))
+                .wrap(Wrap { trim: false })
+                .block(
+                    Block::bordered()
+                        .title(Line::from(" Blobs ").centered())
+                        .border_style(self.theme.border),
+                ),
+            area,
+        );
+    }
+
+    /// Draw the code of the open contract (as a hex dump or disassembly),
+    /// what's known of its source, and the storage slots read from it
+    fn draw_contract_view(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(address) = self.contract_address else {
+            return;
+        };
+        let row = |label: &str, value: String| {
+            Line::from(vec![
+                Span::styled(format!("{label:<12}"), Style::new().bold()),
+                Span::raw(value),
+            ])
+        };
+        let mut lines = vec![Line::from(Span::styled(
+            format!(
+                "Contract {}",
+                label_address(&address, false, self.address_display_mode)
+            ),
+            Style::new().bold(),
+        ))];
+        match &self.contract {
+            Some((fetched, Fetched::Known(contract)))
+                if *fetched == address =>
+            {
+                if contract.code.is_empty() {
+                    lines.push(Line::from(Span::raw(
+                        "No code (externally owned account)",
+                    )));
+                } else {
+                    lines.push(row(
+                        "Code",
+                        format!("{} bytes", contract.code.len()),
+                    ));
+                    lines.push(row(
+                        "Source",
+                        verification_text(&contract.source),
+                    ));
+                    lines.push(row(
+                        "Selectors",
+                        if contract.selectors.is_empty() {
+                            "none found".to_string()
+                        } else {
+                            contract
+                                .selectors
+                                .iter()
+                                .map(|selector| selector.to_string())
+                                .collect::<Vec<_>>()
+                                .join(" ")
+                        },
+                    ));
+                }
+            }
+            Some((fetched, Fetched::Unavailable(e))) if *fetched == address => {
+                lines.push(Line::from(Span::styled(
+                    format!("Unavailable ({e})"),
+                    Style::new().fg(self.theme.error),
+                )))
+            }
+            _ => lines.push(Line::from(Span::raw("Retrieving code..."))),
+        }
+        let chunks =
+            Layout::vertical([Constraint::Length(6), Constraint::Min(0)])
+                .split(area);
+        frame.render_widget(
+            Paragraph::new(Text::from(lines)).wrap(Wrap { trim: false }),
+            chunks[0],
+        );
+        let chunks = Layout::horizontal([
+            Constraint::Min(hex_view::WIDTH),
+            Constraint::Length(STORAGE_PANEL_WIDTH),
+        ])
+        .split(chunks[1]);
+        /* page up and down move through the code a screen at a time */
+        self.list_area = chunks[0];
+
+        if self.code_pane == CodePane::Source {
+            let block = Block::bordered()
+                .title(Line::from(" Source (press v for bytecode) ").centered())
+                .border_style(self.theme.border);
+            if self.source_view.is_empty() {
+                frame.render_widget(
+                    Paragraph::new("No verified source code").block(block),
+                    chunks[0],
+                );
+            } else {
+                self.source_view.draw(frame, chunks[0], block);
+            }
+        } else if self.code_pane == CodePane::Disassembly {
+            let visible = self
+                .instructions
+                .viewport(chunks[0].height.saturating_sub(2) as usize);
+            let instructions: Vec<ListItem> = self.instructions.items[visible]
+                .iter()
+                .map(|instruction| ListItem::new(instruction.to_string()))
+                .collect();
+            frame.render_stateful_widget(
+                List::new(instructions)
+                    .block(
+                        Block::bordered()
+                            .title(Line::from(" Disassembly ").centered())
+                            .border_style(self.theme.border),
+                    )
+                    .highlight_style(self.theme.highlight)
+                    .highlight_symbol("> "),
+                chunks[0],
+                &mut self.instructions.viewport_state(),
+            );
+            self.draw_scrollbar(
+                frame,
+                chunks[0],
+                &mut self.instructions.scrollbar_state(),
+            );
+        } else {
+            let code = self.contract_code();
+            self.bytecode.draw(
+                &code,
+                frame,
+                chunks[0],
+                Block::bordered()
+                    .title(
+                        Line::from(
+                            " Bytecode (press d to disassemble, v for source) ",
+                        )
+                        .centered(),
+                    )
+                    .border_style(self.theme.border),
+                &self.theme,
+            );
+        }
+
+        self.draw_storage(frame, chunks[1]);
+    }
+
+    /// Draw the storage slots read from the open contract (most recent
+    /// first)
+    fn draw_storage(&self, frame: &mut Frame, area: Rect) {
+        let lines: Vec<Line> = if self.storage_slots.is_empty() {
+            vec![Line::from(Span::raw("Press s to read a storage slot"))]
+        } else {
+            self.storage_slots
+                .iter()
+                .flat_map(|(slot, value)| {
+                    let value = match value {
+                        Fetched::Pending => Span::raw("Reading..."),
+                        Fetched::Unavailable(e) => Span::styled(
+                            format!("Unavailable ({e})"),
+                            Style::new().fg(self.theme.error),
+                        ),
+                        Fetched::Known(value) => Span::raw(value.to_string()),
+                    };
+                    [
+                        Line::from(Span::styled(
+                            slot.to_string(),
+                            Style::new().bold(),
+                        )),
+                        Line::from(value),
+                        Line::default(),
+                    ]
+                })
+                .collect()
+        };
+        frame.render_widget(
+            Paragraph::new(Text::from(lines))
+                .wrap(Wrap { trim: false })
+                .block(
+                    Block::bordered()
+                        .title(Line::from(" Storage ").centered())
+                        .border_style(self.theme.border),
+                ),
+            area,
+        );
+    }
+
+    /// Draw where the gas used by the open transaction went
+    fn draw_gas_breakdown(
+        &self,
+        tx: &Transaction,
+        frame: &mut Frame,
+        area: Rect,
+    ) {
+        let hash = *tx.inner.tx_hash();
+        let intrinsic = utils::intrinsic_gas(tx);
+        let refund = match &self.trace {
+            Some((traced, Traced::Known(Trace { refund, .. })))
+                if *traced == hash =>
+            {
+                *refund
+            }
+            _ => None,
+        };
+        let row = |label: &str, value: String| {
+            Line::from(vec![
+                Span::styled(format!("{label:<12}"), Style::new().bold()),
+                Span::raw(value),
+            ])
+        };
+
+        let lines = match self.receipt_statuses.get(&hash) {
+            Some(status) => {
+                let refunded = refund.map(|refund| {
+                    utils::capped_refund(
+                        status.gas_used,
+                        refund,
+                        status.success,
+                    )
+                });
+                let execution = (status.gas_used
+                    + refunded.unwrap_or_default())
+                .saturating_sub(intrinsic);
+                vec![
+                    row("Intrinsic", intrinsic.to_string()),
+                    row(
+                        "Execution",
+                        match refunded {
+                            Some(_) => execution.to_string(),
+                            None => format!("{execution} (net of refunds)"),
+                        },
+                    ),
+                    row(
+                        "Refunded",
+                        match refunded {
+                            Some(refunded) => format!("-{refunded}"),
+                            None => "unknown".to_string(),
+                        },
+                    ),
+                    row(
+                        "Used",
+                        format!(
+                            "{} of {} ({:.2}%)",
+                            status.gas_used,
+                            tx.gas_limit(),
+                            100.0 * status.gas_used as f64
+                                / tx.gas_limit().max(1) as f64
+                        ),
+                    ),
+                ]
+            }
+            None => vec![
+                row("Intrinsic", intrinsic.to_string()),
+                row("Limit", tx.gas_limit().to_string()),
+            ],
+        };
+        frame.render_widget(
+            Paragraph::new(Text::from(lines)).block(
+                Block::bordered()
+                    .title(Line::from(" Gas ").centered())
+                    .border_style(self.theme.border),
+            ),
+            area,
+        );
+    }
+
+    /// Draw what the open transaction paid per gas (and in total), and how
+    /// its priority fee ranks amongst those of the rest of its block
+    fn draw_fee_breakdown(
+        &self,
+        tx: &Transaction,
+        frame: &mut Frame,
+        area: Rect,
+    ) {
+        let hash = *tx.inner.tx_hash();
+        let base_fee = self.selected_block.header.base_fee_per_gas;
+        let effective = tx.effective_gas_price(base_fee);
+        let tip = tx
+            .effective_tip_per_gas(base_fee.unwrap_or_default())
+            .unwrap_or_default();
+        let gas_used = self
+            .receipt_statuses
+            .get(&hash)
+            .map(|status| status.gas_used as u128);
+        let row = |label: &str, value: String| {
+            Line::from(vec![
+                Span::styled(format!("{label:<12}"), Style::new().bold()),
+                Span::raw(value),
+            ])
+        };
+        let total = |per_gas: u128| match gas_used {
+            Some(gas_used) => {
+                format!("{:.9} ETH", to_ether(U256::from(per_gas * gas_used)))
+            }
+            None => "unknown (no receipt)".to_string(),
+        };
+        let mut lines = vec![
+            row(
+                "Effective",
+                format!(
+                    "{} (max {})",
+                    format_fee(effective),
+                    format_fee(tx.max_fee_per_gas())
+                ),
+            ),
+            row(
+                "Priority",
+                match tx.max_priority_fee_per_gas() {
+                    Some(max) => {
+                        format!("{} (max {})", format_fee(tip), format_fee(max))
+                    }
+                    None => format!("{} (legacy gas price)", format_fee(tip)),
+                },
+            ),
+            row("Fee", total(effective)),
+            row("Burnt", total(base_fee.unwrap_or_default() as u128)),
+            row("Tip paid", total(tip)),
+        ];
+        if let Some(l1_fee) = self
+            .receipt_statuses
+            .get(&hash)
+            .and_then(|status| status.l1_fee)
+        {
+            lines.push(row("L1 fee", format!("{:.9} ETH", to_ether(l1_fee))));
+        }
+        let panel = Block::bordered()
+            .title(Line::from(" Fees ").centered())
+            .border_style(self.theme.border);
+        let chunks =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)])
+                .split(panel.inner(area));
+        frame.render_widget(panel, area);
+        frame.render_widget(Paragraph::new(Text::from(lines)), chunks[0]);
+
+        /* the proportion of the block's transactions tipping no more than
+         * this one */
+        let tips: Vec<u128> = self
+            .selected_block
+            .transactions
+            .txns()
+            .map(|tx| {
+                tx.effective_tip_per_gas(base_fee.unwrap_or_default())
+                    .unwrap_or_default()
+            })
+            .collect();
+        if !tips.is_empty() {
+            let rank = tips.iter().filter(|other| **other <= tip).count()
+                as f64
+                / tips.len() as f64;
+            frame.render_widget(
+                LineGauge::default()
+                    .filled_style(Style::new().fg(self.theme.primary))
+                    .label(format!("Tip rank {:>3.0}%", 100.0 * rank))
+                    .ratio(rank),
+                chunks[1],
+            );
+        }
+    }
+
+    /// Draw every account whose ETH balance the open transaction changed
+    fn draw_balance_changes(
+        &self,
+        hash: TxHash,
+        frame: &mut Frame,
+        area: Rect,
+    ) {
+        let lines = match &self.trace {
+            Some((traced, Traced::Known(trace))) if *traced == hash => trace
+                .balance_changes
+                .iter()
+                .map(|change| {
+                    let (sign, style) = if change.delta.is_negative() {
+                        ('-', Style::new().fg(self.theme.error))
+                    } else {
+                        ('+', Style::new().fg(self.theme.primary))
+                    };
+                    Line::from(vec![
+                        Span::raw(format!(
+                            "{:<44}",
+                            label_address(
+                                &change.address,
+                                false,
+                                self.address_display_mode
+                            )
+                        )),
+                        Span::styled(
+                            format!(
+                                "{sign}{:.6} ETH",
+                                to_ether(change.delta.unsigned_abs())
+                            ),
+                            style,
+                        ),
+                    ])
+                })
+                .collect(),
+            Some((traced, Traced::Unavailable(e))) if *traced == hash => {
+                vec![Line::from(Span::styled(
+                    format!("Unavailable ({e})"),
+                    Style::new().fg(self.theme.error),
+                ))]
+            }
+            _ => vec![Line::from(Span::raw("Tracing..."))],
+        };
+        frame.render_widget(
+            Paragraph::new(Text::from(lines))
+                .wrap(Wrap { trim: false })
+                .block(
+                    Block::bordered()
+                        .title(Line::from(" Balance Changes ").centered())
+                        .border_style(self.theme.border),
+                ),
+            area,
+        );
+    }
+
+    fn draw_block_view(&mut self, frame: &mut Frame, area: Rect) {
+        let header_height = if self.expand_header || self.raw_header {
+            Constraint::Percentage(50)
+        } else {
+            Constraint::Percentage(20)
+        };
+        let chunks =
+            Layout::vertical([header_height, Constraint::Min(0)]).split(area);
+        let sibling = self.fork_sibling().filter(|_| self.compare_fork);
+        if let Some((other, other_orphaned)) = sibling.cloned() {
+            let header = self.selected_block.header.clone();
+            let halves = Layout::horizontal([
+                Constraint::Percentage(50),
+                Constraint::Percentage(50),
+            ])
+            .split(chunks[0]);
+            for (area, lines) in halves.iter().zip([
+                self.fork_comparison_lines(
+                    &header,
+                    self.is_orphaned(header.hash),
+                    &other,
+                ),
+                self.fork_comparison_lines(&other, other_orphaned, &header),
+            ]) {
+                frame.render_widget(
+                    Paragraph::new(Text::from(lines))
+                        .wrap(Wrap { trim: false }),
+                    *area,
+                );
+            }
+        } else if self.raw_header {
+            self.draw_raw_block_header(frame, chunks[0]);
+        } else {
+            self.draw_block_header_text(frame, chunks[0]);
+        }
+        self.draw_transactions_list(frame, chunks[1]);
+    }
+
+    /// Another block at the same height as the open one: the canonical block
+    /// if the open one was orphaned, or an orphaned one otherwise
+    fn fork_sibling(&self) -> Option<&(Header, bool)> {
+        let header = &self.selected_block.header;
+        self.fork_siblings.iter().find(|(sibling, _)| {
+            sibling.number == header.number && sibling.hash != header.hash
+        })
+    }
+
+    fn is_orphaned(&self, hash: BlockHash) -> bool {
+        self.orphaned.contains(&hash)
+            || self
+                .fork_siblings
+                .iter()
+                .any(|(header, orphaned)| header.hash == hash && *orphaned)
+    }
+
+    /// Summary of the given header, highlighting the fields that differ from
+    /// those of the other given header (at the same height)
+    fn fork_comparison_lines(
+        &self,
+        header: &Header,
+        orphaned: bool,
+        other: &Header,
+    ) -> Vec<Line<'static>> {
+        type Field = fn(&Header) -> String;
+        let fields: [(&str, Field); 8] = [
+            ("Hash", |header| header.hash.to_string()),
+            ("Parent Hash", |header| header.parent_hash.to_string()),
+            ("Timestamp", |header| header.timestamp.to_string()),
+            ("Fee Recipient", |header| header.beneficiary.to_string()),
+            ("Gas Used", |header| header.gas_used.to_string()),
+            ("State Root", |header| header.state_root.to_string()),
+            ("Transactions Root", |header| {
+                header.transactions_root.to_string()
+            }),
+            ("Extra Data", |header| header.extra_data.to_string()),
+        ];
+        let mut lines = vec![if orphaned {
+            Line::from(Span::styled(
+                format!("Block #{} (orphaned)", header.number),
+                Style::new().fg(self.theme.error).bold(),
+            ))
+        } else {
+            Line::from(Span::styled(
+                format!("Block #{} (canonical)", header.number),
+                Style::new().fg(self.theme.primary).bold(),
+            ))
+        }];
+        lines.extend(fields.iter().map(|(name, value)| {
+            let style = if value(header) == value(other) {
+                Style::new()
+            } else {
+                Style::new().fg(self.theme.secondary)
+            };
+            Line::from(vec![
+                Span::styled(format!("{name}: "), Style::new().bold()),
+                Span::styled(value(header), style),
+            ])
+        }));
+        lines
+    }
+
+    /// Draw the RLP encoding of the open block's header, which hashes to the
+    /// block hash
+    fn draw_raw_block_header(&self, frame: &mut Frame, area: Rect) {
+        let header = &self.selected_block.header;
+        let rlp = alloy::rlp::encode(&header.inner);
+        let verified = keccak256(&rlp) == header.hash;
+        let lines = vec![
+            Line::from(vec![
+                Span::styled(
+                    format!("Block #{} header RLP ", header.number),
+                    Style::new().bold(),
+                ),
+                Span::raw(format!("({} bytes, keccak256 ", rlp.len())),
+                if verified {
+                    Span::styled(
+                        "matches block hash",
+                        Style::new().fg(self.theme.primary),
+                    )
+                } else {
+                    Span::styled(
+                        "doesn't match block hash",
+                        Style::new().fg(self.theme.error).bold(),
+                    )
+                },
+                Span::raw(")"),
+            ]),
+            Line::from(Span::raw(Bytes::from(rlp).to_string())),
+        ];
+        frame.render_widget(
+            Paragraph::new(Text::from(lines)).wrap(Wrap { trim: false }),
+            area,
+        );
+    }
+
+    /// Every header field beyond those shown by default
+    fn full_header_lines(&self) -> Vec<Line<'static>> {
+        let header = &self.selected_block.header;
+        let field = |name: &str, value: String| {
+            Line::from(vec![
+                Span::styled(format!("{name}: "), Style::new().bold()),
+                Span::raw(value),
+            ])
+        };
+        let optional =
+            |value: Option<String>| value.unwrap_or_else(|| "n/a".to_string());
+
+        vec![
+            field("Parent Hash", header.parent_hash.to_string()),
+            field("Ommers Hash", header.ommers_hash.to_string()),
+            field("Transactions Root", header.transactions_root.to_string()),
+            field("Receipts Root", header.receipts_root.to_string()),
+            field(
+                "Withdrawals Root",
+                optional(header.withdrawals_root.map(|x| x.to_string())),
+            ),
+            field("Mix Hash", header.mix_hash.to_string()),
+            field("Nonce", header.nonce.to_string()),
+            field("Difficulty", header.difficulty.to_string()),
+            field("Extra Data", format!("({} bytes)", header.extra_data.len())),
+            field(
+                "Blob Gas Used",
+                optional(header.blob_gas_used.map(|x| x.to_string())),
+            ),
+            field(
+                "Excess Blob Gas",
+                optional(header.excess_blob_gas.map(|x| x.to_string())),
+            ),
+            field(
+                "Parent Beacon Block Root",
+                optional(
+                    header.parent_beacon_block_root.map(|x| x.to_string()),
+                ),
+            ),
+            field(
+                "Requests Hash",
+                optional(header.requests_hash.map(|x| x.to_string())),
+            ),
+            field("Logs Bloom", header.logs_bloom.to_string()),
+        ]
+    }
+
+    fn draw_block_header_text(&mut self, frame: &mut Frame, area: Rect) {
+        let block = &self.selected_block;
+        let mut summary = vec![Span::raw(format!(
+            "Contains {} transactions",
+            block.transactions.len()
+        ))];
+        if let Some(fees) = self
+            .block_fees
+            .filter(|fees| fees.block_hash == block.header.hash)
+        {
+            summary.extend([
+                Span::styled("        Burnt: ", Style::new().bold()),
+                Span::raw(format!("{:.9} ETH", to_ether(fees.burnt))),
+                Span::styled("        Priority Fees: ", Style::new().bold()),
+                Span::raw(format!("{:.9} ETH", to_ether(fees.priority_fees))),
+            ]);
+        }
+        let mut lines = vec![
+            Line::from(vec![Span::styled(
+                format!("Block #{} {}", block.header.number, block.header.hash),
+                Style::default().bold(),
+            )]),
+            Line::from(vec![
+                Span::styled("Timestamp: ", Style::new().bold()),
+                Span::raw(format!(
+                    "{} ({})",
+                    Utc.timestamp_opt(block.header.timestamp as i64, 0)
+                        .single()
+                        .map_or(block.header.timestamp.to_string(), |time| {
+                            time.to_string()
+                        }),
+                    timeago::Formatter::new().convert(
+                        utils::duration_since_timestamp(block.header.timestamp)
+                    )
+                )),
+            ]),
+            Line::from(vec![
+                Span::styled("Gas Usage (wei): ", Style::new().bold()),
+                Span::raw(format!(
+                    "{}  / {} ({:.2}%)",
                     block.header.gas_used,
                     block.header.gas_limit,
                     (block.header.gas_used as f64)
-                        / (block.header.gas_limit as f64)
+                        / (block.header.gas_limit.max(1) as f64)
                         * 100.0
                 )),
                 Span::styled("        Base Fee (gwei): ", Style::new().bold()),
@@ -358,6 +4666,14 @@ impl App {
                     to_gwei(block.header.base_fee_per_gas.unwrap_or_default()
                         as f64)
                 )),
+                Span::styled("        Blob Base Fee: ", Style::new().bold()),
+                Span::raw(
+                    self.chain_id()
+                        .and_then(|chain_id| {
+                            utils::blob_base_fee(&block.header, chain_id)
+                        })
+                        .map_or("n/a".to_string(), format_fee),
+                ),
             ]),
             Line::from(vec![
                 Span::styled("Beneficiary: ", Style::new().bold()),
@@ -378,163 +4694,1490 @@ impl App {
                 Span::styled("State Root: ", Style::new().bold()),
                 Span::raw(format!("{}", block.header.state_root)),
             ]),
-            Line::from(vec![Span::raw(format!(
-                "Contains {} transactions",
-                block.transactions.len()
-            ))]),
+            Line::from(summary),
         ];
-        let block_header_text = Paragraph::new(Text::from(lines));
-        frame.render_widget(block_header_text, area);
+
+        if let Some(finality) = self
+            .finality
+            .filter(|_| !self.is_orphaned(block.header.hash))
+        {
+            let status = finality.status(block.header.number);
+            let head = self
+                .block_headers
+                .items
+                .iter()
+                .map(|header| header.number)
+                .max()
+                .unwrap_or(block.header.number);
+            lines.push(Line::from(vec![
+                Span::styled("Finality: ", Style::new().bold()),
+                Span::styled(status.to_string(), self.theme.finality(status)),
+                Span::raw(format!(
+                    " ({} confirmations)",
+                    head.saturating_sub(block.header.number) + 1
+                )),
+            ]));
+        }
+
+        if let Some(beacon_block) = self
+            .beacon_block
+            .filter(|beacon_block| beacon_block.block_hash == block.header.hash)
+        {
+            lines.push(Line::from(vec![
+                Span::styled("Slot: ", Style::new().bold()),
+                Span::raw(beacon_block.slot.to_string()),
+                Span::styled("        Epoch: ", Style::new().bold()),
+                Span::raw(beacon_block.epoch.to_string()),
+                Span::styled("        Proposer: ", Style::new().bold()),
+                Span::raw(format!("validator {}", beacon_block.proposer_index)),
+            ]));
+        }
+
+        if let Some(l2_block) = self
+            .l2_block
+            .as_ref()
+            .filter(|l2_block| l2_block.block_hash == block.header.hash)
+        {
+            lines.extend(self.l2_block_lines(l2_block));
+        }
+
+        if self.is_orphaned(block.header.hash) {
+            lines.push(Line::from(Span::styled(
+                "Orphaned: lost a reorg (press o to compare with the canonical block)",
+                Style::new().fg(self.theme.error).bold(),
+            )));
+        } else if self.fork_sibling().is_some() {
+            lines.push(Line::from(vec![
+                Span::styled("Fork: ", Style::new().bold()),
+                Span::raw(format!(
+                    "{} orphaned block(s) at this height (press o to compare)",
+                    self.fork_siblings.len() - 1
+                )),
+            ]));
+        }
+
+        if self.expand_header {
+            lines.extend(self.full_header_lines());
+        }
+
+        if let Some(diff) = self
+            .template_diff
+            .as_ref()
+            .filter(|diff| diff.block_hash == block.header.hash)
+            .filter(|diff| !diff.is_empty())
+        {
+            lines.push(Line::from(vec![
+                Span::styled("Local template: ", Style::new().bold()),
+                Span::raw(format!(
+                    "{} of {} transactions landed here, {} not templated (press t for details)",
+                    diff.landed(),
+                    diff.transactions.len(),
+                    diff.unexpected
+                )),
+            ]));
+        }
+
+        let block_header_text =
+            Paragraph::new(Text::from(lines)).wrap(Wrap { trim: false });
+
+        if self.expand_header {
+            let extra_data = self.selected_block.header.extra_data.clone();
+            let chunks = Layout::vertical([
+                Constraint::Min(0),
+                Constraint::Length(
+                    extra_data.len().div_ceil(16).max(1) as u16 + 2,
+                ),
+            ])
+            .split(area);
+            frame.render_widget(block_header_text, chunks[0]);
+            HexView::default().draw(
+                &extra_data,
+                frame,
+                chunks[1],
+                Block::bordered()
+                    .title(Line::from(" Extra Data ").centered())
+                    .border_style(self.theme.border),
+                &self.theme,
+            );
+        } else {
+            frame.render_widget(block_header_text, area);
+        }
+    }
+
+    /// Where the given L2 block sits on L1, and the deposits and transactions
+    /// of unknown types it includes (each of which is listed only while the
+    /// header is expanded)
+    fn l2_block_lines(&self, l2_block: &L2Block) -> Vec<Line<'static>> {
+        let mut lines = vec![];
+
+        if let Some(origin) = l2_block.l1_origin {
+            let mut spans = vec![
+                Span::styled("L1 Origin: ", Style::new().bold()),
+                Span::raw(match origin.hash {
+                    Some(hash) => format!("#{} {hash}", origin.number),
+                    None => format!("#{}", origin.number),
+                }),
+            ];
+            if let Some(sequence_number) = origin.sequence_number {
+                spans.extend([
+                    Span::styled(
+                        "        Sequence Number: ",
+                        Style::new().bold(),
+                    ),
+                    Span::raw(sequence_number.to_string()),
+                ]);
+            }
+            lines.push(Line::from(spans));
+        }
+
+        if !l2_block.deposits.is_empty() {
+            let minted = l2_block
+                .deposits
+                .iter()
+                .fold(U256::ZERO, |total, deposit| total + deposit.mint);
+            lines.push(Line::from(vec![
+                Span::styled("Deposits: ", Style::new().bold()),
+                Span::raw(format!(
+                    "{} ({:.6} ETH minted){}",
+                    l2_block.deposits.len(),
+                    to_ether(minted),
+                    if self.expand_header {
+                        ""
+                    } else {
+                        " (press x to list)"
+                    }
+                )),
+            ]));
+        }
+
+        if !l2_block.others.is_empty() {
+            lines.push(Line::from(vec![
+                Span::styled("Other Transactions: ", Style::new().bold()),
+                Span::raw(format!(
+                    "{} of unknown types{}",
+                    l2_block.others.len(),
+                    if self.expand_header {
+                        ""
+                    } else {
+                        " (press x to list)"
+                    }
+                )),
+            ]));
+        }
+
+        if self.expand_header {
+            lines.extend(l2_block.deposits.iter().map(|deposit| {
+                Line::from(Span::raw(format!(
+                    "  {:<4} {} {} -> {} {:.6} ETH{}",
+                    deposit.position,
+                    deposit.hash,
+                    label_address(
+                        &deposit.from,
+                        false,
+                        self.address_display_mode
+                    ),
+                    deposit.to.map_or(
+                        "(contract creation)".to_string(),
+                        |to| {
+                            label_address(&to, false, self.address_display_mode)
+                        }
+                    ),
+                    to_ether(deposit.value),
+                    if deposit.is_system_tx {
+                        " (system)"
+                    } else {
+                        ""
+                    }
+                )))
+            }));
+            l2_block.others.iter().for_each(|other| {
+                lines.extend(self.other_transaction_lines(other))
+            });
+        }
+
+        lines
+    }
+
+    /// Render a transaction of an unknown type generically: what's known of
+    /// any transaction, followed by each of its fields as served (with long
+    /// values, such as calldata, cut short)
+    fn other_transaction_lines(
+        &self,
+        other: &OtherTransaction,
+    ) -> Vec<Line<'static>> {
+        let mut lines = vec![Line::from(Span::raw(format!(
+            "  {:<4} {} {} (type {:#04x})",
+            other.position,
+            other.hash,
+            label_address(&other.from, false, self.address_display_mode),
+            other.tx_type
+        )))];
+        lines.extend(other.fields.iter().map(|(key, value)| {
+            Line::from(vec![
+                Span::styled(format!("       {key}: "), Style::new().italic()),
+                Span::raw(utils::shorten(
+                    &match value {
+                        serde_json::Value::String(s) => s.clone(),
+                        value => value.to_string(),
+                    },
+                    96,
+                )),
+            ])
+        }));
+        lines
+    }
+
+    fn draw_latest_blocks_list(&mut self, frame: &mut Frame, area: Rect) {
+        if self.block_headers.items.is_empty() {
+            let endpoint = self
+                .indexer
+                .as_ref()
+                .map_or("the RPC node".to_string(), |indexer| {
+                    redacted(&indexer.endpoints.active())
+                });
+            let text = match (&self.startup, &self.startup_error) {
+                (Some(_), _) => format!("Connecting to {endpoint}..."),
+                (None, Some(e)) => format!(
+                    "Failed to retrieve a block from {endpoint} ({e}), \
+                     retrying in the background..."
+                ),
+                (None, None) => "No blocks have been indexed yet".to_string(),
+            };
+            frame.render_widget(
+                Paragraph::new(Span::styled(text, Style::new().italic()))
+                    .wrap(Wrap { trim: true })
+                    .block(
+                        Block::bordered()
+                            .title(Line::from("Latest blocks").centered())
+                            .border_style(self.theme.border),
+                    ),
+                area,
+            );
+            return;
+        }
+
+        let counts: Vec<usize> = self
+            .block_headers
+            .items
+            .iter()
+            .map(|header| {
+                self.transaction_counts
+                    .get(&header.hash)
+                    .copied()
+                    .unwrap_or_default()
+            })
+            .collect();
+        let head = self
+            .block_headers
+            .items
+            .iter()
+            .map(|header| header.number)
+            .max();
+        let now = Utc::now().timestamp() as u64;
+        let chain_id = self.chain_id();
+        let visible = self
+            .block_headers
+            .viewport(area.height.saturating_sub(2) as usize);
+        let block_headers: Vec<ListItem> = self.block_headers.items
+            [visible.clone()]
+        .iter()
+        .zip(visible)
+        .map(|(header, i)| {
+            let window_max = counts[i.saturating_sub(TX_COUNT_WINDOW)..=i]
+                .iter()
+                .copied()
+                .max()
+                .unwrap_or_default();
+            let is_head = Some(header.number) == head;
+            let orphaned = self.orphaned.contains(&header.hash);
+            let age = Duration::from_secs(now.saturating_sub(header.timestamp));
+            let style = if orphaned {
+                Style::new().fg(self.theme.error).crossed_out()
+            } else if is_head {
+                self.theme.freshness(age).fg(self.theme.secondary).bold()
+            } else {
+                self.theme.freshness(age)
+            };
+            ListItem::new(vec![Line::from(vec![
+                Span::styled(
+                    format!(
+                        "{:<20}",
+                        if orphaned {
+                            format!("{} (orphaned)", header.number)
+                        } else if is_head {
+                            format!("{} (head)", header.number)
+                        } else {
+                            header.number.to_string()
+                        }
+                    ),
+                    match self.finality.filter(|_| !orphaned) {
+                        Some(finality) => self
+                            .theme
+                            .finality(finality.status(header.number))
+                            .bold(),
+                        None => Style::new().bold(),
+                    },
+                ),
+                Span::raw(format!(
+                    "{:<20}",
+                    format!(
+                        "{:.3} gwei",
+                        to_gwei(
+                            header.base_fee_per_gas.unwrap_or_default() as f64
+                        )
+                    )
+                )),
+                Span::raw(format!(
+                    "{:<20}",
+                    chain_id
+                        .and_then(|chain_id| {
+                            utils::blob_base_fee(header, chain_id)
+                        })
+                        .map_or(String::new(), format_fee)
+                )),
+                Span::raw(format!("{:<20}", header.gas_used)),
+                Span::raw(format!("{:<20}", header.gas_limit)),
+                Span::styled(
+                    format!(
+                        "{:<20}",
+                        Utc.timestamp_opt(header.timestamp as i64, 0)
+                            .single()
+                            .map_or(header.timestamp.to_string(), |time| {
+                                time.to_string()
+                            })
+                    ),
+                    Style::new().underlined(),
+                ),
+                Span::styled(
+                    format!("    {:<5}", counts[i]),
+                    Style::new().bold(),
+                ),
+                Span::styled(
+                    tx_count_bar(counts[i], window_max),
+                    Style::new().fg(self.theme.tertiary),
+                ),
+                Span::styled(
+                    format!(
+                        "    {:<20}",
+                        BuilderIdentity::from(header.extra_data.clone())
+                    ),
+                    Style::new().italic(),
+                ),
+            ])])
+            .style(style)
+        })
+        .collect();
+        let latest_blocks_list = List::new(block_headers)
+            .block(
+                Block::bordered()
+                    .title(Line::from("Latest blocks").centered())
+                    .title(
+                        Line::from(if self.follow {
+                            " Following head "
+                        } else {
+                            " Pinned "
+                        })
+                        .style(Style::new().fg(if self.follow {
+                            self.theme.primary
+                        } else {
+                            self.theme.secondary
+                        }))
+                        .right_aligned(),
+                    )
+                    .border_style(self.theme.border),
+            )
+            .highlight_style(self.theme.highlight)
+            .highlight_symbol("> ");
+        self.list_area = area;
+        frame.render_stateful_widget(
+            latest_blocks_list,
+            area,
+            &mut self.block_headers.viewport_state(),
+        );
+        self.draw_scrollbar(
+            frame,
+            area,
+            &mut self.block_headers.scrollbar_state(),
+        );
+    }
+
+    fn draw_transactions_list(&mut self, frame: &mut Frame, area: Rect) {
+        if self.transactions.is_empty() {
+            frame.render_widget(
+                Paragraph::new(Span::styled(
+                    "This block has no transactions",
+                    Style::new().italic(),
+                ))
+                .block(
+                    Block::bordered()
+                        .title(Line::from("Transactions").centered())
+                        .border_style(self.theme.border),
+                ),
+                area,
+            );
+            return;
+        }
+
+        let visible = self
+            .transactions
+            .rows
+            .viewport(area.height.saturating_sub(2) as usize);
+        let transactions: Vec<ListItem> = visible
+            .filter_map(|row| self.transactions.get(row))
+            .map(|tx| {
+                let reverted = self
+                    .receipt_statuses
+                    .get(tx.inner.tx_hash())
+                    .is_some_and(|status| !status.success);
+                let mut line = transaction_line(tx, self.address_display_mode);
+                line.spans.insert(
+                    0,
+                    if reverted {
+                        Span::styled("✗ ", Style::new().bold())
+                    } else {
+                        Span::raw("  ")
+                    },
+                );
+                let item = self.transaction_list_item(tx, line);
+                if reverted {
+                    item.style(Style::new().fg(self.theme.error))
+                } else {
+                    item
+                }
+            })
+            .collect();
+        let title = if self.transactions.query.is_empty() {
+            "Transactions".to_string()
+        } else {
+            format!(
+                "Transactions matching {} ({} of {})",
+                self.transactions.query,
+                self.transactions.rows.items.len(),
+                self.transactions.len()
+            )
+        };
+        let transactions_list = List::new(transactions)
+            .block(
+                Block::bordered()
+                    .title(Line::from(title).centered())
+                    .border_style(self.theme.border),
+            )
+            .highlight_style(self.theme.highlight)
+            .highlight_symbol("> ");
+        self.list_area = area;
+        frame.render_stateful_widget(
+            transactions_list,
+            area,
+            &mut self.transactions.rows.viewport_state(),
+        );
+        self.draw_scrollbar(
+            frame,
+            area,
+            &mut self.transactions.rows.scrollbar_state(),
+        );
+    }
+
+    /// Draw a scrollbar over the right-hand border of a list
+    fn draw_scrollbar(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        state: &mut ScrollbarState,
+    ) {
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None)
+                .style(self.theme.border),
+            area.inner(Margin::new(0, 1)),
+            state,
+        );
+    }
+
+    fn draw_gas_barchart(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        block: Block,
+    ) {
+        let barchart = BarChart::default()
+            .block(block)
+            .data(self.gas_bar_group())
+            .bar_width(8)
+            .bar_gap(8)
+            .bar_set(symbols::bar::NINE_LEVELS)
+            .value_style(self.theme.bar_value)
+            .label_style(Style::default().fg(self.theme.secondary))
+            .bar_style(Style::default().fg(self.theme.primary));
+        frame.render_widget(barchart, area);
+    }
+
+    /// Tab-like title listing each [`DashboardChart`] alongside the key that
+    /// selects it
+    fn dashboard_title(&self) -> Line<'static> {
+        Line::from(
+            DashboardChart::ALL
+                .iter()
+                .enumerate()
+                .map(|(i, chart)| {
+                    let label = format!(" [{}] {} ", i + 1, chart.name());
+                    if *chart == self.dashboard_chart {
+                        Span::styled(label, Style::new().bold().reversed())
+                    } else {
+                        Span::raw(label)
+                    }
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Plot the given block header field for the most recent blocks that fit
+    /// in the given area
+    fn draw_sparkline(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        block: Block,
+        field: fn(&Header) -> u64,
+    ) {
+        let width = block.inner(area).width as usize;
+        let data: Vec<u64> = self
+            .block_headers
+            .items
+            .iter()
+            .skip(self.block_headers.items.len().saturating_sub(width))
+            .map(field)
+            .collect();
+        let latest = data.last().copied().unwrap_or_default();
+        let block = block.title_bottom(
+            Line::from(match self.dashboard_chart {
+                DashboardChart::BaseFee => {
+                    format!("latest: {:.3} gwei", to_gwei(latest as f64))
+                }
+                _ => format!("latest: {latest}"),
+            })
+            .right_aligned(),
+        );
+        let sparkline = Sparkline::default()
+            .block(block)
+            .data(&data)
+            .style(Style::default().fg(self.theme.primary));
+        frame.render_widget(sparkline, area);
+    }
+
+    /// Draw the automining status and pre-funded accounts of a development
+    /// chain
+    fn draw_dev_chain_panel(
+        &self,
+        dev_chain: &DevChainInfo,
+        frame: &mut Frame,
+        area: Rect,
+    ) {
+        let mut lines = vec![Line::from(vec![
+            Span::styled("Automine: ", Style::new().bold()),
+            Span::raw(match dev_chain.automine {
+                Some(true) => "on",
+                Some(false) => "off (press m to mine)",
+                None => "unknown",
+            }),
+        ])];
+        if let Some(fork) = &dev_chain.fork {
+            let source = Url::parse(&fork.url)
+                .map_or(fork.url.clone(), |url| redacted(&url));
+            lines.push(Line::from(vec![
+                Span::styled("Fork: ", Style::new().bold()),
+                Span::raw(match fork.block_number {
+                    Some(number) => format!("{source} @ #{number}"),
+                    None => source,
+                }),
+            ]));
+        }
+        lines.extend(dev_chain.accounts.iter().enumerate().map(
+            |(i, (address, balance))| {
+                Line::from(vec![
+                    Span::styled(format!("{i:<3}"), Style::new().bold()),
+                    Span::raw(format!(
+                        "{:<16}",
+                        label_address(address, true, self.address_display_mode)
+                    )),
+                    Span::styled(
+                        format!("{:.2} ETH", to_ether(*balance)),
+                        Style::new().fg(self.theme.primary),
+                    ),
+                ])
+            },
+        ));
+        let panel = Paragraph::new(Text::from(lines)).block(
+            Block::bordered()
+                .title(
+                    Line::from(format!("Dev chain ({})", dev_chain.chain_id))
+                        .centered(),
+                )
+                .border_style(self.theme.border),
+        );
+        frame.render_widget(panel, area);
+    }
+
+    fn draw_watchlist(&mut self, frame: &mut Frame, area: Rect) {
+        let entries: Vec<ListItem> = self
+            .watchlist_entries
+            .items
+            .iter()
+            .map(|address| {
+                let snapshots = self
+                    .account_snapshots
+                    .get(address)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                let mut spans = vec![Span::raw(format!(
+                    "{:<44}",
+                    label_address(address, false, self.address_display_mode)
+                ))];
+                match (snapshots.first(), snapshots.last()) {
+                    (Some(first), Some(last)) => {
+                        let (sign, style) = if last.balance < first.balance {
+                            ('-', Style::new().fg(self.theme.error))
+                        } else {
+                            ('+', Style::new().fg(self.theme.primary))
+                        };
+                        spans.extend([
+                            Span::styled(
+                                format!(
+                                    "{:<24}",
+                                    format!(
+                                        "{:.6} ETH",
+                                        to_ether(last.balance)
+                                    )
+                                ),
+                                Style::new().bold(),
+                            ),
+                            Span::raw(format!("{:<14}", last.nonce)),
+                            Span::styled(
+                                format!(
+                                    "{sign}{:.6} ETH",
+                                    to_ether(
+                                        last.balance.abs_diff(first.balance)
+                                    )
+                                ),
+                                style,
+                            ),
+                        ]);
+                    }
+                    _ => spans.push(Span::styled(
+                        "Not yet recorded",
+                        Style::new().italic(),
+                    )),
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+        let warnings = self.pending_warnings.len() as u16;
+        let chunks = Layout::vertical([
+            Constraint::Fill(1),
+            /* only as tall as needed, if there's anything to warn of */
+            Constraint::Length(match warnings {
+                0 => 0,
+                n => n.min(MAX_PENDING_WARNINGS_SHOWN) + 2,
+            }),
+            Constraint::Fill(1),
+        ])
+        .split(area);
+        let watchlist = List::new(entries)
+            .block(
+                Block::bordered()
+                    .title(
+                        Line::from(format!(
+                            "Watch list ({})",
+                            self.watchlist_entries.items.len()
+                        ))
+                        .centered(),
+                    )
+                    .border_style(self.theme.border),
+            )
+            .highlight_style(self.theme.highlight)
+            .highlight_symbol("> ");
+        self.list_area = chunks[0];
+        frame.render_stateful_widget(
+            watchlist,
+            chunks[0],
+            &mut self.watchlist_entries.state,
+        );
+        if warnings > 0 {
+            self.draw_pending_warnings(frame, chunks[1]);
+        }
+
+        let balances: Vec<(f64, f64)> = self
+            .get_selected_watchlist_entry()
+            .and_then(|address| self.account_snapshots.get(&address))
+            .map(|snapshots| {
+                snapshots
+                    .iter()
+                    .map(|snapshot| {
+                        (
+                            snapshot.block_number as f64,
+                            to_ether(snapshot.balance),
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let x_bounds = match (balances.first(), balances.last()) {
+            (Some((first, _)), Some((last, _))) => [*first, *last],
+            _ => [0.0, 1.0],
+        };
+        let (y_min, y_max) = balances
+            .iter()
+            .map(|(_, y)| *y)
+            .fold((f64::MAX, f64::MIN), |(lo, hi), y| (lo.min(y), hi.max(y)));
+        let (y_min, y_max) = if balances.is_empty() {
+            (0.0, 1.0)
+        } else if y_min == y_max {
+            (y_min * 0.9, y_max * 1.1 + f64::MIN_POSITIVE)
+        } else {
+            (y_min, y_max)
+        };
+        let chart = Chart::new(vec![Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::new().fg(self.theme.primary))
+            .data(&balances)])
+        .block(
+            Block::bordered()
+                .title(Line::from("Balance (ETH)").centered())
+                .border_style(self.theme.border),
+        )
+        .x_axis(Axis::default().bounds(x_bounds).labels([
+            Line::from(format!("{}", x_bounds[0])),
+            Line::from(format!("{}", x_bounds[1])),
+        ]))
+        .y_axis(Axis::default().bounds([y_min, y_max]).labels([
+            Line::from(format!("{y_min:.6}")),
+            Line::from(format!("{y_max:.6}")),
+        ]));
+        frame.render_widget(chart, chunks[2]);
+    }
+
+    /// Warn of pending transactions from watched addresses that are unlikely
+    /// to be included, along with the fees to replace them with
+    fn draw_pending_warnings(&self, frame: &mut Frame, area: Rect) {
+        let lines: Vec<Line> = self
+            .pending_warnings
+            .iter()
+            .map(|warning| {
+                let issue = match warning.issue {
+                    PendingIssue::NonceGap { missing } => {
+                        format!("waiting on nonce {missing}")
+                    }
+                    PendingIssue::Stuck { blocks } => {
+                        format!("pending for {blocks} blocks")
+                    }
+                };
+                Line::from(vec![
+                    Span::raw(format!(
+                        "{:<44}",
+                        label_address(
+                            &warning.from,
+                            false,
+                            self.address_display_mode
+                        )
+                    )),
+                    Span::raw(format!("{:<14}", warning.nonce)),
+                    Span::styled(
+                        format!("{issue:<28}"),
+                        Style::new().fg(self.theme.error),
+                    ),
+                    Span::raw(format!(
+                        "{:<24}",
+                        utils::shorten_hash(&warning.hash)
+                    )),
+                    Span::styled(
+                        format!(
+                            "Replace with {:.3} Gwei max fee, {:.3} Gwei tip",
+                            to_gwei(warning.replacement.max_fee_per_gas as f64),
+                            to_gwei(
+                                warning.replacement.max_priority_fee_per_gas
+                                    as f64
+                            )
+                        ),
+                        Style::new().italic(),
+                    ),
+                ])
+            })
+            .collect();
+        let panel = Paragraph::new(lines).block(
+            Block::bordered()
+                .title(
+                    Line::from(format!(
+                        "Pending transactions at risk ({})",
+                        self.pending_warnings.len()
+                    ))
+                    .centered(),
+                )
+                .border_style(Style::new().fg(self.theme.error)),
+        );
+        frame.render_widget(panel, area);
+    }
+
+    fn draw_bookmarks(&mut self, frame: &mut Frame, area: Rect) {
+        let bookmarks: Vec<ListItem> = self
+            .bookmarks
+            .items
+            .iter()
+            .map(|bookmark| {
+                let target = match bookmark.target {
+                    BookmarkTarget::Block { number, hash } => {
+                        format!("#{number} ({})", utils::shorten_hash(&hash))
+                    }
+                    BookmarkTarget::Transaction(hash) => hash.to_string(),
+                    BookmarkTarget::Address(address) => label_address(
+                        &address,
+                        false,
+                        self.address_display_mode,
+                    ),
+                };
+                let created_at = Utc
+                    .timestamp_opt(bookmark.created_at as i64, 0)
+                    .single()
+                    .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default();
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:<18}", created_at),
+                        Style::new().italic(),
+                    ),
+                    Span::styled(
+                        format!("{:<13}", bookmark.target.kind()),
+                        Style::new().bold(),
+                    ),
+                    Span::raw(format!("{target:<68}")),
+                    Span::raw(bookmark.note.clone().unwrap_or_default()),
+                ]))
+            })
+            .collect();
+        let list = List::new(bookmarks)
+            .block(
+                Block::bordered()
+                    .title(
+                        Line::from(format!(
+                            "Bookmarks ({})",
+                            self.bookmarks.items.len()
+                        ))
+                        .centered(),
+                    )
+                    .border_style(self.theme.border),
+            )
+            .highlight_style(self.theme.highlight)
+            .highlight_symbol("> ");
+        self.list_area = area;
+        frame.render_stateful_widget(list, area, &mut self.bookmarks.state);
     }
 
-    fn draw_latest_blocks_list(&mut self, frame: &mut Frame, area: Rect) {
-        let block_headers: Vec<ListItem> = self
-            .block_headers
+    fn draw_selectors(&mut self, frame: &mut Frame, area: Rect) {
+        let total: u64 = self
+            .selector_stats
             .items
             .iter()
-            .map(|header| {
-                ListItem::new(vec![Line::from(vec![
-                    Span::styled(
-                        format!("{:<20}", header.number.to_string()),
-                        Style::new().bold(),
-                    ),
-                    Span::raw(format!(
-                        "{:<20}",
-                        format!(
-                            "{:.3} gwei",
-                            to_gwei(
-                                header.base_fee_per_gas.unwrap_or_default()
-                                    as f64
-                            )
-                        )
-                    )),
-                    Span::raw(format!("{:<20}", header.gas_used)),
-                    Span::raw(format!("{:<20}", header.gas_limit)),
-                    Span::styled(
-                        format!(
-                            "{:<20}",
-                            Utc.timestamp_opt(header.timestamp as i64, 0)
-                                .unwrap()
-                        ),
-                        Style::new().underlined(),
+            .map(|stats| stats.transactions)
+            .sum();
+        let selectors: Vec<ListItem> = self
+            .selector_stats
+            .items
+            .iter()
+            .map(|stats| {
+                let (selector, name) = match stats.selector {
+                    Some(selector) => (
+                        Span::raw(format!("{selector:<12}")),
+                        match utils::selector_signature(&selector) {
+                            Some(signature) => Span::styled(
+                                signature,
+                                Style::new().fg(self.theme.secondary),
+                            ),
+                            None => {
+                                Span::styled("Unknown", Style::new().italic())
+                            }
+                        },
                     ),
-                    Span::styled(
-                        format!(
-                            "    {:<20}",
-                            BuilderIdentity::from(header.extra_data.clone())
+                    None => (
+                        Span::raw(format!("{:<12}", "-")),
+                        Span::styled(
+                            "Plain transfers",
+                            Style::new().fg(self.theme.secondary),
                         ),
-                        Style::new().italic(),
                     ),
-                ])])
+                };
+                let share =
+                    stats.transactions as f64 / total.max(1) as f64 * 100.0;
+                ListItem::new(Line::from(vec![
+                    selector,
+                    Span::raw(format!("{:<10}", stats.transactions)),
+                    Span::raw(format!("{:<8}", format!("{share:.1}%"))),
+                    Span::raw(format!("{:<16}", stats.gas_used)),
+                    name,
+                ]))
             })
             .collect();
-        let latest_blocks_list = List::new(block_headers)
+        let list = List::new(selectors)
             .block(
                 Block::bordered()
-                    .title(Line::from("Latest blocks").centered())
-                    .border_style(Color::Green),
+                    .title(
+                        Line::from(format!(
+                            "Selectors (last {STATS_WINDOW} blocks, \
+                             {total} transactions)"
+                        ))
+                        .centered(),
+                    )
+                    .border_style(self.theme.border),
             )
-            .highlight_style(Style::default().bg(Color::Magenta))
+            .highlight_style(self.theme.highlight)
             .highlight_symbol("> ");
+        self.list_area = area;
         frame.render_stateful_widget(
-            latest_blocks_list,
+            list,
             area,
-            &mut self.block_headers.state,
+            &mut self.selector_stats.state,
         );
     }
 
-    fn draw_transactions_list(&mut self, frame: &mut Frame, area: Rect) {
-        let transactions: Vec<ListItem> = self
-            .selected_block
-            .transactions
-            .clone()
-            .into_transactions()
-            .map(|tx| {
-                let tx_info = tx.info();
-                ListItem::new(vec![Line::from(vec![
-                    Span::styled(
-                        format!("{:<4}", tx_info.index.unwrap().to_string()),
-                        Style::new().bold(),
+    /// Tabulate how each RPC endpoint is faring in the race to deliver new
+    /// blocks
+    fn draw_endpoints(&self, frame: &mut Frame, area: Rect) {
+        let best = self
+            .endpoint_races
+            .iter()
+            .filter_map(|endpoint| endpoint.head.map(|(number, _)| number))
+            .max();
+        let millis = |duration: Option<Duration>| {
+            duration.map_or("-".to_string(), |duration| {
+                format!("{} ms", duration.as_millis())
+            })
+        };
+        let lines: Vec<Line> = std::iter::once(Line::from(Span::styled(
+            format!(
+                "  {:<40}{:<14}{:<8}{:<12}{:<16}{:<14}{}",
+                "Endpoint",
+                "Head",
+                "Lag",
+                "Latency",
+                "First",
+                "Mean delay",
+                "Status"
+            ),
+            Style::new().bold(),
+        )))
+        .chain(self.endpoint_races.iter().map(|endpoint| {
+            let lag = match (best, endpoint.head) {
+                (Some(best), Some((number, _))) => best.saturating_sub(number),
+                _ => 0,
+            };
+            let share =
+                endpoint.first as f64 / endpoint.blocks.max(1) as f64 * 100.0;
+            Line::from(vec![
+                Span::styled(
+                    format!(
+                        "{} {:<40}",
+                        if endpoint.active { "*" } else { " " },
+                        endpoint.url
+                    ),
+                    if endpoint.active {
+                        Style::new().bold()
+                    } else {
+                        Style::new()
+                    },
+                ),
+                Span::raw(format!(
+                    "{:<14}",
+                    endpoint
+                        .head
+                        .map_or("-".to_string(), |(number, _)| format!(
+                            "#{number}"
+                        ))
+                )),
+                Span::styled(
+                    format!("{lag:<8}"),
+                    if lag > 0 {
+                        Style::new().fg(self.theme.error)
+                    } else {
+                        Style::new()
+                    },
+                ),
+                Span::raw(format!("{:<12}", millis(endpoint.latency))),
+                Span::raw(format!(
+                    "{:<16}",
+                    format!("{} ({share:.0}%)", endpoint.first)
+                )),
+                Span::raw(format!("{:<14}", millis(endpoint.mean_delay()))),
+                match &endpoint.error {
+                    Some(e) => Span::styled(
+                        e.clone(),
+                        Style::new().fg(self.theme.error),
                     ),
+                    None => Span::styled(
+                        "following",
+                        Style::new().fg(self.theme.primary),
+                    ),
+                },
+            ])
+        }))
+        .collect();
+        let lines = if self.endpoint_races.is_empty() {
+            vec![Line::from(Span::styled(
+                "Only one RPC endpoint is configured (see --fallback-rpc)",
+                Style::new().italic(),
+            ))]
+        } else {
+            lines
+        };
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::bordered()
+                    .title(Line::from("RPC endpoints").centered())
+                    .border_style(self.theme.border),
+            ),
+            area,
+        );
+    }
+
+    /// Tabulate the latency and error rate of each RPC method over the last
+    /// few minutes
+    fn draw_rpc_health(&self, frame: &mut Frame, area: Rect) {
+        let millis = |duration: Duration| {
+            format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+        };
+        let lines: Vec<Line> = std::iter::once(Line::from(Span::styled(
+            format!(
+                "{:<40}{:<12}{:<14}{:<14}{}",
+                "Method", "Requests", "p50", "p95", "Errors"
+            ),
+            Style::new().bold(),
+        )))
+        .chain(self.method_health.iter().map(|health| {
+            let error_rate = health.error_rate() * 100.0;
+            Line::from(vec![
+                Span::raw(format!("{:<40}", health.method)),
+                Span::raw(format!("{:<12}", health.requests)),
+                Span::raw(format!("{:<14}", millis(health.p50))),
+                Span::raw(format!("{:<14}", millis(health.p95))),
+                Span::styled(
+                    format!("{} ({error_rate:.1}%)", health.failures),
+                    if health.failures > 0 {
+                        Style::new().fg(self.theme.error)
+                    } else {
+                        Style::new()
+                    },
+                ),
+            ])
+        }))
+        .collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::bordered()
+                    .title(
+                        Line::from(format!(
+                            "RPC health (last {} minutes)",
+                            RPC_HEALTH_WINDOW.as_secs() / 60
+                        ))
+                        .centered(),
+                    )
+                    .border_style(self.theme.border),
+            ),
+            area,
+        );
+    }
+
+    fn draw_stats(&mut self, frame: &mut Frame, area: Rect) {
+        let chunks = Layout::vertical([
+            Constraint::Length(TOP_GAS_CONSUMERS as u16 + 3),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+        ])
+        .split(area);
+        let summaries = Layout::horizontal([
+            Constraint::Percentage(40),
+            Constraint::Percentage(60),
+        ])
+        .split(chunks[0]);
+        let totals = &self.fee_totals;
+        let lines = match &totals.blocks {
+            Some(blocks) => vec![
+                Line::from(vec![
+                    Span::styled("Blocks: ", Style::new().bold()),
                     Span::raw(format!(
-                        "{:<16}",
-                        format!(
-                            "{}",
-                            utils::shorten_hash(&tx_info.hash.unwrap())
-                        )
+                        "#{} to #{} ({} with indexed receipts)",
+                        blocks.start(),
+                        blocks.end(),
+                        totals.count
                     )),
+                ]),
+                Line::from(vec![
+                    Span::styled("Burnt: ", Style::new().bold()),
                     Span::raw(format!(
-                        "{:<32}",
-                        utils::label_address(
-                            &tx.as_recovered().signer(),
-                            true,
-                            self.address_display_mode
-                        )
+                        "{:.9} ETH ({:.9} ETH per block)",
+                        to_ether(totals.burnt),
+                        to_ether(totals.burnt) / totals.count as f64
                     )),
+                ]),
+                Line::from(vec![
+                    Span::styled("Priority Fees: ", Style::new().bold()),
                     Span::raw(format!(
-                        "{:<32}",
-                        utils::label_address(
-                            &tx.to().unwrap_or_default(),
-                            true,
-                            self.address_display_mode
-                        )
+                        "{:.9} ETH ({:.9} ETH per block)",
+                        to_ether(totals.priority_fees),
+                        to_ether(totals.priority_fees) / totals.count as f64
                     )),
-                    Span::raw(format!("{:<8}", tx.nonce())),
+                ]),
+            ],
+            None => vec![Line::from(Span::styled(
+                "Waiting for receipts...",
+                Style::new().italic(),
+            ))],
+        };
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::bordered()
+                    .title(Line::from("Fees").centered())
+                    .border_style(self.theme.border),
+            ),
+            summaries[0],
+        );
+        self.draw_gas_consumers(frame, summaries[1]);
+
+        let charts = Layout::horizontal([
+            Constraint::Percentage(50),
+            Constraint::Percentage(50),
+        ])
+        .split(chunks[1]);
+        let x_bounds = match (
+            self.recent_block_fees.first(),
+            self.recent_block_fees.last(),
+        ) {
+            (Some(first), Some(last)) => {
+                [first.block_number as f64, last.block_number as f64]
+            }
+            _ => [0.0, 1.0],
+        };
+        let x_axis = || {
+            Axis::default().bounds(x_bounds).labels([
+                Line::from(format!("{}", x_bounds[0])),
+                Line::from(format!("{}", x_bounds[1])),
+            ])
+        };
+
+        /* offset by whatever was burnt before the charted blocks, so that
+         * the series ends at the total */
+        let mut cumulative = to_ether(totals.burnt.saturating_sub(
+            self.recent_block_fees.iter().map(|fees| fees.burnt).sum(),
+        ));
+        let cumulative_burn: Vec<(f64, f64)> = self
+            .recent_block_fees
+            .iter()
+            .map(|fees| {
+                cumulative += to_ether(fees.burnt);
+                (fees.block_number as f64, cumulative)
+            })
+            .collect();
+        let (y_min, y_max) =
+            match (cumulative_burn.first(), cumulative_burn.last()) {
+                (Some((_, first)), Some((_, last))) if first < last => {
+                    (*first, *last)
+                }
+                _ => (0.0, cumulative.max(f64::MIN_POSITIVE)),
+            };
+        let chart = Chart::new(vec![Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::new().fg(self.theme.primary))
+            .data(&cumulative_burn)])
+        .block(
+            Block::bordered()
+                .title(Line::from("Cumulative burn (ETH)").centered())
+                .border_style(self.theme.border),
+        )
+        .x_axis(x_axis())
+        .y_axis(Axis::default().bounds([y_min, y_max]).labels([
+            Line::from(format!("{y_min:.6}")),
+            Line::from(format!("{y_max:.6}")),
+        ]));
+        frame.render_widget(chart, charts[0]);
+
+        let series = |fee: fn(&BlockFees) -> U256| -> Vec<(f64, f64)> {
+            self.recent_block_fees
+                .iter()
+                .map(|fees| (fees.block_number as f64, to_ether(fee(fees))))
+                .collect()
+        };
+        let (burnt, priority_fees) =
+            (series(|fees| fees.burnt), series(|fees| fees.priority_fees));
+        let y_max = burnt
+            .iter()
+            .chain(&priority_fees)
+            .map(|(_, y)| *y)
+            .fold(f64::MIN_POSITIVE, f64::max);
+        let chart = Chart::new(vec![
+            Dataset::default()
+                .name("Burnt")
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::new().fg(self.theme.secondary))
+                .data(&burnt),
+            Dataset::default()
+                .name("Priority fees")
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::new().fg(self.theme.primary))
+                .data(&priority_fees),
+        ])
+        .block(
+            Block::bordered()
+                .title(Line::from("Fees per block (ETH)").centered())
+                .border_style(self.theme.border),
+        )
+        .x_axis(x_axis())
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, y_max])
+                .labels([Line::from("0"), Line::from(format!("{y_max:.6}"))]),
+        );
+        frame.render_widget(chart, charts[1]);
+
+        self.draw_hourly_stats(frame, chunks[2]);
+    }
+
+    /// List the recipients that used the most gas over the most recent
+    /// blocks, each with a bar shaded by its share of the gas used
+    fn draw_gas_consumers(&self, frame: &mut Frame, area: Rect) {
+        const BAR_WIDTH: usize = 20;
+        let total = self.gas_by_recipient.total_gas_used;
+        let lines = if self.gas_by_recipient.recipients.is_empty() {
+            vec![Line::from(Span::styled(
+                "Waiting for receipts...",
+                Style::new().italic(),
+            ))]
+        } else {
+            let heaviest = self.gas_by_recipient.recipients[0].gas_used.max(1);
+            std::iter::once(Line::from(Span::styled(
+                format!(
+                    "  {:<44}{:<16}{:<8}{:<8}",
+                    "Recipient", "Gas used", "Txs", "Share"
+                ),
+                Style::new().bold(),
+            )))
+            .chain(self.gas_by_recipient.recipients.iter().map(|recipient| {
+                let share =
+                    recipient.gas_used as f64 / total.max(1) as f64 * 100.0;
+                let heat = match share {
+                    share if share >= 20.0 => self.theme.error,
+                    share if share >= 5.0 => self.theme.secondary,
+                    _ => self.theme.primary,
+                };
+                let width = (recipient.gas_used as f64 / heaviest as f64
+                    * BAR_WIDTH as f64)
+                    .ceil() as usize;
+                Line::from(vec![
                     Span::raw(format!(
-                        "{:<4}",
-                        if tx.to().is_none() {
-                            "📄".to_string()
-                        } else {
-                            "".to_string()
+                        "  {:<44}",
+                        match recipient.to {
+                            Address::ZERO => "Contract creations".to_string(),
+                            to => label_address(
+                                &to,
+                                false,
+                                self.address_display_mode
+                            ),
                         }
                     )),
-                    Span::raw(format!(
-                        "{:<20}",
-                        utils::human_readable_tx_data(tx.input().clone(),)
-                    )),
-                    Span::raw(format!(
-                        "{:<20}",
-                        format!(
-                            "{:.3} gwei",
-                            to_gwei(useful_gas_price(&tx) as f64),
-                        )
-                    )),
-                ])])
-            })
-            .collect();
-        let transactions_list = List::new(transactions)
-            .block(
+                    Span::raw(format!("{:<16}", recipient.gas_used)),
+                    Span::raw(format!("{:<8}", recipient.transactions)),
+                    Span::raw(format!("{:<8}", format!("{share:.1}%"))),
+                    Span::styled("█".repeat(width), Style::new().fg(heat)),
+                ])
+            }))
+            .collect()
+        };
+        frame.render_widget(
+            Paragraph::new(lines).block(
                 Block::bordered()
-                    .title(Line::from("Transactions").centered())
-                    .border_style(Color::Green),
-            )
-            .highlight_style(Style::default().bg(Color::Magenta))
-            .highlight_symbol("> ");
-        frame.render_stateful_widget(
-            transactions_list,
+                    .title(
+                        Line::from(format!(
+                            "Top gas consumers (last {STATS_WINDOW} blocks)"
+                        ))
+                        .centered(),
+                    )
+                    .border_style(self.theme.border),
+            ),
             area,
-            &mut self.transactions.state,
         );
     }
 
-    fn draw_gas_barchart(
-        &mut self,
-        frame: &mut Frame,
-        area: Rect,
-        block: Block,
-    ) {
-        let barchart = BarChart::default()
-            .block(block)
-            .data(self.gas_bar_group())
-            .bar_width(8)
-            .bar_gap(8)
-            .bar_set(symbols::bar::NINE_LEVELS)
-            .value_style(
-                Style::default().fg(Color::Black).bg(Color::Green).italic(),
-            )
-            .label_style(Style::default().fg(Color::Yellow))
-            .bar_style(Style::default().fg(Color::Green));
-        frame.render_widget(barchart, area);
+    /// Chart each of the [`HourlyStats`] series side by side
+    fn draw_hourly_stats(&self, frame: &mut Frame, area: Rect) {
+        let charts = Layout::horizontal([Constraint::Fill(1); 4]).split(area);
+        let x_bounds =
+            match (self.hourly_stats.first(), self.hourly_stats.last()) {
+                (Some(first), Some(last)) => {
+                    [first.hour as f64, last.hour as f64]
+                }
+                _ => [0.0, 1.0],
+            };
+        let hour = |x: f64| {
+            Utc.timestamp_opt(x as i64, 0)
+                .single()
+                .map(|time| time.format("%m-%d %H:00").to_string())
+                .unwrap_or_default()
+        };
+        type Series = fn(&HourlyStats) -> f64;
+        let series: [(&str, Series); 4] = [
+            ("Blocks per hour", |stats| stats.blocks as f64),
+            ("Mean gas used (Mgas)", |stats| {
+                stats.average_gas_used() as f64 / 1e6
+            }),
+            ("Mean base fee (gwei)", |stats| {
+                to_gwei(stats.average_base_fee() as f64)
+            }),
+            ("Throughput (tx/s)", HourlyStats::throughput),
+        ];
+
+        for ((title, value), area) in series.into_iter().zip(charts.iter()) {
+            let data: Vec<(f64, f64)> = self
+                .hourly_stats
+                .iter()
+                .map(|stats| (stats.hour as f64, value(stats)))
+                .collect();
+            let y_max = data
+                .iter()
+                .map(|(_, y)| *y)
+                .fold(f64::MIN_POSITIVE, f64::max);
+            let chart =
+                Chart::new(vec![Dataset::default()
+                    .marker(Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .style(Style::new().fg(self.theme.primary))
+                    .data(&data)])
+                .block(
+                    Block::bordered()
+                        .title(Line::from(title).centered())
+                        .border_style(self.theme.border),
+                )
+                .x_axis(Axis::default().bounds(x_bounds).labels([
+                    Line::from(hour(x_bounds[0])),
+                    Line::from(hour(x_bounds[1])),
+                ]))
+                .y_axis(Axis::default().bounds([0.0, y_max]).labels([
+                    Line::from("0"),
+                    Line::from(format!("{y_max:.2}")),
+                ]));
+            frame.render_widget(chart, *area);
+        }
+    }
+
+    fn draw_fee_panel(&self, frame: &mut Frame, area: Rect) {
+        let panel = Block::bordered()
+            .title(Line::from("Priority fees (gwei)").centered())
+            .border_style(self.theme.border);
+        let chunks = Layout::vertical([
+            Constraint::Fill(2),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .split(panel.inner(area));
+        frame.render_widget(panel, area);
+
+        let series = |percentile: fn(&FeeStats) -> u64| -> Vec<(f64, f64)> {
+            self.fee_stats
+                .iter()
+                .map(|stats| {
+                    (
+                        stats.block_number as f64,
+                        to_gwei(percentile(stats) as f64),
+                    )
+                })
+                .collect()
+        };
+        let (p10, p50, p90) = (
+            series(|stats| stats.p10),
+            series(|stats| stats.p50),
+            series(|stats| stats.p90),
+        );
+        let x_bounds = match (self.fee_stats.first(), self.fee_stats.last()) {
+            (Some(first), Some(last)) => {
+                [first.block_number as f64, last.block_number as f64]
+            }
+            _ => [0.0, 1.0],
+        };
+        let y_max = p90.iter().map(|(_, y)| *y).fold(1.0, f64::max);
+        let datasets = vec![
+            Dataset::default()
+                .name("p10")
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::new().fg(self.theme.tertiary))
+                .data(&p10),
+            Dataset::default()
+                .name("p50")
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::new().fg(self.theme.primary))
+                .data(&p50),
+            Dataset::default()
+                .name("p90")
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::new().fg(self.theme.secondary))
+                .data(&p90),
+        ];
+        let chart =
+            Chart::new(datasets)
+                .x_axis(Axis::default().bounds(x_bounds))
+                .y_axis(Axis::default().bounds([0.0, y_max]).labels([
+                    Line::from("0"),
+                    Line::from(format!("{y_max:.2}")),
+                ]));
+        frame.render_widget(chart, chunks[0]);
+
+        let blob_base_fees: Vec<(f64, f64)> = self
+            .blob_base_fees
+            .iter()
+            .map(|(number, fee)| (*number as f64, to_gwei(*fee as f64)))
+            .collect();
+        let blob_x_bounds =
+            match (blob_base_fees.first(), blob_base_fees.last()) {
+                (Some((first, _)), Some((last, _))) => [*first, *last],
+                _ => [0.0, 1.0],
+            };
+        let blob_y_max = blob_base_fees
+            .iter()
+            .map(|(_, y)| *y)
+            .fold(f64::MIN_POSITIVE, f64::max);
+        let blob_chart = Chart::new(vec![Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::new().fg(self.theme.primary))
+            .data(&blob_base_fees)])
+        .block(
+            Block::new()
+                .borders(Borders::TOP)
+                .title(Line::from("Blob base fee (gwei)").centered())
+                .border_style(self.theme.border),
+        )
+        .x_axis(Axis::default().bounds(blob_x_bounds))
+        .y_axis(Axis::default().bounds([0.0, blob_y_max]).labels([
+            Line::from("0"),
+            Line::from(format!("{blob_y_max:.3e}")),
+        ]));
+        frame.render_widget(blob_chart, chunks[1]);
+
+        let suggestion = match fee_suggestion(&self.fee_stats) {
+            Some((slow, standard, fast)) => Line::from(vec![
+                Span::styled("Slow ", Style::new().bold()),
+                Span::raw(format!("{slow:.3}  ")),
+                Span::styled("Standard ", Style::new().bold()),
+                Span::raw(format!("{standard:.3}  ")),
+                Span::styled("Fast ", Style::new().bold()),
+                Span::raw(format!("{fast:.3}")),
+            ]),
+            None => Line::from(Span::styled(
+                "Waiting for blocks...",
+                Style::new().italic(),
+            )),
+        };
+        frame.render_widget(Paragraph::new(suggestion).centered(), chunks[1]);
     }
 
     fn chart_data(&self) -> Vec<(String, u64)> {
@@ -561,45 +6204,307 @@ impl App {
         xs.clone()
     }
 
-    fn draw_hex_display(
-        &mut self,
-        bytes: &Bytes,
-        frame: &mut Frame,
-        area: Rect,
-    ) {
-        let mut lines = vec![];
+    fn get_selected_header(&self) -> Option<&Header> {
+        self.block_headers
+            .state
+            .selected()
+            .and_then(|offset| self.block_headers.items.get(offset))
+    }
 
-        for i in 0..(bytes.len().div_ceil(32)) {
-            lines.push(Line::from(vec![
-                Span::styled(
-                    format!("{:#06x}", i * 32),
-                    Style::new().underlined(),
-                ),
-                Span::raw(format!(
-                    "        {}",
-                    &grab_range(bytes, i * 32, (i + 1) * 32).to_string()[2..]
-                )),
-            ]));
+    /// Run a database query, recording its latency if metrics are enabled
+    fn timed_query<T>(&self, query: impl FnOnce() -> T) -> T {
+        match &self.metrics {
+            Some(metrics) => {
+                let _timer = metrics.ui_db_query_seconds.start_timer();
+                query()
+            }
+            None => query(),
         }
+    }
 
-        frame.render_widget(
-            Paragraph::new(Text::from(lines))
-                .block(Block::default().borders(Borders::ALL)),
-            area,
-        );
+    /// Run a query of the chain data as part of a tick (as with
+    /// [`App::timed_query`]), noting any failure in the status bar so that
+    /// it's retried on the next tick rather than bringing down the UI
+    fn tick_query<T>(
+        &mut self,
+        query: impl FnOnce() -> DbResult<T>,
+    ) -> Option<T> {
+        self.timed_query(query)
+            .inspect_err(|e| self.db_error = Some(e.to_string()))
+            .ok()
     }
 
-    fn get_selected_header(&self) -> Option<&Header> {
-        self.block_headers
+    fn get_selected_filter_result(&self) -> Option<&Transaction> {
+        self.filter_results
             .state
             .selected()
-            .and_then(|offset| self.block_headers.items.get(offset))
+            .and_then(|offset| self.filter_results.items.get(offset))
     }
 
-    fn get_selected_transaction(&self) -> Option<&Transaction> {
-        self.transactions
+    fn get_selected_log_result(&self) -> Option<&Log> {
+        self.log_results
+            .state
+            .selected()
+            .and_then(|offset| self.log_results.items.get(offset))
+    }
+
+    /// Data of the selected event (empty if there's none)
+    fn selected_log_data(&self) -> Bytes {
+        self.get_selected_log_result()
+            .map(|log| log.data().data.clone())
+            .unwrap_or_default()
+    }
+
+    fn get_selected_template_transaction(
+        &self,
+    ) -> Option<&(TxHash, TemplateTxStatus)> {
+        self.template_transactions
             .state
             .selected()
-            .and_then(|offset| self.transactions.items.get(offset))
+            .and_then(|offset| self.template_transactions.items.get(offset))
+    }
+
+    fn get_selected_watchlist_entry(&self) -> Option<Address> {
+        self.watchlist_entries
+            .state
+            .selected()
+            .and_then(|offset| self.watchlist_entries.items.get(offset))
+            .copied()
+    }
+
+    fn get_selected_bookmark(&self) -> Option<&Bookmark> {
+        self.bookmarks
+            .state
+            .selected()
+            .and_then(|offset| self.bookmarks.items.get(offset))
+    }
+
+    fn get_selected_selector_stats(&self) -> Option<&SelectorStats> {
+        self.selector_stats
+            .state
+            .selected()
+            .and_then(|offset| self.selector_stats.items.get(offset))
+    }
+
+    /// Hash of the transaction shown in the transaction view (if any)
+    fn selected_transaction_hash(&self) -> Option<TxHash> {
+        self.selected_transaction
+            .as_ref()
+            .map(|tx| *tx.inner.tx_hash())
+    }
+
+    fn get_selected_transaction(&self) -> Option<&Transaction> {
+        self.transactions.selected()
+    }
+}
+
+/// Render a single row of a transaction list
+fn transaction_line(
+    tx: &Transaction,
+    address_display_mode: AddressDisplayMode,
+) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(
+            format!(
+                "{:<4}",
+                tx.transaction_index
+                    .map_or("-".to_string(), |i| i.to_string())
+            ),
+            Style::new().bold(),
+        ),
+        Span::raw(format!(
+            "{:<16}",
+            format!("{}", utils::shorten_hash(tx.inner.tx_hash()))
+        )),
+        Span::raw(format!(
+            "{:<32}",
+            utils::label_address(
+                &tx.as_recovered().signer(),
+                true,
+                address_display_mode
+            )
+        )),
+        Span::raw(format!(
+            "{:<32}",
+            utils::label_address(
+                &tx.to().unwrap_or_default(),
+                true,
+                address_display_mode
+            )
+        )),
+        Span::raw(format!("{:<8}", tx.nonce())),
+        Span::raw(format!(
+            "{:<4}",
+            if tx.to().is_none() {
+                "📄".to_string()
+            } else {
+                "".to_string()
+            }
+        )),
+        Span::raw(format!(
+            "{:<20}",
+            match tx.to().and_then(|to| abi::function_name(&to, tx.input())) {
+                Some(name) => utils::shorten(&name, 19),
+                None => utils::human_readable_tx_data(tx.input().clone()),
+            }
+        )),
+        Span::raw(format!(
+            "{:<20}",
+            format!("{:.3} gwei", to_gwei(useful_gas_price(tx) as f64),)
+        )),
+    ])
+}
+
+/// Describe what's known about the source code of a contract
+fn verification_text(source: &Verification) -> String {
+    match source {
+        Verification::Unchecked => {
+            "not looked up (requires --sourcify or --etherscan-api-key)"
+                .to_string()
+        }
+        Verification::Pending => "looking up...".to_string(),
+        Verification::Unverified(verifier) => {
+            format!("not verified on {verifier}")
+        }
+        Verification::Verified(source) => format!(
+            "{} ({} {}, {} on {})",
+            source.name,
+            source.language,
+            source.compiler_version,
+            match source.exact {
+                Some(true) => "exact match",
+                Some(false) => "partial match",
+                None => "verified",
+            },
+            source.verifier
+        ),
+        Verification::Failed(e) => format!("lookup failed ({e})"),
+    }
+}
+
+/// Render a fee (in wei) in gwei, or in wei if it's less than a millionth of
+/// a gwei
+fn format_fee(fee: u128) -> String {
+    if fee < 1_000 {
+        format!("{fee} wei")
+    } else {
+        format!("{:.6} gwei", to_gwei(fee as f64))
+    }
+}
+
+/// Render a number of bytes using binary (i.e., KiB, MiB, etc.) units
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// Render a lag (in seconds) in the largest unit that fits
+fn format_lag(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3_600 => format!("{}m{}s", secs / 60, secs % 60),
+        _ => format!("{}h{}m", secs / 3_600, secs % 3_600 / 60),
+    }
+}
+
+/// Suggest slow, standard, and fast priority fees (in gwei) from the 10th,
+/// 50th, and 90th percentiles of the most recent blocks, respectively
+fn fee_suggestion(fee_stats: &[FeeStats]) -> Option<(f64, f64, f64)> {
+    let recent =
+        &fee_stats[fee_stats.len().saturating_sub(FEE_SUGGESTION_WINDOW)..];
+
+    if recent.is_empty() {
+        return None;
+    }
+
+    let mean = |percentile: fn(&FeeStats) -> u64| {
+        to_gwei(
+            recent
+                .iter()
+                .map(|stats| percentile(stats) as f64)
+                .sum::<f64>()
+                / recent.len() as f64,
+        )
+    };
+    Some((
+        mean(|stats| stats.p10),
+        mean(|stats| stats.p50),
+        mean(|stats| stats.p90),
+    ))
+}
+
+/// Render a horizontal bar representing `count` relative to `max`
+fn tx_count_bar(count: usize, max: usize) -> String {
+    const EIGHTHS: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+
+    if max == 0 {
+        return " ".repeat(TX_COUNT_BAR_WIDTH);
+    }
+
+    let eighths = count * TX_COUNT_BAR_WIDTH * 8 / max;
+    let remainder = eighths % 8;
+    let mut bar = "█".repeat(eighths / 8);
+
+    if remainder > 0 {
+        bar.push(EIGHTHS[remainder - 1]);
+    }
+
+    format!("{bar:<TX_COUNT_BAR_WIDTH$}")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::Location;
+
+    use super::*;
+
+    #[test]
+    fn test_several_blocks_between_ticks() {
+        let block = |number: u64| {
+            let mut header = Header::new(alloy::consensus::Header {
+                number,
+                parent_hash: BlockHash::with_last_byte(number as u8 - 1),
+                ..Default::default()
+            });
+            header.hash = BlockHash::with_last_byte(number as u8);
+            alloy::rpc::types::Block::new(header, Default::default())
+        };
+        let numbers = |app: &App| {
+            app.block_headers
+                .items
+                .iter()
+                .map(|header| header.number)
+                .collect::<Vec<_>>()
+        };
+        let db = Database::new(Location::Memory).unwrap();
+        let mut app = App::new(String::new(), Default::default(), None);
+
+        db.add_block_atomic(&block(1)).unwrap();
+        app.on_chain_update(&db);
+        assert_eq!(numbers(&app), vec![1]);
+
+        db.add_block_atomic(&block(2)).unwrap();
+        app.on_chain_update(&db);
+        assert_eq!(numbers(&app), vec![1, 2]);
+
+        /* e.g., whilst catching up, only the latest block is announced */
+        for number in 3..=6 {
+            db.add_block_atomic(&block(number)).unwrap();
+        }
+        app.on_chain_update(&db);
+        assert_eq!(numbers(&app), vec![1, 2, 3, 4, 5, 6]);
     }
 }
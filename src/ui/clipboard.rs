@@ -0,0 +1,137 @@
+//! Copying text to the system clipboard
+use std::{
+    env,
+    io::{self, Write},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// Programs that copy their standard input to the system clipboard, in order
+/// of preference, each along with the environment variable (if any) that
+/// must be set for it to reach the display server
+const CLIPBOARD_COMMANDS: [(&str, &[&str], Option<&str>); 5] = [
+    ("pbcopy", &[], None),
+    ("wl-copy", &[], Some("WAYLAND_DISPLAY")),
+    ("xclip", &["-selection", "clipboard"], Some("DISPLAY")),
+    ("xsel", &["--clipboard", "--input"], Some("DISPLAY")),
+    ("clip.exe", &[], None),
+];
+
+/// How long a clipboard program is given to take the text and exit before
+/// it's given up on
+const COPY_TIMEOUT: Duration = Duration::from_millis(500);
+/// How often a clipboard program is checked for having exited
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How text was copied to the clipboard
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Copied {
+    /// By a clipboard program
+    Program,
+    /// As an OSC 52 escape sequence, along with why each clipboard program
+    /// that was tried (if any) failed
+    Osc52(Option<String>),
+}
+
+/// Copy the given text to the system clipboard
+///
+/// Over SSH (or if no clipboard program is available, or each fails), the
+/// text is instead sent to the terminal emulator as an OSC 52 escape
+/// sequence, which most modern terminals forward to the local clipboard.
+pub fn copy(text: &str) -> eyre::Result<Copied> {
+    let remote = env::var_os("SSH_TTY").is_some()
+        || env::var_os("SSH_CONNECTION").is_some();
+    let mut failures = vec![];
+
+    if !remote {
+        for (program, args, _) in
+            CLIPBOARD_COMMANDS.iter().filter(|(_, _, var)| {
+                var.is_none_or(|var| env::var_os(var).is_some())
+            })
+        {
+            match pipe_to(program, args, text) {
+                Ok(()) => return Ok(Copied::Program),
+                /* not installed */
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => failures.push(format!("{program}: {e}")),
+            }
+        }
+    }
+
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+    stdout.flush()?;
+    Ok(Copied::Osc52(
+        (!failures.is_empty()).then(|| failures.join("; ")),
+    ))
+}
+
+/// Run the given program, feeding it the given text on its standard input,
+/// and wait (for at most [`COPY_TIMEOUT`]) for it to exit successfully
+fn pipe_to(program: &str, args: &[&str], text: &str) -> io::Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut stdin = child
+        .stdin
+        .take()
+        .expect("invariant violated: child must have piped stdin");
+    let text = text.to_string();
+    /* written from a thread of its own so that a program that stops reading
+     * can't block the write past the deadline (dropping stdin closes it,
+     * signalling the end of the input) */
+    let writer = thread::spawn(move || stdin.write_all(text.as_bytes()));
+
+    let deadline = Instant::now() + COPY_TIMEOUT;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            /* killing it also unblocks the writer */
+            child.kill().ok();
+            child.wait().ok();
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "timed out"));
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    writer
+        .join()
+        .expect("invariant violated: writing to a pipe can't panic")?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(status.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipe_to() {
+        assert!(pipe_to("cat", &[], "0xabcd").is_ok());
+        assert!(pipe_to("false", &[], "0xabcd").is_err());
+        assert_eq!(
+            pipe_to("blocktop-no-such-program", &[], "0xabcd")
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::NotFound
+        );
+
+        let started = Instant::now();
+        assert_eq!(
+            pipe_to("sleep", &["5"], "0xabcd").unwrap_err().kind(),
+            io::ErrorKind::TimedOut
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,286 @@
+//! Commands accepted by the `:` command palette
+use std::{fmt, str::FromStr};
+
+use alloy::{
+    eips::BlockHashOrNumber,
+    primitives::{Address, BlockNumber, TxHash},
+};
+
+use clap::ValueEnum;
+
+use super::preimage::Preimage;
+use crate::{
+    db::{CalldataPattern, LogQuery},
+    export::Format,
+    services::blockchain::ResetAction,
+};
+
+/// Formats that the currently viewed object can be exported in
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExportFormat {
+    Json,
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// A single command entered into the command palette
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// Open the block with the given hash or number
+    Block(BlockHashOrNumber),
+    /// Open the transaction with the given hash
+    Transaction(TxHash),
+    /// Filter indexed transactions by calldata
+    Filter(CalldataPattern),
+    /// List indexed transactions involving the given address
+    Account(Address),
+    /// Show the code and storage of the contract at the given address
+    Contract(Address),
+    /// Search indexed event logs by signature
+    Logs(LogQuery),
+    /// Highlight transactions involving the given address
+    Watch(Address),
+    /// Stop highlighting transactions involving the given address
+    Unwatch(Address),
+    /// Persistently label the given address
+    Label(Address, String),
+    /// Remove the user-defined label of the given address
+    Unlabel(Address),
+    /// Follow the given transaction until it's finalized
+    Track(TxHash),
+    /// Write the currently viewed block or transaction to a file
+    Export(ExportFormat),
+    /// Write the pending transactions seen so far to a file
+    ExportMempool(Format),
+    /// Show the Keccak-256 hash of the given preimage
+    Keccak(Preimage),
+    /// Open the SQL console, running the given read-only query (if any)
+    Sql(Option<String>),
+    /// Re-fork an Anvil node from its fork source at the given block (or
+    /// the latest one)
+    ResetFork(Option<BlockNumber>),
+    /// Deal with the chain having been reset (so that indexing resumes)
+    ResolveReset(ResetAction),
+    /// Exit the application
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let name = words.next().ok_or("empty command")?;
+
+        if matches!(name, "logs" | "l") {
+            return Ok(Self::Logs(
+                words.collect::<Vec<_>>().join(" ").parse()?,
+            ));
+        }
+
+        if name == "sql" {
+            /* taken verbatim, as whitespace may be significant (e.g., within
+             * string literals) */
+            let query = s.trim_start()[name.len()..].trim();
+            return Ok(Self::Sql(
+                (!query.is_empty()).then(|| query.to_string()),
+            ));
+        }
+
+        if name == "slot" {
+            return Ok(Self::Keccak(Preimage::mapping_slot(
+                &words.collect::<Vec<_>>(),
+            )?));
+        }
+
+        if name == "topic" {
+            /* canonical signatures don't contain whitespace */
+            let signature = words.collect::<String>();
+            if signature.is_empty() {
+                return Err("topic requires a signature".to_string());
+            }
+            return Ok(Self::Keccak(Preimage::Signature(signature)));
+        }
+
+        if name == "label" {
+            let address = words
+                .next()
+                .ok_or("label requires an address")?
+                .parse()
+                .map_err(|_| "invalid address")?;
+            let label = words.collect::<Vec<_>>().join(" ");
+            if label.is_empty() {
+                return Err("label requires a label".to_string());
+            }
+            return Ok(Self::Label(address, label));
+        }
+
+        let arg = words.next();
+
+        if words.next().is_some() {
+            return Err("too many arguments".to_string());
+        }
+
+        let required =
+            || arg.ok_or_else(|| format!("{name} requires an argument"));
+
+        match name {
+            "block" | "b" => Ok(Self::Block(
+                required()?
+                    .parse()
+                    .map_err(|_| "invalid block hash or number")?,
+            )),
+            "tx" | "transaction" | "t" => Ok(Self::Transaction(
+                required()?
+                    .parse()
+                    .map_err(|_| "invalid transaction hash")?,
+            )),
+            "filter" | "f" => Ok(Self::Filter(
+                required()?.parse().map_err(|_| "invalid hex pattern")?,
+            )),
+            "account" | "a" => Ok(Self::Account(
+                required()?.parse().map_err(|_| "invalid address")?,
+            )),
+            "contract" | "code" => Ok(Self::Contract(
+                required()?.parse().map_err(|_| "invalid address")?,
+            )),
+            "watch" | "w" => Ok(Self::Watch(
+                required()?.parse().map_err(|_| "invalid address")?,
+            )),
+            "unwatch" => Ok(Self::Unwatch(
+                required()?.parse().map_err(|_| "invalid address")?,
+            )),
+            "track" => Ok(Self::Track(
+                required()?
+                    .parse()
+                    .map_err(|_| "invalid transaction hash")?,
+            )),
+            "unlabel" => Ok(Self::Unlabel(
+                required()?.parse().map_err(|_| "invalid address")?,
+            )),
+            "export" => match arg.unwrap_or("json") {
+                "json" => Ok(Self::Export(ExportFormat::Json)),
+                other => Err(format!("unsupported export format: {other}")),
+            },
+            "mempool" => Ok(Self::ExportMempool(
+                arg.map(|format| Format::from_str(format, true))
+                    .transpose()
+                    .map_err(|_| "unsupported export format")?
+                    .unwrap_or_default(),
+            )),
+            "reset" => Ok(Self::ResetFork(
+                arg.map(|number| number.parse())
+                    .transpose()
+                    .map_err(|_| "invalid block number")?,
+            )),
+            "wipe" => Ok(Self::ResolveReset(ResetAction::Wipe)),
+            "archive" => Ok(Self::ResolveReset(ResetAction::Archive)),
+            "quit" | "q" => Ok(Self::Quit),
+            other => Err(format!("unknown command: {other}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::B256;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(
+            "block 19000000".parse::<Command>(),
+            Ok(Command::Block(BlockHashOrNumber::Number(19_000_000)))
+        );
+        assert_eq!(
+            "tx 0x0000000000000000000000000000000000000000000000000000000000000001"
+                .parse::<Command>(),
+            Ok(Command::Transaction(TxHash::with_last_byte(1)))
+        );
+        assert_eq!(
+            "track 0x0000000000000000000000000000000000000000000000000000000000000001"
+                .parse::<Command>(),
+            Ok(Command::Track(TxHash::with_last_byte(1)))
+        );
+        assert_eq!(
+            "export json".parse::<Command>(),
+            Ok(Command::Export(ExportFormat::Json))
+        );
+        assert_eq!(
+            "logs 0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef 19000000..19000100"
+                .parse::<Command>(),
+            Ok(Command::Logs(LogQuery {
+                topic0: "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+                    .parse()
+                    .unwrap(),
+                address: None,
+                from_block: Some(19_000_000),
+                to_block: Some(19_000_100),
+            }))
+        );
+        assert_eq!(
+            "label 0x0000000000000000000000000000000000000001 My Hot Wallet"
+                .parse::<Command>(),
+            Ok(Command::Label(
+                Address::with_last_byte(1),
+                "My Hot Wallet".to_string()
+            ))
+        );
+        assert_eq!(
+            "account 0x0000000000000000000000000000000000000001"
+                .parse::<Command>(),
+            Ok(Command::Account(Address::with_last_byte(1)))
+        );
+        assert_eq!(
+            "code 0x0000000000000000000000000000000000000001"
+                .parse::<Command>(),
+            Ok(Command::Contract(Address::with_last_byte(1)))
+        );
+        assert_eq!(
+            "topic Transfer(address, address, uint256)".parse::<Command>(),
+            Ok(Command::Keccak(Preimage::Signature(
+                "Transfer(address,address,uint256)".to_string()
+            )))
+        );
+        assert_eq!(
+            "slot 0x01 2".parse::<Command>(),
+            Ok(Command::Keccak(Preimage::MappingSlot {
+                keys: vec![B256::with_last_byte(1)],
+                slot: B256::with_last_byte(2),
+            }))
+        );
+        assert!("slot 2".parse::<Command>().is_err());
+        assert_eq!(
+            "mempool csv".parse::<Command>(),
+            Ok(Command::ExportMempool(Format::Csv))
+        );
+        assert_eq!(
+            "mempool".parse::<Command>(),
+            Ok(Command::ExportMempool(Format::Json))
+        );
+        assert_eq!("reset".parse::<Command>(), Ok(Command::ResetFork(None)));
+        assert_eq!(
+            "reset 19000000".parse::<Command>(),
+            Ok(Command::ResetFork(Some(19_000_000)))
+        );
+        assert!("reset latest".parse::<Command>().is_err());
+        assert_eq!(
+            "archive".parse::<Command>(),
+            Ok(Command::ResolveReset(ResetAction::Archive))
+        );
+        assert_eq!(
+            "sql SELECT 'a  b' FROM labels ".parse::<Command>(),
+            Ok(Command::Sql(Some("SELECT 'a  b' FROM labels".to_string())))
+        );
+        assert_eq!("sql".parse::<Command>(), Ok(Command::Sql(None)));
+        assert!("watch".parse::<Command>().is_err());
+        assert!("frobnicate".parse::<Command>().is_err());
+    }
+}
@@ -0,0 +1,191 @@
+//! Scrollable hex dump of arbitrary bytes (e.g., calldata)
+use std::ops::Range;
+
+use ratatui::{
+    layout::Rect,
+    style::{Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, Paragraph},
+    Frame,
+};
+
+use crate::ui::theme::Theme;
+
+const BYTES_PER_ROW: usize = 16;
+/// Size of an EVM word (e.g., an ABI-encoded argument)
+const WORD_SIZE: usize = 32;
+const ROWS_PER_WORD: usize = WORD_SIZE / BYTES_PER_ROW;
+/// Width of a hex dump (including its borders)
+pub const WIDTH: u16 = 80;
+
+/// Hex dump with an offset column and a printable-ASCII gutter, in which a
+/// single 32-byte word can be selected
+#[derive(Clone, Debug, Default)]
+pub struct HexView {
+    /// First row displayed
+    scroll: usize,
+    /// Index of the selected word (if any)
+    pub word: Option<usize>,
+}
+
+impl HexView {
+    /// Select the next word of the given number of bytes
+    pub fn next_word(&mut self, len: usize) {
+        self.advance(len, 1);
+    }
+
+    pub fn previous_word(&mut self) {
+        self.word = self.word.map(|word| word.saturating_sub(1));
+    }
+
+    /// Select the word a page further on, given the number of rows per page
+    pub fn page_down(&mut self, len: usize, rows: usize) {
+        self.advance(len, (rows / ROWS_PER_WORD).max(1));
+    }
+
+    /// Select the word a page back, given the number of rows per page
+    pub fn page_up(&mut self, rows: usize) {
+        self.word = self
+            .word
+            .map(|word| word.saturating_sub((rows / ROWS_PER_WORD).max(1)));
+    }
+
+    pub fn first(&mut self, len: usize) {
+        self.word = (len > 0).then_some(0);
+    }
+
+    pub fn last(&mut self, len: usize) {
+        self.word = len.div_ceil(WORD_SIZE).checked_sub(1);
+    }
+
+    fn advance(&mut self, len: usize, words: usize) {
+        let last = match len.div_ceil(WORD_SIZE).checked_sub(1) {
+            Some(last) => last,
+            None => return,
+        };
+        self.word = Some(match self.word {
+            Some(word) => (word + words).min(last),
+            None => 0,
+        });
+    }
+
+    /// Draw the given bytes within the given [`Block`], scrolling as needed
+    /// to keep the selected word in view
+    pub fn draw(
+        &mut self,
+        bytes: &[u8],
+        frame: &mut Frame,
+        area: Rect,
+        block: Block,
+        theme: &Theme,
+    ) {
+        let height = block.inner(area).height as usize;
+        let rows = bytes.len().div_ceil(BYTES_PER_ROW);
+
+        if let Some(word) = self.word {
+            let first = word * ROWS_PER_WORD;
+            if first < self.scroll {
+                self.scroll = first;
+            } else if first + ROWS_PER_WORD > self.scroll + height {
+                self.scroll = (first + ROWS_PER_WORD).saturating_sub(height);
+            }
+        }
+        self.scroll = self.scroll.min(rows.saturating_sub(height));
+
+        let selected = self
+            .word
+            .map(|word| word * WORD_SIZE..(word + 1) * WORD_SIZE)
+            .unwrap_or_default();
+        let lines: Vec<Line> = (self.scroll..rows.min(self.scroll + height))
+            .map(|row| hex_row(bytes, row, &selected, theme.highlight))
+            .collect();
+        frame.render_widget(
+            Paragraph::new(Text::from(lines)).block(block),
+            area,
+        );
+    }
+}
+
+/// Render a single row of the hex dump, highlighting the bytes within the
+/// given range
+fn hex_row(
+    bytes: &[u8],
+    row: usize,
+    selected: &Range<usize>,
+    highlight: Style,
+) -> Line<'static> {
+    let start = row * BYTES_PER_ROW;
+    let chunk = &bytes[start..(start + BYTES_PER_ROW).min(bytes.len())];
+    let style = |i: usize| {
+        if selected.contains(&(start + i)) {
+            highlight
+        } else {
+            Style::new()
+        }
+    };
+    let mut spans = vec![
+        Span::styled(format!("{start:08x}"), Style::new().underlined()),
+        Span::raw("  "),
+    ];
+
+    for i in 0..BYTES_PER_ROW {
+        if i == BYTES_PER_ROW / 2 {
+            spans.push(Span::raw(" "));
+        }
+
+        spans.push(match chunk.get(i) {
+            Some(byte) => Span::styled(format!("{byte:02x}"), style(i)),
+            None => Span::raw("  "),
+        });
+        spans.push(Span::raw(" "));
+    }
+
+    spans.push(Span::raw(" |"));
+    spans.extend(chunk.iter().enumerate().map(|(i, &byte)| {
+        let c = if byte.is_ascii_graphic() || byte == b' ' {
+            byte as char
+        } else {
+            '.'
+        };
+        Span::styled(c.to_string(), style(i))
+    }));
+    spans.push(Span::raw("|"));
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_row() {
+        let bytes: Vec<u8> = b"transfer(address,uint256)".to_vec();
+        let render =
+            |row| hex_row(&bytes, row, &(0..0), Style::new()).to_string();
+        assert_eq!(
+            render(0),
+            "00000000  74 72 61 6e 73 66 65 72  28 61 64 64 72 65 73 73  |transfer(address|"
+        );
+        assert_eq!(
+            render(1),
+            "00000010  2c 75 69 6e 74 32 35 36  29                       |,uint256)|"
+        );
+    }
+
+    #[test]
+    fn test_word_selection() {
+        let mut view = HexView::default();
+        view.previous_word();
+        assert_eq!(view.word, None);
+        view.next_word(0);
+        assert_eq!(view.word, None);
+        view.next_word(68);
+        assert_eq!(view.word, Some(0));
+        view.page_down(68, 4);
+        assert_eq!(view.word, Some(2));
+        view.next_word(68);
+        assert_eq!(view.word, Some(2));
+        view.page_up(4);
+        assert_eq!(view.word, Some(0));
+    }
+}
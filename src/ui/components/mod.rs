@@ -1 +1,7 @@
+pub mod hex_view;
+pub mod prompt;
+pub mod source_view;
+pub mod sql_console;
 pub mod stateful_list;
+pub mod transaction_list;
+pub mod txpool_view;
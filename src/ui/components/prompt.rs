@@ -0,0 +1,55 @@
+use ratatui::{
+    layout::Rect,
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+
+use crate::ui::theme::Theme;
+
+/// Single-line text input rendered at the bottom of the screen
+#[derive(Clone, Debug, Default)]
+pub struct Prompt {
+    pub label: String,
+    pub input: String,
+    pub error: Option<String>,
+}
+
+impl Prompt {
+    pub fn new(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            input: String::new(),
+            error: None,
+        }
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.error = None;
+        self.input.push(c);
+    }
+
+    pub fn pop(&mut self) {
+        self.error = None;
+        self.input.pop();
+    }
+
+    pub fn draw(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let mut spans = vec![
+            Span::styled(format!("{} ", self.label), Style::new().bold()),
+            Span::raw(self.input.clone()),
+            Span::styled("█", Style::new().fg(theme.muted)),
+        ];
+
+        if let Some(error) = &self.error {
+            spans.push(Span::styled(
+                format!("  ({error})"),
+                Style::new().fg(theme.error),
+            ));
+        }
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(Paragraph::new(Line::from(spans)), area);
+    }
+}
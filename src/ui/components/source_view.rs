@@ -0,0 +1,355 @@
+//! Scrollable source code with (rudimentary) syntax highlighting
+use ratatui::{
+    layout::Rect,
+    style::{Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, Paragraph},
+    Frame,
+};
+
+use crate::{ui::theme::Theme, verification::SourceFile};
+
+const SOLIDITY_KEYWORDS: &[&str] = &[
+    "abstract",
+    "anonymous",
+    "as",
+    "assembly",
+    "break",
+    "calldata",
+    "catch",
+    "constant",
+    "constructor",
+    "continue",
+    "contract",
+    "delete",
+    "do",
+    "else",
+    "emit",
+    "enum",
+    "error",
+    "event",
+    "external",
+    "fallback",
+    "false",
+    "for",
+    "function",
+    "if",
+    "immutable",
+    "import",
+    "indexed",
+    "interface",
+    "internal",
+    "is",
+    "library",
+    "memory",
+    "modifier",
+    "new",
+    "override",
+    "payable",
+    "pragma",
+    "private",
+    "public",
+    "pure",
+    "receive",
+    "return",
+    "returns",
+    "revert",
+    "storage",
+    "struct",
+    "true",
+    "try",
+    "type",
+    "unchecked",
+    "using",
+    "view",
+    "virtual",
+    "while",
+];
+const VYPER_KEYWORDS: &[&str] = &[
+    "and",
+    "assert",
+    "break",
+    "constant",
+    "continue",
+    "def",
+    "elif",
+    "else",
+    "empty",
+    "event",
+    "False",
+    "for",
+    "if",
+    "immutable",
+    "implements",
+    "import",
+    "in",
+    "indexed",
+    "interface",
+    "log",
+    "not",
+    "or",
+    "pass",
+    "raise",
+    "return",
+    "self",
+    "struct",
+    "True",
+];
+const TYPES: &[&str] = &[
+    "address", "bool", "byte", "bytes", "decimal", "DynArray", "HashMap",
+    "int", "mapping", "string", "String", "uint",
+];
+
+/// What a fragment of source code is (for highlighting)
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Kind {
+    Plain,
+    Keyword,
+    Type,
+    /// Strings and numbers
+    Literal,
+    Comment,
+}
+
+/// Source code of a contract, highlighted once up front and scrolled a line
+/// at a time
+#[derive(Clone, Debug, Default)]
+pub struct SourceView {
+    lines: Vec<Line<'static>>,
+    /// First line displayed
+    scroll: usize,
+}
+
+impl SourceView {
+    /// Highlight the given files (written in the given language), headed by
+    /// their paths
+    pub fn new(files: &[SourceFile], language: &str, theme: &Theme) -> Self {
+        let vyper = language.eq_ignore_ascii_case("vyper");
+        let style = |kind: Kind| match kind {
+            Kind::Plain => Style::new(),
+            Kind::Keyword => Style::new().fg(theme.secondary).bold(),
+            Kind::Type => Style::new().fg(theme.tertiary),
+            Kind::Literal => Style::new().fg(theme.primary),
+            Kind::Comment => Style::new().fg(theme.muted).italic(),
+        };
+        let mut lines = vec![];
+
+        for file in files {
+            lines.push(Line::from(Span::styled(
+                file.path.clone(),
+                Style::new().bold().reversed(),
+            )));
+            let mut in_comment = false;
+
+            for (i, line) in file.content.lines().enumerate() {
+                let mut spans = vec![Span::styled(
+                    format!("{:>5} ", i + 1),
+                    Style::new().fg(theme.muted),
+                )];
+                spans.extend(
+                    tokens(line, vyper, &mut in_comment)
+                        .into_iter()
+                        .map(|(kind, text)| Span::styled(text, style(kind))),
+                );
+                lines.push(Line::from(spans));
+            }
+
+            lines.push(Line::default());
+        }
+
+        Self { lines, scroll: 0 }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn next(&mut self) {
+        self.scroll = (self.scroll + 1).min(self.lines.len().saturating_sub(1));
+    }
+
+    pub fn previous(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+
+    pub fn page_down(&mut self, rows: usize) {
+        self.scroll =
+            (self.scroll + rows).min(self.lines.len().saturating_sub(1));
+    }
+
+    pub fn page_up(&mut self, rows: usize) {
+        self.scroll = self.scroll.saturating_sub(rows);
+    }
+
+    pub fn first(&mut self) {
+        self.scroll = 0;
+    }
+
+    pub fn last(&mut self) {
+        self.scroll = self.lines.len().saturating_sub(1);
+    }
+
+    /// Draw the visible lines within the given [`Block`]
+    pub fn draw(&self, frame: &mut Frame, area: Rect, block: Block) {
+        let height = block.inner(area).height as usize;
+        let end = (self.scroll + height).min(self.lines.len());
+        frame.render_widget(
+            Paragraph::new(Text::from(
+                self.lines[self.scroll.min(end)..end].to_vec(),
+            ))
+            .block(block),
+            area,
+        );
+    }
+}
+
+/// Split a single line of Solidity (or Vyper) into fragments to highlight,
+/// tracking whether a block comment is left open at the end of it
+fn tokens(
+    line: &str,
+    vyper: bool,
+    in_comment: &mut bool,
+) -> Vec<(Kind, String)> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens: Vec<(Kind, String)> = vec![];
+    let mut push = |kind: Kind, text: String| match tokens.last_mut() {
+        Some((last, existing)) if *last == kind => existing.push_str(&text),
+        _ => tokens.push((kind, text)),
+    };
+    let mut i = 0;
+
+    while i < chars.len() {
+        let start = i;
+
+        let kind = if *in_comment {
+            continue_comment(&chars, &mut i, in_comment);
+            Kind::Comment
+        } else if (!vyper && at(&chars, i, "//")) || (vyper && chars[i] == '#')
+        {
+            i = chars.len();
+            Kind::Comment
+        } else if !vyper && at(&chars, i, "/*") {
+            *in_comment = true;
+            i += 2;
+            continue_comment(&chars, &mut i, in_comment);
+            Kind::Comment
+        } else if chars[i] == '"' || chars[i] == '\'' {
+            let quote = chars[i];
+            i += 1;
+            while i < chars.len() && chars[i] != quote {
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            i = (i + 1).min(chars.len());
+            Kind::Literal
+        } else if chars[i].is_ascii_digit() {
+            while i < chars.len()
+                && (chars[i].is_ascii_alphanumeric() || chars[i] == '_')
+            {
+                i += 1;
+            }
+            Kind::Literal
+        } else if chars[i].is_alphabetic() || chars[i] == '_' {
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || chars[i] == '_')
+            {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let keywords = if vyper {
+                VYPER_KEYWORDS
+            } else {
+                SOLIDITY_KEYWORDS
+            };
+            if keywords.contains(&word.as_str()) {
+                Kind::Keyword
+            } else if TYPES
+                .contains(&word.trim_end_matches(|c: char| c.is_ascii_digit()))
+            {
+                Kind::Type
+            } else {
+                Kind::Plain
+            }
+        } else {
+            i += 1;
+            Kind::Plain
+        };
+
+        push(kind, chars[start..i.min(chars.len())].iter().collect());
+    }
+
+    tokens
+}
+
+/// Whether the given text appears at the given position
+fn at(chars: &[char], i: usize, text: &str) -> bool {
+    chars[i..].iter().copied().take(text.len()).eq(text.chars())
+}
+
+/// Advance past the rest of a block comment (if it ends on this line)
+fn continue_comment(chars: &[char], i: &mut usize, in_comment: &mut bool) {
+    while *i < chars.len() {
+        if at(chars, *i, "*/") {
+            *in_comment = false;
+            *i += 2;
+            return;
+        }
+        *i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens() {
+        let mut in_comment = false;
+        assert_eq!(
+            tokens(
+                "uint256 public x = 0x10; // \"not a string\"",
+                false,
+                &mut in_comment
+            ),
+            vec![
+                (Kind::Type, "uint256".to_string()),
+                (Kind::Plain, " ".to_string()),
+                (Kind::Keyword, "public".to_string()),
+                (Kind::Plain, " x = ".to_string()),
+                (Kind::Literal, "0x10".to_string()),
+                (Kind::Plain, "; ".to_string()),
+                (Kind::Comment, "// \"not a string\"".to_string()),
+            ]
+        );
+        assert!(!in_comment);
+
+        assert_eq!(
+            tokens("emit Log(\"a\"); /* spans", false, &mut in_comment),
+            vec![
+                (Kind::Keyword, "emit".to_string()),
+                (Kind::Plain, " Log(".to_string()),
+                (Kind::Literal, "\"a\"".to_string()),
+                (Kind::Plain, "); ".to_string()),
+                (Kind::Comment, "/* spans".to_string()),
+            ]
+        );
+        assert!(in_comment);
+        assert_eq!(
+            tokens("lines */ bool", false, &mut in_comment),
+            vec![
+                (Kind::Comment, "lines */".to_string()),
+                (Kind::Plain, " ".to_string()),
+                (Kind::Type, "bool".to_string()),
+            ]
+        );
+        assert!(!in_comment);
+
+        assert_eq!(
+            tokens("def f(): # uint256", true, &mut in_comment),
+            vec![
+                (Kind::Keyword, "def".to_string()),
+                (Kind::Plain, " f(): ".to_string()),
+                (Kind::Comment, "# uint256".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Results of ad hoc SQL queries against the database, with a history of the
+//! queries run
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, Paragraph},
+    Frame,
+};
+
+use super::stateful_list::StatefulList;
+use crate::{db::QueryResult, ui::theme::Theme};
+
+/// Widest a column is drawn (in characters), beyond which values are cut
+/// short
+const MAX_COLUMN_WIDTH: usize = 48;
+/// Space between adjacent columns
+const COLUMN_GAP: usize = 2;
+
+#[derive(Clone, Debug, Default)]
+pub struct SqlConsole {
+    /// Query whose results are shown (if any)
+    pub query: Option<String>,
+    pub columns: Vec<String>,
+    pub rows: StatefulList<Vec<String>>,
+    /// Whether the query returned more rows than are shown
+    pub truncated: bool,
+    /// Queries entered so far, oldest first
+    history: Vec<String>,
+    /// Position within the history of the query being recalled (if any)
+    recalled: Option<usize>,
+}
+
+impl SqlConsole {
+    /// Add the given query to the history (unless it was also the last one
+    /// entered)
+    pub fn record(&mut self, query: &str) {
+        if self.history.last().map(String::as_str) != Some(query) {
+            self.history.push(query.to_string());
+        }
+        self.recalled = None;
+    }
+
+    /// Show the results of the given query
+    pub fn show(&mut self, query: String, result: QueryResult) {
+        self.query = Some(query);
+        self.columns = result.columns;
+        self.truncated = result.truncated;
+        self.rows = StatefulList::with_items(result.rows);
+        if !self.rows.items.is_empty() {
+            self.rows.state.select(Some(0));
+        }
+    }
+
+    /// Start recalling queries from the most recent one again
+    pub fn reset_recall(&mut self) {
+        self.recalled = None;
+    }
+
+    /// The query entered before the one being recalled (or the most recent
+    /// one), if there is one
+    pub fn recall_previous(&mut self) -> Option<&str> {
+        let i = match self.recalled {
+            Some(i) => i.saturating_sub(1),
+            None => self.history.len().checked_sub(1)?,
+        };
+        self.recalled = Some(i);
+        self.history.get(i).map(String::as_str)
+    }
+
+    /// The query entered after the one being recalled, or `None` once the
+    /// most recent query has been passed (i.e., when back to a new query)
+    pub fn recall_next(&mut self) -> Option<&str> {
+        self.recalled = self
+            .recalled
+            .map(|i| i + 1)
+            .filter(|i| *i < self.history.len());
+        self.recalled.map(|i| self.history[i].as_str())
+    }
+
+    pub fn selected_row(&self) -> Option<&Vec<String>> {
+        self.rows
+            .state
+            .selected()
+            .and_then(|i| self.rows.items.get(i))
+    }
+
+    /// Width of each column, as wide as its widest value (within reason)
+    fn widths(&self) -> Vec<usize> {
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                self.rows
+                    .items
+                    .iter()
+                    .filter_map(|row| row.get(i))
+                    .chain([column])
+                    .map(|value| value.chars().count())
+                    .max()
+                    .unwrap_or_default()
+                    .min(MAX_COLUMN_WIDTH)
+            })
+            .collect()
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let title = match &self.query {
+            Some(_) if self.truncated => format!(
+                "SQL ({} rows shown, more omitted)",
+                self.rows.items.len()
+            ),
+            Some(_) => format!("SQL ({} rows)", self.rows.items.len()),
+            None => "SQL".to_string(),
+        };
+        let block = Block::bordered()
+            .title(Line::from(title).centered())
+            .border_style(theme.border);
+        let chunks = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Min(0),
+        ])
+        .split(block.inner(area));
+        frame.render_widget(block, area);
+
+        frame.render_widget(
+            Paragraph::new(match &self.query {
+                Some(query) => Line::from(Span::raw(query.clone())),
+                None => Line::from(Span::styled(
+                    "Press Enter to run a read-only query (Up/Down recall \
+                     earlier ones)",
+                    Style::new().italic(),
+                )),
+            }),
+            chunks[0],
+        );
+
+        let widths = self.widths();
+        let format_row = |values: &[String]| -> String {
+            values
+                .iter()
+                .zip(&widths)
+                .map(|(value, width)| {
+                    format!("{:<width$}", truncate(value, *width))
+                })
+                .collect::<Vec<_>>()
+                .join(&" ".repeat(COLUMN_GAP))
+        };
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(
+                format!("  {}", format_row(&self.columns)),
+                Style::new().bold(),
+            ))),
+            chunks[1],
+        );
+        let rows: Vec<ListItem> = self
+            .rows
+            .items
+            .iter()
+            .map(|row| ListItem::new(format_row(row)))
+            .collect();
+        frame.render_stateful_widget(
+            List::new(rows)
+                .highlight_style(theme.highlight)
+                .highlight_symbol("> "),
+            chunks[2],
+            &mut self.rows.state,
+        );
+    }
+}
+
+/// Cut the given value short (with an ellipsis) if it's wider than the given
+/// width
+fn truncate(value: &str, width: usize) -> String {
+    if value.chars().count() <= width {
+        value.to_string()
+    } else {
+        value
+            .chars()
+            .take(width.saturating_sub(1))
+            .chain(['…'])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recall() {
+        let mut console = SqlConsole::default();
+        assert_eq!(console.recall_previous(), None);
+        console.record("SELECT 1");
+        console.record("SELECT 2");
+        console.record("SELECT 2");
+        assert_eq!(console.recall_previous(), Some("SELECT 2"));
+        assert_eq!(console.recall_previous(), Some("SELECT 1"));
+        assert_eq!(console.recall_previous(), Some("SELECT 1"));
+        assert_eq!(console.recall_next(), Some("SELECT 2"));
+        assert_eq!(console.recall_next(), None);
+        assert_eq!(console.recall_previous(), Some("SELECT 2"));
+        console.reset_recall();
+        assert_eq!(console.recall_next(), None);
+    }
+
+    #[test]
+    fn test_widths() {
+        let mut console = SqlConsole::default();
+        console.show(
+            "SELECT number, hash FROM block_headers".to_string(),
+            QueryResult {
+                columns: vec!["number".to_string(), "hash".to_string()],
+                rows: vec![vec!["19000000".to_string(), "0x".repeat(40)]],
+                truncated: false,
+            },
+        );
+        assert_eq!(console.widths(), vec![8, MAX_COLUMN_WIDTH]);
+        assert_eq!(console.selected_row().map(Vec::len), Some(2));
+        assert_eq!(truncate("abcdef", 4), "abc…");
+        assert_eq!(truncate("abc", 4), "abc");
+    }
+}
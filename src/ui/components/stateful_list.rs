@@ -1,4 +1,6 @@
-use ratatui::widgets::ListState;
+use std::ops::Range;
+
+use ratatui::widgets::{ListState, ScrollbarState};
 
 #[derive(Clone, Debug, Default)]
 pub struct StatefulList<T> {
@@ -32,6 +34,119 @@ impl<T> StatefulList<T> {
         self.state.select(Some(i));
     }
 
+    /// Move the selection forward by the given number of items, stopping at
+    /// the last item
+    pub fn page_down(&mut self, page: usize) {
+        if let Some(last) = self.items.len().checked_sub(1) {
+            let i = self.state.selected().map_or(0, |i| i.saturating_add(page));
+            self.state.select(Some(i.min(last)));
+        }
+    }
+
+    /// Move the selection back by the given number of items, stopping at the
+    /// first item
+    pub fn page_up(&mut self, page: usize) {
+        if !self.items.is_empty() {
+            let i = self.state.selected().map_or(0, |i| i.saturating_sub(page));
+            self.state.select(Some(i));
+        }
+    }
+
+    /// Insert the given items before the first item, keeping the same item
+    /// selected
+    pub fn prepend(&mut self, items: Vec<T>) {
+        let count = items.len();
+        self.items.splice(0..0, items);
+        *self.state.offset_mut() += count;
+        if let Some(i) = self.state.selected_mut() {
+            *i += count;
+        }
+    }
+
+    /// Remove (up to) the given number of items from the front, keeping the
+    /// same item selected if it remains (and the first item otherwise)
+    pub fn drain_front(&mut self, count: usize) {
+        let count = count.min(self.items.len());
+        self.items.drain(..count);
+        *self.state.offset_mut() = self.state.offset().saturating_sub(count);
+        if let Some(i) = self.state.selected_mut() {
+            *i = i.saturating_sub(count);
+        }
+    }
+
+    /// Remove every item beyond the given number, selecting the last
+    /// remaining item if the selected one is removed
+    pub fn truncate(&mut self, len: usize) {
+        self.items.truncate(len);
+        let last = len.saturating_sub(1);
+        if let Some(i) = self.state.selected_mut() {
+            *i = (*i).min(last);
+        }
+        *self.state.offset_mut() = self.state.offset().min(last);
+    }
+
+    pub fn first(&mut self) {
+        if !self.items.is_empty() {
+            self.state.select(Some(0));
+        }
+    }
+
+    pub fn last(&mut self) {
+        if let Some(last) = self.items.len().checked_sub(1) {
+            self.state.select(Some(last));
+        }
+    }
+
+    /// Scroll the list (as little as possible) so that the selected item fits
+    /// within the given number of rows, returning the range of items that
+    /// are visible
+    pub fn viewport(&mut self, rows: usize) -> Range<usize> {
+        let rows = rows.max(1);
+        let mut offset = self.state.offset();
+
+        if let Some(i) = self.state.selected() {
+            if i < offset {
+                offset = i;
+            } else if i >= offset + rows {
+                offset = i + 1 - rows;
+            }
+        }
+
+        offset = offset.min(self.items.len().saturating_sub(rows));
+        *self.state.offset_mut() = offset;
+        offset..(offset + rows).min(self.items.len())
+    }
+
+    /// State for rendering only the items returned by [`Self::viewport`]
+    pub fn viewport_state(&self) -> ListState {
+        ListState::default().with_selected(
+            self.state
+                .selected()
+                .map(|i| i.saturating_sub(self.state.offset())),
+        )
+    }
+
+    /// State for drawing a scrollbar alongside the list
+    pub fn scrollbar_state(&self) -> ScrollbarState {
+        ScrollbarState::new(self.items.len())
+            .position(self.state.selected().unwrap_or(self.state.offset()))
+    }
+
+    /// Select the item drawn at the given row of the visible portion of the
+    /// list, returning whether it was already selected (or `None` if there's
+    /// no item there)
+    pub fn select_visible(&mut self, row: usize) -> Option<bool> {
+        let i = self.state.offset() + row;
+
+        if i >= self.items.len() {
+            return None;
+        }
+
+        let already_selected = self.state.selected() == Some(i);
+        self.state.select(Some(i));
+        Some(already_selected)
+    }
+
     pub fn previous(&mut self) {
         if self.items.is_empty() {
             return;
@@ -50,3 +165,54 @@ impl<T> StatefulList<T> {
         self.state.select(Some(i));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_viewport() {
+        let mut list = StatefulList::with_items((0..100).collect::<Vec<_>>());
+        assert_eq!(list.viewport(10), 0..10);
+
+        list.page_down(25);
+        assert_eq!(list.state.selected(), Some(0));
+        list.page_down(25);
+        assert_eq!(list.state.selected(), Some(25));
+        assert_eq!(list.viewport(10), 16..26);
+        assert_eq!(list.viewport_state().selected(), Some(9));
+
+        list.page_up(20);
+        assert_eq!(list.viewport(10), 5..15);
+
+        list.last();
+        assert_eq!(list.viewport(10), 90..100);
+        list.page_down(10);
+        assert_eq!(list.state.selected(), Some(99));
+
+        list.first();
+        assert_eq!(list.viewport(10), 0..10);
+    }
+
+    #[test]
+    fn test_sliding_window() {
+        let mut list = StatefulList::with_items((10..20).collect::<Vec<_>>());
+        list.state.select(Some(2));
+
+        list.prepend((0..10).collect());
+        assert_eq!(list.items.len(), 20);
+        assert_eq!(list.state.selected().map(|i| list.items[i]), Some(12));
+
+        list.drain_front(5);
+        assert_eq!(list.items.first(), Some(&5));
+        assert_eq!(list.state.selected().map(|i| list.items[i]), Some(12));
+
+        list.truncate(5);
+        assert_eq!(list.items, vec![5, 6, 7, 8, 9]);
+        assert_eq!(list.state.selected(), Some(4));
+
+        list.drain_front(10);
+        assert!(list.items.is_empty());
+        assert_eq!(list.state.selected(), Some(0));
+    }
+}
@@ -0,0 +1,273 @@
+//! View-model that sorts and filters a block's transactions for display
+//! without reordering the block itself
+use std::{cmp::Reverse, fmt, str::FromStr};
+
+use alloy::{
+    consensus::Transaction as AbstractTransaction,
+    primitives::{keccak256, Address, Selector, U256},
+    rpc::types::Transaction,
+};
+
+use crate::utils::useful_gas_price;
+
+use super::stateful_list::StatefulList;
+
+/// Column that transactions can be ordered by
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SortKey {
+    GasPrice,
+    Value,
+    Nonce,
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::GasPrice => write!(f, "gas-price"),
+            Self::Value => write!(f, "value"),
+            Self::Nonce => write!(f, "nonce"),
+        }
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gas-price" | "gas" => Ok(Self::GasPrice),
+            "value" => Ok(Self::Value),
+            "nonce" => Ok(Self::Nonce),
+            other => Err(format!("unknown sort key: {other}")),
+        }
+    }
+}
+
+/// Which transactions to show, and in what order
+///
+/// Written as whitespace-separated terms, e.g.
+/// `from:0xabcd... method:0xa9059cbb sort:gas-price`. Sorting is descending
+/// unless the key is suffixed with `:asc`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TransactionQuery {
+    pub from: Option<Address>,
+    pub to: Option<Address>,
+    pub selector: Option<Selector>,
+    pub sort: Option<(SortKey, bool)>,
+}
+
+impl TransactionQuery {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn matches(&self, tx: &Transaction) -> bool {
+        self.from
+            .is_none_or(|from| tx.as_recovered().signer() == from)
+            && self.to.is_none_or(|to| tx.to() == Some(to))
+            && self
+                .selector
+                .is_none_or(|selector| tx.input().starts_with(&selector[..]))
+    }
+}
+
+impl fmt::Display for TransactionQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut terms = vec![];
+
+        if let Some(from) = self.from {
+            terms.push(format!("from:{from}"));
+        }
+
+        if let Some(to) = self.to {
+            terms.push(format!("to:{to}"));
+        }
+
+        if let Some(selector) = self.selector {
+            terms.push(format!("method:{selector}"));
+        }
+
+        match self.sort {
+            Some((key, true)) => terms.push(format!("sort:{key}:asc")),
+            Some((key, false)) => terms.push(format!("sort:{key}")),
+            None => {}
+        }
+
+        write!(f, "{}", terms.join(" "))
+    }
+}
+
+impl FromStr for TransactionQuery {
+    type Err = String;
+
+    /// Parses whitespace-separated `from:`, `to:`, `method:` (either a
+    /// selector or a signature such as `transfer(address,uint256)`), and
+    /// `sort:` terms
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut query = Self::default();
+
+        for term in s.split_whitespace() {
+            let (name, value) = term
+                .split_once(':')
+                .ok_or(format!("expected <field>:<value>, got {term}"))?;
+
+            match name {
+                "from" => {
+                    query.from =
+                        Some(value.parse().map_err(|_| "invalid address")?)
+                }
+                "to" => {
+                    query.to =
+                        Some(value.parse().map_err(|_| "invalid address")?)
+                }
+                "method" if value.contains('(') => {
+                    query.selector = Some(Selector::from_slice(
+                        &keccak256(value.as_bytes())[..4],
+                    ))
+                }
+                "method" => {
+                    query.selector =
+                        Some(value.parse().map_err(|_| "invalid selector")?)
+                }
+                "sort" => {
+                    query.sort = Some(match value.strip_suffix(":asc") {
+                        Some(key) => (key.parse()?, true),
+                        None => (
+                            value
+                                .strip_suffix(":desc")
+                                .unwrap_or(value)
+                                .parse()?,
+                            false,
+                        ),
+                    })
+                }
+                other => return Err(format!("unknown field: {other}")),
+            }
+        }
+
+        Ok(query)
+    }
+}
+
+/// A block's transactions (in block order) alongside the rows that are
+/// actually displayed, as indices into them
+#[derive(Clone, Debug, Default)]
+pub struct TransactionList {
+    transactions: Vec<Transaction>,
+    pub query: TransactionQuery,
+    pub rows: StatefulList<usize>,
+}
+
+impl TransactionList {
+    /// Replace the underlying transactions, keeping the current query
+    pub fn set_transactions(&mut self, transactions: Vec<Transaction>) {
+        self.transactions = transactions;
+        self.rows = StatefulList::default();
+        self.refresh();
+    }
+
+    /// Replace the query, keeping the selected transaction selected if it's
+    /// still displayed
+    pub fn set_query(&mut self, query: TransactionQuery) {
+        let selected = self.selected_index();
+        self.query = query;
+        self.refresh();
+
+        if let Some(i) = selected {
+            self.select_index(i);
+        }
+    }
+
+    fn refresh(&mut self) {
+        let mut rows: Vec<usize> = (0..self.transactions.len())
+            .filter(|&i| self.query.matches(&self.transactions[i]))
+            .collect();
+
+        if let Some((key, ascending)) = self.query.sort {
+            let txs = &self.transactions;
+            let value = |&i: &usize| match key {
+                SortKey::GasPrice => U256::from(useful_gas_price(&txs[i])),
+                SortKey::Value => txs[i].value(),
+                SortKey::Nonce => U256::from(txs[i].nonce()),
+            };
+
+            /* ties stay in block order either way */
+            if ascending {
+                rows.sort_by_key(value);
+            } else {
+                rows.sort_by_key(|i| Reverse(value(i)));
+            }
+        }
+
+        self.rows.items = rows;
+        self.rows.state.select(None);
+    }
+
+    /// Total number of transactions (displayed or not)
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    /// Whether there are no transactions at all (as in an empty block)
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    /// The transaction displayed at the given row
+    pub fn get(&self, row: usize) -> Option<&Transaction> {
+        self.rows.items.get(row).map(|&i| &self.transactions[i])
+    }
+
+    pub fn selected(&self) -> Option<&Transaction> {
+        self.rows.state.selected().and_then(|row| self.get(row))
+    }
+
+    /// Position (within the block) of the selected transaction
+    fn selected_index(&self) -> Option<usize> {
+        self.rows
+            .state
+            .selected()
+            .and_then(|row| self.rows.items.get(row))
+            .copied()
+    }
+
+    /// Select the transaction at the given position within the block, if
+    /// it's displayed
+    pub fn select_index(&mut self, index: usize) {
+        self.rows
+            .state
+            .select(self.rows.items.iter().position(|&i| i == index));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_transaction_query() {
+        let query: TransactionQuery =
+            "to:0xdAC17F958D2ee523a2206206994597C13D831ec7 method:transfer(address,uint256) sort:value:asc"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            query.to,
+            Some(
+                "0xdAC17F958D2ee523a2206206994597C13D831ec7"
+                    .parse()
+                    .unwrap()
+            )
+        );
+        assert_eq!(query.selector, Some("0xa9059cbb".parse().unwrap()));
+        assert_eq!(query.sort, Some((SortKey::Value, true)));
+        assert_eq!(query.to_string().parse(), Ok(query));
+
+        assert_eq!(
+            "sort:gas-price".parse::<TransactionQuery>().unwrap().sort,
+            Some((SortKey::GasPrice, false))
+        );
+        assert!("".parse::<TransactionQuery>().unwrap().is_empty());
+        assert!("sort:size".parse::<TransactionQuery>().is_err());
+        assert!("0xa9059cbb".parse::<TransactionQuery>().is_err());
+    }
+}
@@ -0,0 +1,328 @@
+//! Summary of the node's transaction pool by sender, from which a single
+//! sender's transactions can be drilled into
+use std::sync::Arc;
+
+use alloy::{
+    consensus::Transaction as _,
+    primitives::{Address, TxHash},
+};
+use ratatui::{
+    layout::Rect,
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, Paragraph},
+    Frame,
+};
+
+use super::stateful_list::StatefulList;
+use crate::{
+    services::{
+        requests::Fetched,
+        txpool::{Queue, SenderSummary, Txpool, TxpoolTransaction},
+    },
+    ui::{app::AddressDisplayMode, theme::Theme},
+    utils::{label_address, to_ether, to_gwei},
+};
+
+#[derive(Clone, Debug)]
+pub struct TxpoolView {
+    /// Most recently retrieved transaction pool (if any)
+    pub txpool: Option<Fetched<Arc<Txpool>>>,
+    pub senders: StatefulList<SenderSummary>,
+    /// Sender whose transactions are shown (if one has been drilled into)
+    pub sender: Option<Address>,
+    pub transactions: StatefulList<TxpoolTransaction>,
+}
+
+impl Default for TxpoolView {
+    fn default() -> Self {
+        Self {
+            txpool: None,
+            senders: StatefulList::with_items(vec![]),
+            sender: None,
+            transactions: StatefulList::with_items(vec![]),
+        }
+    }
+}
+
+impl TxpoolView {
+    /// Show the given transaction pool (unless it's already shown), keeping
+    /// the same sender and transaction selected where possible
+    pub fn show(&mut self, fetched: Fetched<Arc<Txpool>>) {
+        let txpool = match (&self.txpool, &fetched) {
+            (Some(Fetched::Known(shown)), Fetched::Known(txpool))
+                if Arc::ptr_eq(shown, txpool) =>
+            {
+                return
+            }
+            (_, Fetched::Known(txpool)) => txpool.clone(),
+            _ => {
+                self.txpool = Some(fetched);
+                return;
+            }
+        };
+        self.txpool = Some(fetched);
+
+        let selected = self.selected_sender().map(|summary| summary.sender);
+        let senders = txpool.senders();
+        self.senders.state.select(
+            selected
+                .and_then(|selected| {
+                    senders
+                        .iter()
+                        .position(|summary| summary.sender == selected)
+                })
+                .or((!senders.is_empty()).then_some(0)),
+        );
+        self.senders.items = senders;
+
+        if let Some(sender) = self.sender {
+            let selected =
+                self.selected_transaction().map(|tx| tx.transaction.nonce());
+            let transactions = txpool.transactions(sender).to_vec();
+            self.transactions.state.select(
+                selected
+                    .and_then(|selected| {
+                        transactions
+                            .iter()
+                            .position(|tx| tx.transaction.nonce() == selected)
+                    })
+                    .or((!transactions.is_empty()).then_some(0)),
+            );
+            self.transactions.items = transactions;
+        }
+    }
+
+    /// Show the transactions of the selected sender
+    pub fn open_selected(&mut self) {
+        let Some(sender) = self.selected_sender().map(|summary| summary.sender)
+        else {
+            return;
+        };
+        let transactions = match &self.txpool {
+            Some(Fetched::Known(txpool)) => {
+                txpool.transactions(sender).to_vec()
+            }
+            _ => vec![],
+        };
+        self.sender = Some(sender);
+        self.transactions = StatefulList::with_items(transactions);
+        if !self.transactions.items.is_empty() {
+            self.transactions.state.select(Some(0));
+        }
+    }
+
+    /// Go back to the senders from a single sender's transactions, returning
+    /// whether there was a sender to go back from
+    pub fn close(&mut self) -> bool {
+        self.sender.take().is_some()
+    }
+
+    pub fn selected_sender(&self) -> Option<&SenderSummary> {
+        self.senders
+            .state
+            .selected()
+            .and_then(|i| self.senders.items.get(i))
+    }
+
+    /// The selected transaction of the sender drilled into (if any)
+    pub fn selected_transaction(&self) -> Option<&TxpoolTransaction> {
+        self.sender?;
+        self.transactions
+            .state
+            .selected()
+            .and_then(|i| self.transactions.items.get(i))
+    }
+
+    /// Hash of the selected transaction, if a sender has been drilled into
+    pub fn selected_hash(&self) -> Option<TxHash> {
+        self.selected_transaction()
+            .map(|tx| *tx.transaction.inner.tx_hash())
+    }
+
+    /// The sender drilled into, or else the selected sender
+    pub fn selected_address(&self) -> Option<Address> {
+        self.sender
+            .or(self.selected_sender().map(|summary| summary.sender))
+    }
+
+    pub fn previous(&mut self) {
+        match self.sender {
+            Some(_) => self.transactions.previous(),
+            None => self.senders.previous(),
+        }
+    }
+
+    pub fn next(&mut self) {
+        match self.sender {
+            Some(_) => self.transactions.next(),
+            None => self.senders.next(),
+        }
+    }
+
+    pub fn page_up(&mut self, page: usize) {
+        match self.sender {
+            Some(_) => self.transactions.page_up(page),
+            None => self.senders.page_up(page),
+        }
+    }
+
+    pub fn page_down(&mut self, page: usize) {
+        match self.sender {
+            Some(_) => self.transactions.page_down(page),
+            None => self.senders.page_down(page),
+        }
+    }
+
+    pub fn first(&mut self) {
+        match self.sender {
+            Some(_) => self.transactions.first(),
+            None => self.senders.first(),
+        }
+    }
+
+    pub fn last(&mut self) {
+        match self.sender {
+            Some(_) => self.transactions.last(),
+            None => self.senders.last(),
+        }
+    }
+
+    /// Select whatever is drawn at the given row of the visible portion of
+    /// the list, returning whether it was already selected (or `None` if
+    /// there's nothing there)
+    pub fn select_visible(&mut self, row: usize) -> Option<bool> {
+        match self.sender {
+            Some(_) => self.transactions.select_visible(row),
+            None => self.senders.select_visible(row),
+        }
+    }
+
+    pub fn draw(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        theme: &Theme,
+        mode: AddressDisplayMode,
+    ) {
+        let txpool = match &self.txpool {
+            Some(Fetched::Known(txpool)) => txpool.clone(),
+            other => {
+                let text = match other {
+                    Some(Fetched::Unavailable(e)) => {
+                        format!("Transaction pool unavailable ({e})")
+                    }
+                    _ => "Retrieving the transaction pool...".to_string(),
+                };
+                frame.render_widget(
+                    Paragraph::new(Span::styled(text, Style::new().italic()))
+                        .block(
+                            Block::bordered()
+                                .title(Line::from("Txpool").centered())
+                                .border_style(theme.border),
+                        ),
+                    area,
+                );
+                return;
+            }
+        };
+
+        let (title, items): (String, Vec<ListItem>) = match self.sender {
+            None => (
+                format!(
+                    "Txpool ({} pending, {} queued from {} senders)",
+                    txpool.pending,
+                    txpool.queued,
+                    self.senders.items.len()
+                ),
+                self.senders
+                    .items
+                    .iter()
+                    .map(|summary| {
+                        ListItem::new(Line::from(vec![
+                            Span::raw(format!(
+                                "{:<44}",
+                                label_address(&summary.sender, false, mode)
+                            )),
+                            Span::raw(format!(
+                                "{:<14}",
+                                format!("{} pending", summary.pending)
+                            )),
+                            Span::styled(
+                                format!("{} queued", summary.queued),
+                                if summary.queued > 0 {
+                                    Style::new().fg(theme.error)
+                                } else {
+                                    Style::new()
+                                },
+                            ),
+                        ]))
+                    })
+                    .collect(),
+            ),
+            Some(sender) => (
+                format!(
+                    "Txpool: {} ({} transactions)",
+                    label_address(&sender, false, mode),
+                    self.transactions.items.len()
+                ),
+                self.transactions
+                    .items
+                    .iter()
+                    .map(|tx| {
+                        let (queue, style) = match tx.queue {
+                            Queue::Pending => ("pending", Style::new()),
+                            Queue::Queued => {
+                                ("queued", Style::new().fg(theme.error))
+                            }
+                        };
+                        let transaction = &tx.transaction;
+                        ListItem::new(Line::from(vec![
+                            Span::styled(
+                                format!("{:<10}", transaction.nonce()),
+                                Style::new().bold(),
+                            ),
+                            Span::styled(format!("{queue:<10}"), style),
+                            Span::raw(format!(
+                                "{:<70}",
+                                transaction.inner.tx_hash().to_string()
+                            )),
+                            Span::raw(format!(
+                                "{:<44}",
+                                transaction.to().map_or(
+                                    "Contract creation".to_string(),
+                                    |to| label_address(&to, false, mode)
+                                )
+                            )),
+                            Span::raw(format!(
+                                "{:<20}",
+                                format!(
+                                    "{:.6} ETH",
+                                    to_ether(transaction.value())
+                                )
+                            )),
+                            Span::raw(format!(
+                                "{:.3} Gwei max fee",
+                                to_gwei(transaction.max_fee_per_gas() as f64)
+                            )),
+                        ]))
+                    })
+                    .collect(),
+            ),
+        };
+
+        let list = List::new(items)
+            .block(
+                Block::bordered()
+                    .title(Line::from(title).centered())
+                    .border_style(theme.border),
+            )
+            .highlight_style(theme.highlight)
+            .highlight_symbol("> ");
+        let state = match self.sender {
+            Some(_) => &mut self.transactions.state,
+            None => &mut self.senders.state,
+        };
+        frame.render_stateful_widget(list, area, state);
+    }
+}
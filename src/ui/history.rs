@@ -0,0 +1,73 @@
+//! Back/forward navigation between previously visited pages
+use std::collections::VecDeque;
+
+/// Maximum number of pages remembered in either direction
+const MAX_HISTORY: usize = 64;
+
+/// Pages visited before (and, after going back, since) the current one
+#[derive(Clone, Debug)]
+pub struct History<T> {
+    past: VecDeque<T>,
+    future: Vec<T>,
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        Self {
+            past: VecDeque::new(),
+            future: vec![],
+        }
+    }
+}
+
+impl<T> History<T> {
+    /// Record that the given page is being navigated away from, forgetting
+    /// any pages that could previously have been returned to by going forward
+    pub fn visit(&mut self, current: T) {
+        if self.past.len() == MAX_HISTORY {
+            self.past.pop_front();
+        }
+
+        self.past.push_back(current);
+        self.future.clear();
+    }
+
+    /// The page preceding the given (current) one, if any
+    pub fn back(&mut self, current: T) -> Option<T> {
+        let previous = self.past.pop_back()?;
+        self.future.push(current);
+        Some(previous)
+    }
+
+    /// The page most recently gone back from, if any
+    pub fn forward(&mut self, current: T) -> Option<T> {
+        let next = self.future.pop()?;
+        self.past.push_back(current);
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history() {
+        let mut history = History::default();
+        history.visit(1);
+        history.visit(2);
+        assert_eq!(history.back(3), Some(2));
+        assert_eq!(history.back(2), Some(1));
+        assert_eq!(history.back(1), None);
+        assert_eq!(history.forward(1), Some(2));
+        history.visit(2);
+        assert_eq!(history.forward(4), None);
+        assert_eq!(history.back(4), Some(2));
+
+        for page in 0..(MAX_HISTORY + 1) {
+            history.visit(page);
+        }
+        assert_eq!(history.past.len(), MAX_HISTORY);
+        assert_eq!(history.past.front(), Some(&1));
+    }
+}
@@ -0,0 +1,388 @@
+//! Mapping of key presses to TUI actions
+use std::{collections::HashMap, str::FromStr};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use eyre::eyre;
+use serde::Deserialize;
+
+/// Something the user can ask the TUI to do
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Quit,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    /// Selects the first item of the list
+    First,
+    /// Selects the last item of the list
+    Last,
+    /// Opens the selected list item
+    Select,
+    /// Returns to the previous page (or exits from the main page)
+    Back,
+    /// Returns to the page most recently gone back from
+    Forward,
+    ToggleLabels,
+    CommandPalette,
+    FilterCalldata,
+    /// Sorts and filters the transactions of the open block
+    FilterTransactions,
+    SearchLogs,
+    /// Moves navigation between the logs found by a search and the data of
+    /// the selected one
+    FocusLogData,
+    OpenEtherscan,
+    OpenLibmev,
+    CompareTemplate,
+    /// Follows the selected transaction until it's finalized
+    TrackTransaction,
+    /// Copies the hash of the selected block or transaction
+    Yank,
+    /// Copies the most relevant address (e.g., a transaction's sender)
+    YankAddress,
+    ChartGasUsed,
+    ChartBaseFee,
+    ChartBlobGas,
+    /// Toggles between selecting each new block as it arrives and keeping
+    /// the selection where it is
+    ToggleFollow,
+    /// Mines a new block (on development chains only)
+    Mine,
+    /// Shows (or hides) every field of the open block's header
+    ExpandHeader,
+    /// Shows (or hides) the RLP encoding of the open block's header, or the
+    /// EIP-2718 encoding of the open transaction
+    #[serde(alias = "toggle-raw-header")]
+    ToggleRaw,
+    /// Compares the open block with another (e.g., orphaned) block at the
+    /// same height
+    CompareFork,
+    /// Writes the pending transactions seen so far to a JSON file
+    ExportMempool,
+    /// Steps through the blobs of the open transaction
+    InspectBlobs,
+    /// Shows the code and storage of the contract being looked at (e.g., the
+    /// recipient of the open transaction)
+    OpenContract,
+    /// Switches the contract view between a hex dump and disassembly
+    ToggleDisassembly,
+    /// Reads a storage slot of the open contract
+    ReadStorage,
+    /// Switches the contract view between a hex dump and verified source
+    /// code
+    ToggleSource,
+    /// Adds (or removes) the most relevant address to (or from) the watch
+    /// list
+    WatchAddress,
+    /// Shows the balances and nonces of watched addresses
+    OpenWatchlist,
+    /// Bookmarks (or removes the bookmark of) whatever is selected
+    Bookmark,
+    /// Lists bookmarked blocks, transactions, and addresses
+    OpenBookmarks,
+    /// Shows fee totals and hourly trends across the indexed blocks
+    OpenStats,
+    /// Re-executes the open transaction via `eth_call`, both at its parent
+    /// block and at the latest block
+    Simulate,
+    /// Prompts for a signed transaction to broadcast and then follow
+    SendTransaction,
+    /// Summarises the node's transaction pool by sender
+    OpenTxpool,
+    /// Ranks the function selectors called over the most recent blocks
+    OpenSelectors,
+    /// Compares the chain heads of every configured RPC endpoint
+    OpenEndpoints,
+}
+
+/// Built-in sets of keybindings
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    /// Arrow keys and `j`/`k` for navigation
+    #[default]
+    Default,
+    /// As per the default preset, with `h` also going back, `Ctrl+f`/`Ctrl+b`
+    /// paging, and `g`/`G` jumping to the start/end of lists
+    Vim,
+    /// `Ctrl+n`/`Ctrl+p` for navigation, `Ctrl+v`/`Alt+v` for paging,
+    /// `Alt+<`/`Alt+>` to jump to the start/end of lists, `Ctrl+g` to go back,
+    /// `Alt+x` for the command palette, and `Ctrl+s` to filter calldata
+    Emacs,
+}
+
+/// A single key press (e.g., `j`, `Ctrl+c`, or `Esc`)
+///
+/// Only the Ctrl and Alt modifiers are significant; Shift is implied by the
+/// case of the character itself.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    const fn plain(c: char) -> Self {
+        Self::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    const fn ctrl(c: char) -> Self {
+        Self::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(value: KeyEvent) -> Self {
+        Self::new(
+            value.code,
+            value.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT),
+        )
+    }
+}
+
+impl FromStr for Key {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+
+        /* a lone `+` is a key in its own right */
+        while let Some((modifier, tail)) =
+            rest.split_once('+').filter(|(_, tail)| !tail.is_empty())
+        {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                _ => return Err(eyre!("Unknown modifier: {modifier}")),
+            };
+            rest = tail;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "space" => KeyCode::Char(' '),
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                other => match other.strip_prefix('f').map(u8::from_str) {
+                    Some(Ok(n)) => KeyCode::F(n),
+                    _ => return Err(eyre!("Unknown key: {rest}")),
+                },
+            },
+        };
+
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Lookup table from [`Key`]s to [`Action`]s
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Keymap {
+    bindings: HashMap<Key, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(Preset::default())
+    }
+}
+
+impl Keymap {
+    /// Bindings shared by every preset
+    const COMMON: [(Key, Action); 48] = [
+        (Key::new(KeyCode::Up, KeyModifiers::NONE), Action::Up),
+        (Key::new(KeyCode::Down, KeyModifiers::NONE), Action::Down),
+        (
+            Key::new(KeyCode::PageUp, KeyModifiers::NONE),
+            Action::PageUp,
+        ),
+        (
+            Key::new(KeyCode::PageDown, KeyModifiers::NONE),
+            Action::PageDown,
+        ),
+        (Key::new(KeyCode::Home, KeyModifiers::NONE), Action::First),
+        (Key::new(KeyCode::End, KeyModifiers::NONE), Action::Last),
+        (Key::new(KeyCode::Enter, KeyModifiers::NONE), Action::Select),
+        (Key::new(KeyCode::Esc, KeyModifiers::NONE), Action::Back),
+        (Key::plain('['), Action::Back),
+        (Key::plain(']'), Action::Forward),
+        (Key::ctrl('c'), Action::Quit),
+        (Key::plain('q'), Action::Quit),
+        (Key::plain('r'), Action::ToggleLabels),
+        (Key::plain(':'), Action::CommandPalette),
+        (Key::plain('/'), Action::FilterCalldata),
+        (Key::plain('f'), Action::FilterTransactions),
+        (Key::plain('L'), Action::SearchLogs),
+        (
+            Key::new(KeyCode::Tab, KeyModifiers::NONE),
+            Action::FocusLogData,
+        ),
+        (Key::plain('e'), Action::OpenEtherscan),
+        (Key::plain('l'), Action::OpenLibmev),
+        (Key::plain('t'), Action::CompareTemplate),
+        (Key::plain('T'), Action::TrackTransaction),
+        (Key::plain('y'), Action::Yank),
+        (Key::plain('Y'), Action::YankAddress),
+        (Key::plain('1'), Action::ChartGasUsed),
+        (Key::plain('2'), Action::ChartBaseFee),
+        (Key::plain('3'), Action::ChartBlobGas),
+        (Key::plain('p'), Action::ToggleFollow),
+        (Key::plain('m'), Action::Mine),
+        (Key::plain('x'), Action::ExpandHeader),
+        (Key::plain('R'), Action::ToggleRaw),
+        (Key::plain('o'), Action::CompareFork),
+        (Key::plain('P'), Action::ExportMempool),
+        (Key::plain('b'), Action::InspectBlobs),
+        (Key::plain('c'), Action::OpenContract),
+        (Key::plain('d'), Action::ToggleDisassembly),
+        (Key::plain('s'), Action::ReadStorage),
+        (Key::plain('v'), Action::ToggleSource),
+        (Key::plain('w'), Action::WatchAddress),
+        (Key::plain('W'), Action::OpenWatchlist),
+        (Key::plain('B'), Action::Bookmark),
+        (Key::plain('\''), Action::OpenBookmarks),
+        (Key::plain('S'), Action::OpenStats),
+        (Key::plain('E'), Action::Simulate),
+        (Key::plain('X'), Action::SendTransaction),
+        (Key::plain('M'), Action::OpenTxpool),
+        (Key::plain('F'), Action::OpenSelectors),
+        (Key::plain('H'), Action::OpenEndpoints),
+    ];
+
+    /// The bindings of the given [`Preset`]
+    pub fn new(preset: Preset) -> Self {
+        let extra: &[(Key, Action)] = match preset {
+            Preset::Default => &[
+                (Key::plain('k'), Action::Up),
+                (Key::plain('j'), Action::Down),
+            ],
+            Preset::Vim => &[
+                (Key::plain('k'), Action::Up),
+                (Key::plain('j'), Action::Down),
+                (Key::plain('h'), Action::Back),
+                (Key::ctrl('b'), Action::PageUp),
+                (Key::ctrl('f'), Action::PageDown),
+                (Key::plain('g'), Action::First),
+                (Key::plain('G'), Action::Last),
+            ],
+            Preset::Emacs => &[
+                (Key::ctrl('p'), Action::Up),
+                (Key::ctrl('n'), Action::Down),
+                (Key::ctrl('g'), Action::Back),
+                (
+                    Key::new(KeyCode::Char('x'), KeyModifiers::ALT),
+                    Action::CommandPalette,
+                ),
+                (Key::ctrl('s'), Action::FilterCalldata),
+                (
+                    Key::new(KeyCode::Char('v'), KeyModifiers::ALT),
+                    Action::PageUp,
+                ),
+                (Key::ctrl('v'), Action::PageDown),
+                (
+                    Key::new(KeyCode::Char('<'), KeyModifiers::ALT),
+                    Action::First,
+                ),
+                (
+                    Key::new(KeyCode::Char('>'), KeyModifiers::ALT),
+                    Action::Last,
+                ),
+            ],
+        };
+
+        Self {
+            bindings: Self::COMMON.iter().chain(extra).copied().collect(),
+        }
+    }
+
+    /// Add (or replace) the given bindings
+    pub fn with_bindings(mut self, bindings: &HashMap<Key, Action>) -> Self {
+        self.bindings.extend(bindings);
+        self
+    }
+
+    /// The [`Action`] bound to the given key press (if any)
+    pub fn action(&self, key: impl Into<Key>) -> Option<Action> {
+        self.bindings.get(&key.into()).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_parse() {
+        assert_eq!("j".parse::<Key>().unwrap(), Key::plain('j'));
+        assert_eq!("Ctrl+n".parse::<Key>().unwrap(), Key::ctrl('n'));
+        assert_eq!("+".parse::<Key>().unwrap(), Key::plain('+'));
+        assert_eq!(
+            "Alt+Enter".parse::<Key>().unwrap(),
+            Key::new(KeyCode::Enter, KeyModifiers::ALT)
+        );
+        assert_eq!(
+            "F5".parse::<Key>().unwrap(),
+            Key::new(KeyCode::F(5), KeyModifiers::NONE)
+        );
+        assert!("Hyper+j".parse::<Key>().is_err());
+        assert!("Foo".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn test_keymap() {
+        let keymap = Keymap::new(Preset::Emacs).with_bindings(
+            &[(Key::plain('j'), Action::Down)].into_iter().collect(),
+        );
+        assert_eq!(
+            keymap.action(KeyEvent::new(
+                KeyCode::Char('n'),
+                KeyModifiers::CONTROL
+            )),
+            Some(Action::Down)
+        );
+        assert_eq!(
+            keymap
+                .action(KeyEvent::new(KeyCode::Char('L'), KeyModifiers::SHIFT)),
+            Some(Action::SearchLogs)
+        );
+        assert_eq!(keymap.action(Key::plain('j')), Some(Action::Down));
+        assert_eq!(keymap.action(Key::plain('k')), None);
+
+        /* actions keep answering to names they've since outgrown */
+        for name in ["\"toggle-raw\"", "\"toggle-raw-header\""] {
+            assert_eq!(
+                serde_json::from_str::<Action>(name).unwrap(),
+                Action::ToggleRaw
+            );
+        }
+    }
+}
@@ -1,19 +1,107 @@
-use std::time::{Duration, Instant};
+use std::{
+    io::{self, Write},
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
 
-use alloy::{
-    eips::{BlockHashOrNumber, HashOrNumber},
-    primitives::TxHash,
+use alloy::{eips::BlockHashOrNumber, primitives::TxHash};
+use app::{App, Opening};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyModifiers, MouseEventKind},
+    execute,
+    terminal::SetTitle,
 };
-use app::{App, View};
-use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use keymap::Keymap;
 use ratatui::DefaultTerminal;
+use theme::Theme;
+use ticker::Ticker;
+pub use ticker::IDLE_TICK_RATE;
 
-use crate::db::Database;
+use crate::{
+    abi::set_contract_abi,
+    db::Database,
+    metrics::{Metrics, RpcHealth},
+    services::{
+        blobs::BlobService, blockchain::IndexerState,
+        contracts::ContractService, devchain::DevChainService,
+        mempool::MempoolService, race::RaceService,
+        rawtx::RawTransactionService, retrieval::RetrievalService,
+        signals::SignalService, simulator::SimulatorService,
+        tracer::TracerService, tracker::TrackerService, txpool::TxpoolService,
+        watchlist::WatchlistService,
+    },
+    utils::{set_address_label, status_line},
+};
 
 pub mod app;
+mod clipboard;
+mod command;
 mod components;
+mod history;
+pub mod keymap;
+mod preimage;
+pub mod theme;
+mod ticker;
 
-const TICK_MILLIS: u64 = 250; /* 250ms */
+/// Interval between ticks while new data is arriving (slowing towards
+/// [`IDLE_TICK_RATE`] otherwise)
+pub const DEFAULT_TICK_RATE: Duration = Duration::from_millis(100);
+
+/// User preferences for the look and feel of the TUI
+#[derive(Clone, Debug)]
+pub struct Settings {
+    pub keymap: Keymap,
+    pub theme: Theme,
+    /// Interval between ticks (i.e., refreshes from the database) while new
+    /// data is arriving, which lengthens (up to [`IDLE_TICK_RATE`]) while it
+    /// isn't
+    pub tick_rate: Duration,
+    /// Whether to show the latest block in the terminal's title
+    pub set_title: bool,
+    /// Blocks a watched address's transaction may remain pending for before
+    /// it's flagged as stuck
+    pub stuck_after: u64,
+}
+
+/// Progress of retrieving the first block to show from the RPC node, which
+/// the TUI doesn't wait for
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum Startup {
+    #[default]
+    Connecting,
+    /// Retrieving it failed (though the indexer keeps trying to connect)
+    Failed(String),
+    Done,
+}
+
+/// Handles to the background services that the TUI reports on
+#[derive(Clone, Debug)]
+pub struct Services {
+    pub metrics: Option<Arc<Metrics>>,
+    /// Recent RPC requests, summarised in the endpoints view whether or not
+    /// metrics are exported
+    pub rpc_health: Arc<Mutex<RpcHealth>>,
+    /// Progress of retrieving the block to open on (or the latest)
+    pub startup: Arc<RwLock<Startup>>,
+    pub tracker: TrackerService,
+    pub indexer: Arc<IndexerState>,
+    pub devchain: DevChainService,
+    pub tracer: TracerService,
+    pub simulator: SimulatorService,
+    /// Retrieves the encodings of transactions (e.g., to copy them)
+    pub rawtx: RawTransactionService,
+    /// Retrieves blocks and transactions that aren't indexed
+    pub retrieval: RetrievalService,
+    pub contracts: ContractService,
+    /// Retrieves blobs from the consensus layer node (if there is one)
+    pub blobs: Option<BlobService>,
+    pub mempool: MempoolService,
+    pub txpool: TxpoolService,
+    /// Races the RPC endpoints against each other (if there's more than one)
+    pub race: Option<RaceService>,
+    pub watchlist: WatchlistService,
+    pub signals: SignalService,
+}
 
 /// Drives the TUI app
 pub fn run(
@@ -21,68 +109,175 @@ pub fn run(
     db: &Database,
     block: Option<BlockHashOrNumber>,
     transaction: Option<TxHash>,
+    services: Services,
+    settings: Settings,
 ) -> eyre::Result<()> {
-    /* we're able to wet the UI with selected chain objects due to wetting the
-     * database on startup */
-    let latest_block = db.latest_block()?.expect(
-        "invariant violated: database must always have at least one block",
-    );
-    let latest_tx = latest_block
-        .clone()
-        .transactions
-        .into_transactions()
-        .next()
-        .expect("invariant violated: latest block must be non-empty");
+    let Services {
+        metrics,
+        rpc_health,
+        startup,
+        tracker,
+        indexer,
+        devchain,
+        tracer,
+        simulator,
+        rawtx,
+        retrieval,
+        contracts,
+        blobs,
+        mempool,
+        txpool,
+        race,
+        watchlist,
+        signals,
+    } = services;
+    let Settings {
+        keymap,
+        theme,
+        tick_rate,
+        set_title,
+        stuck_after,
+    } = settings;
+
+    /* user-defined labels take precedence over any loaded from files */
+    db.labels()?.into_iter().for_each(|(address, label)| {
+        set_address_label(address, label);
+    });
+    db.contract_abis()?
+        .into_iter()
+        .for_each(|(address, abi)| set_contract_abi(address, abi));
+
+    /* whatever was indexed previously is shown until the RPC node has been
+     * heard from (should there be anything) */
+    let latest_block = db.latest_block()?.unwrap_or_default();
+    /* testnets and devnets often produce empty blocks */
+    let latest_tx = latest_block.transactions.txns().next().cloned();
     let mut app = App::new("blocktop".to_string(), latest_block, latest_tx);
+    app.metrics = metrics.clone();
+    app.rpc_health = Some(rpc_health);
+    app.theme = theme;
+    app.tracker = Some(tracker);
+    /* the first tick reads everything regardless of whether anything's
+     * been indexed since startup */
+    let mut chain_events = indexer.subscribe();
+    chain_events.mark_changed();
+    app.chain_events = Some(chain_events);
+    app.indexer = Some(indexer);
+    app.devchain = Some(devchain);
+    app.tracer = Some(tracer);
+    app.simulator = Some(simulator);
+    app.rawtx = Some(rawtx);
+    app.retrieval = Some(retrieval);
+    app.contract_service = Some(contracts);
+    app.blob_service = blobs;
+    app.mempool = Some(mempool);
+    app.txpool = Some(txpool);
+    app.race = race;
+    app.watched_addresses = watchlist.addresses().into_iter().collect();
+    app.watchlist = Some(watchlist);
+    app.stuck_after = stuck_after;
 
-    if let Some(specified_block) = block {
-        app.view = View::Block;
-        let specified_block_hash = match specified_block {
-            HashOrNumber::Hash(h) => h,
-            HashOrNumber::Number(x) => {
-                db.block_by_number(x)?.unwrap().header.hash
-            }
-        };
-        app.selected_block = db.block_by_hash(specified_block_hash)?.unwrap();
-    } else if let Some(specified_tx) = transaction {
-        app.view = View::Transaction;
-        app.selected_block =
-            db.block_by_transaction_hash(specified_tx)?.unwrap();
-        app.selected_transaction = db.transaction(specified_tx)?.unwrap();
+    /* the block or transaction asked for (or else the latest block) might
+     * still be being retrieved, so it's opened once it's been indexed */
+    app.startup = Some(startup);
+    app.opening = block
+        .map(Opening::Block)
+        .or(transaction.map(Opening::Transaction));
+
+    let mut ticker = Ticker::new(tick_rate);
+    let mut title_block = None;
+
+    if set_title {
+        /* save the current title so that it can be restored on exit */
+        write_escape("\x1b[22;0t")?;
     }
 
-    let tick_rate: Duration = Duration::from_millis(TICK_MILLIS);
-    let mut last_tick = Instant::now();
-
-    loop {
-        terminal.draw(|frame| app.draw(frame))?;
-
-        let timeout = tick_rate.saturating_sub(last_tick.elapsed());
-        if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Up | KeyCode::Char('k') => app.on_up(),
-                    KeyCode::Down | KeyCode::Char('j') => app.on_down(),
-                    KeyCode::Enter => app.on_enter(),
-                    KeyCode::Esc => app.on_esc(),
-                    KeyCode::Char('c')
-                        if key.modifiers.contains(KeyModifiers::CONTROL) =>
-                    {
-                        app.on_quit()
+    let result = (|| loop {
+        {
+            let _timer = metrics
+                .as_ref()
+                .map(|metrics| metrics.ui_frame_render_seconds.start_timer());
+            terminal.draw(|frame| app.draw(frame))?;
+        }
+
+        if event::poll(ticker.timeout())? {
+            match event::read()? {
+                Event::Key(key) => {
+                    ticker.hurry();
+
+                    app.status_message = None;
+
+                    if app.prompt.is_some() {
+                        match key.code {
+                            KeyCode::Char('c')
+                                if key
+                                    .modifiers
+                                    .contains(KeyModifiers::CONTROL) =>
+                            {
+                                app.on_quit()
+                            }
+                            KeyCode::Enter => app.on_prompt_submit(db),
+                            KeyCode::Esc => app.on_prompt_cancel(),
+                            KeyCode::Backspace => app.on_prompt_backspace(),
+                            KeyCode::Up => app.on_prompt_history(true),
+                            KeyCode::Down => app.on_prompt_history(false),
+                            KeyCode::Char(c) => app.on_prompt_char(c),
+                            _ => {}
+                        }
+                    } else if let Some(action) = keymap.action(key) {
+                        app.on_action(db, action);
+                    }
+                }
+                Event::Paste(text) if app.prompt.is_some() => {
+                    ticker.hurry();
+                    app.on_prompt_paste(&text)
+                }
+                Event::Mouse(mouse) if app.prompt.is_none() => {
+                    /* merely moving the pointer changes nothing */
+                    if mouse.kind != MouseEventKind::Moved {
+                        ticker.hurry();
                     }
-                    KeyCode::Char(c) => app.on_key(c),
-                    _ => {}
+                    app.on_mouse(db, mouse)
                 }
+                _ => {}
             }
         }
 
-        if app.should_quit {
+        if app.should_quit || signals.received() {
             return Ok(());
         }
 
-        if last_tick.elapsed() >= tick_rate {
-            app.on_tick(db);
-            last_tick = Instant::now();
+        if ticker.is_due() {
+            let _timer = metrics
+                .as_ref()
+                .map(|metrics| metrics.ui_tick_seconds.start_timer());
+            let updated = app.on_tick(db);
+            ticker.ticked(updated);
+
+            if let Some(header) =
+                app.block_headers.items.last().filter(|header| {
+                    set_title && title_block != Some(header.hash)
+                })
+            {
+                execute!(
+                    io::stdout(),
+                    SetTitle(format!("blocktop {}", status_line(header)))
+                )?;
+                title_block = Some(header.hash);
+            }
         }
+    })();
+
+    if set_title {
+        write_escape("\x1b[23;0t")?;
     }
+
+    result
+}
+
+/// Write the given escape sequence straight to the terminal
+fn write_escape(sequence: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    stdout.write_all(sequence.as_bytes())?;
+    stdout.flush()
 }
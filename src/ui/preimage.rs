@@ -0,0 +1,153 @@
+//! Keccak-256 preimages that are tedious to hash by hand (e.g., storage slots
+//! of mapping entries)
+use alloy::{
+    hex,
+    primitives::{keccak256, B256, U256},
+};
+
+/// Something for the user to have hashed
+#[derive(Clone, Debug, PartialEq)]
+pub enum Preimage {
+    /// Storage slot of a (possibly nested) mapping entry, given the entry's
+    /// keys (outermost first) and the slot of the mapping itself
+    MappingSlot { keys: Vec<B256>, slot: B256 },
+    /// Event or function signature (e.g., `Transfer(address,address,uint256)`)
+    Signature(String),
+}
+
+impl Preimage {
+    /// Interpret the given words as mapping keys followed by the slot of the
+    /// mapping itself
+    pub fn mapping_slot(words: &[&str]) -> Result<Self, String> {
+        match words.split_last() {
+            Some((slot, keys)) if !keys.is_empty() => Ok(Self::MappingSlot {
+                keys: keys
+                    .iter()
+                    .map(|key| parse_word(key))
+                    .collect::<Result<_, _>>()?,
+                slot: parse_word(slot)?,
+            }),
+            _ => Err("slot requires at least one key and a slot".to_string()),
+        }
+    }
+
+    pub fn hash(&self) -> B256 {
+        match self {
+            Self::MappingSlot { keys, slot } => {
+                keys.iter().fold(*slot, |slot, key| {
+                    keccak256([key.as_slice(), slot.as_slice()].concat())
+                })
+            }
+            Self::Signature(signature) => keccak256(signature),
+        }
+    }
+
+    /// Labelled fields describing both the preimage and its hash
+    pub fn fields(&self) -> Vec<(String, String)> {
+        let hash = self.hash();
+        match self {
+            Self::MappingSlot { keys, slot } => {
+                let mut fields =
+                    vec![("Mapping slot".to_string(), slot.to_string())];
+                fields.extend(keys.iter().enumerate().map(|(i, key)| {
+                    (format!("Key {}", i + 1), key.to_string())
+                }));
+                fields.push(("Entry slot".to_string(), hash.to_string()));
+                fields
+            }
+            Self::Signature(signature) => vec![
+                ("Signature".to_string(), signature.clone()),
+                ("Topic".to_string(), hash.to_string()),
+                ("Selector".to_string(), hex::encode_prefixed(&hash[..4])),
+            ],
+        }
+    }
+}
+
+/// Parse a 32-byte word given either in hex (left-padded, so that e.g.
+/// addresses can be given as is) or in decimal
+fn parse_word(s: &str) -> Result<B256, String> {
+    match s.strip_prefix("0x") {
+        Some(digits) => {
+            let digits = if digits.len() % 2 == 1 {
+                format!("0{digits}")
+            } else {
+                digits.to_string()
+            };
+            let bytes = hex::decode(digits)
+                .map_err(|_| format!("invalid hex word: {s}"))?;
+            if bytes.len() > 32 {
+                return Err(format!("word is longer than 32 bytes: {s}"));
+            }
+            Ok(B256::left_padding_from(&bytes))
+        }
+        None => s
+            .parse::<U256>()
+            .map(|x| B256::from(x.to_be_bytes::<32>()))
+            .map_err(|_| format!("invalid number: {s}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{address, b256};
+
+    use super::*;
+
+    #[test]
+    fn test_mapping_slot() {
+        /* `balanceOf` of the zero address, for a mapping at slot 0 */
+        let preimage = Preimage::mapping_slot(&[
+            "0x0000000000000000000000000000000000000000",
+            "0",
+        ])
+        .unwrap();
+        assert_eq!(
+            preimage.hash(),
+            b256!(
+                "0xad3228b676f7d3cd4284a5443f17f1962b36e491b30a40b2405849e597ba5fb5"
+            )
+        );
+
+        /* nested mappings hash the outermost key first */
+        let owner = address!("0x00000000000000000000000000000000000000aa");
+        let spender = "0xbb";
+        let nested =
+            Preimage::mapping_slot(&[&owner.to_string(), spender, "1"])
+                .unwrap();
+        let inner = keccak256(
+            [
+                owner.into_word().as_slice(),
+                B256::with_last_byte(1).as_slice(),
+            ]
+            .concat(),
+        );
+        assert_eq!(
+            nested.hash(),
+            keccak256(
+                [B256::with_last_byte(0xbb).as_slice(), inner.as_slice()]
+                    .concat()
+            )
+        );
+
+        assert!(Preimage::mapping_slot(&["1"]).is_err());
+        assert!(Preimage::mapping_slot(&["0xzz", "1"]).is_err());
+    }
+
+    #[test]
+    fn test_signature() {
+        let preimage = Preimage::Signature(
+            "Transfer(address,address,uint256)".to_string(),
+        );
+        assert_eq!(
+            preimage.hash(),
+            b256!(
+                "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+            )
+        );
+        assert_eq!(
+            preimage.fields().last().unwrap().1,
+            "0xddf252ad".to_string()
+        );
+    }
+}
@@ -0,0 +1,143 @@
+//! Colour schemes for the TUI
+use std::time::Duration;
+
+use ratatui::style::{Color, Style, Stylize};
+use serde::Deserialize;
+
+use crate::db::FinalityStatus;
+
+/// Built-in colour schemes
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeName {
+    #[default]
+    Dark,
+    Light,
+    Solarized,
+    /// No colours at all, for limited terminals
+    Monochrome,
+}
+
+/// Blocks younger than this (i.e., one slot) are considered just arrived
+const FRESH_BLOCK_AGE: Duration = Duration::from_secs(12);
+/// Blocks older than this are faded out
+const STALE_BLOCK_AGE: Duration = Duration::from_secs(60);
+/// Blocks older than this are faded out even further
+const ANCIENT_BLOCK_AGE: Duration = Duration::from_secs(5 * 60);
+
+/// Styles used consistently throughout the TUI
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Theme {
+    /// Borders of every panel
+    pub border: Color,
+    /// Currently selected list item
+    pub highlight: Style,
+    /// Bars, sparklines, and other "good" things
+    pub primary: Color,
+    /// Labels and things worth drawing attention to
+    pub secondary: Color,
+    /// Supplementary plots (e.g., transaction counts)
+    pub tertiary: Color,
+    /// Errors and other "bad" things
+    pub error: Color,
+    /// De-emphasised decoration (e.g., the prompt cursor)
+    pub muted: Color,
+    /// Values printed inside bars
+    pub bar_value: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::new(ThemeName::default())
+    }
+}
+
+impl Theme {
+    pub fn new(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Dark => Self {
+                border: Color::Green,
+                highlight: Style::new().bg(Color::Magenta),
+                primary: Color::Green,
+                secondary: Color::Yellow,
+                tertiary: Color::Cyan,
+                error: Color::Red,
+                muted: Color::Gray,
+                bar_value: Style::new()
+                    .fg(Color::Black)
+                    .bg(Color::Green)
+                    .italic(),
+            },
+            ThemeName::Light => Self {
+                border: Color::Blue,
+                highlight: Style::new().fg(Color::Black).bg(Color::LightCyan),
+                primary: Color::Blue,
+                secondary: Color::Magenta,
+                tertiary: Color::DarkGray,
+                error: Color::Red,
+                muted: Color::DarkGray,
+                bar_value: Style::new()
+                    .fg(Color::White)
+                    .bg(Color::Blue)
+                    .italic(),
+            },
+            ThemeName::Solarized => {
+                /* see https://ethanschoonover.com/solarized */
+                let base02 = Color::Rgb(0x07, 0x36, 0x42);
+                let base01 = Color::Rgb(0x58, 0x6e, 0x75);
+                let yellow = Color::Rgb(0xb5, 0x89, 0x00);
+                let red = Color::Rgb(0xdc, 0x32, 0x2f);
+                let magenta = Color::Rgb(0xd3, 0x36, 0x82);
+                let blue = Color::Rgb(0x26, 0x8b, 0xd2);
+                let cyan = Color::Rgb(0x2a, 0xa1, 0x98);
+                let green = Color::Rgb(0x85, 0x99, 0x00);
+
+                Self {
+                    border: blue,
+                    highlight: Style::new().fg(magenta).bg(base02),
+                    primary: green,
+                    secondary: yellow,
+                    tertiary: cyan,
+                    error: red,
+                    muted: base01,
+                    bar_value: Style::new().fg(base02).bg(green).italic(),
+                }
+            }
+            ThemeName::Monochrome => Self {
+                border: Color::Reset,
+                highlight: Style::new().reversed(),
+                primary: Color::Reset,
+                secondary: Color::Reset,
+                tertiary: Color::Reset,
+                error: Color::Reset,
+                muted: Color::Reset,
+                bar_value: Style::new().reversed(),
+            },
+        }
+    }
+
+    /// Style of a block, fading from bright (just arrived) to dim (stale) as
+    /// it ages
+    pub fn freshness(&self, age: Duration) -> Style {
+        if age < FRESH_BLOCK_AGE {
+            Style::new().fg(self.primary).bold()
+        } else if age < STALE_BLOCK_AGE {
+            Style::new()
+        } else if age < ANCIENT_BLOCK_AGE {
+            Style::new().fg(self.muted)
+        } else {
+            Style::new().fg(self.muted).dim()
+        }
+    }
+
+    /// Style of a block number, by how final the block is
+    pub fn finality(&self, status: FinalityStatus) -> Style {
+        match status {
+            FinalityStatus::Unsafe => Style::new().fg(self.secondary),
+            FinalityStatus::Safe => Style::new().fg(self.tertiary),
+            FinalityStatus::Finalized => Style::new().fg(self.primary),
+        }
+    }
+}
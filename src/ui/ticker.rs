@@ -0,0 +1,89 @@
+//! Pacing of TUI ticks, which quicken while there's something new to show and
+//! slacken while there isn't
+use std::time::{Duration, Instant};
+
+/// Longest interval between ticks while nothing's happening (unless ticks
+/// were asked to be further apart than this to begin with)
+pub const IDLE_TICK_RATE: Duration = Duration::from_secs(1);
+
+/// When the next tick is due
+#[derive(Clone, Debug)]
+pub struct Ticker {
+    /// Interval between ticks while new data is arriving
+    fast: Duration,
+    /// Interval between ticks once idle for long enough
+    slow: Duration,
+    /// Interval until the next tick
+    interval: Duration,
+    last: Instant,
+}
+
+impl Ticker {
+    pub fn new(fast: Duration) -> Self {
+        Self {
+            fast,
+            slow: fast.max(IDLE_TICK_RATE),
+            interval: fast,
+            last: Instant::now(),
+        }
+    }
+
+    /// How long until the next tick is due
+    pub fn timeout(&self) -> Duration {
+        self.interval.saturating_sub(self.last.elapsed())
+    }
+
+    pub fn is_due(&self) -> bool {
+        self.last.elapsed() >= self.interval
+    }
+
+    /// Have the next tick happen straight away (and those after it quickly),
+    /// as the user's just done something
+    pub fn hurry(&mut self) {
+        self.interval = Duration::ZERO;
+    }
+
+    /// Record that a tick has just happened, backing off if it found nothing
+    /// new
+    pub fn ticked(&mut self, active: bool) {
+        self.interval = if active || self.interval < self.fast {
+            self.fast
+        } else {
+            (self.interval * 2).min(self.slow)
+        };
+        self.last = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ticker() {
+        let fast = Duration::from_millis(100);
+        let mut ticker = Ticker::new(fast);
+
+        let intervals: Vec<u64> = (0..6)
+            .map(|_| {
+                ticker.ticked(false);
+                ticker.interval.as_millis() as u64
+            })
+            .collect();
+        assert_eq!(intervals, vec![200, 400, 800, 1_000, 1_000, 1_000]);
+
+        ticker.hurry();
+        assert!(ticker.is_due());
+        ticker.ticked(false);
+        assert_eq!(ticker.interval, fast);
+
+        ticker.ticked(false);
+        ticker.ticked(true);
+        assert_eq!(ticker.interval, fast);
+
+        /* ticks asked to be slower than idling are left as they are */
+        let mut ticker = Ticker::new(Duration::from_secs(5));
+        ticker.ticked(false);
+        assert_eq!(ticker.interval, Duration::from_secs(5));
+    }
+}
@@ -7,16 +7,49 @@ use std::{
 
 use alloy::{
     consensus::Transaction as AbstractTransaction,
-    primitives::{Address, Bytes, TxHash, B256, U256},
-    rpc::types::Transaction,
+    eips::eip7840::BlobParams,
+    primitives::{Address, Bytes, ChainId, Selector, TxHash, B256, U256},
+    rpc::types::{Header, Transaction},
 };
 use url::Url;
 
-use crate::{ui::app::AddressDisplayMode, ADDRESS_LABELS};
+use crate::{
+    abi, ui::app::AddressDisplayMode, ADDRESS_LABELS, EVENT_SIGNATURES,
+    FUNCTION_SIGNATURES,
+};
 
 const HASH_TRUNCATION_LEN: usize = 8;
 const ADDRESS_HEAD_TAIL_LEN: usize = 4;
 
+/// Blob fee parameters of each mainnet fork that changed them, by activation
+/// timestamp (latest first)
+const MAINNET_BLOB_SCHEDULE: [(u64, BlobParams); 4] = [
+    /* BPO2 */
+    (
+        1_767_747_671,
+        BlobParams {
+            target_blob_count: 14,
+            max_blob_count: 21,
+            update_fraction: 11_684_671,
+            ..BlobParams::prague()
+        },
+    ),
+    /* BPO1 */
+    (
+        1_765_290_071,
+        BlobParams {
+            target_blob_count: 10,
+            max_blob_count: 15,
+            update_fraction: 8_346_193,
+            ..BlobParams::prague()
+        },
+    ),
+    /* Prague */
+    (1_746_612_311, BlobParams::prague()),
+    /* Cancun */
+    (1_710_338_135, BlobParams::cancun()),
+];
+
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum BuilderNetIdentity {
     Flashbots,
@@ -168,6 +201,14 @@ pub fn etherscan_transaction_url(transaction_hash: TxHash) -> Url {
         .expect("invariant violated: constructed invalid transaction URL")
 }
 
+/// Given an [`Address`], produce the Etherscan [`Url`] for the corresponding
+/// account
+pub fn etherscan_address_url(address: Address) -> Url {
+    format!("https://etherscan.io/address/{address}")
+        .parse()
+        .expect("invariant violated: constructed invalid address URL")
+}
+
 pub fn shorten_hash(hash: &B256) -> String {
     format!("{}...", &hash.to_string()[0..HASH_TRUNCATION_LEN])
 }
@@ -185,7 +226,21 @@ pub fn duration_since_timestamp(timestamp: u64) -> Duration {
     let now = SystemTime::now();
     let unix_epoch = SystemTime::UNIX_EPOCH;
     let timestamp_time = unix_epoch + Duration::from_secs(timestamp);
-    now.duration_since(timestamp_time).unwrap()
+    /* dev chains happily mine blocks ahead of the wall clock (e.g., several
+     * per second, each a second after the last) */
+    now.duration_since(timestamp_time).unwrap_or_default()
+}
+
+/// Truncate the given text to at most the given number of characters,
+/// marking where it was cut short
+pub fn shorten(text: &str, max_len: usize) -> String {
+    if text.chars().count() > max_len {
+        let kept: String =
+            text.chars().take(max_len.saturating_sub(3)).collect();
+        format!("{kept}...")
+    } else {
+        text.to_string()
+    }
 }
 
 pub fn human_readable_tx_data(data: Bytes) -> String {
@@ -207,17 +262,7 @@ pub fn to_gwei(x: f64) -> f64 {
 
 #[inline]
 pub fn to_ether(x: U256) -> f64 {
-    if x > U256::from(u128::MAX) {
-        todo!()
-    } else {
-        u128::from_be_bytes(
-            x.to_be_bytes_vec()[0..((u128::BITS / 8) as usize)]
-                .try_into()
-                .expect(
-                    "invariant violated: U256 must have enough bytes for u128",
-                ),
-        ) as f64
-    }
+    f64::from(x) / f64::powi(10.0, 18)
 }
 
 #[inline]
@@ -225,17 +270,148 @@ pub fn useful_gas_price(tx: &Transaction) -> u128 {
     tx.max_fee_per_gas()
 }
 
-pub fn grab_range(xs: &Bytes, a: usize, b: usize) -> Bytes {
-    if a >= xs.len() {
-        Bytes::from(vec![])
-    } else if b > xs.len() {
-        Bytes::from(xs[a..xs.len()].to_vec())
+/// Gas charged for the given transaction before any of it executes (i.e., the
+/// base cost plus its calldata, access list, initcode, and authorizations)
+pub fn intrinsic_gas(tx: &Transaction) -> u64 {
+    let input = tx.input();
+    let zeroes = input.iter().filter(|&&byte| byte == 0).count() as u64;
+    let mut gas = 21_000 + 4 * zeroes + 16 * (input.len() as u64 - zeroes);
+
+    if tx.kind().is_create() {
+        gas += 32_000 + 2 * input.len().div_ceil(32) as u64;
+    }
+
+    if let Some(access_list) = tx.access_list() {
+        gas += access_list
+            .iter()
+            .map(|item| 2_400 + 1_900 * item.storage_keys.len() as u64)
+            .sum::<u64>();
+    }
+
+    if let Some(authorizations) = tx.authorization_list() {
+        gas += 25_000 * authorizations.len() as u64;
+    }
+
+    gas
+}
+
+/// Gas actually refunded to a transaction that used the given amount of gas
+/// (after refunds), ended with the given refund counter, and either succeeded
+/// or reverted
+///
+/// Refunds are capped at a fifth of the gas used before refunds (as per
+/// EIP-3529), and a reverted transaction gets none at all (its refund counter
+/// is discarded along with the rest of its state changes).
+pub fn capped_refund(gas_used: u64, refund: u64, success: bool) -> u64 {
+    if success {
+        refund.min(gas_used / 4)
     } else {
-        Bytes::from(xs[a..b].to_vec())
+        0
+    }
+}
+
+/// Blob fee parameters of each fork that changed them on the chain with the
+/// given ID, by activation timestamp (latest first), if known
+fn blob_schedule(chain_id: ChainId) -> Option<&'static [(u64, BlobParams)]> {
+    match chain_id {
+        1 => Some(&MAINNET_BLOB_SCHEDULE),
+        _ => None,
     }
 }
 
+/// The blob base fee (in wei) of the given block of the chain with the given
+/// ID, as derived from its excess blob gas under that chain's fork schedule
+///
+/// Returns `None` for blocks without blob gas accounting (i.e., pre-Cancun),
+/// and for chains whose schedule isn't known.
+pub fn blob_base_fee(header: &Header, chain_id: ChainId) -> Option<u128> {
+    let excess_blob_gas = header.excess_blob_gas?;
+    let (_, params) = blob_schedule(chain_id)?
+        .iter()
+        .find(|(activation, _)| header.timestamp >= *activation)?;
+    Some(params.calc_blob_fee(excess_blob_gas))
+}
+
+/// One-line summary of the chain's state as of the given block (e.g.,
+/// `#22581735 3.142 gwei`), for terminal titles and status bars
+pub fn status_line(header: &Header) -> String {
+    format!(
+        "#{} {:.3} gwei",
+        header.number,
+        to_gwei(header.base_fee_per_gas.unwrap_or_default() as f64)
+    )
+}
+
 const MAX_ADDR_LEN: usize = 32;
+const MAX_EVENT_LEN: usize = 40;
+
+/// The known event signature (e.g., `Transfer(address,address,uint256)`)
+/// whose hash is the given topic
+pub fn event_signature(topic0: &B256) -> Option<&'static str> {
+    EVENT_SIGNATURES.get(topic0).map(String::as_str)
+}
+
+/// The known function signature (e.g., `transfer(address,uint256)`) with
+/// the given selector
+pub fn function_signature(selector: &Selector) -> Option<&'static str> {
+    FUNCTION_SIGNATURES.get(selector).map(String::as_str)
+}
+
+/// Signature of the function with the given selector, preferring the known
+/// function signatures but falling back on the ABIs of verified contracts
+pub fn selector_signature(selector: &Selector) -> Option<String> {
+    function_signature(selector)
+        .map(str::to_string)
+        .or_else(|| abi::function_signature(selector))
+}
+
+/// Label the event of a log emitted by the given [`Address`], preferring the
+/// known event signatures but falling back on the ABI of the contract that
+/// emitted it
+pub fn label_log_event(
+    address: &Address,
+    topic0: &B256,
+    shorten: bool,
+) -> String {
+    match abi::event_signature(address, topic0) {
+        Some(signature) if event_signature(topic0).is_none() => {
+            if shorten && signature.len() > MAX_EVENT_LEN {
+                format!("{}...", &signature[0..MAX_EVENT_LEN - 3])
+            } else {
+                signature
+            }
+        }
+        _ => label_event(topic0, shorten),
+    }
+}
+
+pub fn label_event(topic0: &B256, shorten: bool) -> String {
+    match event_signature(topic0) {
+        Some(signature) if shorten && signature.len() > MAX_EVENT_LEN => {
+            format!("{}...", &signature[0..MAX_EVENT_LEN - 3])
+        }
+        Some(signature) => signature.to_string(),
+        None if shorten => shorten_hash(topic0),
+        None => topic0.to_string(),
+    }
+}
+
+/// Label the given address for the remainder of this session, returning the
+/// label it previously had (if any)
+pub fn set_address_label(address: Address, label: String) -> Option<String> {
+    ADDRESS_LABELS
+        .write()
+        .ok()
+        .and_then(|mut labels| labels.insert(address, label))
+}
+
+/// Remove the label of the given address for the remainder of this session
+pub fn remove_address_label(address: &Address) -> Option<String> {
+    ADDRESS_LABELS
+        .write()
+        .ok()
+        .and_then(|mut labels| labels.remove(address))
+}
 
 pub fn label_address(
     address: &Address,
@@ -244,11 +420,15 @@ pub fn label_address(
 ) -> String {
     match mode {
         AddressDisplayMode::Cooked => {
-            if let Some(label) = ADDRESS_LABELS.get(address) {
+            let label = ADDRESS_LABELS
+                .read()
+                .ok()
+                .and_then(|labels| labels.get(address).cloned());
+            if let Some(label) = label {
                 if shorten && label.len() > MAX_ADDR_LEN {
                     label[0..MAX_ADDR_LEN].to_string()
                 } else {
-                    label.clone()
+                    label
                 }
             } else if shorten {
                 shorten_address(address)
@@ -265,3 +445,83 @@ pub fn label_address(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_utils::eip1559_transaction;
+
+    fn transaction(
+        changes: impl FnOnce(&mut serde_json::Value),
+    ) -> Transaction {
+        let mut value =
+            eip1559_transaction(TxHash::with_last_byte(1), Address::ZERO, 0);
+        changes(&mut value);
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_intrinsic_gas_calldata() {
+        assert_eq!(intrinsic_gas(&transaction(|_| {})), 21_000);
+        /* one zero byte and two non-zero bytes */
+        assert_eq!(
+            intrinsic_gas(&transaction(|tx| tx["input"] = json!("0x00ff01"))),
+            21_000 + 4 + 2 * 16
+        );
+    }
+
+    #[test]
+    fn test_intrinsic_gas_access_list() {
+        let tx = transaction(|tx| {
+            tx["accessList"] = json!([
+                {
+                    "address": Address::with_last_byte(1),
+                    "storageKeys": [B256::ZERO, B256::with_last_byte(1)],
+                },
+                {
+                    "address": Address::with_last_byte(2),
+                    "storageKeys": [],
+                },
+            ])
+        });
+        assert_eq!(intrinsic_gas(&tx), 21_000 + 2 * 2_400 + 2 * 1_900);
+    }
+
+    #[test]
+    fn test_capped_refund() {
+        assert_eq!(capped_refund(100_000, 4_800, true), 4_800);
+        assert_eq!(capped_refund(100_000, 40_000, true), 25_000);
+        assert_eq!(capped_refund(100_000, 4_800, false), 0);
+    }
+
+    #[test]
+    fn test_blob_base_fee() {
+        let header = |timestamp, excess_blob_gas| {
+            Header::new(alloy::consensus::Header {
+                timestamp,
+                excess_blob_gas,
+                ..Default::default()
+            })
+        };
+        /* e^(10000000 / 3338477) is roughly 19.9 */
+        assert_eq!(
+            blob_base_fee(&header(1_710_338_135, Some(10_000_000)), 1),
+            Some(19)
+        );
+        /* Prague's update fraction is larger, so the same excess costs less */
+        assert_eq!(
+            blob_base_fee(&header(1_746_612_311, Some(10_000_000)), 1),
+            Some(7)
+        );
+        assert_eq!(blob_base_fee(&header(1_710_338_135, None), 1), None);
+        /* before Cancun activated on mainnet */
+        assert_eq!(blob_base_fee(&header(0, Some(10_000_000)), 1), None);
+        /* on a chain whose schedule isn't known */
+        assert_eq!(
+            blob_base_fee(&header(1_710_338_135, Some(10_000_000)), 10),
+            None
+        );
+    }
+}
@@ -0,0 +1,380 @@
+//! Services that verify the source code of deployed contracts
+#![allow(async_fn_in_trait)]
+use std::{collections::BTreeMap, time::Duration};
+
+use alloy::{
+    json_abi::JsonAbi,
+    primitives::{Address, ChainId},
+};
+use eyre::eyre;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+/// Etherscan's multichain API
+const ETHERSCAN_API_URL: &str = "https://api.etherscan.io/v2/api";
+
+/// A single file of a contract's source code
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SourceFile {
+    pub path: String,
+    pub content: String,
+}
+
+/// What a verifier knows about the source code of a contract
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct VerifiedSource {
+    /// Name of the verifier that matched the contract
+    pub verifier: String,
+    /// Name of the contract as written in its source code
+    pub name: String,
+    /// Source language (e.g., Solidity or Vyper)
+    pub language: String,
+    pub compiler_version: String,
+    /// Whether the source matched exactly (including its metadata), rather
+    /// than just compiling to the same code (if the verifier says)
+    pub exact: Option<bool>,
+    pub abi: JsonAbi,
+    /// Every file of the source code, starting with the one defining the
+    /// contract
+    pub sources: Vec<SourceFile>,
+}
+
+/// Something that can look up the verified source code of a contract
+pub trait Verifier {
+    /// Short name of this verifier (for display)
+    fn name(&self) -> &'static str;
+    /// Retrieve the [`VerifiedSource`] of the contract deployed at the given
+    /// [`Address`] on the chain with the given [`ChainId`] (or `None` if it
+    /// isn't verified)
+    async fn verified_source(
+        &self,
+        chain_id: ChainId,
+        address: Address,
+    ) -> eyre::Result<Option<VerifiedSource>>;
+}
+
+/// Verifier type that is generic over all supported verifiers
+#[derive(Clone, Debug)]
+pub enum AnyVerifier {
+    Sourcify(Sourcify),
+    Etherscan(Etherscan),
+}
+
+impl Verifier for AnyVerifier {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Sourcify(t) => t.name(),
+            Self::Etherscan(t) => t.name(),
+        }
+    }
+
+    async fn verified_source(
+        &self,
+        chain_id: ChainId,
+        address: Address,
+    ) -> eyre::Result<Option<VerifiedSource>> {
+        match self {
+            Self::Sourcify(t) => t.verified_source(chain_id, address).await,
+            Self::Etherscan(t) => t.verified_source(chain_id, address).await,
+        }
+    }
+}
+
+/// A Sourcify server (e.g., `https://sourcify.dev/server`)
+#[derive(Clone, Debug)]
+pub struct Sourcify {
+    url: Url,
+    client: reqwest::Client,
+}
+
+/// Response to `GET /v2/contract/{chainId}/{address}`
+#[derive(Debug, Deserialize)]
+struct SourcifyContract {
+    /// Either `exact_match` or `match`
+    #[serde(rename = "match")]
+    matched: Option<String>,
+    compilation: SourcifyCompilation,
+    abi: JsonAbi,
+    sources: BTreeMap<String, SourcifySource>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SourcifyCompilation {
+    name: String,
+    language: String,
+    compiler_version: String,
+    /// Path of the file defining the contract, then its name (e.g.,
+    /// `contracts/Token.sol:Token`)
+    fully_qualified_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourcifySource {
+    content: String,
+}
+
+impl Sourcify {
+    pub fn new(mut url: Url) -> Self {
+        /* so that paths are joined onto the end of any existing path */
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+
+        Self {
+            url,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+                .build()
+                .expect("invariant violated: invalid HTTP client settings"),
+        }
+    }
+}
+
+impl Verifier for Sourcify {
+    fn name(&self) -> &'static str {
+        "Sourcify"
+    }
+
+    async fn verified_source(
+        &self,
+        chain_id: ChainId,
+        address: Address,
+    ) -> eyre::Result<Option<VerifiedSource>> {
+        let url = self
+            .url
+            .join(&format!("v2/contract/{chain_id}/{address}"))?;
+        let response = self
+            .client
+            .get(url)
+            .query(&[("fields", "compilation,abi,sources")])
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let contract: SourcifyContract =
+            response.error_for_status()?.json().await?;
+        let main = contract
+            .compilation
+            .fully_qualified_name
+            .rsplit_once(':')
+            .map(|(path, _)| path.to_string())
+            .unwrap_or_default();
+        let mut sources: Vec<SourceFile> = contract
+            .sources
+            .into_iter()
+            .map(|(path, source)| SourceFile {
+                path,
+                content: source.content,
+            })
+            .collect();
+        sources.sort_by_key(|file| file.path != main);
+        Ok(Some(VerifiedSource {
+            verifier: self.name().to_string(),
+            name: contract.compilation.name,
+            language: contract.compilation.language,
+            compiler_version: contract.compilation.compiler_version,
+            exact: Some(contract.matched.as_deref() == Some("exact_match")),
+            abi: contract.abi,
+            sources,
+        }))
+    }
+}
+
+/// Etherscan (or any explorer with a compatible API), which requires an API
+/// key
+#[derive(Clone, Debug)]
+pub struct Etherscan {
+    url: Url,
+    api_key: String,
+    client: reqwest::Client,
+}
+
+/// Response to `?module=contract&action=getsourcecode`
+#[derive(Debug, Deserialize)]
+struct EtherscanResponse {
+    status: String,
+    /// Either a list of contracts or an error message
+    result: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EtherscanContract {
+    /// Either a single file, a JSON map of files, or standard JSON compiler
+    /// input wrapped in an extra pair of braces
+    source_code: String,
+    /// JSON ABI (or an explanation of why there isn't one)
+    #[serde(rename = "ABI")]
+    abi: String,
+    contract_name: String,
+    /// Compiler version, prefixed by `v` for Solidity or `vyper:` for Vyper
+    compiler_version: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct StandardJsonInput {
+    sources: BTreeMap<String, SourcifySource>,
+}
+
+impl Etherscan {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            url: ETHERSCAN_API_URL
+                .parse()
+                .expect("invariant violated: invalid Etherscan API URL"),
+            api_key,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+                .build()
+                .expect("invariant violated: invalid HTTP client settings"),
+        }
+    }
+}
+
+impl Verifier for Etherscan {
+    fn name(&self) -> &'static str {
+        "Etherscan"
+    }
+
+    async fn verified_source(
+        &self,
+        chain_id: ChainId,
+        address: Address,
+    ) -> eyre::Result<Option<VerifiedSource>> {
+        let response: EtherscanResponse = self
+            .client
+            .get(self.url.clone())
+            .query(&[
+                ("chainid", chain_id.to_string()),
+                ("module", "contract".to_string()),
+                ("action", "getsourcecode".to_string()),
+                ("address", address.to_string()),
+                ("apikey", self.api_key.clone()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if response.status != "1" {
+            return Err(eyre!("{}", response.result));
+        }
+
+        let Some(contract) =
+            serde_json::from_value::<Vec<EtherscanContract>>(response.result)?
+                .into_iter()
+                .next()
+        else {
+            return Ok(None);
+        };
+
+        if contract.source_code.is_empty() {
+            return Ok(None);
+        }
+
+        let (language, compiler_version) =
+            match contract.compiler_version.strip_prefix("vyper:") {
+                Some(version) => ("Vyper", version),
+                None => (
+                    "Solidity",
+                    contract
+                        .compiler_version
+                        .strip_prefix('v')
+                        .unwrap_or(&contract.compiler_version),
+                ),
+            };
+        let extension = if language == "Vyper" { "vy" } else { "sol" };
+        let mut sources = etherscan_sources(
+            &contract.source_code,
+            &format!("{}.{extension}", contract.contract_name),
+        );
+        let definition = format!("contract {}", contract.contract_name);
+        sources.sort_by_key(|file| !file.content.contains(&definition));
+        Ok(Some(VerifiedSource {
+            verifier: self.name().to_string(),
+            name: contract.contract_name,
+            language: language.to_string(),
+            compiler_version: compiler_version.to_string(),
+            exact: None,
+            abi: serde_json::from_str(&contract.abi)?,
+            sources,
+        }))
+    }
+}
+
+/// Split the source code that Etherscan returns into its files, naming it
+/// after the contract if it's just the one
+fn etherscan_sources(source_code: &str, default_path: &str) -> Vec<SourceFile> {
+    let files = |sources: BTreeMap<String, SourcifySource>| {
+        sources
+            .into_iter()
+            .map(|(path, source)| SourceFile {
+                path,
+                content: source.content,
+            })
+            .collect()
+    };
+
+    if let Some(input) = source_code
+        .strip_prefix("{{")
+        .and_then(|rest| rest.strip_suffix("}}"))
+    {
+        if let Ok(input) =
+            serde_json::from_str::<StandardJsonInput>(&format!("{{{input}}}"))
+        {
+            return files(input.sources);
+        }
+    }
+
+    if let Ok(sources) = serde_json::from_str(source_code) {
+        return files(sources);
+    }
+
+    vec![SourceFile {
+        path: default_path.to_string(),
+        content: source_code.to_string(),
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_etherscan_sources() {
+        let single = "pragma solidity ^0.8.0;\ncontract A {}";
+        assert_eq!(
+            etherscan_sources(single, "A.sol"),
+            vec![SourceFile {
+                path: "A.sol".to_string(),
+                content: single.to_string(),
+            }]
+        );
+
+        let file = |path: &str| SourceFile {
+            path: path.to_string(),
+            content: "contract A {}".to_string(),
+        };
+        assert_eq!(
+            etherscan_sources(
+                r#"{"src/A.sol": {"content": "contract A {}"}}"#,
+                "A.sol"
+            ),
+            vec![file("src/A.sol")]
+        );
+        assert_eq!(
+            etherscan_sources(
+                r#"{{"language": "Solidity", "sources": {"src/B.sol": {"content": "contract A {}"}, "src/A.sol": {"content": "contract A {}"}}}}"#,
+                "A.sol"
+            ),
+            vec![file("src/A.sol"), file("src/B.sol")]
+        );
+    }
+}